@@ -16,12 +16,16 @@ use crate::{cli::bio::{origin::Rbac,
 use clap::{App,
            AppSettings,
            Arg,
-           ArgMatches};
+           ArgMatches,
+           SubCommand};
 use biome_common::{cli::{file_into_idents,
                            is_toml_file,
                            BINLINK_DIR_ENVVAR,
                            DEFAULT_BINLINK_DIR,
                            PACKAGE_TARGET_ENVVAR},
+                     sbom::SbomFormat,
+                     templating::helpers::HELPERS_HELP,
+                     types::MemberLabel,
                      FeatureFlag};
 use biome_core::{crypto::CACHE_KEY_PATH_ENV_VAR,
                    env::Config,
@@ -32,7 +36,8 @@ use biome_core::{crypto::CACHE_KEY_PATH_ENV_VAR,
                              PackageTarget},
                    service::ServiceGroup,
                    ChannelIdent};
-use std::{path::Path,
+use std::{net::SocketAddr,
+          path::Path,
           result,
           str::FromStr};
 use structopt::StructOpt;
@@ -105,9 +110,45 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                 (aliases: &["sh", "sho"])
                 (@arg PKG_IDENT: +required +takes_value {valid_ident}
                     "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
+                (@arg MEMBER: --member
+                    "Display the merged configuration of the service on the remote Supervisor, \
+                    along with the layer which supplied each key")
+                (@arg EXPLAIN: --explain
+                    "Like --member, but also show the file, environment variable or gossip \
+                    incarnation each layer was read from")
                 (@arg REMOTE_SUP: --("remote-sup") -r +takes_value default_value("127.0.0.1:9632")
                     "Address to a remote Supervisor's Control Gateway")
             )
+            (@subcommand render =>
+                (about: "Renders templates against the live context of a running service and \
+                    shows how the output differs from the files on disk")
+                (aliases: &["r", "re", "ren", "rend", "rende"])
+                (@arg PKG_IDENT: +required +takes_value {valid_ident}
+                    "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
+                (@arg TEMPLATE: --template -t +takes_value +multiple {file_exists}
+                    "Path to a template to render (default: the configuration templates of the \
+                    installed package)")
+                (@arg REMOTE_SUP: --("remote-sup") -r +takes_value default_value("127.0.0.1:9632")
+                    "Address to a remote Supervisor's Control Gateway")
+            )
+        )
+        (@subcommand depot =>
+            (about: "Commands relating to serving packages without Biome Builder")
+            (aliases: &["d", "de", "dep", "depo"])
+            (@setting ArgRequiredElseHelp)
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand serve =>
+                (about: "Serves downloaded packages and keys over the parts of the Builder API \
+                    used to install them")
+                (aliases: &["s", "se", "ser", "serv"])
+                (@arg DOWNLOAD_DIRECTORY: --("download-directory") +takes_value {dir_exists}
+                    "A directory populated by 'bio pkg download' (default: the local artifact and \
+                    key caches)")
+                (@arg LISTEN: --listen -l +takes_value default_value("0.0.0.0:9636")
+                    {valid_socket_addr} "The address to listen on")
+                (@arg ALLOW_UPLOAD: --("allow-upload")
+                    "Accept uploads of packages and public origin keys")
+            )
         )
         (@subcommand file =>
             (about: "Commands relating to Biome files")
@@ -554,6 +595,7 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                     "A package identifier (ex: core/redis, core/busybox-static/1.42.2)")
              )
             (subcommand: sub_pkg_download())
+            (subcommand: sub_pkg_lock())
             (@subcommand env =>
                 (about: "Prints the runtime environment of a specific installed package")
                 (@arg PKG_IDENT: +required +takes_value {valid_ident}
@@ -611,10 +653,13 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                 (about: "Signs an archive with an origin key, generating a Biome Artifact")
                 (aliases: &["s", "si", "sig"])
                 (@arg ORIGIN: --origin +takes_value {valid_origin} "Origin key used to create signature")
+                (@arg DETACHED: --detached
+                    "Add a detached signature to an existing Biome Artifact given as SOURCE, \
+                    stored in a .sig file next to it")
                 (@arg SOURCE: +required +takes_value {file_exists}
                     "A path to a source archive file \
                     (ex: /home/acme-redis-3.0.7-21120102031201.tar.xz)")
-                (@arg DEST: +required +takes_value
+                (@arg DEST: +takes_value required_unless[DETACHED] conflicts_with[DETACHED]
                     "The destination path to the signed Biome Artifact \
                     (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
                 (arg: arg_cache_key_path())
@@ -632,6 +677,28 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                     (ex: core/redis, core/busybox-static/1.42.2/21120102031201)")
                 (@arg NO_DEPS: --("no-deps") "Don't uninstall dependencies")
                 (@arg IGNORE_UNINSTALL_HOOK: --("ignore-uninstall-hook") "Do not run any uninstall hooks")
+                (@arg FORCE: -f --force
+                    "Stop any service loaded by the Supervisor that uninstalling would break, \
+                    instead of refusing to uninstall")
+                (@arg CASCADE: --cascade conflicts_with[KEEP_LATEST NO_DEPS]
+                    "Also uninstall the dependencies which are only orphaned once other \
+                    dependencies are uninstalled")
+            )
+            (@subcommand gc =>
+                (about: "Uninstall packages not needed by any service, binlink or kept package, \
+                    and prune the artifact cache")
+                (@arg DRYRUN: -d --dryrun "Just show what would be removed, don't actually do it")
+                (@arg KEEP: --keep +takes_value +multiple {valid_ident}
+                    "Identifier of one or more packages to keep installed along with their \
+                    dependencies. (ex: core/redis, core/busybox-static/1.42.2/21120102031201)")
+                (@arg BINLINK_DIR: --("binlink-dir") +takes_value {non_empty} env(BINLINK_DIR_ENVVAR) default_value(DEFAULT_BINLINK_DIR)
+                    "The directory whose binlinks keep the packages they point into installed")
+                (@arg CACHE_MAX_AGE: --("cache-max-age") +takes_value {valid_numeric::<u64>}
+                    "Remove cached artifacts which have not been modified for this number of days")
+                (@arg CACHE_MAX_SIZE: --("cache-max-size") +takes_value {valid_numeric::<u64>}
+                    "Remove the oldest cached artifacts until the cache is no larger than this \
+                    number of megabytes")
+                (@arg IGNORE_UNINSTALL_HOOK: --("ignore-uninstall-hook") "Do not run any uninstall hooks")
             )
             // alas no hyphens in subcommand names..
             // https://github.com/clap-rs/clap/issues/1297
@@ -728,6 +795,7 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                     (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
                 (arg: arg_cache_key_path())
             )
+            (subcommand: sub_pkg_verify_image())
             (@subcommand header =>
                 (about: "Returns the Biome Artifact header")
                 (aliases: &["hea", "head", "heade", "header"])
@@ -742,6 +810,26 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
                 (@arg SOURCE: +required +takes_value {file_exists} "A path to a Biome Artifact \
                     (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
             )
+            (@subcommand diff =>
+                (about: "Compares the metadata and files of two package releases")
+                (@arg TO_JSON: -j --json "Output will be rendered in json")
+                (@arg FROM: +required +takes_value "An installed package identifier or a path \
+                    to a Biome Artifact to compare from (ex: core/redis/4.0.14/20190319155852)")
+                (@arg TO: +required +takes_value "An installed package identifier or a path \
+                    to a Biome Artifact to compare to (ex: \
+                    /home/core-redis-5.0.7-20200110201405-x86_64-linux.hart)")
+            )
+            (@subcommand sbom =>
+                (about: "Generates a software bill of materials for a package and its \
+                    transitive dependencies")
+                (@arg FORMAT: -f --format +takes_value possible_values(SbomFormat::VARIANTS)
+                    default_value("spdx") "The SBOM document format")
+                (@arg OUTPUT: -o --output +takes_value "Write the SBOM to a file instead of \
+                    standard output")
+                (@arg PKG_IDENT_OR_ARTIFACT: +required +takes_value "An installed package \
+                    identifier (ex: core/redis) or a path to a Biome Artifact (ex: \
+                    /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
+            )
             (@subcommand dependencies =>
                 (about: "Returns the Biome Artifact dependencies. By default it will return \
                     the direct dependencies of the package")
@@ -773,6 +861,7 @@ pub fn get(feature_flags: FeatureFlag) -> App<'static, 'static> {
             (@subcommand render =>
                 (about: "Renders plan config files")
                 (aliases: &["r", "re", "ren", "rend", "rende"])
+                (after_help: HELPERS_HELP)
                 (@arg TEMPLATE_PATH: +required +takes_value {file_exists} "Path to config to render")
                 (@arg DEFAULT_TOML: -d --("default-toml") +takes_value default_value("./default.toml") "Path to default.toml")
                 (@arg USER_TOML: -u --("user-toml") +takes_value "Path to user.toml, defaults to none")
@@ -932,6 +1021,27 @@ fn sub_cli_completers() -> App<'static, 'static> {
                                    .possible_values(&supported_shells))
 }
 
+fn sub_pkg_verify_image() -> App<'static, 'static> {
+    SubCommand::with_name("verify-image")
+        .about("Verifies the provenance attestation attached to a container image with an \
+                origin key")
+        .arg(Arg::with_name("IMAGE").required(true)
+                                    .takes_value(true)
+                                    .help("An OCI image layout directory, or an image in a \
+                                           registry (ex: acme/redis:latest)"))
+        .arg(Arg::with_name("REGISTRY_USERNAME").long("username")
+                                                .short("U")
+                                                .takes_value(true)
+                                                .requires("REGISTRY_PASSWORD")
+                                                .help("Remote registry username"))
+        .arg(Arg::with_name("REGISTRY_PASSWORD").long("password")
+                                                .short("P")
+                                                .takes_value(true)
+                                                .requires("REGISTRY_USERNAME")
+                                                .help("Remote registry password"))
+        .arg(arg_cache_key_path())
+}
+
 fn arg_cache_key_path() -> Arg<'static, 'static> {
     Arg::with_name("CACHE_KEY_PATH").long("cache-key-path")
                                     .validator(non_empty)
@@ -987,6 +1097,8 @@ fn sub_pkg_download() -> App<'static, 'static> {
     (@arg CHANNEL: --channel -c +takes_value default_value[stable] env(ChannelIdent::ENVVAR)
         "Download from the specified release channel. Overridden if channel is specified in toml file")
     (@arg DOWNLOAD_DIRECTORY: --("download-directory") +takes_value "The path to store downloaded artifacts")
+    (@arg LOCKFILE: --lockfile +takes_value {file_exists}
+        "Download exactly the artifacts recorded in a lockfile written by 'bio pkg lock'")
     (@arg PKG_IDENT_FILE: --file +takes_value +multiple {valid_ident_or_toml_file}
         "File with newline separated package identifiers, or TOML file (ending with .toml extension)")
     (@arg PKG_IDENT: +multiple +takes_value {valid_ident}
@@ -1001,6 +1113,26 @@ fn sub_pkg_download() -> App<'static, 'static> {
     sub
 }
 
+fn sub_pkg_lock() -> App<'static, 'static> {
+    clap_app!(@subcommand lock =>
+    (about: "Resolves Biome packages and their dependencies to exact artifacts, recorded in a \
+             lockfile")
+    (@arg AUTH_TOKEN: -z --auth +takes_value "Authentication token for Builder")
+    (@arg BLDR_URL: --url -u +takes_value {valid_url}
+        "Specify an alternate Builder endpoint. If not specified, the value will \
+         be taken from the HAB_BLDR_URL environment variable if defined. (default: https://bldr.habitat.sh)")
+    (@arg CHANNEL: --channel -c +takes_value default_value[stable] env(ChannelIdent::ENVVAR)
+        "Resolve from the specified release channel. Overridden if channel is specified in toml file")
+    (@arg LOCKFILE: --lockfile -l +takes_value default_value("bio.lock") "The lockfile to write")
+    (@arg PKG_IDENT_FILE: --file +takes_value +multiple {valid_ident_or_toml_file}
+        "File with newline separated package identifiers, or TOML file (ending with .toml extension)")
+    (@arg PKG_IDENT: +multiple +takes_value {valid_ident}
+            "One or more Biome package identifiers (ex: acme/redis)")
+    (@arg PKG_TARGET: --target -t +takes_value {valid_target}
+            "Target architecture to resolve. E.g. x86_64-linux. Overridden if architecture is specified in toml file")
+    )
+}
+
 fn sub_pkg_install(feature_flags: FeatureFlag) -> App<'static, 'static> {
     let mut sub = clap_app!(@subcommand install =>
         (about: "Installs a Biome package from Builder or locally from a Biome Artifact")
@@ -1010,9 +1142,11 @@ fn sub_pkg_install(feature_flags: FeatureFlag) -> App<'static, 'static> {
                          https://bldr.habitat.sh)")
         (@arg CHANNEL: --channel -c +takes_value default_value[stable] env(ChannelIdent::ENVVAR)
             "Install from the specified release channel")
-        (@arg PKG_IDENT_OR_ARTIFACT: +required +multiple +takes_value
+        (@arg PKG_IDENT_OR_ARTIFACT: +multiple +takes_value required_unless[LOCKFILE]
             "One or more Biome package identifiers (ex: acme/redis) and/or filepaths \
             to a Biome Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)")
+        (@arg LOCKFILE: --lockfile +takes_value {file_exists}
+            "Install exactly the artifacts recorded in a lockfile written by 'bio pkg lock'")
         (@arg BINLINK: -b --binlink
             "Binlink all binaries from installed package(s) into BINLINK_DIR")
         (@arg BINLINK_DIR: --("binlink-dir") +takes_value {non_empty} env(BINLINK_DIR_ENVVAR)
@@ -1020,6 +1154,12 @@ fn sub_pkg_install(feature_flags: FeatureFlag) -> App<'static, 'static> {
         (@arg FORCE: -f --force "Overwrite existing binlinks")
         (@arg AUTH_TOKEN: -z --auth +takes_value "Authentication token for Builder")
         (@arg IGNORE_INSTALL_HOOK: --("ignore-install-hook") "Do not run any install hooks")
+        (@arg REQUIRED_SIGNER: --("required-signer") +takes_value +multiple number_of_values(1)
+            "Only install artifacts signed by this origin key, in their header or in a detached \
+            signature. May be given several times (default: the signers in HAB_REQUIRED_SIGNERS)")
+        (@arg REQUIRED_SIGNATURES: --("required-signatures") +takes_value requires[REQUIRED_SIGNER]
+            {valid_numeric::<usize>}
+            "How many of the required signers must have signed each artifact (default: all)")
     );
     sub = sub.arg(Arg::with_name("OFFLINE").help("Install packages in offline mode")
                                                .hidden(!feature_flags.contains(FeatureFlag::OFFLINE_INSTALL))
@@ -1043,6 +1183,13 @@ fn sub_config_apply() -> App<'static, 'static> {
     (@arg FILE: +takes_value {file_exists_or_stdin}
         "Path to local file on disk (ex: /tmp/config.toml, default: <stdin>)")
     (@arg USER: -u --user +takes_value "Name of a user key to use for encryption")
+    (@arg MEMBER: --member +takes_value +multiple number_of_values(1)
+        "Only apply the configuration to the member with this id")
+    (@arg LABEL: --label +takes_value +multiple number_of_values(1) {valid_member_label}
+        "Only apply the configuration to members carrying this key=value label (see bio sup \
+        run --member-label)")
+    (@arg CLEAR: --clear conflicts_with[FILE USER]
+        "Remove the configuration applied to the members given with --member or --label")
     (@arg REMOTE_SUP: --("remote-sup") -r +takes_value default_value("127.0.0.1:9632")
         "Address to a remote Supervisor's Control Gateway")
     (arg: arg_cache_key_path())
@@ -1116,6 +1263,13 @@ fn valid_service_group(val: String) -> result::Result<(), String> {
     ServiceGroup::validate(&val).map_err(|e| e.to_string())
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_member_label(val: String) -> result::Result<(), String> {
+    val.parse::<MemberLabel>()
+       .map(|_| ())
+       .map_err(|e| e.to_string())
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn dir_exists(val: String) -> result::Result<(), String> {
     if Path::new(&val).is_dir() {
@@ -1150,6 +1304,14 @@ fn valid_url(val: String) -> result::Result<(), String> {
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_socket_addr(val: String) -> result::Result<(), String> {
+    match val.parse::<SocketAddr>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("'{}' is not a valid address (ex: 0.0.0.0:9636)", &val)),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_numeric<T: FromStr>(val: String) -> result::Result<(), String> {
     match val.parse::<T>() {
@@ -1164,7 +1326,8 @@ fn valid_ident(val: String) -> result::Result<(), String> {
         Ok(_) => Ok(()),
         Err(_) => {
             Err(format!("'{}' is not valid. Package identifiers have the \
-                         form origin/name[/version[/release]]",
+                         form origin/name[/version[/release]] or \
+                         origin/name@constraint",
                         &val))
         }
     }
@@ -1355,6 +1518,39 @@ mod tests {
             assert!(matches.is_ok());
         }
 
+        #[test]
+        fn app_and_env_options_required_if_sink_option() {
+            let matches =
+                SupRun::clap().get_matches_from_safe(vec!["run", "--event-sink", "syslog",]);
+            assert!(matches.is_err());
+            let error = matches.unwrap_err();
+            assert_eq!(error.kind, clap::ErrorKind::MissingRequiredArgument);
+            let matches = SupRun::clap().get_matches_from_safe(vec!["run",
+                                                                    "--event-stream-application",
+                                                                    "MY_APP",
+                                                                    "--event-stream-environment",
+                                                                    "MY_ENV",
+                                                                    "--event-sink",
+                                                                    "syslog",
+                                                                    "--event-sink",
+                                                                    "file:/tmp/events",]);
+            assert!(matches.is_ok());
+        }
+
+        #[test]
+        fn sink_option_must_be_a_known_sink() {
+            let matches = SupRun::clap().get_matches_from_safe(vec!["run",
+                                                                    "--event-stream-application",
+                                                                    "MY_APP",
+                                                                    "--event-stream-environment",
+                                                                    "MY_ENV",
+                                                                    "--event-sink",
+                                                                    "/tmp/events",]);
+            assert!(matches.is_err());
+            let error = matches.unwrap_err();
+            assert_eq!(error.kind, clap::ErrorKind::ValueValidation);
+        }
+
         #[test]
         fn app_option_must_take_a_value() {
             let matches = SupRun::clap().get_matches_from_safe(vec!["run",
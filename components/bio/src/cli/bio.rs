@@ -1,6 +1,7 @@
 mod bldr;
 mod cli;
 mod config;
+mod depot;
 mod file;
 pub mod license;
 pub mod origin;
@@ -23,6 +24,8 @@ use self::{bldr::{Bldr,
                     ConfigOptServiceConfigApply,
                     ServiceConfig,
                     ServiceConfigApply},
+           depot::{ConfigOptDepot,
+                   Depot},
            file::{ConfigOptFile,
                   File},
            license::{ConfigOptLicense,
@@ -75,6 +78,8 @@ pub enum Bio {
     Cli(Cli),
     #[structopt(no_version)]
     Config(ServiceConfig),
+    #[structopt(no_version, aliases = &["d", "de", "dep", "depo"])]
+    Depot(Depot),
     #[structopt(no_version)]
     File(File),
     #[structopt(no_version)]
@@ -4,9 +4,12 @@ use super::util::{CacheKeyPath,
                   ConfigOptRemoteSup,
                   PkgIdent,
                   RemoteSup};
-use crate::cli::file_exists_or_stdin;
+use crate::cli::{file_exists,
+                 file_exists_or_stdin};
 use configopt::ConfigOpt;
+use biome_common::types::MemberLabel;
 use biome_core::service::ServiceGroup;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(ConfigOpt, StructOpt)]
@@ -18,6 +21,27 @@ pub enum ServiceConfig {
     Show {
         #[structopt(flatten)]
         pkg_ident:  PkgIdent,
+        /// Display the merged configuration of the service on the remote Supervisor, along with
+        /// the layer which supplied each key
+        #[structopt(name = "MEMBER", long = "member")]
+        member:     bool,
+        /// Like --member, but also show the file, environment variable or gossip incarnation each
+        /// layer was read from
+        #[structopt(name = "EXPLAIN", long = "explain")]
+        explain:    bool,
+        #[structopt(flatten)]
+        remote_sup: RemoteSup,
+    },
+    /// Renders templates against the live context of a running service and shows how the output
+    /// differs from the files on disk
+    #[structopt(no_version, aliases = &["r", "re", "ren", "rend", "rende"])]
+    Render {
+        #[structopt(flatten)]
+        pkg_ident:  PkgIdent,
+        /// Path to a template to render (default: the configuration templates of the installed
+        /// package)
+        #[structopt(name = "TEMPLATE", long = "template", short = "t", validator = file_exists)]
+        template:   Vec<PathBuf>,
         #[structopt(flatten)]
         remote_sup: RemoteSup,
     },
@@ -40,6 +64,16 @@ pub struct ServiceConfigApply {
     /// Name of a user key to use for encryption
     #[structopt(short = "u", long = "user")]
     user:           Option<String>,
+    /// Only apply the configuration to the member with this id
+    #[structopt(long = "member", number_of_values = 1)]
+    member:         Vec<String>,
+    /// Only apply the configuration to members carrying this key=value label (see bio sup run
+    /// --member-label)
+    #[structopt(long = "label", number_of_values = 1)]
+    label:          Vec<MemberLabel>,
+    /// Remove the configuration applied to the members given with --member or --label
+    #[structopt(long = "clear", conflicts_with_all = &["FILE", "USER"])]
+    clear:          bool,
     #[structopt(flatten)]
     remote_sup:     RemoteSup,
     #[structopt(flatten)]
@@ -0,0 +1,35 @@
+use crate::cli::{dir_exists,
+                 valid_socket_addr};
+use configopt::ConfigOpt;
+use std::{net::SocketAddr,
+          path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(no_version)]
+/// Commands relating to serving packages without Biome Builder
+pub enum Depot {
+    #[structopt(no_version, aliases = &["s", "se", "ser", "serv"])]
+    Serve(DepotServe),
+}
+
+/// Serves downloaded packages and keys over the parts of the Builder API used to install them
+#[derive(ConfigOpt, StructOpt)]
+#[structopt(no_version)]
+pub struct DepotServe {
+    /// A directory populated by 'bio pkg download' (default: the local artifact and key caches)
+    #[structopt(name = "DOWNLOAD_DIRECTORY",
+                long = "download-directory",
+                validator = dir_exists)]
+    pub download_directory: Option<PathBuf>,
+    /// The address to listen on
+    #[structopt(name = "LISTEN",
+                long = "listen",
+                short = "l",
+                default_value = "0.0.0.0:9636",
+                validator = valid_socket_addr)]
+    pub listen:             SocketAddr,
+    /// Accept uploads of packages and public origin keys
+    #[structopt(name = "ALLOW_UPLOAD", long = "allow-upload")]
+    pub allow_upload:       bool,
+}
@@ -20,6 +20,7 @@ use configopt::ConfigOpt;
 use biome_common::{cli::{BINLINK_DIR_ENVVAR,
                            DEFAULT_BINLINK_DIR,
                            PACKAGE_TARGET_ENVVAR},
+                     sbom::SbomFormat,
                      FeatureFlag,
                      FEATURE_FLAGS};
 use biome_core::{env::Config,
@@ -185,6 +186,20 @@ pub enum Pkg {
         #[structopt(name = "REVERSE", short = "r", long = "reverse")]
         reverse:    bool,
     },
+    /// Compares the metadata and files of two package releases
+    Diff {
+        /// Output will be rendered in json
+        #[structopt(name = "TO_JSON", short = "j", long = "json")]
+        to_json: bool,
+        /// An installed package identifier or a path to a Biome Artifact to compare from (ex:
+        /// core/redis/4.0.14/20190319155852)
+        #[structopt(name = "FROM")]
+        from:    String,
+        /// An installed package identifier or a path to a Biome Artifact to compare to (ex:
+        /// /home/core-redis-5.0.7-20200110201405-x86_64-linux.hart)
+        #[structopt(name = "TO")]
+        to:      String,
+    },
     /// Download Biome artifacts (including dependencies and keys) from Builder
     Download {
         #[structopt(flatten)]
@@ -202,6 +217,9 @@ pub enum Pkg {
         /// The path to store downloaded artifacts
         #[structopt(name = "DOWNLOAD_DIRECTORY", long = "download-directory")]
         download_directory:  Option<PathBuf>,
+        /// Download exactly the artifacts recorded in a lockfile written by 'bio pkg lock'
+        #[structopt(name = "LOCKFILE", long = "lockfile", validator = file_exists)]
+        lockfile:            Option<PathBuf>,
         /// File with newline separated package identifiers, or TOML file (ending with .toml
         /// extension)
         #[structopt(name = "PKG_IDENT_FILE", long = "file", validator = valid_ident_or_toml_file)]
@@ -227,6 +245,33 @@ pub enum Pkg {
     },
     Exec(PkgExec),
     Export(ExportCommand),
+    /// Uninstall packages not needed by any service, binlink or kept package, and prune the
+    /// artifact cache
+    Gc {
+        /// Just show what would be removed, don't actually do it
+        #[structopt(name = "DRYRUN", short = "d", long = "dryrun")]
+        dryrun:                bool,
+        /// Identifier of one or more packages to keep installed along with their dependencies.
+        /// (ex: core/redis, core/busybox-static/1.42.2/21120102031201)
+        #[structopt(name = "KEEP", long = "keep")]
+        keep:                  Vec<PackageIdent>,
+        /// The directory whose binlinks keep the packages they point into installed
+        #[structopt(name = "BINLINK_DIR",
+                    long = "binlink-dir",
+                    env = BINLINK_DIR_ENVVAR,
+                    default_value = DEFAULT_BINLINK_DIR)]
+        binlink_dir:           PathBuf,
+        /// Remove cached artifacts which have not been modified for this number of days
+        #[structopt(name = "CACHE_MAX_AGE", long = "cache-max-age")]
+        cache_max_age:         Option<u64>,
+        /// Remove the oldest cached artifacts until the cache is no larger than this number of
+        /// megabytes
+        #[structopt(name = "CACHE_MAX_SIZE", long = "cache-max-size")]
+        cache_max_size:        Option<u64>,
+        /// Do not run any uninstall hooks
+        #[structopt(name = "IGNORE_UNINSTALL_HOOK", long = "ignore-uninstall-hook")]
+        ignore_uninstall_hook: bool,
+    },
     /// Generates a blake2b hashsum from a target at any given filepath
     Hash {
         /// A filepath of the target
@@ -254,6 +299,38 @@ pub enum Pkg {
     Install(PkgInstall),
     /// List all versions of installed packages
     List(List),
+    /// Resolves Biome packages and their dependencies to exact artifacts, recorded in a lockfile
+    Lock {
+        #[structopt(flatten)]
+        auth_token:     AuthToken,
+        #[structopt(flatten)]
+        bldr_url:       BldrUrl,
+        /// Resolve from the specified release channel. Overridden if channel is specified in toml
+        /// file
+        #[structopt(name = "CHANNEL",
+                    short = "c",
+                    long = "channel",
+                    default_value = "stable",
+                    env = ChannelIdent::ENVVAR)]
+        channel:        String,
+        /// The lockfile to write
+        #[structopt(name = "LOCKFILE",
+                    short = "l",
+                    long = "lockfile",
+                    default_value = "bio.lock")]
+        lockfile:       PathBuf,
+        /// File with newline separated package identifiers, or TOML file (ending with .toml
+        /// extension)
+        #[structopt(name = "PKG_IDENT_FILE", long = "file", validator = valid_ident_or_toml_file)]
+        pkg_ident_file: Vec<String>,
+        /// One or more Biome package identifiers (ex: acme/redis)
+        #[structopt(name = "PKG_IDENT")]
+        pkg_ident:      Vec<PackageIdent>,
+        /// Target architecture to resolve. E.g. x86_64-linux. Overridden if architecture is
+        /// specified in toml file
+        #[structopt(name = "PKG_TARGET", short = "t", long = "target")]
+        pkg_target:     Option<PackageTarget>,
+    },
     /// Prints the path to a specific installed release of a package
     Path {
         #[structopt(flatten)]
@@ -286,6 +363,23 @@ pub enum Pkg {
         #[structopt(name = "FULL_PATHS", short = "p")]
         full_paths:    bool,
     },
+    /// Generates a software bill of materials for a package and its transitive dependencies
+    Sbom {
+        /// The SBOM document format
+        #[structopt(name = "FORMAT",
+                    short = "f",
+                    long = "format",
+                    possible_values = SbomFormat::VARIANTS,
+                    default_value = "spdx")]
+        format:                String,
+        /// Write the SBOM to a file instead of standard output
+        #[structopt(name = "OUTPUT", short = "o", long = "output")]
+        output:                Option<PathBuf>,
+        /// An installed package identifier (ex: core/redis) or a path to a Biome Artifact (ex:
+        /// /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)
+        #[structopt(name = "PKG_IDENT_OR_ARTIFACT")]
+        pkg_ident_or_artifact: String,
+    },
     /// Search for a package in Builder
     Search {
         /// Search term
@@ -306,14 +400,20 @@ pub enum Pkg {
             long = "origin",
             validator = valid_origin)]
         origin:         Option<String>,
+        /// Add a detached signature to an existing Biome Artifact given as SOURCE, stored in a
+        /// .sig file next to it
+        #[structopt(name = "DETACHED", long = "detached")]
+        detached:       bool,
         /// A path to a source archive file (ex: /home/acme-redis-3.0.7-21120102031201.tar.xz)
         #[structopt(name = "SOURCE",
                     validator = file_exists)]
         source:         PathBuf,
         /// The destination path to the signed Biome Artifact (ex:
         /// /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)
-        #[structopt(name = "DEST")]
-        dest:           PathBuf,
+        #[structopt(name = "DEST",
+                    required_unless = "DETACHED",
+                    conflicts_with = "DETACHED")]
+        dest:           Option<PathBuf>,
         #[structopt(flatten)]
         cache_key_path: CacheKeyPath,
     },
@@ -337,6 +437,16 @@ pub enum Pkg {
         /// Do not run any uninstall hooks
         #[structopt(name = "IGNORE_UNINSTALL_HOOK", long = "ignore-uninstall-hook")]
         ignore_uninstall_hook: bool,
+        /// Stop any service loaded by the Supervisor that uninstalling would break, instead of
+        /// refusing to uninstall
+        #[structopt(name = "FORCE", short = "f", long = "force")]
+        force:                 bool,
+        /// Also uninstall the dependencies which are only orphaned once other dependencies are
+        /// uninstalled
+        #[structopt(name = "CASCADE",
+                    long = "cascade",
+                    conflicts_with_all = &["KEEP_LATEST", "NO_DEPS"])]
+        cascade:               bool,
     },
     /// Uploads a local Biome Artifact to Builder
     Upload {
@@ -371,6 +481,26 @@ pub enum Pkg {
         #[structopt(flatten)]
         cache_key_path: CacheKeyPath,
     },
+    /// Verifies the provenance attestation attached to a container image with an origin key
+    VerifyImage {
+        /// An OCI image layout directory, or an image in a registry (ex: acme/redis:latest)
+        #[structopt(name = "IMAGE")]
+        image:             String,
+        /// Remote registry username
+        #[structopt(name = "REGISTRY_USERNAME",
+                    short = "U",
+                    long = "username",
+                    requires = "REGISTRY_PASSWORD")]
+        registry_username: Option<String>,
+        /// Remote registry password
+        #[structopt(name = "REGISTRY_PASSWORD",
+                    short = "P",
+                    long = "password",
+                    requires = "REGISTRY_USERNAME")]
+        registry_password: Option<String>,
+        #[structopt(flatten)]
+        cache_key_path:    CacheKeyPath,
+    },
 }
 
 /// Executes a command using the 'PATH' context of an installed package
@@ -400,8 +530,11 @@ pub struct PkgInstall {
     channel:               String,
     /// One or more Biome package identifiers (ex: acme/redis) and/or filepaths to a Biome
     /// Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)
-    #[structopt(required = true)]
+    #[structopt(required_unless = "LOCKFILE")]
     pkg_ident_or_artifact: Vec<String>,
+    /// Install exactly the artifacts recorded in a lockfile written by 'bio pkg lock'
+    #[structopt(long = "lockfile", validator = file_exists)]
+    lockfile:              Option<PathBuf>,
     /// Binlink all binaries from installed package(s) into BINLINK_DIR
     #[structopt(short = "b", long = "binlink")]
     binlink:               bool,
@@ -418,6 +551,15 @@ pub struct PkgInstall {
     /// Do not run any install hooks
     #[structopt(long = "ignore-install-hook")]
     ignore_install_hook:   bool,
+    /// Only install artifacts signed by this origin key, in their header or in a detached
+    /// signature. May be given several times (default: the signers in HAB_REQUIRED_SIGNERS)
+    #[structopt(name = "REQUIRED_SIGNER",
+                long = "required-signer",
+                number_of_values = 1)]
+    required_signer:       Vec<String>,
+    /// How many of the required signers must have signed each artifact (default: all)
+    #[structopt(long = "required-signatures", requires = "REQUIRED_SIGNER")]
+    required_signatures:   Option<usize>,
     /// Install packages in offline mode
     #[structopt(long = "offline",
                 hidden = !FEATURE_FLAGS.contains(FeatureFlag::OFFLINE_INSTALL))]
@@ -442,9 +584,15 @@ pub enum ExportCommand {
     #[cfg(any(target_os = "linux", target_os = "windows"))]
     #[structopt(settings = &[AppSettings::Hidden])]
     Docker(ExternalCommandArgs),
+    /// Kubernetes exporter
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    Kubernetes(ExternalCommandArgs),
     /// Mesos exporter
     #[cfg(target_os = "linux")]
     Mesos(ExternalCommandArgs),
+    /// Systemd exporter: the tar exporter, with a systemd unit for the Supervisor
+    #[cfg(target_os = "linux")]
+    Systemd(ExternalCommandArgs),
     /// Tar exporter
     #[cfg(any(target_os = "linux", target_os = "windows"))]
     Tar(ExternalCommandArgs),
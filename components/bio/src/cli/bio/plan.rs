@@ -1,5 +1,6 @@
 use crate::cli::{file_exists,
                  valid_origin};
+use biome_common::templating::helpers::HELPERS_HELP;
 use configopt::ConfigOpt;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -30,6 +31,7 @@ pub enum Plan {
         scaffolding: Option<String>,
     },
     /// Renders plan config files      
+    #[structopt(after_help = HELPERS_HELP)]
     Render {
         /// Path to default.toml
         #[structopt(name = "DEFAULT_TOML",
@@ -14,13 +14,15 @@ use configopt::{self,
 use biome_common::{cli::{RING_ENVVAR,
                            RING_KEY_ENVVAR},
                      command::package::install::InstallSource,
-                     types::{EventStreamConnectMethod,
+                     types::{EventSinkAddress,
+                             EventStreamConnectMethod,
                              EventStreamMetaPair,
                              EventStreamServerCertificate,
                              EventStreamToken,
                              GossipListenAddr,
                              HttpListenAddr,
-                             ListenCtlAddr},
+                             ListenCtlAddr,
+                             MemberLabel},
                      FeatureFlag,
                      FEATURE_FLAGS};
 use biome_core::{env::Config,
@@ -286,6 +288,16 @@ pub struct SupRun {
     /// The certificate should be in PEM format.
     #[structopt(long = "event-stream-server-certificate")]
     pub event_stream_server_certificate: Option<EventStreamServerCertificate>,
+    /// Another destination for events, alongside or instead of the event stream
+    ///
+    /// One of 'file:PATH' or 'unix:PATH' for newline-delimited JSON written to a file or Unix
+    /// socket, an http:// or https:// URL to POST batches of JSON events to, 'syslog' for the
+    /// local syslog daemon, or 'syslog:IP:PORT' for a remote one over UDP. May be given more than
+    /// once. Requires EVENT_STREAM_APPLICATION and EVENT_STREAM_ENVIRONMENT also be set.
+    #[structopt(long = "event-sink",
+                requires_all = &["EVENT_STREAM_APPLICATION",
+                                 "EVENT_STREAM_ENVIRONMENT"])]
+    pub event_sink: Vec<EventSinkAddress>,
     /// Automatically cleanup old packages
     ///
     /// The Supervisor will automatically cleanup old packages only keeping the
@@ -293,6 +305,10 @@ pub struct SupRun {
     /// automatic package cleanup is performed.
     #[structopt(long = "keep-latest-packages", env = "HAB_KEEP_LATEST_PACKAGES")]
     pub keep_latest_packages: Option<usize>,
+    /// A `key=value` label describing this Supervisor. Configuration applied with `bio config
+    /// apply --label` only reaches Supervisors carrying every given label.
+    #[structopt(long = "member-label")]
+    pub member_label: Vec<MemberLabel>,
     /// Paths to files or directories of service config files to load on startup
     ///
     /// See `bio svc bulkload --help` for details
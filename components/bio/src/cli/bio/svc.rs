@@ -15,6 +15,7 @@ use biome_core::{os::process::ShutdownTimeout,
                    package::PackageIdent,
                    service::{BindingMode,
                              HealthCheckInterval,
+                             HookTimeoutOverride,
                              ServiceBind,
                              ServiceGroup},
                    ChannelIdent};
@@ -215,6 +216,13 @@ pub struct SharedLoad {
     /// The default value can be set in the packages plan file.
     #[structopt(long = "shutdown-timeout")]
     pub shutdown_timeout:      Option<ShutdownTimeout>,
+    /// The number of seconds a lifecycle hook may run before it is killed, in the form
+    /// <HOOK>=<SECONDS> (example: init=300)
+    ///
+    /// Default values can be set in the packages plan file.
+    #[structopt(long = "hook-timeout")]
+    #[serde(default)]
+    pub hook_timeout:          Vec<HookTimeoutOverride>,
     #[cfg(target_os = "windows")]
     /// Password of the service user
     #[structopt(long = "password")]
@@ -316,6 +324,7 @@ pub fn shared_load_cli_to_ctl(ident: PackageIdent,
     use biome_sup_protocol::{ctl::{ServiceBindList,
                                      SvcLoad},
                                types::{HealthCheckInterval,
+                                       HookTimeout,
                                        ServiceBind}};
 
     // TODO (DM): This check can eventually be removed.
@@ -368,7 +377,11 @@ pub fn shared_load_cli_to_ctl(ident: PackageIdent,
                  health_check_interval:
                      Some(HealthCheckInterval { seconds: shared_load.health_check_interval, }),
                  shutdown_timeout: shared_load.shutdown_timeout.map(u32::from),
-                 update_condition: Some(shared_load.update_condition as i32) })
+                 update_condition: Some(shared_load.update_condition as i32),
+                 hook_timeouts: shared_load.hook_timeout
+                                           .into_iter()
+                                           .map(HookTimeout::from)
+                                           .collect() })
 }
 
 impl TryFrom<Load> for biome_sup_protocol::ctl::SvcLoad {
@@ -465,6 +478,12 @@ pub struct Update {
     #[structopt(long = "shutdown-timeout")]
     pub shutdown_timeout: Option<ShutdownTimeout>,
 
+    /// The number of seconds a lifecycle hook may run before it is killed, in the form
+    /// <HOOK>=<SECONDS> (example: init=300)
+    #[structopt(long = "hook-timeout")]
+    #[serde(default)]
+    pub hook_timeout: Vec<HookTimeoutOverride>,
+
     /// Password of the service user
     #[cfg(target_os = "windows")]
     #[structopt(long = "password")]
@@ -488,6 +507,10 @@ impl TryFrom<Update> for ctl::SvcUpdate {
                                    update_strategy: u.strategy.map(|v| v as i32),
                                    update_condition: u.update_condition.map(|v| v as i32),
                                    shutdown_timeout: u.shutdown_timeout.map(Into::into),
+                                   hook_timeouts: u.hook_timeout
+                                                   .into_iter()
+                                                   .map(Into::into)
+                                                   .collect(),
                                    #[cfg(windows)]
                                    svc_encrypted_password: u.password,
                                    #[cfg(not(windows))]
@@ -507,11 +530,13 @@ impl TryFrom<Update> for ctl::SvcUpdate {
                                 update_strategy: None,
                                 health_check_interval: None,
                                 shutdown_timeout: None,
-                                update_condition: None, } = &msg
+                                update_condition: None,
+                                hook_timeouts, } = &msg
         {
-            Err(Error::ArgumentError("No fields specified for update".to_string()))
-        } else {
-            Ok(msg)
+            if hook_timeouts.is_empty() {
+                return Err(Error::ArgumentError("No fields specified for update".to_string()));
+            }
         }
+        Ok(msg)
     }
 }
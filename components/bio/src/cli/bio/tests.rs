@@ -8,7 +8,8 @@ use clap::{App,
            ArgSettings};
 use configopt::ConfigOpt;
 use biome_common::FeatureFlag;
-use std::str;
+use std::{convert::TryFrom,
+          str};
 
 fn feature_flags_for_cli_test() -> FeatureFlag {
     let mut f = FeatureFlag::empty();
@@ -450,6 +451,22 @@ fn test_bio_svc_load_flag_ordering() {
     assert_eq!(load.pkg_ident.pkg_ident(), pkg_ident);
 }
 
+#[test]
+fn test_bio_svc_load_version_constraint() {
+    let bio = Bio::try_from_iter_with_configopt(&["bio", "svc", "load", "core/redis@>=4,<6"]).unwrap();
+    let load = extract_bio_svc_load(bio);
+    let pkg_ident = load.pkg_ident.clone().pkg_ident();
+    assert_eq!(pkg_ident.version, None);
+    assert_eq!(pkg_ident.constraint, Some(">=4,<6".parse().unwrap()));
+    assert_eq!(pkg_ident.release, None);
+
+    let svc_load = biome_sup_protocol::ctl::SvcLoad::try_from(load).unwrap();
+    let ident: biome_core::package::PackageIdent = svc_load.ident.unwrap().into();
+    assert_eq!(ident, pkg_ident);
+
+    assert!(Bio::try_from_iter_with_configopt(&["bio", "svc", "load", "core/redis@>=4,"]).is_err());
+}
+
 #[test]
 fn test_bio_svc_update_empty_binds() {
     let bio = Bio::try_from_iter_with_configopt(&["bio", "svc", "update", "core/redis", "--bind"]).unwrap();
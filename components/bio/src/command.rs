@@ -1,5 +1,7 @@
 pub mod bldr;
 pub mod cli;
+pub mod config;
+pub mod depot;
 pub mod launcher;
 pub mod origin;
 pub mod pkg;
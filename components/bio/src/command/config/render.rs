@@ -0,0 +1,236 @@
+use serde_json::Value as Json;
+use similar::TextDiff;
+use std::{fs,
+          io,
+          path::{Path,
+                 PathBuf}};
+use walkdir::WalkDir;
+
+use crate::{common::{self,
+                     templating::TemplateRenderer,
+                     ui::{Glyph,
+                          Status,
+                          UIWriter,
+                          UI}},
+            error::{Error,
+                    Result}};
+
+/// What the Supervisor puts in place of the values it redacts from a render context.
+const REDACTED: &str = "<redacted>";
+
+/// Render templates against the render context of a running service and show a unified diff
+/// of each result against the file the Supervisor last wrote to disk.
+///
+/// When no templates are given, the configuration templates of the installed package are used.
+pub fn start(ui: &mut UI, context: &str, templates: &[PathBuf]) -> Result<()> {
+    let context: Json = serde_json::from_str(context)?;
+    let templates = if templates.is_empty() {
+        package_templates(&context_path(&context, "path")?.join("config"))?
+    } else {
+        named_templates(templates)?
+    };
+    if templates.is_empty() {
+        ui.warn("No templates to render")?;
+        return Ok(());
+    }
+    let svc_config_path = context_path(&context, "svc_config_path")?;
+
+    let mut renderer = TemplateRenderer::new();
+    for (name, path) in &templates {
+        renderer.register_template_file(name, path)
+                .map_err(common::Error::from)?;
+    }
+    for (name, _) in &templates {
+        let on_disk_path = svc_config_path.join(name);
+        let on_disk = match fs::read_to_string(&on_disk_path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let rendered = unredact(&renderer.render(name, &context)?, &on_disk);
+        if rendered == on_disk {
+            ui.status(Status::Found, format!("{} is up to date", name))?;
+        } else {
+            ui.status(Status::Custom(Glyph::RightArrow, String::from("Changed")),
+                      name)?;
+            print!("{}", diff(&on_disk_path, &on_disk, name, &rendered));
+        }
+    }
+    Ok(())
+}
+
+/// Look up a path from the `pkg` section of the render context.
+fn context_path(context: &Json, key: &str) -> Result<PathBuf> {
+    match context["pkg"][key].as_str() {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => {
+            Err(Error::ArgumentError(format!("Render context is missing \
+                                              pkg.{}",
+                                             key)))
+        }
+    }
+}
+
+/// Name explicitly given templates by their file name, the same way the Supervisor names
+/// templates found at the top of a package's `config` directory.
+fn named_templates(templates: &[PathBuf]) -> Result<Vec<(String, PathBuf)>> {
+    templates.iter()
+             .map(|path| {
+                 match path.file_name() {
+                     Some(name) => Ok((name.to_string_lossy().into_owned(), path.clone())),
+                     None => {
+                         Err(Error::ArgumentError(format!("{} is not a template file",
+                                                          path.display())))
+                     }
+                 }
+             })
+             .collect()
+}
+
+/// Collect every file beneath a package's `config` directory, named by its path relative to
+/// that directory.
+fn package_templates(config_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !config_dir.is_dir() {
+        return Err(Error::FileNotFound(format!("{} (use --template when the \
+                                                package is not installed \
+                                                locally)",
+                                               config_dir.display())));
+    }
+    let mut templates = Vec::new();
+    for entry in WalkDir::new(config_dir).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let name = entry.path()
+                            .strip_prefix(config_dir)?
+                            .to_string_lossy()
+                            .into_owned();
+            templates.push((name, entry.path().to_path_buf()));
+        }
+    }
+    Ok(templates)
+}
+
+/// The Supervisor redacts the values of keys that look like secrets from the render context, so
+/// a line rendered from one can never match the line on disk. Compare such lines as they were
+/// before redaction by taking the line from disk in their place whenever it is the same apart
+/// from the redacted values.
+fn unredact(rendered: &str, on_disk: &str) -> String {
+    let on_disk = on_disk.split('\n').collect::<Vec<_>>();
+    let mut next = 0;
+    rendered.split('\n')
+            .map(|line| {
+                if !line.contains(REDACTED) {
+                    return line;
+                }
+                // Look after the last line we took first, so that lines which only differ in
+                // their redacted values are matched up in order.
+                let found = (next..on_disk.len()).chain(0..next)
+                                                 .find(|&i| matches_redacted(line, on_disk[i]));
+                match found {
+                    Some(i) => {
+                        next = i + 1;
+                        on_disk[i]
+                    }
+                    None => line,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+}
+
+/// Whether `line` is the same as `pattern` once each redacted value in `pattern` is replaced.
+fn matches_redacted(pattern: &str, line: &str) -> bool {
+    let pieces = pattern.split(REDACTED).collect::<Vec<_>>();
+    let (first, middle, last) = match pieces.as_slice() {
+        [first, middle @ .., last] => (first, middle, last),
+        _ => return pattern == line,
+    };
+    if !line.starts_with(first) {
+        return false;
+    }
+    let mut remaining = &line[first.len()..];
+    for piece in middle {
+        match remaining.find(piece) {
+            Some(i) => remaining = &remaining[i + piece.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last)
+}
+
+fn diff(on_disk_path: &Path, on_disk: &str, name: &str, rendered: &str) -> String {
+    TextDiff::from_lines(on_disk, rendered).unified_diff()
+                                           .header(&on_disk_path.to_string_lossy(),
+                                                   &format!("{} (rendered)", name))
+                                           .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn package_templates_are_named_relative_to_config_dir() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("conf.d")).unwrap();
+        fs::write(tmp.path().join("redis.conf"), "").unwrap();
+        fs::write(tmp.path().join("conf.d").join("extra.conf"), "").unwrap();
+
+        let names = package_templates(tmp.path()).unwrap()
+                                                 .into_iter()
+                                                 .map(|(name, _)| name)
+                                                 .collect::<Vec<_>>();
+        assert_eq!(names,
+                   vec![Path::new("conf.d").join("extra.conf")
+                                           .to_string_lossy()
+                                           .into_owned(),
+                        "redis.conf".to_string()]);
+    }
+
+    #[test]
+    fn missing_config_dir_is_an_error() {
+        let tmp = TempDir::new().unwrap();
+        assert!(package_templates(&tmp.path().join("config")).is_err());
+    }
+
+    #[test]
+    fn diff_shows_changed_lines() {
+        let output = diff(Path::new("/svc/config/app.conf"),
+                          "port = 1\nhost = a\n",
+                          "app.conf",
+                          "port = 2\nhost = a\n");
+        assert!(output.contains("-port = 1"));
+        assert!(output.contains("+port = 2"));
+        assert!(output.contains(" host = a"));
+    }
+
+    #[test]
+    fn unredact_takes_secret_lines_from_disk() {
+        let on_disk = "user = \"app\"\npassword = \"hunter2\"\ntoken = \"abc\"\n";
+        let rendered = "user = \"app\"\npassword = \"<redacted>\"\ntoken = \"<redacted>\"\n";
+        assert_eq!(unredact(rendered, on_disk), on_disk);
+    }
+
+    #[test]
+    fn unredact_keeps_changed_secret_lines() {
+        let on_disk = "password = \"hunter2\"\n";
+        let rendered = "db_password = \"<redacted>\"\n";
+        assert_eq!(unredact(rendered, on_disk), rendered);
+    }
+
+    #[test]
+    fn matches_redacted_requires_everything_else_to_match() {
+        assert!(matches_redacted("a <redacted> b <redacted> c", "a 1 b 2 c"));
+        assert!(!matches_redacted("a <redacted> b <redacted> c", "a 1 x 2 c"));
+        assert!(!matches_redacted("a = <redacted>;", "a = 1"));
+        assert!(matches_redacted("<redacted>", "anything"));
+    }
+
+    #[test]
+    fn named_templates_need_a_file_name() {
+        assert!(named_templates(&[PathBuf::from("/")]).is_err());
+        assert_eq!(named_templates(&[PathBuf::from("/tmp/app.conf")]).unwrap()[0].0,
+                   "app.conf");
+    }
+}
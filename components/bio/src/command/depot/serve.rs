@@ -0,0 +1,703 @@
+//! Serves packages and keys over the parts of the Builder API used to install them.
+//!
+//! # Examples
+//!
+//! ```bash
+//! $ bio pkg download --download-directory /srv/bio -f idents.toml
+//! $ bio depot serve --download-directory /srv/bio
+//! $ bio sup run --bldr-url http://localhost:9636
+//! ```
+//!
+//! This would serve everything `bio pkg download` fetched into `/srv/bio` to Supervisors and
+//! `bio pkg install` on a network with no access to Builder.
+//!
+//! # Internals
+//!
+//! * Index the artifacts in the artifact directory by ident and target
+//! * Answer `depot/pkgs` and `depot/channels` package queries with the latest matching artifact,
+//!   and list every release in a channel so clients can resolve version constraints. Downloaded
+//!   artifacts don't record the channels they were promoted to, so every artifact is served from
+//!   every channel
+//! * Stream artifacts and public origin signing keys with the `X-Filename` header the client
+//!   expects
+//! * Optionally accept `bio pkg upload` and `bio origin key upload`, adding them to the index
+
+use crate::{common::ui::{Glyph,
+                         Status,
+                         UIWriter,
+                         UI},
+            error::Result,
+            hcore::{self,
+                    crypto::{hash,
+                             keys::{Key,
+                                    KeyCache,
+                                    KeyFile,
+                                    NamedRevision,
+                                    PublicOriginSigningKey}},
+                    fs::AtomicWriter,
+                    package::{PackageArchive,
+                              PackageIdent,
+                              PackageTarget}}};
+use actix_rt::System;
+use actix_web::{web::{self,
+                      Bytes,
+                      Data,
+                      Path,
+                      PayloadConfig,
+                      Query},
+                App,
+                HttpResponse,
+                HttpServer,
+                Scope};
+use futures::stream;
+use serde_json::json;
+use std::{cmp::Reverse,
+          fmt,
+          fs,
+          io::{self,
+               Write},
+          net::SocketAddr,
+          path::{Path as FsPath,
+                 PathBuf},
+          result,
+          str,
+          sync::RwLock,
+          thread};
+use tokio::io::AsyncReadExt;
+
+/// The path `BuilderAPIClient` adds to a bare Builder URL.
+const API_PATH: &str = "/v1";
+/// The header `BuilderAPIClient` takes the name of a downloaded file from.
+const X_FILENAME: &str = "x-filename";
+/// Channels reported for every artifact.
+const CHANNELS: &[&str] = &["stable", "unstable"];
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+const MAX_UPLOAD_SIZE: usize = 2 * 1024 * 1024 * 1024;
+
+/// Serve the artifacts in `artifact_path` and the public keys in `key_path` until the process is
+/// interrupted.
+pub fn start(ui: &mut UI,
+             artifact_path: PathBuf,
+             key_path: PathBuf,
+             listen: SocketAddr,
+             allow_upload: bool)
+             -> Result<()> {
+    ui.begin(format!("Indexing artifacts in {}", artifact_path.display()))?;
+    let depot = Depot::new(artifact_path, key_path, allow_upload)?;
+    ui.status(Status::Found,
+              format!("{} artifacts",
+                      depot.artifacts.read().expect("lock poisoned").len()))?;
+    if allow_upload {
+        ui.warn("Uploads are enabled and are not authenticated")?;
+    }
+    ui.status(Status::Custom(Glyph::RightArrow, String::from("Serving")),
+              format!("http://{}", listen))?;
+
+    let depot = Data::new(depot);
+    // The server runs on its own actix system, which can't be started from within the runtime
+    // the rest of `bio` runs on.
+    thread::spawn(move || System::new("bio-depot").block_on(serve(depot, listen)))
+        .join()
+        .expect("depot server thread panicked")?;
+    Ok(())
+}
+
+async fn serve(depot: Data<Depot>, listen: SocketAddr) -> io::Result<()> {
+    HttpServer::new(move || {
+        App::new().app_data(depot.clone())
+                  .app_data(PayloadConfig::new(MAX_UPLOAD_SIZE))
+                  .service(routes())
+    }).bind(listen)?
+      .run()
+      .await
+}
+
+fn routes() -> Scope {
+    web::scope(API_PATH).route("/depot/pkgs/{origin}/{name}/latest",
+                               web::get().to(show_latest))
+                        .route("/depot/pkgs/{origin}/{name}/{version}/latest",
+                               web::get().to(show_version_latest))
+                        .route("/depot/pkgs/{origin}/{name}/{version}/{release}",
+                               web::get().to(show_release))
+                        .route("/depot/pkgs/{origin}/{name}/{version}/{release}",
+                               web::post().to(upload_package))
+                        .route("/depot/pkgs/{origin}/{name}/{version}/{release}/download",
+                               web::get().to(download_package))
+                        .route("/depot/pkgs/{origin}/{name}/{version}/{release}/channels",
+                               web::get().to(package_channels))
+                        .route("/depot/channels/{origin}", web::get().to(list_channels))
+                        .route("/depot/channels/{origin}/{channel}/pkgs/{name}",
+                               web::get().to(channel_list_releases))
+                        .route("/depot/channels/{origin}/{channel}/pkgs/{name}/latest",
+                               web::get().to(channel_show_latest))
+                        .route("/depot/channels/{origin}/{channel}/pkgs/{name}/{version}/latest",
+                               web::get().to(channel_show_version_latest))
+                        .route("/depot/channels/{origin}/{channel}/pkgs/{name}/{version}/{release}",
+                               web::get().to(channel_show_release))
+                        .route("/depot/origins/{origin}/keys",
+                               web::get().to(list_origin_keys))
+                        .route("/depot/origins/{origin}/keys/{revision}",
+                               web::get().to(download_origin_key))
+                        .route("/depot/origins/{origin}/keys/{revision}",
+                               web::post().to(upload_origin_key))
+}
+
+/// An artifact the depot can serve.
+#[derive(Clone, Debug)]
+struct Artifact {
+    ident:  PackageIdent,
+    target: PackageTarget,
+    path:   PathBuf,
+}
+
+struct Depot {
+    artifact_path: PathBuf,
+    key_cache:     KeyCache,
+    allow_upload:  bool,
+    artifacts:     RwLock<Vec<Artifact>>,
+}
+
+impl Depot {
+    fn new(artifact_path: PathBuf, key_path: PathBuf, allow_upload: bool) -> Result<Self> {
+        let artifacts = RwLock::new(index(&artifact_path)?);
+        let key_cache = KeyCache::new(key_path);
+        key_cache.setup()?;
+        Ok(Depot { artifact_path,
+                   key_cache,
+                   allow_upload,
+                   artifacts })
+    }
+
+    /// The newest artifact for `target` which satisfies `ident`.
+    fn latest(&self, ident: &PackageIdent, target: PackageTarget) -> Option<Artifact> {
+        self.artifacts
+            .read()
+            .expect("lock poisoned")
+            .iter()
+            .filter(|a| a.target == target && a.ident.satisfies(ident))
+            .max_by(|a, b| a.ident.cmp(&b.ident))
+            .cloned()
+    }
+
+    /// The idents of every artifact for `target` which satisfies `ident`, oldest first.
+    fn releases(&self, ident: &PackageIdent, target: PackageTarget) -> Vec<PackageIdent> {
+        let mut releases: Vec<_> = self.artifacts
+                                       .read()
+                                       .expect("lock poisoned")
+                                       .iter()
+                                       .filter(|a| a.target == target && a.ident.satisfies(ident))
+                                       .map(|a| a.ident.clone())
+                                       .collect();
+        releases.sort();
+        releases
+    }
+
+    /// The public signing keys of `origin`, newest first.
+    fn origin_keys(&self, origin: &str) -> io::Result<Vec<NamedRevision>> {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.key_cache)? {
+            let file_name = entry?.file_name().to_string_lossy().into_owned();
+            let named_revision = match file_name.trim_end_matches(".pub").parse::<NamedRevision>() {
+                Ok(nr) if file_name.ends_with(".pub") && nr.name() == origin => nr,
+                _ => continue,
+            };
+            // Origin encryption keys share the extension; only list signing keys
+            if self.key_cache.public_signing_key(&named_revision).is_ok() {
+                keys.push(named_revision);
+            }
+        }
+        keys.sort_by_key(|k| Reverse(k.revision().to_string()));
+        Ok(keys)
+    }
+
+    /// Stores an uploaded artifact, checking it is the one its request describes.
+    fn add_artifact(&self,
+                    ident: &PackageIdent,
+                    target: PackageTarget,
+                    checksum: &str,
+                    body: &[u8])
+                    -> result::Result<(), HttpResponse> {
+        if self.latest(ident, target).is_some() {
+            return Err(HttpResponse::Conflict().finish());
+        }
+        if hash::hash_bytes(body).to_string() != checksum {
+            return Err(HttpResponse::UnprocessableEntity().body("Checksum does not match"));
+        }
+        let file_name = ident.archive_name_with_target(target)
+                             .map_err(|_| HttpResponse::BadRequest().finish())?;
+        let path = self.artifact_path.join(file_name);
+        AtomicWriter::new(&path).and_then(|w| w.with_writer(|f| f.write_all(body)))
+                                .map_err(internal_error)?;
+
+        let mut archive = PackageArchive::new(&path).map_err(internal_error)?;
+        match (archive.ident(), archive.target()) {
+            (Ok(ref i), Ok(t)) if i == ident && t == target => {}
+            _ => {
+                let _ = fs::remove_file(&path);
+                return Err(HttpResponse::UnprocessableEntity().body("Artifact does not match \
+                                                                     the requested ident and \
+                                                                     target"));
+            }
+        }
+        self.artifacts
+            .write()
+            .expect("lock poisoned")
+            .push(Artifact { ident: ident.clone(),
+                             target,
+                             path });
+        Ok(())
+    }
+}
+
+/// Reads the ident and target of every artifact in `artifact_path`.
+fn index(artifact_path: &FsPath) -> Result<Vec<Artifact>> {
+    fs::create_dir_all(artifact_path)?;
+    let mut artifacts = Vec::new();
+    for entry in fs::read_dir(artifact_path)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |e| e != "hart") {
+            continue;
+        }
+        let mut archive = PackageArchive::new(&path)?;
+        match (archive.ident(), archive.target()) {
+            (Ok(ident), Ok(target)) => {
+                artifacts.push(Artifact { ident,
+                                          target,
+                                          path })
+            }
+            (Err(e), _) | (_, Err(e)) => warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    Ok(artifacts)
+}
+
+fn internal_error<E: fmt::Display>(err: E) -> HttpResponse {
+    error!("{}", err);
+    HttpResponse::InternalServerError().finish()
+}
+
+#[derive(Deserialize)]
+struct TargetQuery {
+    target: Option<PackageTarget>,
+}
+
+impl TargetQuery {
+    fn target(&self) -> PackageTarget { self.target.unwrap_or_else(PackageTarget::active_target) }
+}
+
+#[derive(Deserialize)]
+struct UploadQuery {
+    checksum: String,
+    target:   PackageTarget,
+}
+
+// Begin route handlers
+
+#[allow(clippy::needless_pass_by_value)]
+fn show_latest(depot: Data<Depot>,
+               path: Path<(String, String)>,
+               query: Query<TargetQuery>)
+               -> HttpResponse {
+    let (origin, name) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, None, None),
+         query.target())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn show_version_latest(depot: Data<Depot>,
+                       path: Path<(String, String, String)>,
+                       query: Query<TargetQuery>)
+                       -> HttpResponse {
+    let (origin, name, version) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, Some(version), None),
+         query.target())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn show_release(depot: Data<Depot>,
+                path: Path<(String, String, String, String)>,
+                query: Query<TargetQuery>)
+                -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, Some(version), Some(release)),
+         query.target())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn channel_list_releases(depot: Data<Depot>,
+                         path: Path<(String, String, String)>,
+                         query: Query<TargetQuery>)
+                         -> HttpResponse {
+    let (origin, _channel, name) = path.into_inner();
+    let releases = depot.releases(&PackageIdent::new(origin, name, None, None), query.target());
+    // Everything fits in a single page
+    HttpResponse::Ok().json(json!({
+                                "range_start": 0,
+                                "range_end": releases.len().saturating_sub(1),
+                                "total_count": releases.len(),
+                                "data": releases,
+                            }))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn channel_show_latest(depot: Data<Depot>,
+                       path: Path<(String, String, String)>,
+                       query: Query<TargetQuery>)
+                       -> HttpResponse {
+    let (origin, _channel, name) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, None, None),
+         query.target())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn channel_show_version_latest(depot: Data<Depot>,
+                               path: Path<(String, String, String, String)>,
+                               query: Query<TargetQuery>)
+                               -> HttpResponse {
+    let (origin, _channel, name, version) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, Some(version), None),
+         query.target())
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn channel_show_release(depot: Data<Depot>,
+                        path: Path<(String, String, String, String, String)>,
+                        query: Query<TargetQuery>)
+                        -> HttpResponse {
+    let (origin, _channel, name, version, release) = path.into_inner();
+    show(&depot,
+         &PackageIdent::new(origin, name, Some(version), Some(release)),
+         query.target())
+}
+
+fn show(depot: &Depot, ident: &PackageIdent, target: PackageTarget) -> HttpResponse {
+    let artifact = match depot.latest(ident, target) {
+        Some(artifact) => artifact,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    let mut archive = match PackageArchive::new(&artifact.path) {
+        Ok(archive) => archive,
+        Err(e) => return internal_error(e),
+    };
+    let package = (|| -> hcore::error::Result<_> {
+        Ok(json!({
+            "ident": artifact.ident,
+            "target": artifact.target,
+            "checksum": archive.checksum()?,
+            "manifest": archive.manifest()?,
+            "deps": archive.deps()?,
+            "tdeps": archive.tdeps()?,
+            "exposes": archive.exposes()?,
+            "config": archive.config().unwrap_or_default(),
+            "channels": CHANNELS,
+        }))
+    })();
+    match package {
+        Ok(package) => HttpResponse::Ok().json(package),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+async fn download_package(depot: Data<Depot>,
+                          path: Path<(String, String, String, String)>,
+                          query: Query<TargetQuery>)
+                          -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+    let artifact = match depot.latest(&ident, query.target()) {
+        Some(artifact) => artifact,
+        None => return HttpResponse::NotFound().finish(),
+    };
+    let file = match tokio::fs::File::open(&artifact.path).await {
+        Ok(file) => file,
+        Err(e) => return internal_error(e),
+    };
+    let chunks = stream::unfold(Some(file), |file| {
+        async move {
+            let mut file = match file {
+                Some(file) => file,
+                None => return None,
+            };
+            let mut buf = vec![0; DOWNLOAD_CHUNK_SIZE];
+            match file.read(&mut buf).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buf.truncate(n);
+                    Some((Ok(Bytes::from(buf)), Some(file)))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    });
+    let file_name = artifact.path
+                            .file_name()
+                            .map(|f| f.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+    HttpResponse::Ok().content_type("application/octet-stream")
+                      .header(X_FILENAME, file_name)
+                      .streaming(Box::pin(chunks))
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn package_channels(depot: Data<Depot>,
+                    path: Path<(String, String, String, String)>,
+                    query: Query<TargetQuery>)
+                    -> HttpResponse {
+    let (origin, name, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+    match depot.latest(&ident, query.target()) {
+        Some(_) => HttpResponse::Ok().json(CHANNELS),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn upload_package(depot: Data<Depot>,
+                  path: Path<(String, String, String, String)>,
+                  query: Query<UploadQuery>,
+                  body: Bytes)
+                  -> HttpResponse {
+    if !depot.allow_upload {
+        return HttpResponse::Forbidden().body("Uploads are disabled");
+    }
+    let (origin, name, version, release) = path.into_inner();
+    let ident = PackageIdent::new(origin, name, Some(version), Some(release));
+    match depot.add_artifact(&ident, query.target, &query.checksum, &body) {
+        Ok(()) => {
+            info!("Added {} ({})", ident, query.target);
+            HttpResponse::Created().finish()
+        }
+        Err(response) => response,
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn list_channels(_path: Path<(String,)>) -> HttpResponse {
+    let channels = CHANNELS.iter()
+                           .map(|name| json!({ "name": name }))
+                           .collect::<Vec<_>>();
+    HttpResponse::Ok().json(channels)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn list_origin_keys(depot: Data<Depot>, path: Path<(String,)>) -> HttpResponse {
+    let (origin,) = path.into_inner();
+    match depot.origin_keys(&origin) {
+        Ok(keys) => {
+            let keys = keys.iter()
+                           .map(|k| {
+                               json!({
+                                   "origin": k.name(),
+                                   "revision": k.revision().to_string(),
+                                   "location": format!("/origins/{}/keys/{}",
+                                                       k.name(),
+                                                       k.revision()),
+                               })
+                           })
+                           .collect::<Vec<_>>();
+            HttpResponse::Ok().json(keys)
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn download_origin_key(depot: Data<Depot>, path: Path<(String, String)>) -> HttpResponse {
+    let (origin, revision) = path.into_inner();
+    let key =
+        format!("{}-{}", origin, revision).parse::<NamedRevision>()
+                                          .and_then(|nr| depot.key_cache.public_signing_key(&nr));
+    match key {
+        Ok(key) => {
+            HttpResponse::Ok().content_type("application/octet-stream")
+                              .header(X_FILENAME, key.own_filename().to_string_lossy().as_ref())
+                              .body(key.to_key_string())
+        }
+        Err(_) => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn upload_origin_key(depot: Data<Depot>,
+                     path: Path<(String, String)>,
+                     body: Bytes)
+                     -> HttpResponse {
+    if !depot.allow_upload {
+        return HttpResponse::Forbidden().body("Uploads are disabled");
+    }
+    let (origin, revision) = path.into_inner();
+    let key = match str::from_utf8(&body).map(str::parse::<PublicOriginSigningKey>) {
+        Ok(Ok(key)) => key,
+        _ => return HttpResponse::BadRequest().body("Not a public origin signing key"),
+    };
+    if key.named_revision().to_string() != format!("{}-{}", origin, revision) {
+        return HttpResponse::UnprocessableEntity().body("Key does not match the requested \
+                                                         origin and revision");
+    }
+    match depot.key_cache.write_key(&key) {
+        Ok(()) => {
+            info!("Added key {}", key.named_revision());
+            HttpResponse::Created().finish()
+        }
+        Err(_) => HttpResponse::Conflict().finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode,
+                    test};
+    use biome_api_client::{OriginKeyIdent,
+                             Package,
+                             PackageResults};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    const ARTIFACT: &str = "happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart";
+    const KEY: &str = "happyhumans-20160424223347.pub";
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures")
+    }
+
+    fn depot(dir: &TempDir) -> Depot {
+        let artifact_path = dir.path().join("artifacts");
+        let key_path = dir.path().join("keys");
+        fs::create_dir_all(&artifact_path).unwrap();
+        fs::create_dir_all(&key_path).unwrap();
+        fs::copy(fixtures().join(ARTIFACT), artifact_path.join(ARTIFACT)).unwrap();
+        fs::copy(fixtures().join(KEY), key_path.join(KEY)).unwrap();
+        fs::write(artifact_path.join("notes.txt"), "not an artifact").unwrap();
+        Depot::new(artifact_path, key_path, false).unwrap()
+    }
+
+    #[test]
+    fn latest_matches_partial_idents_for_the_target() {
+        let dir = TempDir::new().unwrap();
+        let depot = depot(&dir);
+        let target = PackageTarget::from_str("x86_64-linux").unwrap();
+        let expected: PackageIdent = "happyhumans/possums/8.1.4/20160427165340".parse().unwrap();
+
+        for partial in &["happyhumans/possums",
+                         "happyhumans/possums/8.1.4",
+                         "happyhumans/possums/8.1.4/20160427165340"]
+        {
+            let artifact = depot.latest(&partial.parse().unwrap(), target).unwrap();
+            assert_eq!(artifact.ident, expected);
+        }
+        assert!(depot.latest(&"happyhumans/possums/9.0.0".parse().unwrap(), target)
+                     .is_none());
+        assert!(depot.latest(&"core/possums".parse().unwrap(), target)
+                     .is_none());
+    }
+
+    #[test]
+    fn origin_keys_lists_public_signing_keys_of_the_origin() {
+        let dir = TempDir::new().unwrap();
+        let depot = depot(&dir);
+
+        let keys = depot.origin_keys("happyhumans").unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].to_string(), "happyhumans-20160424223347");
+        assert!(depot.origin_keys("core").unwrap().is_empty());
+    }
+
+    /// The URI of `path` under the API path, for the fixture's target.
+    fn uri(path: &str) -> String { format!("{}{}?target=x86_64-linux", API_PATH, path) }
+
+    #[actix_rt::test]
+    async fn package_routes_serve_builder_package_metadata() {
+        let dir = TempDir::new().unwrap();
+        let mut app =
+            test::init_service(App::new().app_data(Data::new(depot(&dir))).service(routes())).await;
+        let expected: PackageIdent = "happyhumans/possums/8.1.4/20160427165340".parse().unwrap();
+        let mut archive = PackageArchive::new(fixtures().join(ARTIFACT)).unwrap();
+
+        for path in &["/depot/pkgs/happyhumans/possums/latest",
+                      "/depot/pkgs/happyhumans/possums/8.1.4/latest",
+                      "/depot/pkgs/happyhumans/possums/8.1.4/20160427165340",
+                      "/depot/channels/happyhumans/stable/pkgs/possums/latest",
+                      "/depot/channels/happyhumans/stable/pkgs/possums/8.1.4/20160427165340"]
+        {
+            let req = test::TestRequest::get().uri(&uri(path)).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", path);
+            let body = test::read_body(resp).await;
+            let package: Package = serde_json::from_slice(&body).unwrap();
+            assert_eq!(package.ident, expected);
+            assert_eq!(package.checksum, archive.checksum().unwrap());
+            assert_eq!(package.deps, archive.deps().unwrap());
+            assert_eq!(package.tdeps, archive.tdeps().unwrap());
+        }
+
+        let req = test::TestRequest::get().uri(&uri("/depot/pkgs/core/possums/latest"))
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn channel_list_releases_serves_builder_package_results() {
+        let dir = TempDir::new().unwrap();
+        let mut app =
+            test::init_service(App::new().app_data(Data::new(depot(&dir))).service(routes())).await;
+
+        let req = test::TestRequest::get().uri(&uri("/depot/channels/happyhumans/stable/pkgs/\
+                                                     possums"))
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let results: PackageResults<PackageIdent> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results.range_start, 0);
+        assert_eq!(results.range_end, 0);
+        assert_eq!(results.total_count, 1);
+        assert_eq!(results.data,
+                   vec!["happyhumans/possums/8.1.4/20160427165340".parse::<PackageIdent>()
+                                                                     .unwrap()]);
+    }
+
+    #[actix_rt::test]
+    async fn artifact_and_key_routes_serve_files() {
+        let dir = TempDir::new().unwrap();
+        let mut app =
+            test::init_service(App::new().app_data(Data::new(depot(&dir))).service(routes())).await;
+
+        let req = test::TestRequest::get().uri(&uri("/depot/pkgs/happyhumans/possums/8.1.4/\
+                                                     20160427165340/download"))
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(X_FILENAME).unwrap(), ARTIFACT);
+        let body = test::read_body(resp).await;
+        assert_eq!(&body[..], &fs::read(fixtures().join(ARTIFACT)).unwrap()[..]);
+
+        let req = test::TestRequest::get().uri("/v1/depot/origins/happyhumans/keys")
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let keys: Vec<OriginKeyIdent> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].origin, "happyhumans");
+        assert_eq!(keys[0].revision, "20160424223347");
+
+        let req = test::TestRequest::get().uri("/v1/depot/origins/happyhumans/keys/20160424223347")
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(X_FILENAME).unwrap(), KEY);
+        let body = test::read_body(resp).await;
+        let key = str::from_utf8(&body).unwrap()
+                                       .parse::<PublicOriginSigningKey>()
+                                       .unwrap();
+        assert_eq!(key.named_revision().to_string(), "happyhumans-20160424223347");
+    }
+}
@@ -5,22 +5,27 @@ pub mod channels;
 pub mod delete;
 pub mod demote;
 pub mod dependencies;
+pub mod diff;
 pub mod download;
 pub mod env;
 pub mod exec;
 pub mod export;
+pub mod gc;
 pub mod hash;
 pub mod header;
 pub mod info;
 pub mod list;
+pub mod lock;
 pub mod path;
 pub mod promote;
 pub mod provides;
+pub mod sbom;
 pub mod search;
 pub mod sign;
 pub mod uninstall;
 pub mod upload;
 pub mod verify;
+pub mod verify_image;
 
 /// Used in commands like uninstall which provide a --dry-run option
 #[derive(Clone, Copy)]
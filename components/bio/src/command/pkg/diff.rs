@@ -0,0 +1,455 @@
+//! Compares two releases of a package, each given as an installed package identifier or a path
+//! to a `.hart` file.
+//!
+//! # Examples
+//!
+//! ```bash
+//! $ bio pkg diff core/redis/4.0.14/20190319155852 core/redis/5.0.7/20200110201405
+//! $ bio pkg diff core/redis ./results/core-redis-5.0.7-20200110201405-x86_64-linux.hart --json
+//! ```
+//!
+//! # Internals
+//!
+//! * Collect every regular file of each package, keyed by its path relative to the package's
+//!   install prefix, along with its size and blake2b hash
+//! * Compare the dependency and exposed port metafiles as lists, the service user and group as
+//!   values, and the manifest, default configuration and hooks as text
+//! * Report the files that were added, removed or whose contents changed
+
+use crate::{common::ui::{UIWriter,
+                         UI},
+            error::Result,
+            hcore::{crypto::hash,
+                    package::{metadata::MetaFile,
+                              PackageArchive,
+                              PackageIdent,
+                              PackageInstall}}};
+use similar::TextDiff;
+use std::{collections::{BTreeMap,
+                        BTreeSet},
+          fs::File,
+          io::Read,
+          path::Path,
+          str::FromStr};
+use walkdir::WalkDir;
+
+/// Metafiles holding whitespace separated items, compared as sets.
+const LIST_METAFILES: &[(MetaFile, &str)] = &[(MetaFile::Deps, "deps"),
+                                              (MetaFile::TDeps, "tdeps"),
+                                              (MetaFile::Exposes, "exposes")];
+
+/// Metafiles holding a single value.
+const VALUE_METAFILES: &[(MetaFile, &str)] = &[(MetaFile::SvcUser, "svc_user"),
+                                               (MetaFile::SvcGroup, "svc_group")];
+
+/// The default configuration shipped at the top of a package.
+const DEFAULT_CONFIG: &str = "default.toml";
+
+/// The directory holding a package's lifecycle hooks.
+const HOOKS_DIR: &str = "hooks/";
+
+/// The length hashes are abbreviated to in text output.
+const SHORT_HASH_LEN: usize = 12;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FileChange {
+    pub path:      String,
+    pub from_size: u64,
+    pub to_size:   u64,
+    pub from_hash: String,
+    pub to_hash:   String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MetadataChange {
+    List {
+        name:    String,
+        added:   Vec<String>,
+        removed: Vec<String>,
+    },
+    Value {
+        name: String,
+        from: Option<String>,
+        to:   Option<String>,
+    },
+    Text {
+        name: String,
+        diff: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageDiff {
+    pub from:     String,
+    pub to:       String,
+    pub metadata: Vec<MetadataChange>,
+    pub added:    Vec<FileEntry>,
+    pub removed:  Vec<FileEntry>,
+    pub changed:  Vec<FileChange>,
+}
+
+impl PackageDiff {
+    fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+        && self.added.is_empty()
+        && self.removed.is_empty()
+        && self.changed.is_empty()
+    }
+}
+
+/// The files of one side of the comparison, along with the contents of those we compare as
+/// metadata.
+struct Package {
+    ident:    PackageIdent,
+    files:    BTreeMap<String, FileEntry>,
+    contents: BTreeMap<String, String>,
+}
+
+impl Package {
+    fn new(ident: PackageIdent) -> Self {
+        Package { ident,
+                  files: BTreeMap::new(),
+                  contents: BTreeMap::new() }
+    }
+
+    /// Load a package from a `.hart` file if `source` names one, or else from the installed
+    /// package matching `source` as an identifier.
+    fn load(source: &str, fs_root_path: &Path) -> Result<Self> {
+        let path = Path::new(source);
+        if path.is_file() {
+            Self::from_hart(path)
+        } else {
+            Self::from_installed(&PackageIdent::from_str(source)?, fs_root_path)
+        }
+    }
+
+    fn from_hart(path: &Path) -> Result<Self> {
+        let mut archive = PackageArchive::new(path)?;
+        let prefix = Path::new("hab").join("pkgs")
+                                     .join(archive.ident()?.to_string());
+        let mut package = Package::new(archive.ident()?);
+        let mut tar = archive.tar()?;
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let entry_path = entry.path()?.into_owned();
+            let entry_path = entry_path.strip_prefix("/").unwrap_or(&entry_path);
+            if let Ok(relative) = entry_path.strip_prefix(&prefix) {
+                let size = entry.header().size()?;
+                package.add(relative, size, &mut entry)?;
+            }
+        }
+        Ok(package)
+    }
+
+    fn from_installed(ident: &PackageIdent, fs_root_path: &Path) -> Result<Self> {
+        let install = PackageInstall::load(ident, Some(fs_root_path))?;
+        let root = install.installed_path();
+        let mut package = Package::new(install.ident().clone());
+        for entry in WalkDir::new(root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let size = entry.metadata()?.len();
+            let mut file = File::open(entry.path())?;
+            package.add(entry.path().strip_prefix(root)?, size, &mut file)?;
+        }
+        Ok(package)
+    }
+
+    fn add(&mut self, path: &Path, size: u64, reader: &mut dyn Read) -> Result<()> {
+        let path = path.components()
+                       .map(|c| c.as_os_str().to_string_lossy())
+                       .collect::<Vec<_>>()
+                       .join("/");
+        let hash = if is_metadata(&path) {
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents)?;
+            let hash = hash::hash_bytes(&contents);
+            self.contents.insert(path.clone(),
+                                 String::from_utf8_lossy(&contents).into_owned());
+            hash
+        } else {
+            hash::hash_reader(reader)?
+        };
+        self.files.insert(path.clone(),
+                          FileEntry { path,
+                                      size,
+                                      hash: hash.to_string() });
+        Ok(())
+    }
+
+    fn metafile(&self, metafile: MetaFile) -> Option<&str> {
+        self.contents
+            .get(&metafile.to_string())
+            .map(|contents| contents.trim())
+    }
+}
+
+pub fn start(ui: &mut UI, from: &str, to: &str, fs_root_path: &Path, to_json: bool) -> Result<()> {
+    let diff = compare(&Package::load(from, fs_root_path)?,
+                       &Package::load(to, fs_root_path)?);
+
+    if to_json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    ui.begin(format!("Comparing {} with {}", diff.from, diff.to))?;
+    if diff.is_empty() {
+        ui.end("No differences found")?;
+        return Ok(());
+    }
+
+    if !diff.metadata.is_empty() {
+        ui.heading("Metadata")?;
+        for change in &diff.metadata {
+            match change {
+                MetadataChange::List { name,
+                                       added,
+                                       removed, } => {
+                    println!("{}", name);
+                    for item in removed {
+                        println!("  - {}", item);
+                    }
+                    for item in added {
+                        println!("  + {}", item);
+                    }
+                }
+                MetadataChange::Value { name, from, to } => {
+                    println!("{}: {} -> {}",
+                             name,
+                             from.as_deref().unwrap_or("(none)"),
+                             to.as_deref().unwrap_or("(none)"));
+                }
+                MetadataChange::Text { diff, .. } => print!("{}", diff),
+            }
+        }
+        ui.br()?;
+    }
+
+    if !(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty()) {
+        ui.heading("Files")?;
+        for file in &diff.removed {
+            println!("- {} ({} bytes, {})",
+                     file.path,
+                     file.size,
+                     short_hash(&file.hash));
+        }
+        for file in &diff.added {
+            println!("+ {} ({} bytes, {})",
+                     file.path,
+                     file.size,
+                     short_hash(&file.hash));
+        }
+        for file in &diff.changed {
+            println!("~ {} ({} -> {} bytes, {} -> {})",
+                     file.path,
+                     file.from_size,
+                     file.to_size,
+                     short_hash(&file.from_hash),
+                     short_hash(&file.to_hash));
+        }
+        ui.br()?;
+    }
+
+    ui.end(format!("{} metadata changes, {} files added, {} removed, {} changed",
+                   diff.metadata.len(),
+                   diff.added.len(),
+                   diff.removed.len(),
+                   diff.changed.len()))?;
+    Ok(())
+}
+
+fn compare(from: &Package, to: &Package) -> PackageDiff {
+    let mut metadata = Vec::new();
+
+    for (metafile, name) in LIST_METAFILES {
+        let items = |package: &Package| {
+            package.metafile(*metafile)
+                   .map(|contents| contents.split_whitespace().map(str::to_string).collect())
+                   .unwrap_or_else(BTreeSet::new)
+        };
+        let (from_items, to_items) = (items(from), items(to));
+        let added = to_items.difference(&from_items)
+                            .cloned()
+                            .collect::<Vec<_>>();
+        let removed = from_items.difference(&to_items)
+                                .cloned()
+                                .collect::<Vec<_>>();
+        if !(added.is_empty() && removed.is_empty()) {
+            metadata.push(MetadataChange::List { name: name.to_string(),
+                                                 added,
+                                                 removed });
+        }
+    }
+
+    for (metafile, name) in VALUE_METAFILES {
+        let from_value = from.metafile(*metafile).map(str::to_string);
+        let to_value = to.metafile(*metafile).map(str::to_string);
+        if from_value != to_value {
+            metadata.push(MetadataChange::Value { name: name.to_string(),
+                                                  from: from_value,
+                                                  to:   to_value, });
+        }
+    }
+
+    let text_files = from.contents
+                         .keys()
+                         .chain(to.contents.keys())
+                         .filter(|path| is_text(path))
+                         .collect::<BTreeSet<_>>();
+    for path in text_files {
+        let from_text = from.contents.get(path).map(String::as_str).unwrap_or("");
+        let to_text = to.contents.get(path).map(String::as_str).unwrap_or("");
+        if from_text != to_text {
+            let diff = text_diff(&from.ident, &to.ident, path, from_text, to_text);
+            metadata.push(MetadataChange::Text { name: path.clone(),
+                                                 diff });
+        }
+    }
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (path, to_file) in &to.files {
+        match from.files.get(path) {
+            None => added.push(to_file.clone()),
+            Some(from_file) if from_file.hash != to_file.hash => {
+                changed.push(FileChange { path:      path.clone(),
+                                          from_size: from_file.size,
+                                          to_size:   to_file.size,
+                                          from_hash: from_file.hash.clone(),
+                                          to_hash:   to_file.hash.clone(), })
+            }
+            Some(_) => {}
+        }
+    }
+    let removed = from.files
+                      .iter()
+                      .filter(|(path, _)| !to.files.contains_key(*path))
+                      .map(|(_, file)| file.clone())
+                      .collect();
+
+    PackageDiff { from: from.ident.to_string(),
+                  to: to.ident.to_string(),
+                  metadata,
+                  added,
+                  removed,
+                  changed }
+}
+
+/// Whether a file's contents are compared as text rather than only by hash.
+fn is_text(path: &str) -> bool {
+    path == MetaFile::Manifest.to_string() || path == DEFAULT_CONFIG || path.starts_with(HOOKS_DIR)
+}
+
+/// Whether a file's contents are needed to compare package metadata.
+fn is_metadata(path: &str) -> bool {
+    is_text(path)
+    || LIST_METAFILES.iter()
+                     .chain(VALUE_METAFILES)
+                     .any(|(metafile, _)| path == metafile.to_string())
+}
+
+fn text_diff(from: &PackageIdent,
+             to: &PackageIdent,
+             path: &str,
+             from_text: &str,
+             to_text: &str)
+             -> String {
+    TextDiff::from_lines(from_text, to_text).unified_diff()
+                                            .header(&format!("{}/{}", from, path),
+                                                    &format!("{}/{}", to, path))
+                                            .to_string()
+}
+
+fn short_hash(hash: &str) -> &str { &hash[..hash.len().min(SHORT_HASH_LEN)] }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(ident: &str, files: &[(&str, &str)]) -> Package {
+        let mut package = Package::new(ident.parse().unwrap());
+        for (path, contents) in files {
+            package.add(Path::new(path),
+                        contents.len() as u64,
+                        &mut contents.as_bytes())
+                   .unwrap();
+        }
+        package
+    }
+
+    #[test]
+    fn identical_packages_have_no_differences() {
+        let files = [("DEPS", "core/glibc/2.29/20190115012345\n"),
+                     ("bin/redis", "binary")];
+        let diff = compare(&package("core/redis/5.0.7/20200110201405", &files),
+                           &package("core/redis/5.0.7/20200110201405", &files));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn metadata_changes_are_reported() {
+        let from = package("core/redis/4.0.14/20190319155852",
+                           &[("DEPS", "core/glibc/2.27/20190115002733\ncore/openssl/1.0.2\n"),
+                             ("EXPOSES", "6379"),
+                             ("SVC_USER", "hab"),
+                             ("hooks/run", "exec redis-server\n")]);
+        let to = package("core/redis/5.0.7/20200110201405",
+                         &[("DEPS", "core/glibc/2.29/20200305172459\ncore/openssl/1.0.2\n"),
+                           ("EXPOSES", "6379"),
+                           ("SVC_USER", "redis"),
+                           ("hooks/run", "exec redis-server --protected-mode no\n")]);
+
+        let diff = compare(&from, &to);
+
+        let added = vec!["core/glibc/2.29/20200305172459".to_string()];
+        let removed = vec!["core/glibc/2.27/20190115002733".to_string()];
+        assert_eq!(diff.metadata[0],
+                   MetadataChange::List { name: "deps".to_string(),
+                                          added,
+                                          removed });
+        assert_eq!(diff.metadata[1],
+                   MetadataChange::Value { name: "svc_user".to_string(),
+                                           from: Some("hab".to_string()),
+                                           to:   Some("redis".to_string()), });
+        match &diff.metadata[2] {
+            MetadataChange::Text { name, diff } => {
+                assert_eq!(name, "hooks/run");
+                assert!(diff.contains("+exec redis-server --protected-mode no"));
+            }
+            change => panic!("Expected a text change, got {:?}", change),
+        }
+        assert_eq!(diff.metadata.len(), 3);
+    }
+
+    #[test]
+    fn file_changes_are_reported() {
+        let from = package("core/redis/4.0.14/20190319155852",
+                           &[("bin/redis-server", "old"), ("bin/redis-trib.rb", "ruby")]);
+        let to = package("core/redis/5.0.7/20200110201405",
+                         &[("bin/redis-server", "new!"), ("bin/redis-cli", "cli")]);
+
+        let diff = compare(&from, &to);
+
+        assert_eq!(diff.added.iter().map(|f| &f.path).collect::<Vec<_>>(),
+                   vec!["bin/redis-cli"]);
+        assert_eq!(diff.removed.iter().map(|f| &f.path).collect::<Vec<_>>(),
+                   vec!["bin/redis-trib.rb"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].path, "bin/redis-server");
+        assert_eq!((diff.changed[0].from_size, diff.changed[0].to_size), (3, 4));
+    }
+}
@@ -64,6 +64,8 @@ use reqwest::StatusCode;
 use crate::error::{Error,
                    Result};
 
+use super::lock::Lockfile;
+
 #[derive(Debug, Deserialize)]
 pub struct PackageSetFile {
     pub format_version:  Option<u8>,
@@ -167,6 +169,40 @@ pub async fn start<U>(ui: &mut U,
     Ok(())
 }
 
+/// Download exactly the artifacts recorded in a lockfile written by `bio pkg lock`, and their
+/// signing keys, failing if any artifact differs from the checksum recorded for it.
+#[allow(clippy::too_many_arguments)]
+pub async fn start_locked<U>(ui: &mut U,
+                             url: &str,
+                             product: &str,
+                             version: &str,
+                             lockfile: &Lockfile,
+                             download_path: Option<&PathBuf>,
+                             token: Option<&str>,
+                             verify: bool)
+                             -> Result<()>
+    where U: UIWriter
+{
+    let download_path_default = &cache_root_path::<PathBuf>(None); // Satisfy E0716
+    let download_path_expanded = download_path.unwrap_or(download_path_default).as_ref();
+
+    let api_client = Client::new(url, product, version, None)?;
+
+    let task = DownloadTask { package_sets: &[],
+                              url,
+                              api_client,
+                              token,
+                              download_path: download_path_expanded,
+                              verify,
+                              ignore_missing_seeds: false };
+
+    let download_count = task.execute_locked(ui, lockfile).await?;
+
+    debug!("Locked package count: {}", download_count);
+
+    Ok(())
+}
+
 struct DownloadTask<'a> {
     package_sets:         &'a [PackageSet],
     url:                  &'a str,
@@ -196,6 +232,32 @@ impl<'a> DownloadTask<'a> {
         Ok(downloaded_artifacts.len())
     }
 
+    async fn execute_locked<T>(&self, ui: &mut T, lockfile: &Lockfile) -> Result<usize>
+        where T: UIWriter
+    {
+        ui.begin(format!("Storing in download directory {:?} ", self.download_path))?;
+
+        self.verify_and_prepare_download_directory(ui)?;
+
+        // The lockfile already holds every fully qualified dependency, so there is nothing to
+        // expand
+        let locked_idents = lockfile.packages
+                                    .iter()
+                                    .map(|p| (p.ident.clone(), p.target))
+                                    .collect();
+        ui.status(Status::Found,
+                  format!("{} locked artifacts", lockfile.packages.len()))?;
+
+        let downloaded_artifacts = self.download_artifacts(ui, &locked_idents).await?;
+
+        for package in &lockfile.packages {
+            package.verify_checksum(&self.downloaded_artifact_path(&package.ident,
+                                                                   package.target))?;
+        }
+
+        Ok(downloaded_artifacts.len())
+    }
+
     // For each source, use the builder/depot to expand it to a fully qualifed form
     // The same call gives us the TDEPS, add those as well.
     async fn expand_sources<T>(&self, ui: &mut T) -> Result<HashSet<(PackageIdent, PackageTarget)>>
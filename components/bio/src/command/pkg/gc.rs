@@ -0,0 +1,498 @@
+//! Garbage-collects installed packages and cached artifacts which are no longer needed.
+//!
+//! # Examples
+//!
+//! ```bash
+//! $ bio pkg gc --dryrun
+//! $ bio pkg gc --keep core/redis --cache-max-age 30 --cache-max-size 2048
+//! ```
+//!
+//! # Internals
+//!
+//! * Build a `PackageGraph` of every installed package
+//! * Gather the roots: services loaded by a running Supervisor, the idents of the spec files in the
+//!   Supervisor's state directory, packages which a binlink points into, packages the user asked to
+//!   keep, the latest Biome packages and those the running Launcher, Supervisor and `bio` were
+//!   started from
+//! * Uninstall every installed package which is not reachable from a root, dependants first, as
+//!   `bio pkg uninstall` does: running uninstall hooks and never removing `biome/bio` or a package
+//!   a loaded service needs
+//! * Remove cached artifacts older than the maximum age, then the oldest of the rest until the
+//!   cache is no larger than the maximum size
+
+use super::{uninstall::{self,
+                        Deletion,
+                        UninstallHookMode,
+                        UninstallSafetyImpl},
+            ExecutionStrategy};
+use crate::{common::{package_graph::PackageGraph,
+                     ui::{Status,
+                          UIWriter},
+                     util::{dir_size,
+                            human_size}},
+            error::Result,
+            hcore::{fs as hfs,
+                    os::process::{self,
+                                  Pid},
+                    package::{list,
+                              Identifiable,
+                              PackageIdent,
+                              PackageInstall},
+                    util}};
+use std::{collections::HashSet,
+          env,
+          fs,
+          path::{Path,
+                 PathBuf},
+          str::FromStr,
+          time::{Duration,
+                 SystemTime}};
+
+/// The latest installed release of these is always kept, as they are Biome itself.
+const BIOME_PACKAGES: &[&str] = &["biome/bio", "biome/sup", "biome/launcher"];
+
+/// Every installed release of these is kept while a Launcher is running, if the releases it and
+/// the Supervisor were started from can't be found.
+const RUNNING_PACKAGES: &[&str] = &["biome/sup", "biome/launcher"];
+
+/// Where running processes can be looked up by process id.
+const PROC_PATH: &str = "/proc";
+
+/// Where packages are kept installed from, other than running services.
+pub struct Roots<'a> {
+    /// Identifiers of packages to keep, along with their dependencies.
+    pub keep:        &'a [PackageIdent],
+    /// The directory whose binlinks keep the packages they point into installed.
+    pub binlink_dir: &'a Path,
+    /// The Supervisor state directory, whose spec files keep their services' packages installed.
+    pub sup_root:    &'a Path,
+}
+
+/// Limits on what is kept in the artifact cache.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheLimits {
+    pub max_age:  Option<Duration>,
+    pub max_size: Option<u64>,
+}
+
+pub async fn start<U>(ui: &mut U,
+                      fs_root_path: &Path,
+                      roots: &Roots<'_>,
+                      cache_limits: CacheLimits,
+                      execution_strategy: ExecutionStrategy,
+                      uninstall_hook_mode: UninstallHookMode)
+                      -> Result<()>
+    where U: UIWriter
+{
+    ui.begin("Collecting unused packages")?;
+    let mut graph = PackageGraph::from_root_path(fs_root_path)?;
+    let installed = list::all_packages(&hfs::pkg_root_path(Some(fs_root_path)))?;
+
+    ui.status(Status::Determining, "list of loaded services in supervisor")?;
+    let loaded_services = uninstall::supervisor_services().await?;
+    for service in &loaded_services {
+        ui.status(Status::Found, format!("loaded service {}", service.ident))?;
+    }
+    let root_idents = root_idents(fs_root_path,
+                                  roots,
+                                  &installed,
+                                  loaded_services.iter().map(|s| s.ident.clone()))?;
+    let mut reachable = HashSet::new();
+    for ident in &root_idents {
+        reachable.extend(graph.owned_ordered_deps(ident));
+        reachable.insert(ident.clone());
+    }
+    let mut unreachable: Vec<_> = installed.into_iter()
+                                           .filter(|ident| !reachable.contains(ident))
+                                           .collect();
+    unreachable.sort_unstable_by(|a, b| a.by_parts_cmp(b));
+    ui.status(Status::Found,
+              format!("{} packages to keep and {} to uninstall",
+                      reachable.len(),
+                      unreachable.len()))?;
+
+    // Anything depending on an unreachable package is itself unreachable, so repeatedly taking
+    // those without any dependants uninstalls all of them, dependants first. A package which must
+    // be kept after all stays in the graph, keeping its dependencies installed too.
+    let safety = UninstallSafetyImpl::SkipIfLoaded(&loaded_services);
+    let mut uninstalled = 0;
+    let mut reclaimed = 0;
+    while let Some(i) = unreachable.iter()
+                                   .position(|ident| graph.count_rdeps(ident) == Some(0))
+    {
+        let ident = unreachable.remove(i);
+        let install = PackageInstall::load(&ident, Some(fs_root_path))?;
+        // The size is only reported, so failing to measure it does not stop the collection
+        let size = dir_size(install.installed_path()).unwrap_or_default();
+        if uninstall::maybe_delete(ui,
+                                   fs_root_path,
+                                   &install,
+                                   execution_strategy,
+                                   &[],
+                                   uninstall_hook_mode,
+                                   safety).await?
+           == Deletion::Skipped
+        {
+            continue;
+        }
+        graph.remove(&ident);
+        uninstalled += 1;
+        reclaimed += size;
+    }
+
+    let (pruned, pruned_size) =
+        prune_artifact_cache(ui,
+                             &hfs::cache_artifact_path(Some(fs_root_path)),
+                             cache_limits,
+                             execution_strategy)?;
+    reclaimed += pruned_size;
+
+    match execution_strategy {
+        ExecutionStrategy::DryRun => {
+            ui.end(format!("Would uninstall {} packages and remove {} cached artifacts, \
+                            reclaiming {} (Dry run)",
+                           uninstalled,
+                           pruned,
+                           human_size(reclaimed)))?;
+        }
+        ExecutionStrategy::Run => {
+            ui.end(format!("Uninstalled {} packages and removed {} cached artifacts, \
+                            reclaiming {}",
+                           uninstalled,
+                           pruned,
+                           human_size(reclaimed)))?;
+        }
+    }
+    Ok(())
+}
+
+/// The fully qualified idents of the installed packages which are kept, along with their
+/// dependencies.
+fn root_idents(fs_root_path: &Path,
+               roots: &Roots<'_>,
+               installed: &[PackageIdent],
+               loaded: impl Iterator<Item = PackageIdent>)
+               -> Result<Vec<PackageIdent>> {
+    let mut idents: Vec<PackageIdent> = loaded.collect();
+    for ident in spec_idents(&roots.sup_root.join("specs")) {
+        idents.extend(latest_satisfying(installed, &ident));
+    }
+    let pkg_root_path = hfs::pkg_root_path(Some(fs_root_path));
+    let binlink_dir = fs_root_path.join(roots.binlink_dir
+                                             .strip_prefix("/")
+                                             .unwrap_or(roots.binlink_dir));
+    idents.extend(binlinked_idents(&binlink_dir, &pkg_root_path));
+    for keep in roots.keep {
+        idents.extend(installed.iter()
+                               .filter(|ident| ident.satisfies(keep))
+                               .cloned());
+    }
+    for name in BIOME_PACKAGES {
+        idents.extend(latest_satisfying(installed, &PackageIdent::from_str(name)?));
+    }
+    idents.extend(running_idents(fs_root_path, installed)?);
+    if let Ok(exe) = env::current_exe() {
+        idents.extend(ident_from_install_path(&exe, &pkg_root_path));
+    }
+
+    idents.retain(|ident| installed.contains(ident));
+    idents.sort_unstable_by(|a, b| a.by_parts_cmp(b));
+    idents.dedup();
+    Ok(idents)
+}
+
+fn latest_satisfying(installed: &[PackageIdent], ident: &PackageIdent) -> Option<PackageIdent> {
+    installed.iter()
+             .filter(|installed| installed.satisfies(ident))
+             .max_by(|a, b| a.by_parts_cmp(b))
+             .cloned()
+}
+
+/// The packages the running Launcher, and the Supervisor and services it started, were started
+/// from. Where they can't be looked up, every installed release of the Supervisor and Launcher is
+/// kept while a Launcher is running instead.
+fn running_idents(fs_root_path: &Path, installed: &[PackageIdent]) -> Result<Vec<PackageIdent>> {
+    let pid_file = hfs::launcher_root_path(Some(fs_root_path)).join("PID");
+    let launcher_pid = match fs::read_to_string(&pid_file).ok()
+                                                          .and_then(|pid| pid.trim().parse().ok())
+    {
+        Some(pid) if process::is_alive(pid) => pid,
+        _ => return Ok(Vec::new()),
+    };
+    let pkg_root_path = hfs::pkg_root_path(Some(fs_root_path));
+    if let Some(idents) = launched_idents(Path::new(PROC_PATH), launcher_pid, &pkg_root_path) {
+        return Ok(idents);
+    }
+
+    let mut idents = Vec::new();
+    for name in RUNNING_PACKAGES {
+        let ident = PackageIdent::from_str(name)?;
+        idents.extend(installed.iter()
+                               .filter(|installed| installed.satisfies(&ident))
+                               .cloned());
+    }
+    Ok(idents)
+}
+
+/// The packages whose executables the process `launcher_pid` and its children are running, as
+/// found in `proc_path`, or `None` if the launcher's executable can't be found.
+fn launched_idents(proc_path: &Path,
+                   launcher_pid: Pid,
+                   pkg_root_path: &Path)
+                   -> Option<Vec<PackageIdent>> {
+    let launcher_exe = fs::read_link(proc_path.join(launcher_pid.to_string()).join("exe")).ok()?;
+    let mut idents: Vec<_> = ident_from_install_path(&launcher_exe, pkg_root_path).into_iter()
+                                                                                  .collect();
+    if let Ok(entries) = fs::read_dir(proc_path) {
+        idents.extend(entries.filter_map(|entry| entry.ok().map(|e| e.path()))
+                             .filter(|path| parent_pid(path) == Some(launcher_pid))
+                             .filter_map(|path| fs::read_link(path.join("exe")).ok())
+                             .filter_map(|exe| ident_from_install_path(&exe, pkg_root_path)));
+    }
+    Some(idents)
+}
+
+/// The parent of the process whose entry in the proc filesystem is `path`.
+fn parent_pid(path: &Path) -> Option<Pid> {
+    let stat = fs::read_to_string(path.join("stat")).ok()?;
+    // The command name before it is in parentheses and may hold spaces
+    stat.rsplit(')')
+        .next()?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+#[derive(Deserialize)]
+struct SpecIdent {
+    #[serde(with = "util::serde::string")]
+    ident: PackageIdent,
+}
+
+/// The idents of the services whose spec files are in `specs_path`, whether or not a Supervisor
+/// is running them right now.
+fn spec_idents(specs_path: &Path) -> Vec<PackageIdent> {
+    let entries = match fs::read_dir(specs_path) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries.filter_map(|entry| entry.ok().map(|e| e.path()))
+           .filter(|path| path.extension().map_or(false, |ext| ext == "spec"))
+           .filter_map(|path| {
+               match read_spec_ident(&path) {
+                   Ok(ident) => Some(ident),
+                   Err(e) => {
+                       warn!("Ignoring spec file {}: {}", path.display(), e);
+                       None
+                   }
+               }
+           })
+           .collect()
+}
+
+fn read_spec_ident(path: &Path) -> Result<PackageIdent> {
+    let spec: SpecIdent = toml::from_str(&fs::read_to_string(path)?)?;
+    Ok(spec.ident)
+}
+
+/// The idents of the packages which binlinks in `binlink_dir` point into.
+fn binlinked_idents(binlink_dir: &Path, pkg_root_path: &Path) -> Vec<PackageIdent> {
+    let entries = match fs::read_dir(binlink_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries.filter_map(|entry| fs::read_link(entry.ok()?.path()).ok())
+           .filter_map(|target| ident_from_install_path(&target, pkg_root_path))
+           .collect()
+}
+
+/// The ident of the package a path inside `pkg_root_path` belongs to, as in
+/// `/hab/pkgs/core/redis/4.0.14/20190319155852/bin/redis-server`.
+fn ident_from_install_path(path: &Path, pkg_root_path: &Path) -> Option<PackageIdent> {
+    let parts: Vec<_> = path.strip_prefix(pkg_root_path)
+                            .ok()?
+                            .iter()
+                            .take(4)
+                            .map(|part| part.to_string_lossy())
+                            .collect();
+    if parts.len() == 4 {
+        Some(PackageIdent::new(parts[0].as_ref(),
+                               parts[1].as_ref(),
+                               Some(parts[2].as_ref()),
+                               Some(parts[3].as_ref())))
+    } else {
+        None
+    }
+}
+
+/// Removes cached artifacts which are older than the maximum age, then the oldest of those left
+/// until the cache is no larger than the maximum size. Returns the number removed and their size.
+fn prune_artifact_cache<U>(ui: &mut U,
+                           cache_path: &Path,
+                           limits: CacheLimits,
+                           execution_strategy: ExecutionStrategy)
+                           -> Result<(usize, u64)>
+    where U: UIWriter
+{
+    if limits.max_age.is_none() && limits.max_size.is_none() {
+        return Ok((0, 0));
+    }
+    let entries = match fs::read_dir(cache_path) {
+        Ok(entries) => entries,
+        Err(_) => return Ok((0, 0)),
+    };
+
+    // Oldest first
+    let mut artifacts = Vec::<(PathBuf, SystemTime, u64)>::new();
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            artifacts.push((entry.path(), metadata.modified()?, metadata.len()));
+        }
+    }
+    artifacts.sort_by_key(|(_, modified, _)| *modified);
+
+    let now = SystemTime::now();
+    let mut cache_size: u64 = artifacts.iter().map(|(_, _, size)| size).sum();
+    let mut pruned = 0;
+    let mut pruned_size = 0;
+    for (path, modified, size) in artifacts {
+        let too_old = limits.max_age.map_or(false, |max_age| {
+                                        now.duration_since(modified).unwrap_or_default() > max_age
+                                    });
+        let too_big = limits.max_size
+                            .map_or(false, |max_size| cache_size > max_size);
+        if !too_old && !too_big {
+            continue;
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        match execution_strategy {
+            ExecutionStrategy::DryRun => ui.status(Status::DryRunDeleting, &file_name)?,
+            ExecutionStrategy::Run => {
+                ui.status(Status::Deleting, &file_name)?;
+                fs::remove_file(&path)?;
+            }
+        }
+        cache_size -= size;
+        pruned += 1;
+        pruned_size += size;
+    }
+    Ok((pruned, pruned_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hcore::package::PackageTarget;
+    use tempfile::TempDir;
+
+    fn ident(s: &str) -> PackageIdent { PackageIdent::from_str(s).unwrap() }
+
+    fn fake_pkg_install(fs_root_path: &Path, ident: &PackageIdent) {
+        let prefix = hfs::pkg_install_path(ident, Some(fs_root_path));
+        fs::create_dir_all(&prefix).unwrap();
+        fs::write(prefix.join("IDENT"), ident.to_string()).unwrap();
+        fs::write(prefix.join("TARGET"),
+                  PackageTarget::active_target().to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn older_bio_releases_are_kept() {
+        let fs_root = TempDir::new().unwrap();
+        let old_bio = ident("biome/bio/1.0.0/20200101000000");
+        let bio = ident("biome/bio/2.0.0/20200101000000");
+        let unused = ident("core/unused/1.0.0/20200101000000");
+        for ident in &[&old_bio, &bio, &unused] {
+            fake_pkg_install(fs_root.path(), ident);
+        }
+
+        let sup_root = fs_root.path().join("sup");
+        let roots = Roots { keep:        &[],
+                            binlink_dir: Path::new("/bin"),
+                            sup_root:    &sup_root, };
+        let mut ui = crate::common::ui::UI::with_sinks();
+        start(&mut ui,
+              fs_root.path(),
+              &roots,
+              CacheLimits::default(),
+              ExecutionStrategy::Run,
+              UninstallHookMode::Ignore).await
+                                        .unwrap();
+
+        let installed = list::all_packages(&hfs::pkg_root_path(Some(fs_root.path()))).unwrap();
+        assert!(installed.contains(&old_bio));
+        assert!(installed.contains(&bio));
+        assert!(!installed.contains(&unused));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn launched_idents_finds_the_launcher_and_its_children() {
+        let dir = TempDir::new().unwrap();
+        let pkg_root = Path::new("/hab/pkgs");
+        let proc_path = dir.path();
+        for (pid, stat, exe) in
+            &[("100",
+               "100 (bio-launch) S 1 100",
+               "biome/launcher/1.0.0/20200101000000/bin/bio-launch"),
+              ("200", "200 (bio sup) S 100 100", "biome/sup/1.0.0/20200101000000/bin/bio-sup"),
+              ("300", "300 (other) S 1 300", "core/other/1.0.0/20200101000000/bin/other")]
+        {
+            let path = proc_path.join(pid);
+            fs::create_dir(&path).unwrap();
+            fs::write(path.join("stat"), stat).unwrap();
+            std::os::unix::fs::symlink(pkg_root.join(exe), path.join("exe")).unwrap();
+        }
+
+        let mut idents = launched_idents(proc_path, 100, pkg_root).unwrap();
+        idents.sort_unstable_by(|a, b| a.by_parts_cmp(b));
+        assert_eq!(idents,
+                   vec![ident("biome/launcher/1.0.0/20200101000000"),
+                        ident("biome/sup/1.0.0/20200101000000")]);
+        assert!(launched_idents(proc_path, 400, pkg_root).is_none());
+    }
+
+    #[test]
+    fn ident_from_install_path_finds_the_package() {
+        let pkg_root = Path::new("/hab/pkgs");
+        let ident = ident_from_install_path(Path::new("/hab/pkgs/core/redis/4.0.14/\
+                                                       20190319155852/bin/redis-server"),
+                                            pkg_root).unwrap();
+        assert_eq!(ident.to_string(), "core/redis/4.0.14/20190319155852");
+        assert!(ident_from_install_path(Path::new("/usr/bin/redis-server"), pkg_root).is_none());
+        assert!(ident_from_install_path(Path::new("/hab/pkgs/core/redis"), pkg_root).is_none());
+    }
+
+    #[test]
+    fn spec_idents_reads_spec_files() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("redis.spec"),
+                  "ident = \"core/redis\"\ngroup = \"default\"\n").unwrap();
+        fs::write(dir.path().join("broken.spec"), "ident = 42\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "ident = \"core/nginx\"\n").unwrap();
+
+        let idents = spec_idents(dir.path());
+        assert_eq!(idents, vec![PackageIdent::from_str("core/redis").unwrap()]);
+    }
+
+    #[test]
+    fn prune_artifact_cache_removes_the_oldest_first() {
+        let dir = TempDir::new().unwrap();
+        for (name, size) in &[("a.hart", 10), ("b.hart", 20), ("c.hart", 30)] {
+            fs::write(dir.path().join(name), vec![0; *size]).unwrap();
+            // Make sure the modification times differ
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let mut ui = crate::common::ui::UI::with_sinks();
+        let limits = CacheLimits { max_age:  None,
+                                   max_size: Some(50), };
+        let (pruned, pruned_size) =
+            prune_artifact_cache(&mut ui, dir.path(), limits, ExecutionStrategy::Run).unwrap();
+        assert_eq!((pruned, pruned_size), (1, 10));
+        assert!(!dir.path().join("a.hart").exists());
+        assert!(dir.path().join("b.hart").exists());
+    }
+}
@@ -10,6 +10,7 @@ pub fn start(ui: &mut UI, src: &Path) -> Result<()> {
     if let Ok(header) = artifact::get_artifact_header(src) {
         println!("Package        : {}", &src.display());
         println!("Format Version : {}", header.format());
+        println!("Compression    : {}", header.compression());
         println!("Key Name       : {}", header.signer());
         println!("Hash Type      : {}", header.hash_type());
         // This should just be "Signature", but this is a public
@@ -0,0 +1,295 @@
+//! Resolves Biome packages and their dependencies into a lockfile.
+//!
+//! # Examples
+//!
+//! ```bash
+//! $ bio pkg lock --file idents.toml
+//! $ bio pkg download --lockfile bio.lock
+//! $ bio pkg install --lockfile bio.lock
+//! ```
+//!
+//! This would resolve every ident in `idents.toml` once, and then download or install exactly the
+//! artifacts it resolved to, no matter what has been promoted since.
+//!
+//! # Internals
+//!
+//! * Resolve each ident to the latest release in its channel, as `bio pkg download` does
+//! * Record the fully qualified ident, target and blake2b checksum of that release and of each of
+//!   its transitive dependencies
+//! * When installing or downloading from the lockfile, fetch the recorded artifacts and refuse any
+//!   whose checksum differs from the one recorded
+
+use crate::{api_client::{BuilderAPIClient,
+                         Client},
+            common::{error::Error as CommonError,
+                     ui::{Status,
+                          UIWriter}},
+            error::{Error,
+                    Result},
+            hcore::{crypto::hash,
+                    fs::AtomicWriter,
+                    package::{PackageIdent,
+                              PackageTarget},
+                    util,
+                    ChannelIdent}};
+use std::{collections::HashMap,
+          fs,
+          io::Write,
+          path::Path};
+
+use super::download::PackageSet;
+
+/// The only lockfile format understood so far.
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Lockfile {
+    pub format_version: u8,
+    #[serde(default, rename = "package")]
+    pub packages:       Vec<LockedPackage>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockedPackage {
+    #[serde(with = "util::serde::string")]
+    pub ident:         PackageIdent,
+    pub target:        PackageTarget,
+    /// The blake2b checksum of the artifact.
+    pub checksum:      String,
+    /// The ident this package was locked for, if it was requested rather than pulled in as a
+    /// dependency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_from: Option<String>,
+}
+
+impl Lockfile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let lockfile: Lockfile = toml::from_str(&fs::read_to_string(path)?)?;
+        if lockfile.format_version != FORMAT_VERSION {
+            let msg = format!("format_version invalid, only version {} allowed ({} provided)",
+                              FORMAT_VERSION, lockfile.format_version);
+            return Err(Error::LockfileParseError(msg));
+        }
+        if let Some(package) = lockfile.packages
+                                       .iter()
+                                       .find(|p| !p.ident.fully_qualified())
+        {
+            let msg = format!("{} is not fully qualified", package.ident);
+            return Err(Error::LockfileParseError(msg));
+        }
+        Ok(lockfile)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string(self)?;
+        AtomicWriter::new(path)?.with_writer(|f| f.write_all(content.as_bytes()))?;
+        Ok(())
+    }
+
+    /// The locked packages for `target`.
+    pub fn packages_for(&self, target: PackageTarget) -> impl Iterator<Item = &LockedPackage> {
+        self.packages.iter().filter(move |p| p.target == target)
+    }
+}
+
+impl LockedPackage {
+    /// Checks the artifact at `path` is the one which was locked.
+    pub fn verify_checksum(&self, path: &Path) -> Result<()> {
+        let checksum = hash::hash_file(path)?.to_string();
+        if checksum == self.checksum {
+            Ok(())
+        } else {
+            Err(Error::ArtifactChecksumMismatch(self.ident.clone(),
+                                                self.checksum.clone(),
+                                                checksum))
+        }
+    }
+}
+
+/// Resolve every ident of the package sets, and their transitive dependencies, into a lockfile
+/// at `lockfile_path`.
+pub async fn start<U>(ui: &mut U,
+                      url: &str,
+                      product: &str,
+                      version: &str,
+                      package_sets: &[PackageSet],
+                      lockfile_path: &Path,
+                      token: Option<&str>)
+                      -> Result<()>
+    where U: UIWriter
+{
+    if package_sets.is_empty() {
+        ui.fatal("No package identifers provided. Specify identifiers on the command line, or \
+                  via a input file")?;
+        return Err(CommonError::MissingCLIInputError(String::from("No package identifiers \
+                                                                   found")).into());
+    }
+
+    // We deliberately use None to specify the default path as this is used for cert paths, which
+    // we don't want to override.
+    let api_client = Client::new(url, product, version, None)?;
+    let mut packages = Vec::<LockedPackage>::new();
+    let mut locked = HashMap::<(PackageIdent, PackageTarget), usize>::new();
+
+    for package_set in package_sets {
+        ui.begin(format!("Resolving {} package idents for {} from the '{}' channel",
+                         package_set.idents.len(),
+                         package_set.target,
+                         package_set.channel))?;
+        for ident in &package_set.idents {
+            ui.status(Status::Determining, format!("latest version of {}", ident))?;
+            let package = api_client.show_package_metadata((ident, package_set.target),
+                                                           &package_set.channel,
+                                                           token)
+                                    .await?;
+            ui.status(Status::Using, &package.ident)?;
+            let key = (package.ident.clone(), package_set.target);
+            match locked.get(&key) {
+                Some(&i) => packages[i].resolved_from = Some(ident.to_string()),
+                None => {
+                    locked.insert(key, packages.len());
+                    packages.push(LockedPackage { ident:         package.ident,
+                                                  target:        package_set.target,
+                                                  checksum:      package.checksum,
+                                                  resolved_from: Some(ident.to_string()), });
+                }
+            }
+
+            for dep in package.tdeps {
+                let key = (dep, package_set.target);
+                if locked.contains_key(&key) {
+                    continue;
+                }
+                let checksum = checksum_of(&api_client, &key.0, key.1, token).await?;
+                locked.insert(key.clone(), packages.len());
+                packages.push(LockedPackage { ident: key.0,
+                                              target: key.1,
+                                              checksum,
+                                              resolved_from: None });
+            }
+        }
+    }
+
+    packages.sort_by_key(|p| (p.target.to_string(), p.ident.to_string()));
+    ui.status(Status::Found, format!("{} artifacts", packages.len()))?;
+    Lockfile { format_version: FORMAT_VERSION,
+               packages }.write(lockfile_path)?;
+    ui.end(format!("Wrote lockfile {}", lockfile_path.display()))?;
+    Ok(())
+}
+
+/// Ensure the locked artifacts for `target` are in `artifact_path`, downloading any which are
+/// missing and verifying all of them against the lockfile. Returns the idents which were
+/// requested when locking, for installation.
+#[allow(clippy::too_many_arguments)]
+pub async fn cache_locked_artifacts<U>(ui: &mut U,
+                                       url: &str,
+                                       product: &str,
+                                       version: &str,
+                                       lockfile: &Lockfile,
+                                       target: PackageTarget,
+                                       artifact_path: &Path,
+                                       token: Option<&str>)
+                                       -> Result<Vec<PackageIdent>>
+    where U: UIWriter
+{
+    let api_client = Client::new(url, product, version, None)?;
+    let mut requested = Vec::new();
+    for package in lockfile.packages_for(target) {
+        let path = artifact_path.join(package.ident.archive_name_with_target(target)?);
+        if !path.is_file() {
+            ui.status(Status::Downloading, &package.ident)?;
+            api_client.fetch_package((&package.ident, target),
+                                     token,
+                                     artifact_path,
+                                     ui.progress())
+                      .await?;
+        }
+        package.verify_checksum(&path)?;
+        ui.status(Status::Verified, &package.ident)?;
+        if package.resolved_from.is_some() {
+            requested.push(package.ident.clone());
+        }
+    }
+    if requested.is_empty() {
+        ui.warn(format!("The lockfile has no packages for {}", target))?;
+    }
+    Ok(requested)
+}
+
+async fn checksum_of(api_client: &BuilderAPIClient,
+                     ident: &PackageIdent,
+                     target: PackageTarget,
+                     token: Option<&str>)
+                     -> Result<String> {
+    // All packages are in the unstable channel, and a fully qualified ident resolves to the same
+    // release in any channel it is in.
+    let package =
+        api_client.show_package_metadata((ident, target), &ChannelIdent::unstable(), token)
+                  .await?;
+    Ok(package.checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn locked(ident: &str, checksum: &str) -> LockedPackage {
+        LockedPackage { ident:         PackageIdent::from_str(ident).unwrap(),
+                        target:        PackageTarget::from_str("x86_64-linux").unwrap(),
+                        checksum:      checksum.to_string(),
+                        resolved_from: None, }
+    }
+
+    #[test]
+    fn lockfile_round_trips() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bio.lock");
+        let mut redis = locked("core/redis/4.0.14/20190319155852", "abc");
+        redis.resolved_from = Some(String::from("core/redis"));
+        let lockfile = Lockfile { format_version: FORMAT_VERSION,
+                                  packages:       vec![locked("core/glibc/2.29/20190115002733",
+                                                              "def"),
+                                                       redis], };
+        lockfile.write(&path).unwrap();
+
+        let read = Lockfile::read(&path).unwrap();
+        assert_eq!(read.packages.len(), 2);
+        assert_eq!(read.packages[0].resolved_from, None);
+        assert_eq!(read.packages[1].ident.to_string(),
+                   "core/redis/4.0.14/20190319155852");
+        assert_eq!(read.packages[1].resolved_from.as_deref(),
+                   Some("core/redis"));
+    }
+
+    #[test]
+    fn lockfile_rejects_partial_idents() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bio.lock");
+        fs::write(&path,
+                  "format_version = 1\n\n[[package]]\nident = \"core/redis\"\ntarget = \
+                   \"x86_64-linux\"\nchecksum = \"abc\"\n").unwrap();
+
+        assert!(Lockfile::read(&path).is_err());
+    }
+
+    #[test]
+    fn verify_checksum_detects_a_different_artifact() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("artifact.hart");
+        fs::write(&path, "some artifact").unwrap();
+        let checksum = hash::hash_file(&path).unwrap().to_string();
+
+        assert!(locked("core/redis/4.0.14/20190319155852", &checksum).verify_checksum(&path)
+                                                                     .is_ok());
+        match locked("core/redis/4.0.14/20190319155852", "abc").verify_checksum(&path) {
+            Err(Error::ArtifactChecksumMismatch(_, expected, actual)) => {
+                assert_eq!(expected, "abc");
+                assert_eq!(actual, checksum);
+            }
+            other => panic!("Expected a checksum mismatch, got {:?}", other),
+        }
+    }
+}
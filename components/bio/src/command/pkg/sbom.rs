@@ -0,0 +1,46 @@
+use crate::{common::{sbom::{Sbom,
+                            SbomFormat},
+                     ui::{Status,
+                          UIWriter,
+                          UI}},
+            error::Result,
+            hcore::package::{PackageArchive,
+                             PackageIdent,
+                             PackageInstall},
+            PRODUCT,
+            VERSION};
+use std::{fs,
+          path::Path,
+          str::FromStr};
+
+/// Generate an SBOM for an installed package or a Biome Artifact, writing it to `output` or to
+/// standard output.
+pub fn start(ui: &mut UI,
+             ident_or_archive: &str,
+             format: SbomFormat,
+             output: Option<&Path>,
+             fs_root_path: &Path)
+             -> Result<()> {
+    let path = Path::new(ident_or_archive);
+    let sbom = if path.is_file() {
+        Sbom::from_archive(&mut PackageArchive::new(path)?, fs_root_path)?
+    } else {
+        let ident = PackageIdent::from_str(ident_or_archive)?;
+        Sbom::from_install(&PackageInstall::load(&ident, Some(fs_root_path))?,
+                           fs_root_path)?
+    };
+    let document = sbom.render(format, PRODUCT, VERSION);
+
+    match output {
+        Some(output) => {
+            fs::write(output, document)?;
+            ui.status(Status::Generated,
+                      format!("{} SBOM for {} at {}",
+                              format,
+                              sbom.package.ident,
+                              output.display()))?;
+        }
+        None => println!("{}", document),
+    }
+    Ok(())
+}
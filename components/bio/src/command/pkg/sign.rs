@@ -18,3 +18,14 @@ pub fn start(ui: &mut UI, key: &SecretOriginSigningKey, src: &Path, dst: &Path)
     ui.end(format!("Signed artifact {}.", dst.display()))?;
     Ok(())
 }
+
+pub fn start_detached(ui: &mut UI, key: &SecretOriginSigningKey, src: &Path) -> Result<()> {
+    ui.begin(format!("Adding a detached signature to {}", src.display()))?;
+    ui.status(Status::Signing,
+              format!("{} with {}", src.display(), key.named_revision()))?;
+    let sig_path = artifact::sign_detached(src, key)?;
+    ui.end(format!("Signed artifact {} in {}.",
+                   src.display(),
+                   sig_path.display()))?;
+    Ok(())
+}
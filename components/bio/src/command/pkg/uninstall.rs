@@ -8,8 +8,13 @@ use biome_common::ui::UI;
 use biome_core::package::PackageIdent;
 use std::path::Path;
 
+pub(crate) use uninstall_impl::{maybe_delete,
+                                supervisor_services,
+                                Deletion,
+                                UninstallSafetyImpl};
 pub use uninstall_impl::{uninstall,
                          uninstall_all_but_latest,
+                         DependenciesMode,
                          UninstallHookMode,
                          UninstallSafety};
 
@@ -36,7 +41,9 @@ pub async fn start(ui: &mut UI,
                    mode: UninstallMode,
                    scope: Scope,
                    excludes: &[PackageIdent],
-                   uninstall_hook_mode: UninstallHookMode)
+                   uninstall_hook_mode: UninstallHookMode,
+                   safety: UninstallSafety,
+                   dependencies_mode: DependenciesMode)
                    -> Result<()> {
     match mode {
         UninstallMode::Single => {
@@ -47,7 +54,9 @@ pub async fn start(ui: &mut UI,
                       scope,
                       excludes,
                       uninstall_hook_mode,
-                      UninstallSafety::Safe).await
+                      safety,
+                      dependencies_mode).await?;
+            Ok(())
         }
         UninstallMode::KeepLatest(number_latest_to_keep) => {
             uninstall_all_but_latest(ui,
@@ -58,7 +67,7 @@ pub async fn start(ui: &mut UI,
                                      scope,
                                      excludes,
                                      uninstall_hook_mode,
-                                     UninstallSafety::Safe).await?;
+                                     safety).await?;
             Ok(())
         }
     }
@@ -1,6 +1,7 @@
 use super::{ExecutionStrategy,
             Scope};
-use crate::{command::pkg::list,
+use crate::{cli::gateway_util,
+            command::pkg::list,
             config,
             error::{Error,
                     Result}};
@@ -20,9 +21,19 @@ use biome_core::{error as herror,
                              PackageInstall}};
 use biome_sup_client::{SrvClient,
                          SrvClientError};
+use biome_sup_protocol::types::ProcessState;
 use std::{fs,
+          future::Future,
+          iter,
           path::Path,
-          str::FromStr};
+          str::FromStr,
+          time::{Duration,
+                 Instant}};
+
+/// How long to wait for the supervisor to bring down a service it was asked to stop
+const SERVICE_STOP_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often to ask the supervisor whether a stopped service is down yet
+const SERVICE_STOP_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Governs how uninstall hooks behave when uninstalling packages
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -37,15 +48,37 @@ impl Default for UninstallHookMode {
     fn default() -> Self { UninstallHookMode::Run }
 }
 
-/// `Force` indictates that the package should be uninstalled even if it is loaded by the
-/// supervisor. This only applies to packages specified in `idents`. It does not apply to their
-/// dependencies.
+/// Governs how packages specified in `idents` are treated when the supervisor has loaded them. It
+/// does not apply to their dependencies, which are never uninstalled while loaded.
 #[derive(Clone, Copy)]
 pub enum UninstallSafety {
+    /// Skip packages loaded by the supervisor
     Safe,
+    /// Refuse to uninstall a package if doing so would break a service loaded by the supervisor
+    Strict,
+    /// Stop the services loaded by the supervisor that uninstalling a package would break, then
+    /// uninstall it
+    StopServices,
+    /// Uninstall packages even if they are loaded by the supervisor
     Force,
 }
 
+/// Governs which dependencies of an uninstalled package are uninstalled along with it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DependenciesMode {
+    /// Uninstall the dependencies which nothing depends on once the package is gone
+    Orphaned,
+    /// Also uninstall the dependencies which nothing depends on once other dependencies are gone
+    Cascade,
+}
+
+impl Default for DependenciesMode {
+    fn default() -> Self { DependenciesMode::Orphaned }
+}
+
+/// Uninstall a package, as `uninstall_many` does.
+///
+/// Returns every package which was deleted
 #[allow(clippy::too_many_arguments)]
 pub async fn uninstall<U>(ui: &mut U,
                           ident: impl AsRef<PackageIdent>,
@@ -54,8 +87,9 @@ pub async fn uninstall<U>(ui: &mut U,
                           scope: Scope,
                           excludes: &[PackageIdent],
                           uninstall_hook_mode: UninstallHookMode,
-                          safety: UninstallSafety)
-                          -> Result<()>
+                          safety: UninstallSafety,
+                          dependencies_mode: DependenciesMode)
+                          -> Result<Vec<PackageIdent>>
     where U: UIWriter
 {
     uninstall_many(ui,
@@ -65,12 +99,13 @@ pub async fn uninstall<U>(ui: &mut U,
                    scope,
                    excludes,
                    uninstall_hook_mode,
-                   safety).await
+                   safety,
+                   dependencies_mode).await
 }
 
 /// Uninstall all but the `number_latest_to_keep` packages.
 ///
-/// Returns the number of packages that were uninstalled
+/// Returns every package which was deleted, dependencies included
 #[allow(clippy::too_many_arguments)]
 pub async fn uninstall_all_but_latest<U>(ui: &mut U,
                                          ident: impl AsRef<PackageIdent>,
@@ -81,7 +116,7 @@ pub async fn uninstall_all_but_latest<U>(ui: &mut U,
                                          excludes: &[PackageIdent],
                                          uninstall_hook_mode: UninstallHookMode,
                                          safety: UninstallSafety)
-                                         -> Result<usize>
+                                         -> Result<Vec<PackageIdent>>
     where U: UIWriter
 {
     let ident = ident.as_ref();
@@ -91,22 +126,21 @@ pub async fn uninstall_all_but_latest<U>(ui: &mut U,
         ui.begin(format!("Uninstalling {}", ident))?;
         ui.status(Status::Skipping, format!("Only {} packages installed", len))?;
         ui.end(format!("Uninstall of {} complete", ident))?;
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     // Reverse sort the idents so the latest occur first in the list
     idents.sort_unstable_by(|a, b| b.by_parts_cmp(a));
 
-    let to_uninstall = &mut idents[number_latest_to_keep..];
     uninstall_many(ui,
-                   to_uninstall,
+                   &mut idents[number_latest_to_keep..],
                    fs_root_path,
                    execution_strategy,
                    scope,
                    excludes,
                    uninstall_hook_mode,
-                   safety).await?;
-    Ok(to_uninstall.len())
+                   safety,
+                   DependenciesMode::default()).await
 }
 
 /// Delete packages and all dependencies which are not used by the packages.
@@ -118,14 +152,21 @@ pub async fn uninstall_all_but_latest<U>(ui: &mut U,
 /// 1. We find all packages on the filesystem and convert them into a graph
 /// 2. We find the fully qualified package ident and all its dependencies
 /// 3. We do a BFS on the graph to get the dependencies in order
-/// 4. We check if the specified package has any reverse deps
-///     4a. If there are, we throw an error
-///     4b. If not, we delete the package
+/// 4. We check whether removing the specified package would break a service loaded by the
+///    supervisor
+///     4a. If it would, we act according to `safety`
+///     4b. If the package has reverse deps, we throw an error
+///     4c. If not, we delete the package
 /// 5. For each dependency we check if there are any packages which depend on it
 ///     5a. If there are, we skip it
 ///     5b. If there are not, we delete it from disk and the graph
+///     5c. With `DependenciesMode::Cascade`, we go back over the skipped dependencies, deleting
+///         each one which nothing depends on now that the others are gone
 ///
 /// `excludes` is a list of user-supplied `PackageIdent`s.
+///
+/// Returns every package which was deleted, in the order they were deleted. Nothing is deleted
+/// in a dry run.
 #[allow(clippy::too_many_arguments)]
 pub async fn uninstall_many<U>(ui: &mut U,
                                idents: &mut [impl AsRef<PackageIdent>],
@@ -134,10 +175,13 @@ pub async fn uninstall_many<U>(ui: &mut U,
                                scope: Scope,
                                excludes: &[PackageIdent],
                                uninstall_hook_mode: UninstallHookMode,
-                               safety: UninstallSafety)
-                               -> Result<()>
+                               safety: UninstallSafety,
+                               dependencies_mode: DependenciesMode)
+                               -> Result<Vec<PackageIdent>>
     where U: UIWriter
 {
+    let mut deleted = Vec::new();
+
     // 1.
     let mut graph = PackageGraph::from_root_path(fs_root_path)?;
 
@@ -145,13 +189,9 @@ pub async fn uninstall_many<U>(ui: &mut U,
     if !loaded_services.is_empty() {
         ui.status(Status::Determining, "list of loaded services in supervisor")?;
         for s in loaded_services.iter() {
-            ui.status(Status::Found, format!("loaded service {}", s))?;
+            ui.status(Status::Found, format!("loaded service {}", s.ident))?;
         }
     }
-    let safety = match safety {
-        UninstallSafety::Safe => UninstallSafetyImpl::SkipIfLoaded(&loaded_services),
-        UninstallSafety::Force => UninstallSafetyImpl::Force,
-    };
     // Never uninstall a dependency if it is loaded
     let dependency_safety = UninstallSafetyImpl::SkipIfLoaded(&loaded_services);
 
@@ -172,7 +212,11 @@ pub async fn uninstall_many<U>(ui: &mut U,
         // 3.
         let deps = graph.owned_ordered_deps(&ident);
 
-        // 4.
+        // 4a.
+        let safety =
+            check_loaded_services(ui, &ident, &loaded_services, execution_strategy, safety).await?;
+
+        // 4b.
         match graph.count_rdeps(&ident) {
             None => {
                 // package not in graph - this shouldn't happen but could be a race condition in
@@ -186,13 +230,17 @@ pub async fn uninstall_many<U>(ui: &mut U,
                                 &ident))?;
             }
             Some(0) => {
-                maybe_delete(ui,
-                             &fs_root_path,
-                             &pkg_install,
-                             execution_strategy,
-                             &excludes,
-                             uninstall_hook_mode,
-                             safety).await?;
+                if maybe_delete(ui,
+                                &fs_root_path,
+                                &pkg_install,
+                                execution_strategy,
+                                &excludes,
+                                uninstall_hook_mode,
+                                safety).await?
+                   == Deletion::Deleted
+                {
+                    deleted.push(ident.clone());
+                }
                 graph.remove(&ident);
             }
             Some(c) => {
@@ -207,6 +255,7 @@ pub async fn uninstall_many<U>(ui: &mut U,
                 ui.status(Status::Skipping, "dependencies (--no-deps specified)")?;
             }
             Scope::PackageAndDependencies => {
+                let mut skipped = Vec::new();
                 for p in &deps {
                     match graph.count_rdeps(&p) {
                         None => {
@@ -223,21 +272,41 @@ pub async fn uninstall_many<U>(ui: &mut U,
                         }
                         Some(0) => {
                             let install = PackageInstall::load(&p, Some(fs_root_path))?;
-                            maybe_delete(ui,
-                                         &fs_root_path,
-                                         &install,
-                                         execution_strategy,
-                                         &excludes,
-                                         uninstall_hook_mode,
-                                         dependency_safety).await?;
+                            if maybe_delete(ui,
+                                            &fs_root_path,
+                                            &install,
+                                            execution_strategy,
+                                            &excludes,
+                                            uninstall_hook_mode,
+                                            dependency_safety).await?
+                               == Deletion::Deleted
+                            {
+                                deleted.push(p.clone());
+                            }
 
                             graph.remove(&p);
                             count += 1;
                         }
-                        Some(c) => {
-                            ui.status(Status::Skipping,
-                                      format!("{}. It is a dependency of {} packages", &p, c))?;
-                        }
+                        Some(_) => skipped.push(p.clone()),
+                    }
+                }
+
+                // 5c.
+                if dependencies_mode == DependenciesMode::Cascade {
+                    count += uninstall_orphaned(ui,
+                                                &fs_root_path,
+                                                &mut graph,
+                                                &mut skipped,
+                                                &mut deleted,
+                                                execution_strategy,
+                                                &excludes,
+                                                uninstall_hook_mode,
+                                                dependency_safety).await?;
+                }
+                for p in &skipped {
+                    if let Some(c) = graph.count_rdeps(&p) {
+                        ui.status(Status::Skipping,
+                                  format!("{}. It is a dependency of {} packages", &p, c))?;
                     }
                 }
             }
@@ -253,9 +322,154 @@ pub async fn uninstall_many<U>(ui: &mut U,
             }
         };
     }
+    Ok(deleted)
+}
+
+/// Find the services loaded by the supervisor which uninstalling `ident` would break and handle
+/// them according to `safety`. Returns the safety with which to uninstall the package.
+async fn check_loaded_services<'a, U>(ui: &mut U,
+                                      ident: &PackageIdent,
+                                      loaded_services: &'a [LoadedService],
+                                      execution_strategy: ExecutionStrategy,
+                                      safety: UninstallSafety)
+                                      -> Result<UninstallSafetyImpl<'a>>
+    where U: UIWriter
+{
+    let mut affected = loaded_services.iter()
+                                      .filter(|service| service.needs(ident));
+    match safety {
+        UninstallSafety::Safe => Ok(UninstallSafetyImpl::SkipIfLoaded(loaded_services)),
+        UninstallSafety::Force => Ok(UninstallSafetyImpl::Force),
+        UninstallSafety::Strict => {
+            match affected.next() {
+                Some(service) => {
+                    Err(Error::CannotRemoveLoadedPackage(ident.clone(), service.ident.clone()))
+                }
+                None => Ok(UninstallSafetyImpl::SkipIfLoaded(loaded_services)),
+            }
+        }
+        UninstallSafety::StopServices => {
+            for service in affected {
+                match execution_strategy {
+                    ExecutionStrategy::DryRun => {
+                        ui.status(Status::Skipping,
+                                  format!("stopping service {} (Dry run)", service.ident))?;
+                    }
+                    ExecutionStrategy::Run => {
+                        ui.status(Status::Stopping, format!("service {}", service.ident))?;
+                        stop_supervisor_service(&service.ident).await?;
+                        wait_until_down(&service.ident,
+                                        supervisor_service_is_down,
+                                        SERVICE_STOP_TIMEOUT,
+                                        SERVICE_STOP_POLL_INTERVAL).await?;
+                    }
+                }
+            }
+            Ok(UninstallSafetyImpl::Force)
+        }
+    }
+}
+
+/// Delete the dependencies in `skipped` which nothing depends on any more, now that the
+/// packages which depended on them have been deleted. Each is confirmed with
+/// `PackageGraph::ordered_reverse_deps` to be needed by nothing still installed, and deleting it
+/// may orphan others in turn. Deleted packages are added to `deleted` and removed from `skipped`.
+///
+/// Returns the number of dependencies uninstalled, including those which would be in a dry run
+#[allow(clippy::too_many_arguments)]
+async fn uninstall_orphaned<U>(ui: &mut U,
+                               fs_root_path: &Path,
+                               graph: &mut PackageGraph,
+                               skipped: &mut Vec<PackageIdent>,
+                               deleted: &mut Vec<PackageIdent>,
+                               execution_strategy: ExecutionStrategy,
+                               excludes: &[PackageIdent],
+                               uninstall_hook_mode: UninstallHookMode,
+                               safety: UninstallSafetyImpl<'_>)
+                               -> Result<usize>
+    where U: UIWriter
+{
+    let mut count = 0;
+    while let Some(idx) =
+        skipped.iter()
+               .position(|p| graph.has_package(p) && graph.ordered_reverse_deps(p).is_empty())
+    {
+        let p = skipped.remove(idx);
+        let install = PackageInstall::load(&p, Some(fs_root_path))?;
+        if maybe_delete(ui,
+                        fs_root_path,
+                        &install,
+                        execution_strategy,
+                        excludes,
+                        uninstall_hook_mode,
+                        safety).await?
+           == Deletion::Deleted
+        {
+            deleted.push(p.clone());
+        }
+        graph.remove(&p);
+        count += 1;
+    }
+    Ok(count)
+}
+
+async fn stop_supervisor_service(ident: &PackageIdent) -> Result<()> {
+    let msg = biome_sup_protocol::ctl::SvcStop { ident:              Some(ident.clone().into()),
+                                                 timeout_in_seconds: None, };
+    gateway_util::send(&ListenCtlAddr::default(), msg).await
+}
+
+/// Poll `is_down` until it reports that the service `ident` is down, so that nothing is deleted
+/// from under a service which is still shutting down.
+async fn wait_until_down<F, T>(ident: &PackageIdent,
+                               is_down: F,
+                               timeout: Duration,
+                               poll_interval: Duration)
+                               -> Result<()>
+    where F: Fn(PackageIdent) -> T,
+          T: Future<Output = Result<bool>>
+{
+    let deadline = Instant::now() + timeout;
+    while !is_down(ident.clone()).await? {
+        if Instant::now() >= deadline {
+            return Err(Error::ServiceNotStopped(ident.clone()));
+        }
+        tokio::time::delay_for(poll_interval).await;
+    }
     Ok(())
 }
 
+/// Ask the supervisor whether the service `ident` is down
+async fn supervisor_service_is_down(ident: PackageIdent) -> Result<bool> {
+    let cfg = config::load()?;
+    let secret_key = config::ctl_secret_key(&cfg)?;
+    let msg = biome_sup_protocol::ctl::SvcStatus { ident: Some(ident.into()), };
+
+    let mut down = true;
+    let mut response = SrvClient::request(&ListenCtlAddr::default(), &secret_key, msg).await?;
+    while let Some(message_result) = response.next().await {
+        let reply = message_result?;
+        match reply.message_id() {
+            "ServiceStatus" => {
+                let m = reply.parse::<biome_sup_protocol::types::ServiceStatus>()
+                             .map_err(SrvClientError::Decode)?;
+                down = m.process
+                        .map_or(true, |process| process.state() == ProcessState::Down);
+            }
+            "NetOk" => (),
+            "NetErr" => {
+                let err = reply.parse::<biome_sup_protocol::net::NetErr>()
+                               .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(err).into());
+            }
+            _ => {
+                warn!("Unexpected status message, {:?}", reply);
+            }
+        }
+    }
+    Ok(down)
+}
+
 /// Check if we have a launcher/supervisor running out of this biome root.
 /// If the launcher PID file exists then the supervisor is up and running
 fn launcher_is_running(fs_root_path: &Path) -> bool {
@@ -265,7 +479,25 @@ fn launcher_is_running(fs_root_path: &Path) -> bool {
     pid_file_path.is_file()
 }
 
-async fn supervisor_services() -> Result<Vec<PackageIdent>> {
+/// A service loaded by the supervisor
+pub(crate) struct LoadedService {
+    /// The package the service is running
+    pub ident: PackageIdent,
+    /// The transitive dependencies of that package, as resolved by the supervisor
+    pub deps:  Vec<PackageIdent>,
+}
+
+impl LoadedService {
+    /// Whether uninstalling `ident` would break the service
+    fn needs(&self, ident: &PackageIdent) -> bool {
+        iter::once(&self.ident).chain(&self.deps)
+                               .any(|p| p.satisfies(ident))
+    }
+}
+
+/// Ask the supervisor for the services it has loaded and the dependencies each of them is
+/// running with
+pub(crate) async fn supervisor_services() -> Result<Vec<LoadedService>> {
     if !launcher_is_running(&*FS_ROOT_PATH) {
         return Ok(vec![]);
     }
@@ -275,7 +507,7 @@ async fn supervisor_services() -> Result<Vec<PackageIdent>> {
     let listen_ctl_addr = ListenCtlAddr::default();
     let msg = biome_sup_protocol::ctl::SvcStatus::default();
 
-    let mut out: Vec<PackageIdent> = vec![];
+    let mut out: Vec<LoadedService> = vec![];
     let mut response = SrvClient::request(&listen_ctl_addr, &secret_key, msg).await?;
     while let Some(message_result) = response.next().await {
         let reply = message_result?;
@@ -283,7 +515,8 @@ async fn supervisor_services() -> Result<Vec<PackageIdent>> {
             "ServiceStatus" => {
                 let m = reply.parse::<biome_sup_protocol::types::ServiceStatus>()
                              .map_err(SrvClientError::Decode)?;
-                out.push(m.ident.into());
+                out.push(LoadedService { ident: m.ident.into(),
+                                         deps:  m.deps.into_iter().map(Into::into).collect(), });
             }
             "NetOk" => (),
             "NetErr" => {
@@ -300,51 +533,74 @@ async fn supervisor_services() -> Result<Vec<PackageIdent>> {
 }
 
 #[derive(Clone, Copy)]
-enum UninstallSafetyImpl<'a> {
-    SkipIfLoaded(&'a [PackageIdent]),
+pub(crate) enum UninstallSafetyImpl<'a> {
+    SkipIfLoaded(&'a [LoadedService]),
     Force,
 }
 
 impl UninstallSafetyImpl<'_> {
     fn should_skip(&self, ident: &PackageIdent) -> bool {
         if let Self::SkipIfLoaded(services) = self {
-            services.iter().any(|i| i.satisfies(ident))
+            services.iter().any(|service| service.needs(ident))
         } else {
             false
         }
     }
 }
 
-/// Delete a package from disk, depending upon the ExecutionStrategy supplied
+/// What `maybe_delete` did with a package
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Deletion {
+    /// The package was deleted
+    Deleted,
+    /// The package would be deleted but it's a dry run
+    DryRun,
+    /// The package must not be deleted
+    Skipped,
+}
+
+/// Delete a package from disk unless it must be kept, depending upon the ExecutionStrategy
+/// supplied
 ///
 /// Returns:
-///   Ok(true) - package is deleted
-///   Ok(false) - package would be deleted but it's a dry run
+///   Ok(_) - what was done with the package
 ///   Err(_) -  IO problem deleting package from filesystem
-async fn maybe_delete<U>(ui: &mut U,
-                         fs_root_path: &Path,
-                         install: &PackageInstall,
-                         strategy: ExecutionStrategy,
-                         excludes: &[PackageIdent],
-                         uninstall_hook_mode: UninstallHookMode,
-                         safety: UninstallSafetyImpl<'_>)
-                         -> Result<bool>
+pub(crate) async fn maybe_delete<U>(ui: &mut U,
+                                    fs_root_path: &Path,
+                                    install: &PackageInstall,
+                                    strategy: ExecutionStrategy,
+                                    excludes: &[PackageIdent],
+                                    uninstall_hook_mode: UninstallHookMode,
+                                    safety: UninstallSafetyImpl<'_>)
+                                    -> Result<Deletion>
     where U: UIWriter
 {
     let ident = install.ident();
-    let pkg_root_path = hfs::pkg_root_path(Some(fs_root_path));
 
+    if let Some(reason) = skip_reason(ident, excludes, safety)? {
+        ui.status(Status::Skipping, format!("{}. {}", &ident, reason))?;
+        return Ok(Deletion::Skipped);
+    }
+
+    if delete(ui, fs_root_path, install, strategy, uninstall_hook_mode).await? {
+        Ok(Deletion::Deleted)
+    } else {
+        Ok(Deletion::DryRun)
+    }
+}
+
+/// Why a package must not be deleted, if it must not be
+fn skip_reason(ident: &PackageIdent,
+               excludes: &[PackageIdent],
+               safety: UninstallSafetyImpl<'_>)
+               -> Result<Option<&'static str>> {
     let bio = PackageIdent::from_str("biome/bio")?;
     if ident.satisfies(&bio) {
-        ui.status(Status::Skipping,
-                  format!("{}. You can't uninstall biome/bio", &ident))?;
-        return Ok(false);
+        return Ok(Some("You can't uninstall biome/bio"));
     }
 
     if safety.should_skip(ident) {
-        ui.status(Status::Skipping,
-                  format!("{}. It is currently loaded by the supervisor", &ident))?;
-        return Ok(false);
+        return Ok(Some("It is currently loaded by the supervisor"));
     }
 
     // The excludes list could be looser than the fully qualified idents.  E.g. if core/redis is on
@@ -352,11 +608,28 @@ async fn maybe_delete<U>(ui: &mut U,
     // `Identifiable` trait which supplies this logic for PackageIdents
     let should_exclude = excludes.iter().any(|i| i.satisfies(ident));
     if should_exclude {
-        ui.status(Status::Skipping,
-                  format!("{}. It is on the exclusion list", &ident))?;
-        return Ok(false);
+        return Ok(Some("It is on the exclusion list"));
     }
 
+    Ok(None)
+}
+
+/// Delete a package from disk without any of the safety checks of `maybe_delete`, depending upon
+/// the ExecutionStrategy supplied
+///
+/// Returns:
+///   Ok(true) - package is deleted
+///   Ok(false) - package would be deleted but it's a dry run
+///   Err(_) -  IO problem deleting package from filesystem
+async fn delete<U>(ui: &mut U,
+                   fs_root_path: &Path,
+                   install: &PackageInstall,
+                   strategy: ExecutionStrategy,
+                   uninstall_hook_mode: UninstallHookMode)
+                   -> Result<bool>
+    where U: UIWriter
+{
+    let ident = install.ident();
     match strategy {
         ExecutionStrategy::DryRun => {
             ui.status(Status::DryRunDeleting, &ident)?;
@@ -367,6 +640,7 @@ async fn maybe_delete<U>(ui: &mut U,
             if uninstall_hook_mode == UninstallHookMode::Run {
                 maybe_run_uninstall_hook(ui, &install).await?;
             }
+            let pkg_root_path = hfs::pkg_root_path(Some(fs_root_path));
             let pkg_dir = install.installed_path();
             do_clean_delete(&pkg_root_path, &pkg_dir)
         }
@@ -435,3 +709,195 @@ fn do_clean_delete(pkg_root_path: &Path, real_install_path: &Path) -> Result<boo
         None => unreachable!("Install path doesn't have a parent"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::ui::UI;
+    use biome_core::package::PackageTarget;
+    use futures::future;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    fn ident(s: &str) -> PackageIdent { PackageIdent::from_str(s).unwrap() }
+
+    fn fake_pkg_install(fs_root_path: &Path, ident: &PackageIdent, deps: &[&PackageIdent]) {
+        let prefix = hfs::pkg_install_path(ident, Some(fs_root_path));
+        fs::create_dir_all(&prefix).unwrap();
+        fs::write(prefix.join("IDENT"), ident.to_string()).unwrap();
+        fs::write(prefix.join("TARGET"),
+                  PackageTarget::active_target().to_string()).unwrap();
+        if !deps.is_empty() {
+            let deps: Vec<String> = deps.iter().map(ToString::to_string).collect();
+            fs::write(prefix.join("DEPS"), deps.join("\n")).unwrap();
+        }
+    }
+
+    fn is_installed(fs_root_path: &Path, ident: &PackageIdent) -> bool {
+        hfs::pkg_install_path(ident, Some(fs_root_path)).is_dir()
+    }
+
+    fn loaded_app() -> LoadedService {
+        LoadedService { ident: ident("core/app/1.0.0/20200101000000"),
+                        deps:  vec![ident("core/lib/1.0.0/20200101000000"),
+                                    ident("core/libc/1.0.0/20200101000000")], }
+    }
+
+    #[tokio::test]
+    async fn strict_refuses_to_break_a_loaded_service() {
+        let mut ui = UI::with_sinks();
+        let loaded_services = vec![loaded_app()];
+
+        // A transitive dependency of the service is as good as the service itself
+        let libc = ident("core/libc/1.0.0/20200101000000");
+        match check_loaded_services(&mut ui,
+                                    &libc,
+                                    &loaded_services,
+                                    ExecutionStrategy::Run,
+                                    UninstallSafety::Strict).await
+        {
+            Err(Error::CannotRemoveLoadedPackage(p, s)) => {
+                assert_eq!(p, libc);
+                assert_eq!(s, loaded_services[0].ident);
+            }
+            _ => panic!("uninstalling a dependency of a loaded service should be refused"),
+        }
+
+        let other = ident("core/other/1.0.0/20200101000000");
+        let safety = check_loaded_services(&mut ui,
+                                           &other,
+                                           &loaded_services,
+                                           ExecutionStrategy::Run,
+                                           UninstallSafety::Strict).await
+                                                                   .unwrap();
+        assert!(!safety.should_skip(&other));
+        assert!(safety.should_skip(&libc));
+    }
+
+    #[tokio::test]
+    async fn stop_services_forces_the_uninstall() {
+        let mut ui = UI::with_sinks();
+        let loaded_services = vec![loaded_app()];
+
+        let lib = ident("core/lib/1.0.0/20200101000000");
+        let safety = check_loaded_services(&mut ui,
+                                           &lib,
+                                           &loaded_services,
+                                           ExecutionStrategy::DryRun,
+                                           UninstallSafety::StopServices).await
+                                                                         .unwrap();
+        assert!(!safety.should_skip(&lib));
+    }
+
+    #[tokio::test]
+    async fn stop_services_waits_for_the_service_to_be_down() {
+        let polls = Cell::new(0);
+        let is_down = |_: PackageIdent| {
+            polls.set(polls.get() + 1);
+            future::ready(Ok(polls.get() == 3))
+        };
+
+        wait_until_down(&ident("core/app"),
+                        is_down,
+                        Duration::from_secs(10),
+                        Duration::from_millis(1)).await
+                                                 .unwrap();
+        assert_eq!(polls.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn stop_services_gives_up_on_a_service_that_stays_up() {
+        let result = wait_until_down(&ident("core/app"),
+                                     |_| future::ready(Ok(false)),
+                                     Duration::from_millis(20),
+                                     Duration::from_millis(1)).await;
+        assert!(matches!(result, Err(Error::ServiceNotStopped(_))));
+    }
+
+    /// Install `core/app`, whose dependency `core/base` is only orphaned once `core/lib2`, which
+    /// comes after it in the ordered dependencies, is gone
+    fn install_app(fs_root_path: &Path)
+                   -> (PackageIdent, PackageIdent, PackageIdent, PackageIdent) {
+        let base = ident("core/base/1.0.0/20200101000000");
+        let lib2 = ident("core/lib2/1.0.0/20200101000000");
+        let lib1 = ident("core/lib1/1.0.0/20200101000000");
+        let app = ident("core/app/1.0.0/20200101000000");
+        fake_pkg_install(fs_root_path, &base, &[]);
+        fake_pkg_install(fs_root_path, &lib2, &[&base]);
+        fake_pkg_install(fs_root_path, &lib1, &[&lib2]);
+        fake_pkg_install(fs_root_path, &app, &[&lib1, &base]);
+        (app, lib1, lib2, base)
+    }
+
+    #[tokio::test]
+    async fn cascade_uninstalls_dependencies_orphaned_in_turn() {
+        let mut ui = UI::with_sinks();
+
+        let fs_root = TempDir::new().unwrap();
+        let (app, lib1, lib2, base) = install_app(fs_root.path());
+        let deleted = uninstall(&mut ui,
+                                &app,
+                                fs_root.path(),
+                                ExecutionStrategy::Run,
+                                Scope::PackageAndDependencies,
+                                &[],
+                                UninstallHookMode::Ignore,
+                                UninstallSafety::Strict,
+                                DependenciesMode::Orphaned).await
+                                                           .unwrap();
+        assert_eq!(deleted, vec![app.clone(), lib1.clone(), lib2.clone()]);
+        assert!(is_installed(fs_root.path(), &base));
+
+        let fs_root = TempDir::new().unwrap();
+        install_app(fs_root.path());
+        let deleted = uninstall(&mut ui,
+                                &app,
+                                fs_root.path(),
+                                ExecutionStrategy::Run,
+                                Scope::PackageAndDependencies,
+                                &[],
+                                UninstallHookMode::Ignore,
+                                UninstallSafety::Strict,
+                                DependenciesMode::Cascade).await
+                                                          .unwrap();
+        assert_eq!(deleted, vec![app, lib1, lib2, base.clone()]);
+        assert!(!is_installed(fs_root.path(), &base));
+    }
+
+    #[tokio::test]
+    async fn cascade_keeps_what_is_still_needed() {
+        let fs_root = TempDir::new().unwrap();
+        let (app, lib1, lib2, base) = install_app(fs_root.path());
+        let tool = ident("core/tool/1.0.0/20200101000000");
+        fake_pkg_install(fs_root.path(), &tool, &[&base]);
+
+        let mut ui = UI::with_sinks();
+        // Packages which depend on the package itself are never uninstalled
+        let result = uninstall(&mut ui,
+                               &lib2,
+                               fs_root.path(),
+                               ExecutionStrategy::Run,
+                               Scope::PackageAndDependencies,
+                               &[],
+                               UninstallHookMode::Ignore,
+                               UninstallSafety::Strict,
+                               DependenciesMode::Cascade).await;
+        assert!(matches!(result, Err(Error::CannotRemovePackage(_, 1))));
+        assert!(is_installed(fs_root.path(), &lib2));
+        assert!(is_installed(fs_root.path(), &lib1));
+
+        let deleted = uninstall(&mut ui,
+                                &app,
+                                fs_root.path(),
+                                ExecutionStrategy::Run,
+                                Scope::PackageAndDependencies,
+                                &[],
+                                UninstallHookMode::Ignore,
+                                UninstallSafety::Strict,
+                                DependenciesMode::Cascade).await
+                                                          .unwrap();
+        assert_eq!(deleted, vec![app, lib1, lib2]);
+        assert!(is_installed(fs_root.path(), &base));
+        assert!(is_installed(fs_root.path(), &tool));
+    }
+}
@@ -11,6 +11,11 @@ pub fn start(ui: &mut UI, src: &Path, key_cache: &KeyCache) -> Result<()> {
     let (name_with_rev, hash) = artifact::verify(src, &key_cache)?;
     ui.status(Status::Verified,
               format!("checksum {} signed with {}", &hash, &name_with_rev))?;
+    for signature in artifact::detached_signatures(src)? {
+        artifact::verify_detached(src, &signature, &key_cache)?;
+        ui.status(Status::Verified,
+                  format!("detached signature with {}", signature.signer()))?;
+    }
     ui.end(format!("Verified artifact {}.", &src.display()))?;
     Ok(())
 }
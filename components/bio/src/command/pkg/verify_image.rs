@@ -0,0 +1,46 @@
+use crate::{common::{provenance::{self,
+                                  Layout,
+                                  Reference,
+                                  Registry},
+                     ui::{Status,
+                          UIWriter,
+                          UI}},
+            error::Result,
+            PRODUCT,
+            VERSION};
+use biome_core::crypto::keys::KeyCache;
+use std::path::Path;
+
+/// Verify the provenance attestation attached to `image`, which is
+/// either an OCI image layout directory or a reference to an image in
+/// a registry. `credentials` are the base64-encoded
+/// `username:password` of the registry, if it needs any.
+pub async fn start(ui: &mut UI,
+                   image: &str,
+                   credentials: Option<String>,
+                   key_cache: &KeyCache)
+                   -> Result<()> {
+    ui.begin(format!("Verifying provenance of image {}", image))?;
+    let (digest, envelope) = if Layout::is_layout(Path::new(image)) {
+        let layout = Layout::open(Path::new(image))?;
+        let digest = layout.image()?.digest;
+        let envelope = layout.attestation(&digest)?;
+        (digest, envelope)
+    } else {
+        let mut registry =
+            Registry::new(image.parse::<Reference>()?, credentials, PRODUCT, VERSION)?;
+        let digest = registry.image().await?.digest;
+        let envelope = registry.attestation(&digest).await?;
+        (digest, envelope)
+    };
+
+    let (provenance, signer) = provenance::verify(&envelope, &digest, key_cache)?;
+    ui.status(Status::Verified,
+              format!("attestation of {} signed with {}", digest, signer))?;
+    for material in &provenance.materials {
+        ui.para(&format!("{} (checksum {}, signed with {})",
+                         material.ident, material.checksum, material.signer))?;
+    }
+    ui.end(format!("Verified provenance of image {}.", image))?;
+    Ok(())
+}
@@ -23,12 +23,14 @@ pub type Result<T> = result::Result<T, Error>;
 pub enum Error {
     APIClient(api_client::Error),
     ArgumentError(String),
+    ArtifactChecksumMismatch(PackageIdent, String, String),
     ButterflyError(String),
     CacheSslCertError(String),
     CannotParseBinlinkBinaryName(PathBuf),
     CannotParseBinlinkTarget(PathBuf),
     CannotRemoveDockerStudio,
     CannotRemoveFromChannel((String, String)),
+    CannotRemoveLoadedPackage(hcore::package::PackageIdent, hcore::package::PackageIdent),
     CannotRemovePackage(hcore::package::PackageIdent, usize),
     CommandNotFoundInPkg((String, String)),
     ConfigOpt(configopt::Error),
@@ -55,6 +57,7 @@ pub enum Error {
     JsonErr(serde_json::Error),
     KeyTypeParseError(String),
     LicenseNotAccepted,
+    LockfileParseError(String),
     NameLookup,
     NetErr(net::NetErr),
     PackageArchiveMalformed(String),
@@ -65,6 +68,7 @@ pub enum Error {
     ProvidesError(String),
     RootRequired,
     ScheduleStatus(api_client::Error),
+    ServiceNotStopped(hcore::package::PackageIdent),
     SubcommandNotSupported(String),
     UnsupportedExportFormat(String),
     TomlDeserializeError(toml::de::Error),
@@ -79,6 +83,10 @@ impl fmt::Display for Error {
         let msg = match *self {
             Error::APIClient(ref e) => e.to_string(),
             Error::ArgumentError(ref e) => e.to_string(),
+            Error::ArtifactChecksumMismatch(ref ident, ref expected, ref actual) => {
+                format!("Checksum of {} does not match the lockfile: expected {}, found {}",
+                        ident, expected, actual)
+            }
             Error::ButterflyError(ref e) => e.to_string(),
             Error::CacheSslCertError(ref e) => format!("Cannot cache SSL_CERT_FILE: {}", e),
             Error::CannotParseBinlinkBinaryName(ref p) => {
@@ -93,6 +101,12 @@ impl fmt::Display for Error {
             Error::CannotRemoveFromChannel((ref p, ref c)) => {
                 format!("{} cannot be removed from the {} channel.", p, c)
             }
+            Error::CannotRemoveLoadedPackage(ref p, ref s) => {
+                format!("Can't remove package: {}. The Supervisor has loaded the service {}, \
+                         which needs it. Use --force to stop the service and remove the package \
+                         anyway",
+                        p, s)
+            }
             Error::CannotRemovePackage(ref p, ref c) => {
                 format!("Can't remove package: {}. It is a dependency of {} packages",
                         p, c)
@@ -165,6 +179,7 @@ impl fmt::Display for Error {
             Error::JobGroupCancel(ref e) => format!("Failed to cancel job group: {:?}", e),
             Error::KeyTypeParseError(ref s) => format!("Failed to parse key type: {}", s),
             Error::LicenseNotAccepted => "License agreement not accepted".to_string(),
+            Error::LockfileParseError(ref e) => format!("Lockfile could not be parsed: {}", e),
             Error::NameLookup => "Error resolving a name or IP address".to_string(),
             Error::NetErr(ref e) => e.to_string(),
             Error::PackageArchiveMalformed(ref e) => {
@@ -182,6 +197,10 @@ impl fmt::Display for Error {
                 "Root or administrator permissions required to complete operation".to_string()
             }
             Error::ScheduleStatus(ref e) => format!("Failed to retrieve job group status: {:?}", e),
+            Error::ServiceNotStopped(ref s) => {
+                format!("Timed out waiting for the Supervisor to stop the service {}",
+                        s)
+            }
             Error::SubcommandNotSupported(ref e) => {
                 format!("Subcommand `{}' not supported on this operating system", e)
             }
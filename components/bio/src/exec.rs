@@ -1,7 +1,8 @@
 use crate::{common::{self,
                      command::package::install::{InstallHookMode,
                                                  InstallMode,
-                                                 LocalPackageUsage},
+                                                 LocalPackageUsage,
+                                                 SignaturePolicy},
                      ui::{Status,
                           UIWriter,
                           UI}},
@@ -87,6 +88,7 @@ async fn command_from_min_pkg_with_optional_channel(ui: &mut UI,
             ui.status(Status::Missing, format!("package for {}", &ident))?;
 
             let channel = internal_tooling_channel(channel);
+            let signature_policy = SignaturePolicy::from_env()?;
 
             // JB TODO - Does an auth token need to be plumbed into here?  Not 100% sure.
             retry::retry_future!(delay::NoDelay.take(RETRY_LIMIT), async {
@@ -108,7 +110,8 @@ async fn command_from_min_pkg_with_optional_channel(ui: &mut UI,
                                                          // TODO (CM): pass through and enable
                                                          // no-local-package mode
                                                          &LocalPackageUsage::default(),
-                                                         InstallHookMode::default()).await
+                                                         InstallHookMode::default(),
+                                                         &signature_policy).await
             }).await
               .map_err(|e| CommonError::PackageFailedToInstall(ident.clone(), Box::new(e.error)))?
         }
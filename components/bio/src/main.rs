@@ -39,7 +39,10 @@ use bio::{cli::{self,
                     pkg::{download::{PackageSet,
                                      PackageSetFile},
                           list::ListingType,
-                          uninstall::UninstallHookMode}},
+                          lock::Lockfile,
+                          uninstall::{DependenciesMode,
+                                      UninstallHookMode,
+                                      UninstallSafety}}},
           config,
           error::{Error,
                   Result},
@@ -56,7 +59,9 @@ use biome_common::{self as common,
                      command::package::install::{InstallHookMode,
                                                  InstallMode,
                                                  InstallSource,
-                                                 LocalPackageUsage},
+                                                 LocalPackageUsage,
+                                                 SignaturePolicy},
+                     sbom::SbomFormat,
                      types::ListenCtlAddr,
                      ui::{self,
                           Status,
@@ -69,6 +74,7 @@ use biome_core::{crypto::{init,
                    env::{self as henv,
                          Config as _},
                    fs::{cache_artifact_path,
+                        CACHE_KEY_PATH,
                         FS_ROOT_PATH},
                    os::process::ShutdownTimeout,
                    package::{target,
@@ -91,13 +97,15 @@ use std::{collections::HashMap,
           io::{self,
                prelude::*,
                Read},
+          net::SocketAddr,
           path::{Path,
                  PathBuf},
           process,
           result,
           str::FromStr,
           string::ToString,
-          thread};
+          thread,
+          time::Duration};
 use tabwriter::TabWriter;
 
 /// Makes the --org CLI param optional when this env var is set
@@ -284,10 +292,20 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                                              automation and processes accordingly.")?;
                                     return command::pkg::export::container::start(ui, &args.args).await;
                                 }
+                                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                                PkgExportCommand::Kubernetes(args) => {
+                                    return command::pkg::export::kubernetes::start(ui, &args.args).await;
+                                }
                                 #[cfg(target_os = "linux")]
                                 PkgExportCommand::Mesos(args) => {
                                     return command::pkg::export::mesos::start(ui, &args.args).await;
                                 }
+                                #[cfg(target_os = "linux")]
+                                PkgExportCommand::Systemd(args) => {
+                                    let mut tar_args = vec![OsString::from("--systemd")];
+                                    tar_args.extend(args.args.iter().cloned());
+                                    return command::pkg::export::tar::start(ui, &tar_args).await;
+                                }
                                 #[cfg(any(target_os = "linux", target_os = "windows"))]
                                 PkgExportCommand::Tar(args) => {
                                     return command::pkg::export::tar::start(ui, &args.args).await;
@@ -349,6 +367,13 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
             match m.subcommand() {
                 ("apply", Some(m)) => sub_svc_set(m).await?,
                 ("show", Some(m)) => sub_svc_config(m).await?,
+                ("render", Some(m)) => sub_config_render(ui, m).await?,
+                _ => unreachable!(),
+            }
+        }
+        ("depot", Some(m)) => {
+            match m.subcommand() {
+                ("serve", Some(m)) => sub_depot_serve(ui, m)?,
                 _ => unreachable!(),
             }
         }
@@ -437,9 +462,11 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                 ("dependencies", Some(m)) => sub_pkg_dependencies(m)?,
                 ("download", Some(m)) => sub_pkg_download(ui, m, feature_flags).await?,
                 ("env", Some(m)) => sub_pkg_env(m)?,
+                ("gc", Some(m)) => sub_pkg_gc(ui, m).await?,
                 ("hash", Some(m)) => sub_pkg_hash(m)?,
                 ("install", Some(m)) => sub_pkg_install(ui, m, feature_flags).await?,
                 ("list", Some(m)) => sub_pkg_list(m)?,
+                ("lock", Some(m)) => sub_pkg_lock(ui, m).await?,
                 ("path", Some(m)) => sub_pkg_path(m)?,
                 ("provides", Some(m)) => sub_pkg_provides(m)?,
                 ("search", Some(m)) => sub_pkg_search(m).await?,
@@ -449,8 +476,11 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                 ("bulkupload", Some(m)) => sub_pkg_bulkupload(ui, m).await?,
                 ("delete", Some(m)) => sub_pkg_delete(ui, m).await?,
                 ("verify", Some(m)) => sub_pkg_verify(ui, m)?,
+                ("verify-image", Some(m)) => sub_pkg_verify_image(ui, m).await?,
                 ("header", Some(m)) => sub_pkg_header(ui, m)?,
                 ("info", Some(m)) => sub_pkg_info(ui, m)?,
+                ("diff", Some(m)) => sub_pkg_diff(ui, m)?,
+                ("sbom", Some(m)) => sub_pkg_sbom(ui, m)?,
                 ("promote", Some(m)) => sub_pkg_promote(ui, m).await?,
                 ("demote", Some(m)) => sub_pkg_demote(ui, m).await?,
                 _ => unreachable!(),
@@ -855,6 +885,21 @@ async fn sub_pkg_download(ui: &mut UI,
 
     init()?;
 
+    if let Some(lockfile) = m.value_of("LOCKFILE") {
+        let lockfile = Lockfile::read(Path::new(lockfile))?;
+        command::pkg::download::start_locked(ui,
+                                             &url,
+                                             PRODUCT,
+                                             VERSION,
+                                             &lockfile,
+                                             download_dir.as_ref(),
+                                             token.as_deref(),
+                                             verify).await?;
+        if package_sets.is_empty() {
+            return Ok(());
+        }
+    }
+
     command::pkg::download::start(ui,
                                   &url,
                                   PRODUCT,
@@ -867,6 +912,31 @@ async fn sub_pkg_download(ui: &mut UI,
     Ok(())
 }
 
+async fn sub_pkg_lock(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let token = maybe_auth_token(&m);
+    let url = bldr_url_from_matches(&m)?;
+    let lockfile = Path::new(m.value_of("LOCKFILE").unwrap()); // Required via clap
+
+    let channel = channel_from_matches_or_default(m);
+    let target = target_from_matches(m)?;
+
+    let mut package_sets = vec![PackageSet { target,
+                                             channel: channel.clone(),
+                                             idents: idents_from_matches(m)? }];
+    package_sets.append(&mut idents_from_file_matches(ui, m, &channel, target)?);
+    package_sets.retain(|set| !set.idents.is_empty());
+
+    init()?;
+
+    command::pkg::lock::start(ui,
+                              &url,
+                              PRODUCT,
+                              VERSION,
+                              &package_sets,
+                              lockfile,
+                              token.as_deref()).await
+}
+
 fn sub_pkg_env(m: &ArgMatches<'_>) -> Result<()> {
     let ident = required_pkg_ident_from_input(m)?;
     command::pkg::env::start(&ident, &*FS_ROOT_PATH)
@@ -910,6 +980,20 @@ async fn sub_pkg_uninstall(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     } else {
         UninstallHookMode::default()
     };
+    // Removing older releases leaves a loaded one in place, but removing a loaded package
+    // outright must be asked for
+    let safety = if m.is_present("FORCE") {
+        UninstallSafety::StopServices
+    } else if let command::pkg::uninstall::UninstallMode::KeepLatest(_) = mode {
+        UninstallSafety::Safe
+    } else {
+        UninstallSafety::Strict
+    };
+    let dependencies_mode = if m.is_present("CASCADE") {
+        DependenciesMode::Cascade
+    } else {
+        DependenciesMode::default()
+    };
 
     command::pkg::uninstall::start(ui,
                                    &ident,
@@ -918,7 +1002,64 @@ async fn sub_pkg_uninstall(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
                                    mode,
                                    scope,
                                    &excludes,
-                                   uninstall_hook_mode).await
+                                   uninstall_hook_mode,
+                                   safety,
+                                   dependencies_mode).await
+}
+
+async fn sub_pkg_gc(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let execute_strategy = if m.is_present("DRYRUN") {
+        command::pkg::ExecutionStrategy::DryRun
+    } else {
+        command::pkg::ExecutionStrategy::Run
+    };
+    let keep = m.values_of("KEEP")
+                .unwrap_or_default()
+                .map(|i| PackageIdent::from_str(i).unwrap()) // unwrap safe as we've validated the input
+                .collect::<Vec<_>>();
+    let binlink_dir = Path::new(m.value_of("BINLINK_DIR").unwrap()); // required by clap
+    let sup_root = sup_proto::sup_root(None);
+    let roots = command::pkg::gc::Roots { keep: &keep,
+                                          binlink_dir,
+                                          sup_root: &sup_root };
+    // unwraps safe as we've validated the input
+    let max_age =
+        m.value_of("CACHE_MAX_AGE")
+         .map(|days| {
+             days.parse::<u64>()
+                 .unwrap()
+                 .checked_mul(24 * 60 * 60)
+                 .map(Duration::from_secs)
+                 .ok_or_else(|| {
+                     Error::ArgumentError(format!("--cache-max-age of {} days is too large", days))
+                 })
+         })
+         .transpose()?;
+    let max_size = m.value_of("CACHE_MAX_SIZE")
+                    .map(|mb| {
+                        mb.parse::<u64>()
+                          .unwrap()
+                          .checked_mul(1024 * 1024)
+                          .ok_or_else(|| {
+                              Error::ArgumentError(format!("--cache-max-size of {} megabytes is \
+                                                            too large",
+                                                           mb))
+                          })
+                    })
+                    .transpose()?;
+    let cache_limits = command::pkg::gc::CacheLimits { max_age, max_size };
+    let uninstall_hook_mode = if m.is_present("IGNORE_UNINSTALL_HOOK") {
+        UninstallHookMode::Ignore
+    } else {
+        UninstallHookMode::default()
+    };
+
+    command::pkg::gc::start(ui,
+                            &*FS_ROOT_PATH,
+                            &roots,
+                            cache_limits,
+                            execute_strategy,
+                            uninstall_hook_mode).await
 }
 
 async fn sub_bldr_channel_create(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
@@ -1071,7 +1212,7 @@ async fn sub_pkg_install(ui: &mut UI,
                          -> Result<()> {
     let url = bldr_url_from_matches(&m)?;
     let channel = channel_from_matches_or_default(m);
-    let install_sources = install_sources_from_matches(m)?;
+    let mut install_sources = install_sources_from_matches(m)?;
     let token = maybe_auth_token(&m);
     let install_mode =
         if feature_flags.contains(FeatureFlag::OFFLINE_INSTALL) && m.is_present("OFFLINE") {
@@ -1093,8 +1234,33 @@ async fn sub_pkg_install(ui: &mut UI,
         InstallHookMode::default()
     };
 
+    let signature_policy = match m.values_of("REQUIRED_SIGNER") {
+        Some(signers) => {
+            let required = m.value_of("REQUIRED_SIGNATURES")
+                            .map(|n| n.parse().expect("valid REQUIRED_SIGNATURES"));
+            SignaturePolicy::new(signers.map(str::to_string).collect(), required)?
+        }
+        None => SignaturePolicy::from_env()?,
+    };
+
     init()?;
 
+    if let Some(lockfile) = m.value_of("LOCKFILE") {
+        let lockfile = Lockfile::read(Path::new(lockfile))?;
+        // Placing the verified artifacts in the cache makes the installs below, and those of
+        // their dependencies, use them rather than whatever Builder would resolve today
+        let locked =
+            command::pkg::lock::cache_locked_artifacts(ui,
+                                                       &url,
+                                                       PRODUCT,
+                                                       VERSION,
+                                                       &lockfile,
+                                                       PackageTarget::active_target(),
+                                                       &cache_artifact_path(Some(&*FS_ROOT_PATH)),
+                                                       token.as_deref()).await?;
+        install_sources.extend(locked.into_iter().map(InstallSource::from));
+    }
+
     for install_source in install_sources.iter() {
         let pkg_install =
             common::command::package::install::start(ui,
@@ -1108,7 +1274,8 @@ async fn sub_pkg_install(ui: &mut UI,
                                                      token.as_deref(),
                                                      &install_mode,
                                                      &local_package_usage,
-                                                     install_hook_mode).await?;
+                                                     install_hook_mode,
+                                                     &signature_policy).await?;
 
         if let Some(dest_dir) = binlink_dest_dir_from_matches(m) {
             let force = m.is_present("FORCE");
@@ -1157,14 +1324,18 @@ fn sub_pkg_sign(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let origin = origin_param_or_env(&m)?;
 
     let src = Path::new(m.value_of("SOURCE").unwrap()); // Required via clap
-    let dst = Path::new(m.value_of("DEST").unwrap()); // Required via clap
 
     let key_cache = key_cache_from_matches(&m)?;
 
     init()?;
 
     let key = key_cache.latest_secret_origin_signing_key(&origin)?;
-    command::pkg::sign::start(ui, &key, &src, &dst)
+    if m.is_present("DETACHED") {
+        command::pkg::sign::start_detached(ui, &key, &src)
+    } else {
+        let dst = Path::new(m.value_of("DEST").unwrap()); // Required via clap
+        command::pkg::sign::start(ui, &key, &src, &dst)
+    }
 }
 
 async fn sub_pkg_bulkupload(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
@@ -1248,6 +1419,20 @@ fn sub_pkg_verify(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::pkg::verify::start(ui, &src, &key_cache)
 }
 
+async fn sub_pkg_verify_image(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let image = m.value_of("IMAGE").unwrap(); // Required via clap
+    let credentials = match (m.value_of("REGISTRY_USERNAME"), m.value_of("REGISTRY_PASSWORD")) {
+        (Some(username), Some(password)) => {
+            Some(base64::encode(&format!("{}:{}", username, password)))
+        }
+        _ => None,
+    };
+    let key_cache = key_cache_from_matches(&m)?;
+    init()?;
+
+    command::pkg::verify_image::start(ui, image, credentials, &key_cache).await
+}
+
 fn sub_pkg_header(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let src = Path::new(m.value_of("SOURCE").unwrap()); // Required via clap
     init()?;
@@ -1263,6 +1448,26 @@ fn sub_pkg_info(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     command::pkg::info::start(ui, &src, to_json)
 }
 
+fn sub_pkg_diff(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let from = m.value_of("FROM").unwrap(); // Required via clap
+    let to = m.value_of("TO").unwrap(); // Required via clap
+    let to_json = m.is_present("TO_JSON");
+    init()?;
+
+    command::pkg::diff::start(ui, from, to, &*FS_ROOT_PATH, to_json)
+}
+
+fn sub_pkg_sbom(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let ident_or_archive = m.value_of("PKG_IDENT_OR_ARTIFACT").unwrap(); // Required via clap
+    let format = m.value_of("FORMAT")
+                  .expect("required opt FORMAT")
+                  .parse::<SbomFormat>()?;
+    let output = m.value_of("OUTPUT").map(Path::new);
+    init()?;
+
+    command::pkg::sbom::start(ui, ident_or_archive, format, output, &*FS_ROOT_PATH)
+}
+
 async fn sub_pkg_promote(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
     let url = bldr_url_from_matches(&m)?;
     let channel = required_channel_from_matches(&m);
@@ -1298,8 +1503,17 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     let mut ui = ui::ui();
     let mut validate = sup_proto::ctl::SvcValidateCfg::default();
     validate.service_group = Some(service_group.clone().into());
+    let clear = m.is_present("CLEAR");
+    if clear && !m.is_present("MEMBER") && !m.is_present("LABEL") {
+        ui.fatal("--clear removes the configuration of the members given with --member or \
+                  --label, and neither was given.")?;
+        process::exit(1);
+    }
     let mut buf = Vec::with_capacity(sup_proto::butterfly::MAX_SVC_CFG_SIZE);
+    // Clearing gossips an empty configuration for the target, which replaces whatever was
+    // applied to it before.
     let cfg_len = match m.value_of("FILE") {
+        _ if clear => 0,
         Some("-") | None => io::stdin().read_to_end(&mut buf)?,
         Some(f) => {
             let mut file = File::open(f)?;
@@ -1316,7 +1530,7 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
 
     let mut set = sup_proto::ctl::SvcSetCfg::default();
     match (service_group.org(), user_param_or_env(&m)) {
-        (Some(_org), Some(username)) => {
+        (Some(_org), Some(username)) if !clear => {
             let user_key = key_cache.latest_user_secret_key(&username)?;
             let service_key = key_cache.latest_service_public_key(&service_group)?;
             ui.status(Status::Encrypting,
@@ -1332,6 +1546,12 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     }
     set.service_group = Some(service_group.into());
     set.version = Some(value_t!(m, "VERSION_NUMBER", u64).unwrap());
+    set.member_ids = m.values_of("MEMBER")
+                      .map(|v| v.map(String::from).collect())
+                      .unwrap_or_default();
+    set.labels = m.values_of("LABEL")
+                  .map(|v| v.map(String::from).collect())
+                  .unwrap_or_default();
     ui.begin(format!("Setting new configuration version {} for {}",
                      set.version
                         .as_ref()
@@ -1379,6 +1599,9 @@ async fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
 }
 
 async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
+    if m.is_present("MEMBER") || m.is_present("EXPLAIN") {
+        return sub_svc_member_config(m).await;
+    }
     let ident = required_pkg_ident_from_input(m)?;
     let cfg = config::load()?;
     let remote_sup_addr = remote_sup_from_input(m)?;
@@ -1404,6 +1627,76 @@ async fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+async fn sub_svc_member_config(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = required_pkg_ident_from_input(m)?;
+    let explain = m.is_present("EXPLAIN");
+    let cfg = config::load()?;
+    let remote_sup_addr = remote_sup_from_input(m)?;
+    let secret_key = config::ctl_secret_key(&cfg)?;
+    let mut msg = sup_proto::ctl::SvcGetMemberCfg::default();
+    msg.ident = Some(ident.into());
+    let mut response = SrvClient::request(&remote_sup_addr, &secret_key, msg).await?;
+    while let Some(message_result) = response.next().await {
+        let reply = message_result?;
+        match reply.message_id() {
+            "SvcMemberCfg" => {
+                let m = reply.parse::<sup_proto::ctl::SvcMemberCfg>()
+                             .map_err(SrvClientError::Decode)?;
+                for key in m.keys {
+                    let mut source = key.layer.unwrap_or_default();
+                    if explain {
+                        source = format!("{} ({})", source, key.origin.unwrap_or_default());
+                    }
+                    println!("{} = {}  # {}",
+                             key.key.unwrap_or_default(),
+                             key.value.unwrap_or_default(),
+                             source);
+                }
+            }
+            "NetErr" => {
+                let m = reply.parse::<sup_proto::net::NetErr>()
+                             .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(m).into());
+            }
+            _ => return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into()),
+        }
+    }
+    Ok(())
+}
+
+async fn sub_config_render(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let ident = required_pkg_ident_from_input(m)?;
+    let templates = m.values_of("TEMPLATE")
+                     .map(|v| v.map(PathBuf::from).collect::<Vec<_>>())
+                     .unwrap_or_default();
+    let cfg = config::load()?;
+    let remote_sup_addr = remote_sup_from_input(m)?;
+    let secret_key = config::ctl_secret_key(&cfg)?;
+    let mut msg = sup_proto::ctl::SvcGetRenderContext::default();
+    msg.ident = Some(ident.into());
+    let mut context = None;
+    let mut response = SrvClient::request(&remote_sup_addr, &secret_key, msg).await?;
+    while let Some(message_result) = response.next().await {
+        let reply = message_result?;
+        match reply.message_id() {
+            "SvcRenderContext" => {
+                let m = reply.parse::<sup_proto::ctl::SvcRenderContext>()
+                             .map_err(SrvClientError::Decode)?;
+                context = m.context;
+            }
+            "NetErr" => {
+                let m = reply.parse::<sup_proto::net::NetErr>()
+                             .map_err(SrvClientError::Decode)?;
+                return Err(SrvClientError::from(m).into());
+            }
+            _ => return Err(SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into()),
+        }
+    }
+    let context =
+        context.ok_or_else(|| SrvClientError::from(io::Error::from(io::ErrorKind::UnexpectedEof)))?;
+    command::config::render::start(ui, &context, &templates)
+}
+
 async fn sub_svc_load(svc_load: SvcLoad) -> Result<()> {
     let remote_sup_addr = svc_load.remote_sup.to_listen_ctl_addr();
     let msg = biome_sup_protocol::ctl::SvcLoad::try_from(svc_load)?;
@@ -1481,6 +1774,19 @@ async fn sub_svc_stop(m: &ArgMatches<'_>) -> Result<()> {
     gateway_util::send(&remote_sup_addr, msg).await
 }
 
+fn sub_depot_serve(ui: &mut UI, m: &ArgMatches<'_>) -> Result<()> {
+    let (artifact_path, key_path) = match m.value_of("DOWNLOAD_DIRECTORY") {
+        Some(dir) => (Path::new(dir).join("artifacts"), Path::new(dir).join("keys")),
+        None => (cache_artifact_path(Some(&*FS_ROOT_PATH)), CACHE_KEY_PATH.clone()),
+    };
+    let listen = value_t!(m, "LISTEN", SocketAddr).unwrap();
+    command::depot::serve::start(ui,
+                                 artifact_path,
+                                 key_path,
+                                 listen,
+                                 m.is_present("ALLOW_UPLOAD"))
+}
+
 async fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
     let cfg = config::load()?;
@@ -1818,11 +2124,10 @@ fn active_target() -> PackageTarget {
 }
 
 fn install_sources_from_matches(matches: &ArgMatches<'_>) -> Result<Vec<InstallSource>> {
-    matches
-        .values_of("PKG_IDENT_OR_ARTIFACT")
-        .unwrap() // Required via clap
-        .map(|t| t.parse().map_err(Error::from))
-        .collect()
+    match matches.values_of("PKG_IDENT_OR_ARTIFACT") {
+        Some(values) => values.map(|t| t.parse().map_err(Error::from)).collect(),
+        None => Ok(Vec::new()), // Only when installing from a lockfile
+    }
 }
 
 fn idents_from_matches(matches: &ArgMatches<'_>) -> Result<Vec<PackageIdent>> {
@@ -34,7 +34,8 @@ use biome_core::{crypto::keys::AnonymousBox,
 use percent_encoding::{percent_encode,
                        AsciiSet,
                        CONTROLS};
-use reqwest::{header::CONTENT_LENGTH,
+use reqwest::{header::{CONTENT_LENGTH,
+                       RANGE},
               Body,
               IntoUrl,
               RequestBuilder,
@@ -43,19 +44,24 @@ use std::{fs::{self,
                File},
           future::Future,
           io::{self,
-               Cursor},
+               Cursor,
+               Write},
           path::{Path,
                  PathBuf},
           string::ToString,
           time::Duration};
 use tee::TeeReader;
-use tokio::task;
+use tokio::{io::AsyncWriteExt,
+            task};
 use tokio_util::codec::{BytesCodec,
                         FramedRead};
 use url::Url;
 
 const X_FILENAME: &str = "x-filename";
 
+/// The extension given to artifacts while they are being downloaded.
+const PARTIAL_DOWNLOAD_EXTENSION: &str = "part";
+
 const DEFAULT_API_PATH: &str = "/v1";
 
 /// This constant is used by the retry_builder_api! macro and governs the number of maximum
@@ -105,10 +111,10 @@ mod json {
 
     impl From<PackageIdent> for super::PackageIdent {
         fn from(ident: PackageIdent) -> Self {
-            super::PackageIdent { origin:  ident.origin,
-                                  name:    ident.name,
-                                  version: Some(ident.version),
-                                  release: Some(ident.release), }
+            super::PackageIdent::new(ident.origin,
+                                     ident.name,
+                                     Some(ident.version),
+                                     Some(ident.release))
         }
     }
 }
@@ -200,6 +206,75 @@ impl BuilderAPIClient {
         }).await?
     }
 
+    /// Like `download`, but for a file whose name is known up front, so that an interrupted
+    /// download can be resumed. Bytes are streamed into `<file_name>.part` in `dst_path`, and a
+    /// later call picks up from the end of that file with a range request. The partial file is
+    /// only moved into place once the whole body has been received.
+    async fn download_resumable<'a>(&'a self,
+                                    rb: RequestBuilder,
+                                    dst_path: &'a Path,
+                                    file_name: &'a str,
+                                    token: Option<&'a str>,
+                                    permissions: Permissions,
+                                    mut progress: Option<Box<dyn DisplayProgress>>)
+                                    -> Result<PathBuf> {
+        fs::create_dir_all(&dst_path)?;
+        let dst_file_path = dst_path.join(file_name);
+        let partial_path = dst_path.join(format!("{}.{}", file_name, PARTIAL_DOWNLOAD_EXTENSION));
+        let offset = fs::metadata(&partial_path).map(|m| m.len()).unwrap_or(0);
+        debug!("Downloading file to path: {} (resuming from byte {})",
+               dst_file_path.display(),
+               offset);
+
+        let resp = match rb.try_clone() {
+            Some(ranged) if offset > 0 => {
+                let ranged = ranged.header(RANGE, format!("bytes={}-", offset));
+                let resp = self.maybe_add_authz(ranged, token).send().await?;
+                if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+                    // The partial file does not belong to what is being served, so start over
+                    debug!("Discarding partial download {}", partial_path.display());
+                    fs::remove_file(&partial_path)?;
+                    self.maybe_add_authz(rb, token).send().await?
+                } else {
+                    resp
+                }
+            }
+            _ => self.maybe_add_authz(rb, token).send().await?,
+        };
+        let mut resp = response::ok_if(resp, &[StatusCode::OK, StatusCode::PARTIAL_CONTENT]).await?;
+
+        // A server which does not support ranges sends the whole file again
+        let resumed = resp.status() == StatusCode::PARTIAL_CONTENT;
+        let mut file = tokio::fs::OpenOptions::new().create(true)
+                                                    .write(true)
+                                                    .append(resumed)
+                                                    .truncate(!resumed)
+                                                    .open(&partial_path)
+                                                    .await?;
+        // There will be no CONTENT_LENGTH header if an on prem
+        // builder is using chunked transfer encoding
+        if let (Some(progress), Ok(content_length)) =
+            (progress.as_mut(), response::get_header(&resp, CONTENT_LENGTH))
+        {
+            progress.size(content_length.parse().map_err(Error::ParseIntError)?);
+        }
+        while let Some(chunk) = resp.chunk().await? {
+            file.write_all(&chunk).await?;
+            if let Some(progress) = progress.as_mut() {
+                progress.write_all(&chunk)?;
+            }
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        task::spawn_blocking(move || {
+            let w = AtomicWriter::new_with_permissions(&dst_file_path, permissions)?;
+            w.with_writer(|mut f| io::copy(&mut File::open(&partial_path)?, &mut f))?;
+            fs::remove_file(&partial_path)?;
+            Ok(dst_file_path)
+        }).await?
+    }
+
     async fn upload_body(src_path: &Path,
                          progress: Option<Box<dyn DisplayProgress>>)
                          -> Result<Body> {
@@ -248,6 +323,54 @@ impl BuilderAPIClient {
         }
     }
 
+    /// Finds the latest release of a package in a channel whose version satisfies the version
+    /// constraint of the given ident by paging through every release in the channel.
+    async fn latest_release_in_constraint(&self,
+                                          (package, target): (&PackageIdent, PackageTarget),
+                                          channel: &ChannelIdent,
+                                          token: Option<&str>)
+                                          -> Result<PackageIdent> {
+        debug!("Resolving version constraint of {} in channel {}",
+               package, channel);
+        let releases_path =
+            channel_package_path(channel,
+                                 &PackageIdent::new(package.origin(), package.name(), None, None));
+        let mut latest: Option<PackageIdent> = None;
+        let mut range = 0;
+        loop {
+            let req = self.0.get_with_custom_url(&releases_path, |url| {
+                                url.set_query(Some(&format!("range={}&target={}", range, target)));
+                            });
+            let resp = self.maybe_add_authz(req, token).send().await?;
+            let status = resp.status();
+            debug!("Response Status: {:?}", status);
+            if status != StatusCode::OK && status != StatusCode::PARTIAL_CONTENT {
+                return Err(response::err_from_response(resp).await);
+            }
+
+            let encoded = resp.text().await.map_err(Error::BadResponseBody)?;
+            trace!(target: "biome_http_client::api_client::latest_release_in_constraint",
+                   "{:?}",
+                   encoded);
+            let results: PackageResults<PackageIdent> = serde_json::from_str(&encoded)?;
+            let more_to_come = status == StatusCode::PARTIAL_CONTENT && !results.data.is_empty();
+            range += results.data.len();
+            for release in results.data.into_iter().filter(|r| r.satisfies(package)) {
+                if latest.as_ref().map_or(true, |l| release > *l) {
+                    latest = Some(release);
+                }
+            }
+            if !more_to_come {
+                break;
+            }
+        }
+        latest.ok_or_else(|| {
+                  Error::APIError(StatusCode::NOT_FOUND,
+                                  format!("No release in the '{}' channel satisfies {}",
+                                          channel, package))
+              })
+    }
+
     async fn search_package_impl<'a, F>(&'a self,
                                         search_term: &'a str,
                                         limit: usize,
@@ -1001,11 +1124,13 @@ impl BuilderAPIClient {
         let req_builder = self.0.get_with_custom_url(&package_download(ident), |u| {
                                     u.set_query(Some(&format!("target={}", target)))
                                 });
-        let path = self.download(req_builder,
-                                 dst_path.as_ref(),
-                                 token,
-                                 DEFAULT_CACHED_ARTIFACT_PERMISSIONS,
-                                 progress)
+        let file_name = ident.archive_name_with_target(target)?;
+        let path = self.download_resumable(req_builder,
+                                           dst_path.as_ref(),
+                                           &file_name,
+                                           token,
+                                           DEFAULT_CACHED_ARTIFACT_PERMISSIONS,
+                                           progress)
                        .await?;
         Ok(PackageArchive::new(path)?)
     }
@@ -1066,7 +1191,8 @@ impl BuilderAPIClient {
     /// Returns a package struct for the latest package.
     ///
     /// An optional version can be specified which will scope the release returned to the latest
-    /// release of that package.
+    /// release of that package. A version constraint may be given instead, as in
+    /// `core/openssl@>=1.1,<3`, scoping the release to the latest one within the constraint.
     ///
     /// # Failures
     ///
@@ -1080,6 +1206,14 @@ impl BuilderAPIClient {
         debug!("Retrieving package metadata for {}, target {}",
                package, target);
 
+        let resolved;
+        let package = if package.constraint.is_some() {
+            resolved = self.latest_release_in_constraint((package, target), channel, token)
+                           .await?;
+            &resolved
+        } else {
+            package
+        };
         let mut url = channel_package_path(channel, package);
 
         if !package.fully_qualified() {
@@ -1531,6 +1665,13 @@ mod tests {
     use super::*;
     use futures::future::{self,
                           Ready};
+    use std::{io::{BufRead,
+                   BufReader},
+              net::TcpListener,
+              sync::{Arc,
+                     Mutex},
+              thread};
+    use tempfile::TempDir;
 
     #[test]
     fn json_round_trip_u64_fields() {
@@ -1563,10 +1704,7 @@ mod tests {
     }
 
     fn get_test_ident(name: &str) -> PackageIdent {
-        PackageIdent { origin:  String::from("test"),
-                       name:    String::from(name),
-                       version: None,
-                       release: None, }
+        PackageIdent::new("test", name, None, None)
     }
 
     fn seach_generator<'a>(
@@ -1661,4 +1799,131 @@ mod tests {
                       .expect("valid search");
         assert_eq!(r.1, count);
     }
+
+    /// Records the size and progress reported to a progress bar.
+    #[derive(Clone, Default)]
+    struct RecordedProgress(Arc<Mutex<(u64, u64)>>);
+
+    impl DisplayProgress for RecordedProgress {
+        fn size(&mut self, size: u64) { self.0.lock().unwrap().0 = size; }
+
+        fn finish(&mut self) {}
+    }
+
+    impl Write for RecordedProgress {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().1 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    /// Serves `body` on a local listener, honoring `Range: bytes=N-` requests, and records the
+    /// offset each request asked for.
+    fn serve(body: Vec<u8>) -> (String, Arc<Mutex<Vec<Option<usize>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut offset = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    let header = line.trim().to_lowercase();
+                    if header.starts_with("range: bytes=") {
+                        offset = header["range: bytes=".len()..].trim_end_matches('-')
+                                                                .parse()
+                                                                .ok();
+                    }
+                    line.clear();
+                }
+                recorded.lock().unwrap().push(offset);
+
+                let (status, content) = match offset {
+                    Some(offset) if offset >= body.len() => {
+                        ("416 Range Not Satisfiable".to_string(), &body[..0])
+                    }
+                    Some(offset) => {
+                        (format!("206 Partial Content\r\nContent-Range: bytes {}-{}/{}",
+                                 offset,
+                                 body.len() - 1,
+                                 body.len()),
+                         &body[offset..])
+                    }
+                    None => ("200 OK".to_string(), &body[..]),
+                };
+                write!(stream,
+                       "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                       status,
+                       content.len()).unwrap();
+                stream.write_all(content).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn body() -> Vec<u8> { (0..10_000u32).map(|i| (i % 251) as u8).collect() }
+
+    /// Fetch the test package from `url` into `dst`, returning the downloaded bytes.
+    async fn fetch(url: &str, dst: &Path, progress: Option<RecordedProgress>) -> Vec<u8> {
+        let client = BuilderAPIClient::new(url, "test", "0.0.0", None).unwrap();
+        let ident: PackageIdent = "core/possums/8.1.4/20160427165340".parse().unwrap();
+        let archive =
+            client.fetch_package((&ident, PackageTarget::active_target()),
+                                 None,
+                                 dst,
+                                 progress.map(|p| Box::new(p) as Box<dyn DisplayProgress>))
+                  .await
+                  .unwrap();
+        fs::read(archive.path).unwrap()
+    }
+
+    fn partial_path(dst: &Path) -> PathBuf {
+        let ident: PackageIdent = "core/possums/8.1.4/20160427165340".parse().unwrap();
+        let file_name = ident.archive_name_with_target(PackageTarget::active_target())
+                             .unwrap();
+        dst.join(format!("{}.{}", file_name, PARTIAL_DOWNLOAD_EXTENSION))
+    }
+
+    #[tokio::test]
+    async fn fetch_package_resumes_a_partial_download() {
+        let body = body();
+        let (url, requests) = serve(body.clone());
+        let dst = TempDir::new().unwrap();
+        fs::write(partial_path(dst.path()), &body[..4000]).unwrap();
+        let recorded = RecordedProgress::default();
+
+        assert_eq!(fetch(&url, dst.path(), Some(recorded.clone())).await, body);
+        assert_eq!(*requests.lock().unwrap(), vec![Some(4000)]);
+        assert!(!partial_path(dst.path()).exists());
+        // Only what was left to download is reported
+        assert_eq!(*recorded.0.lock().unwrap(), (6000, 6000));
+    }
+
+    #[tokio::test]
+    async fn fetch_package_downloads_everything_without_a_partial_download() {
+        let body = body();
+        let (url, requests) = serve(body.clone());
+        let dst = TempDir::new().unwrap();
+
+        assert_eq!(fetch(&url, dst.path(), None).await, body);
+        assert_eq!(*requests.lock().unwrap(), vec![None]);
+        assert!(!partial_path(dst.path()).exists());
+    }
+
+    #[tokio::test]
+    async fn fetch_package_discards_an_unsatisfiable_partial_download() {
+        let body = body();
+        let (url, requests) = serve(body.clone());
+        let dst = TempDir::new().unwrap();
+        fs::write(partial_path(dst.path()), vec![0; 20_000]).unwrap();
+
+        assert_eq!(fetch(&url, dst.path(), None).await, body);
+        assert_eq!(*requests.lock().unwrap(), vec![Some(20_000), None]);
+        assert!(!partial_path(dst.path()).exists());
+    }
 }
@@ -142,10 +142,10 @@ mod json {
 
     impl From<PackageIdent> for super::PackageIdent {
         fn from(ident: PackageIdent) -> Self {
-            super::PackageIdent { origin:  ident.origin,
-                                  name:    ident.name,
-                                  version: Some(ident.version),
-                                  release: Some(ident.release), }
+            super::PackageIdent::new(ident.origin,
+                                     ident.name,
+                                     Some(ident.version),
+                                     Some(ident.release))
         }
     }
 }
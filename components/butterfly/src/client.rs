@@ -6,7 +6,8 @@ use crate::{error::{Error,
                     Result},
             message,
             rumor::{departure::Departure,
-                    service_config::ServiceConfig,
+                    service_config::{ConfigTarget,
+                                     ServiceConfig},
                     service_file::ServiceFile,
                     Rumor},
             ZMQ_CONTEXT};
@@ -53,7 +54,24 @@ impl Client {
                                config: &[u8],
                                encrypted: bool)
                                -> Result<()> {
-        let mut sc = ServiceConfig::new("butterflyclient", service_group, config.to_vec());
+        self.send_targeted_service_config(service_group,
+                                          ConfigTarget::default(),
+                                          incarnation,
+                                          config,
+                                          encrypted)
+    }
+
+    /// Create a service configuration for a subset of a service group's members and send it to
+    /// the server.
+    pub fn send_targeted_service_config(&mut self,
+                                        service_group: ServiceGroup,
+                                        target: ConfigTarget,
+                                        incarnation: u64,
+                                        config: &[u8],
+                                        encrypted: bool)
+                                        -> Result<()> {
+        let mut sc = ServiceConfig::new("butterflyclient", service_group, config.to_vec())
+            .with_target(target);
         sc.incarnation = incarnation;
         sc.encrypted = encrypted;
         self.send(&sc)
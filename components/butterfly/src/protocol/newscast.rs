@@ -5,7 +5,8 @@ use crate::rumor::{departure::Departure as CDeparture,
                               ElectionUpdate as CElectionUpdate},
                    service::Service as CService,
                    service_config::ServiceConfig as CServiceConfig,
-                   service_file::ServiceFile as CServiceFile};
+                   service_file::ServiceFile as CServiceFile,
+                   Rumor as _};
 
 include!(concat!(env!("OUT_DIR"), "/butterfly.newscast.rs"));
 
@@ -24,6 +25,7 @@ impl fmt::Display for RumorType {
             RumorType::Fake2 => "fake2",
             RumorType::ElectionUpdate => "election-update",
             RumorType::Departure => "departure",
+            RumorType::TargetedServiceConfig => "targeted-service-config",
         };
 
         write!(f, "{}", value)
@@ -88,13 +90,12 @@ impl From<CService> for Rumor {
 
 impl From<CServiceConfig> for Rumor {
     fn from(value: CServiceConfig) -> Self {
-        let payload = ServiceConfig { service_group: Some(value.service_group.to_string()),
-                                      incarnation:   Some(value.incarnation),
-                                      encrypted:     Some(value.encrypted),
-                                      config:        Some(value.config), };
-        Rumor { r#type:  RumorType::ServiceConfig as i32,
+        let from_id = value.from_id.clone();
+        let r#type = value.kind();
+        let payload = ServiceConfig::from(value);
+        Rumor { r#type:  r#type as i32,
                 tag:     Vec::default(),
-                from_id: Some(value.from_id),
+                from_id: Some(from_id),
                 payload: Some(RumorPayload::ServiceConfig(payload)), }
     }
 }
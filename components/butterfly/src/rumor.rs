@@ -490,7 +490,9 @@ impl RumorEnvelope {
             }
             RumorType::Member => RumorKind::Membership(Membership::from_proto(proto)?),
             RumorType::Service => RumorKind::Service(Box::new(Service::from_proto(proto)?)),
-            RumorType::ServiceConfig => RumorKind::ServiceConfig(ServiceConfig::from_proto(proto)?),
+            RumorType::ServiceConfig | RumorType::TargetedServiceConfig => {
+                RumorKind::ServiceConfig(ServiceConfig::from_proto(proto)?)
+            }
             RumorType::ServiceFile => RumorKind::ServiceFile(ServiceFile::from_proto(proto)?),
             RumorType::Fake | RumorType::Fake2 => panic!("fake rumor"),
         };
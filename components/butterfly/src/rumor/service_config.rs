@@ -1,6 +1,7 @@
 //! The ServiceConfig rumor.
 //!
-//! Holds the toml configuration injected for a service.
+//! Holds the toml configuration injected for a service, optionally targeted at a subset of the
+//! service group's members.
 
 use crate::{error::{Error,
                     Result},
@@ -8,10 +9,10 @@ use crate::{error::{Error,
                        newscast::{self,
                                   Rumor as ProtoRumor},
                        FromProto},
-            rumor::{ConstIdRumor,
-                    Rumor,
+            rumor::{Rumor,
                     RumorPayload,
                     RumorType}};
+use biome_common::types::MemberLabel;
 use biome_core::{crypto::keys::{KeyCache,
                                   SignedBox},
                    service::ServiceGroup};
@@ -22,6 +23,58 @@ use std::{borrow::Cow,
           str::{self,
                 FromStr}};
 
+/// The members of a service group a configuration applies to. An empty target applies to the
+/// whole group.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigTarget {
+    member_ids: Vec<String>,
+    labels:     Vec<MemberLabel>,
+}
+
+impl ConfigTarget {
+    pub fn new(mut member_ids: Vec<String>, mut labels: Vec<MemberLabel>) -> Self {
+        // Sorted so that the same target always produces the same rumor id
+        member_ids.sort();
+        member_ids.dedup();
+        labels.sort();
+        labels.dedup();
+        ConfigTarget { member_ids, labels }
+    }
+
+    pub fn member_ids(&self) -> &[String] { &self.member_ids }
+
+    pub fn labels(&self) -> &[MemberLabel] { &self.labels }
+
+    /// Whether this target applies to every member of the service group.
+    pub fn is_group(&self) -> bool { self.member_ids.is_empty() && self.labels.is_empty() }
+
+    /// Whether this target lists the member explicitly by id.
+    pub fn names_member(&self, member_id: &str) -> bool {
+        self.member_ids.iter().any(|id| id == member_id)
+    }
+
+    /// A member is targeted if it is listed by id, or if it carries every targeted label.
+    pub fn matches(&self, member_id: &str, member_labels: &[MemberLabel]) -> bool {
+        self.is_group()
+        || self.names_member(member_id)
+        || (!self.labels.is_empty() && self.labels.iter().all(|l| member_labels.contains(l)))
+    }
+}
+
+impl fmt::Display for ConfigTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_group() {
+            return write!(f, "group");
+        }
+        let parts = self.member_ids
+                        .iter()
+                        .map(|id| format!("member:{}", id))
+                        .chain(self.labels.iter().map(|l| format!("label:{}", l)))
+                        .collect::<Vec<_>>();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ServiceConfig {
     pub from_id:       String,
@@ -29,19 +82,22 @@ pub struct ServiceConfig {
     pub incarnation:   u64,
     pub encrypted:     bool,
     pub config:        Vec<u8>, // TODO: make this a String
+    target:            ConfigTarget,
+    #[serde(skip)]
+    id:                String,
 }
 
 impl fmt::Display for ServiceConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f,
-               "ServiceConfig i/{} m/{} sg/{}",
-               self.incarnation, self.from_id, self.service_group)
+               "ServiceConfig i/{} m/{} sg/{} t/{}",
+               self.incarnation, self.from_id, self.service_group, self.target)
     }
 }
 
 impl PartialOrd for ServiceConfig {
     fn partial_cmp(&self, other: &ServiceConfig) -> Option<Ordering> {
-        if self.service_group != other.service_group {
+        if self.service_group != other.service_group || self.target != other.target {
             None
         } else {
             Some(self.incarnation.cmp(&other.incarnation))
@@ -55,10 +111,14 @@ impl PartialEq for ServiceConfig {
         && self.incarnation == other.incarnation
         && self.encrypted == other.encrypted
         && self.config == other.config
+        && self.target == other.target
     }
 }
 
 impl ServiceConfig {
+    /// The rumor id of the configuration that applies to a whole service group.
+    pub const GROUP_ID: &'static str = "service_config";
+
     /// Creates a new ServiceConfig.
     pub fn new<S1>(member_id: S1, service_group: ServiceGroup, config: Vec<u8>) -> Self
         where S1: Into<String>
@@ -67,9 +127,25 @@ impl ServiceConfig {
                         service_group,
                         incarnation: 0,
                         encrypted: false,
-                        config }
+                        config,
+                        target: ConfigTarget::default(),
+                        id: Self::GROUP_ID.to_string() }
+    }
+
+    /// Restricts the configuration to the given members. Each distinct target is gossiped as its
+    /// own rumor, so targeted configurations never replace the group-wide one.
+    pub fn with_target(mut self, target: ConfigTarget) -> Self {
+        self.id = if target.is_group() {
+            Self::GROUP_ID.to_string()
+        } else {
+            format!("{}/{}", Self::GROUP_ID, target)
+        };
+        self.target = target;
+        self
     }
 
+    pub fn target(&self) -> &ConfigTarget { &self.target }
+
     pub fn config(&self, key_cache: &KeyCache) -> Result<toml::value::Table> {
         let bytes = if self.encrypted {
             let secret = SignedBox::from_bytes(&self.config)?;
@@ -101,30 +177,52 @@ impl protocol::Message<ProtoRumor> for ServiceConfig {
 
 impl FromProto<ProtoRumor> for ServiceConfig {
     fn from_proto(rumor: ProtoRumor) -> Result<Self> {
+        let targeted = rumor.r#type == RumorType::TargetedServiceConfig as i32;
         let payload = match rumor.payload.ok_or(Error::ProtocolMismatch("payload"))? {
             RumorPayload::ServiceConfig(payload) => payload,
             _ => panic!("from-bytes service-config"),
         };
-        Ok(ServiceConfig { from_id:       rumor.from_id
-                                               .ok_or(Error::ProtocolMismatch("from-id"))?,
-                           service_group:
-                               payload.service_group
-                                      .ok_or(Error::ProtocolMismatch("service-group"))
-                                      .and_then(|s| {
-                                          ServiceGroup::from_str(&s).map_err(Error::from)
-                                      })?,
-                           incarnation:   payload.incarnation.unwrap_or(0),
-                           encrypted:     payload.encrypted.unwrap_or(false),
-                           config:        payload.config.unwrap_or_default(), })
+        let target_labels = payload.target_labels
+                                   .iter()
+                                   .map(|l| MemberLabel::from_str(l))
+                                   .collect::<std::result::Result<Vec<_>, _>>()
+                                   .map_err(|_| Error::ProtocolMismatch("target-labels"))?;
+        let service_config =
+            ServiceConfig { from_id:       rumor.from_id
+                                                .ok_or(Error::ProtocolMismatch("from-id"))?,
+                            service_group:
+                                payload.service_group
+                                       .ok_or(Error::ProtocolMismatch("service-group"))
+                                       .and_then(|s| {
+                                           ServiceGroup::from_str(&s).map_err(Error::from)
+                                       })?,
+                            incarnation:   payload.incarnation.unwrap_or(0),
+                            encrypted:     payload.encrypted.unwrap_or(false),
+                            config:        payload.config.unwrap_or_default(),
+                            target:        ConfigTarget::default(),
+                            id:            Self::GROUP_ID.to_string(), };
+        let target = ConfigTarget::new(payload.target_member_ids, target_labels);
+        // The rumor type must agree with the target, or a targeted config could be taken for the
+        // group-wide one.
+        if target.is_group() == targeted {
+            return Err(Error::ProtocolMismatch("target"));
+        }
+        Ok(service_config.with_target(target))
     }
 }
 
 impl From<ServiceConfig> for newscast::ServiceConfig {
     fn from(value: ServiceConfig) -> Self {
-        newscast::ServiceConfig { service_group: Some(value.service_group.to_string()),
-                                  incarnation:   Some(value.incarnation),
-                                  encrypted:     Some(value.encrypted),
-                                  config:        Some(value.config), }
+        newscast::ServiceConfig { service_group:     Some(value.service_group.to_string()),
+                                  incarnation:       Some(value.incarnation),
+                                  encrypted:         Some(value.encrypted),
+                                  config:            Some(value.config),
+                                  target_member_ids: value.target.member_ids,
+                                  target_labels:     value.target
+                                                          .labels
+                                                          .iter()
+                                                          .map(ToString::to_string)
+                                                          .collect(), }
     }
 }
 
@@ -140,22 +238,25 @@ impl Rumor for ServiceConfig {
         }
     }
 
-    fn kind(&self) -> RumorType { RumorType::ServiceConfig }
+    fn kind(&self) -> RumorType {
+        if self.target.is_group() {
+            RumorType::ServiceConfig
+        } else {
+            RumorType::TargetedServiceConfig
+        }
+    }
 
-    fn id(&self) -> &str { Self::const_id() }
+    fn id(&self) -> &str { &self.id }
 
     fn key(&self) -> &str { &self.service_group }
 }
 
-impl ConstIdRumor for ServiceConfig {
-    fn const_id() -> &'static str { "service_config" }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::rumor::{Rumor,
-                       RumorStore};
+    use crate::{protocol::Message,
+                rumor::{Rumor,
+                        RumorStore}};
     use biome_core::service::ServiceGroup;
     use std::{cmp::Ordering,
               str::FromStr};
@@ -184,7 +285,7 @@ mod tests {
         let sub_list = list.get("neurosis.production").unwrap();
         assert_eq!(sub_list.len(), 1); // because only the latest service config is kept
 
-        let sc = sub_list.get(ServiceConfig::const_id()).unwrap();
+        let sc = sub_list.get(ServiceConfig::GROUP_ID).unwrap();
         assert_eq!(sc.config, Vec::<u8>::from("awesome"));
     }
 
@@ -250,6 +351,76 @@ mod tests {
         assert_eq!(s1, s1_check);
     }
 
+    #[test]
+    fn targeted_service_configs_are_kept_alongside_the_group_config() {
+        let rs = create_rumor_store();
+        let mut s1 = create_service_config("timmeh", "lol");
+        s1.incarnation = 2;
+        let target =
+            ConfigTarget::new(vec!["member-b".to_string(), "member-a".to_string()], vec![]);
+        let s2 = create_service_config("timmeh", "awesome").with_target(target);
+        assert_eq!(s2.id(), "service_config/member:member-a,member:member-b");
+        rs.insert_rsw(s1);
+        rs.insert_rsw(s2);
+
+        let list = rs.lock_rsr();
+        let sub_list = list.get("neurosis.production").unwrap();
+        assert_eq!(sub_list.len(), 2);
+        assert_eq!(sub_list.get(ServiceConfig::GROUP_ID).unwrap().config,
+                   Vec::<u8>::from("lol"));
+    }
+
+    #[test]
+    fn config_target_matches_members_by_id_or_labels() {
+        let rack: MemberLabel = "rack=r1".parse().unwrap();
+        let zone: MemberLabel = "zone=a".parse().unwrap();
+
+        assert!(ConfigTarget::default().matches("anyone", &[]));
+
+        let by_id = ConfigTarget::new(vec!["m1".to_string()], vec![]);
+        assert!(by_id.matches("m1", &[]));
+        assert!(!by_id.matches("m2", &[rack.clone()]));
+
+        let by_labels = ConfigTarget::new(vec![], vec![rack.clone(), zone.clone()]);
+        assert!(by_labels.matches("m2", &[zone.clone(), rack.clone()]));
+        assert!(!by_labels.matches("m2", &[rack]));
+    }
+
+    #[test]
+    fn service_configs_with_different_targets_are_not_comparable() {
+        let s1 = create_service_config("adam", "yep");
+        let target = ConfigTarget::new(vec!["m1".to_string()], vec![]);
+        let s2 = create_service_config("adam", "yep").with_target(target);
+        assert_eq!(s1.partial_cmp(&s2), None);
+    }
+
+    #[test]
+    fn targeted_service_configs_have_their_own_rumor_type() {
+        let s1 = create_service_config("adam", "yep");
+        let target = ConfigTarget::new(vec!["m1".to_string()], vec![]);
+        let s2 = create_service_config("adam", "yep").with_target(target);
+        assert_eq!(s1.kind(), RumorType::ServiceConfig);
+        assert_eq!(s2.kind(), RumorType::TargetedServiceConfig);
+
+        let s2_check = ServiceConfig::from_bytes(&s2.write_to_bytes().unwrap()).unwrap();
+        assert_eq!(s2_check.kind(), RumorType::TargetedServiceConfig);
+        assert_eq!(s2_check.id(), s2.id());
+        assert_eq!(s2_check, s2);
+    }
+
+    #[test]
+    fn service_config_rumor_type_must_agree_with_its_target() {
+        let target = ConfigTarget::new(vec!["m1".to_string()], vec![]);
+        let s1 = create_service_config("adam", "yep").with_target(target);
+        let mut proto = ProtoRumor::from(s1);
+        proto.r#type = RumorType::ServiceConfig as i32;
+        assert!(ServiceConfig::from_proto(proto).is_err());
+
+        let mut proto = ProtoRumor::from(create_service_config("adam", "yep"));
+        proto.r#type = RumorType::TargetedServiceConfig as i32;
+        assert!(ServiceConfig::from_proto(proto).is_err());
+    }
+
     #[test]
     fn config_comes_back_as_a_toml_value() {
         let s1 = create_service_config("adam", "yep=1");
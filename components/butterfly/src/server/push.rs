@@ -204,7 +204,7 @@ fn send_rumors_rsr_mlr_rhw(server: &Server, member: &Member, rumors: &[RumorKey]
                     }
                 }
             }
-            RumorType::ServiceConfig => {
+            RumorType::ServiceConfig | RumorType::TargetedServiceConfig => {
                 match server.service_config_store
                             .lock_rsr()
                             .encode_rumor_for(&rumor_key)
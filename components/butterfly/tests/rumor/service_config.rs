@@ -1,7 +1,6 @@
 use crate::btest;
 use biome_butterfly::{client::Client,
-                        rumor::{ConstIdRumor as _,
-                                ServiceConfig}};
+                        rumor::ServiceConfig};
 use biome_core::service::ServiceGroup;
 
 #[test]
@@ -13,7 +12,7 @@ fn two_members_share_service_config() {
     assert!(net[1].service_config_store
                   .lock_rsr()
                   .service_group("witcher.prod")
-                  .contains_id(ServiceConfig::const_id()));
+                  .contains_id(ServiceConfig::GROUP_ID));
 }
 
 #[test]
@@ -35,5 +34,5 @@ fn service_config_via_client() {
     assert!(net[1].service_config_store
                   .lock_rsr()
                   .service_group("witcher.prod")
-                  .contains_id(ServiceConfig::const_id()));
+                  .contains_id(ServiceConfig::GROUP_ID));
 }
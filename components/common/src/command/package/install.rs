@@ -16,7 +16,7 @@
 //!
 //! # Internals
 //!
-//! * Download the artifact
+//! * Download the artifact, and any of its dependencies which are not cached, several at a time
 //! * Verify it is un-altered
 //! * Unpack it
 
@@ -25,6 +25,7 @@ use crate::{api_client::{self,
                          APIFailure,
                          BuilderAPIClient,
                          Client,
+                         DisplayProgress,
                          Error::{APIClientError,
                                  APIError},
                          API_RETRY_COUNT,
@@ -33,8 +34,11 @@ use crate::{api_client::{self,
                     Result},
             templating::hooks::{InstallHook,
                                 PackageMaintenanceHookExt},
-            ui::{Status,
+            ui::{AggregateProgressBar,
+                 Status,
                  UIWriter}};
+use futures::stream::{self,
+                      StreamExt};
 use biome_core::{self,
                    crypto::{artifact,
                             keys::{Key,
@@ -70,6 +74,12 @@ use std::{convert::TryFrom,
 pub const RETRIES: usize = 5;
 pub const RETRY_WAIT: Duration = Duration::from_millis(3000);
 
+// The number of a package's dependencies which are downloaded at once when installing it.
+biome_core::env_config_int!(ArtifactDownloadConcurrency,
+                              usize,
+                              HAB_ARTIFACT_DOWNLOAD_CONCURRENCY,
+                              4);
+
 /// Represents a locally-available `.hart` file for package
 /// installation purposes only.
 ///
@@ -275,6 +285,107 @@ impl Default for LocalPackageUsage {
     fn default() -> Self { LocalPackageUsage::Prefer }
 }
 
+/// Environment variable naming, comma-separated, the origin keys whose
+/// signatures count toward the install signature policy
+pub const REQUIRED_SIGNERS_ENVVAR: &str = "HAB_REQUIRED_SIGNERS";
+/// Environment variable setting how many of the required signers must
+/// have signed an artifact before it is installed
+pub const REQUIRED_SIGNATURES_ENVVAR: &str = "HAB_REQUIRED_SIGNATURES";
+
+/// Governs which signers must have signed an artifact before it is
+/// installed.
+///
+/// An artifact is signed by the origin key named in its header and by
+/// the key of each detached signature in the `.sig` file kept next to
+/// it. The policy is satisfied when at least `required` of `signers`
+/// have verifiably signed the artifact, and applies to every artifact
+/// installed, dependencies included. The default policy names no
+/// signers, so only the header signature is checked.
+///
+/// Builder does not serve detached signatures, so an artifact
+/// downloaded from it only carries its header signature. The `.sig`
+/// file of an artifact installed from a local file is copied into the
+/// artifact cache along with it; for a downloaded artifact, the `.sig`
+/// file must be put next to the artifact in the cache before it is
+/// installed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SignaturePolicy {
+    signers:  Vec<String>,
+    required: usize,
+}
+
+impl SignaturePolicy {
+    /// Require signatures from `required` of the named origin keys, or
+    /// from all of them when `required` is not given. An origin named
+    /// more than once counts once.
+    pub fn new(mut signers: Vec<String>, required: Option<usize>) -> Result<Self> {
+        signers.sort();
+        signers.dedup();
+        let required = required.unwrap_or_else(|| signers.len());
+        if required > signers.len() {
+            return Err(Error::InvalidSignaturePolicy(format!("{} signatures \
+                                                              required but only \
+                                                              {} signers given",
+                                                             required,
+                                                             signers.len())));
+        }
+        if required == 0 && !signers.is_empty() {
+            return Err(Error::InvalidSignaturePolicy("at least one signature must be \
+                                                      required"
+                                                               .to_string()));
+        }
+        Ok(SignaturePolicy { signers, required })
+    }
+
+    /// The policy set by `HAB_REQUIRED_SIGNERS` and
+    /// `HAB_REQUIRED_SIGNATURES`, or the default policy if neither is
+    /// set.
+    pub fn from_env() -> Result<Self> {
+        let signers = biome_core::env::var(REQUIRED_SIGNERS_ENVVAR).unwrap_or_default()
+                                                                   .split(',')
+                                                                   .map(str::trim)
+                                                                   .filter(|s| !s.is_empty())
+                                                                   .map(str::to_string)
+                                                                   .collect();
+        let required = match biome_core::env::var(REQUIRED_SIGNATURES_ENVVAR) {
+            Ok(required) => {
+                Some(required.trim()
+                             .parse()
+                             .map_err(|_| invalid_count(&required))?)
+            }
+            Err(_) => None,
+        };
+        Self::new(signers, required)
+    }
+
+    fn is_empty(&self) -> bool { self.signers.is_empty() }
+
+    /// Whether a signature by the named origin key counts toward the
+    /// policy.
+    fn trusts(&self, signer: &str) -> bool { self.signers.iter().any(|s| s == signer) }
+
+    /// Check the names of the keys that verifiably signed an artifact
+    /// against the policy.
+    fn check(&self, ident: &FullyQualifiedPackageIdent, verified: &[&String]) -> Result<()> {
+        let found = self.signers
+                        .iter()
+                        .filter(|signer| verified.contains(signer))
+                        .count();
+        if found >= self.required {
+            Ok(())
+        } else {
+            Err(Error::SignaturePolicyNotSatisfied(ident.as_ref().clone(),
+                                                   found,
+                                                   self.required))
+        }
+    }
+}
+
+fn invalid_count(required: &str) -> Error {
+    Error::InvalidSignaturePolicy(format!("{} must be a number, got '{}'",
+                                          REQUIRED_SIGNATURES_ENVVAR, required))
+}
+
 /// Install a Biome package.
 ///
 /// If an `InstallSource::Ident` is given, we retrieve the package
@@ -308,9 +419,47 @@ pub async fn start<U>(ui: &mut U,
                       token: Option<&str>,
                       install_mode: &InstallMode,
                       local_package_usage: &LocalPackageUsage,
-                      install_hook_mode: InstallHookMode)
+                      install_hook_mode: InstallHookMode,
+                      signature_policy: &SignaturePolicy)
                       -> Result<PackageInstall>
     where U: UIWriter
+{
+    start_with_new_packages(ui,
+                            url,
+                            channel,
+                            install_source,
+                            product,
+                            version,
+                            fs_root_path,
+                            artifact_cache_path,
+                            token,
+                            install_mode,
+                            local_package_usage,
+                            install_hook_mode,
+                            signature_policy).await
+                                             .map(|(package, _)| package)
+}
+
+/// `start`, also returning the packages which were newly installed, dependencies first. Packages
+/// which were already installed, including any installed by another process meanwhile, are left
+/// out.
+#[allow(clippy::too_many_arguments)]
+pub async fn start_with_new_packages<U>(
+    ui: &mut U,
+    url: &str,
+    channel: &ChannelIdent,
+    install_source: &InstallSource,
+    product: &str,
+    version: &str,
+    fs_root_path: &Path,
+    artifact_cache_path: &Path,
+    token: Option<&str>,
+    install_mode: &InstallMode,
+    local_package_usage: &LocalPackageUsage,
+    install_hook_mode: InstallHookMode,
+    signature_policy: &SignaturePolicy)
+    -> Result<(PackageInstall, Vec<FullyQualifiedPackageIdent>)>
+    where U: UIWriter
 {
     let key_cache = KeyCache::new(cache_key_path(fs_root_path));
     key_cache.setup()?;
@@ -324,7 +473,8 @@ pub async fn start<U>(ui: &mut U,
                              fs_root_path,
                              artifact_cache_path,
                              key_cache,
-                             install_hook_mode };
+                             install_hook_mode,
+                             signature_policy };
 
     match *install_source {
         InstallSource::Ident(ref ident, target) => {
@@ -350,7 +500,8 @@ pub fn type_erased_start<'a, U>(
     token: Option<&'a str>,
     install_mode: &'a InstallMode,
     local_package_usage: &'a LocalPackageUsage,
-    install_hook_mode: InstallHookMode)
+    install_hook_mode: InstallHookMode,
+    signature_policy: &'a SignaturePolicy)
     -> Pin<Box<dyn std::future::Future<Output = Result<PackageInstall>> + Send + 'a>>
     where U: UIWriter + Send + Sync
 {
@@ -365,7 +516,8 @@ pub fn type_erased_start<'a, U>(
                    token,
                    install_mode,
                    local_package_usage,
-                   install_hook_mode))
+                   install_hook_mode,
+                   signature_policy))
 }
 
 pub async fn check_install_hooks<T, P>(ui: &mut T,
@@ -428,6 +580,7 @@ struct InstallTask<'a> {
     artifact_cache_path: &'a Path,
     key_cache:           KeyCache,
     install_hook_mode:   InstallHookMode,
+    signature_policy:    &'a SignaturePolicy,
 }
 
 impl<'a> InstallTask<'a> {
@@ -448,7 +601,7 @@ impl<'a> InstallTask<'a> {
                            ui: &mut T,
                            (ident, target): (PackageIdent, PackageTarget),
                            token: Option<&str>)
-                           -> Result<PackageInstall>
+                           -> Result<(PackageInstall, Vec<FullyQualifiedPackageIdent>)>
         where T: UIWriter
     {
         ui.begin(format!("Installing {}", &ident))?;
@@ -464,7 +617,7 @@ impl<'a> InstallTask<'a> {
                 }
                 ui.end(format!("Install of {} complete with {} new packages installed.",
                                &target_ident, 0))?;
-                Ok(package_install)
+                Ok((package_install, Vec::new()))
             }
             None => {
                 // No installed package was found
@@ -480,7 +633,7 @@ impl<'a> InstallTask<'a> {
                              ui: &mut T,
                              local_archive: &LocalArchive,
                              token: Option<&str>)
-                             -> Result<PackageInstall>
+                             -> Result<(PackageInstall, Vec<FullyQualifiedPackageIdent>)>
         where T: UIWriter
     {
         ui.begin(format!("Installing {}", local_archive.path.display()))?;
@@ -494,7 +647,7 @@ impl<'a> InstallTask<'a> {
                 }
                 ui.end(format!("Install of {} complete with {} new packages installed.",
                                &target_ident, 0))?;
-                Ok(package_install)
+                Ok((package_install, Vec::new()))
             }
             None => {
                 // No installed package was found
@@ -602,11 +755,14 @@ impl<'a> InstallTask<'a> {
     /// If the package is already present in the cache, it is not
     /// re-downloaded. Any dependencies of the package that are not
     /// installed will be re-cached (as needed) and installed.
+    ///
+    /// Returns the package along with the packages which were newly
+    /// installed.
     async fn install_package<T>(&self,
                                 ui: &mut T,
                                 (ident, target): (&FullyQualifiedPackageIdent, PackageTarget),
                                 token: Option<&str>)
-                                -> Result<PackageInstall>
+                                -> Result<(PackageInstall, Vec<FullyQualifiedPackageIdent>)>
         where T: UIWriter
     {
         // TODO (CM): rename artifact to archive
@@ -615,31 +771,35 @@ impl<'a> InstallTask<'a> {
         // Ensure that all transitive dependencies, as well as the
         // original package itself, are cached locally.
         let dependencies = artifact.tdeps()?;
-        let mut artifacts_to_install = Vec::with_capacity(dependencies.len() + 1);
-        // TODO fn: I'd prefer this list to be a `Vec<FullyQualifiedPackageIdent>` but that
-        // requires a conversion that could fail (i.e. returns a `Result<...>`). Should be
-        // possible though.
+        let mut uninstalled = Vec::with_capacity(dependencies.len());
         for dependency in dependencies.iter() {
-            if self.installed_package(&FullyQualifiedPackageIdent::try_from(dependency)?)
-                   .is_some()
-            {
-                ui.status(Status::Using, dependency)?;
+            let dependency = FullyQualifiedPackageIdent::try_from(dependency)?;
+            if self.installed_package(&dependency).is_some() {
+                ui.status(Status::Using, &dependency)?;
             } else {
-                artifacts_to_install.push(self.get_cached_artifact(
-                    ui,
-                    (&FullyQualifiedPackageIdent::try_from(dependency)?, target),
-                    token,
-                ).await?);
+                uninstalled.push(dependency);
             }
         }
+        // Downloads happen concurrently, but verification and unpacking happen in dependency
+        // order below.
+        self.fetch_artifacts(ui, &uninstalled, target, token)
+            .await?;
+        let mut artifacts_to_install = Vec::with_capacity(uninstalled.len() + 1);
+        for dependency in uninstalled.iter() {
+            artifacts_to_install.push(self.get_cached_artifact(ui, (dependency, target), token)
+                                          .await?);
+        }
         // The package we're actually trying to install goes last; we
         // want to ensure that its dependencies get installed before
         // it does.
         artifacts_to_install.push(artifact);
 
         // Ensure all uninstalled artifacts get installed
+        let mut new_packages = Vec::with_capacity(artifacts_to_install.len());
         for artifact in artifacts_to_install.iter_mut() {
-            self.unpack_artifact(ui, artifact)?;
+            if self.unpack_artifact(ui, artifact)? {
+                new_packages.push(FullyQualifiedPackageIdent::try_from(artifact.ident()?)?);
+            }
         }
 
         if self.install_hook_mode != InstallHookMode::Ignore {
@@ -653,7 +813,8 @@ impl<'a> InstallTask<'a> {
                        artifacts_to_install.len()))?;
 
         // Return the thing we just installed
-        PackageInstall::load(ident.as_ref(), Some(self.fs_root_path)).map_err(Error::from)
+        let package = PackageInstall::load(ident.as_ref(), Some(self.fs_root_path))?;
+        Ok((package, new_packages))
     }
 
     /// This ensures the identified package is in the local cache,
@@ -682,7 +843,10 @@ impl<'a> InstallTask<'a> {
     }
 
     /// Adapter function wrapping `PackageArchive::unpack`
-    fn unpack_artifact<T>(&self, ui: &mut T, artifact: &mut PackageArchive) -> Result<()>
+    ///
+    /// Returns whether the package was installed, rather than found to
+    /// have been installed by someone else meanwhile.
+    fn unpack_artifact<T>(&self, ui: &mut T, artifact: &mut PackageArchive) -> Result<bool>
         where T: UIWriter
     {
         let ident = &artifact.ident()?;
@@ -704,6 +868,8 @@ impl<'a> InstallTask<'a> {
                     if PackageInstall::load(ident, Some(self.fs_root_path)).is_err() {
                         return Err(Error::from(e));
                     }
+                    ui.status(Status::Using, ident)?;
+                    return Ok(false);
                 }
 
                 if cfg!(unix) {
@@ -711,7 +877,7 @@ impl<'a> InstallTask<'a> {
                 }
 
                 ui.status(Status::Installed, ident)?;
-                Ok(())
+                Ok(true)
             }
             None => unreachable!("Install path doesn't have a parent"),
         }
@@ -784,7 +950,7 @@ impl<'a> InstallTask<'a> {
                 continue;
             }
             let artifact_ident = artifact_ident.unwrap();
-            if artifact_ident.origin == ident.origin && artifact_ident.name == ident.name {
+            if artifact_ident.satisfies(ident) {
                 if latest.is_empty() {
                     latest.push((artifact_ident, artifact));
                 } else if artifact_ident > latest[0].0 {
@@ -863,6 +1029,88 @@ impl<'a> InstallTask<'a> {
         Ok(())
     }
 
+    /// Download those of the identified packages which are not already cached, several at a
+    /// time, onto a single progress bar.
+    ///
+    /// Every download is from the same Builder, so the number in flight is bounded by
+    /// `ArtifactDownloadConcurrency` to stay within its per-host connection limits. An
+    /// interrupted download is resumed from where it left off the next time it is fetched, and an
+    /// artifact which then fails verification is removed so that it is downloaded from scratch.
+    async fn fetch_artifacts<T>(&self,
+                                ui: &mut T,
+                                idents: &[FullyQualifiedPackageIdent],
+                                target: PackageTarget,
+                                token: Option<&str>)
+                                -> Result<()>
+        where T: UIWriter
+    {
+        let missing: Vec<_> = idents.iter()
+                                    .filter(|ident| !self.is_artifact_cached(ident))
+                                    .collect();
+        // Offline, `get_cached_artifact` reports what is missing
+        if missing.is_empty() || self.is_offline() {
+            return Ok(());
+        }
+
+        let concurrency = usize::from(ArtifactDownloadConcurrency::configured_value()).max(1);
+        ui.status(Status::Downloading,
+                  format!("{} artifacts for {}, {} at a time",
+                          missing.len(),
+                          target,
+                          concurrency.min(missing.len())))?;
+        let progress = ui.progress().map(AggregateProgressBar::new);
+        self.download_artifacts(&missing, target, token, progress, concurrency)
+            .await
+    }
+
+    /// Download the identified packages into the cache, `concurrency` at a time, each reporting
+    /// to its own child of `progress`.
+    async fn download_artifacts(&self,
+                                idents: &[&FullyQualifiedPackageIdent],
+                                target: PackageTarget,
+                                token: Option<&str>,
+                                progress: Option<AggregateProgressBar>,
+                                concurrency: usize)
+                                -> Result<()> {
+        let mut downloads = stream::iter(idents.iter().copied()).map(|ident| {
+                                let progress = progress.as_ref().map(AggregateProgressBar::child);
+                                self.download_artifact((ident, target), token, progress)
+                            })
+                            .buffer_unordered(concurrency);
+        while let Some(ident) = downloads.next().await {
+            debug!("Downloaded {}", ident?);
+        }
+        Ok(())
+    }
+
+    /// Download a single artifact into the cache, reporting to a shared progress bar. Every
+    /// retry reports to the same child of the progress bar.
+    async fn download_artifact<'b>(&self,
+                                   (ident, target): (&'b FullyQualifiedPackageIdent,
+                                    PackageTarget),
+                                   token: Option<&str>,
+                                   progress: Option<AggregateProgressBar>)
+                                   -> Result<&'b FullyQualifiedPackageIdent> {
+        retry_builder_api!(async {
+            let progress = progress.clone()
+                                   .map(|p| Box::new(p) as Box<dyn DisplayProgress>);
+            self.api_client
+                .fetch_package((ident.as_ref(), target),
+                               token,
+                               self.artifact_cache_path,
+                               progress)
+                .await
+        }).await
+          .map_err(|e| {
+              APIClientError(APIFailure::DownloadPackageFailed(API_RETRY_COUNT,
+                                                               PackageIdent::from(ident.clone()),
+                                                               target,
+                                                               Box::new(e)))
+          })?;
+
+        Ok(ident)
+    }
+
     async fn fetch_origin_key<T>(&self,
                                  ui: &mut T,
                                  named_revision: &NamedRevision,
@@ -926,6 +1174,12 @@ impl<'a> InstallTask<'a> {
                  let mut f = File::open(artifact_path)?;
                  io::copy(&mut f, &mut w)
              })?;
+
+            // Detached signatures travel with the artifact so they can be checked on install
+            let sig_path = artifact::detached_signature_path(&artifact_path);
+            if sig_path.is_file() {
+                fs::copy(&sig_path, artifact::detached_signature_path(&cache_path))?;
+            }
         }
         Ok(())
     }
@@ -965,10 +1219,62 @@ impl<'a> InstallTask<'a> {
             self.fetch_origin_key(ui, &named_revision, token).await?;
         };
 
-        artifact::verify(&artifact.path, &self.key_cache)?;
+        if let Err(e) = artifact::verify(&artifact.path, &self.key_cache) {
+            // A download resumed onto bytes of a different artifact fails here, and would fail
+            // the same way on every later attempt if it were left in the cache
+            debug!("Removing {} from the artifact cache, err: {}",
+                   artifact.path.display(),
+                   e);
+            if let Err(e) = fs::remove_file(&artifact.path) {
+                warn!("Unable to remove {}, err: {}", artifact.path.display(), e);
+            }
+            return Err(e.into());
+        }
 
         debug!("Verified {} signed by {}", ident, named_revision);
-        Ok(())
+        self.check_signature_policy(ui, ident, token, artifact, &named_revision)
+            .await
+    }
+
+    /// Verify those detached signatures of the artifact made by signers
+    /// the policy names, and ensure enough of them, counting the header
+    /// signature, are present.
+    async fn check_signature_policy<T>(&self,
+                                       ui: &mut T,
+                                       ident: &FullyQualifiedPackageIdent,
+                                       token: Option<&str>,
+                                       artifact: &PackageArchive,
+                                       header_signer: &NamedRevision)
+                                       -> Result<()>
+        where T: UIWriter
+    {
+        if self.signature_policy.is_empty() {
+            return Ok(());
+        }
+
+        let sig_path = artifact::detached_signature_path(&artifact.path);
+        if !sig_path.is_file() {
+            ui.warn(format!("No detached signatures of {} found at {}. Builder does not serve \
+                             detached signatures, so they must be copied into the artifact \
+                             cache.",
+                            ident,
+                            sig_path.display()))?;
+        }
+        let signatures = artifact::detached_signatures(&artifact.path)?;
+        let mut verified = vec![header_signer.name()];
+        for signature in signatures.iter()
+                                   .filter(|s| self.signature_policy.trusts(s.signer().name()))
+        {
+            let signer = signature.signer();
+            if self.key_cache.public_signing_key(signer).is_err() {
+                self.fetch_origin_key(ui, signer, token).await?;
+            }
+            artifact::verify_detached(&artifact.path, signature, &self.key_cache)?;
+            debug!("Verified detached signature of {} by {}", ident, signer);
+            verified.push(signer.name());
+        }
+
+        self.signature_policy.check(ident, &verified)
     }
 
     fn is_offline(&self) -> bool { self.install_mode == &InstallMode::Offline }
@@ -1044,3 +1350,281 @@ impl<'a> InstallTask<'a> {
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{io::Write,
+              net::TcpListener,
+              sync::{Arc,
+                     Mutex},
+              thread};
+    use tempfile::TempDir;
+
+    fn ident() -> FullyQualifiedPackageIdent {
+        FullyQualifiedPackageIdent::from_str("core/redis/5.0.7/20200101000000").unwrap()
+    }
+
+    #[test]
+    fn signature_policy_requires_all_signers_by_default() {
+        let core = "core".to_string();
+        let release = "release".to_string();
+        let policy = SignaturePolicy::new(vec![core.clone(), release.clone()], None).unwrap();
+
+        assert!(policy.check(&ident(), &[&core, &release]).is_ok());
+        assert!(policy.check(&ident(), &[&core]).is_err());
+    }
+
+    #[test]
+    fn signature_policy_accepts_n_of_m_signers() {
+        let core = "core".to_string();
+        let release = "release".to_string();
+        let other = "other".to_string();
+        let policy = SignaturePolicy::new(vec![core.clone(), release.clone(), "audit".to_string()],
+                                          Some(2)).unwrap();
+
+        assert!(policy.check(&ident(), &[&core, &release]).is_ok());
+        assert!(policy.check(&ident(), &[&core, &other]).is_err());
+        assert!(policy.trusts("audit"));
+        assert!(!policy.trusts("other"));
+    }
+
+    #[test]
+    fn signature_policy_counts_repeated_signers_once() {
+        let core = "core".to_string();
+        let release = "release".to_string();
+
+        assert!(SignaturePolicy::new(vec![core.clone(), core.clone()], Some(2)).is_err());
+        let policy =
+            SignaturePolicy::new(vec![core.clone(), release.clone(), core.clone()], None).unwrap();
+        assert!(policy.check(&ident(), &[&core]).is_err());
+        assert!(policy.check(&ident(), &[&core, &release]).is_ok());
+    }
+
+    #[test]
+    fn signature_policy_rejects_impossible_counts() {
+        assert!(SignaturePolicy::new(vec!["core".to_string()], Some(2)).is_err());
+        assert!(SignaturePolicy::new(vec!["core".to_string()], Some(0)).is_err());
+        assert!(SignaturePolicy::new(vec![], None).unwrap().is_empty());
+    }
+
+    /// Records the size and progress reported to a progress bar.
+    #[derive(Clone, Default)]
+    struct RecordedProgress(Arc<Mutex<(u64, u64)>>);
+
+    impl DisplayProgress for RecordedProgress {
+        fn size(&mut self, size: u64) { self.0.lock().unwrap().0 = size; }
+
+        fn finish(&mut self) {}
+    }
+
+    impl Write for RecordedProgress {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().1 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    /// Answers every request on a local listener with `body` after a delay, recording the most
+    /// requests that were in flight at once.
+    fn serve(body: Vec<u8>) -> (String, Arc<Mutex<(usize, usize)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let in_flight = Arc::new(Mutex::new((0, 0)));
+        let counts = Arc::clone(&in_flight);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let body = body.clone();
+                let counts = Arc::clone(&counts);
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap() > 2 {
+                        line.clear();
+                    }
+                    {
+                        let mut counts = counts.lock().unwrap();
+                        counts.0 += 1;
+                        counts.1 = counts.1.max(counts.0);
+                    }
+                    thread::sleep(Duration::from_millis(200));
+                    counts.lock().unwrap().0 -= 1;
+                    write!(stream,
+                           "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                           body.len()).unwrap();
+                    stream.write_all(&body).unwrap();
+                });
+            }
+        });
+        (url, in_flight)
+    }
+
+    #[tokio::test]
+    async fn download_artifacts_bounds_concurrency_and_shares_progress() {
+        let body = vec![7; 4096];
+        let (url, in_flight) = serve(body.clone());
+        let fs_root = TempDir::new().unwrap();
+        let artifact_cache_path = fs_root.path().join("artifacts");
+        let install_mode = InstallMode::default();
+        let local_package_usage = LocalPackageUsage::default();
+        let channel = ChannelIdent::stable();
+        let signature_policy = SignaturePolicy::default();
+        let task = InstallTask { install_mode:        &install_mode,
+                                 local_package_usage: &local_package_usage,
+                                 api_client:          BuilderAPIClient::new(url.as_str(),
+                                                                            "test",
+                                                                            "0.0.0",
+                                                                            None).unwrap(),
+                                 channel:             &channel,
+                                 fs_root_path:        fs_root.path(),
+                                 artifact_cache_path: &artifact_cache_path,
+                                 key_cache:           KeyCache::new(fs_root.path().join("keys")),
+                                 install_hook_mode:   InstallHookMode::default(),
+                                 signature_policy:    &signature_policy, };
+        let idents = (0..6).map(|i| {
+                               FullyQualifiedPackageIdent::from_str(&format!("core/pkg-{}/1.0.0/\
+                                                                              20200101000000",
+                                                                             i)).unwrap()
+                           })
+                           .collect::<Vec<_>>();
+        let recorded = RecordedProgress::default();
+
+        task.download_artifacts(&idents.iter().collect::<Vec<_>>(),
+                                PackageTarget::active_target(),
+                                None,
+                                Some(AggregateProgressBar::new(Box::new(recorded.clone()))),
+                                3)
+            .await
+            .unwrap();
+
+        for ident in &idents {
+            assert!(task.is_artifact_cached(ident), "{} should be cached", ident);
+        }
+        let max_in_flight = in_flight.lock().unwrap().1;
+        assert!(max_in_flight > 1 && max_in_flight <= 3,
+                "{} downloads were in flight at once",
+                max_in_flight);
+        let (size, written) = *recorded.0.lock().unwrap();
+        assert_eq!(size, 6 * body.len() as u64);
+        assert_eq!(written, 6 * body.len() as u64);
+    }
+
+    #[test]
+    fn aggregate_progress_bar_replaces_the_size_of_each_child() {
+        let recorded = RecordedProgress::default();
+        let progress = AggregateProgressBar::new(Box::new(recorded.clone()));
+        let mut first = progress.child();
+        let mut second = progress.child();
+
+        first.size(100);
+        second.size(50);
+        assert_eq!(recorded.0.lock().unwrap().0, 150);
+
+        // A retried operation reports its size again through a clone, having already written
+        // some of it
+        first.write_all(&[0; 40]).unwrap();
+        first.clone().size(60);
+        assert_eq!(recorded.0.lock().unwrap().0, 150);
+
+        second.write_all(&[0; 50]).unwrap();
+        first.write_all(&[0; 60]).unwrap();
+        assert_eq!(*recorded.0.lock().unwrap(), (150, 150));
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[tokio::test]
+    async fn signature_policy_needs_detached_signatures_copied_into_the_cache() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures");
+        let artifact = "happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart";
+        let (url, _) = serve(fs::read(fixtures.join(artifact)).unwrap());
+        let fs_root = TempDir::new().unwrap();
+        let artifact_cache_path = fs_root.path().join("artifacts");
+        let key_path = fs_root.path().join("keys");
+        let key_cache = KeyCache::new(&key_path);
+        key_cache.setup().unwrap();
+        fs::copy(fixtures.join("happyhumans-20160424223347.pub"),
+                 key_path.join("happyhumans-20160424223347.pub")).unwrap();
+        let (_, release_key) = key_cache.new_signing_pair("release").unwrap();
+        let install_mode = InstallMode::default();
+        let local_package_usage = LocalPackageUsage::default();
+        let channel = ChannelIdent::stable();
+        let signature_policy = SignaturePolicy::new(vec!["happyhumans".to_string(),
+                                                         "release".to_string()],
+                                                    None).unwrap();
+        let task = InstallTask { install_mode:        &install_mode,
+                                 local_package_usage: &local_package_usage,
+                                 api_client:          BuilderAPIClient::new(url.as_str(),
+                                                                            "test",
+                                                                            "0.0.0",
+                                                                            None).unwrap(),
+                                 channel:             &channel,
+                                 fs_root_path:        fs_root.path(),
+                                 artifact_cache_path: &artifact_cache_path,
+                                 key_cache:           KeyCache::new(&key_path),
+                                 install_hook_mode:   InstallHookMode::default(),
+                                 signature_policy:    &signature_policy, };
+        let ident = FullyQualifiedPackageIdent::from_str("happyhumans/possums/8.1.4/\
+                                                          20160427165340").unwrap();
+        let mut ui = crate::ui::NullUi::new();
+
+        // Only the header signature comes with the artifact from Builder
+        match task.get_cached_artifact(&mut ui, (&ident, PackageTarget::active_target()), None)
+                  .await
+        {
+            Err(Error::SignaturePolicyNotSatisfied(_, found, required)) => {
+                assert_eq!((found, required), (1, 2))
+            }
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(_) => panic!("The signature policy should not be satisfied"),
+        }
+        assert!(task.is_artifact_cached(&ident));
+
+        artifact::sign_detached(task.cached_artifact_path(&ident), &release_key).unwrap();
+        assert!(task.get_cached_artifact(&mut ui, (&ident, PackageTarget::active_target()), None)
+                    .await
+                    .is_ok());
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[tokio::test]
+    async fn start_with_new_packages_reports_only_what_it_installed() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures");
+        let artifact = "happyhumans-possums-8.1.4-20160427165340-x86_64-linux.hart";
+        let install_source: InstallSource =
+            fixtures.join(artifact).to_string_lossy().parse().unwrap();
+        let fs_root = TempDir::new().unwrap();
+        let key_path = cache_key_path(fs_root.path());
+        fs::create_dir_all(&key_path).unwrap();
+        fs::copy(fixtures.join("happyhumans-20160424223347.pub"),
+                 key_path.join("happyhumans-20160424223347.pub")).unwrap();
+        let artifact_cache_path = fs_root.path().join("artifacts");
+        let mut ui = crate::ui::NullUi::new();
+
+        for expected in &[vec!["happyhumans/possums/8.1.4/20160427165340"], vec![]] {
+            let (package, new_packages) =
+                start_with_new_packages(&mut ui,
+                                        "http://127.0.0.1:1",
+                                        &ChannelIdent::stable(),
+                                        &install_source,
+                                        "test",
+                                        "0.0.0",
+                                        fs_root.path(),
+                                        &artifact_cache_path,
+                                        None,
+                                        &InstallMode::default(),
+                                        &LocalPackageUsage::default(),
+                                        InstallHookMode::Ignore,
+                                        &SignaturePolicy::default()).await
+                                                                    .unwrap();
+            assert_eq!(package.ident().to_string(),
+                       "happyhumans/possums/8.1.4/20160427165340");
+            assert_eq!(new_packages.iter()
+                                   .map(ToString::to_string)
+                                   .collect::<Vec<_>>(),
+                       *expected);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{api_client,
             hcore::{self,
                     package::{FullyQualifiedPackageIdent,
-                              PackageIdent}}};
+                              PackageIdent}},
+            http_client};
 #[cfg(windows)]
 use biome_core::os::process::windows_child::ExitStatus;
 #[cfg(not(windows))]
@@ -14,7 +15,8 @@ use std::{env,
           path::PathBuf,
           result,
           str,
-          string};
+          string,
+          time::Duration};
 
 pub const DEFAULT_ERROR_EXIT_CODE: i32 = 1;
 
@@ -62,6 +64,7 @@ pub enum Error {
     /// Occurs when there is no valid toml of json in the environment variable
     BadEnvConfig(String),
     BadGlyphStyle(String),
+    BadSbomFormat(String),
     CantUploadGossipToml,
     ChannelNotFound,
     CryptoKeyError(String),
@@ -72,12 +75,18 @@ pub enum Error {
     FileNotFound(String),
     GossipFileRelativePath(String),
     BiomeCore(hcore::Error),
+    BiomeHttpClient(http_client::Error),
     HookFailed {
         package_ident: FullyQualifiedPackageIdent,
         hook:          &'static str,
         error:         CommandExecutionError,
     },
+    /// Occurs when a hook runs longer than its configured timeout and is killed.
+    HookTimedOut(&'static str, Duration),
+    InvalidEventSink(String),
     InvalidEventStreamToken(String),
+    InvalidImageReference(String),
+    InvalidSignaturePolicy(String),
     /// Occurs when making lower level IO calls.
     IO(io::Error),
     /// Errors when joining paths :)
@@ -93,10 +102,16 @@ pub enum Error {
     PackageNotFound(String),
     /// Occurs upon errors related to file or directory permissions.
     PermissionFailed(String),
+    ProvenanceInvalid(String),
+    ProvenanceNotFound(String),
     /// When an error occurs serializing rendering context
+    RegistryRequest(String),
     RenderContextSerialization(serde_json::Error),
     RemoteSupResolutionError(String, io::Error),
+    ReqwestError(reqwest::Error),
     RootRequired,
+    /// Occurs when an artifact lacks the detached signatures required to install it.
+    SignaturePolicyNotSatisfied(PackageIdent, usize, usize),
     StatusFileCorrupt(PathBuf),
     StrFromUtf8Error(str::Utf8Error),
     StringFromUtf8Error(string::FromUtf8Error),
@@ -151,6 +166,7 @@ impl fmt::Display for Error {
                 format!("Unable to find valid TOML or JSON in {} ENVVAR", varname)
             }
             Error::BadGlyphStyle(ref style) => format!("Unknown symbol style '{}'", style),
+            Error::BadSbomFormat(ref format) => format!("Unknown SBOM format '{}'", format),
             Error::CantUploadGossipToml => {
                 "Can't upload gossip.toml, it's a reserved file name".to_string()
             }
@@ -165,6 +181,7 @@ impl fmt::Display for Error {
                         s)
             }
             Error::BiomeCore(ref e) => format!("{}", e),
+            Error::BiomeHttpClient(ref e) => format!("{}", e),
             Error::MissingCLIInputError(ref arg) => {
                 format!("Missing required CLI argument!: {}", arg)
             }
@@ -173,9 +190,21 @@ impl fmt::Display for Error {
                                 ref error, } => {
                 format!("{} {} hook failed: {}", package_ident, hook, error)
             }
+            Error::HookTimedOut(ref hook, ref timeout) => {
+                format!("{} hook did not finish within {}s and was killed",
+                        hook,
+                        timeout.as_secs())
+            }
+            Error::InvalidEventSink(ref s) => {
+                format!("Invalid event sink '{}' (must be 'file:PATH', 'unix:PATH', an http:// \
+                         or https:// URL, 'syslog', or 'syslog:IP:PORT')",
+                        s)
+            }
             Error::InvalidEventStreamToken(ref s) => {
                 format!("Invalid event stream token provided: '{}'", s)
             }
+            Error::InvalidImageReference(ref s) => format!("Invalid image reference: '{}'", s),
+            Error::InvalidSignaturePolicy(ref e) => format!("Invalid signature policy: {}", e),
             Error::IO(ref err) => format!("{}", err),
             Error::JoinPathsError(ref err) => format!("{}", err),
             Error::NamedPipeTimeoutOnStart(ref group, ref hook, ref err) => {
@@ -201,6 +230,11 @@ impl fmt::Display for Error {
             }
             Error::PackageNotFound(ref e) => format!("Package not found. {}", e),
             Error::PermissionFailed(ref e) => e.to_string(),
+            Error::ProvenanceInvalid(ref e) => format!("Invalid provenance attestation: {}", e),
+            Error::ProvenanceNotFound(ref image) => {
+                format!("No provenance attestation is attached to {}", image)
+            }
+            Error::RegistryRequest(ref e) => format!("Container registry request failed: {}", e),
             Error::RenderContextSerialization(ref e) => {
                 format!("Unable to serialize rendering context, {}", e)
             }
@@ -208,9 +242,14 @@ impl fmt::Display for Error {
                 format!("Failed to resolve remote supervisor '{}': {}",
                         sup_addr, err,)
             }
+            Error::ReqwestError(ref err) => format!("{}", err),
             Error::RootRequired => {
                 "Root or administrator permissions required to complete operation".to_string()
             }
+            Error::SignaturePolicyNotSatisfied(ref ident, found, required) => {
+                format!("{} has {} of the {} signatures required by the signature policy",
+                        ident, found, required)
+            }
             Error::StatusFileCorrupt(ref path) => {
                 format!("Unable to decode contents of INSTALL_STATUS file, {}",
                         path.display())
@@ -251,6 +290,10 @@ impl From<hcore::Error> for Error {
     fn from(err: hcore::Error) -> Self { Error::BiomeCore(err) }
 }
 
+impl From<http_client::Error> for Error {
+    fn from(err: http_client::Error) -> Self { Error::BiomeHttpClient(err) }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self { Error::IO(err) }
 }
@@ -275,6 +318,10 @@ impl From<net::AddrParseError> for Error {
     fn from(err: net::AddrParseError) -> Self { Error::NetParseError(err) }
 }
 
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self { Error::ReqwestError(err) }
+}
+
 impl From<native_tls::Error> for Error {
     fn from(error: native_tls::Error) -> Self { Error::NativeTls(error) }
 }
@@ -2,6 +2,7 @@ use crate::ui::{NullUi,
                 UIWriter};
 use biome_api_client as api_client;
 use biome_core as hcore;
+use biome_http_client as http_client;
 use lazy_static::lazy_static;
 use std::{collections::HashMap,
           env,
@@ -31,6 +32,9 @@ pub mod liveliness_checker;
 pub mod output;
 pub mod owning_refs;
 pub mod package_graph;
+pub mod preload;
+pub mod provenance;
+pub mod sbom;
 pub mod templating;
 pub mod types;
 pub mod ui;
@@ -0,0 +1,338 @@
+//! Configuration which exporters bake into the file systems they export, so that the Supervisor in
+//! an exported image or tarball starts its service as intended with nothing more given at
+//! runtime.
+//!
+//! Three things can be preloaded:
+//!
+//! * A service spec, as `bio svc load` would write, with the service's group, topology, update
+//!   strategy and binds. The Supervisor loads every spec in its specs directory when it starts.
+//! * The service's `user.toml`.
+//! * Public origin signing keys, for verifying packages the Supervisor installs when updating.
+//!
+//! Secret keys, including ring keys, are never preloaded; those are for the deployer to provide.
+
+use crate::{error::{Error,
+                    Result},
+            hcore::{crypto::keys::{Key,
+                                   KeyCache,
+                                   PublicOriginSigningKey},
+                    fs::{CACHE_KEY_PATH_POSTFIX,
+                         USER_CONFIG_FILE},
+                    package::PackageIdent,
+                    service::ServiceBind,
+                    ChannelIdent},
+            ui::{Status,
+                 UIWriter}};
+use clap::{Arg,
+           ArgMatches};
+use std::{fs,
+          path::{Path,
+                 PathBuf},
+          result,
+          str::FromStr};
+
+/// Where the Supervisor looks for service specs, relative to a file system root.
+pub const SPECS_DIR: &str = "hab/sup/default/specs";
+/// Where services' user configuration lives, relative to a file system root.
+const USER_DIR: &str = "hab/user";
+
+/// The arguments which make up the preloaded service spec, for exporters with modes that run
+/// without a Supervisor to conflict with.
+pub const SPEC_ARGS: &[&str] = &["GROUP", "TOPOLOGY", "STRATEGY", "BIND"];
+
+/// The topologies a spec can give, as `bio svc load` takes them.
+const TOPOLOGIES: &[&str] = &["standalone", "leader"];
+/// The update strategies a spec can give, as `bio svc load` takes them.
+const STRATEGIES: &[&str] = &["none", "at-once", "rolling"];
+
+/// The command line arguments for everything which can be preloaded.
+pub fn args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![Arg::with_name("GROUP").long("group")
+                                .value_name("GROUP")
+                                .help("Preload a service spec which loads the service into the \
+                                       given service group"),
+         Arg::with_name("TOPOLOGY").long("topology")
+                                   .value_name("TOPOLOGY")
+                                   .possible_values(TOPOLOGIES)
+                                   .help("Preload a service spec which runs the service in the \
+                                          given topology"),
+         Arg::with_name("STRATEGY").long("strategy")
+                                   .value_name("STRATEGY")
+                                   .possible_values(STRATEGIES)
+                                   .help("Preload a service spec which updates the service with \
+                                          the given strategy from the export's channel. Without \
+                                          one, the spec loads the exported release."),
+         Arg::with_name("BIND").long("bind")
+                               .value_name("BIND")
+                               .multiple(true)
+                               .number_of_values(1)
+                               .validator(valid_bind)
+                               .help("Preload a service spec which binds the service to another \
+                                      (ex: --bind name:service.group)"),
+         Arg::with_name("USER_TOML").long("user-toml")
+                                    .value_name("USER_TOML")
+                                    .validator(valid_file)
+                                    .help("Preload the given file as the service's user.toml"),
+         Arg::with_name("ORIGIN_KEY").long("origin-key")
+                                     .value_name("ORIGIN_KEY")
+                                     .multiple(true)
+                                     .number_of_values(1)
+                                     .validator(valid_file)
+                                     .help("Preload the given public origin signing key file \
+                                            into the key cache"),]
+}
+
+/// What to preload, as given on the command line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preload {
+    pub group:       Option<String>,
+    pub topology:    Option<String>,
+    pub strategy:    Option<String>,
+    pub binds:       Vec<String>,
+    pub user_toml:   Option<PathBuf>,
+    pub origin_keys: Vec<PathBuf>,
+}
+
+impl Preload {
+    pub fn from_matches(m: &ArgMatches<'_>) -> Self {
+        Preload { group:       m.value_of("GROUP").map(str::to_string),
+                  topology:    m.value_of("TOPOLOGY").map(str::to_string),
+                  strategy:    m.value_of("STRATEGY").map(str::to_string),
+                  binds:       m.values_of("BIND")
+                                .map(|values| values.map(str::to_string).collect())
+                                .unwrap_or_default(),
+                  user_toml:   m.value_of("USER_TOML").map(PathBuf::from),
+                  origin_keys: m.values_of("ORIGIN_KEY")
+                                .map(|values| values.map(PathBuf::from).collect())
+                                .unwrap_or_default(), }
+    }
+
+    /// Whether any part of a service spec was given.
+    pub fn has_spec(&self) -> bool {
+        self.group.is_some()
+        || self.topology.is_some()
+        || self.strategy.is_some()
+        || !self.binds.is_empty()
+    }
+
+    /// Write everything given into the file system at `rootfs`, for the exported service `ident`,
+    /// which is fully qualified. A spec is only written if any part of it was given.
+    ///
+    /// # Errors
+    ///
+    /// * If any of the files cannot be read or written
+    /// * If an origin key is not a public origin signing key
+    pub fn write<U>(&self,
+                    ui: &mut U,
+                    rootfs: &Path,
+                    ident: &PackageIdent,
+                    bldr_url: &str,
+                    channel: &ChannelIdent)
+                    -> Result<()>
+        where U: UIWriter
+    {
+        if self.has_spec() {
+            self.write_spec(ui, rootfs, ident, bldr_url, channel)?;
+        }
+        self.write_user_toml(ui, rootfs, ident)?;
+        self.write_origin_keys(ui, rootfs)
+    }
+
+    /// Write the service spec for `ident` into the file system at `rootfs`, whether or not any
+    /// part of it was given.
+    ///
+    /// # Errors
+    ///
+    /// * If the spec cannot be written
+    pub fn write_spec<U>(&self,
+                         ui: &mut U,
+                         rootfs: &Path,
+                         ident: &PackageIdent,
+                         bldr_url: &str,
+                         channel: &ChannelIdent)
+                         -> Result<()>
+        where U: UIWriter
+    {
+        ui.status(Status::Creating, format!("service spec for {}", ident))?;
+        let specs_dir = rootfs.join(SPECS_DIR);
+        fs::create_dir_all(&specs_dir)?;
+        fs::write(specs_dir.join(format!("{}.spec", ident.name)),
+                  self.service_spec(ident, bldr_url, channel)?)?;
+        Ok(())
+    }
+
+    /// The spec which `bio svc load` would write for the service. The Supervisor fills in the
+    /// defaults of any fields not given here.
+    ///
+    /// The spec loads the exported release, unless an update strategy is given, in which case it
+    /// loads the service by origin and name so that the Supervisor can update it from the
+    /// channel.
+    pub fn service_spec(&self,
+                        ident: &PackageIdent,
+                        bldr_url: &str,
+                        channel: &ChannelIdent)
+                        -> Result<String> {
+        let loaded = match self.strategy.as_ref().map(String::as_str) {
+            None | Some("none") => ident.clone(),
+            Some(_) => PackageIdent::new(ident.origin.clone(), ident.name.clone(), None, None),
+        };
+        let mut spec = toml::value::Table::new();
+        spec.insert("ident".to_string(), loaded.to_string().into());
+        spec.insert("bldr_url".to_string(), bldr_url.into());
+        spec.insert("channel".to_string(), channel.to_string().into());
+        if let Some(ref group) = self.group {
+            spec.insert("group".to_string(), group.as_str().into());
+        }
+        if let Some(ref topology) = self.topology {
+            spec.insert("topology".to_string(), topology.as_str().into());
+        }
+        if let Some(ref strategy) = self.strategy {
+            spec.insert("update_strategy".to_string(), strategy.as_str().into());
+        }
+        if !self.binds.is_empty() {
+            spec.insert("binds".to_string(), self.binds.clone().into());
+        }
+        Ok(toml::to_string(&spec)?)
+    }
+
+    fn write_user_toml<U>(&self, ui: &mut U, rootfs: &Path, ident: &PackageIdent) -> Result<()>
+        where U: UIWriter
+    {
+        if let Some(ref src) = self.user_toml {
+            let dir = rootfs.join(USER_DIR).join(&ident.name).join("config");
+            ui.status(Status::Adding,
+                      format!("{} as /{}/{}/config/{}",
+                              src.display(),
+                              USER_DIR,
+                              ident.name,
+                              USER_CONFIG_FILE))?;
+            fs::create_dir_all(&dir)?;
+            fs::copy(src, dir.join(USER_CONFIG_FILE))?;
+        }
+        Ok(())
+    }
+
+    /// Origin keys are written into a key cache of the file system's own, so this must not be
+    /// called while the file system's key cache is linked to the exporting host's.
+    fn write_origin_keys<U>(&self, ui: &mut U, rootfs: &Path) -> Result<()>
+        where U: UIWriter
+    {
+        if self.origin_keys.is_empty() {
+            return Ok(());
+        }
+        let key_cache = KeyCache::new(rootfs.join(CACHE_KEY_PATH_POSTFIX));
+        key_cache.setup()?;
+        for path in &self.origin_keys {
+            let key = fs::read_to_string(path)?.parse::<PublicOriginSigningKey>()
+                                               .map_err(|_| {
+                                                   Error::CryptoKeyError(format!("{} is not a \
+                                                                                  public origin \
+                                                                                  signing key",
+                                                                                 path.display()))
+                                               })?;
+            ui.status(Status::Adding,
+                      format!("public origin key {}", key.named_revision()))?;
+            key_cache.write_key(&key)?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_bind(val: String) -> result::Result<(), String> {
+    match ServiceBind::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_file(val: String) -> result::Result<(), String> {
+    if Path::new(&val).is_file() {
+        Ok(())
+    } else {
+        Err(format!("File: '{}' not found", &val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hcore::crypto::keys::{generate_signing_key_pair,
+                                      KeyFile},
+                ui::UI};
+    use tempfile::TempDir;
+
+    fn redis() -> PackageIdent {
+        PackageIdent::from_str("core/redis/4.0.14/20190319155852").unwrap()
+    }
+
+    fn spec(preload: &Preload) -> toml::Value {
+        preload.service_spec(&redis(), "https://bldr.biome.sh", &ChannelIdent::stable())
+               .unwrap()
+               .parse()
+               .unwrap()
+    }
+
+    #[test]
+    fn a_spec_without_a_strategy_loads_the_exported_release() {
+        let spec = spec(&Preload { group: Some("cache".to_string()),
+                                   binds: vec!["db:postgres.default".to_string()],
+                                   ..Preload::default() });
+
+        assert_eq!(spec["ident"].as_str(),
+                   Some("core/redis/4.0.14/20190319155852"));
+        assert_eq!(spec["bldr_url"].as_str(), Some("https://bldr.biome.sh"));
+        assert_eq!(spec["channel"].as_str(), Some("stable"));
+        assert_eq!(spec["group"].as_str(), Some("cache"));
+        assert_eq!(spec["binds"].as_array().unwrap()[0].as_str(),
+                   Some("db:postgres.default"));
+        assert!(spec.get("topology").is_none());
+        assert!(spec.get("update_strategy").is_none());
+    }
+
+    #[test]
+    fn a_spec_with_a_strategy_loads_the_service_from_its_channel() {
+        let spec = spec(&Preload { topology: Some("leader".to_string()),
+                                   strategy: Some("rolling".to_string()),
+                                   ..Preload::default() });
+
+        assert_eq!(spec["ident"].as_str(), Some("core/redis"));
+        assert_eq!(spec["topology"].as_str(), Some("leader"));
+        assert_eq!(spec["update_strategy"].as_str(), Some("rolling"));
+    }
+
+    #[test]
+    fn user_config_and_public_keys_are_written_into_the_root() {
+        let tmp = TempDir::new().unwrap();
+        let rootfs = tmp.path().join("rootfs");
+        let user_toml = tmp.path().join("user.toml");
+        fs::write(&user_toml, "port = 6380\n").unwrap();
+        let (public, secret) = generate_signing_key_pair("acme");
+        let public_key = tmp.path().join("acme.pub");
+        fs::write(&public_key, public.to_key_string()).unwrap();
+        let preload = Preload { user_toml: Some(user_toml),
+                                origin_keys: vec![public_key],
+                                ..Preload::default() };
+
+        preload.write(&mut UI::with_sinks(),
+                      &rootfs,
+                      &redis(),
+                      "https://bldr.biome.sh",
+                      &ChannelIdent::stable())
+               .unwrap();
+
+        assert_eq!(fs::read_to_string(rootfs.join("hab/user/redis/config/user.toml")).unwrap(),
+                   "port = 6380\n");
+        assert!(KeyCache::new(rootfs.join(CACHE_KEY_PATH_POSTFIX)).path_in_cache(&public)
+                                                                  .is_file());
+        assert!(!rootfs.join(SPECS_DIR).exists());
+
+        let secret_key = tmp.path().join("acme.sig.key");
+        fs::write(&secret_key, secret.to_key_string()).unwrap();
+        let preload = Preload { origin_keys: vec![secret_key],
+                                ..Preload::default() };
+        assert!(preload.write_origin_keys(&mut UI::with_sinks(), &rootfs)
+                       .is_err());
+    }
+}
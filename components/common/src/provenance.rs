@@ -0,0 +1,446 @@
+//! Provenance attestations for container images exported from Biome packages.
+//!
+//! An attestation is an in-toto statement with a SLSA provenance predicate. Its subject is the
+//! image, named by the digest of its manifest, and it lists every Biome artifact the image was
+//! built from, with its BLAKE2b checksum and the origin key which signed it.
+//!
+//! The statement is wrapped in a DSSE envelope, signed with an origin signing key, and attached
+//! to the image as an OCI artifact whose manifest has the image's manifest as its subject. The
+//! artifact is kept alongside the image, either in an OCI image layout or in a registry.
+
+mod layout;
+mod registry;
+
+pub use self::{layout::Layout,
+               registry::{Reference,
+                          Registry}};
+use crate::{error::{Error,
+                    Result},
+            hcore::{crypto::{artifact,
+                             hash,
+                             keys::{Key,
+                                    KeyCache,
+                                    NamedRevision,
+                                    SecretOriginSigningKey}},
+                    package::PackageIdent}};
+use serde_json::{json,
+                 Value};
+use sha2::{Digest,
+           Sha256};
+use std::{path::Path,
+          str::FromStr};
+
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+const BUILD_TYPE: &str = "https://biome.sh/provenance/container-export/v1";
+/// The DSSE payload type of in-toto statements, which is also the artifact type of the OCI
+/// manifests which attach them to images.
+const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+/// The config of OCI artifacts, which have no use for one.
+const EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const EMPTY_BLOB: &[u8] = b"{}";
+/// The key of Biome's checksums in in-toto digest sets. Biome checksums are 256-bit BLAKE2b
+/// hashes, whereas in-toto's `blake2b` is the 512-bit variant.
+const CHECKSUM_ALGORITHM: &str = "blake2b-256";
+
+/// A Biome artifact which an image was built from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub ident:    PackageIdent,
+    /// The hex-encoded BLAKE2b checksum of the artifact.
+    pub checksum: String,
+    /// The named revision of the origin key which signed the artifact.
+    pub signer:   String,
+}
+
+impl Material {
+    /// Describe the artifact at `path`, which holds the package `ident`.
+    ///
+    /// # Errors
+    ///
+    /// * If the artifact cannot be read
+    pub fn from_artifact(ident: &PackageIdent, path: &Path) -> Result<Self> {
+        Ok(Material { ident:    ident.clone(),
+                      checksum: hash::hash_file(path)?.to_string(),
+                      signer:   artifact::artifact_signer(path)?.to_string(), })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "uri": format!("biome:{}", self.ident),
+            "digest": { CHECKSUM_ALGORITHM: self.checksum },
+            "annotations": { "signer": self.signer },
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let uri = value["uri"].as_str()?;
+        Some(Material { ident:    PackageIdent::from_str(uri.trim_start_matches("biome:")).ok()?,
+                        checksum: value["digest"][CHECKSUM_ALGORITHM].as_str()?.to_string(),
+                        signer:   value["annotations"]["signer"].as_str()?.to_string(), })
+    }
+}
+
+/// How an image was built, as recorded in an attestation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    /// The name of the image, such as `acme/redis`.
+    pub image:     String,
+    /// The digest of the image's manifest, such as `sha256:...`.
+    pub digest:    String,
+    pub materials: Vec<Material>,
+    /// The tool which built the image, and its version.
+    pub builder:   String,
+}
+
+impl Provenance {
+    /// The in-toto statement of the provenance.
+    pub fn statement(&self) -> Value {
+        let (algorithm, hex) = split_digest(&self.digest);
+        json!({
+            "_type": STATEMENT_TYPE,
+            "subject": [{
+                "name": self.image,
+                "digest": { algorithm: hex },
+            }],
+            "predicateType": PREDICATE_TYPE,
+            "predicate": {
+                "buildDefinition": {
+                    "buildType": BUILD_TYPE,
+                    "externalParameters": {},
+                    "resolvedDependencies":
+                        self.materials.iter().map(Material::to_json).collect::<Vec<_>>(),
+                },
+                "runDetails": {
+                    "builder": { "id": self.builder },
+                },
+            },
+        })
+    }
+
+    /// Read the provenance back from an in-toto statement.
+    ///
+    /// # Errors
+    ///
+    /// * If the statement is not a provenance statement written by `statement`
+    pub fn from_statement(statement: &Value) -> Result<Self> {
+        let invalid = |what: &str| Error::ProvenanceInvalid(format!("the statement {}", what));
+        if statement["_type"] != STATEMENT_TYPE || statement["predicateType"] != PREDICATE_TYPE {
+            return Err(invalid("is not an in-toto SLSA provenance statement"));
+        }
+        let subject = &statement["subject"][0];
+        let digest = subject["digest"].as_object()
+                                      .and_then(|digests| digests.iter().next())
+                                      .and_then(|(algorithm, hex)| {
+                                          hex.as_str().map(|hex| format!("{}:{}", algorithm, hex))
+                                      })
+                                      .ok_or_else(|| invalid("has no subject digest"))?;
+        let predicate = &statement["predicate"];
+        let dependencies =
+            predicate["buildDefinition"]["resolvedDependencies"].as_array()
+                                                                .ok_or_else(|| {
+                                                                    invalid("has no dependencies")
+                                                                })?;
+        let materials = dependencies.iter()
+                                    .map(|value| {
+                                        Material::from_json(value).ok_or_else(|| {
+                                                                      invalid("has an unknown \
+                                                                               dependency")
+                                                                  })
+                                    })
+                                    .collect::<Result<_>>()?;
+        Ok(Provenance { image: subject["name"].as_str().unwrap_or_default().to_string(),
+                        digest,
+                        materials,
+                        builder: predicate["runDetails"]["builder"]["id"].as_str()
+                                                                         .unwrap_or_default()
+                                                                         .to_string() })
+    }
+
+    /// Sign the statement with an origin key, as a DSSE envelope.
+    ///
+    /// # Errors
+    ///
+    /// * If the statement cannot be signed
+    pub fn sign(&self, key: &SecretOriginSigningKey) -> Result<Envelope> {
+        let payload = serde_json::to_vec(&self.statement()).expect("statements are valid JSON");
+        let sig = key.sign_reader(&mut pae(PAYLOAD_TYPE, &payload).as_slice())?;
+        Ok(Envelope { payload_type: PAYLOAD_TYPE.to_string(),
+                      payload:      base64::encode(&payload),
+                      signatures:   vec![Signature { keyid: key.named_revision().to_string(),
+                                                     sig:   base64::encode(&sig), }], })
+    }
+}
+
+/// A DSSE envelope, which carries a signed payload.
+///
+/// Signatures are Biome origin signatures of the payload's pre-authentication encoding: the
+/// origin key signs the hex-encoded BLAKE2b hash of the encoding, as it does for artifacts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Envelope {
+    #[serde(rename = "payloadType")]
+    pub payload_type: String,
+    /// The base64-encoded payload.
+    pub payload:      String,
+    pub signatures:   Vec<Signature>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Signature {
+    /// The named revision of the signing key.
+    pub keyid: String,
+    /// The base64-encoded signature.
+    pub sig:   String,
+}
+
+impl Envelope {
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).map_err(|e| Error::ProvenanceInvalid(e.to_string()))
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> { serde_json::to_vec(self).expect("envelopes are valid JSON") }
+
+    /// Verify the envelope with a public origin key from `cache`, returning the provenance it
+    /// carries and the key which signed it.
+    ///
+    /// # Errors
+    ///
+    /// * If the envelope is not signed by any key in `cache`
+    /// * If the payload is not a provenance statement
+    pub fn verify(&self, cache: &KeyCache) -> Result<(Provenance, NamedRevision)> {
+        let invalid = |e: &dyn ToString| Error::ProvenanceInvalid(e.to_string());
+        if self.payload_type != PAYLOAD_TYPE {
+            return Err(invalid(&format!("unknown payload type '{}'", self.payload_type)));
+        }
+        let payload = base64::decode(&self.payload).map_err(|e| invalid(&e))?;
+        let pae = pae(&self.payload_type, &payload);
+
+        let mut signer = None;
+        for signature in &self.signatures {
+            let named_revision = NamedRevision::from_str(&signature.keyid);
+            let key = match named_revision.and_then(|n| cache.public_signing_key(&n)) {
+                Ok(key) => key,
+                Err(e) => {
+                    debug!("Skipping signature by {}: {}", signature.keyid, e);
+                    continue;
+                }
+            };
+            let sig = base64::decode(&signature.sig).map_err(|e| invalid(&e))?;
+            key.verify(&sig, &mut pae.as_slice())?;
+            signer = Some(key.named_revision().clone());
+            break;
+        }
+        let signer = signer.ok_or_else(|| {
+                               let keys = self.signatures
+                                              .iter()
+                                              .map(|signature| signature.keyid.as_str())
+                                              .collect::<Vec<_>>();
+                               Error::CryptoKeyError(format!("no public key for any of the \
+                                                              attestation's signers ({}) is in \
+                                                              the key cache",
+                                                             keys.join(", ")))
+                           })?;
+
+        let statement = serde_json::from_slice(&payload).map_err(|e| invalid(&e))?;
+        Ok((Provenance::from_statement(&statement)?, signer))
+    }
+}
+
+/// The DSSE pre-authentication encoding of a payload, which is what is signed.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut pae = format!("DSSEv1 {} {} {} ",
+                          payload_type.len(),
+                          payload_type,
+                          payload.len()).into_bytes();
+    pae.extend_from_slice(payload);
+    pae
+}
+
+/// A reference to a blob, as found in OCI manifests and indexes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Descriptor {
+    pub media_type: String,
+    /// The digest of the blob, such as `sha256:...`.
+    pub digest:     String,
+    pub size:       u64,
+}
+
+impl Descriptor {
+    /// Describe `content` as a blob of the given media type.
+    pub fn of(media_type: &str, content: &[u8]) -> Self {
+        Descriptor { media_type: media_type.to_string(),
+                     digest:     sha256_digest(content),
+                     size:       content.len() as u64, }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "mediaType": self.media_type,
+            "digest": self.digest,
+            "size": self.size,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(Descriptor { media_type: value["mediaType"].as_str()?.to_string(),
+                          digest:     value["digest"].as_str()?.to_string(),
+                          size:       value["size"].as_u64()?, })
+    }
+
+    /// Check that `content` is the blob described.
+    fn check(&self, content: &[u8]) -> Result<()> {
+        if sha256_digest(content) == self.digest {
+            Ok(())
+        } else {
+            Err(Error::ProvenanceInvalid(format!("the blob {} does not \
+                                                  match its digest",
+                                                 self.digest)))
+        }
+    }
+}
+
+/// An attestation as an OCI artifact: the envelope as its only layer, and a manifest which has
+/// the image's manifest as its subject.
+#[derive(Debug)]
+pub struct Attachment {
+    envelope: Vec<u8>,
+    manifest: Vec<u8>,
+}
+
+impl Attachment {
+    /// Attach `envelope` to the image whose manifest is `subject`.
+    pub fn new(envelope: &Envelope, subject: &Descriptor) -> Self {
+        let envelope = envelope.to_vec();
+        let manifest = json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+            "artifactType": PAYLOAD_TYPE,
+            "config": Descriptor::of(EMPTY_MEDIA_TYPE, EMPTY_BLOB).to_json(),
+            "layers": [Descriptor::of(ENVELOPE_MEDIA_TYPE, &envelope).to_json()],
+            "subject": subject.to_json(),
+        });
+        Attachment { envelope,
+                     manifest: serde_json::to_vec(&manifest).expect("manifests are valid JSON") }
+    }
+
+    /// The blobs the manifest refers to.
+    fn blobs(&self) -> Vec<(Descriptor, &[u8])> {
+        vec![(Descriptor::of(EMPTY_MEDIA_TYPE, EMPTY_BLOB), EMPTY_BLOB),
+             (Descriptor::of(ENVELOPE_MEDIA_TYPE, &self.envelope), &self.envelope)]
+    }
+
+    fn manifest_descriptor(&self) -> Descriptor {
+        Descriptor::of(OCI_MANIFEST_MEDIA_TYPE, &self.manifest)
+    }
+}
+
+/// The descriptor of the envelope of an attestation, given its manifest, if the manifest attaches
+/// an attestation to the image whose manifest has the digest `subject`.
+fn envelope_of(manifest: &Value, subject: &str) -> Option<Descriptor> {
+    if manifest["artifactType"] != PAYLOAD_TYPE || manifest["subject"]["digest"] != subject {
+        return None;
+    }
+    manifest["layers"].as_array()?
+                      .iter()
+                      .filter_map(Descriptor::from_json)
+                      .find(|layer| layer.media_type == ENVELOPE_MEDIA_TYPE)
+}
+
+fn sha256_digest(content: &[u8]) -> String { format!("sha256:{:x}", Sha256::digest(content)) }
+
+fn split_digest(digest: &str) -> (&str, &str) {
+    let mut parts = digest.splitn(2, ':');
+    let algorithm = parts.next().unwrap_or_default();
+    (algorithm, parts.next().unwrap_or_default())
+}
+
+/// Verify an attestation of the image whose manifest has the digest `subject`, returning its
+/// provenance and the key which signed it.
+///
+/// # Errors
+///
+/// * If the envelope is not signed by any key in `cache`
+/// * If the attestation is of another image
+pub fn verify(envelope: &[u8],
+              subject: &str,
+              cache: &KeyCache)
+              -> Result<(Provenance, NamedRevision)> {
+    let (provenance, signer) = Envelope::from_slice(envelope)?.verify(cache)?;
+    if provenance.digest != subject {
+        return Err(Error::ProvenanceInvalid(format!("the attestation is of \
+                                                     the image {}, not {}",
+                                                    provenance.digest,
+                                                    subject)));
+    }
+    Ok((provenance, signer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hcore::crypto::keys::generate_signing_key_pair;
+    use tempfile::TempDir;
+
+    pub(super) fn provenance() -> Provenance {
+        Provenance { image:     "acme/redis".to_string(),
+                     digest:    sha256_digest(b"manifest"),
+                     materials: vec![Material { ident:    PackageIdent::from_str("core/redis/4.0.\
+                                                                                  14/\
+                                                                                  20190319155852")
+                                                                                  .unwrap(),
+                                                checksum: "7".repeat(64),
+                                                signer:   "core-20180119235000".to_string(), }],
+                     builder:   "bio-pkg-export-container/1.0.0".to_string(), }
+    }
+
+    /// A key cache holding the public key of a new origin key pair, and the secret key.
+    pub(super) fn keys(dir: &Path) -> (KeyCache, SecretOriginSigningKey) {
+        let cache = KeyCache::new(dir);
+        cache.setup().unwrap();
+        let (public, secret) = generate_signing_key_pair("acme");
+        cache.write_key(&public).unwrap();
+        (cache, secret)
+    }
+
+    #[test]
+    fn provenance_survives_a_round_trip_through_its_statement() {
+        let provenance = provenance();
+        let statement = provenance.statement();
+
+        assert_eq!(statement["subject"][0]["digest"]["sha256"],
+                   split_digest(&provenance.digest).1);
+        assert_eq!(statement["predicate"]["buildDefinition"]["resolvedDependencies"][0]["uri"],
+                   "biome:core/redis/4.0.14/20190319155852");
+        assert_eq!(Provenance::from_statement(&statement).unwrap(), provenance);
+    }
+
+    #[test]
+    fn signed_envelopes_verify_with_the_public_key() {
+        let tmp = TempDir::new().unwrap();
+        let (cache, secret) = keys(tmp.path());
+        let envelope = provenance().sign(&secret).unwrap();
+
+        let (verified, signer) = verify(&envelope.to_vec(), &provenance().digest, &cache).unwrap();
+        assert_eq!(verified, provenance());
+        assert_eq!(&signer, secret.named_revision());
+
+        assert!(verify(&envelope.to_vec(), &sha256_digest(b"other"), &cache).is_err());
+        assert!(verify(&envelope.to_vec(),
+                       &provenance().digest,
+                       &KeyCache::new(tmp.path().join("empty"))).is_err());
+    }
+
+    #[test]
+    fn tampered_payloads_do_not_verify() {
+        let tmp = TempDir::new().unwrap();
+        let (cache, secret) = keys(tmp.path());
+        let mut envelope = provenance().sign(&secret).unwrap();
+        let mut tampered = provenance();
+        tampered.materials[0].checksum = "0".repeat(64);
+        envelope.payload = base64::encode(&serde_json::to_vec(&tampered.statement()).unwrap());
+
+        assert!(envelope.verify(&cache).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Attestations kept in an OCI image layout, next to the image they attest to.
+//!
+//! The attestation's manifest and blobs are written into the layout's blob store, and the
+//! manifest is added to `index.json` with its artifact type, which is how it is told apart from
+//! the image's own manifest.
+
+use super::{envelope_of,
+            Attachment,
+            Descriptor,
+            PAYLOAD_TYPE};
+use crate::error::{Error,
+                   Result};
+use serde_json::Value;
+use std::{fs,
+          path::{Path,
+                 PathBuf}};
+
+const INDEX: &str = "index.json";
+const OCI_LAYOUT: &str = "oci-layout";
+
+/// An OCI image layout on disk.
+#[derive(Debug)]
+pub struct Layout {
+    dir: PathBuf,
+}
+
+impl Layout {
+    /// Whether `dir` holds an OCI image layout.
+    pub fn is_layout(dir: &Path) -> bool {
+        dir.join(OCI_LAYOUT).is_file() && dir.join(INDEX).is_file()
+    }
+
+    pub fn open(dir: &Path) -> Result<Self> {
+        if Self::is_layout(dir) {
+            Ok(Layout { dir: dir.to_path_buf(), })
+        } else {
+            Err(Error::FileNotFound(dir.join(OCI_LAYOUT)
+                                       .display()
+                                       .to_string()))
+        }
+    }
+
+    /// The descriptor of the image's manifest. The index may list it several times, once for
+    /// each of the image's tags.
+    ///
+    /// # Errors
+    ///
+    /// * If the index lists no image, or more than one
+    pub fn image(&self) -> Result<Descriptor> {
+        let mut images = self.manifests()?
+                             .iter()
+                             .filter(|manifest| manifest.get("artifactType").is_none())
+                             .filter_map(Descriptor::from_json)
+                             .collect::<Vec<_>>();
+        images.dedup_by(|a, b| a.digest == b.digest);
+        match images.len() {
+            1 => Ok(images.remove(0)),
+            n => {
+                Err(Error::ProvenanceInvalid(format!("expected one image in \
+                                                      {}, found {}",
+                                                     self.dir.display(),
+                                                     n)))
+            }
+        }
+    }
+
+    /// Add an attestation to the layout.
+    pub fn attach(&self, attachment: &Attachment) -> Result<()> {
+        for (descriptor, blob) in attachment.blobs() {
+            fs::write(self.blob_path(&descriptor.digest), blob)?;
+        }
+        let manifest = attachment.manifest_descriptor();
+        fs::write(self.blob_path(&manifest.digest), &attachment.manifest)?;
+
+        let mut index = self.index()?;
+        let mut descriptor = manifest.to_json();
+        descriptor["artifactType"] = PAYLOAD_TYPE.into();
+        index["manifests"].as_array_mut()
+                          .ok_or_else(|| self.invalid_index())?
+                          .push(descriptor);
+        fs::write(self.dir.join(INDEX),
+                  serde_json::to_vec(&index).expect("indexes are valid JSON"))?;
+        Ok(())
+    }
+
+    /// The envelope of the attestation of the image whose manifest has the digest `subject`.
+    ///
+    /// # Errors
+    ///
+    /// * If no attestation of the image is in the layout
+    /// * If a blob does not match its digest
+    pub fn attestation(&self, subject: &str) -> Result<Vec<u8>> {
+        for descriptor in self.manifests()?
+                              .iter()
+                              .filter(|manifest| manifest["artifactType"] == PAYLOAD_TYPE)
+                              .filter_map(Descriptor::from_json)
+        {
+            let manifest = self.blob(&descriptor)?;
+            let manifest =
+                serde_json::from_slice(&manifest).map_err(|e| {
+                                                     Error::ProvenanceInvalid(e.to_string())
+                                                 })?;
+            if let Some(envelope) = envelope_of(&manifest, subject) {
+                return self.blob(&envelope);
+            }
+        }
+        Err(Error::ProvenanceNotFound(format!("{}@{}",
+                                              self.dir.display(),
+                                              subject)))
+    }
+
+    fn index(&self) -> Result<Value> {
+        serde_json::from_slice(&fs::read(self.dir.join(INDEX))?).map_err(|_| self.invalid_index())
+    }
+
+    fn manifests(&self) -> Result<Vec<Value>> {
+        self.index()?["manifests"].as_array()
+                                  .cloned()
+                                  .ok_or_else(|| self.invalid_index())
+    }
+
+    fn blob(&self, descriptor: &Descriptor) -> Result<Vec<u8>> {
+        let blob = fs::read(self.blob_path(&descriptor.digest))?;
+        descriptor.check(&blob)?;
+        Ok(blob)
+    }
+
+    fn blob_path(&self, digest: &str) -> PathBuf {
+        let (algorithm, hex) = super::split_digest(digest);
+        self.dir.join("blobs").join(algorithm).join(hex)
+    }
+
+    fn invalid_index(&self) -> Error {
+        Error::ProvenanceInvalid(format!("{} is not a valid OCI image index",
+                                         self.dir.join(INDEX).display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::{tests,
+                        Envelope,
+                        OCI_MANIFEST_MEDIA_TYPE},
+                *};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// A layout holding only the manifest of an image, tagged twice.
+    fn layout(dir: &Path) -> (Layout, Descriptor) {
+        let manifest = b"manifest";
+        let image = Descriptor::of(OCI_MANIFEST_MEDIA_TYPE, manifest);
+        fs::create_dir_all(dir.join("blobs").join("sha256")).unwrap();
+        fs::write(dir.join(OCI_LAYOUT), r#"{"imageLayoutVersion":"1.0.0"}"#).unwrap();
+        let index = json!({
+            "schemaVersion": 2,
+            "manifests": [image.to_json(), image.to_json()],
+        });
+        fs::write(dir.join(INDEX), index.to_string()).unwrap();
+        let layout = Layout::open(dir).unwrap();
+        fs::write(layout.blob_path(&image.digest), manifest).unwrap();
+        (layout, image)
+    }
+
+    #[test]
+    fn attestations_are_found_by_the_image_they_are_attached_to() {
+        let tmp = TempDir::new().unwrap();
+        let (cache, secret) = tests::keys(&tmp.path().join("keys"));
+        let (layout, image) = layout(&tmp.path().join("layout"));
+        assert_eq!(layout.image().unwrap(), image);
+        assert!(layout.attestation(&image.digest).is_err());
+
+        let envelope = tests::provenance().sign(&secret).unwrap();
+        layout.attach(&Attachment::new(&envelope, &image)).unwrap();
+
+        assert_eq!(layout.image().unwrap(), image);
+        let attestation = layout.attestation(&image.digest).unwrap();
+        assert_eq!(Envelope::from_slice(&attestation).unwrap(), envelope);
+        assert!(super::super::verify(&attestation, &image.digest, &cache).is_ok());
+        assert!(layout.attestation(&tests::provenance().materials[0].checksum)
+                      .is_err());
+    }
+}
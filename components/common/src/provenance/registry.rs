@@ -0,0 +1,397 @@
+//! Attestations kept in a container registry, next to the image they attest to.
+//!
+//! The attestation's manifest is pushed with the tag `sha256-<hex>.att`, named after the digest
+//! of the image's manifest, so that it can be found by registries which do not support the OCI
+//! referrers API. The manifest's `subject` also links it to the image for those which do.
+
+use super::{envelope_of,
+            split_digest,
+            Attachment,
+            Descriptor,
+            OCI_MANIFEST_MEDIA_TYPE};
+use crate::{error::{Error,
+                    Result},
+            http_client::ApiClient};
+use regex::Regex;
+use reqwest::{header::{HeaderMap,
+                       ACCEPT,
+                       AUTHORIZATION,
+                       CONTENT_TYPE,
+                       LOCATION,
+                       WWW_AUTHENTICATE},
+              RequestBuilder,
+              Response,
+              StatusCode,
+              Url};
+use std::{fmt,
+          result,
+          str::FromStr};
+
+const DOCKER_HUB: &str = "docker.io";
+const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+const DEFAULT_TAG: &str = "latest";
+/// The manifest media types of images, in order of preference.
+const MANIFEST_MEDIA_TYPES: &[&str] = &[OCI_MANIFEST_MEDIA_TYPE,
+                                        "application/vnd.oci.image.index.v1+json",
+                                        "application/vnd.docker.distribution.manifest.v2+json",
+                                        "application/vnd.docker.distribution.manifest.list.\
+                                         v2+json"];
+
+lazy_static! {
+    static ref CHALLENGE_PARAM: Regex = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+}
+
+/// A reference to an image in a registry, such as `registry.example.com/acme/redis:latest`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    /// The host, and port if any, of the registry.
+    pub registry:   String,
+    pub repository: String,
+    /// A tag, or a digest such as `sha256:...`.
+    pub reference:  String,
+}
+
+impl Reference {
+    fn base_url(&self) -> String {
+        let scheme =
+            if self.registry.starts_with("localhost") || self.registry.starts_with("127.0.0.1") {
+                "http"
+            } else {
+                "https"
+            };
+        format!("{}://{}/v2", scheme, self.registry)
+    }
+}
+
+impl FromStr for Reference {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let invalid = || Error::InvalidImageReference(value.to_string());
+        let (name, reference) = match value.find('@') {
+            Some(at) => (&value[..at], value[at + 1..].to_string()),
+            None => {
+                match value.rfind(':') {
+                    Some(colon) if !value[colon..].contains('/') => {
+                        (&value[..colon], value[colon + 1..].to_string())
+                    }
+                    _ => (value, DEFAULT_TAG.to_string()),
+                }
+            }
+        };
+        let mut components = name.splitn(2, '/');
+        let first = components.next().ok_or_else(invalid)?;
+        let (registry, repository) = match components.next() {
+            Some(rest) if first.contains('.') || first.contains(':') || first == "localhost" => {
+                (first, rest.to_string())
+            }
+            _ => (DOCKER_HUB, name.to_string()),
+        };
+        if repository.is_empty() || reference.is_empty() {
+            return Err(invalid());
+        }
+        Ok(match registry {
+            DOCKER_HUB | "index.docker.io" => {
+                let repository = if repository.contains('/') {
+                    repository
+                } else {
+                    format!("library/{}", repository)
+                };
+                Reference { registry: DOCKER_HUB_REGISTRY.to_string(),
+                            repository,
+                            reference }
+            }
+            _ => {
+                Reference { registry: registry.to_string(),
+                            repository,
+                            reference }
+            }
+        })
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.reference.contains(':') {
+            '@'
+        } else {
+            ':'
+        };
+        write!(f,
+               "{}/{}{}{}",
+               self.registry, self.repository, separator, self.reference)
+    }
+}
+
+/// How requests to the registry are authorized.
+#[derive(Debug)]
+enum Auth {
+    None,
+    Basic,
+    Bearer(String),
+}
+
+/// A client of the repository of an image in a registry.
+pub struct Registry {
+    client:    ApiClient,
+    reference: Reference,
+    /// The base64-encoded `username:password` credentials of the registry, if any.
+    basic:     Option<String>,
+    auth:      Auth,
+}
+
+impl Registry {
+    /// # Errors
+    ///
+    /// * If the HTTP client cannot be created
+    pub fn new(reference: Reference,
+               basic: Option<String>,
+               product: &str,
+               version: &str)
+               -> Result<Self> {
+        Ok(Registry { client: ApiClient::new(reference.base_url().as_str(),
+                                             product,
+                                             version,
+                                             None)?,
+                      reference,
+                      basic,
+                      auth: Auth::None })
+    }
+
+    /// The descriptor of the manifest of the image.
+    ///
+    /// # Errors
+    ///
+    /// * If the image is not in the registry
+    pub async fn image(&mut self) -> Result<Descriptor> {
+        let path = self.manifest_path(&self.reference.reference);
+        let response = self.send(|client| {
+                               client.get(&path)
+                                     .header(ACCEPT, MANIFEST_MEDIA_TYPES.join(", "))
+                           })
+                           .await?;
+        let media_type = response.headers()
+                                 .get(CONTENT_TYPE)
+                                 .and_then(|value| value.to_str().ok())
+                                 .unwrap_or(OCI_MANIFEST_MEDIA_TYPE)
+                                 .to_string();
+        Ok(Descriptor::of(&media_type, &response.bytes().await?))
+    }
+
+    /// Push an attestation of the image whose manifest is `subject`.
+    ///
+    /// # Errors
+    ///
+    /// * If a blob or the manifest cannot be uploaded
+    pub async fn attach(&mut self, attachment: &Attachment, subject: &Descriptor) -> Result<()> {
+        for (descriptor, blob) in attachment.blobs() {
+            self.upload(&descriptor, blob).await?;
+        }
+        let path = self.manifest_path(&attestation_tag(&subject.digest));
+        let manifest = attachment.manifest.clone();
+        self.send(|client| {
+                client.put(&path)
+                      .header(CONTENT_TYPE, OCI_MANIFEST_MEDIA_TYPE)
+                      .body(manifest.clone())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// The envelope of the attestation of the image whose manifest has the digest `subject`.
+    ///
+    /// # Errors
+    ///
+    /// * If no attestation of the image is in the registry
+    /// * If a blob does not match its digest
+    pub async fn attestation(&mut self, subject: &str) -> Result<Vec<u8>> {
+        let path = self.manifest_path(&attestation_tag(subject));
+        let response =
+            self.request(|client| client.get(&path).header(ACCEPT, OCI_MANIFEST_MEDIA_TYPE))
+                .await?;
+        let not_found = || Error::ProvenanceNotFound(self.reference.to_string());
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(not_found());
+        }
+        let response = checked(response)?;
+        let manifest = serde_json::from_slice(&response.bytes().await?).map_err(|e| {
+                           Error::ProvenanceInvalid(e.to_string())
+                       })?;
+        let envelope = envelope_of(&manifest, subject).ok_or_else(not_found)?;
+
+        let path = self.blob_path(&envelope.digest);
+        let blob = self.send(|client| client.get(&path)).await?.bytes().await?;
+        envelope.check(&blob)?;
+        Ok(blob.to_vec())
+    }
+
+    /// Upload a blob, unless the repository already has it.
+    async fn upload(&mut self, descriptor: &Descriptor, blob: &[u8]) -> Result<()> {
+        let path = self.blob_path(&descriptor.digest);
+        if self.request(|client| client.head(&path))
+               .await?
+               .status()
+               .is_success()
+        {
+            return Ok(());
+        }
+
+        let path = format!("{}/blobs/uploads/", self.reference.repository);
+        let response = self.send(|client| client.post(&path)).await?;
+        let location = location(response.headers(), response.url())?;
+        let digest = descriptor.digest.clone();
+        let blob = blob.to_vec();
+        self.send(|client| {
+                client.put_with_custom_url("", |url| {
+                          *url = location.clone();
+                          url.query_pairs_mut().append_pair("digest", &digest);
+                      })
+                      .header(CONTENT_TYPE, "application/octet-stream")
+                      .body(blob.clone())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Send a request, failing unless the registry accepts it.
+    async fn send<F>(&mut self, request: F) -> Result<Response>
+        where F: Fn(&ApiClient) -> RequestBuilder
+    {
+        checked(self.request(request).await?)
+    }
+
+    /// Send a request, authorizing it as the registry asks when it is refused.
+    async fn request<F>(&mut self, request: F) -> Result<Response>
+        where F: Fn(&ApiClient) -> RequestBuilder
+    {
+        let response = self.authorize(request(&self.client)).send().await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.auth = self.challenge(&response).await?;
+            Ok(self.authorize(request(&self.client)).send().await?)
+        } else {
+            Ok(response)
+        }
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match (&self.auth, &self.basic) {
+            (Auth::Bearer(token), _) => request.bearer_auth(token),
+            (Auth::Basic, Some(basic)) => request.header(AUTHORIZATION, format!("Basic {}", basic)),
+            _ => request,
+        }
+    }
+
+    /// Answer the registry's `WWW-Authenticate` challenge. Bearer challenges are answered with a
+    /// token from the registry's token service, scoped to the repository.
+    async fn challenge(&self, response: &Response) -> Result<Auth> {
+        let challenge = response.headers()
+                                .get(WWW_AUTHENTICATE)
+                                .and_then(|value| value.to_str().ok())
+                                .unwrap_or_default();
+        if !challenge.to_lowercase().starts_with("bearer") {
+            return Ok(Auth::Basic);
+        }
+
+        let mut params = CHALLENGE_PARAM.captures_iter(challenge)
+                                        .map(|param| (param[1].to_string(), param[2].to_string()))
+                                        .collect::<Vec<_>>();
+        let realm = match params.iter().position(|(name, _)| name == "realm") {
+            Some(realm) => params.remove(realm).1,
+            None => {
+                return Err(Error::RegistryRequest(format!("{} sent a bearer \
+                                                           challenge without a realm",
+                                                          response.url())));
+            }
+        };
+        let realm = Url::parse(&realm).map_err(|e| Error::RegistryRequest(e.to_string()))?;
+        let scope = format!("repository:{}:pull,push", self.reference.repository);
+        let request = self.client.get_with_custom_url("", |url| {
+                                     *url = realm.clone();
+                                     let mut query = url.query_pairs_mut();
+                                     for (name, value) in &params {
+                                         if name != "scope" {
+                                             query.append_pair(name, value);
+                                         }
+                                     }
+                                     query.append_pair("scope", &scope);
+                                 });
+        let request = match self.basic {
+            Some(ref basic) => request.header(AUTHORIZATION, format!("Basic {}", basic)),
+            None => request,
+        };
+        let body: serde_json::Value = checked(request.send().await?)?.json().await?;
+        body["token"].as_str()
+                     .or_else(|| body["access_token"].as_str())
+                     .map(|token| Auth::Bearer(token.to_string()))
+                     .ok_or_else(|| Error::RegistryRequest(format!("{} returned no token", realm)))
+    }
+
+    fn manifest_path(&self, reference: &str) -> String {
+        format!("{}/manifests/{}", self.reference.repository, reference)
+    }
+
+    fn blob_path(&self, digest: &str) -> String {
+        format!("{}/blobs/{}", self.reference.repository, digest)
+    }
+}
+
+fn checked(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(Error::RegistryRequest(format!("{} returned {}",
+                                           response.url(),
+                                           response.status())))
+    }
+}
+
+/// The tag of the attestation of the image whose manifest has the digest `subject`.
+fn attestation_tag(subject: &str) -> String {
+    let (algorithm, hex) = split_digest(subject);
+    format!("{}-{}.att", algorithm, hex)
+}
+
+/// Where to upload a blob to, which may be relative to the URL which was asked for an upload.
+fn location(headers: &HeaderMap, url: &Url) -> Result<Url> {
+    headers.get(LOCATION)
+           .and_then(|value| value.to_str().ok())
+           .and_then(|location| url.join(location).ok())
+           .ok_or_else(|| Error::RegistryRequest(format!("{} returned no upload location", url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(registry: &str, repository: &str, reference: &str) -> Reference {
+        Reference { registry:   registry.to_string(),
+                    repository: repository.to_string(),
+                    reference:  reference.to_string(), }
+    }
+
+    #[test]
+    fn references_default_to_docker_hub_and_the_latest_tag() {
+        assert_eq!(Reference::from_str("redis").unwrap(),
+                   reference(DOCKER_HUB_REGISTRY, "library/redis", "latest"));
+        assert_eq!(Reference::from_str("docker.io/acme/redis:4.0.14").unwrap(),
+                   reference(DOCKER_HUB_REGISTRY, "acme/redis", "4.0.14"));
+        assert_eq!(Reference::from_str("acme/redis@sha256:abc").unwrap(),
+                   reference(DOCKER_HUB_REGISTRY, "acme/redis", "sha256:abc"));
+    }
+
+    #[test]
+    fn references_name_their_registry_by_its_host() {
+        assert_eq!(Reference::from_str("registry.example.com/acme/redis").unwrap(),
+                   reference("registry.example.com", "acme/redis", "latest"));
+        let local = Reference::from_str("localhost:5000/acme/redis:dev").unwrap();
+        assert_eq!(local, reference("localhost:5000", "acme/redis", "dev"));
+        assert_eq!(local.base_url(), "http://localhost:5000/v2");
+        assert_eq!(local.to_string(), "localhost:5000/acme/redis:dev");
+        assert!(Reference::from_str("registry.example.com/").is_err());
+    }
+
+    #[test]
+    fn attestations_are_tagged_after_their_subject() {
+        assert_eq!(attestation_tag("sha256:abc"), "sha256-abc.att");
+    }
+}
@@ -0,0 +1,506 @@
+//! Software bills of materials for Biome packages.
+//!
+//! An SBOM describes a package and every package in its transitive dependencies, using what the
+//! packages already record about themselves: their `DEPS` and `TDEPS` metafiles and the
+//! maintainer, license, upstream URL, source URL and source checksum from their `MANIFEST`.
+//!
+//! Both SPDX 2.2 JSON and CycloneDX 1.4 JSON documents can be rendered from an `Sbom`.
+
+use crate::{error::{Error,
+                    Result},
+            hcore::package::{PackageArchive,
+                             PackageIdent,
+                             PackageInstall}};
+use chrono::{SecondsFormat,
+             Utc};
+use serde_json::{json,
+                 Value};
+use std::{fmt,
+          fs,
+          path::{Path,
+                 PathBuf},
+          str::FromStr};
+use uuid::Uuid;
+
+/// The directory, relative to a file system root, into which exporters write SBOMs.
+pub const SBOM_DIR: &str = "hab/sbom";
+
+/// License identifiers which are not written as SPDX license identifiers are referenced with
+/// this prefix.
+const LICENSE_REF_PREFIX: &str = "LicenseRef-";
+
+/// SPDX's value for information which was not determined.
+const NOASSERTION: &str = "NOASSERTION";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    /// The names accepted on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["spdx", "cyclonedx"];
+
+    /// The conventional file extension for documents in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "spdx.json",
+            SbomFormat::CycloneDx => "cdx.json",
+        }
+    }
+}
+
+impl fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            SbomFormat::Spdx => "spdx",
+            SbomFormat::CycloneDx => "cyclonedx",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for SbomFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_ref() {
+            "spdx" => Ok(SbomFormat::Spdx),
+            "cyclonedx" => Ok(SbomFormat::CycloneDx),
+            _ => Err(Error::BadSbomFormat(value.to_string())),
+        }
+    }
+}
+
+/// A package described by an SBOM.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    pub ident:         PackageIdent,
+    /// The direct dependencies of the package, if they are known.
+    pub deps:          Vec<PackageIdent>,
+    pub maintainer:    Option<String>,
+    pub licenses:      Vec<String>,
+    pub upstream_url:  Option<String>,
+    pub source:        Option<String>,
+    pub source_shasum: Option<String>,
+}
+
+impl Component {
+    fn new(ident: PackageIdent, deps: Vec<PackageIdent>, manifest: Option<&str>) -> Self {
+        let manifest = manifest.unwrap_or_default();
+        Component { ident,
+                    deps,
+                    maintainer: manifest_field(manifest, "Maintainer").map(str::to_string),
+                    licenses: manifest_field(manifest, "License").map(split_licenses)
+                                                                 .unwrap_or_default(),
+                    upstream_url: manifest_field(manifest, "Upstream URL").and_then(link_target),
+                    source: manifest_field(manifest, "Source").and_then(link_target),
+                    source_shasum: manifest_field(manifest, "SHA").and_then(code_span) }
+    }
+
+    fn from_install(install: &PackageInstall) -> Result<Self> {
+        Ok(Component::new(install.ident().clone(),
+                          install.deps()?,
+                          install.manifest()?.as_deref()))
+    }
+
+    /// Describe a dependency from its installed package, or by its identifier alone when it is
+    /// not installed.
+    fn dependency(ident: &PackageIdent, fs_root_path: &Path) -> Result<Self> {
+        match PackageInstall::load(ident, Some(fs_root_path)) {
+            Ok(install) => Component::from_install(&install),
+            Err(e) => {
+                debug!("Describing {} without its metadata: {}", ident, e);
+                Ok(Component::new(ident.clone(), Vec::new(), None))
+            }
+        }
+    }
+
+    fn spdx_id(&self) -> String {
+        let id = self.ident
+                     .to_string()
+                     .chars()
+                     .map(|c| {
+                         if c.is_ascii_alphanumeric() || c == '.' {
+                             c
+                         } else {
+                             '-'
+                         }
+                     })
+                     .collect::<String>();
+        format!("SPDXRef-Package-{}", id)
+    }
+
+    fn purl(&self) -> String {
+        let mut purl = format!("pkg:generic/{}/{}", self.ident.origin, self.ident.name);
+        if let Some(version) = &self.ident.version {
+            purl.push_str(&format!("@{}", version));
+        }
+        if let Some(release) = &self.ident.release {
+            purl.push_str(&format!("?release={}", release));
+        }
+        purl
+    }
+
+    fn version(&self) -> String {
+        match (&self.ident.version, &self.ident.release) {
+            (Some(version), Some(release)) => format!("{}/{}", version, release),
+            (Some(version), None) => version.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn spdx_license(&self) -> String {
+        if self.licenses.is_empty() {
+            return NOASSERTION.to_string();
+        }
+        self.licenses
+            .iter()
+            .map(|license| {
+                if license.chars()
+                          .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+')
+                {
+                    license.to_string()
+                } else {
+                    let id = license.chars()
+                                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                                    .collect::<String>();
+                    format!("{}{}", LICENSE_REF_PREFIX, id)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    fn to_spdx(&self) -> Value {
+        let mut package = json!({
+            "name": format!("{}/{}", self.ident.origin, self.ident.name),
+            "SPDXID": self.spdx_id(),
+            "versionInfo": self.version(),
+            "supplier": format!("Organization: {}", self.ident.origin),
+            "downloadLocation": self.source.as_deref().unwrap_or(NOASSERTION),
+            "filesAnalyzed": false,
+            "licenseConcluded": NOASSERTION,
+            "licenseDeclared": self.spdx_license(),
+            "copyrightText": NOASSERTION,
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": self.purl(),
+            }],
+        });
+        if let Some(shasum) = &self.source_shasum {
+            package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": shasum }]);
+        }
+        if let Some(url) = &self.upstream_url {
+            package["homepage"] = json!(url);
+        }
+        if let Some(maintainer) = &self.maintainer {
+            package["originator"] = json!(format!("Person: {}", maintainer));
+        }
+        package
+    }
+
+    fn to_cyclonedx(&self, component_type: &str) -> Value {
+        let mut component = json!({
+            "type": component_type,
+            "bom-ref": self.ident.to_string(),
+            "group": self.ident.origin,
+            "name": self.ident.name,
+            "version": self.version(),
+            "purl": self.purl(),
+            "licenses": self.licenses
+                            .iter()
+                            .map(|license| json!({ "license": { "name": license } }))
+                            .collect::<Vec<_>>(),
+        });
+        if let Some(maintainer) = &self.maintainer {
+            component["author"] = json!(maintainer);
+        }
+        let mut references = Vec::new();
+        if let Some(url) = &self.upstream_url {
+            references.push(json!({ "type": "website", "url": url }));
+        }
+        if let Some(source) = &self.source {
+            let mut reference = json!({ "type": "distribution", "url": source });
+            if let Some(shasum) = &self.source_shasum {
+                reference["hashes"] = json!([{ "alg": "SHA-256", "content": shasum }]);
+            }
+            references.push(reference);
+        }
+        if !references.is_empty() {
+            component["externalReferences"] = json!(references);
+        }
+        component
+    }
+}
+
+/// A package along with every package in its transitive dependencies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sbom {
+    pub package:      Component,
+    pub dependencies: Vec<Component>,
+}
+
+impl Sbom {
+    /// Describe an installed package. Its dependencies are looked up beneath the same root.
+    pub fn from_install(install: &PackageInstall, fs_root_path: &Path) -> Result<Self> {
+        let dependencies = install.tdeps()?
+                                  .iter()
+                                  .map(|dep| Component::dependency(dep, fs_root_path))
+                                  .collect::<Result<_>>()?;
+        Ok(Sbom { package: Component::from_install(install)?,
+                  dependencies })
+    }
+
+    /// Describe a package artifact. Its dependencies are described from their installed
+    /// packages beneath `fs_root_path` where present.
+    pub fn from_archive(archive: &mut PackageArchive, fs_root_path: &Path) -> Result<Self> {
+        let package = Component::new(archive.ident()?, archive.deps()?, Some(archive.manifest()?));
+        let dependencies = archive.tdeps()?
+                                  .iter()
+                                  .map(|dep| Component::dependency(dep, fs_root_path))
+                                  .collect::<Result<_>>()?;
+        Ok(Sbom { package,
+                  dependencies })
+    }
+
+    /// Render the SBOM as a pretty-printed JSON document. The tool is recorded as the document's
+    /// creator.
+    pub fn render(&self, format: SbomFormat, tool_name: &str, tool_version: &str) -> String {
+        let document = match format {
+            SbomFormat::Spdx => self.to_spdx(tool_name, tool_version),
+            SbomFormat::CycloneDx => self.to_cyclonedx(tool_name, tool_version),
+        };
+        format!("{:#}", document)
+    }
+
+    /// Write the SBOM into `SBOM_DIR` beneath a file system root, such as the root file system of
+    /// an exported image, returning the path of the written file.
+    pub fn write_to_root(&self,
+                         format: SbomFormat,
+                         tool_name: &str,
+                         tool_version: &str,
+                         fs_root_path: &Path)
+                         -> Result<PathBuf> {
+        let dir = fs_root_path.join(SBOM_DIR);
+        fs::create_dir_all(&dir)?;
+        let file_name = format!("{}.{}",
+                                self.package.ident.to_string().replace('/', "-"),
+                                format.extension());
+        let path = dir.join(file_name);
+        fs::write(&path, self.render(format, tool_name, tool_version))?;
+        Ok(path)
+    }
+
+    fn components(&self) -> impl Iterator<Item = &Component> {
+        std::iter::once(&self.package).chain(self.dependencies.iter())
+    }
+
+    fn to_spdx(&self, tool_name: &str, tool_version: &str) -> Value {
+        let mut relationships =
+            vec![spdx_relationship("SPDXRef-DOCUMENT", "DESCRIBES", &self.package.spdx_id())];
+        for component in self.components() {
+            for dep in &component.deps {
+                if let Some(dep) = self.components().find(|c| &c.ident == dep) {
+                    relationships.push(spdx_relationship(&component.spdx_id(),
+                                                         "DEPENDS_ON",
+                                                         &dep.spdx_id()));
+                }
+            }
+        }
+        json!({
+            "spdxVersion": "SPDX-2.2",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.package.ident.to_string(),
+            "documentNamespace": format!("https://biome.sh/spdx/{}-{}",
+                                         self.package.ident,
+                                         Uuid::new_v4()),
+            "creationInfo": {
+                "created": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                "creators": [format!("Tool: {}-{}", tool_name, tool_version)],
+            },
+            "documentDescribes": [self.package.spdx_id()],
+            "packages": self.components().map(Component::to_spdx).collect::<Vec<_>>(),
+            "relationships": relationships,
+        })
+    }
+
+    fn to_cyclonedx(&self, tool_name: &str, tool_version: &str) -> Value {
+        let dependencies = self.components()
+                               .map(|component| {
+                                   json!({
+                                       "ref": component.ident.to_string(),
+                                       "dependsOn": component.deps
+                                                             .iter()
+                                                             .map(ToString::to_string)
+                                                             .collect::<Vec<_>>(),
+                                   })
+                               })
+                               .collect::<Vec<_>>();
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "serialNumber": format!("urn:uuid:{}", Uuid::new_v4()),
+            "version": 1,
+            "metadata": {
+                "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                "tools": [{ "vendor": "Biome", "name": tool_name, "version": tool_version }],
+                "component": self.package.to_cyclonedx("application"),
+            },
+            "components": self.dependencies
+                              .iter()
+                              .map(|component| component.to_cyclonedx("library"))
+                              .collect::<Vec<_>>(),
+            "dependencies": dependencies,
+        })
+    }
+}
+
+fn spdx_relationship(element: &str, relationship: &str, related: &str) -> Value {
+    json!({
+        "spdxElementId": element,
+        "relationshipType": relationship,
+        "relatedSpdxElement": related,
+    })
+}
+
+/// Find the value of a `* __Field__: value` line in a MANIFEST. Older manifests without the
+/// markdown emphasis are also understood.
+fn manifest_field<'a>(manifest: &'a str, field: &str) -> Option<&'a str> {
+    manifest.lines().find_map(|line| {
+                        let line = line.trim_start_matches(|c| c == '*' || c == ' ');
+                        let line = line.trim_start_matches("__");
+                        if !line.starts_with(field) {
+                            return None;
+                        }
+                        let rest = line[field.len()..].trim_start_matches("__");
+                        if rest.starts_with(':') {
+                            Some(rest[1..].trim()).filter(|value| !value.is_empty())
+                        } else {
+                            None
+                        }
+                    })
+}
+
+fn split_licenses(licenses: &str) -> Vec<String> {
+    licenses.split_whitespace().map(str::to_string).collect()
+}
+
+/// The target of a markdown link, `[text](target)`.
+fn link_target(value: &str) -> Option<String> {
+    let start = value.find("](")? + 2;
+    if value.ends_with(')') && start < value.len() - 1 {
+        Some(value[start..value.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
+/// The contents of a markdown code span, `` `value` ``.
+fn code_span(value: &str) -> Option<String> {
+    if value.len() > 2 && value.starts_with('`') && value.ends_with('`') {
+        Some(value[1..value.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MANIFEST: &str = "# core / redis
+Persistent key-value database, with built-in net interface
+
+* __Maintainer__: The Biome Maintainers <humans@biome.sh>
+* __Version__: 5.0.7
+* __Release__: 20200110201405
+* __Target__: x86_64-linux
+* __Upstream URL__: [http://redis.io/](http://redis.io/)
+* __License__: BSD-3-Clause
+* __Source__: [http://download.redis.io/releases/redis-5.0.7.tar.gz](http://download.redis.io/releases/redis-5.0.7.tar.gz)
+* __SHA__: `61db74eabf6801f057fd24b590232f2f337d422280fd19486eca03be87d3a82b`
+* __Path__: `/hab/pkgs/core/redis/5.0.7/20200110201405`
+* __Build Dependencies__: no build dependencies or undefined
+* __Dependencies__: `core/glibc `
+* __Interpreters__: no interpreters or undefined
+";
+
+    fn sbom() -> Sbom {
+        let glibc: PackageIdent = "core/glibc/2.29/20200305172459".parse().unwrap();
+        let package = Component::new("core/redis/5.0.7/20200110201405".parse().unwrap(),
+                                     vec![glibc.clone()],
+                                     Some(MANIFEST));
+        Sbom { package,
+               dependencies: vec![Component::new(glibc, Vec::new(), None)] }
+    }
+
+    #[test]
+    fn component_is_described_from_manifest() {
+        let component = sbom().package;
+        assert_eq!(component.maintainer.as_deref(),
+                   Some("The Biome Maintainers <humans@biome.sh>"));
+        assert_eq!(component.licenses, vec!["BSD-3-Clause".to_string()]);
+        assert_eq!(component.upstream_url.as_deref(), Some("http://redis.io/"));
+        assert_eq!(component.source.as_deref(),
+                   Some("http://download.redis.io/releases/redis-5.0.7.tar.gz"));
+        assert_eq!(component.source_shasum.as_deref(),
+                   Some("61db74eabf6801f057fd24b590232f2f337d422280fd19486eca03be87d3a82b"));
+    }
+
+    #[test]
+    fn missing_manifest_values_are_not_recorded() {
+        let manifest = "* __Source__: source URL not provided or required\n* __SHA__: SHA256 \
+                        checksum not provided or required\n* __License__: \n";
+        let component = Component::new("core/foo".parse().unwrap(), Vec::new(), Some(manifest));
+        assert_eq!(component.source, None);
+        assert_eq!(component.source_shasum, None);
+        assert!(component.licenses.is_empty());
+        assert_eq!(component.spdx_license(), NOASSERTION);
+    }
+
+    #[test]
+    fn non_spdx_licenses_are_license_refs() {
+        let mut component = sbom().package;
+        component.licenses = vec!["Apache-2.0".to_string(), "Biome EULA".to_string()];
+        assert_eq!(component.spdx_license(),
+                   "Apache-2.0 AND LicenseRef-Biome-EULA");
+    }
+
+    #[test]
+    fn spdx_document_relates_package_to_dependencies() {
+        let document = sbom().to_spdx("bio", "1.0.0");
+        assert_eq!(document["spdxVersion"], "SPDX-2.2");
+        assert_eq!(document["packages"].as_array().unwrap().len(), 2);
+        assert_eq!(document["packages"][0]["downloadLocation"],
+                   "http://download.redis.io/releases/redis-5.0.7.tar.gz");
+        assert_eq!(document["relationships"][1]["spdxElementId"],
+                   "SPDXRef-Package-core-redis-5.0.7-20200110201405");
+        assert_eq!(document["relationships"][1]["relationshipType"],
+                   "DEPENDS_ON");
+        assert_eq!(document["relationships"][1]["relatedSpdxElement"],
+                   "SPDXRef-Package-core-glibc-2.29-20200305172459");
+    }
+
+    #[test]
+    fn cyclonedx_document_lists_dependencies() {
+        let document = sbom().to_cyclonedx("bio", "1.0.0");
+        assert_eq!(document["bomFormat"], "CycloneDX");
+        assert_eq!(document["metadata"]["component"]["bom-ref"],
+                   "core/redis/5.0.7/20200110201405");
+        assert_eq!(document["components"][0]["name"], "glibc");
+        assert_eq!(document["dependencies"][0]["dependsOn"][0],
+                   "core/glibc/2.29/20200305172459");
+    }
+
+    #[test]
+    fn sbom_formats_parse() {
+        assert_eq!("spdx".parse::<SbomFormat>().unwrap(), SbomFormat::Spdx);
+        assert_eq!("CycloneDX".parse::<SbomFormat>().unwrap(),
+                   SbomFormat::CycloneDx);
+        assert!("swid".parse::<SbomFormat>().is_err());
+    }
+}
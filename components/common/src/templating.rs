@@ -73,10 +73,27 @@ impl TemplateRenderer {
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
         handlebars.register_helper("eachAlive", Box::new(helpers::EACH_ALIVE));
+        handlebars.register_helper("eachSortedBy", Box::new(helpers::EACH_SORTED_BY));
+        handlebars.register_helper("eachWhere", Box::new(helpers::EACH_WHERE));
         handlebars.register_helper("pkgPathFor", Box::new(helpers::PKG_PATH_FOR));
+        handlebars.register_helper("fileContents", Box::new(helpers::FILE_CONTENTS));
+        handlebars.register_helper("default", Box::new(helpers::DEFAULT));
+        handlebars.register_helper("coalesce", Box::new(helpers::COALESCE));
+        handlebars.register_helper("add", Box::new(helpers::ADD));
+        handlebars.register_helper("sub", Box::new(helpers::SUB));
+        handlebars.register_helper("mul", Box::new(helpers::MUL));
+        handlebars.register_helper("div", Box::new(helpers::DIV));
+        handlebars.register_helper("mod", Box::new(helpers::MOD));
+        handlebars.register_helper("base64Encode", Box::new(helpers::BASE64_ENCODE));
+        handlebars.register_helper("base64Decode", Box::new(helpers::BASE64_DECODE));
+        handlebars.register_helper("sha256", Box::new(helpers::SHA256));
+        handlebars.register_helper("ipInCidr", Box::new(helpers::IP_IN_CIDR));
+        handlebars.register_helper("cidrHost", Box::new(helpers::CIDR_HOST));
         handlebars.register_helper("strConcat", Box::new(helpers::STR_CONCAT));
         handlebars.register_helper("strJoin", Box::new(helpers::STR_JOIN));
+        handlebars.register_helper("strMatches", Box::new(helpers::STR_MATCHES));
         handlebars.register_helper("strReplace", Box::new(helpers::STR_REPLACE));
+        handlebars.register_helper("strSplit", Box::new(helpers::STR_SPLIT));
         handlebars.register_helper("toUppercase", Box::new(helpers::TO_UPPERCASE));
         handlebars.register_helper("toLowercase", Box::new(helpers::TO_LOWERCASE));
         handlebars.register_helper("toJson", Box::new(helpers::TO_JSON));
@@ -244,6 +261,25 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_handlebars_builtin_helpers() {
+        let content = "{{add cfg.port 1}} {{default cfg.host \"localhost\"}} {{base64Encode \
+                       cfg.user}}"
+                                  .to_string();
+        let mut renderer = TemplateRenderer::new();
+        renderer.register_template_string("t", content).unwrap();
+
+        let data = json!({
+            "cfg": {
+                "port": 8080,
+                "user": "admin"
+            }
+        });
+
+        assert_eq!(renderer.render("t", &data).unwrap(),
+                   "8081 localhost YWRtaW4=");
+    }
+
     #[test]
     fn test_handlebars_toml_helper() {
         let content = "{{toToml x}}".to_string();
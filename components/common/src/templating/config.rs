@@ -13,7 +13,9 @@ use serde::{Serialize,
 use std::{self,
           borrow::Cow,
           clone::Clone,
+          collections::BTreeMap,
           env,
+          fmt,
           fs::File,
           io::prelude::*,
           path::{Path,
@@ -88,6 +90,8 @@ pub struct Cfg {
     pub user:               Option<toml::value::Table>,
     /// Gossip level configuration loaded by a census group
     pub gossip:             Option<toml::value::Table>,
+    /// Gossip level configuration targeted at this member, applied in order after `gossip`
+    pub member_gossip:      Vec<MemberGossipLayer>,
     /// Environment level configuration loaded by the Supervisor's process environment
     pub environment:        Option<toml::value::Table>,
     /// Source of the user configuration
@@ -97,6 +101,10 @@ pub struct Cfg {
     /// The path to an optional dev-time configuration directory that
     /// is being used.
     override_config_dir:    Option<PathBuf>,
+    /// The directory the default configuration was loaded from
+    default_config_dir:     PathBuf,
+    /// The environment variable the environment configuration was loaded from
+    environment_var:        String,
 }
 
 impl Cfg {
@@ -104,23 +112,24 @@ impl Cfg {
         where P: PackageConfigPaths
     {
         let override_config_dir = config_from.map(Clone::clone);
-        let default = {
-            let pkg_root = match override_config_dir {
-                Some(ref path) => Cow::Borrowed(path),
-                None => Cow::Owned(package.default_config_dir()),
-            };
-            Self::load_default(pkg_root.as_ref())?
+        let default_config_dir = match override_config_dir {
+            Some(ref path) => path.clone(),
+            None => package.default_config_dir(),
         };
+        let default = Self::load_default(&default_config_dir)?;
         let user_config_path = Self::determine_user_config_path(package);
         let user = Self::load_user(user_config_path.get_path())?;
         let environment = Self::load_environment(&package.name())?;
         Ok(Self { default,
                   user,
                   gossip: None,
+                  member_gossip: Vec::new(),
                   environment,
                   gossip_incarnation: 0,
                   user_config_path,
-                  override_config_dir })
+                  override_config_dir,
+                  default_config_dir,
+                  environment_var: Self::environment_var_name(&package.name()) })
     }
 
     /// Validates a service configuration against a configuration interface.
@@ -177,6 +186,9 @@ impl Cfg {
             Self::load_default(pkg_root.as_ref())?
         };
 
+        if self.override_config_dir.is_none() {
+            self.default_config_dir = package.default_config_dir();
+        }
         if incoming_defaults != self.default {
             self.default = incoming_defaults;
             Ok(true)
@@ -191,6 +203,51 @@ impl Cfg {
         self.gossip = Some(gossip);
     }
 
+    /// Replaces the member-targeted gossip layers of the configuration.
+    ///
+    /// Returns `true` if the layers changed.
+    pub fn set_member_gossip(&mut self, layers: Vec<MemberGossipLayer>) -> bool {
+        if self.member_gossip == layers {
+            false
+        } else {
+            self.member_gossip = layers;
+            true
+        }
+    }
+
+    /// Every layer of the configuration, with where it came from, in the order they are merged.
+    pub fn layers(&self) -> Vec<(CfgSource, &toml::value::Table)> {
+        let mut layers = Vec::new();
+        let default_path = self.default_config_dir.join("default.toml");
+        let user_path = self.user_config_path.get_path().join(USER_CONFIG_FILE);
+        let base =
+            vec![("default", default_path.display().to_string(), &self.default),
+                 ("environment", self.environment_var.clone(), &self.environment),
+                 ("user", user_path.display().to_string(), &self.user),
+                 ("gossip", format!("incarnation {}", self.gossip_incarnation), &self.gossip)];
+        for (name, origin, cfg) in base {
+            if let Some(cfg) = cfg {
+                layers.push((CfgSource::new(name, origin), cfg));
+            }
+        }
+        for layer in &self.member_gossip {
+            layers.push((CfgSource::new(format!("gossip[{}]", layer.target),
+                                        format!("incarnation {}", layer.incarnation)),
+                         &layer.cfg));
+        }
+        layers
+    }
+
+    /// Lists each key of the merged configuration, in dotted form, with its value and the layer
+    /// that supplied it.
+    pub fn explain(&self) -> BTreeMap<String, (toml::Value, CfgSource)> {
+        let mut sources = BTreeMap::new();
+        for (source, cfg) in self.layers() {
+            explain_layer(&mut sources, "", cfg, &source);
+        }
+        sources
+    }
+
     /// Returns a subset of the overall configuration which intersects with the given package
     /// exports.
     pub fn to_exported(&self, pkg: &Pkg) -> Result<toml::value::Table> {
@@ -296,9 +353,13 @@ impl Cfg {
         Ok(())
     }
 
+    fn environment_var_name(package_name: &str) -> String {
+        format!("{}_{}", ENV_VAR_PREFIX, package_name).to_ascii_uppercase()
+                                                      .replace("-", "_")
+    }
+
     fn load_environment(package_name: &str) -> Result<Option<toml::value::Table>> {
-        let var_name = format!("{}_{}", ENV_VAR_PREFIX, package_name).to_ascii_uppercase()
-                                                                     .replace("-", "_");
+        let var_name = Self::environment_var_name(package_name);
         match env::var(&var_name) {
             Ok(config) => {
                 // If we've got an environment variable, we'll parsing
@@ -361,24 +422,9 @@ impl Serialize for Cfg {
         where S: Serializer
     {
         let mut table = toml::value::Table::new();
-        if let Some(ref default_cfg) = self.default {
-            if let Err(err) = toml_merge(&mut table, default_cfg) {
-                outputln!("Error merging default-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref env_cfg) = self.environment {
-            if let Err(err) = toml_merge(&mut table, env_cfg) {
-                outputln!("Error merging environment-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref user_cfg) = self.user {
-            if let Err(err) = toml_merge(&mut table, user_cfg) {
-                outputln!("Error merging user-cfg into config, {}", err);
-            }
-        }
-        if let Some(ref gossip_cfg) = self.gossip {
-            if let Err(err) = toml_merge(&mut table, gossip_cfg) {
-                outputln!("Error merging gossip-cfg into config, {}", err);
+        for (source, cfg) in self.layers() {
+            if let Err(err) = toml_merge(&mut table, cfg) {
+                outputln!("Error merging {}-cfg into config, {}", source.layer, err);
             }
         }
 
@@ -386,6 +432,41 @@ impl Serialize for Cfg {
     }
 }
 
+/// Identifies the layer of configuration which supplied a value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CfgSource {
+    /// Name of the layer, such as `user` or `gossip[member:m1]`
+    pub layer:  String,
+    /// The file, environment variable or gossip incarnation the layer was read from
+    pub origin: String,
+}
+
+impl CfgSource {
+    pub fn new<L, O>(layer: L, origin: O) -> Self
+        where L: Into<String>,
+              O: Into<String>
+    {
+        CfgSource { layer:  layer.into(),
+                    origin: origin.into(), }
+    }
+}
+
+impl fmt::Display for CfgSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.layer, self.origin)
+    }
+}
+
+/// A layer of gossiped configuration which only applies to some members of a service group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberGossipLayer {
+    /// Describes the members the layer was targeted at
+    pub target:      String,
+    /// Incarnation of the service config the layer came from
+    pub incarnation: u64,
+    pub cfg:         toml::value::Table,
+}
+
 #[derive(Debug)]
 /// Renders configuration templates into config files.
 pub struct CfgRenderer(TemplateRenderer);
@@ -405,6 +486,20 @@ impl CfgRenderer {
         }
     }
 
+    /// Render all configuration templates without writing anything,
+    /// returning the path of each file relative to the configuration
+    /// directory along with its contents, sorted by path.
+    pub fn render<T>(&self, ctx: &T) -> Result<Vec<(String, String)>>
+        where T: Serialize
+    {
+        let mut rendered = Vec::new();
+        for template in self.0.get_templates().keys() {
+            rendered.push((template.clone(), self.0.render(&template, ctx)?));
+        }
+        rendered.sort();
+        Ok(rendered)
+    }
+
     /// Compile and write all configuration files to the configuration directory.
     ///
     /// Returns `true` if the configuration has changed.
@@ -512,6 +607,35 @@ fn toml_merge_recurse(me: &mut toml::value::Table,
     Ok(())
 }
 
+/// Records the leaves of a configuration layer, mirroring how `toml_merge` lets a layer's
+/// values replace those of the layers beneath it.
+fn explain_layer(sources: &mut BTreeMap<String, (toml::Value, CfgSource)>,
+                 prefix: &str,
+                 cfg: &toml::value::Table,
+                 source: &CfgSource) {
+    for (key, value) in cfg.iter() {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        if let toml::Value::Table(table) = value {
+            sources.remove(&path);
+            explain_layer(sources, &path, table, source);
+        } else {
+            let nested = format!("{}.", path);
+            let replaced = sources.range(nested.clone()..)
+                                  .take_while(|(k, _)| k.starts_with(&nested))
+                                  .map(|(k, _)| k.clone())
+                                  .collect::<Vec<_>>();
+            for k in replaced {
+                sources.remove(&k);
+            }
+            sources.insert(path, (value.clone(), source.clone()));
+        }
+    }
+}
+
 fn is_toml_value_a_table(key: &str, table: &toml::value::Table) -> bool {
     match table.get(key) {
         None => false,
@@ -948,6 +1072,61 @@ mod test {
         assert_eq!(default_toml, toml::to_string(&cfg).unwrap());
     }
 
+    #[test]
+    fn member_gossip_layers_merge_after_group_gossip() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("port = 1\n[db]\nhost = \"a\"\nuser = \"hab\""));
+        cfg.set_gossip(1, toml_from_str("port = 2\n[db]\nhost = \"b\""));
+        let layer = MemberGossipLayer { target:      "member:m1".to_string(),
+                                        incarnation: 3,
+                                        cfg:         toml_from_str("[db]\nhost = \"c\""), };
+        assert!(cfg.set_member_gossip(vec![layer.clone()]));
+        assert!(!cfg.set_member_gossip(vec![layer]));
+
+        let merged = toml::Value::try_from(&cfg).unwrap();
+        assert_eq!(merged["port"].as_integer(), Some(2));
+        assert_eq!(merged["db"]["host"].as_str(), Some("c"));
+        assert_eq!(merged["db"]["user"].as_str(), Some("hab"));
+
+        let explained = cfg.explain();
+        assert_eq!(explained["port"].1.layer, "gossip");
+        assert_eq!(explained["db.host"].1.layer, "gossip[member:m1]");
+        assert_eq!(explained["db.user"].1.layer, "default");
+    }
+
+    #[test]
+    fn explain_records_the_origin_of_each_layer() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("port = 1\nhost = \"a\""));
+        cfg.set_gossip(7, toml_from_str("port = 2"));
+
+        let explained = cfg.explain();
+        let default_toml = pkg.default_config_dir().join("default.toml");
+        assert_eq!(explained["host"].1,
+                   CfgSource::new("default", default_toml.display().to_string()));
+        assert_eq!(explained["port"].1,
+                   CfgSource::new("gossip", "incarnation 7"));
+        assert_eq!(explained["port"].1.to_string(), "gossip (incarnation 7)");
+    }
+
+    #[test]
+    fn explain_drops_keys_replaced_by_a_scalar() {
+        let concrete_path = TempDir::new().expect("create temp dir");
+        let pkg = TestPkg::new(&concrete_path);
+        let mut cfg = Cfg::new(&pkg, None).expect("Could not create config");
+        cfg.default = Some(toml_from_str("[db]\nhost = \"a\""));
+        cfg.user = Some(toml_from_str("db = \"sqlite\""));
+
+        let explained = cfg.explain();
+        assert_eq!(explained.len(), 1);
+        assert_eq!(explained["db"].0, toml::Value::String("sqlite".to_string()));
+        assert_eq!(explained["db"].1.layer, "user");
+    }
+
     // env_key: the name of the environment variable the config should
     //     be read from
     // package_name: the name of the package that would read
@@ -1164,4 +1343,33 @@ mod test {
         assert_eq!(file_content(deep_output_dir.join("config.txt")),
                    "config message is Hello");
     }
+
+    #[tokio::test]
+    async fn test_render_does_not_write() {
+        let root = TempDir::new().expect("create temp dir").into_path();
+        let pkg_dir = root.join("pkg/testing/test");
+        let pg_id = PackageIdent::new("testing", "test", Some("1.0.0"), Some("20170712000000"));
+        let pkg_install = PackageInstall::new_from_parts(pg_id,
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone(),
+                                                         pkg_dir.clone());
+        let config_dir = pkg_dir.join("config");
+        fs::create_dir_all(config_dir.join("dir_a")).expect("create config/dir_a");
+        create_with_content(pkg_dir.join("default.toml"), "message = \"Hello\"");
+        create_with_content(config_dir.join("dir_a").join("b.txt"),
+                            "b says {{cfg.message}}");
+        create_with_content(config_dir.join("a.txt"), "a says {{cfg.message}}");
+
+        let pkg = Pkg::from_install(&pkg_install).await.unwrap();
+        let cfg = Cfg::new(&pkg, None).unwrap();
+        let ctx = RenderContext::new(&pkg, &cfg);
+        let renderer = CfgRenderer::new(&config_dir).expect("create cfg renderer");
+
+        let rendered = renderer.render(&ctx).expect("render");
+        let b_path = PathBuf::from("dir_a").join("b.txt");
+        assert_eq!(rendered,
+                   vec![("a.txt".to_string(), "a says Hello".to_string()),
+                        (b_path.to_string_lossy().into_owned(), "b says Hello".to_string()),]);
+        assert_eq!(fs::read_dir(&config_dir).unwrap().count(), 2);
+    }
 }
@@ -1,27 +1,92 @@
+mod base64_codec;
+mod cidr_host;
+mod coalesce;
+mod default;
 mod each_alive;
+mod each_sorted_by;
+mod each_where;
+mod file_contents;
+mod int_math;
+mod ip_in_cidr;
 mod pkg_path_for;
+mod sha256;
 mod str_concat;
 mod str_join;
+mod str_matches;
 mod str_replace;
+mod str_split;
 mod to_json;
 mod to_lowercase;
 mod to_toml;
 mod to_uppercase;
 mod to_yaml;
 
-pub use self::{each_alive::EACH_ALIVE,
+pub use self::{base64_codec::{BASE64_DECODE,
+                              BASE64_ENCODE},
+               cidr_host::CIDR_HOST,
+               coalesce::COALESCE,
+               default::DEFAULT,
+               each_alive::EACH_ALIVE,
+               each_sorted_by::EACH_SORTED_BY,
+               each_where::EACH_WHERE,
+               file_contents::FILE_CONTENTS,
+               int_math::{ADD,
+                          DIV,
+                          MOD,
+                          MUL,
+                          SUB},
+               ip_in_cidr::IP_IN_CIDR,
                pkg_path_for::PKG_PATH_FOR,
+               sha256::SHA256,
                str_concat::STR_CONCAT,
                str_join::STR_JOIN,
+               str_matches::STR_MATCHES,
                str_replace::STR_REPLACE,
+               str_split::STR_SPLIT,
                to_json::TO_JSON,
                to_lowercase::TO_LOWERCASE,
                to_toml::TO_TOML,
                to_uppercase::TO_UPPERCASE,
                to_yaml::TO_YAML};
+use super::RenderResult;
+use handlebars::{Handlebars,
+                 Helper,
+                 RenderContext,
+                 Renderable};
 use serde::Serialize;
 use serde_json::{self,
                  Value as Json};
+use std::collections::BTreeMap;
+
+/// Summary of the built-in helpers, displayed in the help for `bio plan render`.
+pub const HELPERS_HELP: &str = "\
+TEMPLATE HELPERS:
+    In addition to the standard Handlebars helpers, templates may use:
+
+    eachAlive LIST            Iterate over the alive members of a census group
+    eachSortedBy LIST KEY     Iterate over LIST sorted by the (dotted) KEY of each item
+    eachWhere LIST KEY VALUE  Iterate over the items of LIST whose KEY equals VALUE
+    strSplit STRING SEP       Iterate over the parts of STRING split on SEP
+    strMatches STRING REGEX   Render the block only if STRING matches REGEX
+    ipInCidr IP CIDR          Render the block only if IP is within the CIDR range
+    pkgPathFor IDENT          The installed path of a dependency
+    fileContents NAME         The contents of the file NAME from `svc.files`
+    default VALUE FALLBACK    VALUE, or FALLBACK when VALUE is missing or empty
+    coalesce VALUE...         The first argument that is not missing or empty
+    add|sub|mul|div|mod A B   Integer arithmetic
+    base64Encode STRING       STRING encoded as base64
+    base64Decode STRING       STRING decoded from base64
+    sha256 STRING             The hex encoded SHA-256 digest of STRING
+    cidrHost CIDR N           The Nth host address within the CIDR range
+    strConcat STRING...       The arguments concatenated together
+    strJoin LIST SEP          The items of LIST joined with SEP
+    strReplace STRING OLD NEW STRING with all instances of OLD replaced with NEW
+    toUppercase STRING        STRING in upper case
+    toLowercase STRING        STRING in lower case
+    toJson|toToml|toYaml VAL  VAL serialized to JSON, TOML or YAML
+
+    Block helpers expose each item through a block parameter, e.g.
+    {{#eachSortedBy bind.backend.members \"sys.ip\" as |member|}}{{member.sys.ip}}{{/eachSortedBy}}";
 
 // Taken from `handlebars::context::JsonTruthy`. The trait is marked public but it's in a private
 // module. It's super useful so let's pull it into here.
@@ -48,3 +113,92 @@ fn to_json<T>(src: &T) -> Json
 {
     serde_json::to_value(src).unwrap_or(Json::Null)
 }
+
+/// Returns the value found by following a dot separated path (e.g. `sys.ip`) into `value`.
+/// Array elements can be addressed by their index.
+fn lookup<'a>(value: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.').try_fold(value, |v, key| {
+                       match v {
+                           Json::Object(map) => map.get(key),
+                           Json::Array(list) => key.parse::<usize>().ok().and_then(|i| list.get(i)),
+                           _ => None,
+                       }
+                   })
+}
+
+/// Whether a helper parameter should be treated as not having been given a value.
+fn is_missing(value: &Json) -> bool {
+    match value {
+        Json::Null => true,
+        Json::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// Renders a value the way it would appear in a template; strings are written without quotes.
+fn render_value(value: &Json) -> String {
+    match value {
+        Json::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Renders the block of an iterating helper once for each item, setting the `@first`, `@last`
+/// and `@index` local variables and binding the item to the block parameter, if one was given.
+/// When there are no items the `else` block is rendered instead.
+fn render_each(h: &Helper<'_>,
+               r: &Handlebars,
+               rc: &mut RenderContext<'_>,
+               items: &[Json])
+               -> RenderResult<()> {
+    let template = match h.template() {
+        Some(template) => template,
+        None => return Ok(()),
+    };
+    if items.is_empty() {
+        if let Some(else_template) = h.inverse() {
+            else_template.render(r, rc)?;
+        }
+        return Ok(());
+    }
+
+    rc.promote_local_vars();
+    let len = items.len();
+    for (i, item) in items.iter().enumerate() {
+        let mut local_rc = rc.derive();
+        local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
+        local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
+        local_rc.set_local_var("@index".to_string(), to_json(&i));
+
+        if let Some(block_param) = h.block_param() {
+            let mut map = BTreeMap::new();
+            map.insert(block_param.to_string(), item.clone());
+            local_rc.push_block_context(&map)?;
+        }
+
+        template.render(r, &mut local_rc)?;
+
+        if h.block_param().is_some() {
+            local_rc.pop_block_context();
+        }
+    }
+    rc.demote_local_vars();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup_follows_dotted_paths() {
+        let json = json!({
+            "sys": { "ip": "10.0.0.1" },
+            "ports": [80, 443]
+        });
+        assert_eq!(lookup(&json, "sys.ip"), Some(&json!("10.0.0.1")));
+        assert_eq!(lookup(&json, "ports.1"), Some(&json!(443)));
+        assert_eq!(lookup(&json, "sys.hostname"), None);
+        assert_eq!(lookup(&json, "ports.2"), None);
+    }
+}
@@ -0,0 +1,87 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Base64EncodeHelper;
+
+impl HelperDef for Base64EncodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"base64Encode\"")
+                                                        })?;
+        rc.writer
+          .write_all(base64::encode(param).into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Base64DecodeHelper;
+
+impl HelperDef for Base64DecodeHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"base64Decode\"")
+                                                        })?;
+        let bytes = base64::decode(param.trim()).map_err(|e| {
+                                                    RenderError::new(format!("Can't decode \
+                                                                              parameter as \
+                                                                              base64: {}",
+                                                                             e))
+                                                })?;
+        let decoded = String::from_utf8(bytes).map_err(|e| {
+                                                  RenderError::new(format!("Decoded base64 is \
+                                                                            not valid UTF-8: {}",
+                                                                           e))
+                                              })?;
+        rc.writer.write_all(decoded.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static BASE64_ENCODE: Base64EncodeHelper = Base64EncodeHelper;
+pub static BASE64_DECODE: Base64DecodeHelper = Base64DecodeHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64Encode", Box::new(BASE64_ENCODE));
+        handlebars.register_helper("base64Decode", Box::new(BASE64_DECODE));
+        handlebars
+    }
+
+    #[test]
+    fn test_base64_encode_helper() {
+        assert_eq!("aGVsbG8gd29ybGQ=",
+                   handlebars().template_render("{{base64Encode \"hello world\"}}", &json!({}))
+                               .unwrap());
+    }
+
+    #[test]
+    fn test_base64_decode_helper() {
+        let json = json!({ "secret": "aGVsbG8gd29ybGQ=" });
+        assert_eq!("hello world",
+                   handlebars().template_render("{{base64Decode secret}}", &json)
+                               .unwrap());
+    }
+
+    #[test]
+    fn test_base64_decode_helper_errors_on_invalid_input() {
+        assert!(handlebars().template_render("{{base64Decode \"not base64!\"}}", &json!({}))
+                            .is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            ip_in_cidr::Cidr};
+
+#[derive(Clone, Copy)]
+pub struct CidrHostHelper;
+
+impl HelperDef for CidrHostHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let cidr = h.param(0)
+                    .and_then(|v| v.value().as_str())
+                    .ok_or_else(|| RenderError::new("Expected 2 parameters for \"cidrHost\""))?
+                    .parse::<Cidr>()
+                    .map_err(RenderError::new)?;
+        let n = match h.param(1).map(|v| v.value()) {
+                    Some(Json::Number(n)) => n.as_u64(),
+                    Some(Json::String(s)) => s.parse().ok(),
+                    _ => None,
+                }.ok_or_else(|| {
+                     RenderError::new("Expected a non-negative host number for \"cidrHost\"")
+                 })?;
+        let host = cidr.host(u128::from(n)).ok_or_else(|| {
+                                                RenderError::new(format!("Host number {} is \
+                                                                          outside of the range \
+                                                                          for \"cidrHost\"",
+                                                                         n))
+                                            })?;
+        rc.writer
+          .write_all(host.to_string().into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static CIDR_HOST: CidrHostHelper = CidrHostHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(template: &str) -> RenderResult<String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("cidrHost", Box::new(CIDR_HOST));
+        handlebars.template_render(template, &json!({}))
+    }
+
+    #[test]
+    fn test_cidr_host_helper() {
+        assert_eq!("10.1.2.5",
+                   render("{{cidrHost \"10.1.2.0/24\" 5}}").unwrap());
+        assert_eq!("10.1.3.4",
+                   render("{{cidrHost \"10.1.0.0/16\" 772}}").unwrap());
+        assert_eq!("fd00::10", render("{{cidrHost \"fd00::/64\" 16}}").unwrap());
+    }
+
+    #[test]
+    fn test_cidr_host_helper_errors_outside_range() {
+        assert!(render("{{cidrHost \"10.1.2.0/24\" 256}}").is_err());
+    }
+}
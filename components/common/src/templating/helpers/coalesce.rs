@@ -0,0 +1,51 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext};
+
+use super::{super::RenderResult,
+            is_missing,
+            render_value};
+
+#[derive(Clone, Copy)]
+pub struct CoalesceHelper;
+
+impl HelperDef for CoalesceHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.params()
+                     .iter()
+                     .map(handlebars::ContextJson::value)
+                     .find(|v| !is_missing(v))
+                     .map(render_value)
+                     .unwrap_or_default();
+        rc.writer.write_all(value.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static COALESCE: CoalesceHelper = CoalesceHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_helper() {
+        let json = json!({
+            "cfg": {
+                "listen": "",
+                "bind": "10.0.0.1"
+            }
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("coalesce", Box::new(COALESCE));
+        assert_eq!("10.0.0.1",
+                   handlebars.template_render("{{coalesce cfg.missing cfg.listen cfg.bind \
+                                               \"0.0.0.0\"}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("",
+                   handlebars.template_render("{{coalesce cfg.missing cfg.listen}}", &json)
+                             .unwrap());
+    }
+}
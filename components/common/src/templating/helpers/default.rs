@@ -0,0 +1,62 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+
+use super::{super::RenderResult,
+            is_missing,
+            render_value};
+
+#[derive(Clone, Copy)]
+pub struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value = h.param(0)
+                     .ok_or_else(|| RenderError::new("Expected 2 parameters for \"default\""))?
+                     .value();
+        let fallback = h.param(1)
+                        .ok_or_else(|| RenderError::new("Expected 2 parameters for \"default\""))?
+                        .value();
+        let rendered = if is_missing(value) {
+            render_value(fallback)
+        } else {
+            render_value(value)
+        };
+        rc.writer.write_all(rendered.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static DEFAULT: DefaultHelper = DefaultHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(template: &str) -> String {
+        let json = json!({
+            "cfg": {
+                "port": 0,
+                "host": "",
+                "name": "redis"
+            }
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("default", Box::new(DEFAULT));
+        handlebars.template_render(template, &json).unwrap()
+    }
+
+    #[test]
+    fn test_default_helper_uses_value_when_present() {
+        assert_eq!("redis", render("{{default cfg.name \"postgres\"}}"));
+        assert_eq!("0", render("{{default cfg.port 8080}}"));
+    }
+
+    #[test]
+    fn test_default_helper_uses_fallback_when_missing_or_empty() {
+        assert_eq!("localhost", render("{{default cfg.host \"localhost\"}}"));
+        assert_eq!("8080", render("{{default cfg.other_port 8080}}"));
+    }
+}
@@ -0,0 +1,79 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+use std::cmp::Ordering;
+
+use super::{super::RenderResult,
+            lookup,
+            render_each};
+
+#[derive(Clone, Copy)]
+pub struct EachSortedByHelper;
+
+impl HelperDef for EachSortedByHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let mut items: Vec<Json> = match h.param(0).map(|v| v.value()) {
+            Some(Json::Array(list)) => list.clone(),
+            Some(Json::Object(obj)) => obj.values().cloned().collect(),
+            Some(Json::Null) => Vec::new(),
+            _ => return Err(RenderError::new("Expected a list parameter for \"eachSortedBy\"")),
+        };
+        let key =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a key \
+                                                                              parameter for \
+                                                                              \"eachSortedBy\"")
+                                                        })?;
+        items.sort_by(|a, b| compare(lookup(a, key), lookup(b, key)));
+        render_each(h, r, rc, &items)
+    }
+}
+
+/// Orders numbers numerically and strings lexically. Items missing the key sort last.
+fn compare(a: Option<&Json>, b: Option<&Json>) -> Ordering {
+    match (a, b) {
+        (Some(Json::Number(a)), Some(Json::Number(b))) => {
+            a.as_f64()
+             .partial_cmp(&b.as_f64())
+             .unwrap_or(Ordering::Equal)
+        }
+        (Some(Json::String(a)), Some(Json::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) => a.to_string().cmp(&b.to_string()),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+pub static EACH_SORTED_BY: EachSortedByHelper = EachSortedByHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_each_sorted_by_helper() {
+        let json = json!({
+            "members": [
+                { "member_id": "c", "sys": { "ip": "10.0.0.3" }, "pid": 30 },
+                { "member_id": "a", "sys": { "ip": "10.0.0.1" }, "pid": 200 },
+                { "member_id": "b", "sys": { "ip": "10.0.0.2" } }
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachSortedBy", Box::new(EACH_SORTED_BY));
+        assert_eq!("10.0.0.1 10.0.0.2 10.0.0.3 ",
+                   handlebars.template_render("{{#eachSortedBy members \"sys.ip\" as \
+                                               |m|}}{{m.sys.ip}} {{/eachSortedBy}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("cab",
+                   handlebars.template_render("{{#eachSortedBy members \"pid\" as \
+                                               |m|}}{{m.member_id}}{{/eachSortedBy}}",
+                                              &json)
+                             .unwrap());
+    }
+}
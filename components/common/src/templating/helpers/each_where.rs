@@ -0,0 +1,70 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            lookup,
+            render_each};
+
+#[derive(Clone, Copy)]
+pub struct EachWhereHelper;
+
+impl HelperDef for EachWhereHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let items: Vec<&Json> = match h.param(0).map(|v| v.value()) {
+            Some(Json::Array(list)) => list.iter().collect(),
+            Some(Json::Object(obj)) => obj.values().collect(),
+            Some(Json::Null) => Vec::new(),
+            _ => return Err(RenderError::new("Expected a list parameter for \"eachWhere\"")),
+        };
+        let key = h.param(1)
+                   .and_then(|v| v.value().as_str())
+                   .ok_or_else(|| RenderError::new("Expected 3 parameters for \"eachWhere\""))?;
+        let expected = h.param(2)
+                        .ok_or_else(|| RenderError::new("Expected 3 parameters for \"eachWhere\""))?
+                        .value();
+        let matching: Vec<Json> = items.into_iter()
+                                       .filter(|item| lookup(item, key) == Some(expected))
+                                       .cloned()
+                                       .collect();
+        render_each(h, r, rc, &matching)
+    }
+}
+
+pub static EACH_WHERE: EachWhereHelper = EachWhereHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_each_where_helper() {
+        let json = json!({
+            "members": [
+                { "member_id": "a", "leader": false, "pkg": { "version": "1.0.0" } },
+                { "member_id": "b", "leader": true, "pkg": { "version": "1.1.0" } },
+                { "member_id": "c", "leader": false, "pkg": { "version": "1.1.0" } }
+            ]
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eachWhere", Box::new(EACH_WHERE));
+        assert_eq!("b",
+                   handlebars.template_render("{{#eachWhere members \"leader\" true as \
+                                               |m|}}{{m.member_id}}{{/eachWhere}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("bc",
+                   handlebars.template_render("{{#eachWhere members \"pkg.version\" \"1.1.0\" \
+                                               as |m|}}{{m.member_id}}{{/eachWhere}}",
+                                              &json)
+                             .unwrap());
+        assert_eq!("none",
+                   handlebars.template_render("{{#eachWhere members \"leader\" \"yes\" as \
+                                               |m|}}{{m.member_id}}{{else}}none{{/eachWhere}}",
+                                              &json)
+                             .unwrap());
+    }
+}
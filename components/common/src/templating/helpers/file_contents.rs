@@ -0,0 +1,85 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use std::{fs,
+          path::Path};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct FileContentsHelper;
+
+impl HelperDef for FileContentsHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let name =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"fileContents\"")
+                                                        })?;
+        // Only files placed directly in `svc.files` (e.g. with `bio file upload`) may be read.
+        if Path::new(name).file_name().and_then(|n| n.to_str()) != Some(name) {
+            return Err(RenderError::new(format!("Invalid file name for \
+                                                 \"fileContents\": {}",
+                                                name)));
+        }
+        let files_path =
+            rc.context().data()["pkg"]["svc_files_path"].as_str()
+                                                        .map(Path::new)
+                                                        .ok_or_else(|| {
+                                                            RenderError::new("No svc_files_path \
+                                                                              available for \
+                                                                              \"fileContents\"")
+                                                        })?
+                                                        .join(name);
+        let contents = fs::read_to_string(&files_path).map_err(|e| {
+                           RenderError::new(format!("Can't read {} for \"fileContents\": {}",
+                                                    files_path.display(),
+                                                    e))
+                       })?;
+        rc.writer.write_all(contents.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static FILE_CONTENTS: FileContentsHelper = FileContentsHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn render(template: &str, tmpdir: &TempDir) -> RenderResult<String> {
+        let json = json!({
+            "pkg": { "svc_files_path": tmpdir.path() }
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("fileContents", Box::new(FILE_CONTENTS));
+        handlebars.template_render(template, &json)
+    }
+
+    #[test]
+    fn test_file_contents_helper() {
+        let tmpdir = TempDir::new().unwrap();
+        fs::write(tmpdir.path().join("ca.pem"), "CERTIFICATE").unwrap();
+        assert_eq!("CERTIFICATE",
+                   render("{{fileContents \"ca.pem\"}}", &tmpdir).unwrap());
+    }
+
+    #[test]
+    fn test_file_contents_helper_errors_on_missing_file() {
+        let tmpdir = TempDir::new().unwrap();
+        assert!(render("{{fileContents \"ca.pem\"}}", &tmpdir).is_err());
+    }
+
+    #[test]
+    fn test_file_contents_helper_rejects_paths() {
+        let tmpdir = TempDir::new().unwrap();
+        fs::create_dir(tmpdir.path().join("nested")).unwrap();
+        fs::write(tmpdir.path().join("nested").join("ca.pem"), "CERTIFICATE").unwrap();
+        assert!(render("{{fileContents \"nested/ca.pem\"}}", &tmpdir).is_err());
+        assert!(render("{{fileContents \"../ca.pem\"}}", &tmpdir).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use handlebars::{ContextJson,
+                 Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Performs integer arithmetic on its two parameters. Numeric strings are accepted so that values
+/// read from configuration as strings can be used directly.
+#[derive(Clone, Copy)]
+pub struct IntMathHelper {
+    name:      &'static str,
+    operation: Operation,
+}
+
+impl IntMathHelper {
+    fn operand(&self, param: Option<&ContextJson>) -> RenderResult<i64> {
+        let value = param.map(ContextJson::value);
+        match value {
+            Some(Json::Number(n)) => n.as_i64(),
+            Some(Json::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        }.ok_or_else(|| {
+             RenderError::new(format!("Expected 2 integer parameters for \"{}\"", self.name))
+         })
+    }
+}
+
+impl HelperDef for IntMathHelper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let lhs = self.operand(h.param(0))?;
+        let rhs = self.operand(h.param(1))?;
+        let result = match self.operation {
+                         Operation::Add => lhs.checked_add(rhs),
+                         Operation::Sub => lhs.checked_sub(rhs),
+                         Operation::Mul => lhs.checked_mul(rhs),
+                         Operation::Div => lhs.checked_div(rhs),
+                         Operation::Mod => lhs.checked_rem(rhs),
+                     }.ok_or_else(|| {
+                          RenderError::new(format!("Can't compute \"{}\" of {} and {}",
+                                                   self.name, lhs, rhs))
+                      })?;
+        rc.writer
+          .write_all(result.to_string().into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static ADD: IntMathHelper = IntMathHelper { name:      "add",
+                                                operation: Operation::Add, };
+pub static SUB: IntMathHelper = IntMathHelper { name:      "sub",
+                                                operation: Operation::Sub, };
+pub static MUL: IntMathHelper = IntMathHelper { name:      "mul",
+                                                operation: Operation::Mul, };
+pub static DIV: IntMathHelper = IntMathHelper { name:      "div",
+                                                operation: Operation::Div, };
+pub static MOD: IntMathHelper = IntMathHelper { name:      "mod",
+                                                operation: Operation::Mod, };
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handlebars() -> Handlebars {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("add", Box::new(ADD));
+        handlebars.register_helper("sub", Box::new(SUB));
+        handlebars.register_helper("mul", Box::new(MUL));
+        handlebars.register_helper("div", Box::new(DIV));
+        handlebars.register_helper("mod", Box::new(MOD));
+        handlebars
+    }
+
+    #[test]
+    fn test_int_math_helpers() {
+        let json = json!({
+            "cfg": {
+                "port": 8080,
+                "workers": "6"
+            }
+        });
+        let handlebars = handlebars();
+        let render = |t| handlebars.template_render(t, &json).unwrap();
+        assert_eq!("8081", render("{{add cfg.port 1}}"));
+        assert_eq!("8070", render("{{sub cfg.port 10}}"));
+        assert_eq!("12", render("{{mul cfg.workers 2}}"));
+        assert_eq!("3", render("{{div cfg.workers 2}}"));
+        assert_eq!("2", render("{{mod cfg.port 3}}"));
+    }
+
+    #[test]
+    fn test_int_math_helpers_error_on_bad_input() {
+        let handlebars = handlebars();
+        assert!(handlebars.template_render("{{div 1 0}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1 \"two\"}}", &json!({}))
+                          .is_err());
+        assert!(handlebars.template_render("{{add 1}}", &json!({})).is_err());
+    }
+}
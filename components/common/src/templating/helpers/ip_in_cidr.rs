@@ -0,0 +1,161 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError,
+                 Renderable};
+use std::{net::{IpAddr,
+                Ipv4Addr,
+                Ipv6Addr},
+          str::FromStr};
+
+use super::super::RenderResult;
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8` or `fd00::/8`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Cidr {
+    address:    IpAddr,
+    prefix_len: u32,
+}
+
+impl Cidr {
+    fn bits(&self) -> u32 {
+        match self.address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// All of the bits of an address of this family set.
+    fn all(&self) -> u128 {
+        if self.bits() == 32 {
+            u128::from(u32::max_value())
+        } else {
+            u128::max_value()
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        self.all()
+            .checked_shl(self.bits() - self.prefix_len)
+            .unwrap_or(0)
+        & self.all()
+    }
+
+    fn network(&self) -> u128 { to_u128(self.address) & self.mask() }
+
+    pub(super) fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.address.is_ipv4() && to_u128(ip) & self.mask() == self.network()
+    }
+
+    /// Returns the `n`th address in the network, if the network is large enough.
+    pub(super) fn host(&self, n: u128) -> Option<IpAddr> {
+        let host_bits = !self.mask() & self.all();
+        if n & !host_bits != 0 {
+            return None;
+        }
+        let address = self.network() | n;
+        Some(match self.address {
+                 IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(address as u32)),
+                 IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(address)),
+             })
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid CIDR '{}'", value);
+        let mut parts = value.splitn(2, '/');
+        let address = parts.next()
+                           .and_then(|a| a.parse::<IpAddr>().ok())
+                           .ok_or_else(invalid)?;
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match parts.next() {
+            Some(len) => len.parse::<u32>().map_err(|_| invalid())?,
+            None => bits,
+        };
+        if prefix_len > bits {
+            return Err(invalid());
+        }
+        Ok(Cidr { address,
+                  prefix_len })
+    }
+}
+
+fn to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => u128::from(u32::from(ip)),
+        IpAddr::V6(ip) => u128::from(ip),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct IpInCidrHelper;
+
+impl HelperDef for IpInCidrHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let ip = h.param(0)
+                  .and_then(|v| v.value().as_str())
+                  .and_then(|v| v.parse::<IpAddr>().ok())
+                  .ok_or_else(|| RenderError::new("Invalid IP address for \"ipInCidr\""))?;
+        let cidr =
+            h.param(1)
+             .and_then(|v| v.value().as_str())
+             .ok_or_else(|| RenderError::new("Expected 2 string parameters for \"ipInCidr\""))?
+             .parse::<Cidr>()
+             .map_err(RenderError::new)?;
+        let template = if cidr.contains(ip) {
+            h.template()
+        } else {
+            h.inverse()
+        };
+        if let Some(template) = template {
+            template.render(r, rc)?;
+        }
+        Ok(())
+    }
+}
+
+pub static IP_IN_CIDR: IpInCidrHelper = IpInCidrHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cidr_contains() {
+        let cidr: Cidr = "10.1.0.0/16".parse().unwrap();
+        assert!(cidr.contains("10.1.255.3".parse().unwrap()));
+        assert!(!cidr.contains("10.2.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::1".parse().unwrap()));
+
+        let cidr: Cidr = "fd00::/8".parse().unwrap();
+        assert!(cidr.contains("fd12:3456::1".parse().unwrap()));
+        assert!(!cidr.contains("fe80::1".parse().unwrap()));
+
+        let everything: Cidr = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains("192.168.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn cidr_from_str_invalid() {
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("10.0.0/8".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/x".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_ip_in_cidr_helper() {
+        let json = json!({ "sys": { "ip": "192.168.1.20" } });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("ipInCidr", Box::new(IP_IN_CIDR));
+        let template = "{{#ipInCidr sys.ip \"192.168.0.0/16\"}}private{{else}}public{{/ipInCidr}}";
+        assert_eq!("private",
+                   handlebars.template_render(template, &json).unwrap());
+        let template = "{{#ipInCidr sys.ip \"10.0.0.0/8\"}}private{{else}}public{{/ipInCidr}}";
+        assert_eq!("public",
+                   handlebars.template_render(template, &json).unwrap());
+    }
+}
@@ -0,0 +1,43 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use sha2::{Digest,
+           Sha256};
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct Sha256Helper;
+
+impl HelperDef for Sha256Helper {
+    fn call(&self, h: &Helper<'_>, _: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let param =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected a string \
+                                                                              parameter for \
+                                                                              \"sha256\"")
+                                                        })?;
+        let digest = format!("{:x}", Sha256::digest(param.as_bytes()));
+        rc.writer.write_all(digest.into_bytes().as_ref())?;
+        Ok(())
+    }
+}
+
+pub static SHA256: Sha256Helper = Sha256Helper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sha256_helper() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("sha256", Box::new(SHA256));
+        let expected = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert_eq!(expected,
+                   handlebars.template_render("{{sha256 \"hello world\"}}", &json!({}))
+                             .unwrap());
+    }
+}
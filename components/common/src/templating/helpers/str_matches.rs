@@ -0,0 +1,74 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError,
+                 Renderable};
+use regex::Regex;
+
+use super::super::RenderResult;
+
+#[derive(Clone, Copy)]
+pub struct StrMatchesHelper;
+
+impl HelperDef for StrMatchesHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"strMatches\"")
+                                                        })?;
+        let pattern =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"strMatches\"")
+                                                        })?;
+        let regex = Regex::new(pattern).map_err(|e| {
+                                           RenderError::new(format!("Invalid regular expression \
+                                                                     for \"strMatches\": {}",
+                                                                    e))
+                                       })?;
+        let template = if regex.is_match(value) {
+            h.template()
+        } else {
+            h.inverse()
+        };
+        if let Some(template) = template {
+            template.render(r, rc)?;
+        }
+        Ok(())
+    }
+}
+
+pub static STR_MATCHES: StrMatchesHelper = StrMatchesHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(value: &str) -> RenderResult<String> {
+        let json = json!({ "version": value });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strMatches", Box::new(STR_MATCHES));
+        handlebars.template_render("{{#strMatches version \
+                                    \"^1[.]\"}}v1{{else}}other{{/strMatches}}",
+                                   &json)
+    }
+
+    #[test]
+    fn test_str_matches_helper() {
+        assert_eq!("v1", render("1.2.3").unwrap());
+        assert_eq!("other", render("2.0.0").unwrap());
+    }
+
+    #[test]
+    fn test_str_matches_helper_errors_on_invalid_regex() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strMatches", Box::new(STR_MATCHES));
+        assert!(handlebars.template_render("{{#strMatches \"a\" \"(\"}}x{{/strMatches}}",
+                                           &json!({}))
+                          .is_err());
+    }
+}
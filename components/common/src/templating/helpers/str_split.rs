@@ -0,0 +1,64 @@
+use handlebars::{Handlebars,
+                 Helper,
+                 HelperDef,
+                 RenderContext,
+                 RenderError};
+use serde_json::Value as Json;
+
+use super::{super::RenderResult,
+            render_each};
+
+#[derive(Clone, Copy)]
+pub struct StrSplitHelper;
+
+impl HelperDef for StrSplitHelper {
+    fn call(&self, h: &Helper<'_>, r: &Handlebars, rc: &mut RenderContext<'_>) -> RenderResult<()> {
+        let value =
+            h.param(0).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"strSplit\"")
+                                                        })?;
+        let separator =
+            h.param(1).and_then(|v| v.value().as_str()).ok_or_else(|| {
+                                                            RenderError::new("Expected 2 string \
+                                                                              parameters for \
+                                                                              \"strSplit\"")
+                                                        })?;
+        let parts: Vec<Json> = if value.is_empty() {
+            Vec::new()
+        } else {
+            value.split(separator)
+                 .map(|part| Json::String(part.to_string()))
+                 .collect()
+        };
+        render_each(h, r, rc, &parts)
+    }
+}
+
+pub static STR_SPLIT: StrSplitHelper = StrSplitHelper;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_helper() {
+        let json = json!({ "hosts": "a.example.com,b.example.com" });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strSplit", Box::new(STR_SPLIT));
+        let template = "{{#strSplit hosts \",\" as |host|}}{{@index}}={{host}}{{#unless \
+                        @last}};{{/unless}}{{/strSplit}}";
+        assert_eq!("0=a.example.com;1=b.example.com",
+                   handlebars.template_render(template, &json).unwrap());
+    }
+
+    #[test]
+    fn test_split_helper_renders_else_for_empty_string() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("strSplit", Box::new(STR_SPLIT));
+        let template = "{{#strSplit \"\" \",\" as |h|}}{{h}}{{else}}none{{/strSplit}}";
+        assert_eq!("none",
+                   handlebars.template_render(template, &json!({})).unwrap());
+    }
+}
@@ -14,6 +14,8 @@ use biome_core::{crypto::{self,
                             Blake2bHash},
                    fs,
                    fs::svc_hooks_path,
+                   os::process::{self as core_process,
+                                 Pid},
                    package::PackageInstall,
                    util::BufReadLossy};
 use serde::{Serialize,
@@ -30,7 +32,14 @@ use std::{ffi::OsStr,
                BufReader},
           path::{Path,
                  PathBuf},
-          result};
+          result,
+          sync::{atomic::{AtomicBool,
+                          Ordering},
+                 mpsc::{self,
+                        RecvTimeoutError},
+                 Arc},
+          thread,
+          time::Duration};
 
 #[cfg(not(windows))]
 pub const HOOK_PERMISSIONS: u32 = 0o755;
@@ -193,20 +202,25 @@ pub trait Hook: fmt::Debug + Sized + Send {
     }
 
     /// Run a compiled hook.
+    ///
+    /// If a `timeout` is given and the hook is still running once it elapses, the hook's entire
+    /// process tree is killed and `Error::HookTimedOut` is returned.
     fn run<T>(&self,
               service_group: &str,
               pkg: &Pkg,
-              svc_encrypted_password: Option<T>)
+              svc_encrypted_password: Option<T>,
+              timeout: Option<Duration>)
               -> Result<Self::ExitValue>
         where T: ToString
     {
-        self.run_impl(service_group, pkg, svc_encrypted_password)
+        self.run_impl(service_group, pkg, svc_encrypted_password, timeout)
     }
 
     fn run_impl<T>(&self,
                    service_group: &str,
                    pkg: &Pkg,
-                   svc_encrypted_password: Option<T>)
+                   svc_encrypted_password: Option<T>,
+                   timeout: Option<Duration>)
                    -> Result<Self::ExitValue>
         where T: ToString
     {
@@ -215,15 +229,23 @@ pub trait Hook: fmt::Debug + Sized + Send {
                                       "Hook failed to run, {}, {}", Self::FILE_NAME, err);
                             err
                         })?;
+        let watchdog =
+            timeout.map(|timeout| HookWatchdog::start(Self::FILE_NAME, child.id() as Pid, timeout));
         let mut hook_output = HookOutput::new(self.stdout_log_path(), self.stderr_log_path());
         hook_output.output_standard_streams::<Self>(service_group, &mut child);
-        Ok(child.wait()
-                .map_err(|err| {
-                    outputln!(preamble service_group,
-                              "Hook failed to run, {}, {}", Self::FILE_NAME, err);
-                    err
-                })
-                .map(|status| self.handle_exit(pkg, &hook_output, status))?)
+        let status = child.wait().map_err(|err| {
+                                      outputln!(preamble service_group,
+                                               "Hook failed to run, {}, {}", Self::FILE_NAME, err);
+                                      err
+                                  })?;
+        if let Some(timeout) = watchdog.and_then(HookWatchdog::finish) {
+            outputln!(preamble service_group,
+                      "{} hook exceeded its timeout of {}s and was killed",
+                      Self::FILE_NAME,
+                      timeout.as_secs());
+            return Err(Error::HookTimedOut(Self::FILE_NAME, timeout));
+        }
+        Ok(self.handle_exit(pkg, &hook_output, status))
     }
 
     #[cfg(windows)]
@@ -338,7 +360,10 @@ pub trait PackageMaintenanceHookExt: Hook<ExitValue = ExitStatus> + Sync {
             #[cfg(not(target_os = "windows"))]
             let pkg = Pkg::from_install(package).await?;
 
-            match hook.run(package_name, &pkg, None::<&str>) {
+            let timeout = package.hook_timeouts()?
+                                 .get(hook_name)
+                                 .map(|&t| t.into());
+            match hook.run(package_name, &pkg, None::<&str>, timeout) {
                 Ok(exit_status) if exit_status.success() => Ok(()),
                 Ok(exit_status) => {
                     Err(Error::hook_exit_status(pkg.ident.clone(),
@@ -476,6 +501,53 @@ impl Hook for UninstallHook {
     fn stderr_log_path(&self) -> &Path { &self.stderr_log_path }
 }
 
+/// Watches a running hook and kills its process tree if it is still running once its timeout
+/// elapses.
+struct HookWatchdog {
+    timeout:   Duration,
+    done:      mpsc::Sender<()>,
+    timed_out: Arc<AtomicBool>,
+    handle:    thread::JoinHandle<()>,
+}
+
+impl HookWatchdog {
+    fn start(hook_name: &'static str, pid: Pid, timeout: Duration) -> Self {
+        let (done, rx) = mpsc::channel();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timed_out_for_thread = Arc::clone(&timed_out);
+        let handle = thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(timeout) {
+                timed_out_for_thread.store(true, Ordering::SeqCst);
+                debug!("{} hook (pid {}) timed out after {}s; killing it",
+                       hook_name,
+                       pid,
+                       timeout.as_secs());
+                if let Err(e) = core_process::kill_process_tree(pid) {
+                    error!("Failed to kill {} hook (pid {}): {}", hook_name, pid, e);
+                }
+            }
+        });
+        Self { timeout,
+               done,
+               timed_out,
+               handle }
+    }
+
+    /// Stop watching the hook. Returns the timeout if the hook was killed for exceeding it.
+    fn finish(self) -> Option<Duration> {
+        // If the watchdog already fired, its receiver may be gone, so a failed send is fine.
+        self.done.send(()).ok();
+        if self.handle.join().is_err() {
+            error!("Hook watchdog thread panicked");
+        }
+        if self.timed_out.load(Ordering::SeqCst) {
+            Some(self.timeout)
+        } else {
+            None
+        }
+    }
+}
+
 /// Cryptographically hash the contents of the compiled hook
 /// file.
 ///
@@ -898,14 +970,52 @@ echo "The message is Hello"
         #[cfg(unix)]
         {
             // Run the hook
-            assert!(matches!(hook.run(&service_group, &pkg, None::<&str>), Ok(exit_status) if exit_status.success()));
+            assert!(matches!(hook.run(&service_group, &pkg, None::<&str>, None), Ok(exit_status) if exit_status.success()));
 
             // Remove the hook file and try run this should fail
             std::fs::remove_dir_all(&concrete_path).expect("remove temp dir");
-            assert!(hook.run(&service_group, &pkg, None::<&str>).is_err())
+            assert!(hook.run(&service_group, &pkg, None::<&str>, None).is_err())
         }
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn running_a_hook_past_its_timeout_kills_it() {
+        let service_group = service_group();
+        let concrete_path = rendered_hooks_path();
+        let template_path = TempDir::new().expect("create temp dir");
+        create_with_content(template_path.path().join(InstallHook::FILE_NAME),
+                            "#!/bin/sh\nsleep 30 &\nwait\n");
+
+        let hook = InstallHook::load(&service_group,
+                                     &concrete_path,
+                                     &template_path,
+                                     FeatureFlag::empty()).expect("Could not create testing \
+                                                                   install hook");
+        assert!(hook.compile(&service_group, &()).unwrap());
+
+        let pg_id = PackageIdent::new("testing",
+                                      &service_group.service(),
+                                      Some("1.0.0"),
+                                      Some("20170712000000"));
+        let pkg_install = PackageInstall::new_from_parts(pg_id,
+                                                         PathBuf::from("/tmp"),
+                                                         PathBuf::from("/tmp"),
+                                                         PathBuf::from("/tmp"));
+        let pkg = Pkg::from_install(&pkg_install).await
+                                                 .expect("Could not create package!");
+
+        let start = std::time::Instant::now();
+        let result = hook.run(&service_group,
+                              &pkg,
+                              None::<&str>,
+                              Some(Duration::from_secs(1)));
+        assert!(matches!(result, Err(Error::HookTimedOut("install", _))));
+        // The backgrounded `sleep` must have been killed along with the hook, or we would still
+        // be waiting on it to close the hook's output streams.
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
     ////////////////////////////////////////////////////////////////////////
 
     #[test]
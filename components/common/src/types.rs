@@ -79,6 +79,50 @@ impl Into<String> for EventStreamMetaPair {
     fn into(self) -> String { self.to_string() }
 }
 
+/// A key-value label describing a Supervisor, used to target
+/// configuration at a subset of a service group's members.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "&str", into = "String")]
+pub struct MemberLabel {
+    pub key:   String,
+    pub value: String,
+}
+
+impl FromStr for MemberLabel {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.splitn(2, '=').collect::<Vec<_>>().as_slice() {
+            [key, value] if !key.is_empty() && !value.is_empty() => {
+                Ok(Self { key:   String::from(*key),
+                          value: String::from(*value), })
+            }
+            _ => {
+                let e = format!("Invalid label given (must be '='-delimited pair of non-empty \
+                                 strings): {}",
+                                s);
+                Err(io::Error::new(io::ErrorKind::InvalidInput, e))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MemberLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+impl std::convert::TryFrom<&str> for MemberLabel {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> { Ok(MemberLabel::from_str(s)?) }
+}
+
+impl Into<String> for MemberLabel {
+    fn into(self) -> String { self.to_string() }
+}
+
 /// Captures arbitrary key-value pair metadata to attach to all events
 /// generated by the Supervisor.
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -161,6 +205,77 @@ impl fmt::Display for EventStreamToken {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.0) }
 }
 
+/// Somewhere, other than the NATS event stream, that the Supervisor
+/// sends its events.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "&str", into = "String")]
+pub enum EventSinkAddress {
+    /// Newline-delimited JSON appended to a file (`file:PATH`).
+    File(PathBuf),
+    /// Newline-delimited JSON written to a Unix domain socket
+    /// (`unix:PATH`).
+    UnixSocket(PathBuf),
+    /// Batches of JSON events POSTed to an `http://` or `https://`
+    /// URL.
+    Webhook(String),
+    /// Syslog messages sent to the local syslog daemon (`syslog`), or
+    /// over UDP to a remote one (`syslog:IP:PORT`).
+    Syslog(Option<SocketAddr>),
+}
+
+impl EventSinkAddress {
+    /// The name of the Clap argument we'll use for arguments of this type.
+    pub const ARG_NAME: &'static str = "EVENT_SINK";
+}
+
+impl FromStr for EventSinkAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        let invalid = || Error::InvalidEventSink(s.to_string());
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(EventSinkAddress::Webhook(s.to_string()));
+        }
+        if s == "syslog" {
+            return Ok(EventSinkAddress::Syslog(None));
+        }
+        match s.splitn(2, ':').collect::<Vec<_>>().as_slice() {
+            ["file", path] if !path.is_empty() => Ok(EventSinkAddress::File(PathBuf::from(path))),
+            ["unix", path] if !path.is_empty() => {
+                Ok(EventSinkAddress::UnixSocket(PathBuf::from(path)))
+            }
+            ["syslog", address] => {
+                address.parse()
+                       .map(|address| EventSinkAddress::Syslog(Some(address)))
+                       .map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for EventSinkAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventSinkAddress::File(path) => write!(f, "file:{}", path.display()),
+            EventSinkAddress::UnixSocket(path) => write!(f, "unix:{}", path.display()),
+            EventSinkAddress::Webhook(url) => write!(f, "{}", url),
+            EventSinkAddress::Syslog(None) => write!(f, "syslog"),
+            EventSinkAddress::Syslog(Some(address)) => write!(f, "syslog:{}", address),
+        }
+    }
+}
+
+impl std::convert::TryFrom<&str> for EventSinkAddress {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> { EventSinkAddress::from_str(s) }
+}
+
+impl Into<String> for EventSinkAddress {
+    fn into(self) -> String { self.to_string() }
+}
+
 /// The event stream connection method.
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(from = "u64", into = "u64")]
@@ -430,6 +545,55 @@ mod tests {
         fn cannot_parse_from_empty_string() { assert!("".parse::<EventStreamToken>().is_err()) }
     }
 
+    mod event_sink_address {
+        use super::*;
+
+        #[test]
+        fn parses_every_kind_of_sink() {
+            let sinks = vec![("file:/tmp/events",
+                              EventSinkAddress::File(PathBuf::from("/tmp/events"))),
+                             ("unix:/run/events",
+                              EventSinkAddress::UnixSocket(PathBuf::from("/run/events"))),
+                             ("http://localhost/events",
+                              EventSinkAddress::Webhook(String::from("http://localhost/events"))),
+                             ("syslog", EventSinkAddress::Syslog(None)),
+                             ("syslog:10.0.0.5:514",
+                              EventSinkAddress::Syslog(Some("10.0.0.5:514".parse().unwrap())))];
+            for (s, expected) in sinks {
+                let address = s.parse::<EventSinkAddress>().unwrap();
+                assert_eq!(address, expected);
+                assert_eq!(address.to_string(), s);
+            }
+        }
+
+        #[test]
+        fn rejects_unknown_sinks() {
+            assert!("file:".parse::<EventSinkAddress>().is_err());
+            assert!("nats://localhost:4222".parse::<EventSinkAddress>().is_err());
+            assert!("syslog:localhost".parse::<EventSinkAddress>().is_err());
+            assert!("/tmp/events.ndjson".parse::<EventSinkAddress>().is_err());
+        }
+    }
+
+    mod member_label {
+        use super::*;
+
+        #[test]
+        fn parses_key_value_pairs() {
+            let label = "rack=r1=a".parse::<MemberLabel>().unwrap();
+            assert_eq!(label.key, "rack");
+            assert_eq!(label.value, "r1=a");
+            assert_eq!(label.to_string(), "rack=r1=a");
+        }
+
+        #[test]
+        fn rejects_missing_key_or_value() {
+            assert!("rack".parse::<MemberLabel>().is_err());
+            assert!("=r1".parse::<MemberLabel>().is_err());
+            assert!("rack=".parse::<MemberLabel>().is_err());
+        }
+    }
+
     mod gossip_listen_addr {
         use super::*;
         #[test]
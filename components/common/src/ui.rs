@@ -3,6 +3,7 @@ use crate::{api_client::DisplayProgress,
             error::{Error,
                     Result},
             output};
+use parking_lot::Mutex;
 use std::{env,
           fmt,
           fs::{self,
@@ -15,7 +16,8 @@ use std::{env,
                Write},
           process::{self,
                     Command},
-          str::FromStr};
+          str::FromStr,
+          sync::Arc};
 use termcolor::{self,
                 ColorChoice,
                 ColorSpec,
@@ -226,6 +228,7 @@ pub enum Status {
     Signed,
     Signing,
     Skipping,
+    Stopping,
     Transferred,
     Transferring,
     Updating,
@@ -282,6 +285,7 @@ impl Status {
             Status::Signed => (Glyph::CheckMark, "Signed".into(), Color::Important),
             Status::Signing => (Glyph::FingerPoint, "Signing".into(), Color::Important),
             Status::Skipping => (Glyph::Elipses, "Skipping".into(), Color::Info),
+            Status::Stopping => (Glyph::DownArrow, "Stopping".into(), Color::Info),
             Status::Transferred => (Glyph::CheckMark, "Transferred".into(), Color::Info),
             Status::Transferring => (Glyph::RightArrow, "Transferring".into(), Color::Info),
             Status::Updating => (Glyph::UpArrow, "Updating".into(), Color::Info),
@@ -928,6 +932,9 @@ impl DisplayProgress for ConsoleProgressBar {
         self.bar.set_units(pbr::Units::Bytes);
         self.bar.show_tick = true;
         self.bar.message("    ");
+        // Keep any progress already made when the size is revised, as an `AggregateProgressBar`
+        // does
+        self.bar.set(self.current);
         self.total = size;
     }
 
@@ -954,6 +961,73 @@ impl Write for ConsoleProgressBar {
     fn flush(&mut self) -> io::Result<()> { self.bar.flush() }
 }
 
+/// A progress bar shared by several concurrent operations, tracking their combined progress.
+///
+/// Each operation reports to its own `child`, and the size of the underlying progress bar is the
+/// sum of the sizes the children last reported. A clone reports for the same operation as the
+/// bar it was cloned from, so an operation which is retried can report its size again without
+/// it being counted twice.
+#[derive(Clone)]
+pub struct AggregateProgressBar {
+    progress: Arc<Mutex<AggregateProgress>>,
+    child:    usize,
+}
+
+struct AggregateProgress {
+    bar:      Box<dyn DisplayProgress>,
+    children: Vec<ChildProgress>,
+}
+
+#[derive(Default)]
+struct ChildProgress {
+    size:    u64,
+    written: u64,
+}
+
+impl AggregateProgressBar {
+    pub fn new(bar: Box<dyn DisplayProgress>) -> Self {
+        let progress = AggregateProgress { bar,
+                                           children: vec![ChildProgress::default()] };
+        AggregateProgressBar { progress: Arc::new(Mutex::new(progress)),
+                               child:    0, }
+    }
+
+    /// A progress bar for another operation reporting to the same underlying progress bar.
+    pub fn child(&self) -> Self {
+        let mut progress = self.progress.lock();
+        progress.children.push(ChildProgress::default());
+        AggregateProgressBar { progress: Arc::clone(&self.progress),
+                               child:    progress.children.len() - 1, }
+    }
+}
+
+impl DisplayProgress for AggregateProgressBar {
+    /// Replace the size of this bar's operation. What the operation has already written counts
+    /// towards its new size, as it does when a download is resumed and reports the size of what
+    /// is left.
+    fn size(&mut self, size: u64) {
+        let mut progress = self.progress.lock();
+        let child = &mut progress.children[self.child];
+        child.size = child.written + size;
+        let total = progress.children.iter().map(|c| c.size).sum::<u64>();
+        progress.bar.size(total);
+    }
+
+    // The underlying progress bar finishes once the combined size has been written
+    fn finish(&mut self) {}
+}
+
+impl Write for AggregateProgressBar {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut progress = self.progress.lock();
+        let n = progress.bar.write(buf)?;
+        progress.children[self.child].written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { self.progress.lock().bar.flush() }
+}
+
 pub fn print_wrapped<U>(stream: &mut dyn WriteColor,
                         text: U,
                         wrap_width: usize,
@@ -1 +1,62 @@
 pub mod path;
+
+use std::{fs,
+          io,
+          path::Path};
+
+/// Returns the combined size of the files under `path`, without following symbolic links.
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut size = 0;
+    if metadata.is_file() {
+        size = metadata.len();
+    } else if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            size += dir_size(&entry?.path())?;
+        }
+    }
+    Ok(size)
+}
+
+/// Formats a number of bytes in the largest unit in which it is at least one, as in `1.5 KB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn dir_size_sums_files_without_following_symlinks() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/one"), vec![0; 10]).unwrap();
+        fs::write(dir.path().join("a/b/two"), vec![0; 20]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("link")).unwrap();
+
+        assert_eq!(dir_size(dir.path()).unwrap(), 30);
+        assert_eq!(dir_size(&dir.path().join("a/one")).unwrap(), 10);
+        assert!(dir_size(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn human_size_picks_a_unit() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
@@ -108,20 +108,26 @@
 //!
 //! ## Biome artifacts
 //!
-//! A signed Biome artifact (a file with the extension `.hart`) has 5 plaintext lines followed by
-//! a binary blob of data, which is an unsigned, compressed tarfile. The lines are as follows:
+//! A signed Biome artifact (a file with the extension `.hart`) has a few plaintext header lines
+//! followed by a binary blob of data, which is an unsigned, compressed tarfile. There are two
+//! versions of the header. A `HART-1` artifact has 5 plaintext lines:
 //!
-//! 1. The artifact format version
+//! 1. The artifact format version, `HART-1`
 //! 1. The name with revision of the origin key which was used to sign the artifact
 //! 1. The hashing algorithm used, which at present is only `BLAKE2b`, but may expand in the future
 //! 1. A Base64 *signed* value of the binary blob's Base64 file hash
 //! 1. The last line is left empty, meaning that 2 newline characters (`\n`) separate the header
 //!    from the payload
 //!
-//! The remainder of the file is a compressed tarball of the contents to be extracted on disk. At
-//! present, the tarball is compressed using `xz` but is considered an implementation detail. Also
-//! note unlike the format of keys, the compressed tarball is **not** Base64 encoded--it is the
-//! compressed tarball itself.
+//! A `HART-2` artifact has 6 plaintext lines. The format version, `HART-2`, is followed by a line
+//! naming the compression of the tarball, currently only `zstd`, and then by the same 4 lines as
+//! a `HART-1` header.
+//!
+//! The remainder of the file is a compressed tarball of the contents to be extracted on disk. The
+//! tarball of a `HART-1` artifact is always compressed using `xz`, so that it can be read by
+//! every release of Biome; artifacts are only signed as `HART-2` when their tarball is compressed
+//! another way. Also note unlike the format of keys, the compressed tarball is **not** Base64
+//! encoded--it is the compressed tarball itself.
 //!
 //! Note that the BLAKE2b hash functions use a digest length of 32 bytes (256 bits!). More details
 //! about the hashing strategy can be found in the [libsodium hashing
@@ -130,7 +136,7 @@
 //! Signing uses a secret origin key, while verifying uses the public origin key. Thus, it it safe
 //! to distribute public origin keys.
 //!
-//! Example header:
+//! Example headers:
 //!
 //! ```text
 //! HART-1
@@ -141,6 +147,16 @@
 //! <binary-blob>
 //! ```
 //!
+//! ```text
+//! HART-2
+//! zstd
+//! biome-20160405144945
+//! BLAKE2b
+//! signed BLAKE2b signature
+//!
+//! <binary-blob>
+//! ```
+//!
 //! Due to the simple, line-driven structure of the header it's possible to examine the contents of
 //! a Biome artifact using standard Unix tooling:
 //!
@@ -159,16 +175,18 @@
 //!
 //! ```text
 //! tail -n +6 /tmp/somefile.hart > somefile.tar.xz
+//! tail -n +7 /tmp/somefile.hart > somefile.tar.zst
 //! ```
 //!
-//! The above command starts streaming the file to standard out at line 6, skipping the first 5
-//! plaintext lines.
+//! The above commands start streaming the file to standard out after the 5 plaintext lines of a
+//! `HART-1` header, or the 6 of a `HART-2` header.
 //!
 //! If the Biome artifact needs to be extracted on disk without verifying its integrity or
 //! authenticity, this can be accomplished with:
 //!
 //! ```text
 //! tail -n +6 /tmp/somefile.hart | xzcat | tar x -C /
+//! tail -n +7 /tmp/somefile.hart | zstdcat | tar x -C /
 //! ```
 //!
 //! **Caution!** Working with Biome artifacts in this manner this is not normally recommended and
@@ -229,6 +247,12 @@ pub const SIG_HASH_TYPE: &str = "BLAKE2b";
 /// at runtime. This is useful for testing.
 pub const CACHE_KEY_PATH_ENV_VAR: &str = "HAB_CACHE_KEY_PATH";
 pub const HART_FORMAT_VERSION: &str = "HART-1";
+/// Hart format whose header declares the compression of the payload
+pub const HART_FORMAT_VERSION_2: &str = "HART-2";
+/// Format of the `.sig` file holding the detached signatures of a hart
+pub const DETACHED_SIG_FORMAT_VERSION: &str = "HART-SIG-1";
+/// The suffix appended to a hart's file name to find its detached signatures
+pub const DETACHED_SIG_SUFFIX: &str = "sig";
 pub const BOX_FORMAT_VERSION: &str = "BOX-1";
 pub const ANONYMOUS_BOX_FORMAT_VERSION: &str = "ANONYMOUS-BOX-1";
 
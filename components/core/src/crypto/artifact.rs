@@ -3,28 +3,86 @@ use crate::{crypto::{keys::{Key,
                             NamedRevision,
                             SecretOriginSigningKey},
                      Blake2bHash,
+                     DETACHED_SIG_FORMAT_VERSION,
+                     DETACHED_SIG_SUFFIX,
                      HART_FORMAT_VERSION,
+                     HART_FORMAT_VERSION_2,
                      SIG_HASH_TYPE},
             error::{Error,
                     Result}};
-use std::{fs::File,
+use std::{fmt,
+          fs::File,
           io::{self,
                prelude::*,
                BufRead,
                BufReader,
                BufWriter},
-          path::Path};
+          path::{Path,
+                 PathBuf},
+          str::FromStr};
+
+/// Leading bytes of an xz stream
+const XZ_MAGIC: &[u8] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+/// Leading bytes of a zstd frame
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+/// The compression algorithm used for the tarball carried in a hart
+/// file. `HART-1` artifacts are always xz; `HART-2` artifacts declare
+/// the algorithm in their header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Sniff the compression of a payload from its leading bytes.
+    fn detect(payload: &[u8]) -> Option<Self> {
+        if payload.starts_with(XZ_MAGIC) {
+            Some(Compression::Xz)
+        } else if payload.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "xz" => Ok(Compression::Xz),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(Error::CryptoError(format!("Unsupported compression: {}", value))),
+        }
+    }
+}
 
 pub struct ArtifactHeader {
-    format:    String,
-    signer:    NamedRevision,
-    hash_type: String,
-    signature: Vec<u8>,
+    format:      String,
+    compression: Compression,
+    signer:      NamedRevision,
+    hash_type:   String,
+    signature:   Vec<u8>,
 }
 
 impl ArtifactHeader {
     pub fn format(&self) -> &String { &self.format }
 
+    pub fn compression(&self) -> Compression { self.compression }
+
     pub fn signer(&self) -> &NamedRevision { &self.signer }
 
     pub fn hash_type(&self) -> &String { &self.hash_type }
@@ -36,20 +94,43 @@ impl ArtifactHeader {
 }
 
 /// Generate and sign a package
+///
+/// A zstd-compressed payload produces a `HART-2` artifact which records its
+/// compression in the header; any other payload produces a `HART-1` artifact
+/// so that existing consumers can continue to read it.
 pub fn sign<P1: ?Sized, P2: ?Sized>(src: &P1, dst: &P2, key: &SecretOriginSigningKey) -> Result<()>
     where P1: AsRef<Path>,
           P2: AsRef<Path>
 {
     let signature = key.sign(src)?;
+    let mut file = File::open(src)?;
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    file.by_ref()
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    file.seek(io::SeekFrom::Start(0))?;
+
     let output_file = File::create(dst)?;
     let mut writer = BufWriter::new(&output_file);
-    write!(writer,
-           "{}\n{}\n{}\n{}\n\n",
-           HART_FORMAT_VERSION,
-           key.named_revision(),
-           SIG_HASH_TYPE,
-           base64::encode(&signature))?;
-    let mut file = File::open(src)?;
+    match Compression::detect(&magic) {
+        Some(Compression::Zstd) => {
+            write!(writer,
+                   "{}\n{}\n{}\n{}\n{}\n\n",
+                   HART_FORMAT_VERSION_2,
+                   Compression::Zstd,
+                   key.named_revision(),
+                   SIG_HASH_TYPE,
+                   base64::encode(&signature))?
+        }
+        _ => {
+            write!(writer,
+                   "{}\n{}\n{}\n{}\n\n",
+                   HART_FORMAT_VERSION,
+                   key.named_revision(),
+                   SIG_HASH_TYPE,
+                   base64::encode(&signature))?
+        }
+    }
     io::copy(&mut file, &mut writer)?;
     Ok(())
 }
@@ -72,7 +153,10 @@ pub fn get_artifact_header<P>(src: P) -> Result<ArtifactHeader>
     Ok(header)
 }
 
-fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRead)>
+/// Read the header of the artifact and return it along with a BufReader to
+/// the compressed .tar bytestream that follows it. Callers decompressing the
+/// payload should consult `ArtifactHeader::compression`.
+pub fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRead)>
     where P: AsRef<Path>
 {
     let f = File::open(path)?;
@@ -86,7 +170,7 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
                                         .to_string()))
     } else {
         let line = line.trim();
-        if line != HART_FORMAT_VERSION {
+        if line != HART_FORMAT_VERSION && line != HART_FORMAT_VERSION_2 {
             Err(Error::CryptoError(format!("Unsupported format version: \
                                             {}",
                                            line)))
@@ -95,7 +179,22 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
         }
     }?;
 
-    // Second line is the revision of the signing key used.
+    // HART-2 declares the payload compression on the next line; HART-1
+    // payloads are always xz.
+    let compression = if format == HART_FORMAT_VERSION_2 {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            Err(Error::CryptoError("Corrupt payload, can't read \
+                                    compression"
+                                                .to_string()))
+        } else {
+            line.trim().parse::<Compression>()
+        }?
+    } else {
+        Compression::Xz
+    };
+
+    // Next line is the revision of the signing key used.
     let mut line = String::new();
     let named_revision = if reader.read_line(&mut line)? == 0 {
         Err(Error::CryptoError("Corrupt payload, can't read origin \
@@ -106,7 +205,7 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
         line.parse::<NamedRevision>()
     }?;
 
-    // Third line is the hash type of the signature.
+    // Next line is the hash type of the signature.
     let mut line = String::new();
     let hash_type = if reader.read_line(&mut line)? == 0 {
         Err(Error::CryptoError("Corrupt payload, can't read hash type".to_string()))
@@ -121,7 +220,7 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
         }
     }?;
 
-    // Fourth line is the base64-encoded signature.
+    // Next line is the base64-encoded signature.
     let mut line = String::new();
     let signature = if reader.read_line(&mut line)? == 0 {
         Err(Error::CryptoError("Corrupt payload, can't read signature".to_string()))
@@ -132,7 +231,7 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
                             })
     }?;
 
-    // Fifth line should be an empty delimiter line.
+    // Last line of the header should be an empty delimiter line.
     let mut line = String::new();
     if reader.read_line(&mut line)? == 0 {
         Err(Error::CryptoError("Corrupt payload, can't find end of \
@@ -154,6 +253,7 @@ fn artifact_header_and_archive<P>(path: P) -> Result<(ArtifactHeader, impl BufRe
     // of the file for further processing (either signature
     // verification or decompression).
     let header = ArtifactHeader { format,
+                                  compression,
                                   signer: named_revision,
                                   hash_type,
                                   signature };
@@ -181,10 +281,153 @@ pub fn artifact_signer<P>(hart_file_path: P) -> Result<NamedRevision>
     Ok(header.signer)
 }
 
+/// A signature over the payload of a hart made by a key other than the
+/// one named in its header, such as a release-approval key. These are
+/// kept in a `.sig` file next to the artifact, one per line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetachedSignature {
+    signer:    NamedRevision,
+    hash_type: String,
+    signature: Vec<u8>,
+}
+
+impl DetachedSignature {
+    pub fn signer(&self) -> &NamedRevision { &self.signer }
+
+    pub fn hash_type(&self) -> &String { &self.hash_type }
+
+    pub fn encoded_signature(&self) -> String { base64::encode(&self.signature) }
+}
+
+impl fmt::Display for DetachedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} {} {}",
+               self.signer,
+               self.hash_type,
+               self.encoded_signature())
+    }
+}
+
+impl FromStr for DetachedSignature {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 3 {
+            return Err(Error::CryptoError(format!("Malformed detached \
+                                                   signature: {}",
+                                                  line)));
+        }
+        if fields[1] != SIG_HASH_TYPE {
+            return Err(Error::CryptoError(format!("Unsupported signature type: \
+                                                   {}",
+                                                  fields[1])));
+        }
+        let signature = base64::decode(fields[2]).map_err(|e| {
+                                                     Error::CryptoError(format!("Can't decode \
+                                                                                 signature: {}",
+                                                                                e))
+                                                 })?;
+        Ok(DetachedSignature { signer: fields[0].parse()?,
+                               hash_type: fields[1].to_string(),
+                               signature })
+    }
+}
+
+/// The path of the file holding the detached signatures of a hart,
+/// which is the artifact's own path with `.sig` appended.
+pub fn detached_signature_path<P>(hart_file_path: P) -> PathBuf
+    where P: AsRef<Path>
+{
+    let mut path = hart_file_path.as_ref().as_os_str().to_owned();
+    path.push(".");
+    path.push(DETACHED_SIG_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Read the detached signatures of a hart. An artifact without a `.sig`
+/// file simply has none.
+pub fn detached_signatures<P>(hart_file_path: P) -> Result<Vec<DetachedSignature>>
+    where P: AsRef<Path>
+{
+    let file = match File::open(detached_signature_path(hart_file_path)) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut lines = BufReader::new(file).lines();
+    match lines.next().transpose()? {
+        Some(ref line) if line.trim() == DETACHED_SIG_FORMAT_VERSION => {}
+        Some(line) => {
+            return Err(Error::CryptoError(format!("Unsupported format version: \
+                                                   {}",
+                                                  line.trim())));
+        }
+        None => {
+            return Err(Error::CryptoError("Corrupt signature file, can't read \
+                                           format version"
+                                                          .to_string()));
+        }
+    }
+
+    let mut signatures = Vec::new();
+    for line in lines {
+        let line = line?;
+        if !line.trim().is_empty() {
+            signatures.push(line.parse()?);
+        }
+    }
+    Ok(signatures)
+}
+
+/// Add a detached signature by `key` to an existing hart, replacing any
+/// earlier signature made with the same key revision. Returns the path
+/// of the `.sig` file that was written.
+///
+/// The payload is signed exactly as it is for the header signature, so
+/// the same content hash is attested by every signer.
+pub fn sign_detached<P>(hart_file_path: P, key: &SecretOriginSigningKey) -> Result<PathBuf>
+    where P: AsRef<Path>
+{
+    let hart_file_path = hart_file_path.as_ref();
+    let (_header, mut reader) = artifact_header_and_archive(hart_file_path)?;
+    let signature = DetachedSignature { signer:    key.named_revision().clone(),
+                                        hash_type: SIG_HASH_TYPE.to_string(),
+                                        signature: key.sign_reader(&mut reader)?, };
+
+    let mut signatures = detached_signatures(hart_file_path)?;
+    signatures.retain(|existing| existing.signer != signature.signer);
+    signatures.push(signature);
+
+    let path = detached_signature_path(hart_file_path);
+    let mut writer = BufWriter::new(File::create(&path)?);
+    writeln!(writer, "{}", DETACHED_SIG_FORMAT_VERSION)?;
+    for signature in signatures {
+        writeln!(writer, "{}", signature)?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// Verify a detached signature against the payload of a hart, returning
+/// the Blake2b hash of the payload.
+pub fn verify_detached<P>(hart_file_path: P,
+                          signature: &DetachedSignature,
+                          cache: &KeyCache)
+                          -> Result<Blake2bHash>
+    where P: AsRef<Path>
+{
+    let (_header, mut reader) = artifact_header_and_archive(hart_file_path)?;
+    let key = cache.public_signing_key(&signature.signer)?;
+    key.verify(signature.signature.as_slice(), &mut reader)
+}
+
 #[cfg(test)]
 mod test {
     use super::{super::{test_support::*,
                         HART_FORMAT_VERSION,
+                        HART_FORMAT_VERSION_2,
                         SIG_HASH_TYPE},
                 *};
 
@@ -370,11 +613,113 @@ mod test {
 
         let hart_header = get_artifact_header(&dst).unwrap();
         assert_eq!(HART_FORMAT_VERSION, hart_header.format());
+        assert_eq!(Compression::Xz, hart_header.compression());
         assert_eq!("unicorn", hart_header.signer().name());
         assert_eq!(SIG_HASH_TYPE, hart_header.hash_type());
         assert!(!hart_header.encoded_signature().is_empty());
     }
 
+    #[test]
+    #[should_panic(expected = "Unsupported compression: lzma")]
+    fn verify_invalid_compression() {
+        let (cache, dir) = new_cache();
+
+        let dst = dir.path().join("signed.dat");
+        let mut f = File::create(&dst).unwrap();
+        f.write_all(b"HART-2\nlzma\nuhoh").unwrap();
+
+        verify(&dst, &cache).unwrap();
+    }
+
+    #[test]
+    fn sign_zstd_payload_writes_hart_2_header() {
+        let (cache, dir) = new_cache();
+        let (_public, secret) = cache.new_signing_pair("unicorn").unwrap();
+
+        let src = dir.path().join("src.tar.zst");
+        let dst = dir.path().join("src.signed");
+        let mut f = File::create(&src).unwrap();
+        f.write_all(ZSTD_MAGIC).unwrap();
+        f.write_all(b"hearty goodness").unwrap();
+        sign(&src, &dst, &secret).unwrap();
+
+        let hart_header = get_artifact_header(&dst).unwrap();
+        assert_eq!(HART_FORMAT_VERSION_2, hart_header.format());
+        assert_eq!(Compression::Zstd, hart_header.compression());
+        assert_eq!("unicorn", hart_header.signer().name());
+
+        let mut buffer = Vec::new();
+        get_archive_reader(&dst).unwrap()
+                                .read_to_end(&mut buffer)
+                                .unwrap();
+        assert_eq!(&buffer[..ZSTD_MAGIC.len()], ZSTD_MAGIC);
+        assert_eq!(&buffer[ZSTD_MAGIC.len()..], b"hearty goodness");
+
+        let (signer, _hash) = verify(&dst, &cache).unwrap();
+        assert_eq!("unicorn", signer.name());
+    }
+
+    #[test]
+    fn sign_detached_adds_verifiable_signatures() {
+        let (cache, dir) = new_cache();
+        let (_public, origin) = cache.new_signing_pair("unicorn").unwrap();
+        let (_public, release) = cache.new_signing_pair("release").unwrap();
+        let (_public, audit) = cache.new_signing_pair("audit").unwrap();
+
+        let dst = dir.path().join("signed.hart");
+        sign(&fixture("signme.dat"), &dst, &origin).unwrap();
+        assert!(detached_signatures(&dst).unwrap().is_empty());
+
+        let sig_path = sign_detached(&dst, &release).unwrap();
+        assert_eq!(dir.path().join("signed.hart.sig"), sig_path);
+        sign_detached(&dst, &audit).unwrap();
+        // Signing again with the same key replaces its earlier signature
+        sign_detached(&dst, &release).unwrap();
+
+        let signatures = detached_signatures(&dst).unwrap();
+        let signers: Vec<&str> = signatures.iter()
+                                           .map(|s| s.signer().name().as_str())
+                                           .collect();
+        assert_eq!(vec!["audit", "release"], signers);
+
+        let (_signer, hash) = verify(&dst, &cache).unwrap();
+        for signature in signatures.iter() {
+            assert_eq!(hash, verify_detached(&dst, signature, &cache).unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Biome artifact is invalid")]
+    fn verify_detached_rejects_signature_for_other_payload() {
+        let (cache, dir) = new_cache();
+        let (_public, origin) = cache.new_signing_pair("unicorn").unwrap();
+        let (_public, release) = cache.new_signing_pair("release").unwrap();
+
+        let src = dir.path().join("src.in");
+        let mut f = File::create(&src).unwrap();
+        f.write_all(b"hearty goodness").unwrap();
+        let signed = dir.path().join("signed.hart");
+        let other = dir.path().join("other.hart");
+        sign(&src, &signed, &origin).unwrap();
+        sign(&fixture("signme.dat"), &other, &origin).unwrap();
+
+        sign_detached(&signed, &release).unwrap();
+        let signature = detached_signatures(&signed).unwrap().remove(0);
+        verify_detached(&other, &signature, &cache).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported format version: HART-1")]
+    fn detached_signatures_invalid_format_version() {
+        let (_cache, dir) = new_cache();
+
+        let dst = dir.path().join("signed.hart");
+        let mut f = File::create(detached_signature_path(&dst)).unwrap();
+        f.write_all(b"HART-1\n").unwrap();
+
+        detached_signatures(&dst).unwrap();
+    }
+
     mod artifact_header {
         use super::*;
 
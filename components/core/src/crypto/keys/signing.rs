@@ -100,6 +100,13 @@ impl SecretOriginSigningKey {
         Ok(self.sign_inner(hex_encoded_hash.to_string().as_bytes()))
     }
 
+    /// Like `sign`, but for content that is read from a stream rather
+    /// than a whole file, such as the payload of an existing artifact.
+    pub fn sign_reader(&self, content: &mut dyn Read) -> Result<Vec<u8>> {
+        let hex_encoded_hash = hash::hash_reader(content)?;
+        Ok(self.sign_inner(hex_encoded_hash.to_string().as_bytes()))
+    }
+
     /// Does the actual heavy lifting of signing a string of bytes.
     ///
     /// Mainly separate to facilitate testing.
@@ -81,6 +81,8 @@ pub enum Error {
     FullyQualifiedPackageIdentRequired(String),
     /// Occurs when a service binding cannot be successfully parsed.
     InvalidBinding(String),
+    /// Occurs when a hook timeout override cannot be successfully parsed.
+    InvalidHookTimeout(String),
     /// Occurs when an origin is in an invalid format
     InvalidOrigin(String),
     /// Occurs when a package identifier string cannot be successfully parsed.
@@ -97,6 +99,8 @@ pub enum Error {
     InvalidServiceGroup(String),
     /// Occurs when a Url is in an invalid format.
     InvalidUrl(String),
+    /// Occurs when a package version constraint cannot be successfully parsed.
+    InvalidVersionConstraint(String),
     /// Occurs when making lower level IO calls.
     IO(io::Error),
     /// Errors when joining paths :)
@@ -272,6 +276,11 @@ impl fmt::Display for Error {
                          <NAME> is a service name, and <SERVICE_GROUP> is a valid service group",
                         binding)
             }
+            Error::InvalidHookTimeout(ref timeout) => {
+                format!("Invalid hook timeout '{}', must be of the form <HOOK>=<SECONDS> where \
+                         <HOOK> is a lifecycle hook name, and <SECONDS> is a positive integer",
+                        timeout)
+            }
             Error::InvalidOrigin(ref origin) => {
                 format!("Invalid origin: {}. Origins must begin with a lowercase letter or \
                          number. Allowed characters include lowercase letters, numbers, -, and _. \
@@ -299,6 +308,12 @@ impl fmt::Display for Error {
                         e)
             }
             Error::InvalidUrl(ref url) => format!("Invalid url: {}", url),
+            Error::InvalidVersionConstraint(ref e) => {
+                format!("Invalid version constraint: {}. A valid constraint is a comma separated \
+                         list of versions prefixed with =, >, >=, <, <=, ~ or ^ (example: \
+                         >=1.1,<3)",
+                        e)
+            }
             Error::IO(ref err) => format!("{}", err),
             Error::JoinPathsError(ref err) => format!("{}", err),
             Error::LogonTypeNotGranted => {
@@ -19,6 +19,7 @@ pub use implementation::{become_command,
                          can_run_services_as_svc_user,
                          current_pid,
                          is_alive,
+                         kill_process_tree,
                          Pid};
 
 #[cfg(unix)]
@@ -55,6 +55,21 @@ pub fn signal(pid: Pid, signal: Signal) -> Result<()> {
     }
 }
 
+/// Forcefully kill a process along with all of its descendants.
+///
+/// If the process leads its own process group (as all hooks do), the
+/// entire group is killed, which takes any children it spawned along
+/// with it.
+pub fn kill_process_tree(pid: Pid) -> Result<()> {
+    let pgid = unsafe { libc::getpgid(pid) };
+    if pgid == pid {
+        // Signaling the negated pid signals every member of the group
+        signal(-pid, Signal::KILL)
+    } else {
+        signal(pid, Signal::KILL)
+    }
+}
+
 // This only makes sense on Unix platforms, because not all of these
 // symbols are actually defined on Windows. Also, this is only used
 // for actually sending the given signal to a process, which only
@@ -1,21 +1,30 @@
 use crate::error::{Error,
                    Result};
-use std::{ffi::OsString,
+use std::{collections::HashMap,
+          ffi::OsString,
           io,
+          mem,
           path::PathBuf,
           process::{self,
                     Command}};
 use winapi::{shared::minwindef::{DWORD,
                                  FALSE,
-                                 LPDWORD},
-             um::{handleapi,
+                                 LPDWORD,
+                                 MAX_PATH},
+             um::{handleapi::{self,
+                              INVALID_HANDLE_VALUE},
                   processthreadsapi,
+                  tlhelp32::{self,
+                             LPPROCESSENTRY32W,
+                             PROCESSENTRY32W,
+                             TH32CS_SNAPPROCESS},
                   winnt::{HANDLE,
                           PROCESS_QUERY_LIMITED_INFORMATION,
                           PROCESS_TERMINATE,
                           SYNCHRONIZE}}};
 
 const STILL_ACTIVE: u32 = 259;
+type ProcessTable = HashMap<DWORD, Vec<DWORD>>;
 
 pub type Pid = DWORD;
 
@@ -78,6 +87,13 @@ pub fn terminate(pid: Pid) -> Result<()> {
     Ok(())
 }
 
+/// Forcefully terminate a process along with all of its descendants.
+pub fn kill_process_tree(pid: Pid) -> Result<()> {
+    let proc_table = build_proc_table();
+    terminate_process_descendants(&proc_table, pid);
+    Ok(())
+}
+
 /// Executes a command as a child process and exits with the child's exit code.
 ///
 /// Note that if successful, this function will not return.
@@ -111,3 +127,65 @@ fn exit_status(handle: HANDLE) -> Result<u32> {
 }
 
 pub fn can_run_services_as_svc_user() -> bool { true }
+
+fn build_proc_table() -> ProcessTable {
+    let processes_snap_handle =
+        unsafe { tlhelp32::CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+
+    if processes_snap_handle == INVALID_HANDLE_VALUE {
+        error!("Failed to call CreateToolhelp32Snapshot: {}",
+               io::Error::last_os_error());
+        return ProcessTable::new();
+    }
+    let mut table = ProcessTable::new();
+    let mut process_entry = PROCESSENTRY32W { dwSize:              mem::size_of::<PROCESSENTRY32W>()
+                                                                   as u32,
+                                              cntUsage:            0,
+                                              th32ProcessID:       0,
+                                              th32DefaultHeapID:   0,
+                                              th32ModuleID:        0,
+                                              cntThreads:          0,
+                                              th32ParentProcessID: 0,
+                                              pcPriClassBase:      0,
+                                              dwFlags:             0,
+                                              szExeFile:           [0; MAX_PATH], };
+    // Get the first process from the snapshot.
+    match unsafe {
+              tlhelp32::Process32FirstW(processes_snap_handle,
+                                        &mut process_entry as LPPROCESSENTRY32W)
+          } {
+        1 => {
+            // First process worked, so record every process under its parent.
+            let mut process_success: i32 = 1;
+            while process_success == 1 {
+                let children = table.entry(process_entry.th32ParentProcessID)
+                                    .or_insert_with(Vec::new);
+                (*children).push(process_entry.th32ProcessID);
+                process_success =
+                    unsafe { tlhelp32::Process32NextW(processes_snap_handle, &mut process_entry) };
+            }
+            unsafe { handleapi::CloseHandle(processes_snap_handle) };
+        }
+        _ => unsafe {
+            handleapi::CloseHandle(processes_snap_handle);
+        },
+    }
+    table
+}
+
+fn terminate_process_descendants(table: &ProcessTable, pid: DWORD) {
+    if let Some(children) = table.get(&pid) {
+        for child in children {
+            terminate_process_descendants(table, *child);
+        }
+    }
+    unsafe {
+        if let Some(h) = handle_from_pid(pid) {
+            if processthreadsapi::TerminateProcess(h, 1) == 0 {
+                error!("Failed to call TerminateProcess on pid {}: {}",
+                       pid,
+                       io::Error::last_os_error());
+            }
+        }
+    }
+}
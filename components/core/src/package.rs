@@ -1,4 +1,5 @@
 pub mod archive;
+pub mod constraint;
 pub mod ident;
 pub mod install;
 pub mod list;
@@ -9,6 +10,7 @@ pub mod target;
 pub use self::{archive::{FromArchive,
                          PackageArchive,
                          PackageArchiveInfo},
+               constraint::VersionConstraint,
                ident::{FullyQualifiedPackageIdent,
                        Identifiable,
                        PackageIdent},
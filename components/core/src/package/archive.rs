@@ -3,7 +3,8 @@ use super::{metadata::{MetaFile,
             Identifiable,
             PackageIdent,
             PackageTarget};
-use crate::{crypto::{artifact,
+use crate::{crypto::{artifact::{self,
+                                Compression},
                      hash},
             error::{Error,
                     Result},
@@ -23,6 +24,7 @@ use std::{collections::HashMap,
           string::ToString};
 use tar::Archive;
 use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
 
 lazy_static::lazy_static! {
     static ref METAFILE_REGXS: HashMap<MetaFile, Regex> = {
@@ -329,15 +331,19 @@ impl PackageArchive {
     /// * If the package cannot be unpacked
     pub fn unpack(&self, fs_root_path: Option<&Path>) -> Result<()> {
         let root = fs_root_path.unwrap_or_else(|| Path::new("/"));
-        let hart_payload_tar_xz = artifact::get_archive_reader(&self.path)?;
-        let decoder = XzDecoder::new(hart_payload_tar_xz);
-        let mut tar = Archive::new(decoder);
+        let mut tar = self.tar()?;
         tar.set_preserve_permissions(true);
         tar.set_preserve_mtime(true);
         tar.unpack(root)?;
         Ok(())
     }
 
+    /// Returns the decompressed tarball carried by the package, for reading its entries without
+    /// unpacking them.
+    pub fn tar(&self) -> Result<Archive<Box<dyn Read>>> {
+        Ok(Archive::new(Self::payload_reader(&self.path)?))
+    }
+
     fn read_deps(&mut self, file: MetaFile) -> Result<Vec<PackageIdent>> {
         let mut deps = vec![];
 
@@ -362,11 +368,20 @@ impl PackageArchive {
         self.metadata.get(&file).map(String::as_str)
     }
 
+    /// Returns a reader over the decompressed .tar bytestream of the hart,
+    /// decoded according to the compression declared in its header.
+    fn payload_reader(path: impl AsRef<Path>) -> Result<Box<dyn Read>> {
+        let (header, payload) = artifact::artifact_header_and_archive(path.as_ref().to_path_buf())?;
+        let decoder: Box<dyn Read> = match header.compression() {
+            Compression::Xz => Box::new(XzDecoder::new(payload)),
+            Compression::Zstd => Box::new(ZstdDecoder::with_buffer(payload)?),
+        };
+        Ok(decoder)
+    }
+
     fn get_all_metadata(path: impl AsRef<Path>) -> Result<Metadata> {
         let mut metadata = Metadata::new();
-        let hart_payload_tar_xz = artifact::get_archive_reader(path)?;
-        let decoder = XzDecoder::new(hart_payload_tar_xz);
-        let mut tar = Archive::new(decoder);
+        let mut tar = Archive::new(Self::payload_reader(path)?);
 
         // Check all entries in the tar archive for metafiles and add them to the `Metadata` store
         for entry in tar.entries()? {
@@ -413,6 +428,7 @@ pub trait FromArchive: Sized {
 #[derive(Serialize)]
 pub struct PackageArchiveInfo {
     pub format_version: String,
+    pub compression:    String,
     pub key_name:       String,
     pub hash_type:      String,
     // This should probably be called `encoded_signature`, or perhaps
@@ -447,6 +463,7 @@ impl TryFrom<PackageArchive> for PackageArchiveInfo {
         let header = artifact::get_artifact_header(&archive.path)?;
         let ident: FullyQualifiedPackageIdent = archive.ident()?.try_into()?;
         Ok(PackageArchiveInfo { format_version: header.format().clone(),
+                                compression:    header.compression().to_string(),
 
                                 // TODO (CM): NamedRevision!
                                 key_name:      header.signer().to_string(),
@@ -512,6 +529,36 @@ mod test {
         assert_eq!(ident.release, Some("20160427165340".to_string()));
     }
 
+    #[test]
+    fn reading_zstd_artifact_metadata() {
+        let (cache, dir) = crate::crypto::test_support::new_cache();
+        let (_public, secret) = cache.new_signing_pair("happyhumans").unwrap();
+
+        let ident = "happyhumans/possums/8.1.4/20160427165340";
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(ident.len() as u64);
+        header.set_mode(0o644);
+        tar.append_data(&mut header,
+                        format!("hab/pkgs/{}/{}", ident, MetaFile::Ident),
+                        ident.as_bytes())
+           .unwrap();
+        let payload = zstd::encode_all(tar.into_inner().unwrap().as_slice(), 0).unwrap();
+
+        let src = dir.path().join("possums.tar.zst");
+        let dst = dir.path().join("possums.hart");
+        std::fs::write(&src, payload).unwrap();
+        crate::crypto::artifact::sign(&src, &dst, &secret).unwrap();
+
+        let mut hart = PackageArchive::new(&dst).unwrap();
+        assert_eq!(hart.ident().unwrap().to_string(), ident);
+
+        let fs_root = dir.path().join("root");
+        hart.unpack(Some(&fs_root)).unwrap();
+        let unpacked = fs_root.join("hab/pkgs").join(ident).join("IDENT");
+        assert_eq!(std::fs::read_to_string(unpacked).unwrap(), ident);
+    }
+
     #[test]
     fn reading_artifact_extended_metadata() {
         let hart =
@@ -519,6 +566,7 @@ mod test {
                                                  4-20160427165340-x86_64-linux.hart")).unwrap();
         let info = PackageArchiveInfo::from_path(hart.path).unwrap();
         assert_eq!(info.format_version, "HART-1");
+        assert_eq!(info.compression, "xz");
         assert_eq!(info.key_name, "happyhumans-20160424223347");
         assert_eq!(info.hash_type, "BLAKE2b");
         assert_eq!(info.signature_raw, "AgdmAKa9wr4ExnSWe5rg2VJh6cc2vOfyXCs3JOnsSm1XtmtQNhhON6fhgp0hW0xZkNcgXmC1lQ7w5WdZU0M4Bjg4MDVlNTU3NWFiOGMwMjllNmQyNTgyNjEzNzlmYmQwMmQ0YmIzZDkwZTIwNjg0N2Q0NTUzYTFiZjczOTVkNjU=");
@@ -536,6 +584,7 @@ mod test {
         let info = PackageArchiveInfo::from_path(hart.path).unwrap();
         let expected = serde_json::json!({
         "format_version": "HART-1",
+        "compression": "xz",
         "key_name": "happyhumans-20160424223347",
         "hash_type": "BLAKE2b",
         "signature_raw": "U0cp/+npru0ZxhK76zm+PDVSV/707siyrO1r7T6CZZ4ShSLrIxyx8jLSMr5wnLuGrVIV358smQPWOSTOmyfFCjBmMmM1ZjRkZTE0NWM3Zjc4NjAxY2FhZTljN2I4NzY3MDk4NDEzZDA1NzM5ZGU5MTNjMDEyOTIyYjdlZWQ3NjA=",
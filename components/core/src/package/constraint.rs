@@ -0,0 +1,278 @@
+//! Version constraints which can stand in for the version and release of a package identifier.
+//!
+//! A constraint is a comma separated list of comparators, all of which must hold for a version to
+//! match, as in `core/openssl@>=1.1,<3`. Each comparator is a version prefixed with one of:
+//!
+//! * `=`: exactly the version
+//! * `>`, `>=`, `<`, `<=`: ordered against the version using [`version_sort`]
+//! * `~`: at least the version, only allowing the last given component after the first to change
+//!   (`~1.2.3` is `>=1.2.3,<1.3`, `~1.2` is `>=1.2,<1.3` and `~1` is `>=1,<2`)
+//! * `^`: at least the version, without changing the left-most non-zero component (`^1.2.3` is
+//!   `>=1.2.3,<2`, `^0.2.3` is `>=0.2.3,<0.3`)
+//!
+//! A version with no prefix is treated as `^` and `*` matches any version. Versions which cannot be
+//! ordered by [`version_sort`] never match a constraint.
+//!
+//! Constraints are only given after an `@`, and are carried in the `constraint` field of a
+//! `PackageIdent` whose version and release are unset.
+//!
+//! [`version_sort`]: ../ident/fn.version_sort.html
+
+use super::ident::{split_version,
+                   version_sort};
+use crate::{error::{Error,
+                    Result},
+            util};
+use serde::{Deserialize,
+            Deserializer,
+            Serialize,
+            Serializer};
+use std::{cmp::Ordering,
+          fmt,
+          result,
+          str::FromStr};
+
+const CONSTRAINT_CHARS: &[char] = &['=', '<', '>', '~', '^', '*', ','];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Op {
+    Any,
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+impl Op {
+    fn prefix(self) -> &'static str {
+        match self {
+            Op::Any => "*",
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Tilde => "~",
+            Op::Caret => "^",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Comparator {
+    op:      Op,
+    version: String,
+}
+
+impl Comparator {
+    fn matches(&self, version: &str) -> bool {
+        if self.op == Op::Any {
+            return true;
+        }
+        let ordering = match version_sort(version, &self.version) {
+            Ok(ordering) => ordering,
+            Err(_) => return false,
+        };
+        match self.op {
+            Op::Any => true,
+            Op::Exact => ordering == Ordering::Equal,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEq => ordering != Ordering::Less,
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEq => ordering != Ordering::Greater,
+            Op::Tilde | Op::Caret => {
+                ordering != Ordering::Less
+                && version_sort(version, &self.upper_bound()).ok() == Some(Ordering::Less)
+            }
+        }
+    }
+
+    /// The exclusive upper bound of a `~` or `^` comparator.
+    fn upper_bound(&self) -> String {
+        let mut parts =
+            numeric_parts(&self.version).expect("comparator versions are validated on parse");
+        let bump = match self.op {
+            Op::Tilde => parts.len().min(2) - 1,
+            _ => {
+                parts.iter()
+                     .position(|&part| part != 0)
+                     .unwrap_or(parts.len() - 1)
+            }
+        };
+        parts.truncate(bump + 1);
+        parts[bump] += 1;
+        parts.iter()
+             .map(u64::to_string)
+             .collect::<Vec<_>>()
+             .join(".")
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op.prefix(), self.version)
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let value = value.trim();
+        if value == "*" {
+            return Ok(Comparator { op:      Op::Any,
+                                   version: String::new(), });
+        }
+        let (op, version) = [Op::GreaterEq,
+                             Op::LessEq,
+                             Op::Greater,
+                             Op::Less,
+                             Op::Exact,
+                             Op::Tilde,
+                             Op::Caret].iter()
+                                       .find(|op| value.starts_with(op.prefix()))
+                                       .map_or((Op::Caret, value), |&op| {
+                                           (op, value[op.prefix().len()..].trim_start())
+                                       });
+        if version.is_empty()
+           || version.contains(CONSTRAINT_CHARS)
+           || version.contains(|c: char| c == '/' || c == '@' || c.is_whitespace())
+           || numeric_parts(version).is_none()
+        {
+            return Err(Error::InvalidVersionConstraint(value.to_string()));
+        }
+        Ok(Comparator { op,
+                        version: version.to_string() })
+    }
+}
+
+/// A set of comparators which a version must all match.
+///
+/// # Examples
+///
+/// ```
+/// use biome_core::package::VersionConstraint;
+///
+/// let constraint: VersionConstraint = ">=1.1,<3".parse().unwrap();
+///
+/// assert!(constraint.matches("1.1.1"));
+/// assert!(constraint.matches("2.9"));
+/// assert!(!constraint.matches("3.0.0"));
+/// assert!(!constraint.matches("1.0.2"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VersionConstraint(Vec<Comparator>);
+
+impl VersionConstraint {
+    /// Does the version satisfy every comparator of the constraint?
+    pub fn matches(&self, version: &str) -> bool {
+        self.0.iter().all(|comparator| comparator.matches(version))
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparators: Vec<_> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", comparators.join(","))
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let comparators = value.split(',')
+                               .map(str::parse)
+                               .collect::<Result<Vec<Comparator>>>()
+                               .map_err(|_| Error::InvalidVersionConstraint(value.to_string()))?;
+        Ok(VersionConstraint(comparators))
+    }
+}
+
+impl Serialize for VersionConstraint {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        util::serde::string::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionConstraint {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        util::serde::string::deserialize(deserializer)
+    }
+}
+
+fn numeric_parts(version: &str) -> Option<Vec<u64>> {
+    let (parts, _) = split_version(version).ok()?;
+    parts.iter().map(|part| part.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraint(value: &str) -> VersionConstraint { value.parse().unwrap() }
+
+    #[test]
+    fn display_normalizes_the_constraint() {
+        assert_eq!(constraint(">= 1.1, <3").to_string(), ">=1.1,<3");
+        assert_eq!(constraint("1.2").to_string(), "^1.2");
+        assert_eq!(constraint("*").to_string(), "*");
+    }
+
+    #[test]
+    fn ordered_comparators() {
+        assert!(constraint(">1.2").matches("1.2.1"));
+        assert!(!constraint(">1.2").matches("1.2.0"));
+        assert!(constraint("<=1.2").matches("1.2.0"));
+        assert!(!constraint("<=1.2").matches("1.2.1"));
+        assert!(constraint("=1.2").matches("1.2.0"));
+        assert!(!constraint("=1.2").matches("1.2.1"));
+        assert!(constraint(">=1.1,<3").matches("2.99.1"));
+        assert!(!constraint(">=1.1,<3").matches("3.0.0"));
+    }
+
+    #[test]
+    fn tilde_allows_the_last_given_component_to_change() {
+        assert!(constraint("~1.2.3").matches("1.2.9"));
+        assert!(!constraint("~1.2.3").matches("1.3.0"));
+        assert!(!constraint("~1.2.3").matches("1.2.2"));
+        assert!(constraint("~1.2").matches("1.2.7"));
+        assert!(!constraint("~1.2").matches("1.3"));
+        assert!(constraint("~1").matches("1.9"));
+        assert!(!constraint("~1").matches("2.0"));
+    }
+
+    #[test]
+    fn caret_keeps_the_left_most_non_zero_component() {
+        assert!(constraint("^1.2.3").matches("1.9.0"));
+        assert!(!constraint("^1.2.3").matches("2.0.0"));
+        assert!(constraint("^0.2.3").matches("0.2.9"));
+        assert!(!constraint("^0.2.3").matches("0.3.0"));
+        assert!(constraint("^0.0.3").matches("0.0.3"));
+        assert!(!constraint("^0.0.3").matches("0.0.4"));
+        assert!(constraint("^0").matches("0.9"));
+        assert!(!constraint("^0").matches("1.0"));
+    }
+
+    #[test]
+    fn non_numeric_versions_never_match() {
+        assert!(!constraint(">=1.0").matches("master"));
+        assert!(constraint("*").matches("master"));
+    }
+
+    #[test]
+    fn invalid_constraints_are_rejected() {
+        for value in &["", ">=", ">=1.1,", "~master", ">=1.1/2", "=>1.0", "1.0 2.0"] {
+            assert!(value.parse::<VersionConstraint>().is_err(),
+                    "'{}' should not parse",
+                    value);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{error::{Error,
                     Result},
-            package::PackageTarget};
+            package::{constraint::VersionConstraint,
+                      PackageTarget}};
 use regex::Regex;
 use serde_derive::{Deserialize,
                    Serialize};
@@ -22,6 +23,10 @@ pub struct PackageIdent {
     pub name:    String,
     pub version: Option<String>,
     pub release: Option<String>,
+    /// The version constraint given after an `@` in place of a version and release, as in
+    /// `core/openssl@>=1.1,<3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<VersionConstraint>,
 }
 
 pub trait Identifiable: fmt::Display {
@@ -32,6 +37,9 @@ pub trait Identifiable: fmt::Display {
 
     fn fully_qualified(&self) -> bool { self.version().is_some() && self.release().is_some() }
 
+    /// The version constraint standing in for the version, as in `core/openssl@>=1.1,<3`.
+    fn version_constraint(&self) -> Option<&VersionConstraint> { None }
+
     fn valid(&self) -> bool {
         let re = Regex::new(r"^[A-Za-z0-9_-]+$").unwrap();
         re.is_match(self.name())
@@ -41,6 +49,10 @@ pub trait Identifiable: fmt::Display {
         if self.origin() != other.origin() || self.name() != other.name() {
             return false;
         }
+        if let Some(constraint) = other.version_constraint() {
+            return self.version()
+                       .map_or(false, |version| constraint.matches(version));
+        }
         if self.version().is_some() {
             if other.version().is_none() {
                 return true;
@@ -68,10 +80,20 @@ impl PackageIdent {
                                 version: Option<T>,
                                 release: Option<T>)
                                 -> Self {
-        PackageIdent { origin:  origin.into(),
-                       name:    name.into(),
-                       version: version.map(Into::into),
-                       release: release.map(Into::into), }
+        PackageIdent { origin:     origin.into(),
+                       name:       name.into(),
+                       version:    version.map(Into::into),
+                       release:    release.map(Into::into),
+                       constraint: None, }
+    }
+
+    /// Creates a new package identifier for the versions matching a constraint
+    pub fn with_constraint<T: Into<String>>(origin: T,
+                                            name: T,
+                                            constraint: VersionConstraint)
+                                            -> Self {
+        PackageIdent { constraint: Some(constraint),
+                       ..PackageIdent::new(origin, name, None, None) }
     }
 
     pub fn archive_name(&self) -> Result<String> {
@@ -164,6 +186,8 @@ impl Identifiable for PackageIdent {
     fn version(&self) -> Option<&str> { self.version.as_deref() }
 
     fn release(&self) -> Option<&str> { self.release.as_deref() }
+
+    fn version_constraint(&self) -> Option<&VersionConstraint> { self.constraint.as_ref() }
 }
 
 // It does not make sense for `PackageIdent` to implement `Default`. This should be removed.
@@ -187,6 +211,8 @@ impl fmt::Display for PackageIdent {
                    self.origin,
                    self.name,
                    self.version.as_ref().unwrap())
+        } else if let Some(ref constraint) = self.constraint {
+            write!(f, "{}/{}@{}", self.origin, self.name, constraint)
         } else {
             write!(f, "{}/{}", self.origin, self.name)
         }
@@ -201,6 +227,14 @@ impl FromStr for PackageIdent {
     type Err = Error;
 
     fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        if let Some(at) = value.find('@') {
+            let constraint = value[at + 1..].parse()?;
+            let items: Vec<&str> = value[..at].split('/').collect();
+            return match items.len() {
+                2 => Ok(PackageIdent::with_constraint(items[0], items[1], constraint)),
+                _ => Err(Error::InvalidPackageIdent(value.to_string())),
+            };
+        }
         let items: Vec<&str> = value.split('/').collect();
         let (origin, name, ver, rel) = match items.len() {
             2 => (items[0], items[1], None, None),
@@ -301,10 +335,7 @@ pub struct FullyQualifiedPackageIdent(PackageIdent);
 impl FullyQualifiedPackageIdent {
     /// Creates a new fully qualified package identifier
     pub fn new<T: Into<String>>(origin: T, name: T, version: T, release: T) -> Self {
-        let ident = PackageIdent { origin:  origin.into(),
-                                   name:    name.into(),
-                                   version: Some(version.into()),
-                                   release: Some(release.into()), };
+        let ident = PackageIdent::new(origin, name, Some(version), Some(release));
         FullyQualifiedPackageIdent(ident)
     }
 
@@ -499,7 +530,7 @@ pub fn version_sort(a_version: &str, b_version: &str) -> Result<Ordering> {
     }
 }
 
-fn split_version(version: &str) -> Result<(Vec<&str>, Option<String>)> {
+pub(crate) fn split_version(version: &str) -> Result<(Vec<&str>, Option<String>)> {
     let re = Regex::new(r"([\d\.]+)(.+)?")?;
     let caps = match re.captures(version) {
         Some(caps) => caps,
@@ -838,4 +869,63 @@ mod tests {
         assert_eq!(Some("rise-up"), iter.next());
         assert_eq!(None, iter.next());
     }
+
+    #[test]
+    fn from_str_with_version_constraint() {
+        let ident = PackageIdent::from_str("core/openssl@>= 1.1, <3").unwrap();
+
+        assert_eq!(ident.version, None);
+        assert_eq!(ident.release, None);
+        assert_eq!(ident.constraint, Some(">=1.1,<3".parse().unwrap()));
+        assert_eq!(ident.to_string(), "core/openssl@>=1.1,<3");
+        assert_eq!(PackageIdent::from_str(&ident.to_string()).unwrap(), ident);
+
+        let ident = PackageIdent::from_str("core/openssl@1.1").unwrap();
+        assert_eq!(ident.to_string(), "core/openssl@^1.1");
+    }
+
+    #[test]
+    fn from_str_with_invalid_version_constraint() {
+        for ident in &["core/openssl@>=1.1,",
+                       "core/openssl@master",
+                       "core/openssl/1.1@~1",
+                       "core@~1"]
+        {
+            assert!(PackageIdent::from_str(ident).is_err(),
+                    "'{}' should not parse",
+                    ident);
+        }
+    }
+
+    #[test]
+    fn from_str_only_takes_constraints_after_an_at() {
+        for (value, version) in &[("core/openssl/1.0~rc1/20200101000000", "1.0~rc1"),
+                                  ("core/openssl/~1", "~1"),
+                                  ("core/openssl/1.0^2", "1.0^2")]
+        {
+            let ident = PackageIdent::from_str(value).unwrap();
+
+            assert_eq!(ident.version.as_deref(), Some(*version));
+            assert_eq!(ident.constraint, None);
+            assert_eq!(ident.to_string(), *value);
+        }
+    }
+
+    #[test]
+    fn satisfies_version_constraint() {
+        let wanted = PackageIdent::from_str("core/openssl@~1.1").unwrap();
+
+        for ident in &["core/openssl/1.1.0/20180701141405",
+                       "core/openssl/1.1.1g/20200501000000"]
+        {
+            assert!(PackageIdent::from_str(ident).unwrap().satisfies(&wanted));
+        }
+        for ident in &["core/openssl/1.0.2/20180701141405",
+                       "core/openssl/3.0.0/20210901000000",
+                       "core/libressl/1.1.0/20180701141405",
+                       "core/openssl"]
+        {
+            assert!(!PackageIdent::from_str(ident).unwrap().satisfies(&wanted));
+        }
+    }
 }
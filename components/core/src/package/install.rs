@@ -13,7 +13,8 @@ use crate::{error::{Error,
                     Result},
             fs,
             os::process::{ShutdownSignal,
-                          ShutdownTimeout}};
+                          ShutdownTimeout},
+            service::HookTimeout};
 use serde_derive::{Deserialize,
                    Serialize};
 use std::{cmp::{Ordering,
@@ -132,6 +133,10 @@ impl PackageInstall {
                                       -> Result<PackageInstall>
         where T: AsRef<Path>
     {
+        // A version constraint already bounds the versions which are acceptable
+        if ident.constraint.is_some() {
+            return Self::resolve_package_install(ident, fs_root_path);
+        }
         let original_ident = ident;
         // If the PackageIndent is does not have a version, use a reasonable minimum version that
         // will be satisfied by any installed package with the same origin/name
@@ -600,6 +605,16 @@ impl PackageInstall {
         }
     }
 
+    /// Returns the contents of the package's MANIFEST
+    /// or None if the package doesn't contain a MANIFEST Metafile
+    pub fn manifest(&self) -> Result<Option<String>> {
+        match self.read_metafile(MetaFile::Manifest) {
+            Ok(body) => Ok(Some(body)),
+            Err(Error::MetaFileNotFound(MetaFile::Manifest)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Returns the shutdown signal that the package is specified to shutdown with
     /// or None if the package doesn't contain a SHUTDOWN_SIGNAL Metafile
     pub fn shutdown_signal(&self) -> Result<Option<ShutdownSignal>> {
@@ -620,6 +635,28 @@ impl PackageInstall {
         }
     }
 
+    /// Returns the timeouts the package declares for its lifecycle hooks, keyed by hook name.
+    ///
+    /// Hooks without an entry in the HOOK_TIMEOUTS Metafile are allowed to run indefinitely.
+    pub fn hook_timeouts(&self) -> Result<BTreeMap<String, HookTimeout>> {
+        match self.read_metafile(MetaFile::HookTimeouts) {
+            Ok(body) => {
+                let parsed_value = parse_key_value(&body);
+                let timeouts =
+                    parsed_value.map_err(|_| Error::MetaFileMalformed(MetaFile::HookTimeouts))?;
+                timeouts.into_iter()
+                        .map(|(hook, timeout)| {
+                            timeout.parse()
+                                   .map(|timeout| (hook, timeout))
+                                   .map_err(|_| Error::MetaFileMalformed(MetaFile::HookTimeouts))
+                        })
+                        .collect()
+            }
+            Err(Error::MetaFileNotFound(MetaFile::HookTimeouts)) => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Read the contents of a given metafile.
     ///
     /// # Failures
@@ -887,6 +924,31 @@ core/bar=pub:core/publish sub:core/subscribe
         assert_eq!(active_target, loaded.target().unwrap());
     }
 
+    #[test]
+    fn load_with_version_constraint_picks_latest_within_constraint() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let active_target = PackageTarget::active_target();
+        for ident_s in &["dream-theater/systematic-chaos/1.2.3/20180704142702",
+                         "dream-theater/systematic-chaos/1.9.0/20180704142702",
+                         "dream-theater/systematic-chaos/2.0.0/20180704142702"]
+        {
+            let pkg_install = testing_package_install(ident_s, fs_root.path());
+            write_metafile(&pkg_install, MetaFile::Target, &active_target);
+        }
+
+        let wanted = PackageIdent::from_str("dream-theater/systematic-chaos@>=1.2,<2").unwrap();
+        let loaded = PackageInstall::load(&wanted, Some(fs_root.path())).unwrap();
+        assert_eq!(loaded.ident().version, Some(String::from("1.9.0")));
+        let loaded = PackageInstall::load_at_least(&wanted, Some(fs_root.path())).unwrap();
+        assert_eq!(loaded.ident().version, Some(String::from("1.9.0")));
+
+        let wanted = PackageIdent::from_str("dream-theater/systematic-chaos@~3").unwrap();
+        match PackageInstall::load(&wanted, Some(fs_root.path())) {
+            Err(Error::PackageNotFound(ident)) => assert_eq!(ident, wanted),
+            other => panic!("Should not have found a package, found={:?}", other),
+        }
+    }
+
     #[test]
     fn load_with_fuzzy_ident_with_wrong_target_returns_package_not_found_err() {
         let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
@@ -1434,4 +1496,47 @@ core/bar=pub:core/publish sub:core/subscribe
 
         assert_eq!(expected, pkg_install.environment_for_command().unwrap());
     }
+
+    #[test]
+    fn hook_timeouts_missing_metafile() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let pkg_install = testing_package_install("acme/hooky", fs_root.path());
+
+        assert!(pkg_install.hook_timeouts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn hook_timeouts_from_metafile() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let pkg_install = testing_package_install("acme/hooky", fs_root.path());
+        write_metafile(&pkg_install,
+                       MetaFile::HookTimeouts,
+                       "health-check=30\ninit=300\n");
+
+        let mut expected = BTreeMap::new();
+        expected.insert("health-check".to_string(), "30".parse().unwrap());
+        expected.insert("init".to_string(), "300".parse().unwrap());
+
+        assert_eq!(expected, pkg_install.hook_timeouts().unwrap());
+    }
+
+    #[test]
+    fn hook_timeouts_with_malformed_timeout() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let pkg_install = testing_package_install("acme/hooky", fs_root.path());
+        write_metafile(&pkg_install, MetaFile::HookTimeouts, "init=forever\n");
+
+        assert!(matches!(pkg_install.hook_timeouts(),
+                         Err(Error::MetaFileMalformed(MetaFile::HookTimeouts))));
+    }
+
+    #[test]
+    fn hook_timeouts_with_zero_timeout() {
+        let fs_root = Builder::new().prefix("fs-root").tempdir().unwrap();
+        let pkg_install = testing_package_install("acme/hooky", fs_root.path());
+        write_metafile(&pkg_install, MetaFile::HookTimeouts, "init=0\n");
+
+        assert!(matches!(pkg_install.hook_timeouts(),
+                         Err(Error::MetaFileMalformed(MetaFile::HookTimeouts))));
+    }
 }
@@ -1,5 +1,6 @@
 use super::{metadata::{read_metafile,
                        MetaFile},
+            Identifiable,
             PackageIdent,
             PackageTarget};
 use crate::error::{Error,
@@ -90,6 +91,9 @@ pub fn package_list_for_origin(base_pkg_path: &Path, origin: &str) -> Result<Vec
 /// structure is:
 ///
 ///    /base/ORIGIN/NAME/VERSION/RELEASE/
+///
+/// When the ident carries a version constraint, every version is walked and only those matching
+/// the constraint are returned.
 pub fn package_list_for_ident(base_pkg_path: &Path,
                               ident: &PackageIdent)
                               -> Result<Vec<PackageIdent>> {
@@ -102,6 +106,12 @@ pub fn package_list_for_ident(base_pkg_path: &Path,
         return Ok(package_list);
     }
 
+    if let Some(ref constraint) = ident.constraint {
+        walk_versions(&ident.origin, &ident.name, &package_path, &mut package_list)?;
+        package_list.retain(|p| p.version().map_or(false, |v| constraint.matches(v)));
+        return Ok(package_list);
+    }
+
     match (&ident.version, &ident.release) {
         // origin/name
         (None, _) => walk_versions(&ident.origin, &ident.name, &package_path, &mut package_list)?,
@@ -148,6 +148,7 @@ pub enum MetaFile {
     EnvironmentSep,
     Exports,
     Exposes,
+    HookTimeouts,
     Ident,
     LdFlags,
     LdRunPath,
@@ -182,6 +183,7 @@ impl fmt::Display for MetaFile {
             MetaFile::EnvironmentSep => "ENVIRONMENT_SEP",
             MetaFile::Exports => "EXPORTS",
             MetaFile::Exposes => "EXPOSES",
+            MetaFile::HookTimeouts => "HOOK_TIMEOUTS",
             MetaFile::Ident => "IDENT",
             MetaFile::LdFlags => "LDFLAGS",
             MetaFile::LdRunPath => "LD_RUN_PATH",
@@ -3,8 +3,10 @@ use crate::error::{Error,
 use regex::Regex;
 use serde_derive::{Deserialize,
                    Serialize};
-use std::{fmt,
-          num::ParseIntError,
+use std::{convert::TryFrom,
+          fmt,
+          num::{NonZeroU32,
+                ParseIntError},
           ops::{Deref,
                 DerefMut},
           result,
@@ -303,6 +305,113 @@ impl From<Duration> for HealthCheckInterval {
     fn from(d: Duration) -> Self { Self(d) }
 }
 
+/// The names of the lifecycle hooks a timeout can be given for.
+///
+/// The `run` hook is absent because it runs for as long as the service does.
+pub const TIMEOUT_HOOK_NAMES: &[&str] = &["file-updated",
+                                          "health-check",
+                                          "init",
+                                          "post-run",
+                                          "post-stop",
+                                          "reconfigure",
+                                          "reload",
+                                          "suitability"];
+
+/// The number of seconds a lifecycle hook is allowed to run before the
+/// Supervisor kills it. A timeout is never zero, as that would kill the hook
+/// before it could do anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct HookTimeout(NonZeroU32);
+
+impl TryFrom<u32> for HookTimeout {
+    type Error = Error;
+
+    fn try_from(seconds: u32) -> Result<Self> {
+        NonZeroU32::new(seconds).map(Self)
+                                .ok_or_else(|| Error::InvalidHookTimeout(seconds.to_string()))
+    }
+}
+
+impl From<HookTimeout> for u32 {
+    fn from(timeout: HookTimeout) -> Self { timeout.0.get() }
+}
+
+impl From<HookTimeout> for Duration {
+    fn from(timeout: HookTimeout) -> Self { Duration::from_secs(timeout.0.get().into()) }
+}
+
+impl FromStr for HookTimeout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse::<u32>()
+         .map_err(|_| Error::InvalidHookTimeout(s.to_string()))
+         .and_then(Self::try_from)
+    }
+}
+
+impl fmt::Display for HookTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}", self.0) }
+}
+
+/// A timeout for a single named hook, as given on the command line in
+/// `<HOOK>=<SECONDS>` format (example init=300).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct HookTimeoutOverride {
+    hook:    String,
+    timeout: HookTimeout,
+}
+
+impl HookTimeoutOverride {
+    pub fn new(hook: &str, timeout: HookTimeout) -> Self {
+        Self { hook: hook.to_string(),
+               timeout }
+    }
+
+    pub fn hook(&self) -> &str { &self.hook }
+
+    pub fn timeout(&self) -> HookTimeout { self.timeout }
+}
+
+impl FromStr for HookTimeoutOverride {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split('=').collect();
+        match parts.as_slice() {
+            [hook, timeout] if TIMEOUT_HOOK_NAMES.contains(hook) => {
+                timeout.parse()
+                       .map(|timeout| HookTimeoutOverride::new(hook, timeout))
+                       .map_err(|_| Error::InvalidHookTimeout(s.to_string()))
+            }
+            _ => Err(Error::InvalidHookTimeout(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for HookTimeoutOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.hook, self.timeout)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HookTimeoutOverride {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        crate::util::serde::string::deserialize(deserializer)
+    }
+}
+
+impl serde::Serialize for HookTimeoutOverride {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
@@ -597,4 +706,38 @@ mod test {
                                                                               without app/env");
         assert_eq!(sg, *data.key.service_group());
     }
+
+    #[test]
+    fn hook_timeout_override_from_str() {
+        let timeout = HookTimeoutOverride::from_str("health-check=45").unwrap();
+        assert_eq!(timeout.hook(), "health-check");
+        assert_eq!(timeout.timeout(), HookTimeout::try_from(45).unwrap());
+        assert_eq!(timeout.to_string(), "health-check=45");
+    }
+
+    #[test]
+    fn hook_timeout_override_from_str_invalid() {
+        for s in &["init",
+                   "init=",
+                   "=30",
+                   "init=-5",
+                   "init=0",
+                   "init=30=40",
+                   "inti=30",
+                   "run=30"]
+        {
+            match HookTimeoutOverride::from_str(s) {
+                Err(Error::InvalidHookTimeout(val)) => assert_eq!(*s, val),
+                other => panic!("Unexpected result for '{}': {:?}", s, other),
+            }
+        }
+    }
+
+    #[test]
+    fn hook_timeout_rejects_zero() {
+        assert!(HookTimeout::try_from(0).is_err());
+        assert!(HookTimeout::from_str("0").is_err());
+        assert!(serde_json::from_str::<HookTimeout>("0").is_err());
+        assert_eq!(u32::from(serde_json::from_str::<HookTimeout>("30").unwrap()), 30);
+    }
 }
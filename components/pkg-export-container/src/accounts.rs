@@ -6,7 +6,7 @@
 use std::fmt;
 
 /// Represents an entry for a user in `/etc/passwd`
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct EtcPasswdEntry {
     pub name: String,
     pub uid:  u32,
@@ -32,6 +32,16 @@ impl fmt::Display for EtcPasswdEntry {
     }
 }
 
+/// The user to run as in an image which must not run as root: the
+/// service's own user, unless that is `root`, in which case the `hab`
+/// user that is always added alongside it.
+pub fn non_root_user<'a>(users: &'a [EtcPasswdEntry],
+                         svc_user: &str)
+                         -> Option<&'a EtcPasswdEntry> {
+    let name = if svc_user == "root" { "hab" } else { svc_user };
+    users.iter().find(|user| user.name == name)
+}
+
 /// Represents an entry for a group in `/etc/group`
 #[derive(Debug)]
 pub struct EtcGroupEntry {
@@ -77,6 +87,16 @@ mod test {
         assert_eq!(rendered, "my_user:x:123:456:my_user User:/:/bin/false");
     }
 
+    #[test]
+    fn non_root_user_falls_back_to_hab_for_root_services() {
+        let users = vec![EtcPasswdEntry::new("my_user", 42, 42),
+                         EtcPasswdEntry::new("hab", 84, 84)];
+
+        assert_eq!(non_root_user(&users, "my_user").unwrap().uid, 42);
+        assert_eq!(non_root_user(&users, "root").unwrap().uid, 84);
+        assert!(non_root_user(&users, "nobody").is_none());
+    }
+
     #[test]
     fn etc_group_entry_with_users_renders_correctly() {
         let entry = EtcGroupEntry::group_with_users("my_group", 456, &["larry", "moe", "curly"]);
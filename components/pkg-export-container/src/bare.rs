@@ -0,0 +1,306 @@
+//! Renders the primary service for a "bare" image, which runs the
+//! service's hooks directly rather than under a Supervisor.
+//!
+//! Configuration and hooks are rendered once, at build time, from the
+//! package's `default.toml` and any `HAB_<SERVICE>` overrides in the
+//! exporter's environment. Anything only a running Supervisor can
+//! provide (binds, gossiped configuration, `sys` data, updates) is
+//! unavailable, so services which require binds are refused.
+
+use crate::{build::BuildRootContext,
+            error::{Error,
+                    Result}};
+use biome_common::{templating::{config::{Cfg,
+                                         CfgRenderer,
+                                         PackageConfigPaths},
+                                package::{Pkg,
+                                          DEFAULT_USER},
+                                RenderContext,
+                                TemplateRenderer},
+                   ui::{UIWriter,
+                        UI}};
+use biome_core::{os::process::ShutdownSignal,
+                 package::{FullyQualifiedPackageIdent,
+                           PackageInstall}};
+use failure::SyncFailure;
+use std::{collections::BTreeMap,
+          env,
+          fs,
+          path::{Path,
+                 PathBuf}};
+
+/// Where services live in the image.
+const SVC_ROOT: &str = "/hab/svc";
+
+const INIT_HOOK: &str = "init";
+const RUN_HOOK: &str = "run";
+const HEALTH_CHECK_HOOK: &str = "health-check";
+/// Hooks which are run when the package is installed or removed, and
+/// so have nothing to do with running the service.
+const PACKAGE_HOOKS: &[&str] = &["install", "uninstall"];
+
+/// The directories the Supervisor would create for a service.
+#[cfg(unix)]
+const SVC_DIRS: &[&str] = &["config", "data", "files", "hooks", "logs", "static", "var"];
+
+/// The primary service, rendered for a bare image.
+#[derive(Debug)]
+pub struct BareService {
+    name:            String,
+    /// The service's runtime environment, other than `PATH`.
+    environment:     BTreeMap<String, String>,
+    /// The service's `PATH`, as seen from inside the image.
+    path:            String,
+    /// The user and group the service's hooks run as.
+    svc_user:        String,
+    svc_group:       String,
+    shutdown_signal: ShutdownSignal,
+    /// Rendered configuration files, relative to the config directory.
+    config:          Vec<(String, String)>,
+    /// Rendered hooks, by name.
+    hooks:           BTreeMap<&'static str, String>,
+}
+
+impl BareService {
+    /// Render the configuration and hooks of the primary service
+    /// installed in the build root.
+    ///
+    /// # Errors
+    ///
+    /// * If the service requires binds
+    /// * If the package has neither a run hook nor a run file
+    /// * If the package's metadata, configuration, or hooks cannot be read or rendered
+    pub fn render(ui: &mut UI, ctx: &BuildRootContext) -> Result<Self> {
+        let rootfs = ctx.rootfs();
+        let ident = ctx.installed_primary_svc_ident()?;
+        let install = PackageInstall::load(ident.as_ref(), Some(rootfs))?;
+
+        let binds = install.binds()?;
+        if !binds.is_empty() {
+            let services: Vec<_> = binds.into_iter().map(|bind| bind.service).collect();
+            return Err(Error::BareServiceRequiresBinds(ident.into(), services.join(", ")).into());
+        }
+
+        let pkg = image_pkg(&install, ident, ctx)?;
+        let cfg = Cfg::new(&RootfsConfigPaths { pkg: &pkg, rootfs }, None)?;
+        let render_ctx = RenderContext::new(&pkg, &cfg);
+
+        let config = CfgRenderer::new(install.installed_path.join("config"))?.render(&render_ctx)?;
+
+        let hooks_path = install.installed_path.join("hooks");
+        let mut sources = vec![(INIT_HOOK, hooks_path.join(INIT_HOOK)),
+                               (HEALTH_CHECK_HOOK, hooks_path.join(HEALTH_CHECK_HOOK))];
+        // Without a run hook, the Supervisor runs the package's `run`
+        // file instead.
+        if hooks_path.join(RUN_HOOK).is_file() {
+            sources.push((RUN_HOOK, hooks_path.join(RUN_HOOK)));
+        } else {
+            sources.push((RUN_HOOK, install.installed_path.join(RUN_HOOK)));
+        }
+        let mut renderer = TemplateRenderer::new();
+        let mut hooks = BTreeMap::new();
+        for (name, source) in sources.into_iter().filter(|(_, source)| source.is_file()) {
+            renderer.register_template_file(name, &source)
+                    .map_err(SyncFailure::new)?;
+            hooks.insert(name, renderer.render(name, &render_ctx)?);
+        }
+        if !hooks.contains_key(RUN_HOOK) {
+            return Err(Error::BareServiceNotRunnable(pkg.ident.clone().into()).into());
+        }
+
+        if hooks_path.is_dir() {
+            for entry in fs::read_dir(&hooks_path)? {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                if !hooks.contains_key(name.as_str()) && !PACKAGE_HOOKS.contains(&name.as_str()) {
+                    ui.warn(format!("Ignoring the {} hook of {}, which needs a Supervisor",
+                                    name, pkg.ident))?;
+                }
+            }
+        }
+
+        let environment = pkg.env
+                             .iter()
+                             .filter(|(name, _)| name.as_str() != "PATH")
+                             .map(|(name, value)| (name.clone(), value.clone()))
+                             .collect();
+        Ok(BareService { path: pkg.env.get("PATH").cloned().unwrap_or_default(),
+                         name: pkg.name,
+                         environment,
+                         svc_user: pkg.svc_user,
+                         svc_group: pkg.svc_group,
+                         shutdown_signal: pkg.shutdown_signal,
+                         config,
+                         hooks })
+    }
+
+    /// The service's directory in the image.
+    pub fn svc_path(&self) -> PathBuf { Path::new(SVC_ROOT).join(&self.name) }
+
+    /// The path of the rendered hook in the image, if the package has
+    /// it.
+    pub fn hook_path(&self, name: &str) -> Option<PathBuf> {
+        if self.hooks.contains_key(name) {
+            Some(self.svc_path().join("hooks").join(name))
+        } else {
+            None
+        }
+    }
+
+    pub fn init_hook(&self) -> Option<PathBuf> { self.hook_path(INIT_HOOK) }
+
+    /// The path of the rendered run hook in the image, which `render`
+    /// makes sure every bare service has.
+    pub fn run_hook(&self) -> PathBuf { self.svc_path().join("hooks").join(RUN_HOOK) }
+
+    pub fn health_check_hook(&self) -> Option<PathBuf> { self.hook_path(HEALTH_CHECK_HOOK) }
+
+    pub fn path(&self) -> &str { &self.path }
+
+    pub fn svc_user(&self) -> &str { &self.svc_user }
+
+    pub fn svc_group(&self) -> &str { &self.svc_group }
+
+    /// The signal to stop the service with, when the container is
+    /// asked to stop.
+    pub fn shutdown_signal(&self) -> ShutdownSignal { self.shutdown_signal }
+
+    /// The service's runtime environment, as `NAME='value'` assignments
+    /// for a shell.
+    pub fn shell_environment(&self) -> Vec<String> {
+        self.environment
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, shell_quote(value)))
+            .collect()
+    }
+
+    /// Write the service's directories, configuration, and hooks into
+    /// the root file system.
+    #[cfg(unix)]
+    pub fn write(&self, rootfs: &Path) -> Result<()> {
+        use biome_common::templating::{config::CONFIG_PERMISSIONS,
+                                       hooks::HOOK_PERMISSIONS};
+        use biome_core::util::posix_perm;
+
+        let svc_path = rootfs.join(self.svc_path().strip_prefix("/")?);
+        for dir in SVC_DIRS {
+            fs::create_dir_all(svc_path.join(dir))?;
+        }
+        for (name, contents) in self.config.iter() {
+            let path = svc_path.join("config").join(name);
+            fs::create_dir_all(path.parent().expect("config files are in a directory"))?;
+            fs::write(&path, contents)?;
+            posix_perm::set_permissions(&path, CONFIG_PERMISSIONS)?;
+        }
+        for (name, contents) in self.hooks.iter() {
+            let path = svc_path.join("hooks").join(name);
+            fs::write(&path, contents)?;
+            posix_perm::set_permissions(&path, HOOK_PERMISSIONS)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the primary service's configuration in the build root.
+struct RootfsConfigPaths<'a> {
+    pkg:    &'a Pkg,
+    rootfs: &'a Path,
+}
+
+impl<'a> RootfsConfigPaths<'a> {
+    fn rooted(&self, path: &Path) -> PathBuf {
+        self.rootfs.join(path.strip_prefix("/").unwrap_or(path))
+    }
+}
+
+impl<'a> PackageConfigPaths for RootfsConfigPaths<'a> {
+    fn name(&self) -> String { self.pkg.name.clone() }
+
+    fn default_config_dir(&self) -> PathBuf { self.rooted(&self.pkg.path) }
+
+    fn recommended_user_config_dir(&self) -> PathBuf {
+        self.rooted(&Path::new("/hab/user").join(&self.pkg.name).join("config"))
+    }
+
+    fn deprecated_user_config_dir(&self) -> PathBuf { self.rooted(&self.pkg.svc_path) }
+}
+
+/// Describe the installed package as the Supervisor would inside the
+/// image, with every path as seen from inside it.
+fn image_pkg(install: &PackageInstall,
+             ident: FullyQualifiedPackageIdent,
+             ctx: &BuildRootContext)
+             -> Result<Pkg> {
+    let rootfs = ctx.rootfs();
+    let mut environment = install.environment_for_command()?;
+    let mut paths: Vec<_> =
+        environment.get("PATH")
+                   .map(|path| {
+                       env::split_paths(path).map(|path| unrooted(rootfs, &path))
+                                             .collect()
+                   })
+                   .unwrap_or_default();
+    paths.extend(env::split_paths(ctx.env_path()));
+    environment.insert("PATH".to_string(),
+                       env::join_paths(paths)?.to_string_lossy().into_owned());
+
+    let name = ident.as_ref().name.clone();
+    let svc_path = Path::new(SVC_ROOT).join(&name);
+    Ok(Pkg { origin: ident.as_ref().origin.clone(),
+             name,
+             version: ident.version().to_string(),
+             release: ident.release().to_string(),
+             deps: install.tdeps()?,
+             env: environment.into(),
+             exposes: install.exposes()?,
+             exports: install.exports()?,
+             path: unrooted(rootfs, &install.installed_path),
+             svc_config_path: svc_path.join("config"),
+             svc_config_install_path: svc_path.join("config_install"),
+             svc_data_path: svc_path.join("data"),
+             svc_files_path: svc_path.join("files"),
+             svc_static_path: svc_path.join("static"),
+             svc_var_path: svc_path.join("var"),
+             svc_pid_file: svc_path.join("PID"),
+             svc_run: svc_path.join("run"),
+             svc_path,
+             svc_user: install.svc_user()?
+                              .unwrap_or_else(|| DEFAULT_USER.to_string()),
+             svc_group: install.svc_group()?
+                               .unwrap_or_else(|| DEFAULT_USER.to_string()),
+             shutdown_signal: install.shutdown_signal()?.unwrap_or_default(),
+             shutdown_timeout: install.shutdown_timeout()?.unwrap_or_default(),
+             ident })
+}
+
+/// The path inside the image of a path in the build root.
+fn unrooted(rootfs: &Path, path: &Path) -> PathBuf {
+    match path.strip_prefix(rootfs) {
+        Ok(path) => Path::new("/").join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Quote a value so a POSIX shell takes it literally.
+fn shell_quote(value: &str) -> String { format!("'{}'", value.replace('\'', r"'\''")) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_quoted_for_the_shell() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("$HOME `id`"), "'$HOME `id`'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn build_root_paths_are_seen_from_inside_the_image() {
+        let rootfs = Path::new("/tmp/build/rootfs");
+
+        assert_eq!(unrooted(rootfs, &rootfs.join("hab/pkgs/core/redis")),
+                   Path::new("/hab/pkgs/core/redis"));
+        assert_eq!(unrooted(rootfs, Path::new("/usr/bin")),
+                   Path::new("/usr/bin"));
+    }
+}
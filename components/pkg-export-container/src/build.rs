@@ -1,10 +1,14 @@
 #[cfg(unix)]
 use crate::rootfs;
-use crate::{accounts::{EtcGroupEntry,
+use crate::{accounts::{self,
+                       EtcGroupEntry,
                        EtcPasswdEntry},
+            bare::BareService,
             error::{Error,
                     Result},
             graph::Graph,
+            hardened::Hardened,
+            layering::LayerPlan,
             util,
             BUSYBOX_IDENT,
             CACERTS_IDENT,
@@ -17,7 +21,11 @@ use bio::license;
 use biome_common::{command::package::install::{InstallHookMode,
                                                  InstallMode,
                                                  InstallSource,
-                                                 LocalPackageUsage},
+                                                 LocalPackageUsage,
+                                                 SignaturePolicy},
+                     preload::Preload,
+                     sbom::{Sbom,
+                            SbomFormat},
                      ui::{Status,
                           UIWriter,
                           UI},
@@ -107,9 +115,23 @@ pub struct BuildSpec {
     pub auth:               Option<String>,
     /// Base image used in Dockerfile
     pub base_image:         String,
-    /// Whether or not to create an image with a single layer for each
-    /// Biome package.
+    /// Whether or not to create an image with packages spread across
+    /// multiple layers.
     pub multi_layer:        bool,
+    /// The most layers to spread packages across, if limited.
+    pub max_layers:         Option<usize>,
+    /// Whether to report which packages went into which layer.
+    pub layer_report:       bool,
+    /// The format of the SBOM to embed in the image for each user package, if any.
+    pub sbom:               Option<SbomFormat>,
+    /// Whether to run the primary service's hooks directly, without a
+    /// Supervisor or Launcher.
+    pub bare:               bool,
+    /// Whether to build an image with no shell, which runs as a
+    /// non-root user.
+    pub hardened:           bool,
+    /// The service spec, configuration and keys to bake into the image.
+    pub preload:            Preload,
 }
 
 impl TryFrom<&ArgMatches<'_>> for BuildSpec {
@@ -151,7 +173,15 @@ impl TryFrom<&ArgMatches<'_>> for BuildSpec {
                             .unwrap_or_else(|| {
                                 default_base_image().expect("No base image supported")
                             }),
-                       multi_layer:        m.is_present("MULTI_LAYER"), })
+                       multi_layer:        m.is_present("MULTI_LAYER"),
+                       max_layers:         clap::value_t!(m, "MAX_LAYERS", usize).ok(),
+                       layer_report:       m.is_present("LAYER_REPORT"),
+                       sbom:
+                           m.value_of("SBOM")
+                            .map(|format| format.parse().expect("valid SBOM format")),
+                       bare:               m.is_present("BARE"),
+                       hardened:           m.is_present("HARDENED"),
+                       preload:            Preload::from_matches(m), })
     }
 }
 
@@ -170,9 +200,40 @@ impl BuildSpec {
         ui.status(Status::Creating,
                   format!("build root in {}", workdir.path().display()))?;
         let graph = self.prepare_rootfs(ui, &rootfs).await?;
+        let layers = if self.multi_layer {
+            let plan = LayerPlan::new(&graph, &rootfs, self.max_layers)?;
+            if self.layer_report {
+                plan.report(ui)?;
+            }
+            Some(plan)
+        } else {
+            None
+        };
+        let ctx = BuildRootContext::from_spec(&self, &rootfs)?;
+        // Preloaded configuration is written before a bare image's
+        // service is rendered, so that its user.toml is rendered too.
+        self.preload.write(ui,
+                            &rootfs,
+                            ctx.installed_primary_svc_ident()?.as_ref(),
+                            &self.url,
+                            &self.channel)?;
+        let bare = if self.bare {
+            Some(BareService::render(ui, &ctx)?)
+        } else {
+            None
+        };
+        let hardened = if self.hardened {
+            Some(Hardened::prepare(ui, &ctx, &graph, workdir.path(), self.preload.has_spec())?)
+        } else {
+            None
+        };
         Ok(BuildRoot { workdir,
-                       ctx: BuildRootContext::from_spec(&self, &rootfs)?,
-                       graph })
+                       ctx,
+                       graph,
+                       layers,
+                       bare,
+                       hardened,
+                       preloaded_spec: self.preload.has_spec() })
     }
 
     #[cfg(unix)]
@@ -183,6 +244,7 @@ impl BuildSpec {
         self.create_symlink_to_key_cache(ui, rootfs)?;
         let base_pkgs = self.install_base_pkgs(ui, rootfs).await?;
         let user_pkgs = self.install_user_pkgs(ui, rootfs).await?;
+        self.write_sboms(ui, rootfs, &user_pkgs)?;
         self.link_binaries(ui, rootfs, &base_pkgs)?;
         self.link_cacerts(ui, rootfs, &base_pkgs)?;
         self.link_user_pkgs(ui, rootfs, &user_pkgs)?;
@@ -201,6 +263,7 @@ impl BuildSpec {
         self.create_symlink_to_key_cache(ui, rootfs)?;
         let base_pkgs = self.install_base_pkgs(ui, rootfs).await?;
         let user_pkgs = self.install_user_pkgs(ui, rootfs).await?;
+        self.write_sboms(ui, rootfs, &user_pkgs)?;
         self.remove_symlink_to_key_cache(ui, rootfs)?;
         self.remove_symlink_to_artifact_cache(ui, rootfs)?;
 
@@ -237,10 +300,17 @@ impl BuildSpec {
 
     async fn install_base_pkgs(&self, ui: &mut UI, rootfs: &Path) -> Result<BasePkgIdents> {
         let bio = self.install_base_pkg(ui, &self.bio, rootfs).await?;
-        let sup = self.install_base_pkg(ui, &self.bio_sup, rootfs).await?;
-        let launcher = self.install_base_pkg(ui, &self.bio_launcher, rootfs)
-                           .await?;
-        let busybox = if cfg!(target_os = "linux") {
+        // Bare images run the service's hooks directly, so have no use
+        // for a Supervisor or Launcher.
+        let (sup, launcher) = if self.bare {
+            (None, None)
+        } else {
+            (Some(self.install_base_pkg(ui, &self.bio_sup, rootfs).await?),
+             Some(self.install_base_pkg(ui, &self.bio_launcher, rootfs)
+                      .await?))
+        };
+        // Hardened images have no shell at all.
+        let busybox = if cfg!(target_os = "linux") && !self.hardened {
             Some(self.install_base_pkg(ui, BUSYBOX_IDENT, rootfs).await?)
         } else {
             None
@@ -266,6 +336,28 @@ impl BuildSpec {
         Ok(idents)
     }
 
+    fn write_sboms(&self,
+                   ui: &mut UI,
+                   rootfs: &Path,
+                   user_pkgs: &[FullyQualifiedPackageIdent])
+                   -> Result<()> {
+        if let Some(format) = self.sbom {
+            for pkg in user_pkgs.iter() {
+                let install = PackageInstall::load(pkg.as_ref(), Some(rootfs))?;
+                let path = Sbom::from_install(&install, rootfs)?.write_to_root(format,
+                                                                               &*PROGRAM_NAME,
+                                                                               VERSION,
+                                                                               rootfs)?;
+                ui.status(Status::Generated,
+                          format!("{} SBOM for {} at /{}",
+                                  format,
+                                  pkg,
+                                  path.strip_prefix(rootfs)?.display()))?;
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(unix)]
     fn link_user_pkgs(&self,
                       ui: &mut UI,
@@ -284,14 +376,13 @@ impl BuildSpec {
     fn link_binaries(&self, ui: &mut UI, rootfs: &Path, base_pkgs: &BasePkgIdents) -> Result<()> {
         let dst = util::bin_path();
 
-        bio::command::pkg::binlink::binlink_all_in_pkg(ui,
-                                                       base_pkgs.busybox
-                                                                .as_ref()
-                                                                .expect("No busybox in idents")
-                                                                .as_ref(),
-                                                       &dst,
-                                                       rootfs,
-                                                       true).map_err(SyncFailure::new)?;
+        if let Some(busybox) = base_pkgs.busybox.as_ref() {
+            bio::command::pkg::binlink::binlink_all_in_pkg(ui,
+                                                           busybox.as_ref(),
+                                                           &dst,
+                                                           rootfs,
+                                                           true).map_err(SyncFailure::new)?;
+        }
         bio::command::pkg::binlink::start(ui, base_pkgs.bio.as_ref(), "bio", &dst, rootfs, true)
             .map_err(SyncFailure::new)?;
         Ok(())
@@ -377,7 +468,8 @@ impl BuildSpec {
                                                      // TODO (CM): pass through and enable
                                                      // ignore-local mode
                                                      &LocalPackageUsage::default(),
-                                                     InstallHookMode::Ignore).await?;
+                                                     InstallHookMode::Ignore,
+                                                     &SignaturePolicy::from_env()?).await?;
 
         // TODO (CM): Ideally, the typing of PackageInstall would be
         // such that we'd automatically get a
@@ -395,12 +487,22 @@ impl BuildSpec {
 pub struct BuildRoot {
     /// The temporary directory under which all root file system and other related files and
     /// directories will be created.
-    workdir: TempDir,
+    workdir:        TempDir,
     /// The build root context containing information about Biome packages, `PATH` info, etc.
-    ctx:     BuildRootContext,
+    ctx:            BuildRootContext,
     /// Dependency graph of the Biome packages installed in the
     /// build root
-    graph:   Graph,
+    graph:          Graph,
+    /// How packages are spread across layers, for multi-layer images.
+    layers:         Option<LayerPlan>,
+    /// The primary service as rendered for a bare image, if building
+    /// one.
+    bare:           Option<BareService>,
+    /// What the image runs as, if building a hardened one.
+    hardened:       Option<Hardened>,
+    /// Whether a service spec for the primary service was baked into
+    /// the image.
+    preloaded_spec: bool,
 }
 
 impl BuildRoot {
@@ -412,6 +514,20 @@ impl BuildRoot {
 
     pub fn graph(&self) -> &Graph { &self.graph }
 
+    /// Returns the layers to add packages in, for multi-layer images.
+    pub fn layer_plan(&self) -> Option<&LayerPlan> { self.layers.as_ref() }
+
+    /// Returns the rendered primary service, for bare images.
+    pub fn bare_service(&self) -> Option<&BareService> { self.bare.as_ref() }
+
+    /// Returns what the image runs as, for hardened images.
+    pub fn hardened(&self) -> Option<&Hardened> { self.hardened.as_ref() }
+
+    /// Returns whether the image has a spec for the primary service,
+    /// which the Supervisor loads in place of being given the service
+    /// to run.
+    pub fn preloaded_spec(&self) -> bool { self.preloaded_spec }
+
     /// Destroys the temporary build root.
     ///
     /// Note that the `BuildRoot` will automatically destroy itself when it falls out of scope, so
@@ -583,9 +699,7 @@ impl BuildRootContext {
         let gid = DEFAULT_USER_AND_GROUP_ID;
 
         let pkg = self.primary_svc()?;
-        let user_name = pkg.svc_user()
-                           .unwrap_or_default()
-                           .unwrap_or_else(|| String::from("hab"));
+        let user_name = self.svc_user_name()?;
         let group_name = pkg.svc_group()
                             .unwrap_or_default()
                             .unwrap_or_else(|| String::from("hab"));
@@ -712,6 +826,22 @@ impl BuildRootContext {
         Ok((users, groups))
     }
 
+    /// Returns the user a hardened image runs as: the primary service's
+    /// user, or the `hab` user if the service runs as root.
+    pub fn hardened_user(&self) -> Result<EtcPasswdEntry> {
+        let (users, _) = self.svc_users_and_groups()?;
+        let name = self.svc_user_name()?;
+        let user = accounts::non_root_user(&users, &name).expect("A non-root user is always added");
+        Ok(user.clone())
+    }
+
+    fn svc_user_name(&self) -> Result<String> {
+        Ok(self.primary_svc()?
+               .svc_user()
+               .unwrap_or_default()
+               .unwrap_or_else(|| String::from("hab")))
+    }
+
     /// Returns the `bin` path which is used for all program symlinking.
     pub fn bin_path(&self) -> &Path { self.bin_path.as_ref() }
 
@@ -748,10 +878,12 @@ impl BuildRootContext {
 pub struct BasePkgIdents {
     /// Installed package identifer for the Biome CLI package.
     pub bio:      FullyQualifiedPackageIdent,
-    /// Installed package identifer for the Supervisor package.
-    pub sup:      FullyQualifiedPackageIdent,
-    /// Installed package identifer for the Launcher package.
-    pub launcher: FullyQualifiedPackageIdent,
+    /// Installed package identifer for the Supervisor package, unless
+    /// building a bare image.
+    pub sup:      Option<FullyQualifiedPackageIdent>,
+    /// Installed package identifer for the Launcher package, unless
+    /// building a bare image.
+    pub launcher: Option<FullyQualifiedPackageIdent>,
     /// Installed package identifer for the Busybox package.
     pub busybox:  Option<FullyQualifiedPackageIdent>,
     /// Installed package identifer for the CA certs package.
@@ -813,7 +945,13 @@ mod test {
                     idents_or_archives: Vec::new(),
                     auth:               Some("heresafakeauthtokenduh".to_string()),
                     base_image:         "scratch".to_string(),
-                    multi_layer:        false, }
+                    multi_layer:        false,
+                    max_layers:         None,
+                    layer_report:       false,
+                    sbom:               None,
+                    bare:               false,
+                    hardened:           false,
+                    preload:            Preload::default(), }
     }
 
     struct FakePkg {
@@ -956,8 +1094,8 @@ mod test {
         #[cfg(not(windows))]
         fn base_pkgs<P: AsRef<Path>>(rootfs: P) -> BasePkgIdents {
             BasePkgIdents { bio:      fake_bio_install(&rootfs),
-                            sup:      fake_sup_install(&rootfs),
-                            launcher: fake_launcher_install(&rootfs),
+                            sup:      Some(fake_sup_install(&rootfs)),
+                            launcher: Some(fake_launcher_install(&rootfs)),
                             busybox:  Some(fake_busybox_install(&rootfs)),
                             cacerts:  fake_cacerts_install(&rootfs), }
         }
@@ -2,8 +2,11 @@ use crate::{engine,
             RegistryType};
 use clap::{App,
            Arg};
-use biome_common::PROGRAM_NAME;
-use biome_core::package::PackageIdent;
+use biome_common::{preload,
+                   sbom::SbomFormat,
+                   PROGRAM_NAME};
+use biome_core::{origin::Origin,
+                 package::PackageIdent};
 use std::{path::Path,
           result,
           str::FromStr};
@@ -24,10 +27,15 @@ pub fn cli<'a, 'b>() -> App<'a, 'b> {
                                        .add_publishing_args()
                                        .add_memory_arg()
                                        .add_layer_arg()
+                                       .add_sbom_arg()
+                                       .add_provenance_arg()
+                                       .add_preload_args()
                                        .add_pkg_ident_arg()
                                        .add_engine_arg();
     if cfg!(windows) {
         cli = cli.add_base_image_arg();
+    } else {
+        cli = cli.add_bare_arg().add_hardened_arg();
     }
     cli.app
 }
@@ -311,28 +319,117 @@ impl<'a, 'b> Cli<'a, 'b> {
                                                   .takes_value(false)
                                                   .multiple(false)
                                                   .help("If specified, creates an image where \
-                                                         each Biome package is added in its \
-                                                         own layer, in dependency order (that \
-                                                         is, low-level dependencies are added \
-                                                         first, with user packages added last). \
-                                                         This will allow for reusable layers, \
-                                                         reducing storage and network \
-                                                         transmission costs. If the resulting \
-                                                         image cannot be built because there \
-                                                         are too many layers, re-build without \
-                                                         specifying this option to add all \
-                                                         Biome packages in a single layer \
-                                                         (which is the default behavior)."));
+                                                         Biome packages are spread across layers \
+                                                         by how often they are likely to change \
+                                                         (that is, the Supervisor and its \
+                                                         dependencies are added first, then \
+                                                         dependencies shared by user packages, \
+                                                         then those of each user package, with \
+                                                         user packages added last). This will \
+                                                         allow for reusable layers, reducing \
+                                                         storage and network transmission costs. \
+                                                         If the resulting image cannot be built \
+                                                         because there are too many layers, \
+                                                         limit them with --max-layers, or \
+                                                         re-build without specifying this option \
+                                                         to add all Biome packages in a single \
+                                                         layer (which is the default behavior)."))
+                .arg(Arg::with_name("MAX_LAYERS").value_name("MAX_LAYERS")
+                                                 .long("max-layers")
+                                                 .takes_value(true)
+                                                 .requires("MULTI_LAYER")
+                                                 .validator(valid_layer_count)
+                                                 .help("The most layers to spread Biome packages \
+                                                        across with --multi-layer; the smallest \
+                                                        neighboring layers are merged until the \
+                                                        image fits"))
+                .arg(Arg::with_name("LAYER_REPORT").long("layer-report")
+                                                   .requires("MULTI_LAYER")
+                                                   .help("Show which packages landed in which \
+                                                          layer with --multi-layer, and their \
+                                                          sizes"));
+        Cli { app }
+    }
+
+    fn add_sbom_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("SBOM").value_name("FORMAT")
+                                                     .long("sbom")
+                                                     .possible_values(SbomFormat::VARIANTS)
+                                                     .help("Embed a software bill of materials \
+                                                            in the given format for each \
+                                                            package in the image, under \
+                                                            /hab/sbom"));
+        Cli { app }
+    }
+
+    fn add_provenance_arg(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("PROVENANCE").value_name("ORIGIN")
+                                                       .long("provenance")
+                                                       .validator(Origin::validate)
+                                                       .help("Attach a provenance attestation \
+                                                              listing the image's Biome \
+                                                              artifacts, signed with the latest \
+                                                              secret signing key of ORIGIN, to \
+                                                              the image in its OCI image layout \
+                                                              or registry (requires --push-image \
+                                                              or --image-format oci)"));
+        Cli { app }
+    }
+
+    fn add_preload_args(self) -> Self {
+        let app = self.app.args(&preload::args());
+        Cli { app }
+    }
+
+    fn add_bare_arg(self) -> Self {
+        let app =
+            self.app.arg(Arg::with_name("BARE").long("bare")
+                                               .conflicts_with_all(&["HAB_SUP_PKG",
+                                                                     "HAB_LAUNCHER_PKG"])
+                                               .conflicts_with_all(preload::SPEC_ARGS)
+                                               .help("Create an image without a Supervisor or \
+                                                      Launcher, which renders the primary \
+                                                      service's configuration at build time and \
+                                                      runs its init and run hooks directly, \
+                                                      with its health-check hook as the image's \
+                                                      HEALTHCHECK. Services which require binds \
+                                                      cannot be exported this way."));
+        Cli { app }
+    }
+
+    fn add_hardened_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("HARDENED").long("hardened")
+                                                         .conflicts_with("BARE")
+                                                         .help("Create an image with no shell or \
+                                                                busybox, which runs the \
+                                                                Supervisor as the service's user \
+                                                                (or `hab`, for services which run \
+                                                                as root), with /hab/pkgs \
+                                                                read-only and /hab/svc as its \
+                                                                only writable volume. Hooks which \
+                                                                need a shell are reported, as \
+                                                                they will not run."));
         Cli { app }
     }
 
     fn add_engine_arg(self) -> Self {
         let arg = engine::cli_arg();
         let app = self.app.arg(arg);
+        #[cfg(not(windows))]
+        let app = app.args(&engine::native_cli_args());
         Cli { app }
     }
 }
 
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_layer_count(val: String) -> result::Result<(), String> {
+    match val.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number of layers", &val)),
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
 fn valid_ident_or_hart(val: String) -> result::Result<(), String> {
     if Path::new(&val).is_file() {
@@ -1,6 +1,8 @@
 use crate::{build::BuildRoot,
             engine::Engine,
             error::Result,
+            hardened,
+            layering,
             naming::{ImageIdentifiers,
                      Naming},
             util};
@@ -142,7 +144,11 @@ impl BuildContext {
     pub fn from_build_root(build_root: BuildRoot, ui: &mut UI) -> Result<Self> {
         let context = BuildContext(build_root);
         context.add_users_and_groups(ui)?;
-        context.create_entrypoint(ui)?;
+        // Hardened images run the Supervisor directly, having no
+        // shell to run an entrypoint script with.
+        if context.0.hardened().is_none() {
+            context.create_entrypoint(ui)?;
+        }
         context.create_dockerfile(ui)?;
 
         Ok(context)
@@ -168,6 +174,11 @@ impl BuildContext {
     /// * If the temporary work directory cannot be removed
     pub fn destroy(self, ui: &mut UI) -> Result<()> { self.0.destroy(ui) }
 
+    /// The directory holding the root file system and the `Dockerfile`.
+    pub fn workdir(&self) -> &Path { self.0.workdir() }
+
+    pub(crate) fn build_root(&self) -> &BuildRoot { &self.0 }
+
     #[cfg(unix)]
     fn add_users_and_groups(&self, ui: &mut UI) -> Result<()> {
         use std::{fs::OpenOptions,
@@ -204,20 +215,45 @@ impl BuildContext {
 
         /// The entrypoint script template.
         const INIT_SH: &str = include_str!("../defaults/init.sh.hbs");
+        /// The entrypoint script template for bare images.
+        const INIT_BARE_SH: &str = include_str!("../defaults/init_bare.sh.hbs");
 
         ui.status(Status::Creating, "entrypoint script")?;
         let ctx = self.0.ctx();
         let busybox_shell =
             util::pkg_path_for(&util::busybox_ident()?, ctx.rootfs())?.join("bin/sh");
-        let json = json!({
-            "busybox_shell": busybox_shell,
-            "path": ctx.env_path(),
-            "sup_bin": format!("{} sup", ctx.bin_path().join("bio").display()),
-            "primary_svc_ident": ctx.primary_svc_ident().to_string(),
-        });
+        let (template, json) = match self.0.bare_service() {
+            Some(svc) => {
+                svc.write(ctx.rootfs())?;
+                (INIT_BARE_SH,
+                 json!({
+                     "busybox_shell": busybox_shell,
+                     "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+                     "environment": svc.shell_environment(),
+                     "path": svc.path(),
+                     "svc_path": svc.svc_path(),
+                     "svc_user": svc.svc_user(),
+                     "svc_group": svc.svc_group(),
+                     "init_hook": svc.init_hook(),
+                     "run_hook": svc.run_hook(),
+                     "health_check_hook": svc.health_check_hook(),
+                     "shutdown_signal": svc.shutdown_signal().to_string(),
+                 }))
+            }
+            None => {
+                (INIT_SH,
+                 json!({
+                     "busybox_shell": busybox_shell,
+                     "path": ctx.env_path(),
+                     "sup_bin": format!("{} sup", ctx.bin_path().join("bio").display()),
+                     "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+                     "preloaded_spec": self.0.preloaded_spec(),
+                 }))
+            }
+        };
         let init = ctx.rootfs().join("init.sh");
         util::write_file(&init,
-                         &Handlebars::new().template_render(INIT_SH, &json)
+                         &Handlebars::new().template_render(template, &json)
                                            .map_err(SyncFailure::new)?)?;
         posix_perm::set_permissions(init.to_string_lossy().as_ref(), 0o0755)?;
         Ok(())
@@ -226,11 +262,33 @@ impl BuildContext {
     fn create_dockerfile(&self, ui: &mut UI) -> Result<()> {
         ui.status(Status::Creating, "image Dockerfile")?;
         let ctx = self.0.ctx();
+        let rootfs = ctx.rootfs()
+                        .file_name()
+                        .expect("file_name exists")
+                        .to_string_lossy()
+                        .into_owned();
+        let hardened = self.0.hardened();
+        // Hardened images are given their packages on their own, so
+        // that the directories their user owns can be added after.
+        let hab_extras = if ctx.multi_layer() || hardened.is_some() {
+            layering::hab_extras(ctx.rootfs())?
+        } else {
+            Vec::new()
+        };
+        let owner = hardened.map(|h| format!("{}:{}", h.uid(), h.gid()));
+        let hab_extras = hab_extras.into_iter()
+                                   .map(|name| {
+                                       let writable = hardened::WRITABLE_DIRS
+                                           .contains(&format!("hab/{}", name).as_str());
+                                       json!({
+                                           "name": name,
+                                           "owner": owner.as_ref().filter(|_| writable),
+                                       })
+                                   })
+                                   .collect::<Vec<_>>();
         let json = json!({
             "base_image": ctx.base_image(),
-            "rootfs": ctx.rootfs().file_name().expect("file_name exists")
-                .to_string_lossy()
-                .as_ref(),
+            "rootfs": rootfs,
             "path": ctx.env_path(),
             "bio_path": util::pkg_path_for(
                 &PackageIdent::from_str("biome/bio")?,
@@ -238,11 +296,26 @@ impl BuildContext {
                 .to_string_lossy()
                 .replace("\\", "/"),
             "exposes": ctx.svc_exposes().join(" "),
+            "bare": self.0.bare_service().is_some(),
+            "health_check": self.0
+                                .bare_service()
+                                .and_then(|svc| svc.health_check_hook())
+                                .is_some(),
             "multi_layer": ctx.multi_layer(),
+            "layers": self.layer_stages(&rootfs),
+            "hab_extras": hab_extras,
+            "hardened": hardened.map(|h| {
+                json!({
+                    "user": owner,
+                    "volume": hardened::VOLUME,
+                    "scratch": hardened::SCRATCH_DIRS,
+                    "entrypoint": serde_json::to_string(h.entrypoint()).expect("strings serialize"),
+                })
+            }),
             "primary_svc_ident": ctx.primary_svc_ident().to_string(),
+            "preloaded_spec": self.0.preloaded_spec(),
             "installed_primary_svc_ident": ctx.installed_primary_svc_ident()?.to_string(),
             "environment": ctx.environment,
-            "packages": self.0.graph().reverse_topological_sort().iter().map(ToString::to_string).collect::<Vec<_>>(),
         });
         util::write_file(self.0.workdir().join("Dockerfile"),
                          &Handlebars::new().template_render(DOCKERFILE, &json)
@@ -250,6 +323,30 @@ impl BuildContext {
         Ok(())
     }
 
+    /// For multi-layer images, the packages of each layer are gathered
+    /// in a build stage of their own, which is then copied into the
+    /// image in one go.
+    fn layer_stages(&self, rootfs: &str) -> Vec<serde_json::Value> {
+        let layers = match self.0.layer_plan() {
+            Some(plan) => plan.layers(),
+            None => return Vec::new(),
+        };
+        layers.iter()
+              .enumerate()
+              .map(|(i, layer)| {
+                  let copies = layer.packages()
+                                    .map(|pkg| {
+                                        json!({
+                                            "src": format!("{}/hab/pkgs/{}", rootfs, pkg),
+                                            "dest": format!("/hab/pkgs/{}", pkg),
+                                        })
+                                    })
+                                    .collect::<Vec<_>>();
+                  json!({ "stage": format!("layer{}", i), "copies": copies })
+              })
+              .collect()
+    }
+
     /// Build the image locally using the provided naming policy.
     pub fn export(&self,
                   ui: &mut UI,
@@ -268,7 +365,7 @@ impl BuildContext {
                                expanded_identifiers, } =
             naming.image_identifiers(&ident, &channel)?;
 
-        let id = engine.build(self.0.workdir(), &expanded_identifiers, memory)?;
+        let id = engine.build(self, &expanded_identifiers, memory)?;
 
         // TODO (CM): Once ContainerImage doesn't need access to
         // workdir, we could just have Engine::build return a
@@ -3,9 +3,12 @@
 //! which underlying tool is actually performing those tasks.
 //!
 //! This allows us to swap out the `docker` CLI for `buildah` if we
-//! want to create containers as a non-root user, for instance.
+//! want to create containers as a non-root user, for instance, or for
+//! the `native` engine, which writes the image out itself without any
+//! container tooling at all.
 
-use crate::error::Result;
+use crate::{container::BuildContext,
+            error::Result};
 use clap::{Arg,
            ArgMatches};
 use biome_core::fs::find_command;
@@ -20,6 +23,8 @@ use std::{convert::TryFrom,
 #[cfg(not(windows))]
 mod buildah;
 mod docker;
+#[cfg(not(windows))]
+mod native;
 
 #[derive(Debug, Fail)]
 enum EngineError {
@@ -40,6 +45,10 @@ enum EngineError {
     #[fail(display = "Cannot use `--engine=buildah` with `--multi-layer` due to https://github.com/containers/buildah/issues/2215. Please use `--engine=docker` or remove `--multi-layer`.")]
     BuildahIncompatibleWithMultiLayer,
     #[cfg(not(windows))]
+    #[fail(display = "The native engine writes images to disk and cannot push them. Please push \
+                      the image with a tool such as skopeo, or use `--engine=docker`.")]
+    NativeCannotPush,
+    #[cfg(not(windows))]
     #[fail(display = "{}", _0)]
     EngineSpecificError(failure::Error),
 }
@@ -68,6 +77,8 @@ enum EngineKind {
     Docker,
     #[cfg(not(windows))]
     Buildah,
+    #[cfg(not(windows))]
+    Native,
 }
 
 impl FromStr for EngineKind {
@@ -78,6 +89,8 @@ impl FromStr for EngineKind {
             "docker" => Ok(EngineKind::Docker),
             #[cfg(not(windows))]
             "buildah" => Ok(EngineKind::Buildah),
+            #[cfg(not(windows))]
+            "native" => Ok(EngineKind::Native),
             _ => Err(EngineError::UnknownEngine(s.to_string())),
         }
     }
@@ -118,13 +131,37 @@ daemon. This is the recommended engine for use in CI systems and
 other environments where security is of particular concern.
 Please see https://buildah.io for more details.
 
-Both engines create equivalent container images.
+Using the `native` engine writes the image out directly, as an OCI
+image layout or a `docker-archive` tarball (see --image-format and
+--image-output), with no daemon, container tooling, or root access.
+Its layers are reproducible: the same packages always produce the
+same image. It cannot run package install hooks, nor push images.
+
+All engines create equivalent container images.
 ",
         )
-            .possible_values(&["docker", "buildah"])
+            .possible_values(&["docker", "buildah", "native"])
     }
 }
 
+/// Define the CLAP CLI arguments governing the images written by the
+/// `native` engine.
+#[cfg(not(windows))]
+pub fn native_cli_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![Arg::with_name("IMAGE_FORMAT").value_name("IMAGE_FORMAT")
+                                       .long("image-format")
+                                       .takes_value(true)
+                                       .possible_values(native::ImageFormat::VARIANTS)
+                                       .default_value("oci")
+                                       .help("The format of the image written by the native \
+                                              engine"),
+         Arg::with_name("IMAGE_OUTPUT").value_name("PATH")
+                                       .long("image-output")
+                                       .takes_value(true)
+                                       .help("Where the native engine writes the image (default: \
+                                              results/oci-image or results/docker-image.tar)"),]
+}
+
 impl TryFrom<&ArgMatches<'_>> for Box<dyn Engine> {
     type Error = failure::Error;
 
@@ -135,11 +172,49 @@ impl TryFrom<&ArgMatches<'_>> for Box<dyn Engine> {
             EngineKind::Docker => Ok(Box::new(docker::DockerEngine::new()?)),
             #[cfg(not(windows))]
             EngineKind::Buildah => Ok(Box::new(buildah::BuildahEngine::new()?)),
+            #[cfg(not(windows))]
+            EngineKind::Native => {
+                if value.is_present("PUSH_IMAGE") {
+                    return Err(EngineError::NativeCannotPush.into());
+                }
+                let format = clap::value_t!(value, "IMAGE_FORMAT", native::ImageFormat)
+                    .expect("IMAGE_FORMAT has a default value");
+                let output = value.value_of("IMAGE_OUTPUT").map(PathBuf::from);
+                Ok(Box::new(native::NativeEngine::new(format, output)?))
+            }
         }
     }
 }
 
 pub trait Engine {
+    /// Delete the referenced image in the local image store.
+    fn remove_image(&self, image_reference: &str) -> Result<()>;
+
+    /// Pushes the specified container image to a remote repository, using
+    /// configuration stored in `config_dir`.
+    fn push_image(&self, image_reference: &str, config_dir: &Path) -> Result<()>;
+
+    /// Actually create the image from the prepared `context`, tagging
+    /// it with each of `tags`.
+    ///
+    /// `memory` governs how much memory is provided to the build
+    /// process, where the engine runs one.
+    ///
+    /// Returns the ID of the image that was built.
+    fn build(&self,
+             context: &BuildContext,
+             tags: &[String],
+             memory: Option<&str>)
+             -> Result<String>;
+
+    /// The OCI image layout the image is written to, if the engine
+    /// writes one rather than keeping the image in an image store.
+    fn oci_layout(&self) -> Option<&Path> { None }
+}
+
+/// An engine which builds images by driving the CLI of a container
+/// tool, such as `docker` or `buildah`, from a `Dockerfile`.
+trait CliEngine {
     /// A command that takes a container image reference and returns
     /// the ID of that image on the first line of standard output.
     fn image_id_command(&self, image_reference: &str) -> Command;
@@ -168,15 +243,14 @@ pub trait Engine {
             None => Err(EngineError::ImageIdNotFound(image_reference.to_string()).into()),
         }
     }
+}
 
-    /// Delete the referenced image in the local image store.
+impl<T: CliEngine> Engine for T {
     fn remove_image(&self, image_reference: &str) -> Result<()> {
         run(self.image_removal_command(image_reference),
             EngineError::RemoveFailed)
     }
 
-    /// Pushes the specified container image to a remote repository, using
-    /// configuration stored in `config_dir`.
     // TODO (CM): accept repository URL information
     // TODO (CM): worth taking credential / repo information and
     // handling the config directory stuff internally?
@@ -185,18 +259,15 @@ pub trait Engine {
             EngineError::PushFailed)
     }
 
-    /// Actually create the image.
-    ///
-    /// `build_context` will serve as the build context directory, and
-    /// a suitable `Dockerfile` is expected to be present in it. The
-    /// image will be tagged with each of `tags`.
-    ///
-    /// `memory` governs how much memory is provided to the build
-    /// process.
-    ///
-    /// Returns the ID of the image that was built.
-    fn build(&self, build_context: &Path, tags: &[String], memory: Option<&str>) -> Result<String> {
-        run(self.build_command(build_context, tags, memory),
+    /// The context's work directory serves as the build context
+    /// directory, in which a suitable `Dockerfile` is expected to be
+    /// present.
+    fn build(&self,
+             context: &BuildContext,
+             tags: &[String],
+             memory: Option<&str>)
+             -> Result<String> {
+        run(self.build_command(context.workdir(), tags, memory),
             EngineError::BuildFailed)?;
 
         let identifier = tags.first()
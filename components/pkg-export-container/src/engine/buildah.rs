@@ -1,5 +1,5 @@
 use super::{resolve_engine_binary,
-            Engine,
+            CliEngine,
             EngineError};
 use std::{io::Write,
           path::{Path,
@@ -60,7 +60,7 @@ impl BuildahEngine {
     }
 }
 
-impl Engine for BuildahEngine {
+impl CliEngine for BuildahEngine {
     /// `buildah images -q mycompany/coolapp`
     fn image_id_command(&self, image_reference: &str) -> Command {
         let mut cmd = Command::new(&self.binary);
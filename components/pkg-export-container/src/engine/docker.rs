@@ -1,5 +1,5 @@
 use super::{resolve_engine_binary,
-            Engine,
+            CliEngine,
             EngineError};
 use std::{path::{Path,
                  PathBuf},
@@ -18,7 +18,7 @@ impl DockerEngine {
     }
 }
 
-impl Engine for DockerEngine {
+impl CliEngine for DockerEngine {
     /// `docker images -q mycompany/coolapp`
     fn image_id_command(&self, image_reference: &str) -> Command {
         let mut cmd = Command::new(&self.binary);
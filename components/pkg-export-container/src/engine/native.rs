@@ -0,0 +1,734 @@
+//! An engine which writes container images out itself, rather than
+//! driving a container tool.
+//!
+//! The image mirrors what the `Dockerfile` produces, layer for layer,
+//! but is assembled directly from the prepared root file system. No
+//! daemon, container tooling, or root access is needed, and since
+//! every layer is written deterministically (sorted entries, fixed
+//! ownership and timestamps), the same packages always produce the
+//! same image.
+
+use super::{Engine,
+            EngineError};
+use crate::{container::BuildContext,
+            error::Result,
+            hardened,
+            layering};
+use biome_core::package::PackageIdent;
+use flate2::{write::GzEncoder,
+             Compression};
+use sha2::{Digest,
+           Sha256};
+use std::{collections::BTreeMap,
+          env,
+          fmt,
+          fs::{self,
+               File},
+          io::{self,
+               Write},
+          os::unix::fs::PermissionsExt,
+          path::{Path,
+                 PathBuf},
+          str::FromStr};
+use tar::{Builder,
+          EntryType,
+          Header};
+
+const OCI_LAYOUT_VERSION: &str = "1.0.0";
+const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.image.config.v1+json";
+const OCI_LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+/// The annotation naming an image in an OCI layout by its tag.
+const OCI_REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+/// The annotation containerd (and so `ctr image import`) uses to name
+/// an image by its full reference.
+const CONTAINERD_NAME_ANNOTATION: &str = "io.containerd.image.name";
+
+/// Honored for the timestamps of every entry in every layer, per
+/// https://reproducible-builds.org/specs/source-date-epoch/
+const SOURCE_DATE_EPOCH_ENVVAR: &str = "SOURCE_DATE_EPOCH";
+
+/// How the built image is written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// An OCI image layout directory.
+    Oci,
+    /// A tarball that can be given to `docker load`.
+    DockerArchive,
+}
+
+impl ImageFormat {
+    /// The names accepted on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["oci", "docker-archive"];
+
+    /// Where the image is written, relative to the results directory,
+    /// if no other location is given.
+    fn default_output(self) -> &'static str {
+        match self {
+            ImageFormat::Oci => "oci-image",
+            ImageFormat::DockerArchive => "docker-image.tar",
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            ImageFormat::Oci => "oci",
+            ImageFormat::DockerArchive => "docker-archive",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = NativeError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "oci" => Ok(ImageFormat::Oci),
+            "docker-archive" => Ok(ImageFormat::DockerArchive),
+            _ => Err(NativeError::UnknownImageFormat(value.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub(super) enum NativeError {
+    #[fail(display = "Unknown image format '{}'", _0)]
+    UnknownImageFormat(String),
+    #[fail(display = "The native engine can only build images from scratch, not from the base \
+                      image '{}'. Please use `--engine=docker` or `--engine=buildah`.",
+           _0)]
+    UnsupportedBaseImage(String),
+    #[fail(display = "{} has an install hook, which the native engine cannot run. Please use \
+                      `--engine=docker` or `--engine=buildah`.",
+           _0)]
+    InstallHookPresent(PackageIdent),
+}
+
+#[derive(Debug)]
+pub(super) struct NativeEngine {
+    format: ImageFormat,
+    /// Where the image is written; any existing image there is
+    /// replaced.
+    output: PathBuf,
+}
+
+impl NativeEngine {
+    pub fn new(format: ImageFormat, output: Option<PathBuf>) -> Result<Self> {
+        let output = match output {
+            Some(output) => output,
+            None => {
+                env::current_dir()?.join("results")
+                                   .join(format.default_output())
+            }
+        };
+        Ok(NativeEngine { format, output })
+    }
+
+    /// The native engine cannot run the `bio pkg install` step of
+    /// the `Dockerfile`, nor layer on top of another image, so refuse
+    /// to build anything which would need either.
+    fn check_supported(context: &BuildContext) -> Result<()> {
+        let ctx = context.build_root().ctx();
+        if ctx.base_image() != "scratch" {
+            return Err(NativeError::UnsupportedBaseImage(ctx.base_image().to_string()).into());
+        }
+        for pkg in context.build_root().graph().reverse_topological_sort() {
+            let hook = ctx.rootfs()
+                          .join("hab/pkgs")
+                          .join(pkg.to_string())
+                          .join("hooks/install");
+            if hook.is_file() {
+                return Err(NativeError::InstallHookPresent(pkg).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Lay out the image's layers as the `Dockerfile` would.
+    fn layers(context: &BuildContext) -> Result<Vec<Layer>> {
+        let root = context.build_root();
+        let rootfs = root.ctx().rootfs();
+        let hardened = root.hardened();
+        let new_layer = || Layer::new(hardened.is_some());
+        let mut layers = Vec::new();
+
+        if root.layer_plan().is_some() || hardened.is_some() {
+            match root.layer_plan() {
+                Some(plan) => {
+                    for package_layer in plan.layers() {
+                        let mut layer = new_layer();
+                        for pkg in package_layer.packages() {
+                            layer.add_tree(rootfs, &format!("hab/pkgs/{}", pkg))?;
+                        }
+                        layers.push(layer);
+                    }
+                }
+                None => {
+                    let mut layer = new_layer();
+                    layer.add_tree(rootfs, "hab/pkgs")?;
+                    layers.push(layer);
+                }
+            }
+            // The directories a hardened image's user owns go in a
+            // layer of their own, which is all that user can write to.
+            let mut layer = new_layer();
+            let mut owned = new_layer();
+            owned.owner = hardened.map(|h| (h.uid(), h.gid()));
+            for name in layering::hab_extras(rootfs)? {
+                let path = format!("hab/{}", name);
+                if hardened.is_some() && hardened::WRITABLE_DIRS.contains(&path.as_str()) {
+                    owned.add_tree(rootfs, &path)?;
+                } else {
+                    layer.add_tree(rootfs, &path)?;
+                }
+            }
+            for layer in vec![layer, owned] {
+                if !layer.is_empty() {
+                    layers.push(layer);
+                }
+            }
+        } else {
+            let mut layer = new_layer();
+            layer.add_tree(rootfs, "hab")?;
+            layers.push(layer);
+        }
+
+        let mut layer = new_layer();
+        layer.add_tree(rootfs, "bin")?;
+        layers.push(layer);
+
+        let mut layer = new_layer();
+        if hardened.is_none() {
+            layer.add_dir("root", 0o750);
+        }
+        layer.add_dir("tmp", 0o1777);
+        layer.add_dir("var", 0o755);
+        layer.add_dir("var/tmp", 0o1777);
+        layer.add_tree(rootfs, "etc")?;
+        if hardened.is_none() {
+            layer.add_tree(rootfs, "init.sh")?;
+        }
+        layers.push(layer);
+
+        Ok(layers)
+    }
+
+    fn config(context: &BuildContext, diff_ids: &[String]) -> serde_json::Value {
+        let ctx = context.build_root().ctx();
+        let bare = context.build_root().bare_service();
+        let mut exposed_ports = serde_json::Map::new();
+        if bare.is_none() {
+            exposed_ports.insert("9631/tcp".to_string(), json!({}));
+        }
+        for port in ctx.svc_exposes() {
+            exposed_ports.insert(exposed_port(port), json!({}));
+        }
+        let mut config = json!({
+            "Env": [format!("PATH={}", ctx.env_path())],
+            "ExposedPorts": exposed_ports,
+            "Entrypoint": ["/init.sh"],
+        });
+        if let Some(hardened) = context.build_root().hardened() {
+            config["User"] = json!(format!("{}:{}", hardened.uid(), hardened.gid()));
+            config["Volumes"] = json!({ (hardened::VOLUME): {} });
+            config["Entrypoint"] = json!(hardened.entrypoint());
+        }
+        match bare {
+            Some(svc) => {
+                if svc.health_check_hook().is_some() {
+                    config["Healthcheck"] =
+                        json!({ "Test": ["CMD", "/init.sh", "--health-check"] });
+                }
+            }
+            // The entrypoint of a hardened image already runs the service
+            None if context.build_root().hardened().is_some() => config["Cmd"] = json!([]),
+            None if context.build_root().preloaded_spec() => config["Cmd"] = json!(["run"]),
+            None => config["Cmd"] = json!(["run", ctx.primary_svc_ident().to_string()]),
+        }
+        json!({
+            "architecture": oci_architecture(),
+            "os": "linux",
+            "config": config,
+            "rootfs": {
+                "type": "layers",
+                "diff_ids": diff_ids,
+            },
+        })
+    }
+
+    /// Write the image as an OCI image layout in `dir`, returning the
+    /// digests of the config and the manifest.
+    fn write_oci_layout(&self,
+                        context: &BuildContext,
+                        tags: &[String],
+                        dir: &Path)
+                        -> Result<(Descriptor, Vec<Descriptor>)> {
+        let blobs = dir.join("blobs").join("sha256");
+        fs::create_dir_all(&blobs)?;
+
+        let mtime = source_date_epoch();
+        let mut diff_ids = Vec::new();
+        let mut layers = Vec::new();
+        for layer in Self::layers(context)? {
+            let (diff_id, descriptor) = layer.write_blob(&blobs, mtime)?;
+            diff_ids.push(diff_id);
+            layers.push(descriptor);
+        }
+
+        let config = write_json_blob(&blobs,
+                                     OCI_CONFIG_MEDIA_TYPE,
+                                     &Self::config(context, &diff_ids))?;
+        let manifest = json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_MANIFEST_MEDIA_TYPE,
+            "config": config.to_json(),
+            "layers": layers.iter().map(Descriptor::to_json).collect::<Vec<_>>(),
+        });
+        let manifest = write_json_blob(&blobs, OCI_MANIFEST_MEDIA_TYPE, &manifest)?;
+
+        let manifests = tags.iter()
+                            .map(|tag| {
+                                let mut descriptor = manifest.to_json();
+                                descriptor["annotations"] = json!({
+                                    CONTAINERD_NAME_ANNOTATION: tag,
+                                    OCI_REF_NAME_ANNOTATION: tag_of(tag),
+                                });
+                                descriptor
+                            })
+                            .collect::<Vec<_>>();
+        let index = json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_INDEX_MEDIA_TYPE,
+            "manifests": manifests,
+        });
+        fs::write(dir.join("index.json"), serde_json::to_vec(&index)?)?;
+        fs::write(dir.join("oci-layout"),
+                  serde_json::to_vec(&json!({ "imageLayoutVersion": OCI_LAYOUT_VERSION }))?)?;
+
+        Ok((config, layers))
+    }
+
+    /// Write the image as a `docker load`-able tarball. The tarball
+    /// is also a complete OCI image layout, so tools which understand
+    /// `oci-archive` can read it too.
+    fn write_docker_archive(&self, context: &BuildContext, tags: &[String]) -> Result<Descriptor> {
+        let staging = context.workdir().join("docker-archive");
+        let (config, layers) = self.write_oci_layout(context, tags, &staging)?;
+        let manifest = json!([{
+            "Config": config.blob_path(),
+            "RepoTags": tags,
+            "Layers": layers.iter().map(Descriptor::blob_path).collect::<Vec<_>>(),
+        }]);
+        fs::write(staging.join("manifest.json"),
+                  serde_json::to_vec(&manifest)?)?;
+
+        let mut archive = Layer::default();
+        for name in sorted_dir_names(&staging)? {
+            archive.walk(&staging, Path::new(&name))?;
+        }
+        if let Some(parent) = self.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        archive.write_tar(File::create(&self.output)?, source_date_epoch())?;
+        Ok(config)
+    }
+}
+
+impl Engine for NativeEngine {
+    /// Images are only ever written to `output`, never to an image
+    /// store, so there is nothing to remove.
+    fn remove_image(&self, image_reference: &str) -> Result<()> {
+        debug!("Nothing to remove for {}; the native engine has no image store",
+               image_reference);
+        Ok(())
+    }
+
+    fn push_image(&self, _image_reference: &str, _config_dir: &Path) -> Result<()> {
+        Err(EngineError::NativeCannotPush.into())
+    }
+
+    fn build(&self,
+             context: &BuildContext,
+             tags: &[String],
+             _memory: Option<&str>)
+             -> Result<String> {
+        Self::check_supported(context)?;
+
+        let config = match self.format {
+            ImageFormat::Oci => {
+                if self.output.exists() {
+                    fs::remove_dir_all(&self.output)?;
+                }
+                self.write_oci_layout(context, tags, &self.output)?.0
+            }
+            ImageFormat::DockerArchive => self.write_docker_archive(context, tags)?,
+        };
+        info!("Wrote {} image to {}", self.format, self.output.display());
+        Ok(config.digest())
+    }
+
+    fn oci_layout(&self) -> Option<&Path> {
+        match self.format {
+            ImageFormat::Oci => Some(&self.output),
+            ImageFormat::DockerArchive => None,
+        }
+    }
+}
+
+/// A reference to a blob, as found in OCI manifests and indexes.
+#[derive(Clone, Debug, PartialEq)]
+struct Descriptor {
+    media_type: &'static str,
+    /// The hex-encoded sha256 digest of the blob.
+    hex:        String,
+    size:       u64,
+}
+
+impl Descriptor {
+    fn digest(&self) -> String { format!("sha256:{}", self.hex) }
+
+    /// Where the blob lives, relative to the root of the layout.
+    fn blob_path(&self) -> String { format!("blobs/sha256/{}", self.hex) }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "mediaType": self.media_type,
+            "digest": self.digest(),
+            "size": self.size,
+        })
+    }
+}
+
+/// Passes writes through to `inner`, keeping a sha256 digest and a
+/// count of everything written.
+struct DigestWriter<W> {
+    inner:  W,
+    hasher: Sha256,
+    size:   u64,
+}
+
+impl<W: Write> DigestWriter<W> {
+    fn new(inner: W) -> Self {
+        DigestWriter { inner,
+                       hasher: Sha256::new(),
+                       size: 0 }
+    }
+
+    /// Returns the inner writer, the hex-encoded digest, and the size
+    /// of everything written.
+    fn finish(self) -> (W, String, u64) {
+        (self.inner, format!("{:x}", self.hasher.finalize()), self.size)
+    }
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+}
+
+#[derive(Debug)]
+enum Source {
+    /// A directory with the given mode, which has no counterpart in
+    /// the root file system.
+    Dir(u32),
+    /// A file, directory, or symlink in the root file system.
+    Path(PathBuf),
+}
+
+/// The contents of a single image layer.
+#[derive(Debug, Default)]
+struct Layer {
+    /// Keyed (and so sorted) by their path in the image.
+    entries:    BTreeMap<String, Source>,
+    /// The user and group which own everything taken from the root
+    /// file system, if not root.
+    owner:      Option<(u32, u32)>,
+    /// Whether directories keep their modes, rather than giving the
+    /// group the owner's access under `/hab`.
+    keep_modes: bool,
+}
+
+impl Layer {
+    fn new(keep_modes: bool) -> Self {
+        Layer { keep_modes,
+                ..Layer::default() }
+    }
+
+    fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    fn add_dir(&mut self, path: &str, mode: u32) {
+        self.entries.insert(path.to_string(), Source::Dir(mode));
+    }
+
+    /// Add `path` beneath `rootfs` and everything within it, along
+    /// with any parent directories, as `COPY` would.
+    fn add_tree(&mut self, rootfs: &Path, path: &str) -> Result<()> {
+        for parent in Path::new(path).ancestors().skip(1) {
+            if parent != Path::new("") {
+                self.entries
+                    .entry(parent.to_string_lossy().into_owned())
+                    .or_insert(Source::Dir(0o755));
+            }
+        }
+        self.walk(rootfs, Path::new(path))
+    }
+
+    fn walk(&mut self, root: &Path, path: &Path) -> Result<()> {
+        let source = root.join(path);
+        let is_dir = fs::symlink_metadata(&source)?.is_dir();
+        self.entries.insert(path.to_string_lossy().into_owned(),
+                            Source::Path(source.clone()));
+        if is_dir {
+            for name in sorted_dir_names(&source)? {
+                self.walk(root, &path.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the layer as a gzipped tarball into `blobs`, returning
+    /// its diff ID (the digest of the uncompressed tarball) and a
+    /// descriptor of the blob.
+    fn write_blob(&self, blobs: &Path, mtime: u64) -> Result<(String, Descriptor)> {
+        let partial = blobs.join("layer.partial");
+        let compressed = DigestWriter::new(File::create(&partial)?);
+        let uncompressed = DigestWriter::new(GzEncoder::new(compressed, Compression::default()));
+        let (encoder, diff_id, _) = self.write_tar(uncompressed, mtime)?.finish();
+        let (_, hex, size) = encoder.finish()?.finish();
+        fs::rename(&partial, blobs.join(&hex))?;
+        Ok((format!("sha256:{}", diff_id),
+            Descriptor { media_type: OCI_LAYER_MEDIA_TYPE,
+                         hex,
+                         size }))
+    }
+
+    fn write_tar<W: Write>(&self, writer: W, mtime: u64) -> Result<W> {
+        let mut builder = Builder::new(writer);
+        for (path, source) in self.entries.iter() {
+            let mut header = Header::new_gnu();
+            header.set_uid(0);
+            header.set_gid(0);
+            header.set_mtime(mtime);
+            header.set_size(0);
+            match source {
+                Source::Dir(mode) => {
+                    header.set_entry_type(EntryType::Directory);
+                    header.set_mode(self.dir_mode(path, *mode));
+                    builder.append_data(&mut header, path, io::empty())?;
+                }
+                Source::Path(source) => {
+                    if let Some((uid, gid)) = self.owner {
+                        header.set_uid(uid.into());
+                        header.set_gid(gid.into());
+                    }
+                    let metadata = fs::symlink_metadata(source)?;
+                    let mode = metadata.permissions().mode() & 0o7777;
+                    if metadata.file_type().is_symlink() {
+                        header.set_entry_type(EntryType::Symlink);
+                        header.set_mode(0o777);
+                        // Link names over 100 bytes need a GNU long link entry, which
+                        // `append_link` writes and `set_link_name` cannot
+                        builder.append_link(&mut header, path, fs::read_link(source)?)?;
+                    } else if metadata.is_dir() {
+                        header.set_entry_type(EntryType::Directory);
+                        header.set_mode(self.dir_mode(path, mode));
+                        builder.append_data(&mut header, path, io::empty())?;
+                    } else {
+                        header.set_entry_type(EntryType::Regular);
+                        header.set_mode(mode);
+                        header.set_size(metadata.len());
+                        builder.append_data(&mut header, path, File::open(source)?)?;
+                    }
+                }
+            }
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// In order to be able to run a Supervisor as non-root, the group
+    /// needs the same access to directories under /hab as their owner
+    /// (the `find /hab -type d -exec chmod g=u {} \;` of the
+    /// `Dockerfile`). Hardened images have an owner of their own for
+    /// what the Supervisor writes to, so keep their modes.
+    fn dir_mode(&self, path: &str, mode: u32) -> u32 {
+        if !self.keep_modes && (path == "hab" || path.starts_with("hab/")) {
+            (mode & !0o070) | ((mode & 0o700) >> 3)
+        } else {
+            mode
+        }
+    }
+}
+
+fn write_json_blob(blobs: &Path,
+                   media_type: &'static str,
+                   value: &serde_json::Value)
+                   -> Result<Descriptor> {
+    let content = serde_json::to_vec(value)?;
+    let hex = format!("{:x}", Sha256::digest(&content));
+    fs::write(blobs.join(&hex), &content)?;
+    Ok(Descriptor { media_type,
+                    hex,
+                    size: content.len() as u64 })
+}
+
+fn sorted_dir_names(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn source_date_epoch() -> u64 {
+    env::var(SOURCE_DATE_EPOCH_ENVVAR).ok()
+                                      .and_then(|s| s.parse().ok())
+                                      .unwrap_or(0)
+}
+
+/// Ports are exposed over TCP unless a protocol is given, as with
+/// `EXPOSE`.
+fn exposed_port(port: &str) -> String {
+    if port.contains('/') {
+        port.to_string()
+    } else {
+        format!("{}/tcp", port)
+    }
+}
+
+/// The tag portion of an image reference such as
+/// `registry:5000/org/app:1.0`.
+fn tag_of(reference: &str) -> &str {
+    match reference.rfind(':') {
+        Some(i) if !reference[i..].contains('/') => &reference[i + 1..],
+        _ => "latest",
+    }
+}
+
+/// The name for this architecture in OCI image configs, which follow
+/// Go's `GOARCH` names.
+fn oci_architecture() -> &'static str {
+    match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rootfs() -> TempDir {
+        let rootfs = TempDir::new().unwrap();
+        let pkg = rootfs.path().join("hab/pkgs/core/foo/1.0.0/20200101000000");
+        fs::create_dir_all(pkg.join("bin")).unwrap();
+        fs::write(pkg.join("bin/foo"), "#!/bin/sh\necho foo\n").unwrap();
+        fs::set_permissions(pkg.join("bin/foo"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::create_dir_all(rootfs.path().join("bin")).unwrap();
+        std::os::unix::fs::symlink("/hab/pkgs/core/foo/1.0.0/20200101000000/bin/foo",
+                                   rootfs.path().join("bin/foo")).unwrap();
+        rootfs
+    }
+
+    fn blob(layer: &Layer) -> (String, Descriptor) {
+        let blobs = TempDir::new().unwrap();
+        layer.write_blob(blobs.path(), 0).unwrap()
+    }
+
+    #[test]
+    fn layers_are_reproducible() {
+        let first = rootfs();
+        let second = rootfs();
+        // Nothing about when the files were written should leak into
+        // the layer.
+        fs::write(second.path()
+                        .join("hab/pkgs/core/foo/1.0.0/20200101000000/bin/foo"),
+                  "#!/bin/sh\necho foo\n").unwrap();
+
+        let mut a = Layer::default();
+        a.add_tree(first.path(), "hab").unwrap();
+        a.add_tree(first.path(), "bin").unwrap();
+        let mut b = Layer::default();
+        b.add_tree(second.path(), "bin").unwrap();
+        b.add_tree(second.path(), "hab").unwrap();
+
+        assert_eq!(blob(&a), blob(&b));
+    }
+
+    #[test]
+    fn hab_directories_are_group_writable() {
+        let layer = Layer::default();
+        assert_eq!(layer.dir_mode("hab/pkgs/core", 0o755), 0o775);
+        assert_eq!(layer.dir_mode("hab", 0o700), 0o770);
+        assert_eq!(layer.dir_mode("habitat", 0o755), 0o755);
+        assert_eq!(layer.dir_mode("tmp", 0o1777), 0o1777);
+        assert_eq!(Layer::new(true).dir_mode("hab/pkgs/core", 0o755), 0o755);
+    }
+
+    #[test]
+    fn owned_layers_only_own_what_is_in_the_root_file_system() {
+        let rootfs = rootfs();
+        let mut layer = Layer::new(true);
+        layer.owner = Some((42, 43));
+        layer.add_tree(rootfs.path(), "hab/pkgs").unwrap();
+
+        let tar = layer.write_tar(Vec::new(), 0).unwrap();
+        let mut archive = tar::Archive::new(tar.as_slice());
+        for entry in archive.entries().unwrap() {
+            let header = entry.unwrap().header().clone();
+            let path = header.path().unwrap().into_owned();
+            let owner = (header.uid().unwrap(), header.gid().unwrap());
+            if path == Path::new("hab") {
+                assert_eq!(owner, (0, 0), "parent directories are owned by root");
+            } else {
+                assert_eq!(owner,
+                           (42, 43),
+                           "{} is owned by the layer's owner",
+                           path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn long_symlink_targets_are_kept_whole() {
+        let rootfs = TempDir::new().unwrap();
+        let target = format!("/hab/pkgs/core/{}/1.0.0/20200101000000/bin/foo",
+                             "x".repeat(100));
+        fs::create_dir_all(rootfs.path().join("bin")).unwrap();
+        std::os::unix::fs::symlink(&target, rootfs.path().join("bin/foo")).unwrap();
+        let mut layer = Layer::default();
+        layer.add_tree(rootfs.path(), "bin").unwrap();
+
+        let tar = layer.write_tar(Vec::new(), 0).unwrap();
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let link = archive.entries()
+                          .unwrap()
+                          .map(Result::unwrap)
+                          .find(|entry| entry.path().unwrap() == Path::new("bin/foo"))
+                          .unwrap();
+        assert_eq!(link.link_name().unwrap().unwrap(), Path::new(&target));
+    }
+
+    #[test]
+    fn image_references_have_tags() {
+        assert_eq!(tag_of("core/redis:4.0.14"), "4.0.14");
+        assert_eq!(tag_of("registry:5000/core/redis"), "latest");
+        assert_eq!(tag_of("registry:5000/core/redis:latest"), "latest");
+        assert_eq!(exposed_port("6379"), "6379/tcp");
+        assert_eq!(exposed_port("53/udp"), "53/udp");
+    }
+}
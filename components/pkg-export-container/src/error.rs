@@ -1,4 +1,5 @@
 use base64::DecodeError;
+use biome_core::package::PackageIdent;
 use rusoto_core::RusotoError;
 use rusoto_ecr::GetAuthorizationTokenError;
 use std::result;
@@ -7,6 +8,14 @@ pub type Result<T> = result::Result<T, failure::Error>;
 
 #[derive(Debug, Fail)]
 pub enum Error {
+    #[fail(display = "{} requires binds ({}), which need a Supervisor, so it cannot be exported \
+                      as a bare image",
+           _0, _1)]
+    BareServiceRequiresBinds(PackageIdent, String),
+    #[fail(display = "{} has neither a run hook nor a run file, so it cannot be exported as a \
+                      bare image",
+           _0)]
+    BareServiceNotRunnable(PackageIdent),
     #[fail(display = "{}", _0)]
     Base64DecodeError(DecodeError),
     #[fail(display = "Invalid registry type: {}", _0)]
@@ -15,6 +24,10 @@ pub enum Error {
     NoECRTokensReturned,
     #[fail(display = "{}", _0)]
     TokenFetchFailed(RusotoError<GetAuthorizationTokenError>),
+    #[fail(display = "A provenance attestation can only be attached to an image written as an \
+                      OCI image layout (--image-format oci) or pushed to a registry \
+                      (--push-image)")]
+    ProvenanceNowhereToAttach,
     #[fail(display = "A primary service package could not be determined from: {:?}. At least \
                       one package with a run hook must be provided.",
            _0)]
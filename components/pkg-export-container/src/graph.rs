@@ -39,9 +39,13 @@ impl Graph {
         if let Some(ref busybox) = self.base.busybox {
             idents.push(busybox.as_ref().clone());
         }
-        idents.push(self.base.launcher.as_ref().clone());
+        if let Some(ref launcher) = self.base.launcher {
+            idents.push(launcher.as_ref().clone());
+        }
         idents.push(self.base.bio.as_ref().clone());
-        idents.push(self.base.sup.as_ref().clone());
+        if let Some(ref sup) = self.base.sup {
+            idents.push(sup.as_ref().clone());
+        }
         idents.push(self.base.cacerts.as_ref().clone());
         idents
     }
@@ -51,10 +55,23 @@ impl Graph {
     /// the underlying package graph, we do the same thing here. Once
     /// the type abstractions are more harmonized, we can dispense
     /// with this.
-    fn user_idents(&self) -> Vec<PackageIdent> {
+    pub fn user_idents(&self) -> Vec<PackageIdent> {
         self.user.iter().map(|fqpi| fqpi.as_ref().clone()).collect()
     }
 
+    /// The base packages and all their dependencies, in dependency
+    /// order.
+    pub fn base_packages(&self) -> Vec<PackageIdent> {
+        self.ordered_closure(self.idents_from_base())
+    }
+
+    /// The dependencies of `ident`, most basic first.
+    pub fn deps_of(&self, ident: &PackageIdent) -> Vec<PackageIdent> {
+        let mut deps = self.g.owned_ordered_deps(ident);
+        deps.reverse();
+        deps
+    }
+
     /// Return the list of packages to install in the image in
     /// dependency order.
     ///
@@ -66,30 +83,36 @@ impl Graph {
     /// their packages and creating images, this should mean that all
     /// the dependencies are already available as cached layers.
     pub fn reverse_topological_sort(&self) -> Vec<PackageIdent> {
-        self.idents_from_base()
-            .into_iter()
-            .chain(self.user_idents())
-            .map(|ident| {
-                let mut pkgs = self.g.owned_ordered_deps(&ident);
-                // We want the most basic dependencies first.
-                pkgs.reverse();
-                // owned_ordered_deps does not include the given
-                // ident, so let's add it.
-                pkgs.push(ident);
-                pkgs
-            })
-            .flatten()
-            .fold(LinkedHashMap::new(), |mut acc, ident| {
-                // NOTE: We are using LinkedHashMap here to simulate
-                // an insertion-order-preserving Set. As of this
-                // writing (April 2020), however, LinkedHashMap is in
-                // maintenance mode. It is still used by things we
-                // depend on, though, so we're already using it,
-                // regardless. If this becomes problematic in the future,
-                // we can always revert to using a Vec directly. It's
-                // not as efficient, of course, but this call is not
-                // likely to be any sort of bottlneck in the creation
-                // of a container image.
+        self.ordered_closure(self.idents_from_base()
+                                 .into_iter()
+                                 .chain(self.user_idents())
+                                 .collect())
+    }
+
+    /// The given packages and all their dependencies, each package
+    /// appearing once, after all of its dependencies.
+    fn ordered_closure(&self, idents: Vec<PackageIdent>) -> Vec<PackageIdent> {
+        idents.into_iter()
+              .map(|ident| {
+                  // We want the most basic dependencies first.
+                  let mut pkgs = self.deps_of(&ident);
+                  // owned_ordered_deps does not include the given
+                  // ident, so let's add it.
+                  pkgs.push(ident);
+                  pkgs
+              })
+              .flatten()
+              .fold(LinkedHashMap::new(), |mut acc, ident| {
+                  // NOTE: We are using LinkedHashMap here to simulate
+                  // an insertion-order-preserving Set. As of this
+                  // writing (April 2020), however, LinkedHashMap is in
+                  // maintenance mode. It is still used by things we
+                  // depend on, though, so we're already using it,
+                  // regardless. If this becomes problematic in the future,
+                  // we can always revert to using a Vec directly. It's
+                  // not as efficient, of course, but this call is not
+                  // likely to be any sort of bottlneck in the creation
+                  // of a container image.
 
                 // You have to check first before inserting;
                 // otherwise, it increments the insertion order
@@ -108,7 +131,7 @@ impl Graph {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use std::convert::TryFrom;
 
@@ -156,7 +179,7 @@ mod tests {
 
     /// Create a Graph manually, bypassing the need to generate one
     /// based on the package contents of a local directory.
-    fn test_graph() -> Result<Graph> {
+    pub(crate) fn test_graph() -> Result<Graph> {
         let mut graph = PackageGraph::default();
 
         // bio, busybox, and cacerts have no dependencies
@@ -176,9 +199,9 @@ mod tests {
         // User package and its dependencies
         graph.extend(&redis(), &[glibc()]);
 
-        let base = BasePkgIdents { bio:      fqpi!(hab()),
-                                   sup:      fqpi!(sup()),
-                                   launcher: fqpi!(launcher()),
+        let base = BasePkgIdents { bio:      fqpi!(bio()),
+                                   sup:      Some(fqpi!(sup())),
+                                   launcher: Some(fqpi!(launcher())),
                                    busybox:  Some(fqpi!(busybox())),
                                    cacerts:  fqpi!(cacerts()), };
 
@@ -189,6 +212,18 @@ mod tests {
                    g: graph })
     }
 
+    /// Add user packages (and any new dependencies, given as pairs of
+    /// a package and its direct dependencies) to a test graph.
+    pub(crate) fn add_user_packages(graph: &mut Graph,
+                                    user: &[PackageIdent],
+                                    deps: &[(PackageIdent, Vec<PackageIdent>)]) {
+        for (ident, direct) in deps {
+            graph.g.extend(ident, direct);
+        }
+        graph.user
+             .extend(user.iter().cloned().map(|ident| fqpi!(ident)));
+    }
+
     #[test]
     fn reverse_topological_sort_produces_the_correct_ordering() {
         let g = test_graph().unwrap();
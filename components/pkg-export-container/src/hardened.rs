@@ -0,0 +1,206 @@
+//! Prepares the build root for a "hardened" image, which has no shell
+//! or busybox and runs as a fixed, non-root user.
+//!
+//! Nothing but `bio` and the packages' own binaries is linked into
+//! `/bin`, and the Supervisor is run directly rather than through an
+//! entrypoint script. The entrypoint is `bio sup run` with the primary
+//! service, so that, as with the entrypoint script, the arguments an
+//! image is run with are those of `bio sup run`. `/hab/pkgs` is owned
+//! by root, and so read-only
+//! to the service's user, which owns only `/hab/svc` (the image's one
+//! volume) and `/hab/sup` (where the Supervisor keeps its state).
+//!
+//! The Supervisor runs hooks directly, so a hook whose interpreter is
+//! not in the image (`#!/bin/sh`, most commonly) cannot run. Such
+//! hooks are reported while the image is built.
+
+use crate::{accounts::EtcPasswdEntry,
+            build::BuildRootContext,
+            error::Result,
+            graph::Graph,
+            util};
+use biome_common::ui::{Status,
+                       UIWriter,
+                       UI};
+use biome_core::{package::PackageIdent,
+                 util::posix_perm};
+use std::{fs::{self,
+               File},
+          io::{BufRead,
+               BufReader},
+          path::Path,
+          str::FromStr};
+
+/// The directories the service's user owns, relative to the root.
+pub const WRITABLE_DIRS: &[&str] = &["hab/svc", "hab/sup"];
+/// The image's only volume.
+pub const VOLUME: &str = "/hab/svc";
+/// Where the world-writable scratch directories every image needs
+/// are staged, relative to the work directory, so that they can be
+/// copied into the image with their modes intact.
+pub const SCRATCH_DIRS: &str = "scratch";
+/// The world-writable scratch directories, relative to the root.
+pub const TMP_DIRS: &[&str] = &["tmp", "var/tmp"];
+
+/// A hook which cannot run in a hardened image.
+#[derive(Debug, PartialEq)]
+pub struct ShellHook {
+    pub ident:       PackageIdent,
+    pub hook:        String,
+    /// The hook's interpreter, if it names one.
+    pub interpreter: Option<String>,
+}
+
+/// What a hardened image runs as, and how it is started.
+#[derive(Debug)]
+pub struct Hardened {
+    user:       EtcPasswdEntry,
+    entrypoint: Vec<String>,
+}
+
+impl Hardened {
+    /// Make the directories the service's user owns, stage the
+    /// scratch directories, and report any hook which will not run.
+    ///
+    /// # Errors
+    ///
+    /// * If the primary service package cannot be read
+    /// * If the directories cannot be created
+    pub fn prepare(ui: &mut UI,
+                   ctx: &BuildRootContext,
+                   graph: &Graph,
+                   workdir: &Path,
+                   preloaded_spec: bool)
+                   -> Result<Self> {
+        let rootfs = ctx.rootfs();
+        let user = ctx.hardened_user()?;
+        ui.status(Status::Creating,
+                  format!("hardened image running as '{}' ({}:{})",
+                          user.name, user.uid, user.gid))?;
+        for dir in WRITABLE_DIRS {
+            fs::create_dir_all(rootfs.join(dir))?;
+        }
+        for dir in TMP_DIRS {
+            let dir = workdir.join(SCRATCH_DIRS).join(dir);
+            fs::create_dir_all(&dir)?;
+            posix_perm::set_permissions(&dir, 0o1777)?;
+        }
+
+        let hooks = shell_hooks(rootfs, graph)?;
+        for hook in &hooks {
+            let reason = match hook.interpreter {
+                Some(ref interpreter) => format!("its interpreter {} is not", interpreter),
+                None => "it names no interpreter, and no shell is".to_string(),
+            };
+            ui.warn(format!("The {} hook of {} will not run: {} in a hardened image",
+                            hook.hook, hook.ident, reason))?;
+        }
+        if !hooks.is_empty() {
+            ui.warn("Give these hooks an interpreter from a package dependency, such as \
+                     #!{{pkgPathFor \"core/bash\"}}/bin/bash, or export without --hardened")?;
+        }
+
+        let bio = util::pkg_path_for(&PackageIdent::from_str("biome/bio")?, rootfs)?;
+        let mut entrypoint = vec![bio.join("bin/bio").to_string_lossy().into_owned(),
+                                  "sup".to_string(),
+                                  "run".to_string()];
+        // A preloaded spec already names the service to run.
+        if !preloaded_spec {
+            entrypoint.push(ctx.primary_svc_ident().to_string());
+        }
+        Ok(Hardened { user, entrypoint })
+    }
+
+    pub fn uid(&self) -> u32 { self.user.uid }
+
+    pub fn gid(&self) -> u32 { self.user.gid }
+
+    /// The image's entrypoint, which runs the Supervisor directly with
+    /// the primary service. Images run with arguments pass them on to
+    /// `bio sup run`.
+    pub fn entrypoint(&self) -> &[String] { &self.entrypoint }
+}
+
+/// Every hook of every package in the image whose interpreter will
+/// not be in a hardened image.
+///
+/// Interpreters are taken from the hooks' `#!` lines. Those under
+/// `/hab` are in the image if a package provides them, and templated
+/// ones (`{{pkgPathFor ...}}`) are assumed to be; anything else, such
+/// as `/bin/sh` or `/usr/bin/env`, is not.
+fn shell_hooks(rootfs: &Path, graph: &Graph) -> Result<Vec<ShellHook>> {
+    let mut hooks = Vec::new();
+    for ident in graph.reverse_topological_sort() {
+        let dir = rootfs.join("hab/pkgs")
+                        .join(ident.to_string())
+                        .join("hooks");
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            names.push(entry?.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        for name in names {
+            let interpreter = interpreter(&dir.join(&name))?;
+            let runs = match interpreter {
+                Some(ref interpreter) if interpreter.contains("{{") => true,
+                Some(ref interpreter) if interpreter.starts_with("/hab/") => {
+                    rootfs.join(&interpreter[1..]).exists()
+                }
+                _ => false,
+            };
+            if !runs {
+                hooks.push(ShellHook { ident: ident.clone(),
+                                       hook: name,
+                                       interpreter });
+            }
+        }
+    }
+    Ok(hooks)
+}
+
+/// The interpreter named by the `#!` line of a hook, if any.
+fn interpreter(hook: &Path) -> Result<Option<String>> {
+    let mut line = String::new();
+    BufReader::new(File::open(hook)?).read_line(&mut line)?;
+    if !line.starts_with("#!") {
+        return Ok(None);
+    }
+    let line = line[2..].trim();
+    // A templated interpreter may contain spaces, so runs at least to
+    // the end of its closing braces.
+    let start = line.rfind("}}").map_or(0, |braces| braces + 2);
+    let end = line[start..].find(char::is_whitespace)
+                           .map_or(line.len(), |end| start + end);
+    Ok(Some(line[..end].to_string()).filter(|interpreter| !interpreter.is_empty()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn hook(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn interpreters_are_read_from_the_first_line() {
+        let dir = TempDir::new().unwrap();
+        let dir = dir.path();
+
+        assert_eq!(interpreter(&hook(dir, "sh", "#!/bin/sh\necho hi\n")).unwrap(),
+                   Some("/bin/sh".to_string()));
+        assert_eq!(interpreter(&hook(dir, "env", "#!/usr/bin/env bash\n")).unwrap(),
+                   Some("/usr/bin/env".to_string()));
+        assert_eq!(interpreter(&hook(dir, "tmpl",
+                                     "#!{{pkgPathFor \"core/bash\"}}/bin/bash -e\n")).unwrap(),
+                   Some("{{pkgPathFor \"core/bash\"}}/bin/bash".to_string()));
+        assert_eq!(interpreter(&hook(dir, "none", "echo hi\n")).unwrap(), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Plans which packages go into which layer of a multi-layer image.
+//!
+//! Rather than giving every package a layer of its own, which can run
+//! into the limit on the number of layers an image may have, packages
+//! are grouped by how often they are likely to change: first a layer
+//! for the Supervisor, Launcher, `bio`, and their runtime
+//! dependencies, then the dependencies shared by the user packages,
+//! then the dependencies of each user package, and finally the user
+//! packages themselves. Rebuilding an image for a new release of a
+//! user package thus only changes the last layers.
+
+use crate::{error::Result,
+            graph::Graph};
+use biome_common::{ui::{UIWriter,
+                        UI},
+                   util::{dir_size,
+                          human_size}};
+use biome_core::package::PackageIdent;
+use std::{collections::{HashMap,
+                        HashSet},
+          fmt,
+          fs,
+          path::Path};
+
+/// What a layer holds, from the least to the most likely to change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayerKind {
+    /// The Supervisor, Launcher, `bio`, and their dependencies.
+    Base,
+    /// Dependencies of more than one user package.
+    Shared,
+    /// Dependencies of a single user package.
+    Dependencies,
+    /// User packages.
+    User,
+}
+
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            LayerKind::Base => "base",
+            LayerKind::Shared => "shared dependencies",
+            LayerKind::Dependencies => "dependencies",
+            LayerKind::User => "user",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A group of packages added to the image in a single layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackageLayer {
+    kind:     LayerKind,
+    /// Each package, in dependency order, with its installed size in
+    /// bytes.
+    packages: Vec<(PackageIdent, u64)>,
+}
+
+impl PackageLayer {
+    pub fn kind(&self) -> LayerKind { self.kind }
+
+    pub fn packages(&self) -> impl Iterator<Item = &PackageIdent> {
+        self.packages.iter().map(|(ident, _)| ident)
+    }
+
+    /// The installed size of all the layer's packages, in bytes.
+    pub fn size(&self) -> u64 { self.packages.iter().map(|(_, size)| size).sum() }
+}
+
+#[derive(Debug)]
+pub struct LayerPlan {
+    layers: Vec<PackageLayer>,
+}
+
+impl LayerPlan {
+    /// Plan the layers for the packages in `graph`, as installed in
+    /// `rootfs`, using at most `max_layers` layers if given.
+    pub fn new(graph: &Graph, rootfs: &Path, max_layers: Option<usize>) -> Result<Self> {
+        let mut sizes = HashMap::new();
+        for ident in graph.reverse_topological_sort() {
+            let size = dir_size(&rootfs.join("hab/pkgs").join(ident.to_string()))?;
+            sizes.insert(ident, size);
+        }
+        Ok(Self::with_sizes(graph,
+                            |ident| {
+                                sizes.get(ident).copied().unwrap_or_default()
+                            },
+                            max_layers))
+    }
+
+    fn with_sizes<F>(graph: &Graph, size_of: F, max_layers: Option<usize>) -> Self
+        where F: Fn(&PackageIdent) -> u64
+    {
+        let order = graph.reverse_topological_sort();
+        let base: HashSet<_> = graph.base_packages().into_iter().collect();
+        let users = graph.user_idents();
+        let user_set: HashSet<_> = users.iter().cloned().collect();
+
+        // How many user packages depend on each package that isn't
+        // already in the base layer.
+        let mut dependents = HashMap::new();
+        let mut user_deps = Vec::new();
+        for user in users.iter() {
+            let deps: HashSet<_> =
+                graph.deps_of(user)
+                     .into_iter()
+                     .filter(|dep| !base.contains(dep) && !user_set.contains(dep))
+                     .collect();
+            for dep in deps.iter() {
+                *dependents.entry(dep.clone()).or_insert(0) += 1;
+            }
+            user_deps.push(deps);
+        }
+        let dependents_of = |ident: &PackageIdent| dependents.get(ident).copied().unwrap_or(0);
+
+        let layer = |kind, include: &dyn Fn(&PackageIdent) -> bool| {
+            PackageLayer { kind,
+                           packages: order.iter()
+                                          .filter(|ident| include(ident))
+                                          .map(|ident| (ident.clone(), size_of(ident)))
+                                          .collect() }
+        };
+        let mut layers = vec![layer(LayerKind::Base, &|ident| base.contains(ident)),
+                              layer(LayerKind::Shared, &|ident| dependents_of(ident) > 1),];
+        for deps in user_deps.iter() {
+            layers.push(layer(LayerKind::Dependencies, &|ident| {
+                            deps.contains(ident) && dependents_of(ident) == 1
+                        }));
+        }
+        for user in order.iter()
+                         .filter(|ident| user_set.contains(ident) && !base.contains(ident))
+        {
+            layers.push(layer(LayerKind::User, &|ident| ident == user));
+        }
+        layers.retain(|layer| !layer.packages.is_empty());
+
+        if let Some(max_layers) = max_layers {
+            merge_layers(&mut layers, max_layers);
+        }
+        LayerPlan { layers }
+    }
+
+    pub fn layers(&self) -> &[PackageLayer] { &self.layers }
+
+    /// Show which packages landed in which layer, and their sizes.
+    pub fn report(&self, ui: &mut UI) -> Result<()> {
+        ui.heading(format!("Package layers ({})", self.layers.len()))?;
+        for (i, layer) in self.layers.iter().enumerate() {
+            ui.info(format!("Layer {}: {} ({})",
+                            i + 1,
+                            layer.kind,
+                            human_size(layer.size())))?;
+            for (ident, size) in layer.packages.iter() {
+                ui.info(format!("    {} ({})", ident, human_size(*size)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Merge adjacent layers until there are no more than `max_layers`.
+///
+/// The adjacent pair which is smallest together is merged first, so
+/// large layers stay cacheable on their own for as long as possible.
+/// A merged layer is as likely to change as the more volatile of the
+/// two.
+fn merge_layers(layers: &mut Vec<PackageLayer>, max_layers: usize) {
+    while layers.len() > max_layers.max(1) {
+        let i = (0..layers.len() - 1).min_by_key(|&i| layers[i].size() + layers[i + 1].size())
+                                     .expect("there are at least two layers");
+        let next = layers.remove(i + 1);
+        layers[i].kind = next.kind;
+        layers[i].packages.extend(next.packages);
+    }
+}
+
+/// Everything under `/hab` other than the packages themselves (such
+/// as embedded SBOMs), which multi-layer images add after the package
+/// layers.
+pub fn hab_extras(rootfs: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(rootfs.join("hab"))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name != "pkgs" {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::{add_user_packages,
+                              test_graph};
+
+    fn ident(s: &str) -> PackageIdent { s.parse().unwrap() }
+
+    fn kinds_and_packages(plan: &LayerPlan) -> Vec<(LayerKind, Vec<String>)> {
+        plan.layers()
+            .iter()
+            .map(|layer| (layer.kind(), layer.packages().map(ToString::to_string).collect()))
+            .collect()
+    }
+
+    /// The test graph plus two services which share openssl, with
+    /// one also needing its own pcre.
+    fn graph() -> Graph {
+        let glibc = ident("core/glibc/2.27/20190115002733");
+        let openssl = ident("core/openssl/1.0.2t/20200306005450");
+        let pcre = ident("core/pcre/8.42/20190115012526");
+        let nginx = ident("core/nginx/1.17.4/20200306011839");
+        let haproxy = ident("core/haproxy/2.0.8/20200306031011");
+        let mut graph = test_graph().unwrap();
+        add_user_packages(&mut graph,
+                          &[nginx.clone(), haproxy.clone()],
+                          &[(openssl.clone(), vec![glibc.clone()]),
+                            (pcre.clone(), vec![glibc.clone()]),
+                            (nginx, vec![glibc.clone(), openssl.clone(), pcre]),
+                            (haproxy, vec![glibc, openssl])]);
+        graph
+    }
+
+    #[test]
+    fn packages_are_grouped_by_how_often_they_change() {
+        let plan = LayerPlan::with_sizes(&graph(), |_| 1, None);
+        let layers = kinds_and_packages(&plan);
+
+        assert_eq!(layers.len(), 6);
+        assert_eq!(layers[0].0, LayerKind::Base);
+        let base = &layers[0].1;
+        assert!(base.contains(&"biome/bio-sup/1.5.71/20200318174937".to_string()));
+        assert!(base.contains(&"core/glibc/2.27/20190115002733".to_string()));
+        assert_eq!(layers[1..].to_vec(),
+                   vec![(LayerKind::Shared,
+                         vec!["core/openssl/1.0.2t/20200306005450".to_string()]),
+                        (LayerKind::Dependencies,
+                         vec!["core/pcre/8.42/20190115012526".to_string()]),
+                        (LayerKind::User, vec!["core/redis/4.0.14/20190319155852".to_string()]),
+                        (LayerKind::User, vec!["core/nginx/1.17.4/20200306011839".to_string()]),
+                        (LayerKind::User, vec!["core/haproxy/2.0.8/20200306031011".to_string()]),]);
+    }
+
+    #[test]
+    fn the_smallest_neighbors_are_merged_to_fit_the_maximum() {
+        let size_of = |ident: &PackageIdent| if ident.name == "glibc" { 100 } else { 1 };
+        let plan = LayerPlan::with_sizes(&graph(), size_of, Some(2));
+        let layers = kinds_and_packages(&plan);
+
+        assert_eq!(layers.len(), 2);
+        // The big base layer is left alone, and everything else is
+        // merged into a single layer as likely to change as a user
+        // package.
+        assert_eq!(layers[0].0, LayerKind::Base);
+        assert_eq!(layers[1].0, LayerKind::User);
+        assert_eq!(layers[1].1.len(), 5);
+    }
+
+    #[test]
+    fn at_least_one_layer_is_kept() {
+        let plan = LayerPlan::with_sizes(&graph(), |_| 1, Some(0));
+
+        assert_eq!(plan.layers().len(), 1);
+        assert_eq!(plan.layers()[0].packages().count(),
+                   graph().reverse_topological_sort().len());
+    }
+}
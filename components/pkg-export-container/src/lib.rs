@@ -32,14 +32,18 @@ use std::{convert::TryFrom,
           result,
           str::FromStr};
 mod accounts;
+mod bare;
 mod build;
 mod cli;
 mod container;
 mod engine;
 mod error;
 mod graph;
+mod hardened;
+mod layering;
 mod naming;
 mod os;
+mod provenance;
 #[cfg(unix)]
 mod rootfs;
 mod util;
@@ -155,12 +159,24 @@ pub async fn export_for_cli_matches(ui: &mut UI,
     let naming = Naming::from(matches);
     let engine: Box<dyn Engine> = TryFrom::try_from(matches)?;
     let memory = matches.value_of("MEMORY_LIMIT");
+    let push = matches.is_present("PUSH_IMAGE");
+    let signing_key = match matches.value_of("PROVENANCE") {
+        Some(origin) if push || engine.oci_layout().is_some() => {
+            Some(provenance::signing_key(origin)?)
+        }
+        Some(_) => return Err(Error::ProvenanceNowhereToAttach.into()),
+        None => None,
+    };
 
     ui.begin(format!("Building a container image with: {}",
                      spec.idents_or_archives.join(", ")))?;
 
     let build_context = BuildContext::from_build_root(spec.create(ui).await?, ui)?;
     let container_image = build_context.export(ui, &naming, memory, engine.as_ref())?;
+    let materials = match signing_key {
+        Some(_) => provenance::materials(build_context.build_root().graph())?,
+        None => Vec::new(),
+    };
 
     build_context.destroy(ui)?;
     ui.end(format!("Container image '{}' created with tags: {}",
@@ -169,7 +185,10 @@ pub async fn export_for_cli_matches(ui: &mut UI,
 
     container_image.create_report(ui, env::current_dir()?.join("results"))?;
 
-    if matches.is_present("PUSH_IMAGE") {
+    if let (Some(key), Some(dir)) = (&signing_key, engine.oci_layout()) {
+        provenance::attach_to_layout(ui, dir, &container_image, &materials, key)?;
+    }
+    if push {
         let credentials = Credentials::new(naming.registry_type,
                                            matches.value_of("REGISTRY_USERNAME")
                                                   .expect("Username not specified"),
@@ -180,6 +199,10 @@ pub async fn export_for_cli_matches(ui: &mut UI,
                    &container_image,
                    &credentials,
                    naming.registry_url.as_deref())?;
+        if let Some(ref key) = signing_key {
+            provenance::attach_to_registry(ui, &container_image, &credentials, &materials, key)
+                .await?;
+        }
     }
     if matches.is_present("RM_IMAGE") {
         remove_image(ui, engine.as_ref(), &container_image)?;
@@ -0,0 +1,102 @@
+//! Signed provenance attestations for exported images, linking them
+//! to the Biome artifacts they were built from.
+
+use crate::{graph::Graph,
+            ContainerImage,
+            Credentials,
+            Result,
+            VERSION};
+use biome_common::{provenance::{Attachment,
+                                Descriptor,
+                                Layout,
+                                Material,
+                                Provenance,
+                                Reference,
+                                Registry},
+                   ui::{Status,
+                        UIWriter,
+                        UI},
+                   PROGRAM_NAME};
+use biome_core::{crypto::keys::{Key,
+                                KeyCache,
+                                SecretOriginSigningKey},
+                 fs::{cache_artifact_path,
+                      CACHE_KEY_PATH}};
+use std::path::Path;
+
+/// Load the latest secret signing key of `origin` from the host's
+/// key cache, to sign attestations with.
+///
+/// Only origin keys can sign; user keys are encryption keys.
+pub fn signing_key(origin: &str) -> Result<SecretOriginSigningKey> {
+    Ok(KeyCache::new(&*CACHE_KEY_PATH).latest_secret_origin_signing_key(origin)?)
+}
+
+/// Describe each package in the image by the artifact it was
+/// installed from, which is left in the host's artifact cache.
+pub fn materials(graph: &Graph) -> Result<Vec<Material>> {
+    let cache = cache_artifact_path(None::<&Path>);
+    let mut materials = Vec::new();
+    for ident in graph.reverse_topological_sort() {
+        let artifact = cache.join(ident.archive_name()?);
+        materials.push(Material::from_artifact(&ident, &artifact)?);
+    }
+    Ok(materials)
+}
+
+/// Sign the provenance of the image written to the OCI image layout
+/// at `dir` and add it to the layout.
+pub fn attach_to_layout(ui: &mut UI,
+                        dir: &Path,
+                        image: &ContainerImage,
+                        materials: &[Material],
+                        key: &SecretOriginSigningKey)
+                        -> Result<()> {
+    let layout = Layout::open(dir)?;
+    let subject = layout.image()?;
+    let attachment = attachment(ui, image, &subject, materials, key)?;
+    layout.attach(&attachment)?;
+    ui.status(Status::Created,
+              format!("provenance attestation in {}", dir.display()))?;
+    Ok(())
+}
+
+/// Sign the provenance of the pushed image and push it alongside the
+/// image.
+pub async fn attach_to_registry(ui: &mut UI,
+                                image: &ContainerImage,
+                                credentials: &Credentials,
+                                materials: &[Material],
+                                key: &SecretOriginSigningKey)
+                                -> Result<()> {
+    let identifier = &image.expanded_identifiers()[0];
+    let mut registry = Registry::new(identifier.parse::<Reference>()?,
+                                     Some(credentials.token.clone()),
+                                     &*PROGRAM_NAME,
+                                     VERSION)?;
+    let subject = registry.image().await?;
+    let attachment = attachment(ui, image, &subject, materials, key)?;
+    ui.status(Status::Uploading,
+              format!("provenance attestation of '{}'", identifier))?;
+    registry.attach(&attachment, &subject).await?;
+    ui.status(Status::Uploaded,
+              format!("provenance attestation of '{}'", identifier))?;
+    Ok(())
+}
+
+fn attachment(ui: &mut UI,
+              image: &ContainerImage,
+              subject: &Descriptor,
+              materials: &[Material],
+              key: &SecretOriginSigningKey)
+              -> Result<Attachment> {
+    ui.status(Status::Signing,
+              format!("provenance of {} with {}",
+                      subject.digest,
+                      key.named_revision()))?;
+    let provenance = Provenance { image: image.name(),
+                                  digest: subject.digest.clone(),
+                                  materials: materials.to_vec(),
+                                  builder: format!("{}/{}", &*PROGRAM_NAME, VERSION.trim()) };
+    Ok(Attachment::new(&provenance.sign(key)?, subject))
+}
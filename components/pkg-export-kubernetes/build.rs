@@ -0,0 +1,4 @@
+// Inline common build behavior
+include!("../libbuild.rs");
+
+fn main() { biome::common(); }
@@ -0,0 +1,139 @@
+use crate::manifest::{Topology,
+                      DEFAULT_GROUP};
+use biome_common::PROGRAM_NAME;
+use biome_core::package::PackageIdent;
+use clap::{App,
+           Arg};
+use std::{path::Path,
+          result,
+          str::FromStr};
+
+/// The version of this library and program when built.
+const VERSION: &str = include_str!(concat!(env!("OUT_DIR"), "/VERSION"));
+
+/// Create the Clap CLI for the Kubernetes exporter
+pub fn cli<'a, 'b>() -> App<'a, 'b> {
+    let name: &str = &*PROGRAM_NAME;
+    clap_app!((name) =>
+        (about: "Creates Kubernetes manifests, or a Helm chart, which run a Biome service \
+                 from its container image")
+        (version: VERSION)
+        (author: "\nAuthors: The Biome Maintainers <humans@biome.sh>\n\n")
+    ).arg(Arg::with_name("PKG_IDENT_OR_ARTIFACT").value_name("PKG_IDENT_OR_ARTIFACT")
+                                                 .required(true)
+                                                 .validator(valid_ident_or_hart)
+                                                 .help("An installed Biome package identifier \
+                                                        (ex: acme/redis) or filepath to a Biome \
+                                                        Artifact (ex: \
+                                                        /home/acme-redis-3.0.\
+                                                        7-21120102031201-x86_64-linux.hart)"))
+     .arg(Arg::with_name("IMAGE").long("image")
+                                 .value_name("IMAGE")
+                                 .help("The container image of the service, as exported by `bio \
+                                        pkg export container` (default: \
+                                        {{pkg_origin}}/{{pkg_name}}:\
+                                        {{pkg_version}}-{{pkg_release}})"))
+     .arg(Arg::with_name("NAME").long("name")
+                                .value_name("NAME")
+                                .help("The name of the Kubernetes objects (default: {{pkg_name}})"))
+     .arg(Arg::with_name("NAMESPACE").long("namespace")
+                                     .value_name("NAMESPACE")
+                                     .help("The namespace of the Kubernetes objects"))
+     .arg(Arg::with_name("REPLICAS").long("replicas")
+                                    .value_name("REPLICAS")
+                                    .default_value("1")
+                                    .validator(valid_replicas)
+                                    .help("The number of replicas of the service"))
+     .arg(Arg::with_name("TOPOLOGY").long("topology")
+                                    .value_name("TOPOLOGY")
+                                    .possible_values(Topology::VARIANTS)
+                                    .default_value("standalone")
+                                    .help("The service's topology; a leader topology is run as a \
+                                           StatefulSet of at least 3 replicas"))
+     .arg(Arg::with_name("GROUP").long("group")
+                                 .value_name("GROUP")
+                                 .default_value(DEFAULT_GROUP)
+                                 .help("The service group of the service"))
+     .arg(Arg::with_name("BIND").long("bind")
+                                .value_name("BIND")
+                                .multiple(true)
+                                .number_of_values(1)
+                                .validator(valid_bind)
+                                .help("Bind to another service to form a producer/consumer \
+                                       relationship (ex: --bind name:service.group)"))
+     .arg(Arg::with_name("PEER").long("peer")
+                                .value_name("PEER")
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help("An initial peer for the Supervisors, such as the peers \
+                                       Service of a bound service (default: the replicas find \
+                                       each other through a headless Service)"))
+     .arg(Arg::with_name("RING_SECRET").long("ring-secret")
+                                       .value_name("SECRET")
+                                       .help("The name of a Secret holding the ring key under \
+                                              \"ring-key\", for wire encryption"))
+     .arg(Arg::with_name("USER_TOML").long("user-toml")
+                                     .value_name("FILE")
+                                     .validator(valid_file)
+                                     .help("A user.toml for the service, mounted from a \
+                                            ConfigMap over any the image was exported with"))
+     .arg(Arg::with_name("BARE").long("bare")
+                                .conflicts_with_all(&["PEER", "RING_SECRET", "USER_TOML"])
+                                .help("The image was exported with `--bare`, and runs the \
+                                       service without a Supervisor"))
+     .arg(Arg::with_name("OUTPUT").long("output")
+                                  .short("o")
+                                  .value_name("FILE")
+                                  .help("Write the manifests to FILE rather than standard output"))
+     .arg(Arg::with_name("HELM_CHART").long("helm-chart")
+                                      .value_name("DIR")
+                                      .conflicts_with("OUTPUT")
+                                      .help("Write a Helm chart into DIR, with the image, the \
+                                             number of replicas, and the service's user.toml as \
+                                             values"))
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_ident_or_hart(val: String) -> result::Result<(), String> {
+    if Path::new(&val).is_file() {
+        Ok(())
+    } else if val.ends_with(".hart") {
+        Err(format!("Biome artifact file: '{}' not found", &val))
+    } else {
+        match PackageIdent::from_str(&val) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("{}", e)),
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_file(val: String) -> result::Result<(), String> {
+    if Path::new(&val).is_file() {
+        Ok(())
+    } else {
+        Err(format!("File: '{}' not found", &val))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_replicas(val: String) -> result::Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number of replicas", &val)),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // Signature required by CLAP
+fn valid_bind(val: String) -> result::Result<(), String> {
+    let mut parts = val.splitn(2, ':');
+    let name = parts.next().unwrap_or_default();
+    let mut service_group = parts.next().unwrap_or_default().splitn(2, '.');
+    let service = service_group.next().unwrap_or_default();
+    let group = service_group.next().unwrap_or_default();
+    if name.is_empty() || service.is_empty() || group.is_empty() {
+        Err(format!("'{}' is not a bind of the form name:service.group", &val))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use biome_core::package::PackageIdent;
+use std::{path::PathBuf,
+          result};
+
+pub type Result<T> = result::Result<T, failure::Error>;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "{} is a bare image, which cannot satisfy binds ({})",
+           _0, _1)]
+    BareImageWithBinds(PackageIdent, String),
+    #[fail(display = "{} has no run hook, so there is no service to deploy", _0)]
+    NotAService(PackageIdent),
+    #[fail(display = "{} is not installed; install it with `bio pkg install {}` or export a \
+                      .hart file instead",
+           _0, _0)]
+    PackageNotInstalled(PackageIdent),
+    #[fail(display = "The Helm chart directory {} already exists", _0)]
+    HelmChartExists(PathBuf),
+    #[fail(display = "{} requires binds ({}), which must be satisfied with --bind",
+           _0, _1)]
+    UnsatisfiedBinds(PackageIdent, String),
+    #[fail(display = "The leader topology needs at least 3 replicas, not {}", _0)]
+    TooFewForLeaderTopology(u32),
+}
@@ -0,0 +1,205 @@
+//! Writes the objects for a service as a Helm chart, with the image,
+//! the number of replicas, and the service's user.toml as values.
+//!
+//! The user.toml's ConfigMap, and the volume it is mounted from, are
+//! only rendered when the `config` value is set. The package's
+//! defaults are left in `values.yaml` as a commented out example.
+
+use crate::{error::{Error,
+                    Result},
+            manifest::{self,
+                       DeploymentSpec,
+                       Manifests,
+                       Values},
+            package::PackageMetadata};
+use std::{fs,
+          path::Path};
+
+/// The version of new charts.
+const CHART_VERSION: &str = "0.1.0";
+
+const IMAGE: &str = "{{ .Values.image | quote }}";
+const REPLICAS: &str = "{{ .Values.replicas }}";
+const CONFIG: &str = "{{ .Values.config | quote }}";
+const IF_CONFIG: &str = "{{- if .Values.config }}";
+const END: &str = "{{- end }}";
+/// The keys of the parts of the workload which are only rendered when
+/// the `config` value is set.
+const CONFIG_KEYS: &[&str] = &["volumes:", "volumeMounts:"];
+
+/// Write a chart for the service into `dir`, which must not exist yet.
+///
+/// # Errors
+///
+/// * If `dir` already exists or cannot be written to
+/// * If the objects cannot be generated
+pub fn write_chart(dir: &Path, pkg: &PackageMetadata, spec: &DeploymentSpec) -> Result<()> {
+    if dir.exists() {
+        return Err(Error::HelmChartExists(dir.to_path_buf()).into());
+    }
+    let manifests = Manifests::with_values(pkg,
+                                           spec,
+                                           &Values { image:    json!(IMAGE),
+                                                     replicas: json!(REPLICAS),
+                                                     config:   Some(json!(CONFIG)), },
+                                           false)?;
+
+    let templates = dir.join("templates");
+    fs::create_dir_all(&templates)?;
+    fs::write(dir.join("Chart.yaml"), chart(pkg, spec)?)?;
+    fs::write(dir.join("values.yaml"), values(pkg, spec)?)?;
+    for object in manifests.objects() {
+        let template = template(&manifest::to_yaml(&object.value)?);
+        let template = if object.file_name == "configmap.yaml" {
+            format!("{}\n{}{}\n", IF_CONFIG, template, END)
+        } else {
+            only_with_config(&template)
+        };
+        fs::write(templates.join(object.file_name), template)?;
+    }
+    Ok(())
+}
+
+fn chart(pkg: &PackageMetadata, spec: &DeploymentSpec) -> Result<String> {
+    manifest::to_yaml(&json!({
+                          "apiVersion": "v2",
+                          "name": spec.name,
+                          "description": format!("{} from Biome", pkg.ident),
+                          "type": "application",
+                          "version": CHART_VERSION,
+                          "appVersion": pkg.version_release(),
+                      }))
+}
+
+fn values(pkg: &PackageMetadata, spec: &DeploymentSpec) -> Result<String> {
+    let mut values = json!({
+        "image": spec.image,
+        "replicas": spec.replicas,
+    });
+    if spec.bare {
+        return manifest::to_yaml(&values);
+    }
+    if let Some(ref config) = spec.user_toml {
+        values["config"] = json!(config);
+    }
+    let mut values = manifest::to_yaml(&values)?;
+    if let Some(ref default_toml) = pkg.default_toml {
+        values.push_str("\n# Setting config mounts it as the service's user.toml, overriding \
+                         the\n# defaults of its package:\n#\n# config: |\n");
+        for line in default_toml.lines() {
+            values.push_str(format!("#   {}", line).trim_end());
+            values.push('\n');
+        }
+    }
+    Ok(values)
+}
+
+/// Template actions are strings in the objects, which have to be
+/// unquoted for Helm to substitute non-string values such as numbers.
+fn template(yaml: &str) -> String {
+    let mut template = yaml.to_string();
+    for action in &[IMAGE, REPLICAS, CONFIG] {
+        template = template.replace(&format!("\"{}\"", action), action)
+                           .replace(&format!("'{}'", action), action);
+    }
+    template
+}
+
+/// Wraps the blocks under `CONFIG_KEYS` in a condition on the
+/// `config` value.
+fn only_with_config(yaml: &str) -> String {
+    let mut template = String::new();
+    let mut block: Option<usize> = None;
+    for line in yaml.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if let Some(block_indent) = block {
+            if indent < block_indent || (indent == block_indent && !trimmed.starts_with("- ")) {
+                template.push_str(END);
+                template.push('\n');
+                block = None;
+            }
+        }
+        if block.is_none() && CONFIG_KEYS.contains(&trimmed) {
+            template.push_str(IF_CONFIG);
+            template.push('\n');
+            block = Some(indent);
+        }
+        template.push_str(line);
+        template.push('\n');
+    }
+    if block.is_some() {
+        template.push_str(END);
+        template.push('\n');
+    }
+    template
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::tests::{redis,
+                                 spec};
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    fn read_yaml(path: &Path) -> Value {
+        serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn the_chart_takes_the_image_replicas_and_config_from_values() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("redis");
+        let mut spec = spec();
+        spec.user_toml = Some("port = 6380\n".to_string());
+        write_chart(&dir, &redis(), &spec).unwrap();
+
+        assert_eq!(read_yaml(&dir.join("Chart.yaml"))["appVersion"],
+                   "4.0.14-20190319155852");
+        let values = read_yaml(&dir.join("values.yaml"));
+        assert_eq!(values["image"], "core/redis:4.0.14-20190319155852");
+        assert_eq!(values["replicas"], 1);
+        assert_eq!(values["config"], "port = 6380\n");
+
+        let deployment = fs::read_to_string(dir.join("templates/deployment.yaml")).unwrap();
+        assert!(deployment.contains("replicas: {{ .Values.replicas }}\n"));
+        assert!(deployment.contains("image: {{ .Values.image | quote }}\n"));
+        let config_map = fs::read_to_string(dir.join("templates/configmap.yaml")).unwrap();
+        assert!(config_map.starts_with("{{- if .Values.config }}\n"));
+        assert!(config_map.contains("user.toml: {{ .Values.config | quote }}\n"));
+        assert!(config_map.ends_with("{{- end }}\n"));
+    }
+
+    #[test]
+    fn the_package_defaults_are_only_an_example() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("redis");
+        write_chart(&dir, &redis(), &spec()).unwrap();
+
+        let values = fs::read_to_string(dir.join("values.yaml")).unwrap();
+        assert!(read_yaml(&dir.join("values.yaml"))["config"].is_null());
+        assert!(values.ends_with("# config: |\n#   port = 6379\n"));
+
+        let deployment = fs::read_to_string(dir.join("templates/deployment.yaml")).unwrap();
+        assert_eq!(deployment.matches("{{- if .Values.config }}").count(), 2);
+        assert_eq!(deployment.matches("{{- end }}").count(), 2);
+    }
+
+    #[test]
+    fn config_blocks_are_wrapped_in_a_condition() {
+        let yaml =
+            "spec:\n  volumes:\n  - name: config\n    configMap:\n      name: x\n  other: 1\n";
+
+        assert_eq!(only_with_config(yaml),
+                   "spec:\n{{- if .Values.config }}\n  volumes:\n  - name: config\n    \
+                    configMap:\n      name: x\n{{- end }}\n  other: 1\n");
+    }
+
+    #[test]
+    fn an_existing_directory_is_left_alone() {
+        let tmp = TempDir::new().unwrap();
+
+        assert!(write_chart(tmp.path(), &redis(), &spec()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate failure_derive;
+#[macro_use]
+extern crate serde_json;
+
+mod cli;
+mod error;
+mod helm;
+mod manifest;
+mod package;
+
+pub use crate::{cli::cli,
+                error::{Error,
+                        Result},
+                manifest::{DeploymentSpec,
+                           Manifests,
+                           Topology},
+                package::PackageMetadata};
+use biome_common::ui::{Status,
+                       UIWriter,
+                       UI};
+use clap::ArgMatches;
+use std::{fs,
+          path::Path};
+
+/// Reads the package named on the command line, and writes
+/// manifests or a Helm chart which deploy it.
+pub fn export_for_cli_matches(ui: &mut UI, matches: &ArgMatches<'_>) -> Result<()> {
+    let pkg = PackageMetadata::load(matches.value_of("PKG_IDENT_OR_ARTIFACT")
+                                           .expect("PKG_IDENT_OR_ARTIFACT is required"))?;
+    let spec = deployment_spec(matches, &pkg)?;
+
+    if let Some(dir) = matches.value_of("HELM_CHART") {
+        helm::write_chart(Path::new(dir), &pkg, &spec)?;
+        ui.status(Status::Created,
+                  format!("Helm chart for {} in {}", pkg.ident, dir))?;
+        return Ok(());
+    }
+    let yaml = Manifests::new(&pkg, &spec)?.to_yaml()?;
+    match matches.value_of("OUTPUT") {
+        Some(file) => {
+            fs::write(file, yaml)?;
+            ui.status(Status::Created,
+                      format!("Kubernetes manifests for {} in {}", pkg.ident, file))?;
+        }
+        None => print!("{}", yaml),
+    }
+    Ok(())
+}
+
+fn deployment_spec(m: &ArgMatches<'_>, pkg: &PackageMetadata) -> Result<DeploymentSpec> {
+    let values = |name| {
+        m.values_of(name)
+         .map(|values| values.map(str::to_string).collect())
+         .unwrap_or_default()
+    };
+    let image = match m.value_of("IMAGE") {
+        Some(image) => image.to_string(),
+        None => {
+            format!("{}/{}:{}",
+                    pkg.ident.origin,
+                    pkg.ident.name,
+                    pkg.version_release())
+        }
+    };
+    let user_toml = match m.value_of("USER_TOML") {
+        Some(file) => Some(fs::read_to_string(file)?),
+        None => None,
+    };
+    Ok(DeploymentSpec { name: m.value_of("NAME").unwrap_or(&pkg.ident.name).to_string(),
+                        namespace: m.value_of("NAMESPACE").map(str::to_string),
+                        image,
+                        replicas: value_t!(m, "REPLICAS", u32).expect("validated by CLAP"),
+                        topology: value_t!(m, "TOPOLOGY", Topology).expect("validated by CLAP"),
+                        group: m.value_of("GROUP")
+                                .expect("GROUP has a default")
+                                .to_string(),
+                        binds: values("BIND"),
+                        peers: values("PEER"),
+                        ring_secret: m.value_of("RING_SECRET").map(str::to_string),
+                        bare: m.is_present("BARE"),
+                        user_toml })
+}
@@ -0,0 +1,23 @@
+#[macro_use]
+extern crate log;
+
+use biome_common::ui::{UIWriter,
+                       UI};
+use biome_pkg_export_kubernetes::{cli,
+                                  export_for_cli_matches,
+                                  Result};
+
+fn main() {
+    env_logger::init();
+    let mut ui = UI::default_with_env();
+    if let Err(e) = start(&mut ui) {
+        ui.fatal(e).unwrap();
+        std::process::exit(1)
+    }
+}
+
+fn start(ui: &mut UI) -> Result<()> {
+    let m = cli().get_matches();
+    debug!("clap cli args: {:?}", m);
+    export_for_cli_matches(ui, &m)
+}
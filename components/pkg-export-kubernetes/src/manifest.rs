@@ -0,0 +1,684 @@
+//! Kubernetes objects which run a Biome service.
+//!
+//! Unless the image is bare, each replica runs a Supervisor, and the
+//! replicas find each other through a headless Service so that they
+//! form a ring. A user.toml given for the service is kept in a
+//! ConfigMap and mounted over the one the image may have been given,
+//! leaving the package's defaults where they are.
+
+use crate::{error::{Error,
+                    Result},
+            package::PackageMetadata};
+use serde_json::{Map,
+                 Value};
+use std::{fmt,
+          result,
+          str::FromStr};
+
+/// The port of the Supervisor's HTTP gateway.
+const HTTP_GATEWAY_PORT: u16 = 9631;
+/// The port of the Supervisor's gossip listener.
+const GOSSIP_PORT: u16 = 9638;
+/// The key of the ring key in the Secret given with `--ring-secret`.
+pub const RING_KEY_SECRET_KEY: &str = "ring-key";
+/// The service group Supervisors use unless told otherwise.
+pub const DEFAULT_GROUP: &str = "default";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+    Standalone,
+    Leader,
+}
+
+impl Topology {
+    pub const VARIANTS: &'static [&'static str] = &["standalone", "leader"];
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value {
+            "standalone" => Ok(Topology::Standalone),
+            "leader" => Ok(Topology::Leader),
+            _ => Err(format!("Invalid topology: {}", value)),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            Topology::Standalone => "standalone",
+            Topology::Leader => "leader",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How to deploy a service.
+#[derive(Clone, Debug)]
+pub struct DeploymentSpec {
+    /// The name of the Kubernetes objects.
+    pub name:        String,
+    pub namespace:   Option<String>,
+    /// The container image, as exported by `bio pkg export container`.
+    pub image:       String,
+    pub replicas:    u32,
+    pub topology:    Topology,
+    /// The service group of the service.
+    pub group:       String,
+    /// Binds, as `NAME:SERVICE.GROUP`, passed on to the Supervisor.
+    pub binds:       Vec<String>,
+    /// Initial peers of the Supervisors, instead of each other.
+    pub peers:       Vec<String>,
+    /// The name of a Secret holding the ring key under
+    /// `RING_KEY_SECRET_KEY`.
+    pub ring_secret: Option<String>,
+    /// Whether the image was exported with `--bare`, and so runs the
+    /// service without a Supervisor.
+    pub bare:        bool,
+    /// The contents of a user.toml for the service.
+    pub user_toml:   Option<String>,
+}
+
+impl DeploymentSpec {
+    /// The name of the headless Service through which Supervisors find
+    /// each other.
+    fn peers_service(&self) -> String { format!("{}-peers", self.name) }
+
+    fn config_map(&self) -> String { format!("{}-config", self.name) }
+
+    fn validate(&self, pkg: &PackageMetadata) -> Result<()> {
+        let bound: Vec<_> = self.binds
+                                .iter()
+                                .map(|bind| bind.split(':').next().unwrap_or_default())
+                                .collect();
+        if self.bare {
+            if !pkg.binds.is_empty() || !self.binds.is_empty() {
+                let mut binds = pkg.binds.clone();
+                binds.extend(bound.iter().map(ToString::to_string));
+                binds.sort();
+                binds.dedup();
+                return Err(Error::BareImageWithBinds(pkg.ident.clone(), binds.join(", ")).into());
+            }
+        } else {
+            let unbound: Vec<_> = pkg.binds
+                                     .iter()
+                                     .filter(|bind| !bound.contains(&bind.as_str()))
+                                     .cloned()
+                                     .collect();
+            if !unbound.is_empty() {
+                return Err(Error::UnsatisfiedBinds(pkg.ident.clone(), unbound.join(", ")).into());
+            }
+        }
+        if self.topology == Topology::Leader && self.replicas < 3 {
+            return Err(Error::TooFewForLeaderTopology(self.replicas).into());
+        }
+        Ok(())
+    }
+}
+
+/// The parts of the objects which a Helm chart takes from its values.
+pub(crate) struct Values {
+    pub image:    Value,
+    pub replicas: Value,
+    /// The service's user.toml, if it has one.
+    pub config:   Option<Value>,
+}
+
+impl Values {
+    fn literal(spec: &DeploymentSpec) -> Self {
+        Values { image:    json!(spec.image),
+                 replicas: json!(spec.replicas),
+                 config:   spec.user_toml.as_ref().map(|user_toml| json!(user_toml)), }
+    }
+}
+
+/// A Kubernetes object.
+#[derive(Clone, Debug)]
+pub struct Object {
+    /// The name of the file the object is written to in a Helm chart.
+    pub file_name: &'static str,
+    pub value:     Value,
+}
+
+/// The Kubernetes objects which run a service.
+#[derive(Debug)]
+pub struct Manifests {
+    objects: Vec<Object>,
+}
+
+impl Manifests {
+    /// # Errors
+    ///
+    /// * If the package's binds are not satisfied, or the image is bare and there are binds
+    /// * If the leader topology is asked for with too few replicas
+    pub fn new(pkg: &PackageMetadata, spec: &DeploymentSpec) -> Result<Self> {
+        Self::with_values(pkg, spec, &Values::literal(spec), true)
+    }
+
+    pub(crate) fn with_values(pkg: &PackageMetadata,
+                              spec: &DeploymentSpec,
+                              values: &Values,
+                              namespaced: bool)
+                              -> Result<Self> {
+        spec.validate(pkg)?;
+        let namespace = if namespaced {
+            spec.namespace.as_ref()
+        } else {
+            None
+        };
+        let metadata = |name: String| {
+            let mut metadata = json!({
+                "name": name,
+                "labels": labels(pkg, spec),
+            });
+            if let Some(namespace) = namespace {
+                metadata["namespace"] = json!(namespace);
+            }
+            metadata
+        };
+
+        let config = values.config.as_ref().filter(|_| !spec.bare);
+        let mut objects = Vec::new();
+        if let Some(config) = config {
+            objects.push(Object { file_name: "configmap.yaml",
+                                  value:     json!({
+                                      "apiVersion": "v1",
+                                      "kind": "ConfigMap",
+                                      "metadata": metadata(spec.config_map()),
+                                      "data": { "user.toml": config },
+                                  }), });
+        }
+        if !spec.bare {
+            // Not-ready Supervisors are published too, as they can't
+            // become ready without joining the ring first.
+            objects.push(Object { file_name: "peers-service.yaml",
+                                  value:     json!({
+                                      "apiVersion": "v1",
+                                      "kind": "Service",
+                                      "metadata": metadata(spec.peers_service()),
+                                      "spec": {
+                                          "clusterIP": "None",
+                                          "publishNotReadyAddresses": true,
+                                          "selector": selector(spec),
+                                          "ports": [
+                                              {
+                                                  "name": "gossip",
+                                                  "port": GOSSIP_PORT,
+                                                  "protocol": "TCP",
+                                              },
+                                              {
+                                                  "name": "gossip-udp",
+                                                  "port": GOSSIP_PORT,
+                                                  "protocol": "UDP",
+                                              },
+                                          ],
+                                      },
+                                  }), });
+        }
+        if !pkg.exposes.is_empty() {
+            let ports: Vec<_> = pkg.exposes
+                                   .iter()
+                                   .map(|port| {
+                                       json!({
+                                           "name": format!("port-{}", port),
+                                           "port": port,
+                                           "targetPort": port,
+                                       })
+                                   })
+                                   .collect();
+            objects.push(Object { file_name: "service.yaml",
+                                  value:     json!({
+                                      "apiVersion": "v1",
+                                      "kind": "Service",
+                                      "metadata": metadata(spec.name.clone()),
+                                      "spec": {
+                                          "selector": selector(spec),
+                                          "ports": ports,
+                                      },
+                                  }), });
+        }
+
+        let mut pod = json!({ "containers": [container(pkg, spec, values, config.is_some())] });
+        if config.is_some() {
+            pod["volumes"] = json!([{
+                "name": "config",
+                "configMap": { "name": spec.config_map() },
+            }]);
+        }
+        let mut workload = json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": metadata(spec.name.clone()),
+            "spec": {
+                "replicas": values.replicas,
+                "selector": { "matchLabels": selector(spec) },
+                "template": {
+                    "metadata": { "labels": labels(pkg, spec) },
+                    "spec": pod,
+                },
+            },
+        });
+        // A leader election needs a quorum of stable members, which
+        // must all be started at once, as none is ready before there
+        // is a leader.
+        let file_name = if spec.topology == Topology::Leader {
+            workload["kind"] = json!("StatefulSet");
+            workload["spec"]["serviceName"] = json!(spec.peers_service());
+            workload["spec"]["podManagementPolicy"] = json!("Parallel");
+            "statefulset.yaml"
+        } else {
+            "deployment.yaml"
+        };
+        objects.push(Object { file_name,
+                              value: workload });
+
+        Ok(Manifests { objects })
+    }
+
+    pub fn objects(&self) -> &[Object] { &self.objects }
+
+    /// All the objects, as a multi-document YAML stream.
+    pub fn to_yaml(&self) -> Result<String> {
+        let mut yaml = String::new();
+        for object in self.objects.iter() {
+            yaml.push_str(&to_yaml(&object.value)?);
+        }
+        Ok(yaml)
+    }
+}
+
+/// A single YAML document, starting with a document marker.
+pub(crate) fn to_yaml(value: &Value) -> Result<String> {
+    let yaml = serde_yaml::to_string(value)?;
+    let body = yaml.trim_start_matches("---\n").trim_end();
+    Ok(format!("---\n{}\n", body))
+}
+
+fn labels(pkg: &PackageMetadata, spec: &DeploymentSpec) -> Value {
+    json!({
+        "app.kubernetes.io/name": spec.name,
+        "app.kubernetes.io/version": pkg.version_release(),
+    })
+}
+
+fn selector(spec: &DeploymentSpec) -> Value { json!({ "app.kubernetes.io/name": spec.name }) }
+
+fn container(pkg: &PackageMetadata, spec: &DeploymentSpec, values: &Values, config: bool) -> Value {
+    let mut container = Map::new();
+    container.insert("name".to_string(), json!(pkg.ident.name));
+    container.insert("image".to_string(), values.image.clone());
+    let mut ports: Vec<_> = pkg.exposes
+                               .iter()
+                               .map(|port| {
+                                   json!({
+                                       "name": format!("port-{}", port),
+                                       "containerPort": port,
+                                   })
+                               })
+                               .collect();
+
+    if spec.bare {
+        if !ports.is_empty() {
+            container.insert("ports".to_string(), json!(ports));
+        }
+        if pkg.health_check {
+            let probe = json!({ "exec": { "command": ["/init.sh", "--health-check"] } });
+            container.insert("readinessProbe".to_string(), probe.clone());
+            container.insert("livenessProbe".to_string(), liveness(probe));
+        }
+        return Value::Object(container);
+    }
+
+    // The image's entrypoint passes arguments starting with a dash on
+    // to `bio sup run`.
+    let mut args = Vec::new();
+    if spec.peers.is_empty() {
+        args.push("--peer".to_string());
+        args.push(spec.peers_service());
+    }
+    for peer in spec.peers.iter() {
+        args.push("--peer".to_string());
+        args.push(peer.clone());
+    }
+    if spec.group != DEFAULT_GROUP {
+        args.push("--group".to_string());
+        args.push(spec.group.clone());
+    }
+    if spec.topology == Topology::Leader {
+        args.push("--topology".to_string());
+        args.push(spec.topology.to_string());
+    }
+    for bind in spec.binds.iter() {
+        args.push("--bind".to_string());
+        args.push(bind.clone());
+    }
+    container.insert("args".to_string(), json!(args));
+
+    if let Some(ref secret) = spec.ring_secret {
+        container.insert("env".to_string(),
+                         json!([{
+                             "name": "HAB_RING_KEY",
+                             "valueFrom": {
+                                 "secretKeyRef": {
+                                     "name": secret,
+                                     "key": RING_KEY_SECRET_KEY,
+                                 },
+                             },
+                         }]));
+    }
+
+    ports.push(json!({ "name": "http-gateway", "containerPort": HTTP_GATEWAY_PORT }));
+    ports.push(json!({ "name": "gossip", "containerPort": GOSSIP_PORT, "protocol": "TCP" }));
+    ports.push(json!({ "name": "gossip-udp", "containerPort": GOSSIP_PORT, "protocol": "UDP" }));
+    container.insert("ports".to_string(), json!(ports));
+
+    // The HTTP gateway reports the result of the last health check: OK
+    // for `ok` and `warning`, and an error otherwise.
+    if pkg.health_check {
+        let probe = json!({
+            "httpGet": {
+                "path": format!("/services/{}/{}/health", pkg.ident.name, spec.group),
+                "port": HTTP_GATEWAY_PORT,
+            },
+        });
+        container.insert("readinessProbe".to_string(), probe.clone());
+        container.insert("livenessProbe".to_string(), liveness(probe));
+    }
+
+    // Only the user.toml is mounted, so that the config directory is
+    // otherwise as the image has it.
+    if config {
+        container.insert("volumeMounts".to_string(),
+                         json!([{
+                             "name": "config",
+                             "mountPath": format!("/hab/user/{}/config/user.toml",
+                                                  pkg.ident.name),
+                             "subPath": "user.toml",
+                         }]));
+    }
+    Value::Object(container)
+}
+
+/// A failing health check makes a replica unready right away, but it
+/// is only restarted once the check has failed for a while, and not
+/// while the service is still starting.
+fn liveness(mut probe: Value) -> Value {
+    probe["initialDelaySeconds"] = json!(60);
+    probe["periodSeconds"] = json!(30);
+    probe["failureThreshold"] = json!(5);
+    probe
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn redis() -> PackageMetadata {
+        PackageMetadata { ident:        "core/redis/4.0.14/20190319155852".parse().unwrap(),
+                          exposes:      vec![6379],
+                          binds:        Vec::new(),
+                          default_toml: Some("port = 6379\n".to_string()),
+                          health_check: true, }
+    }
+
+    pub(crate) fn spec() -> DeploymentSpec {
+        DeploymentSpec { name:        "redis".to_string(),
+                         namespace:   None,
+                         image:       "core/redis:4.0.14-20190319155852".to_string(),
+                         replicas:    1,
+                         topology:    Topology::Standalone,
+                         group:       DEFAULT_GROUP.to_string(),
+                         binds:       Vec::new(),
+                         peers:       Vec::new(),
+                         ring_secret: None,
+                         bare:        false,
+                         user_toml:   None, }
+    }
+
+    fn object<'a>(manifests: &'a Manifests, file_name: &str) -> &'a Value {
+        &manifests.objects()
+                  .iter()
+                  .find(|object| object.file_name == file_name)
+                  .unwrap_or_else(|| panic!("no {}", file_name))
+                  .value
+    }
+
+    fn yaml(text: &str) -> Value { serde_yaml::from_str(text).unwrap() }
+
+    #[test]
+    fn a_standalone_service() {
+        let manifests = Manifests::new(&redis(), &spec()).unwrap();
+
+        let file_names: Vec<_> = manifests.objects().iter().map(|o| o.file_name).collect();
+        assert_eq!(file_names,
+                   vec!["peers-service.yaml", "service.yaml", "deployment.yaml"]);
+        assert_eq!(
+                   object(&manifests, "service.yaml"),
+                   &yaml(
+            r#"
+apiVersion: v1
+kind: Service
+metadata:
+  name: redis
+  labels:
+    app.kubernetes.io/name: redis
+    app.kubernetes.io/version: 4.0.14-20190319155852
+spec:
+  selector:
+    app.kubernetes.io/name: redis
+  ports:
+    - name: port-6379
+      port: 6379
+      targetPort: 6379
+"#
+        )
+        );
+        assert_eq!(
+                   object(&manifests, "deployment.yaml"),
+                   &yaml(
+            r#"
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: redis
+  labels:
+    app.kubernetes.io/name: redis
+    app.kubernetes.io/version: 4.0.14-20190319155852
+spec:
+  replicas: 1
+  selector:
+    matchLabels:
+      app.kubernetes.io/name: redis
+  template:
+    metadata:
+      labels:
+        app.kubernetes.io/name: redis
+        app.kubernetes.io/version: 4.0.14-20190319155852
+    spec:
+      containers:
+        - name: redis
+          image: core/redis:4.0.14-20190319155852
+          args: ["--peer", "redis-peers"]
+          readinessProbe:
+            httpGet:
+              path: /services/redis/default/health
+              port: 9631
+          livenessProbe:
+            httpGet:
+              path: /services/redis/default/health
+              port: 9631
+            initialDelaySeconds: 60
+            periodSeconds: 30
+            failureThreshold: 5
+          ports:
+            - name: port-6379
+              containerPort: 6379
+            - name: http-gateway
+              containerPort: 9631
+            - name: gossip
+              containerPort: 9638
+              protocol: TCP
+            - name: gossip-udp
+              containerPort: 9638
+              protocol: UDP
+"#
+        )
+        );
+    }
+
+    #[test]
+    fn a_user_toml_is_mounted_over_the_images() {
+        let mut spec = spec();
+        spec.user_toml = Some("port = 6380\n".to_string());
+        let manifests = Manifests::new(&redis(), &spec).unwrap();
+
+        assert_eq!(
+                   object(&manifests, "configmap.yaml"),
+                   &yaml(
+            r#"
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: redis-config
+  labels:
+    app.kubernetes.io/name: redis
+    app.kubernetes.io/version: 4.0.14-20190319155852
+data:
+  user.toml: "port = 6380\n"
+"#
+        )
+        );
+        let pod = &object(&manifests, "deployment.yaml")["spec"]["template"]["spec"];
+        assert_eq!(
+                   pod["containers"][0]["volumeMounts"],
+                   yaml(
+            r#"
+- name: config
+  mountPath: /hab/user/redis/config/user.toml
+  subPath: user.toml
+"#
+        )
+        );
+        assert_eq!(
+                   pod["volumes"],
+                   yaml(
+            r#"
+- name: config
+  configMap:
+    name: redis-config
+"#
+        )
+        );
+    }
+
+    #[test]
+    fn a_leader_topology_is_a_stateful_set() {
+        let mut spec = spec();
+        spec.namespace = Some("cache".to_string());
+        spec.replicas = 3;
+        spec.topology = Topology::Leader;
+        spec.group = "prod".to_string();
+        spec.ring_secret = Some("ring".to_string());
+        let manifests = Manifests::new(&redis(), &spec).unwrap();
+
+        let workload = object(&manifests, "statefulset.yaml");
+        assert_eq!(workload["kind"], "StatefulSet");
+        assert_eq!(workload["metadata"]["namespace"], "cache");
+        assert_eq!(workload["spec"]["replicas"], 3);
+        assert_eq!(workload["spec"]["serviceName"], "redis-peers");
+        assert_eq!(workload["spec"]["podManagementPolicy"], "Parallel");
+        let container = &workload["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(container["args"],
+                   yaml(r#"["--peer", "redis-peers", "--group", "prod", "--topology", "leader"]"#));
+        assert_eq!(
+                   container["env"],
+                   yaml(
+            r#"
+- name: HAB_RING_KEY
+  valueFrom:
+    secretKeyRef:
+      name: ring
+      key: ring-key
+"#
+        )
+        );
+        assert_eq!(container["readinessProbe"]["httpGet"]["path"],
+                   "/services/redis/prod/health");
+    }
+
+    #[test]
+    fn a_leader_topology_needs_a_quorum() {
+        let mut spec = spec();
+        spec.topology = Topology::Leader;
+        spec.replicas = 2;
+
+        assert!(Manifests::new(&redis(), &spec).is_err());
+    }
+
+    #[test]
+    fn required_binds_must_be_satisfied() {
+        let mut pkg = redis();
+        pkg.binds = vec!["backend".to_string()];
+        let mut spec = spec();
+
+        assert!(Manifests::new(&pkg, &spec).is_err());
+
+        spec.binds = vec!["backend:postgresql.default".to_string()];
+        spec.peers = vec!["postgresql-peers".to_string()];
+        let manifests = Manifests::new(&pkg, &spec).unwrap();
+        let container =
+            &object(&manifests, "deployment.yaml")["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(container["args"],
+                   yaml(r#"["--peer", "postgresql-peers", "--bind", "backend:postgresql.default"]"#));
+    }
+
+    #[test]
+    fn a_bare_image_runs_without_a_supervisor() {
+        let mut spec = spec();
+        spec.bare = true;
+        spec.user_toml = Some("port = 6380\n".to_string());
+        let manifests = Manifests::new(&redis(), &spec).unwrap();
+
+        let file_names: Vec<_> = manifests.objects().iter().map(|o| o.file_name).collect();
+        assert_eq!(file_names, vec!["service.yaml", "deployment.yaml"]);
+        let pod = &object(&manifests, "deployment.yaml")["spec"]["template"]["spec"];
+        assert_eq!(
+                   pod,
+                   &yaml(
+            r#"
+containers:
+  - name: redis
+    image: core/redis:4.0.14-20190319155852
+    readinessProbe:
+      exec:
+        command: ["/init.sh", "--health-check"]
+    livenessProbe:
+      exec:
+        command: ["/init.sh", "--health-check"]
+      initialDelaySeconds: 60
+      periodSeconds: 30
+      failureThreshold: 5
+    ports:
+      - name: port-6379
+        containerPort: 6379
+"#
+        )
+        );
+
+        spec.binds = vec!["backend:postgresql.default".to_string()];
+        assert!(Manifests::new(&redis(), &spec).is_err());
+    }
+
+    #[test]
+    fn the_yaml_stream_has_every_object() {
+        let yaml = Manifests::new(&redis(), &spec()).unwrap()
+                                                    .to_yaml()
+                                                    .unwrap();
+
+        assert_eq!(yaml.matches("---").count(), 3);
+        assert!(yaml.contains("kind: Deployment"));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::error::{Error,
+                   Result};
+use biome_core::{self,
+                 package::{PackageArchive,
+                           PackageIdent,
+                           PackageInstall}};
+use std::{fs,
+          path::Path,
+          str::FromStr};
+use tempfile::TempDir;
+
+/// What the manifests need to know about a service package, as read
+/// from its metadata.
+#[derive(Clone, Debug)]
+pub struct PackageMetadata {
+    /// The fully qualified identifier of the package.
+    pub ident:        PackageIdent,
+    pub exposes:      Vec<u16>,
+    /// The names of the binds the service cannot run without.
+    pub binds:        Vec<String>,
+    /// The contents of the package's `default.toml`, if it has one.
+    pub default_toml: Option<String>,
+    /// Whether the package has a `health-check` hook.
+    pub health_check: bool,
+}
+
+impl PackageMetadata {
+    /// Read the metadata of a package from a Biome artifact, or from
+    /// the locally installed package with the given identifier.
+    ///
+    /// # Errors
+    ///
+    /// * If the package is not installed or the artifact cannot be unpacked
+    /// * If the package is not a service
+    pub fn load(ident_or_archive: &str) -> Result<Self> {
+        if Path::new(ident_or_archive).is_file() {
+            let mut archive = PackageArchive::new(ident_or_archive)?;
+            let ident = archive.ident()?;
+            let fs_root = TempDir::new()?;
+            archive.unpack(Some(fs_root.path()))?;
+            Self::from_install(&PackageInstall::load(&ident, Some(fs_root.path()))?)
+        } else {
+            let ident = PackageIdent::from_str(ident_or_archive)?;
+            match PackageInstall::load(&ident, None) {
+                Ok(install) => Self::from_install(&install),
+                Err(biome_core::Error::PackageNotFound(_)) => {
+                    Err(Error::PackageNotInstalled(ident).into())
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    pub fn from_install(install: &PackageInstall) -> Result<Self> {
+        if !install.is_runnable() {
+            return Err(Error::NotAService(install.ident().clone()).into());
+        }
+        let mut exposes = Vec::new();
+        for port in install.exposes()? {
+            exposes.push(port.parse()?);
+        }
+        let default_toml = install.installed_path.join("default.toml");
+        let default_toml = if default_toml.is_file() {
+            Some(fs::read_to_string(default_toml)?)
+        } else {
+            None
+        };
+        Ok(PackageMetadata { ident: install.ident().clone(),
+                             exposes,
+                             binds: install.binds()?
+                                           .into_iter()
+                                           .map(|bind| bind.service)
+                                           .collect(),
+                             default_toml,
+                             health_check: install.installed_path
+                                                  .join("hooks/health-check")
+                                                  .is_file() })
+    }
+
+    /// The version and release of the package, as a single label
+    /// value.
+    pub fn version_release(&self) -> String {
+        format!("{}-{}",
+                self.ident.version.as_ref().expect("fully qualified"),
+                self.ident.release.as_ref().expect("fully qualified"))
+    }
+}
@@ -4,7 +4,11 @@ use crate::{common::{self,
                      command::package::install::{InstallHookMode,
                                                  InstallMode,
                                                  InstallSource,
-                                                 LocalPackageUsage},
+                                                 LocalPackageUsage,
+                                                 SignaturePolicy},
+                     preload::Preload,
+                     sbom::{Sbom,
+                            SbomFormat},
                      ui::{Status,
                           UIWriter,
                           UI},
@@ -14,9 +18,12 @@ use crate::{common::{self,
                          CACHE_ARTIFACT_PATH,
                          CACHE_KEY_PATH,
                          CACHE_KEY_PATH_POSTFIX},
-                    package::PackageIdent,
+                    package::{PackageIdent,
+                              PackageInstall},
                     ChannelIdent},
-            rootfs};
+            rootfs,
+            system_package::Kind,
+            systemd};
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 #[cfg(windows)]
@@ -59,6 +66,14 @@ pub struct BuildSpec<'a> {
     pub ident_or_archive:  &'a str,
     /// The Builder Auth Token to use in the request
     pub auth:              Option<&'a str>,
+    /// The format of the SBOM to embed in the tarball, if any.
+    pub sbom:              Option<SbomFormat>,
+    /// Whether to include a systemd unit for the Supervisor, with the package loaded into it.
+    pub systemd:           bool,
+    /// The kind of system package to create rather than a tarball, if any.
+    pub system_package:    Option<Kind>,
+    /// The service spec, configuration and keys to bake into the tarball.
+    pub preload:           Preload,
 }
 
 impl<'a> BuildSpec<'a> {
@@ -77,7 +92,12 @@ impl<'a> BuildSpec<'a> {
                                         .map(ChannelIdent::from)
                                         .unwrap_or_default(),
                     auth:              m.value_of("BLDR_AUTH_TOKEN"),
-                    ident_or_archive:  m.value_of("PKG_IDENT_OR_ARTIFACT").unwrap(), }
+                    ident_or_archive:  m.value_of("PKG_IDENT_OR_ARTIFACT").unwrap(),
+                    sbom:              m.value_of("SBOM")
+                                        .map(|format| format.parse().expect("valid SBOM format")),
+                    systemd:           m.is_present("SYSTEMD") || m.is_present("SYSTEM_PACKAGE"),
+                    system_package:    value_t!(m, "SYSTEM_PACKAGE", Kind).ok(),
+                    preload:           Preload::from_matches(m), }
     }
 
     /// Creates a `BuildRoot` for the given specification.
@@ -107,12 +127,40 @@ impl<'a> BuildSpec<'a> {
         self.install_base_pkgs(ui, &rootfs).await?;
         let ident = self.install_user_pkg(ui, self.ident_or_archive, rootfs)
                         .await?;
+        self.write_sbom(ui, &ident, rootfs)?;
         self.remove_symlink_to_key_cache(ui, &rootfs)?;
         self.remove_symlink_to_artifact_cache(ui, &rootfs)?;
+        if self.systemd {
+            systemd::write(ui, rootfs)?;
+            // The unit runs the Supervisor without naming a service, so it only runs the package
+            // if there is a spec for it, given or not.
+            if !self.preload.has_spec() {
+                self.preload
+                    .write_spec(ui, rootfs, &ident, self.url, &self.channel)?;
+            }
+        }
+        self.preload
+            .write(ui, rootfs, &ident, self.url, &self.channel)?;
 
         Ok(ident)
     }
 
+    fn write_sbom(&self, ui: &mut UI, ident: &PackageIdent, rootfs: &Path) -> Result<()> {
+        if let Some(format) = self.sbom {
+            let install = PackageInstall::load(ident, Some(rootfs))?;
+            let path = Sbom::from_install(&install, rootfs)?.write_to_root(format,
+                                                                           &*PROGRAM_NAME,
+                                                                           VERSION,
+                                                                           rootfs)?;
+            ui.status(Status::Generated,
+                      format!("{} SBOM for {} at /{}",
+                              format,
+                              ident,
+                              path.strip_prefix(rootfs)?.display()))?;
+        }
+        Ok(())
+    }
+
     fn create_symlink_to_artifact_cache<P: AsRef<Path>>(&self,
                                                         ui: &mut UI,
                                                         rootfs: P)
@@ -211,7 +259,8 @@ impl<'a> BuildSpec<'a> {
                                                      // TODO (CM): pass through and enable
                                                      // ignore-local mode
                                                      &LocalPackageUsage::default(),
-                                                     InstallHookMode::Ignore).await?;
+                                                     InstallHookMode::Ignore,
+                                                     &SignaturePolicy::from_env()?).await?;
         Ok(package_install.into())
     }
 
@@ -3,7 +3,10 @@ use clap::{App,
 use std::{result,
           str::FromStr};
 
-use crate::common::command::package::install::InstallSource;
+use crate::{common::{command::package::install::InstallSource,
+                     preload,
+                     sbom::SbomFormat},
+            system_package::Kind};
 use url::Url;
 
 /// The version of this library and program when built.
@@ -119,6 +122,43 @@ impl<'a, 'b> Cli<'a, 'b> {
         Cli { app }
     }
 
+    pub fn add_sbom_arg(self) -> Self {
+        let app = self.app.arg(Arg::with_name("SBOM").value_name("FORMAT")
+                                                     .long("sbom")
+                                                     .possible_values(SbomFormat::VARIANTS)
+                                                     .help("Embed a software bill of materials \
+                                                            in the given format for the package \
+                                                            in the tarball, under /hab/sbom"));
+
+        Cli { app }
+    }
+
+    pub fn add_systemd_args(self) -> Self {
+        let app = self.app
+                      .arg(Arg::with_name("SYSTEMD").long("systemd")
+                                                    .help("Include a systemd unit which runs the \
+                                                           Supervisor, and a service spec which \
+                                                           loads the package into it, so that the \
+                                                           service starts with the host"))
+                      .arg(Arg::with_name("SYSTEM_PACKAGE").long("system-package")
+                                                           .value_name("KIND")
+                                                           .possible_values(Kind::VARIANTS)
+                                                           .help("Create a system package of the \
+                                                                  given kind rather than a \
+                                                                  tarball, which includes the \
+                                                                  systemd unit, and creates the \
+                                                                  hab user and enables the unit \
+                                                                  when it is installed"));
+
+        Cli { app }
+    }
+
+    pub fn add_preload_args(self) -> Self {
+        let app = self.app.args(&preload::args());
+
+        Cli { app }
+    }
+
     pub fn add_pkg_ident_arg(self) -> Self {
         let help = "A Biome package identifier (ex: acme/redis) and/or filepath to a Biome \
                     Artifact (ex: /home/acme-redis-3.0.7-21120102031201-x86_64-linux.hart)";
@@ -11,4 +11,7 @@ pub enum Error {
                       one package with a run hook must be provided.",
            _0)]
     PrimaryServicePackageNotFound(Vec<String>),
+    #[fail(display = "Unknown kind of system package '{}', expected one of: deb, rpm",
+           _0)]
+    UnknownSystemPackageKind(String),
 }
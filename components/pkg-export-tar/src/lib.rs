@@ -13,17 +13,25 @@ mod build;
 pub mod cli;
 mod error;
 mod rootfs;
+mod system_package;
+mod systemd;
 
 pub use crate::{cli::Cli,
                 error::{Error,
                         Result}};
-use crate::{common::ui::UI,
+use crate::{common::ui::{Status,
+                         UIWriter,
+                         UI},
             hcore::{package::{PackageIdent,
                               PackageInstall},
-                    url as hurl}};
+                    url as hurl},
+            system_package::{Kind,
+                             Payload,
+                             SystemPackage}};
 use flate2::{write::GzEncoder,
              Compression};
-use std::{fs::File,
+use std::{env,
+          fs::File,
           path::{Path,
                  PathBuf},
           str::FromStr};
@@ -46,11 +54,15 @@ pub async fn export_for_cli_matches(ui: &mut UI, matches: &clap::ArgMatches<'_>)
 
 pub async fn export(ui: &mut UI, build_spec: BuildSpec<'_>) -> Result<()> {
     let bio_pkg = build_spec.bio;
+    let system_package = build_spec.system_package;
     let build_result = build_spec.create(ui).await.unwrap();
     let builder_dir_path = build_result.0.path();
     let pkg_ident = build_result.1;
 
-    tar_command(builder_dir_path, pkg_ident, bio_pkg);
+    match system_package {
+        Some(kind) => system_package_command(ui, builder_dir_path, &pkg_ident, bio_pkg, kind)?,
+        None => tar_command(builder_dir_path, pkg_ident, bio_pkg),
+    }
     Ok(())
 }
 
@@ -82,6 +94,34 @@ fn tar_command(temp_dir_path: &Path, pkg_ident: PackageIdent, bio_pkg: &str) {
 
     // Append the bio binary to the tar ball
     tar_builder.append_dir_all("hab/bin", bio_pkg_binary_path);
+
+    // Append the systemd unit, if there is one
+    let etc_path = root_fs.join("etc");
+    if etc_path.is_dir() {
+        tar_builder.append_dir_all("etc", etc_path);
+    }
+}
+
+/// Create a system package of the same files as the tarball, in the current directory.
+fn system_package_command(ui: &mut UI,
+                          temp_dir_path: &Path,
+                          pkg_ident: &PackageIdent,
+                          bio_pkg: &str,
+                          kind: Kind)
+                          -> Result<()> {
+    let root_fs = temp_dir_path.join("rootfs");
+    let mut payload = Payload::default();
+    payload.add("hab", &root_fs.join("hab"))?;
+    payload.add("hab/bin",
+                &bio_install_path(&bio_package_ident(bio_pkg), &root_fs).join("bin"))?;
+    let unit = format!("{}/{}", systemd::UNIT_DIR, systemd::UNIT_NAME);
+    payload.add(&unit, &root_fs.join(&unit))?;
+
+    let install = PackageInstall::load(pkg_ident, Some(&root_fs))?;
+    let path = SystemPackage::new(&install, &root_fs, payload)?.write(kind, &env::current_dir()?)?;
+    ui.status(Status::Created,
+              format!("{} package {}", kind, path.display()))?;
+    Ok(())
 }
 
 fn format_tar_name(ident: PackageIdent) -> String {
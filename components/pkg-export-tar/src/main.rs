@@ -31,8 +31,12 @@ async fn start(ui: &mut UI) -> Result<()> {
 fn cli<'a, 'b>() -> App<'a, 'b> {
     let name: &str = &*PROGRAM_NAME;
     let about = "Creates a tar package from a Biome package";
-    Cli::new(name, about).add_base_packages_args()
-                         .add_builder_args()
-                         .add_pkg_ident_arg()
-                         .app
+    let mut cli = Cli::new(name, about).add_base_packages_args()
+                                       .add_builder_args()
+                                       .add_sbom_arg()
+                                       .add_preload_args();
+    if cfg!(target_os = "linux") {
+        cli = cli.add_systemd_args();
+    }
+    cli.add_pkg_ident_arg().app
 }
@@ -0,0 +1,353 @@
+//! Wraps the exported files as a `.deb` or `.rpm` package, which installs the Supervisor's
+//! systemd unit along with them, creates the `hab` user for services to run as, and enables the
+//! unit. The packages are built here rather than by `dpkg-deb` or `rpmbuild`, so that exporting
+//! needs no packaging tools on the exporting host.
+
+mod deb;
+mod rpm;
+
+use crate::{common::{sbom::Sbom,
+                     templating::package::DEFAULT_USER},
+            error::{Error,
+                    Result},
+            hcore::package::{PackageIdent,
+                             PackageInstall},
+            systemd::UNIT_NAME};
+use std::{collections::BTreeMap,
+          env,
+          fmt,
+          fs::{self,
+               File,
+               Metadata},
+          io::{BufWriter,
+               Write},
+          path::{Path,
+                 PathBuf},
+          result,
+          str::FromStr,
+          time::{SystemTime,
+                 UNIX_EPOCH}};
+
+/// The kind of system package to build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Deb,
+    Rpm,
+}
+
+impl Kind {
+    /// The names accepted on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["deb", "rpm"];
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            Kind::Deb => "deb",
+            Kind::Rpm => "rpm",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        match value.to_lowercase().as_ref() {
+            "deb" => Ok(Kind::Deb),
+            "rpm" => Ok(Kind::Rpm),
+            _ => Err(Error::UnknownSystemPackageKind(value.to_string())),
+        }
+    }
+}
+
+/// What a file system entry in a package holds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Content {
+    Dir,
+    /// A regular file, read from the given path when the package is written.
+    File(PathBuf),
+    /// A symbolic link to the given target.
+    Symlink(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub content: Content,
+    /// The permission bits of the entry.
+    pub mode:    u32,
+    pub size:    u64,
+    pub mtime:   u64,
+}
+
+impl Entry {
+    fn from_metadata(src: &Path, metadata: &Metadata) -> Result<Option<Self>> {
+        let file_type = metadata.file_type();
+        let content = if file_type.is_dir() {
+            Content::Dir
+        } else if file_type.is_file() {
+            Content::File(src.to_path_buf())
+        } else if file_type.is_symlink() {
+            Content::Symlink(fs::read_link(src)?.to_string_lossy().into_owned())
+        } else {
+            debug!("Leaving {} out of the package, as it is not a regular file",
+                   src.display());
+            return Ok(None);
+        };
+        let size = match content {
+            Content::File(_) => metadata.len(),
+            Content::Symlink(ref target) => target.len() as u64,
+            Content::Dir => 0,
+        };
+        let mtime = metadata.modified()?
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or_default();
+        Ok(Some(Entry { mode: permissions(metadata, &content),
+                        content,
+                        size,
+                        mtime }))
+    }
+
+    /// The file type bits of the entry's mode, as in `st_mode`.
+    pub fn file_type_bits(&self) -> u32 {
+        match self.content {
+            Content::Dir => 0o040_000,
+            Content::File(_) => 0o100_000,
+            Content::Symlink(_) => 0o120_000,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &Metadata, _content: &Content) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(windows)]
+fn permissions(_metadata: &Metadata, content: &Content) -> u32 {
+    match content {
+        Content::File(_) => 0o644,
+        _ => 0o755,
+    }
+}
+
+/// The files a package installs, keyed by their paths relative to the root file system, in the
+/// sorted order both package formats expect.
+#[derive(Debug, Default)]
+pub struct Payload {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Payload {
+    /// Add `src`, and everything beneath it when it is a directory, to be installed at `path`.
+    ///
+    /// # Errors
+    ///
+    /// * If `src` or anything beneath it cannot be read
+    pub fn add(&mut self, path: &str, src: &Path) -> Result<()> {
+        let entry = match Entry::from_metadata(src, &fs::symlink_metadata(src)?)? {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        let is_dir = entry.content == Content::Dir;
+        self.entries.insert(path.to_string(), entry);
+        if is_dir {
+            for child in fs::read_dir(src)? {
+                let child = child?;
+                self.add(&format!("{}/{}", path, child.file_name().to_string_lossy()),
+                         &child.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Entry)> { self.entries.iter() }
+
+    pub fn get(&self, path: &str) -> Option<&Entry> { self.entries.get(path) }
+
+    /// The total size of the entries, in bytes.
+    pub fn size(&self) -> u64 { self.entries.values().map(|entry| entry.size).sum() }
+}
+
+/// A system package which installs a Biome package along with a Supervisor to run it.
+#[derive(Debug)]
+pub struct SystemPackage {
+    pub ident:      PackageIdent,
+    /// The name of the system package, which is the package's origin and name.
+    pub name:       String,
+    pub maintainer: Option<String>,
+    pub licenses:   Vec<String>,
+    pub payload:    Payload,
+    /// When the package is built, in seconds since the epoch.
+    pub build_time: u64,
+}
+
+impl SystemPackage {
+    /// Describe a system package for the installed package, installing the given files.
+    ///
+    /// # Errors
+    ///
+    /// * If the package's metadata cannot be read
+    pub fn new(install: &PackageInstall, rootfs: &Path, payload: Payload) -> Result<Self> {
+        let component = Sbom::from_install(install, rootfs)?.package;
+        Ok(SystemPackage { name: system_name(install.ident()),
+                           ident: install.ident().clone(),
+                           maintainer: component.maintainer,
+                           licenses: component.licenses,
+                           payload,
+                           build_time: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() })
+    }
+
+    /// Write the package into `dir`, returning the path of the written file.
+    ///
+    /// # Errors
+    ///
+    /// * If the package file cannot be written
+    /// * If any of the payload's files cannot be read
+    pub fn write(&self, kind: Kind, dir: &Path) -> Result<PathBuf> {
+        let path = dir.join(match kind {
+                                Kind::Deb => deb::file_name(self),
+                                Kind::Rpm => rpm::file_name(self),
+                            });
+        let mut file = BufWriter::new(File::create(&path)?);
+        match kind {
+            Kind::Deb => deb::write(self, &mut file)?,
+            Kind::Rpm => rpm::write(self, &mut file)?,
+        }
+        file.flush()?;
+        Ok(path)
+    }
+
+    fn summary(&self) -> String { format!("{} with a Biome Supervisor to run it", self.ident) }
+
+    fn description(&self) -> String {
+        format!("Runs {} under the Biome Supervisor, which is started by the systemd unit {}.",
+                self.ident, UNIT_NAME)
+    }
+}
+
+/// The name of the system package for a Biome package; both formats allow only a subset of the
+/// characters Biome does.
+fn system_name(ident: &PackageIdent) -> String {
+    format!("{}-{}", ident.origin, ident.name).to_lowercase()
+                                              .chars()
+                                              .map(|c| {
+                                                  if c.is_ascii_alphanumeric()
+                                                     || c == '.'
+                                                     || c == '+'
+                                                  {
+                                                      c
+                                                  } else {
+                                                      '-'
+                                                  }
+                                              })
+                                              .collect()
+}
+
+/// The architecture of the exported packages, by the name the given kind of package uses.
+fn architecture(kind: Kind) -> &'static str {
+    match (kind, env::consts::ARCH) {
+        (Kind::Deb, "x86_64") => "amd64",
+        (Kind::Deb, "aarch64") => "arm64",
+        (_, arch) => arch,
+    }
+}
+
+/// Creates the `hab` user and group, which services run as by default, unless they exist.
+fn pre_install_script() -> String {
+    format!(
+            "#!/bin/sh
+set -e
+if ! getent group {user} >/dev/null; then
+  groupadd --system {user}
+fi
+if ! getent passwd {user} >/dev/null; then
+  useradd --system --gid {user} --home-dir /hab --no-create-home --shell /bin/false {user}
+fi
+",
+            user = DEFAULT_USER
+    )
+}
+
+/// Enables and (re)starts the unit, when the host is running systemd and `condition` holds.
+fn post_install_script(condition: &str) -> String {
+    format!(
+            "#!/bin/sh
+set -e
+if {condition} && [ -d /run/systemd/system ]; then
+  systemctl daemon-reload
+  systemctl enable {unit}
+  systemctl restart {unit}
+fi
+",
+            condition = condition,
+            unit = UNIT_NAME
+    )
+}
+
+/// Stops and disables the unit, when the host is running systemd and `condition` holds, which is
+/// when the package is being removed rather than upgraded.
+fn pre_remove_script(condition: &str) -> String {
+    format!(
+            "#!/bin/sh
+if {condition} && [ -d /run/systemd/system ]; then
+  systemctl disable --now {unit} || true
+fi
+",
+            condition = condition,
+            unit = UNIT_NAME
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    pub(super) fn package(rootfs: &Path) -> SystemPackage {
+        fs::create_dir_all(rootfs.join("hab/bin")).unwrap();
+        fs::write(rootfs.join("hab/bin/bio"), "#!/bin/sh\n").unwrap();
+        fs::create_dir_all(rootfs.join("etc/systemd/system")).unwrap();
+        fs::write(rootfs.join("etc/systemd/system/bio-sup.service"),
+                  "[Unit]\n").unwrap();
+
+        let mut payload = Payload::default();
+        payload.add("hab", &rootfs.join("hab")).unwrap();
+        payload.add("etc/systemd/system/bio-sup.service",
+                    &rootfs.join("etc/systemd/system/bio-sup.service"))
+               .unwrap();
+        SystemPackage { ident: PackageIdent::from_str("core/redis_server/4.0.14/20190319155852")
+                                                   .unwrap(),
+                        name: "core-redis-server".to_string(),
+                        maintainer: Some("The Biome Maintainers <humans@biome.sh>".to_string()),
+                        licenses: vec!["BSD-3-Clause".to_string()],
+                        payload,
+                        build_time: 1_553_011_132 }
+    }
+
+    #[test]
+    fn the_payload_is_sorted_by_path_and_sized_by_its_files() {
+        let tmp = TempDir::new().unwrap();
+        let payload = package(tmp.path()).payload;
+
+        assert_eq!(payload.entries()
+                          .map(|(path, _)| path.as_str())
+                          .collect::<Vec<_>>(),
+                   vec!["etc/systemd/system/bio-sup.service",
+                        "hab",
+                        "hab/bin",
+                        "hab/bin/bio"]);
+        assert_eq!(payload.size(), 17);
+    }
+
+    #[test]
+    fn system_names_are_lowercase_with_dashes() {
+        let ident = PackageIdent::from_str("Acme/redis_server").unwrap();
+
+        assert_eq!(system_name(&ident), "acme-redis-server");
+    }
+}
@@ -0,0 +1,270 @@
+//! Debian packages, which are `ar` archives of the format version, a tarball of the control
+//! files, and a tarball of the files to install.
+
+use super::{architecture,
+            post_install_script,
+            pre_install_script,
+            pre_remove_script,
+            Content,
+            Kind,
+            Payload,
+            SystemPackage};
+use crate::error::Result;
+use flate2::{write::GzEncoder,
+             Compression};
+use std::{collections::BTreeSet,
+          fs::File,
+          io::{self,
+               Write}};
+use tar::{Builder,
+          EntryType,
+          Header};
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const FORMAT_VERSION: &[u8] = b"2.0\n";
+
+/// The file name `dpkg-deb` would give the package.
+pub fn file_name(pkg: &SystemPackage) -> String {
+    format!("{}_{}_{}.deb",
+            pkg.name,
+            version(pkg),
+            architecture(Kind::Deb))
+}
+
+/// Write the package to `out`.
+///
+/// # Errors
+///
+/// * If the package cannot be written
+/// * If any of the payload's files cannot be read
+pub fn write<W: Write>(pkg: &SystemPackage, out: &mut W) -> Result<()> {
+    let control = control_tarball(pkg)?;
+    let data = data_tarball(&pkg.payload, pkg.build_time)?;
+
+    out.write_all(AR_MAGIC)?;
+    write_member(out, "debian-binary", pkg.build_time, FORMAT_VERSION)?;
+    write_member(out, "control.tar.gz", pkg.build_time, &control)?;
+    write_member(out, "data.tar.gz", pkg.build_time, &data)?;
+    Ok(())
+}
+
+/// The Biome version and release as a Debian upstream version and revision. Upstream versions
+/// must start with a digit, and only some punctuation is allowed.
+fn version(pkg: &SystemPackage) -> String {
+    let version = pkg.ident.version.as_ref().expect("fully qualified");
+    let mut upstream: String = version.chars()
+                                      .map(|c| {
+                                          if c.is_ascii_alphanumeric() || ".+~".contains(c) {
+                                              c
+                                          } else {
+                                              '~'
+                                          }
+                                      })
+                                      .collect();
+    if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+        upstream.insert_str(0, "0~");
+    }
+    format!("{}-{}",
+            upstream,
+            pkg.ident.release.as_ref().expect("fully qualified"))
+}
+
+fn control(pkg: &SystemPackage) -> String {
+    let mut control = format!("Package: {}\nVersion: {}\nArchitecture: {}\n",
+                              pkg.name,
+                              version(pkg),
+                              architecture(Kind::Deb));
+    control.push_str(&format!("Maintainer: {}\n",
+                              pkg.maintainer
+                                 .as_ref()
+                                 .map(String::as_str)
+                                 .unwrap_or("unknown")));
+    // Installed-Size is in KiB, rounded up
+    control.push_str(&format!("Installed-Size: {}\n", (pkg.payload.size() + 1023) / 1024));
+    control.push_str("Section: misc\nPriority: optional\n");
+    control.push_str(&format!("Description: {}\n {}\n", pkg.summary(), pkg.description()));
+    control
+}
+
+fn control_tarball(pkg: &SystemPackage) -> Result<Vec<u8>> {
+    let mut tarball = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, mode, contents) in
+        &[("control", 0o644, control(pkg)),
+          ("preinst", 0o755, pre_install_script()),
+          ("postinst", 0o755, post_install_script("[ \"$1\" = configure ]")),
+          ("prerm", 0o755, pre_remove_script("[ \"$1\" = remove ]"))]
+    {
+        let mut header = header(EntryType::Regular, *mode, pkg.build_time);
+        header.set_size(contents.len() as u64);
+        tarball.append_data(&mut header, format!("./{}", name), contents.as_bytes())?;
+    }
+    Ok(tarball.into_inner()?.finish()?)
+}
+
+/// A tarball of the payload. Any directories above the payload's entries which are not in the
+/// payload, such as `/etc/systemd`, are added too, as `dpkg` does not create them.
+fn data_tarball(payload: &Payload, build_time: u64) -> Result<Vec<u8>> {
+    let mut tarball = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut dirs = BTreeSet::new();
+    for (path, entry) in payload.entries() {
+        for (i, _) in path.match_indices('/') {
+            let parent = &path[..i];
+            if dirs.insert(parent.to_string()) && payload.get(parent).is_none() {
+                append_dir(&mut tarball, &format!("./{}", parent), 0o755, build_time)?;
+            }
+        }
+        let name = format!("./{}", path);
+        match entry.content {
+            Content::Dir => {
+                dirs.insert(path.to_string());
+                append_dir(&mut tarball, &name, entry.mode, entry.mtime)?;
+            }
+            Content::File(ref src) => {
+                let mut header = header(EntryType::Regular, entry.mode, entry.mtime);
+                header.set_size(entry.size);
+                tarball.append_data(&mut header, name, File::open(src)?)?;
+            }
+            Content::Symlink(ref target) => {
+                let mut header = header(EntryType::Symlink, entry.mode, entry.mtime);
+                header.set_size(0);
+                // Link names over 100 bytes need a GNU long link entry, which `append_link`
+                // writes and `set_link_name` cannot
+                tarball.append_link(&mut header, name, target)?;
+            }
+        }
+    }
+    Ok(tarball.into_inner()?.finish()?)
+}
+
+fn header(entry_type: EntryType, mode: u32, mtime: u64) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_mtime(mtime);
+    header.set_uid(0);
+    header.set_gid(0);
+    header
+}
+
+fn append_dir<W: Write>(tarball: &mut Builder<W>,
+                        name: &str,
+                        mode: u32,
+                        mtime: u64)
+                        -> io::Result<()> {
+    let mut header = header(EntryType::Directory, mode, mtime);
+    header.set_size(0);
+    tarball.append_data(&mut header, name, io::empty())
+}
+
+/// Write a member of an `ar` archive, which has a fixed-width, space-padded header, and is padded
+/// to an even length.
+fn write_member<W: Write>(out: &mut W, name: &str, mtime: u64, data: &[u8]) -> io::Result<()> {
+    writeln!(out,
+             "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}`",
+             name,
+             mtime,
+             0,
+             0,
+             0o100_644,
+             data.len())?;
+    out.write_all(data)?;
+    if data.len() % 2 == 1 {
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::package,
+                *};
+    use flate2::read::GzDecoder;
+    use std::{fs,
+              io::Read,
+              path::Path};
+    use tar::Archive;
+    use tempfile::TempDir;
+
+    /// The members of an `ar` archive, by name.
+    fn members(mut ar: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert!(ar.starts_with(AR_MAGIC));
+        ar = &ar[AR_MAGIC.len()..];
+        let mut members = Vec::new();
+        while !ar.is_empty() {
+            let header = std::str::from_utf8(&ar[..60]).unwrap();
+            assert!(header.ends_with("`\n"));
+            let size: usize = header[48..58].trim().parse().unwrap();
+            members.push((header[..16].trim().to_string(), ar[60..60 + size].to_vec()));
+            ar = &ar[60 + size + size % 2..];
+        }
+        members
+    }
+
+    fn tarball_files(tarball: &[u8]) -> Vec<(String, String)> {
+        let mut archive = Archive::new(GzDecoder::new(tarball));
+        let mut files = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            files.push((path, contents));
+        }
+        files
+    }
+
+    #[test]
+    fn a_deb_has_the_format_version_control_files_and_data() {
+        let tmp = TempDir::new().unwrap();
+        let pkg = package(tmp.path());
+        let mut deb = Vec::new();
+        write(&pkg, &mut deb).unwrap();
+
+        let members = members(&deb);
+        assert_eq!(members.iter()
+                          .map(|(name, _)| name.as_str())
+                          .collect::<Vec<_>>(),
+                   vec!["debian-binary", "control.tar.gz", "data.tar.gz"]);
+        assert_eq!(members[0].1, b"2.0\n");
+
+        let control = tarball_files(&members[1].1);
+        let (_, control_file) = control.iter().find(|(path, _)| path == "control").unwrap();
+        assert!(control_file.starts_with("Package: core-redis-server\nVersion: \
+                                          4.0.14-20190319155852\n"));
+        assert!(control.iter().any(|(path, _)| path == "preinst"));
+
+        let data = tarball_files(&members[2].1);
+        assert_eq!(data.iter()
+                       .map(|(path, _)| path.as_str())
+                       .collect::<Vec<_>>(),
+                   vec!["etc",
+                        "etc/systemd",
+                        "etc/systemd/system",
+                        "etc/systemd/system/bio-sup.service",
+                        "hab",
+                        "hab/bin",
+                        "hab/bin/bio"]);
+        assert!(data.contains(&("hab/bin/bio".to_string(), "#!/bin/sh\n".to_string())));
+    }
+
+    #[test]
+    fn long_symlink_targets_are_kept_whole() {
+        let tmp = TempDir::new().unwrap();
+        let target = format!("/hab/pkgs/core/{}/1.0.0/20200101000000/bin/bio",
+                             "x".repeat(100));
+        fs::create_dir_all(tmp.path().join("hab/bin")).unwrap();
+        std::os::unix::fs::symlink(&target, tmp.path().join("hab/bin/bio")).unwrap();
+        let mut payload = Payload::default();
+        payload.add("hab/bin/bio", &tmp.path().join("hab/bin/bio"))
+               .unwrap();
+
+        let tarball = data_tarball(&payload, 0).unwrap();
+        let mut archive = Archive::new(GzDecoder::new(tarball.as_slice()));
+        let link = archive.entries()
+                          .unwrap()
+                          .map(Result::unwrap)
+                          .find(|entry| entry.path().unwrap() == Path::new("hab/bin/bio"))
+                          .unwrap();
+        assert_eq!(link.link_name().unwrap().unwrap(), Path::new(&target));
+    }
+}
@@ -0,0 +1,613 @@
+//! RPM packages, which are a lead, a signature header holding digests of what follows, a header
+//! describing the package and its files, and a gzipped `cpio` archive of the files.
+//!
+//! Headers are an index of tagged, typed entries followed by the entries' data, in big-endian
+//! byte order. Both headers are "immutable regions": their first entry points at a trailing copy
+//! of itself, which covers every other entry.
+
+use super::{architecture,
+            post_install_script,
+            pre_install_script,
+            pre_remove_script,
+            Content,
+            Entry,
+            Kind,
+            SystemPackage};
+use crate::error::Result;
+use flate2::{write::GzEncoder,
+             Compression};
+use sha2::{Digest,
+           Sha256};
+use std::{collections::BTreeMap,
+          fs::File,
+          io::{self,
+               Seek,
+               SeekFrom,
+               Write}};
+use tempfile::tempfile;
+
+const LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+const CPIO_MAGIC: &str = "070701";
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+/// The type of binary data in a header, which regions are.
+const BIN_TYPE: u32 = 7;
+/// The size of an index entry, which is also the size of a region's trailer.
+const INDEX_ENTRY_SIZE: usize = 16;
+
+// Header tags, from rpmtag.h.
+const TAG_HEADERSIGNATURES: u32 = 62;
+const TAG_HEADERIMMUTABLE: u32 = 63;
+const TAG_HEADERI18NTABLE: u32 = 100;
+const SIGTAG_SHA256: u32 = 273;
+const SIGTAG_SIZE: u32 = 1000;
+const SIGTAG_PAYLOADSIZE: u32 = 1007;
+const TAG_NAME: u32 = 1000;
+const TAG_VERSION: u32 = 1001;
+const TAG_RELEASE: u32 = 1002;
+const TAG_SUMMARY: u32 = 1004;
+const TAG_DESCRIPTION: u32 = 1005;
+const TAG_BUILDTIME: u32 = 1006;
+const TAG_SIZE: u32 = 1009;
+const TAG_LICENSE: u32 = 1014;
+const TAG_PACKAGER: u32 = 1015;
+const TAG_GROUP: u32 = 1016;
+const TAG_OS: u32 = 1021;
+const TAG_ARCH: u32 = 1022;
+const TAG_PREIN: u32 = 1023;
+const TAG_POSTIN: u32 = 1024;
+const TAG_PREUN: u32 = 1025;
+const TAG_FILESIZES: u32 = 1028;
+const TAG_FILEMODES: u32 = 1030;
+const TAG_FILERDEVS: u32 = 1033;
+const TAG_FILEMTIMES: u32 = 1034;
+const TAG_FILEDIGESTS: u32 = 1035;
+const TAG_FILELINKTOS: u32 = 1036;
+const TAG_FILEFLAGS: u32 = 1037;
+const TAG_FILEUSERNAME: u32 = 1039;
+const TAG_FILEGROUPNAME: u32 = 1040;
+const TAG_SOURCERPM: u32 = 1044;
+const TAG_REQUIREFLAGS: u32 = 1048;
+const TAG_REQUIRENAME: u32 = 1049;
+const TAG_REQUIREVERSION: u32 = 1050;
+const TAG_PREINPROG: u32 = 1085;
+const TAG_POSTINPROG: u32 = 1086;
+const TAG_PREUNPROG: u32 = 1087;
+const TAG_FILEDEVICES: u32 = 1095;
+const TAG_FILEINODES: u32 = 1096;
+const TAG_FILELANGS: u32 = 1097;
+const TAG_DIRINDEXES: u32 = 1116;
+const TAG_BASENAMES: u32 = 1117;
+const TAG_DIRNAMES: u32 = 1118;
+const TAG_PAYLOADFORMAT: u32 = 1124;
+const TAG_PAYLOADCOMPRESSOR: u32 = 1125;
+const TAG_PAYLOADFLAGS: u32 = 1126;
+const TAG_FILEDIGESTALGO: u32 = 5011;
+const TAG_PAYLOADDIGEST: u32 = 5092;
+const TAG_PAYLOADDIGESTALGO: u32 = 5093;
+
+/// The PGP hash algorithm number of SHA-256, which rpm uses to name digest algorithms.
+const DIGEST_ALGO_SHA256: u32 = 8;
+/// The `RPMSENSE_LESS | RPMSENSE_EQUAL | RPMSENSE_RPMLIB` flags of the features the package
+/// needs rpm to have.
+const RPMLIB_REQUIRE_FLAGS: u32 = 0x0100_000a;
+/// The features of rpm the package's format relies on.
+const RPMLIB_REQUIRES: &[(&str, &str)] = &[("rpmlib(CompressedFileNames)", "3.0.4-1"),
+                                           ("rpmlib(FileDigests)", "4.6.0-1"),
+                                           ("rpmlib(PayloadFilesHavePrefix)", "4.0-1")];
+
+/// The file name `rpmbuild` would give the package.
+pub fn file_name(pkg: &SystemPackage) -> String {
+    format!("{}-{}-{}.{}.rpm",
+            pkg.name,
+            version(pkg),
+            release(pkg),
+            architecture(Kind::Rpm))
+}
+
+/// Write the package to `out`.
+///
+/// The payload is compressed into a temporary file first, as the headers hold its digest and
+/// the digests of the files in it.
+///
+/// # Errors
+///
+/// * If the package cannot be written
+/// * If any of the payload's files cannot be read
+pub fn write<W: Write>(pkg: &SystemPackage, out: &mut W) -> Result<()> {
+    let mut compressed = DigestWriter::new(tempfile()?);
+    let mut cpio = Cpio::new(GzEncoder::new(&mut compressed, Compression::default()));
+    let mut files = Vec::new();
+    for (ino, (path, entry)) in pkg.payload.entries().enumerate() {
+        let digest = cpio.append(path, entry, ino as u32 + 1)?;
+        files.push(FileInfo { path,
+                              entry,
+                              digest });
+    }
+    let (gz, payload_size) = cpio.finish()?;
+    gz.finish()?;
+    let (mut payload, payload_digest, payload_len) = compressed.finish();
+
+    let header = header(pkg, &files, &payload_digest);
+    let mut signature =
+        index(TAG_HEADERSIGNATURES,
+              vec![(SIGTAG_SHA256, Value::String(format!("{:x}", Sha256::digest(&header)))),
+                   (SIGTAG_SIZE, Value::Int32(vec![(header.len() as u64 + payload_len) as u32])),
+                   (SIGTAG_PAYLOADSIZE, Value::Int32(vec![payload_size as u32]))]);
+    // The signature is padded so that the header starts on an 8 byte boundary
+    signature.resize((signature.len() + 7) / 8 * 8, 0);
+
+    out.write_all(&lead(pkg))?;
+    out.write_all(&signature)?;
+    out.write_all(&header)?;
+    payload.seek(SeekFrom::Start(0))?;
+    io::copy(&mut payload, out)?;
+    Ok(())
+}
+
+/// RPM versions may not contain dashes, and only some punctuation is allowed.
+fn version(pkg: &SystemPackage) -> String {
+    pkg.ident
+       .version
+       .as_ref()
+       .expect("fully qualified")
+       .chars()
+       .map(|c| {
+           if c.is_ascii_alphanumeric() || "._+~".contains(c) {
+               c
+           } else {
+               '_'
+           }
+       })
+       .collect()
+}
+
+fn release(pkg: &SystemPackage) -> String { pkg.ident.release.clone().expect("fully qualified") }
+
+/// The lead, which only older tools read: a fixed-size block naming the package.
+fn lead(pkg: &SystemPackage) -> Vec<u8> {
+    let mut lead = LEAD_MAGIC.to_vec();
+    // Format version 3.0 of a binary package
+    lead.extend_from_slice(&[3, 0, 0, 0]);
+    let arch_num: u16 = if architecture(Kind::Rpm) == "aarch64" {
+        19
+    } else {
+        1
+    };
+    lead.extend_from_slice(&arch_num.to_be_bytes());
+    let mut name = [0; 66];
+    let nvr = format!("{}-{}-{}", pkg.name, version(pkg), release(pkg));
+    let len = nvr.len().min(name.len() - 1);
+    name[..len].copy_from_slice(&nvr.as_bytes()[..len]);
+    lead.extend_from_slice(&name);
+    // Linux, and a signature in a header
+    lead.extend_from_slice(&1u16.to_be_bytes());
+    lead.extend_from_slice(&5u16.to_be_bytes());
+    lead.extend_from_slice(&[0; 16]);
+    lead
+}
+
+/// What the header records about each file in the payload.
+struct FileInfo<'a> {
+    path:   &'a str,
+    entry:  &'a Entry,
+    /// The SHA-256 digest of a regular file's contents.
+    digest: Option<String>,
+}
+
+fn header(pkg: &SystemPackage, files: &[FileInfo<'_>], payload_digest: &str) -> Vec<u8> {
+    // Files are recorded as indexes into a list of their parent directories, and base names
+    let mut dir_names = BTreeMap::new();
+    let mut dir_indexes = Vec::new();
+    let mut base_names = Vec::new();
+    for file in files {
+        let (dir, base) = match file.path.rfind('/') {
+            Some(i) => (format!("/{}/", &file.path[..i]), &file.path[i + 1..]),
+            None => ("/".to_string(), file.path),
+        };
+        let next = dir_names.len() as u32;
+        dir_indexes.push(*dir_names.entry(dir).or_insert(next));
+        base_names.push(base.to_string());
+    }
+    let mut dir_names: Vec<_> = dir_names.into_iter().collect();
+    dir_names.sort_by_key(|(_, index)| *index);
+
+    let strings = |f: &dyn Fn(&FileInfo<'_>) -> String| files.iter().map(f).collect::<Vec<_>>();
+    let mut entries =
+        vec![(TAG_HEADERI18NTABLE, Value::StringArray(vec!["C".to_string()])),
+             (TAG_NAME, Value::String(pkg.name.clone())),
+             (TAG_VERSION, Value::String(version(pkg))),
+             (TAG_RELEASE, Value::String(release(pkg))),
+             (TAG_SUMMARY, Value::I18nString(pkg.summary())),
+             (TAG_DESCRIPTION, Value::I18nString(pkg.description())),
+             (TAG_BUILDTIME, Value::Int32(vec![pkg.build_time as u32])),
+             (TAG_SIZE, Value::Int32(vec![pkg.payload.size() as u32])),
+             (TAG_LICENSE,
+              Value::String(if pkg.licenses.is_empty() {
+                                "unknown".to_string()
+                            } else {
+                                pkg.licenses.join(" and ")
+                            })),
+             (TAG_GROUP, Value::I18nString("Unspecified".to_string())),
+             (TAG_OS, Value::String("linux".to_string())),
+             (TAG_ARCH, Value::String(architecture(Kind::Rpm).to_string())),
+             (TAG_PREIN, Value::String(pre_install_script())),
+             (TAG_POSTIN, Value::String(post_install_script("true"))),
+             (TAG_PREUN, Value::String(pre_remove_script("[ \"$1\" -eq 0 ]"))),
+             (TAG_FILESIZES, Value::Int32(files.iter().map(|f| f.entry.size as u32).collect())),
+             (TAG_FILEMODES,
+              Value::Int16(files.iter()
+                                .map(|f| (f.entry.file_type_bits() | f.entry.mode) as u16)
+                                .collect())),
+             (TAG_FILERDEVS, Value::Int16(vec![0; files.len()])),
+             (TAG_FILEMTIMES, Value::Int32(files.iter().map(|f| f.entry.mtime as u32).collect())),
+             (TAG_FILEDIGESTS,
+              Value::StringArray(strings(&|f| f.digest.clone().unwrap_or_default()))),
+             (TAG_FILELINKTOS,
+              Value::StringArray(strings(&|f| {
+                                     match f.entry.content {
+                                         Content::Symlink(ref target) => target.clone(),
+                                         _ => String::new(),
+                                     }
+                                 }))),
+             (TAG_FILEFLAGS, Value::Int32(vec![0; files.len()])),
+             (TAG_FILEUSERNAME, Value::StringArray(strings(&|_| "root".to_string()))),
+             (TAG_FILEGROUPNAME, Value::StringArray(strings(&|_| "root".to_string()))),
+             (TAG_SOURCERPM,
+              Value::String(format!("{}-{}-{}.src.rpm", pkg.name, version(pkg), release(pkg)))),
+             (TAG_REQUIREFLAGS, Value::Int32(vec![RPMLIB_REQUIRE_FLAGS; RPMLIB_REQUIRES.len()])),
+             (TAG_REQUIRENAME,
+              Value::StringArray(RPMLIB_REQUIRES.iter()
+                                                .map(|(name, _)| name.to_string())
+                                                .collect())),
+             (TAG_REQUIREVERSION,
+              Value::StringArray(RPMLIB_REQUIRES.iter()
+                                                .map(|(_, version)| version.to_string())
+                                                .collect())),
+             (TAG_PREINPROG, Value::StringArray(vec!["/bin/sh".to_string()])),
+             (TAG_POSTINPROG, Value::StringArray(vec!["/bin/sh".to_string()])),
+             (TAG_PREUNPROG, Value::StringArray(vec!["/bin/sh".to_string()])),
+             (TAG_FILEDEVICES, Value::Int32(vec![1; files.len()])),
+             (TAG_FILEINODES, Value::Int32((1..=files.len() as u32).collect())),
+             (TAG_FILELANGS, Value::StringArray(strings(&|_| String::new()))),
+             (TAG_DIRINDEXES, Value::Int32(dir_indexes)),
+             (TAG_BASENAMES, Value::StringArray(base_names)),
+             (TAG_DIRNAMES,
+              Value::StringArray(dir_names.into_iter().map(|(dir, _)| dir).collect())),
+             (TAG_PAYLOADFORMAT, Value::String("cpio".to_string())),
+             (TAG_PAYLOADCOMPRESSOR, Value::String("gzip".to_string())),
+             (TAG_PAYLOADFLAGS, Value::String("6".to_string())),
+             (TAG_FILEDIGESTALGO, Value::Int32(vec![DIGEST_ALGO_SHA256])),
+             (TAG_PAYLOADDIGEST, Value::StringArray(vec![payload_digest.to_string()])),
+             (TAG_PAYLOADDIGESTALGO, Value::Int32(vec![DIGEST_ALGO_SHA256]))];
+    if let Some(ref maintainer) = pkg.maintainer {
+        entries.push((TAG_PACKAGER, Value::String(maintainer.clone())));
+    }
+    index(TAG_HEADERIMMUTABLE, entries)
+}
+
+/// The data of a header entry.
+#[derive(Debug)]
+enum Value {
+    Int16(Vec<u16>),
+    Int32(Vec<u32>),
+    String(String),
+    StringArray(Vec<String>),
+    I18nString(String),
+}
+
+impl Value {
+    fn type_id(&self) -> u32 {
+        match self {
+            Value::Int16(_) => 3,
+            Value::Int32(_) => 4,
+            Value::String(_) => 6,
+            Value::StringArray(_) => 8,
+            Value::I18nString(_) => 9,
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            Value::Int16(values) => values.len(),
+            Value::Int32(values) => values.len(),
+            Value::StringArray(values) => values.len(),
+            Value::String(_) | Value::I18nString(_) => 1,
+        }
+    }
+
+    fn alignment(&self) -> usize {
+        match self {
+            Value::Int16(_) => 2,
+            Value::Int32(_) => 4,
+            _ => 1,
+        }
+    }
+
+    fn write_to(&self, data: &mut Vec<u8>) {
+        match self {
+            Value::Int16(values) => {
+                for value in values {
+                    data.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            Value::Int32(values) => {
+                for value in values {
+                    data.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            Value::String(value) | Value::I18nString(value) => {
+                data.extend_from_slice(value.as_bytes());
+                data.push(0);
+            }
+            Value::StringArray(values) => {
+                for value in values {
+                    data.extend_from_slice(value.as_bytes());
+                    data.push(0);
+                }
+            }
+        }
+    }
+}
+
+/// A header of the given entries, as an immutable region with the given tag. Entries are sorted
+/// by tag, with their data in the same order, as rpm checks both.
+fn index(region_tag: u32, mut entries: Vec<(u32, Value)>) -> Vec<u8> {
+    entries.sort_by_key(|(tag, _)| *tag);
+    let entry_count = entries.len() + 1;
+
+    let mut index = Vec::with_capacity(entry_count * INDEX_ENTRY_SIZE);
+    let mut data = Vec::new();
+    for (tag, value) in &entries {
+        let alignment = value.alignment();
+        data.resize((data.len() + alignment - 1) / alignment * alignment, 0);
+        index_entry(&mut index,
+                    *tag,
+                    value.type_id(),
+                    data.len() as u32,
+                    value.count());
+        value.write_to(&mut data);
+    }
+    // The region's trailer comes after all other data, with an offset back to the start of the
+    // index, which it covers entirely
+    let trailer_offset = data.len() as u32;
+    index_entry(&mut data,
+                region_tag,
+                BIN_TYPE,
+                (-((entry_count * INDEX_ENTRY_SIZE) as i32)) as u32,
+                INDEX_ENTRY_SIZE);
+
+    let mut header = HEADER_MAGIC.to_vec();
+    header.extend_from_slice(&(entry_count as u32).to_be_bytes());
+    header.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    index_entry(&mut header,
+                region_tag,
+                BIN_TYPE,
+                trailer_offset,
+                INDEX_ENTRY_SIZE);
+    header.extend_from_slice(&index);
+    header.extend_from_slice(&data);
+    header
+}
+
+fn index_entry(out: &mut Vec<u8>, tag: u32, type_id: u32, offset: u32, count: usize) {
+    out.extend_from_slice(&tag.to_be_bytes());
+    out.extend_from_slice(&type_id.to_be_bytes());
+    out.extend_from_slice(&offset.to_be_bytes());
+    out.extend_from_slice(&(count as u32).to_be_bytes());
+}
+
+/// Writes a `cpio` archive in the "new ASCII" format rpm uses, with names prefixed by `./`.
+struct Cpio<W: Write> {
+    out:     W,
+    written: u64,
+}
+
+impl<W: Write> Cpio<W> {
+    fn new(out: W) -> Self { Cpio { out, written: 0 } }
+
+    /// Append an entry, returning the digest of its contents when it is a regular file.
+    fn append(&mut self, path: &str, entry: &Entry, ino: u32) -> io::Result<Option<String>> {
+        self.write_header(&format!("./{}", path),
+                          ino,
+                          entry.file_type_bits() | entry.mode,
+                          entry.mtime as u32,
+                          entry.size as u32)?;
+        let digest = match entry.content {
+            Content::Dir => None,
+            Content::Symlink(ref target) => {
+                self.write(target.as_bytes())?;
+                None
+            }
+            Content::File(ref src) => {
+                let mut contents = DigestWriter::new(&mut self.out);
+                io::copy(&mut File::open(src)?, &mut contents)?;
+                let (_, digest, len) = contents.finish();
+                self.written += len;
+                Some(digest)
+            }
+        };
+        self.pad()?;
+        Ok(digest)
+    }
+
+    /// Write the archive's trailer, returning the writer and the size of the archive.
+    fn finish(mut self) -> io::Result<(W, u64)> {
+        self.write_header(CPIO_TRAILER, 0, 0, 0, 0)?;
+        Ok((self.out, self.written))
+    }
+
+    fn write_header(&mut self,
+                    name: &str,
+                    ino: u32,
+                    mode: u32,
+                    mtime: u32,
+                    size: u32)
+                    -> io::Result<()> {
+        let header = format!("{}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:\
+                              08x}{:08x}{:08x}",
+                             CPIO_MAGIC,
+                             ino,
+                             mode,
+                             0, // uid
+                             0, // gid
+                             1, // nlink
+                             mtime,
+                             size,
+                             0, // devmajor
+                             0, // devminor
+                             0, // rdevmajor
+                             0, // rdevminor
+                             name.len() + 1,
+                             0 /* check */);
+        self.write(header.as_bytes())?;
+        self.write(name.as_bytes())?;
+        self.write(&[0])?;
+        self.pad()
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.out.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Pad to a 4 byte boundary, which headers and file contents are aligned to.
+    fn pad(&mut self) -> io::Result<()> {
+        let padding = (4 - self.written % 4) % 4;
+        self.write(&[0; 3][..padding as usize])
+    }
+}
+
+/// Passes writes through to `inner`, keeping a sha256 digest and a count of everything written.
+struct DigestWriter<W> {
+    inner:  W,
+    hasher: Sha256,
+    size:   u64,
+}
+
+impl<W: Write> DigestWriter<W> {
+    fn new(inner: W) -> Self {
+        DigestWriter { inner,
+                       hasher: Sha256::new(),
+                       size: 0 }
+    }
+
+    /// Returns the inner writer, the hex-encoded digest, and the size of everything written.
+    fn finish(self) -> (W, String, u64) {
+        (self.inner, format!("{:x}", self.hasher.finalize()), self.size)
+    }
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::package,
+                *};
+    use flate2::read::GzDecoder;
+    use std::{convert::TryInto,
+              io::Read};
+    use tempfile::TempDir;
+
+    fn be32(bytes: &[u8]) -> u32 { u32::from_be_bytes(bytes[..4].try_into().unwrap()) }
+
+    /// The index entries of a header, as (tag, type, offset, count), and its data.
+    fn parse(header: &[u8]) -> (Vec<(u32, u32, u32, u32)>, &[u8]) {
+        assert_eq!(&header[..8], HEADER_MAGIC);
+        let entry_count = be32(&header[8..]) as usize;
+        let data_len = be32(&header[12..]) as usize;
+        let entries = (0..entry_count).map(|i| {
+                                          let entry = &header[16 + i * 16..];
+                                          (be32(entry),
+                                           be32(&entry[4..]),
+                                           be32(&entry[8..]),
+                                           be32(&entry[12..]))
+                                      })
+                                      .collect();
+        let data_start = 16 + entry_count * 16;
+        (entries, &header[data_start..data_start + data_len])
+    }
+
+    fn string_at(data: &[u8], offset: u32) -> &str {
+        let data = &data[offset as usize..];
+        std::str::from_utf8(&data[..data.iter().position(|b| *b == 0).unwrap()]).unwrap()
+    }
+
+    #[test]
+    fn a_header_is_a_sorted_immutable_region() {
+        let header = index(TAG_HEADERIMMUTABLE,
+                           vec![(TAG_VERSION, Value::String("4.0.14".to_string())),
+                                (TAG_NAME, Value::String("redis".to_string())),
+                                (TAG_FILEMODES, Value::Int16(vec![0o100_644])),
+                                (TAG_FILESIZES, Value::Int32(vec![7]))]);
+        let (entries, data) = parse(&header);
+
+        assert_eq!(entries.iter().map(|e| e.0).collect::<Vec<_>>(),
+                   vec![TAG_HEADERIMMUTABLE,
+                        TAG_NAME,
+                        TAG_VERSION,
+                        TAG_FILESIZES,
+                        TAG_FILEMODES]);
+        // The region points at its trailer, at the end of the data
+        assert_eq!(entries[0],
+                   (TAG_HEADERIMMUTABLE, BIN_TYPE, data.len() as u32 - 16, 16));
+        let trailer = &data[data.len() - 16..];
+        assert_eq!(be32(trailer), TAG_HEADERIMMUTABLE);
+        assert_eq!(be32(&trailer[8..]) as i32, -5 * 16);
+        // Data is in the order of the index, and aligned to its type
+        assert_eq!(string_at(data, entries[1].2), "redis");
+        assert_eq!(string_at(data, entries[2].2), "4.0.14");
+        assert_eq!(entries[3].2 % 4, 0);
+        assert_eq!(be32(&data[entries[3].2 as usize..]), 7);
+        assert!(entries[4].2 > entries[3].2);
+    }
+
+    #[test]
+    fn an_rpm_has_a_lead_a_signature_a_header_and_a_payload() {
+        let tmp = TempDir::new().unwrap();
+        let pkg = package(tmp.path());
+        let mut rpm = Vec::new();
+        write(&pkg, &mut rpm).unwrap();
+
+        assert_eq!(&rpm[..4], LEAD_MAGIC);
+        assert_eq!(file_name(&pkg),
+                   format!("core-redis-server-4.0.14-20190319155852.{}.rpm",
+                           std::env::consts::ARCH));
+
+        let signature = &rpm[96..];
+        let (sig_entries, sig_data) = parse(signature);
+        let sig_len = 16 + sig_entries.len() * 16 + sig_data.len();
+        let header = &rpm[96 + (sig_len + 7) / 8 * 8..];
+        let (entries, data) = parse(header);
+        let header_len = 16 + entries.len() * 16 + data.len();
+        let (header, payload) = header.split_at(header_len);
+
+        let sha256 = sig_entries.iter().find(|e| e.0 == SIGTAG_SHA256).unwrap();
+        assert_eq!(string_at(sig_data, sha256.2),
+                   format!("{:x}", Sha256::digest(header)));
+        let size = sig_entries.iter().find(|e| e.0 == SIGTAG_SIZE).unwrap();
+        assert_eq!(be32(&sig_data[size.2 as usize..]) as usize,
+                   header.len() + payload.len());
+        let name = entries.iter().find(|e| e.0 == TAG_NAME).unwrap();
+        assert_eq!(string_at(data, name.2), "core-redis-server");
+
+        let mut cpio = Vec::new();
+        GzDecoder::new(payload).read_to_end(&mut cpio).unwrap();
+        let cpio = String::from_utf8_lossy(&cpio);
+        assert!(cpio.starts_with(CPIO_MAGIC));
+        assert!(cpio.contains("./hab/bin/bio\0"));
+        assert!(cpio.contains("#!/bin/sh\n"));
+        assert!(cpio.contains(CPIO_TRAILER));
+    }
+}
@@ -0,0 +1,44 @@
+//! Runs the exported Supervisor under systemd, with the exported package loaded into it by a
+//! service spec, so that the service starts along with the host. The spec is written with the
+//! rest of the preloaded configuration, by `common::preload`.
+
+use crate::{common::ui::{Status,
+                         UIWriter,
+                         UI},
+            error::Result};
+use std::{fs,
+          path::Path};
+
+/// The name of the systemd unit which runs the Supervisor.
+pub const UNIT_NAME: &str = "bio-sup.service";
+/// Where the unit is installed, relative to the root file system.
+pub const UNIT_DIR: &str = "etc/systemd/system";
+
+const UNIT: &str = "[Unit]
+Description=The Biome Supervisor
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+ExecStart=/hab/bin/bio sup run
+ExecStop=/hab/bin/bio sup term
+KillMode=process
+Restart=on-failure
+LimitNOFILE=65536
+
+[Install]
+WantedBy=multi-user.target
+";
+
+/// Write the Supervisor's unit into the root file system.
+///
+/// # Errors
+///
+/// * If the unit cannot be written
+pub fn write(ui: &mut UI, rootfs: &Path) -> Result<()> {
+    ui.status(Status::Creating, format!("systemd unit {}", UNIT_NAME))?;
+    let unit_dir = rootfs.join(UNIT_DIR);
+    fs::create_dir_all(&unit_dir)?;
+    fs::write(unit_dir.join(UNIT_NAME), UNIT)?;
+    Ok(())
+}
@@ -112,6 +112,8 @@ $script:pkg_svc_run = ''
 $script:pkg_expose = @()
 # An associative array representing configuration data which should be gossiped to peers.
 $script:pkg_exports = @{}
+# An associative array of lifecycle hook names and the seconds each may run before being killed.
+$script:pkg_hook_timeouts = @{}
 # The user to run the service as
 $script:pkg_svc_user = ""
 # svc_group is not actually used on Windows but it needs to exist.
@@ -1405,6 +1407,12 @@ function Write-Metadata {
         }
     }
 
+    if ($pkg_hook_timeouts) {
+        foreach ($timeout in $pkg_hook_timeouts.GetEnumerator()) {
+            "$($timeout.Key)=$($timeout.Value)" | Out-File "$pkg_prefix\HOOK_TIMEOUTS" -Encoding ascii -Append
+        }
+    }
+
     Write-EnvironmentFiles
 
     $pkg_build_deps_resolved | ForEach-Object {
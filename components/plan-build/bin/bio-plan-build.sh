@@ -124,9 +124,12 @@
 #
 #
 # ### pkg_deps
-# An array of the package dependencies needed at runtime.
+# An array of the package dependencies needed at runtime. A dependency may
+# carry a version constraint after an `@`, in which case the latest release
+# within the constraint is used.
 # ```
 # pkg_deps=(glibc pcre openssl zlib)
+# pkg_deps=(core/glibc "core/openssl@>=1.1,<3" core/zlib@~1.2)
 # ```
 #
 # ### pkg_build_deps
@@ -211,6 +214,17 @@
 #   [storage]="port host"
 # )
 #
+# ### pkg_hook_timeouts
+# An associative array of lifecycle hook names and the number of seconds each hook may run before
+# the Supervisor kills it. Hooks without an entry may run indefinitely. These can be overridden
+# with `bio svc load --hook-timeout`.
+# ```
+# pkg_hook_timeouts=(
+#   [init]=300
+#   [health-check]=10
+# )
+# ```
+#
 # ### pkg_origin
 # A string to use for the origin. The origin is used to denote a particular upstream of a
 # package; when we resolve dependencies, we consider a version of a package to be equal
@@ -363,6 +377,8 @@ INITIAL_PATH="$PATH"
 INITIAL_PWD="$(pwd)"
 # The compression level to use when compression harts (0..9)
 : "${HAB_HART_COMPRESSION_LEVEL:=6}"
+# The compression algorithm to use for hart payloads (`xz` or `zstd`)
+: "${HAB_HART_COMPRESSION:=xz}"
 # The full target tuple this plan will be built for
 pkg_target='@@pkg_target@@'
 # The package's origin (i.e. acme)
@@ -388,6 +404,7 @@ pkg_exposes=()
 declare -A pkg_exports
 declare -A pkg_binds
 declare -A pkg_binds_optional
+declare -A pkg_hook_timeouts
 # The user to run the service as
 pkg_svc_user=hab
 # The group to run the service as
@@ -580,6 +597,7 @@ _ensure_origin_key_present() {
 # * `$_wget_cmd` (wget on system)
 # * `$_shasum_cmd` (either gsha256sum or sha256sum on system)
 # * `$_tar_cmd` (GNU version of tar)
+# * `$_xz_cmd` or `$_zstd_cmd` (depending on `$HAB_HART_COMPRESSION`)
 # * `$_mktemp_cmd` (GNU version from coreutils)
 # * `$_rq_cmd`
 #
@@ -622,15 +640,33 @@ _find_system_commands() {
   fi
   debug "Setting _tar_cmd=$_tar_cmd"
 
-  if exists xz; then
-    _xz_cmd=$(command -v xz)
-    if [[ "${HAB_NONINTERACTIVE:-}" != "true" ]]; then
-      _xz_cmd="$_xz_cmd --verbose"
-    fi
-  else
-    exit_with "We require xz to compress artifacts; aborting" 1
-  fi
-  debug "Setting _xz_cmd=$_xz_cmd"
+  case "$HAB_HART_COMPRESSION" in
+    xz)
+      if exists xz; then
+        _xz_cmd=$(command -v xz)
+        if [[ "${HAB_NONINTERACTIVE:-}" != "true" ]]; then
+          _xz_cmd="$_xz_cmd --verbose"
+        fi
+      else
+        exit_with "We require xz to compress artifacts; aborting" 1
+      fi
+      debug "Setting _xz_cmd=$_xz_cmd"
+      ;;
+    zstd)
+      if exists zstd; then
+        _zstd_cmd=$(command -v zstd)
+        if [[ "${HAB_NONINTERACTIVE:-}" == "true" ]]; then
+          _zstd_cmd="$_zstd_cmd --quiet"
+        fi
+      else
+        exit_with "We require zstd to compress artifacts with HAB_HART_COMPRESSION=zstd; aborting" 1
+      fi
+      debug "Setting _zstd_cmd=$_zstd_cmd"
+      ;;
+    *)
+      exit_with "Unsupported HAB_HART_COMPRESSION '$HAB_HART_COMPRESSION', expected xz or zstd; aborting" 1
+      ;;
+  esac
 
   if exists bio; then
     _bio_cmd=$(command -v bio)
@@ -684,6 +720,8 @@ _latest_installed_package() {
 # # /hab/pkgs/acme/zlib/1.2.8/20151216221001
 # _resolve_dependency acme/zlib/1.2.8/20151216221001
 # # /hab/pkgs/acme/zlib/1.2.8/20151216221001
+# _resolve_dependency acme/zlib@~1.2
+# # /hab/pkgs/acme/zlib/1.2.11/20190115003731
 # ```
 #
 # Will return 0 if a package was found or installed on disk, and 1 if a package
@@ -714,6 +752,7 @@ _resolve_dependency() {
 # _install_dependency acme/zlib
 # _install_dependency acme/zlib/1.2.8
 # _install_dependency acme/zlib/1.2.8/20151216221001
+# _install_dependency acme/zlib@~1.2
 # ```
 _install_dependency() {
     local dep="${1}"
@@ -1954,6 +1993,7 @@ do_default_install() {
 # * `$pkg_prefix/EXPOSES` - An array of `pkg_exports` for which ports that this package exposes
 # * `$pkg_prefix/BINDS` - A list of services you connect to and keys that you expect to be exported
 # * `$pkg_prefix/BINDS_OPTIONAL` - Same as `BINDS` but not required for the service to start
+# * `$pkg_prefix/HOOK_TIMEOUTS` - The number of seconds each listed hook may run before being killed
 # * `$pkg_prefix/FILES` - blake2b checksums of all files in the package
 # * `$pkg_prefix/LDFLAGS` - Any LDFLAGS for things that link against us
 # * `$pkg_prefix/LD_RUN_PATH` - The LD_RUN_PATH for things that link against us
@@ -1974,6 +2014,7 @@ _build_metadata() {
   _render_metadata_EXPORTS
   _render_metadata_BINDS
   _render_metadata_BINDS_OPTIONAL
+  _render_metadata_HOOK_TIMEOUTS
   _render_metadata_EXPOSES
   _render_metadata_INTERPRETERS
   _render_metadata_BUILD_DEPS
@@ -2268,14 +2309,25 @@ _generate_artifact() {
   build_line "Generating package artifact"
   local tarf
   tarf="$(dirname "$pkg_artifact")/.$(basename "${pkg_artifact/%.${_artifact_ext}/.tar}")"
-  local xzf="${tarf}.xz"
+  local compressedf
 
   mkdir -pv "$(dirname "$pkg_artifact")"
-  rm -fv "$tarf" "$xzf" "$pkg_artifact"
+  rm -fv "$tarf" "${tarf}.xz" "${tarf}.zst" "$pkg_artifact"
   $_tar_cmd -cf "$tarf" "$pkg_prefix"
-  $_xz_cmd --compress -${HAB_HART_COMPRESSION_LEVEL} --threads=0 "$tarf"
-  $HAB_BIN pkg sign --origin $pkg_origin "$xzf" "$pkg_artifact"
-  rm -f "$tarf" "$xzf"
+  # `bio pkg sign` detects the payload compression and writes the matching
+  # hart header
+  case "$HAB_HART_COMPRESSION" in
+    zstd)
+      compressedf="${tarf}.zst"
+      $_zstd_cmd -${HAB_HART_COMPRESSION_LEVEL} -T0 --rm "$tarf" -o "$compressedf"
+      ;;
+    *)
+      compressedf="${tarf}.xz"
+      $_xz_cmd --compress -${HAB_HART_COMPRESSION_LEVEL} --threads=0 "$tarf"
+      ;;
+  esac
+  $HAB_BIN pkg sign --origin $pkg_origin "$compressedf" "$pkg_artifact"
+  rm -f "$tarf" "$compressedf"
 }
 
 _prepare_build_outputs() {
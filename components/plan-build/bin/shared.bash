@@ -53,6 +53,10 @@ _render_metadata_EXPORTS() {
     _render_associative_array_file "${pkg_prefix}" EXPORTS pkg_exports
 }
 
+_render_metadata_HOOK_TIMEOUTS() {
+    _render_associative_array_file "${pkg_prefix}" HOOK_TIMEOUTS pkg_hook_timeouts
+}
+
 _render_metadata_EXPOSES() {
   # TODO (CM): rename port_part and make it an array
   local port_part=""
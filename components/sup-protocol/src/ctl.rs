@@ -36,6 +36,22 @@ impl message::MessageStatic for SvcGetDefaultCfg {
     const MESSAGE_ID: &'static str = "SvcGetDefaultCfg";
 }
 
+impl message::MessageStatic for SvcGetMemberCfg {
+    const MESSAGE_ID: &'static str = "SvcGetMemberCfg";
+}
+
+impl message::MessageStatic for SvcMemberCfg {
+    const MESSAGE_ID: &'static str = "SvcMemberCfg";
+}
+
+impl message::MessageStatic for SvcGetRenderContext {
+    const MESSAGE_ID: &'static str = "SvcGetRenderContext";
+}
+
+impl message::MessageStatic for SvcRenderContext {
+    const MESSAGE_ID: &'static str = "SvcRenderContext";
+}
+
 impl message::MessageStatic for SvcValidateCfg {
     const MESSAGE_ID: &'static str = "SvcValidateCfg";
 }
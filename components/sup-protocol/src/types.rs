@@ -38,6 +38,9 @@ impl message::MessageStatic for ServiceStatus {
 impl message::MessageStatic for HealthCheckInterval {
     const MESSAGE_ID: &'static str = "HealthCheckInterval";
 }
+impl message::MessageStatic for HookTimeout {
+    const MESSAGE_ID: &'static str = "HookTimeout";
+}
 
 impl ServiceGroup {
     pub fn validate(value: &str) -> core::Result<()> {
@@ -58,12 +61,17 @@ impl fmt::Display for BindingMode {
 
 impl fmt::Display for PackageIdent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match (self.version.as_ref(), self.release.as_ref()) {
-            (Some(ref version), Some(ref release)) => {
+        match (self.version.as_ref(), self.release.as_ref(), self.constraint.as_ref()) {
+            (Some(ref version), Some(ref release), _) => {
                 write!(f, "{}/{}/{}/{}", self.origin, self.name, version, release,)
             }
-            (Some(ref version), None) => write!(f, "{}/{}/{}", self.origin, self.name, version,),
-            (None, Some(_)) | (None, None) => write!(f, "{}/{}", self.origin, self.name),
+            (Some(ref version), None, _) => {
+                write!(f, "{}/{}/{}", self.origin, self.name, version,)
+            }
+            (None, _, Some(ref constraint)) => {
+                write!(f, "{}/{}@{}", self.origin, self.name, constraint)
+            }
+            (None, _, None) => write!(f, "{}/{}", self.origin, self.name),
         }
     }
 }
@@ -198,18 +206,31 @@ impl From<core::service::HealthCheckInterval> for HealthCheckInterval {
     fn from(h: core::service::HealthCheckInterval) -> Self { Self { seconds: h.into() } }
 }
 
+impl From<core::service::HookTimeoutOverride> for HookTimeout {
+    fn from(o: core::service::HookTimeoutOverride) -> Self {
+        Self { hook:    o.hook().to_string(),
+               seconds: o.timeout().into(), }
+    }
+}
+
 impl From<package::PackageIdent> for PackageIdent {
     fn from(ident: package::PackageIdent) -> Self {
-        Self { origin:  ident.origin,
-               name:    ident.name,
-               version: ident.version,
-               release: ident.release, }
+        Self { origin:     ident.origin,
+               name:       ident.name,
+               version:    ident.version,
+               release:    ident.release,
+               constraint: ident.constraint.map(|constraint| constraint.to_string()), }
     }
 }
 
 impl Into<package::PackageIdent> for PackageIdent {
     fn into(self) -> package::PackageIdent {
-        package::PackageIdent::new(self.origin, self.name, self.version, self.release)
+        let mut ident =
+            package::PackageIdent::new(self.origin, self.name, self.version, self.release);
+        // The constraint was validated when the ident it came from was parsed
+        ident.constraint = self.constraint
+                               .and_then(|constraint| constraint.parse().ok());
+        ident
     }
 }
 
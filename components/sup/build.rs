@@ -49,6 +49,22 @@ fn raml2html_cmd(dst: PathBuf, src: PathBuf) -> io::Result<ExitStatus> {
 
 fn generate_event_protobufs() {
     let mut config = prost_build::Config::new();
+    // Events are also sent as JSON to sinks other than NATS. Well-known
+    // types and enums are rendered as the proto3 JSON mapping would,
+    // rather than as bare structs and numbers.
+    config.type_attribute(".", "#[derive(Serialize)]");
+    for (field, serializer) in &[("EventMetadata.occurred_at", "serialize_timestamp"),
+                                 ("UpdateConfig.strategy", "serialize_update_strategy"),
+                                 ("HealthCheckEvent.result", "serialize_health_check_result"),
+                                 ("HealthCheckEvent.execution", "serialize_duration"),
+                                 ("HealthCheckEvent.interval", "serialize_duration"),
+                                 ("MemberHealthEvent.previous_health", "serialize_member_health"),
+                                 ("MemberHealthEvent.health", "serialize_member_health"),
+                                 ("SupervisorStoppedEvent.reason", "serialize_shutdown_reason")]
+    {
+        config.field_attribute(format!(".chef.habitat.supervisor.event.{}", field),
+                               format!("#[serde(serialize_with = \"{}\")]", serializer));
+    }
     config.compile_protos(&["protocols/event.proto"], &["protocols/"])
           .unwrap()
 }
@@ -1,4 +1,5 @@
-use crate::error::Error;
+use crate::{error::Error,
+            event};
 use biome_butterfly::{member::{Health,
                                  Member,
                                  MemberList,
@@ -8,11 +9,14 @@ use biome_butterfly::{member::{Health,
                                            ElectionUpdate as ElectionUpdateRumor},
                                 service::{Service as ServiceRumor,
                                           SysInfo},
-                                service_config::ServiceConfig as ServiceConfigRumor,
+                                service_config::{ConfigTarget,
+                                                 ServiceConfig as ServiceConfigRumor},
                                 service_file::ServiceFile as ServiceFileRumor,
                                 ConstIdRumor as _,
+                                Rumor as _,
                                 RumorStore}};
-use biome_common::outputln;
+use biome_common::{outputln,
+                     types::MemberLabel};
 use biome_core::{self,
                    crypto::keys::KeyCache,
                    package::PackageIdent,
@@ -44,6 +48,10 @@ pub struct CensusRing {
     last_membership_counter: usize,
     last_service_config_counter: usize,
     last_service_file_counter: usize,
+    /// The last health we saw for each member, so that we can send an
+    /// event whenever it changes.
+    #[serde(skip)]
+    member_health: HashMap<MemberId, Health>,
 }
 
 impl CensusRing {
@@ -62,7 +70,8 @@ impl CensusRing {
                      last_election_update_counter: 0,
                      last_membership_counter: 0,
                      last_service_config_counter: 0,
-                     last_service_file_counter: 0, }
+                     last_service_file_counter: 0,
+                     member_health: HashMap::new(), }
     }
 
     /// # Locking (see locking.md)
@@ -151,6 +160,15 @@ impl CensusRing {
                                census_member.update_from_health(health);
                            }
                        }
+                       // Members we haven't seen before have no
+                       // transition to report.
+                       if let Some(previous_health) =
+                           self.member_health.insert(member.id.clone(), health)
+                       {
+                           if previous_health != health {
+                               event::member_health(&member, previous_health, health);
+                           }
+                       }
                        Ok(())
                    })
                    .ok();
@@ -191,8 +209,8 @@ impl CensusRing {
                                       service_config_rumors: &RumorStore<ServiceConfigRumor>) {
         for (service_group, rumors) in service_config_rumors.lock_rsr().iter() {
             if let Ok(sg) = service_group_from_str(service_group) {
-                if let Some(service_config) = rumors.get(ServiceConfigRumor::const_id()) {
-                    if let Some(census_group) = self.census_groups.get_mut(&sg) {
+                if let Some(census_group) = self.census_groups.get_mut(&sg) {
+                    for service_config in rumors.values() {
                         census_group.update_from_service_config_rumor(key_cache, service_config);
                     }
                 }
@@ -307,15 +325,18 @@ pub struct ServiceFile {
 pub struct ServiceConfig {
     pub incarnation: u64,
     pub value:       toml::value::Table,
+    pub target:      ConfigTarget,
 }
 
 #[derive(Debug)]
 pub struct CensusGroup {
-    pub service_group:          ServiceGroup,
-    pub election_status:        ElectionStatus,
-    pub update_election_status: ElectionStatus,
-    pub leader_id:              Option<MemberId>,
-    pub service_config:         Option<ServiceConfig>,
+    pub service_group:            ServiceGroup,
+    pub election_status:          ElectionStatus,
+    pub update_election_status:   ElectionStatus,
+    pub leader_id:                Option<MemberId>,
+    pub service_config:           Option<ServiceConfig>,
+    /// Service configs targeted at a subset of the group's members, keyed by rumor id
+    pub targeted_service_configs: BTreeMap<String, ServiceConfig>,
 
     local_member_id:       MemberId,
     population:            BTreeMap<MemberId, CensusMember>,
@@ -326,16 +347,34 @@ pub struct CensusGroup {
 
 impl CensusGroup {
     fn new(sg: ServiceGroup, local_member_id: &str) -> Self {
-        CensusGroup { service_group:          sg,
-                      election_status:        ElectionStatus::None,
-                      update_election_status: ElectionStatus::None,
-                      local_member_id:        local_member_id.to_string(),
-                      population:             BTreeMap::new(),
-                      leader_id:              None,
-                      update_leader_id:       None,
-                      service_config:         None,
-                      service_files:          HashMap::new(),
-                      changed_service_files:  HashSet::new(), }
+        CensusGroup { service_group:            sg,
+                      election_status:          ElectionStatus::None,
+                      update_election_status:   ElectionStatus::None,
+                      local_member_id:          local_member_id.to_string(),
+                      population:               BTreeMap::new(),
+                      leader_id:                None,
+                      update_leader_id:         None,
+                      service_config:           None,
+                      targeted_service_configs: BTreeMap::new(),
+                      service_files:            HashMap::new(),
+                      changed_service_files:    HashSet::new(), }
+    }
+
+    /// Returns the targeted service configs that apply to a member, in the order they should
+    /// be layered: configs targeting labels come before configs naming the member, so the most
+    /// specific target wins. An empty config clears its target, so it is never returned.
+    pub fn targeted_service_configs_for(&self,
+                                        member_id: &str,
+                                        labels: &[MemberLabel])
+                                        -> Vec<&ServiceConfig> {
+        let mut configs = self.targeted_service_configs
+                              .values()
+                              .filter(|c| {
+                                  !c.value.is_empty() && c.target.matches(member_id, labels)
+                              })
+                              .collect::<Vec<_>>();
+        configs.sort_by_key(|c| (c.target.names_member(member_id), c.incarnation));
+        configs
     }
 
     /// Returns the census member in the census ring for the running Supervisor.
@@ -468,14 +507,25 @@ impl CensusGroup {
     fn update_from_service_config_rumor(&mut self,
                                         key_cache: &KeyCache,
                                         service_config: &ServiceConfigRumor) {
+        let target = service_config.target();
+        let current = if target.is_group() {
+            self.service_config.as_ref()
+        } else {
+            self.targeted_service_configs.get(service_config.id())
+        };
+        if current.map_or(false, |c| service_config.incarnation <= c.incarnation) {
+            return;
+        }
         match service_config.config(key_cache) {
             Ok(config) => {
-                if self.service_config.is_none()
-                   || service_config.incarnation > self.service_config.as_ref().unwrap().incarnation
-                {
-                    self.service_config = Some(ServiceConfig { incarnation:
-                                                                   service_config.incarnation,
-                                                               value:       config, });
+                let config = ServiceConfig { incarnation: service_config.incarnation,
+                                             value:       config,
+                                             target:      target.clone(), };
+                if target.is_group() {
+                    self.service_config = Some(config);
+                } else {
+                    self.targeted_service_configs
+                        .insert(service_config.id().to_string(), config);
                 }
             }
             Err(err) => warn!("{}", err),
@@ -977,4 +1027,25 @@ mod tests {
         assert_eq_member_ids(CensusGroup::previous_peer_impl(members.iter(), &me),
                              Some("left_of_me_with_wrapping"));
     }
+
+    #[test]
+    fn an_empty_targeted_service_config_clears_its_target() {
+        let key_cache = KeyCache::new(&*CACHE_KEY_PATH);
+        let sg = ServiceGroup::new("shield", "one", None).unwrap();
+        let mut group = CensusGroup::new(sg.clone(), "member-a");
+        let target = ConfigTarget::new(vec!["member-a".to_string()], vec![]);
+
+        let mut applied = ServiceConfigRumor::new("member-b", sg.clone(), b"port = 1".to_vec())
+            .with_target(target.clone());
+        applied.incarnation = 1;
+        group.update_from_service_config_rumor(&key_cache, &applied);
+        assert_eq!(group.targeted_service_configs_for("member-a", &[]).len(), 1);
+
+        let mut cleared =
+            ServiceConfigRumor::new("member-b", sg, Vec::new()).with_target(target);
+        cleared.incarnation = 2;
+        group.update_from_service_config_rumor(&key_cache, &cleared);
+        assert!(group.targeted_service_configs_for("member-a", &[]).is_empty());
+        assert!(group.service_config.is_none());
+    }
 }
@@ -294,6 +294,12 @@ impl SrvHandler {
                                           -> std::result::Result<CtlCommand, HandlerError> {
         match msg.message_id() {
             "SvcGetDefaultCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_msr),
+            "SvcGetMemberCfg" => {
+                util::to_command(msg, ctl_sender, commands::service_member_cfg_msr)
+            }
+            "SvcGetRenderContext" => {
+                util::to_command(msg, ctl_sender, commands::service_render_context_msr)
+            }
             "SvcFilePut" => util::to_command(msg, ctl_sender, commands::service_file_put),
             "SvcSetCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_set),
             "SvcValidateCfg" => util::to_command(msg, ctl_sender, commands::service_cfg_validate),
@@ -1,11 +1,13 @@
 //! Main interface for a stream of events the Supervisor can send out
 //! in the course of its operations.
 //!
-//! Currently, the Supervisor is able to send events to a [NATS][1]
-//! server. The `init_stream` function must be called
-//! before sending events to initialize the publishing thread in the
-//! background. Thereafter, you can pass "event" structs to the
-//! `event` function, which will publish the event to the stream.
+//! The Supervisor is able to send events to a [NATS][1] server, and
+//! to any number of other sinks: files and Unix sockets (as
+//! newline-delimited JSON), HTTP webhooks and syslog. The `init`
+//! function must be called before sending events to start each sink's
+//! publishing task in the background. Thereafter, you can pass
+//! "event" structs to the `event` function, which will publish the
+//! event to every sink.
 //!
 //! All events are published under the "habitat" subject.
 //!
@@ -13,34 +15,54 @@
 
 mod error;
 mod nats_message_stream;
+mod sink;
 mod types;
 
 pub(crate) use self::types::ServiceMetadata;
-use self::types::{EventMessage,
+use self::types::{ElectionLostEvent,
+                  ElectionStartedEvent,
+                  ElectionWonEvent,
+                  EventMessage,
                   EventMetadata,
                   HealthCheckEvent,
+                  HookFailedEvent,
+                  MemberHealthEvent,
+                  PackageInstalledEvent,
+                  PackageUninstalledEvent,
+                  ServiceConfigAppliedEvent,
+                  ServiceFileUploadedEvent,
+                  ServiceLoadedEvent,
                   ServiceStartedEvent,
                   ServiceStoppedEvent,
-                  ServiceUpdateStartedEvent};
-use crate::manager::{service::{HealthCheckHookStatus,
-                               HealthCheckResult,
-                               ProcessOutput,
-                               Service,
-                               StandardStreams},
-                     sys::Sys};
+                  ServiceUnloadedEvent,
+                  ServiceUpdateStartedEvent,
+                  SupervisorStartedEvent,
+                  SupervisorStoppedEvent};
+use crate::{manager::{service::{HealthCheckHookStatus,
+                                HealthCheckResult,
+                                ProcessOutput,
+                                Service,
+                                StandardStreams},
+                      sys::Sys,
+                      ShutdownMode},
+            VERSION};
 pub use error::{Error,
                 Result};
-use biome_common::types::{EventStreamConnectMethod,
+use biome_common::types::{EventSinkAddress,
+                            EventStreamConnectMethod,
                             EventStreamMetadata,
                             EventStreamServerCertificate,
                             EventStreamToken};
+use biome_butterfly::member::{Health,
+                              Member};
 use biome_core::{package::ident::PackageIdent,
                    service::HealthCheckInterval};
-use nats_message_stream::{NatsMessage,
-                          NatsMessageStream};
+use nats_message_stream::NatsMessageStream;
 use prost_types::Duration as ProstDuration;
 use rants::{Address,
             Subject};
+use sink::{Event,
+           EventSink};
 use state::Storage;
 use std::{net::SocketAddr,
           time::Duration};
@@ -58,25 +80,57 @@ lazy_static! {
         "habitat.event.service_update_started".parse().expect("valid NATS subject");
     static ref HEALTHCHECK_SUBJECT: Subject =
         "habitat.event.healthcheck".parse().expect("valid NATS subject");
+    static ref SERVICE_LOADED_SUBJECT: Subject =
+        "habitat.event.service_loaded".parse().expect("valid NATS subject");
+    static ref SERVICE_UNLOADED_SUBJECT: Subject =
+        "habitat.event.service_unloaded".parse().expect("valid NATS subject");
+    static ref SERVICE_CONFIG_APPLIED_SUBJECT: Subject =
+        "habitat.event.service_config_applied".parse().expect("valid NATS subject");
+    static ref SERVICE_FILE_UPLOADED_SUBJECT: Subject =
+        "habitat.event.service_file_uploaded".parse().expect("valid NATS subject");
+    static ref ELECTION_STARTED_SUBJECT: Subject =
+        "habitat.event.election_started".parse().expect("valid NATS subject");
+    static ref ELECTION_WON_SUBJECT: Subject =
+        "habitat.event.election_won".parse().expect("valid NATS subject");
+    static ref ELECTION_LOST_SUBJECT: Subject =
+        "habitat.event.election_lost".parse().expect("valid NATS subject");
+    static ref MEMBER_HEALTH_SUBJECT: Subject =
+        "habitat.event.member_health".parse().expect("valid NATS subject");
+    static ref HOOK_FAILED_SUBJECT: Subject =
+        "habitat.event.hook_failed".parse().expect("valid NATS subject");
+    static ref PACKAGE_INSTALLED_SUBJECT: Subject =
+        "habitat.event.package_installed".parse().expect("valid NATS subject");
+    static ref PACKAGE_UNINSTALLED_SUBJECT: Subject =
+        "habitat.event.package_uninstalled".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_STARTED_SUBJECT: Subject =
+        "habitat.event.supervisor_started".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_STOPPED_SUBJECT: Subject =
+        "habitat.event.supervisor_stopped".parse().expect("valid NATS subject");
 
-    /// Reference to the event stream.
-    static ref NATS_MESSAGE_STREAM: Storage<NatsMessageStream> = Storage::new();
+    /// Every sink events are sent to.
+    static ref EVENT_SINKS: Storage<Vec<Box<dyn EventSink>>> = Storage::new();
     /// Core information that is shared between all events.
     static ref EVENT_CORE: Storage<EventCore> = Storage::new();
 }
 
-/// Starts a new task for sending events to a NATS Streaming
-/// server. Stashes the handle to the stream, as well as the core
-/// event information that will be a part of all events, in a global
-/// static reference for access later.
+/// Starts a new task for sending events to a NATS Streaming server,
+/// if one is configured, and to each other sink. Stashes the handles
+/// to the sinks, as well as the core event information that will be a
+/// part of all events, in a global static reference for access later.
 pub async fn init(sys: &Sys, fqdn: String, config: EventStreamConfig) -> Result<()> {
     // Only initialize once
     if !initialized() {
         let supervisor_id = sys.member_id.clone();
         let ip_address = sys.gossip_listen();
         let event_core = EventCore::new(&supervisor_id, ip_address, &fqdn, &config);
-        let stream = NatsMessageStream::new(&supervisor_id, config).await?;
-        NATS_MESSAGE_STREAM.set(stream);
+        let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+        if let Some(nats) = config.nats {
+            sinks.push(Box::new(NatsMessageStream::new(&supervisor_id, nats).await?));
+        }
+        for address in config.sinks {
+            sinks.push(sink::start(address, &fqdn).await?);
+        }
+        EVENT_SINKS.set(sinks);
         EVENT_CORE.set(event_core);
     }
     Ok(())
@@ -88,10 +142,19 @@ pub async fn init(sys: &Sys, fqdn: String, config: EventStreamConfig) -> Result<
 // layout so this can be avoided.
 #[derive(Clone, Debug, PartialEq)]
 pub struct EventStreamConfig {
-    pub environment:        String,
-    pub application:        String,
-    pub site:               Option<String>,
-    pub meta:               EventStreamMetadata,
+    pub environment: String,
+    pub application: String,
+    pub site:        Option<String>,
+    pub meta:        EventStreamMetadata,
+    /// The NATS server events are published to, if any.
+    pub nats:        Option<NatsConfig>,
+    /// Every other sink events are sent to.
+    pub sinks:       Vec<EventSinkAddress>,
+}
+
+/// How to connect to the NATS server events are published to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NatsConfig {
     pub token:              EventStreamToken,
     pub url:                Address,
     pub connect_method:     EventStreamConnectMethod,
@@ -158,6 +221,149 @@ pub fn health_check(metadata: ServiceMetadata,
     }
 }
 
+/// Send an event for a Service being loaded, which may be before it
+/// starts.
+pub fn service_loaded(metadata: ServiceMetadata) {
+    if initialized() {
+        publish(&SERVICE_LOADED_SUBJECT,
+                ServiceLoadedEvent { service_metadata: Some(metadata),
+                                     event_metadata:   None, });
+    }
+}
+
+/// Send an event for a Service being unloaded.
+pub fn service_unloaded(metadata: ServiceMetadata) {
+    if initialized() {
+        publish(&SERVICE_UNLOADED_SUBJECT,
+                ServiceUnloadedEvent { service_metadata: Some(metadata),
+                                       event_metadata:   None, });
+    }
+}
+
+/// Send an event for a Service taking on a new incarnation of its
+/// gossiped configuration.
+pub fn service_config_applied(service: &Service, incarnation: u64) {
+    if initialized() {
+        publish(&SERVICE_CONFIG_APPLIED_SUBJECT,
+                ServiceConfigAppliedEvent { service_metadata:
+                                                Some(service.to_service_metadata()),
+                                            event_metadata: None,
+                                            incarnation });
+    }
+}
+
+/// Send an event for a gossiped file being written for a Service.
+pub fn service_file_uploaded(service: &Service, filename: &str, incarnation: u64) {
+    if initialized() {
+        publish(&SERVICE_FILE_UPLOADED_SUBJECT,
+                ServiceFileUploadedEvent { service_metadata:
+                                               Some(service.to_service_metadata()),
+                                           event_metadata: None,
+                                           filename: filename.to_string(),
+                                           incarnation });
+    }
+}
+
+/// Send an event for the start of an election in a Service's group.
+pub fn election_started(service: &Service) {
+    if initialized() {
+        publish(&ELECTION_STARTED_SUBJECT,
+                ElectionStartedEvent { service_metadata: Some(service.to_service_metadata()),
+                                       event_metadata:   None, });
+    }
+}
+
+/// Send an event for this Supervisor winning the election in a
+/// Service's group.
+pub fn election_won(service: &Service) {
+    if initialized() {
+        publish(&ELECTION_WON_SUBJECT,
+                ElectionWonEvent { service_metadata: Some(service.to_service_metadata()),
+                                   event_metadata:   None, });
+    }
+}
+
+/// Send an event for another member, `leader_id`, winning the
+/// election in a Service's group.
+pub fn election_lost(service: &Service, leader_id: &str) {
+    if initialized() {
+        publish(&ELECTION_LOST_SUBJECT,
+                ElectionLostEvent { service_metadata: Some(service.to_service_metadata()),
+                                    event_metadata:   None,
+                                    leader_id:        leader_id.to_string(), });
+    }
+}
+
+/// Send an event for a change in the health of a member of the
+/// network.
+pub fn member_health(member: &Member, previous_health: Health, health: Health) {
+    if initialized() {
+        publish(&MEMBER_HEALTH_SUBJECT,
+                MemberHealthEvent { event_metadata:  None,
+                                    member_id:       member.id.clone(),
+                                    address:         format!("{}:{}",
+                                                             member.address, member.gossip_port),
+                                    previous_health:
+                                        types::MemberHealth::from(previous_health).into(),
+                                    health:          types::MemberHealth::from(health).into(), });
+    }
+}
+
+/// Send an event for a hook which could not be run, or which exited
+/// unsuccessfully.
+pub fn hook_failed(metadata: &ServiceMetadata,
+                   hook: &str,
+                   exit_status: Option<i32>,
+                   error: Option<String>) {
+    if initialized() {
+        publish(&HOOK_FAILED_SUBJECT,
+                HookFailedEvent { service_metadata: Some(metadata.clone()),
+                                  event_metadata: None,
+                                  hook: hook.to_string(),
+                                  exit_status,
+                                  error });
+    }
+}
+
+/// Send an event for a package being installed by the Supervisor.
+pub fn package_installed(ident: &PackageIdent) {
+    if initialized() {
+        publish(&PACKAGE_INSTALLED_SUBJECT,
+                PackageInstalledEvent { event_metadata: None,
+                                        package_ident:  ident.to_string(), });
+    }
+}
+
+/// Send an event for a package being uninstalled by the Supervisor.
+pub fn package_uninstalled(ident: &PackageIdent) {
+    if initialized() {
+        publish(&PACKAGE_UNINSTALLED_SUBJECT,
+                PackageUninstalledEvent { event_metadata: None,
+                                          package_ident:  ident.to_string(), });
+    }
+}
+
+/// Send an event for the Supervisor having started.
+pub fn supervisor_started() {
+    if initialized() {
+        publish(&SUPERVISOR_STARTED_SUBJECT,
+                SupervisorStartedEvent { event_metadata: None,
+                                         version:        VERSION.to_string(), });
+    }
+}
+
+/// Send an event for the Supervisor shutting down.
+///
+/// Sinks deliver events in the background, so this is sent on a
+/// best-effort basis: the Supervisor may exit before it is.
+pub fn supervisor_stopped(mode: ShutdownMode) {
+    if initialized() {
+        publish(&SUPERVISOR_STOPPED_SUBJECT,
+                SupervisorStoppedEvent { event_metadata: None,
+                                         reason:         types::ShutdownReason::from(mode).into(), });
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////
 
 /// A collection of data that will be present in all events. Rather
@@ -200,7 +406,7 @@ impl EventCore {
 /// Internal helper function to know whether or not to go to the trouble of
 /// creating event structures. If the event stream hasn't been
 /// initialized, then we shouldn't need to do anything.
-fn initialized() -> bool { NATS_MESSAGE_STREAM.try_get().is_some() }
+fn initialized() -> bool { EVENT_SINKS.try_get().is_some() }
 
 /// Publish an event. This is the main interface that client code will
 /// use.
@@ -208,7 +414,7 @@ fn initialized() -> bool { NATS_MESSAGE_STREAM.try_get().is_some() }
 /// If `init_stream` has not been called already, this function will
 /// be a no-op.
 fn publish(subject: &'static Subject, mut event: impl EventMessage) {
-    if let Some(stream) = NATS_MESSAGE_STREAM.try_get() {
+    if let Some(sinks) = EVENT_SINKS.try_get() {
         // TODO (CM): Yeah... this is looking pretty gross. The
         // intention is to be able to timestamp the events right as
         // they go out.
@@ -225,70 +431,108 @@ fn publish(subject: &'static Subject, mut event: impl EventMessage) {
                                                  Some(std::time::SystemTime::now().into()),
                                              ..EVENT_CORE.get().to_event_metadata() });
 
-        let packet = NatsMessage::new(subject, event.to_bytes());
-        stream.send(packet);
+        let event = Event::new(subject, &event);
+        for sink in sinks {
+            sink.send(&event);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{nats_message_stream::NatsMessageStream,
+    use super::{nats_message_stream::{NatsMessage,
+                                      NatsMessageStream},
                 *};
-    use crate::prost::Message;
-    use futures::{channel::mpsc as futures_mpsc,
-                  stream::StreamExt};
+    use crate::{census::CensusRing,
+                manager::service::tests::initialize_test_service,
+                prost::Message,
+                util::pkg};
+    use biome_butterfly::{member::MemberList,
+                            rumor::RumorStore};
     #[cfg(windows)]
     use biome_core::os::process::windows_child::ExitStatus;
-    use biome_core::service::HealthCheckInterval;
+    use biome_core::{crypto::keys::KeyCache,
+                       fs::CACHE_KEY_PATH,
+                       service::HealthCheckInterval};
+    use futures::channel::mpsc as futures_mpsc;
     #[cfg(unix)]
     use std::{os::unix::process::ExitStatusExt,
               process::ExitStatus};
+    use std::{ptr,
+              sync::{Mutex,
+                     PoisonError}};
 
-    #[tokio::test]
+    lazy_static! {
+        // The sinks can only be set once per process, so every test
+        // shares this one channel.
+        static ref CAPTURED: Mutex<futures_mpsc::UnboundedReceiver<NatsMessage>> = {
+            let (tx, rx) = futures_mpsc::unbounded();
+            EVENT_SINKS.set(vec![Box::new(NatsMessageStream(tx))]);
+            EVENT_CORE.set(EventCore { supervisor_id: String::from("supervisor_id"),
+                                       ip_address:    "127.0.0.1:8080".parse().unwrap(),
+                                       fqdn:          String::from("fqdn"),
+                                       application:   String::from("application"),
+                                       environment:   String::from("environment"),
+                                       site:          None,
+                                       meta:          EventStreamMetadata::default(), });
+            Mutex::new(rx)
+        };
+    }
+
+    /// Run `f`, returning the messages it sent under `subject`. Other
+    /// tests may send events of their own meanwhile, so anything under
+    /// another subject is dropped.
+    fn capture(subject: &'static Subject, f: impl FnOnce()) -> Vec<NatsMessage> {
+        let mut rx = CAPTURED.lock().unwrap_or_else(PoisonError::into_inner);
+        while let Ok(Some(_)) = rx.try_next() {}
+        f();
+        let mut messages = Vec::new();
+        while let Ok(Some(message)) = rx.try_next() {
+            if ptr::eq(message.subject(), subject) {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    #[test]
     #[cfg(any(unix, windows))]
-    async fn health_check_event() {
-        let (tx, rx) = futures_mpsc::unbounded();
-        NATS_MESSAGE_STREAM.set(NatsMessageStream(tx));
-        EVENT_CORE.set(EventCore { supervisor_id: String::from("supervisor_id"),
-                                   ip_address:    "127.0.0.1:8080".parse().unwrap(),
-                                   fqdn:          String::from("fqdn"),
-                                   application:   String::from("application"),
-                                   environment:   String::from("environment"),
-                                   site:          None,
-                                   meta:          EventStreamMetadata::default(), });
-        health_check(ServiceMetadata::default(),
-                     HealthCheckResult::Ok,
-                     HealthCheckHookStatus::NoHook,
-                     HealthCheckInterval::default());
-        health_check(ServiceMetadata::default(),
-                     HealthCheckResult::Warning,
-                     HealthCheckHookStatus::FailedToRun(Duration::from_secs(5)),
-                     HealthCheckInterval::default());
-        #[cfg(windows)]
-        let exit_status = ExitStatus::from(2);
-        #[cfg(unix)]
-        let exit_status = ExitStatus::from_raw(2);
-        let process_output =
-            ProcessOutput::from_raw(StandardStreams { stdout: Some(String::from("stdout")),
-                                                      stderr: Some(String::from("stderr")), },
-                                    exit_status);
-        health_check(ServiceMetadata::default(),
-                     HealthCheckResult::Critical,
-                     HealthCheckHookStatus::Ran(process_output, Duration::from_secs(10)),
-                     HealthCheckInterval::default());
-        #[cfg(windows)]
-        let exit_status = ExitStatus::from(3);
-        #[cfg(unix)]
-        let exit_status = ExitStatus::from_raw(3);
-        let process_output =
-            ProcessOutput::from_raw(StandardStreams { stdout: None,
-                                                      stderr: Some(String::from("stderr")), },
-                                    exit_status);
-        health_check(ServiceMetadata::default(),
-                     HealthCheckResult::Unknown,
-                     HealthCheckHookStatus::Ran(process_output, Duration::from_secs(15)),
-                     HealthCheckInterval::default());
-        let events = rx.take(4).collect::<Vec<_>>().await;
+    fn health_check_event() {
+        let events = capture(&HEALTHCHECK_SUBJECT, || {
+            health_check(ServiceMetadata::default(),
+                         HealthCheckResult::Ok,
+                         HealthCheckHookStatus::NoHook,
+                         HealthCheckInterval::default());
+            health_check(ServiceMetadata::default(),
+                         HealthCheckResult::Warning,
+                         HealthCheckHookStatus::FailedToRun(Duration::from_secs(5)),
+                         HealthCheckInterval::default());
+            #[cfg(windows)]
+            let exit_status = ExitStatus::from(2);
+            #[cfg(unix)]
+            let exit_status = ExitStatus::from_raw(2);
+            let process_output =
+                ProcessOutput::from_raw(StandardStreams { stdout: Some(String::from("stdout")),
+                                                          stderr: Some(String::from("stderr")), },
+                                        exit_status);
+            health_check(ServiceMetadata::default(),
+                         HealthCheckResult::Critical,
+                         HealthCheckHookStatus::Ran(process_output, Duration::from_secs(10)),
+                         HealthCheckInterval::default());
+            #[cfg(windows)]
+            let exit_status = ExitStatus::from(3);
+            #[cfg(unix)]
+            let exit_status = ExitStatus::from_raw(3);
+            let process_output =
+                ProcessOutput::from_raw(StandardStreams { stdout: None,
+                                                          stderr: Some(String::from("stderr")), },
+                                        exit_status);
+            health_check(ServiceMetadata::default(),
+                         HealthCheckResult::Unknown,
+                         HealthCheckHookStatus::Ran(process_output, Duration::from_secs(15)),
+                         HealthCheckInterval::default());
+        });
+        assert_eq!(events.len(), 4);
 
         let event = HealthCheckEvent::decode(events[0].payload()).unwrap();
         assert_eq!(event.result, 0);
@@ -332,4 +576,139 @@ mod tests {
         assert_eq!(event.stdout, None);
         assert_eq!(event.stderr, Some(String::from("stderr")));
     }
+
+    #[test]
+    fn census_health_transition_events() {
+        let member = Member { address: String::from("10.0.0.1"),
+                              gossip_port: 9638,
+                              ..Member::default() };
+        let member_list = MemberList::new();
+        let mut ring = CensusRing::new("supervisor_id");
+        let mut update = |health| {
+            member_list.insert_mlw(member.clone(), health);
+            ring.update_from_rumors_rsr_mlr(&KeyCache::new(&*CACHE_KEY_PATH),
+                                            &RumorStore::default(),
+                                            &RumorStore::default(),
+                                            &RumorStore::default(),
+                                            &member_list,
+                                            &RumorStore::default(),
+                                            &RumorStore::default());
+        };
+        let events = capture(&MEMBER_HEALTH_SUBJECT, || {
+            // A member's first health is not a transition
+            update(Health::Alive);
+            update(Health::Suspect);
+            update(Health::Confirmed);
+        });
+        assert_eq!(events.len(), 2);
+
+        let event = MemberHealthEvent::decode(events[0].payload()).unwrap();
+        assert_eq!(event.member_id, member.id);
+        assert_eq!(event.address, "10.0.0.1:9638");
+        assert_eq!(event.previous_health, types::MemberHealth::Alive as i32);
+        assert_eq!(event.health, types::MemberHealth::Suspect as i32);
+
+        let event = MemberHealthEvent::decode(events[1].payload()).unwrap();
+        assert_eq!(event.previous_health, types::MemberHealth::Suspect as i32);
+        assert_eq!(event.health, types::MemberHealth::Confirmed as i32);
+    }
+
+    #[tokio::test]
+    async fn election_events() {
+        let service = initialize_test_service().await;
+        let package_ident = service.pkg.ident.to_string();
+
+        let events = capture(&ELECTION_WON_SUBJECT, || election_won(&service));
+        assert_eq!(events.len(), 1);
+        let event = ElectionWonEvent::decode(events[0].payload()).unwrap();
+        assert_eq!(event.service_metadata.unwrap().package_ident, package_ident);
+
+        let events = capture(&ELECTION_LOST_SUBJECT, || {
+            election_lost(&service, "leader_id")
+        });
+        assert_eq!(events.len(), 1);
+        let event = ElectionLostEvent::decode(events[0].payload()).unwrap();
+        assert_eq!(event.service_metadata.unwrap().package_ident, package_ident);
+        assert_eq!(event.leader_id, "leader_id");
+    }
+
+    #[test]
+    fn hook_failed_events() {
+        let metadata = ServiceMetadata { package_ident: String::from("core/redis"),
+                                         ..ServiceMetadata::default() };
+        // The two ways `run_hook` reports a failure: a hook which
+        // exited unsuccessfully, and one which couldn't be run at all
+        let events = capture(&HOOK_FAILED_SUBJECT, || {
+            hook_failed(&metadata, "run", Some(1), None);
+            hook_failed(&metadata,
+                        "health-check",
+                        None,
+                        Some(String::from("timed out")));
+        });
+        assert_eq!(events.len(), 2);
+
+        let event = HookFailedEvent::decode(events[0].payload()).unwrap();
+        assert_eq!(event.service_metadata, Some(metadata.clone()));
+        assert_eq!(event.hook, "run");
+        assert_eq!(event.exit_status, Some(1));
+        assert_eq!(event.error, None);
+
+        let event = HookFailedEvent::decode(events[1].payload()).unwrap();
+        assert_eq!(event.service_metadata, Some(metadata));
+        assert_eq!(event.hook, "health-check");
+        assert_eq!(event.exit_status, None);
+        assert_eq!(event.error, Some(String::from("timed out")));
+    }
+
+    #[test]
+    fn package_events() {
+        let installed: Vec<PackageIdent> = vec!["core/redis/4.0.14/20190319155852".parse()
+                                                                                  .unwrap(),
+                                                "core/glibc/2.27/20190115002733".parse().unwrap()];
+        let uninstalled: Vec<PackageIdent> =
+            vec!["core/redis/4.0.10/20180801003001".parse().unwrap()];
+
+        let events = capture(&PACKAGE_INSTALLED_SUBJECT, || {
+            pkg::send_package_events(&installed, &uninstalled)
+        });
+        let idents = events.iter()
+                           .map(|e| {
+                               PackageInstalledEvent::decode(e.payload()).unwrap()
+                                                                         .package_ident
+                           })
+                           .collect::<Vec<_>>();
+        assert_eq!(idents,
+                   vec!["core/redis/4.0.14/20190319155852",
+                        "core/glibc/2.27/20190115002733"]);
+
+        let events = capture(&PACKAGE_UNINSTALLED_SUBJECT, || {
+            pkg::send_package_events(&installed, &uninstalled)
+        });
+        let idents = events.iter()
+                           .map(|e| {
+                               PackageUninstalledEvent::decode(e.payload()).unwrap()
+                                                                           .package_ident
+                           })
+                           .collect::<Vec<_>>();
+        assert_eq!(idents, vec!["core/redis/4.0.10/20180801003001"]);
+    }
+
+    #[test]
+    fn lifecycle_event_enums_serialize_to_json_by_name() {
+        let alive = types::MemberHealth::from(Health::Alive);
+        let suspect = types::MemberHealth::from(Health::Suspect);
+        let event = MemberHealthEvent { event_metadata:  None,
+                                        member_id:       String::from("member_id"),
+                                        address:         String::from("10.0.0.1:9638"),
+                                        previous_health: alive.into(),
+                                        health:          suspect.into(), };
+        let json = event.to_json();
+        assert_eq!(json["previous_health"], "Alive");
+        assert_eq!(json["health"], "Suspect");
+
+        let departed = types::ShutdownReason::from(ShutdownMode::Departed);
+        let event = SupervisorStoppedEvent { event_metadata: None,
+                                             reason:         departed.into(), };
+        assert_eq!(event.to_json()["reason"], "Departed");
+    }
 }
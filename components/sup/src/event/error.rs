@@ -4,6 +4,7 @@ use rants::{error::Error as RantsError,
             native_tls};
 use std::{error,
           fmt,
+          io,
           result};
 
 pub type Result<T> = result::Result<T, Error>;
@@ -12,8 +13,10 @@ pub type Result<T> = result::Result<T, Error>;
 pub enum Error {
     ConnectNatsServer,
     BiomeHttpClient(biome_http_client::Error),
+    Io(io::Error),
     NativeTls(native_tls::Error),
     Rants(RantsError),
+    UnsupportedEventSink(String),
 }
 
 // TODO (CM): I would have like to have derived Fail on our Error
@@ -29,8 +32,13 @@ impl fmt::Display for Error {
         match self {
             Error::ConnectNatsServer => "Could not establish connection to NATS server".fmt(f),
             Error::BiomeHttpClient(_) => "{}".fmt(f),
+            Error::Io(e) => format!("{}", e).fmt(f),
             Error::NativeTls(e) => format!("{}", e).fmt(f),
             Error::Rants(e) => format!("{}", e).fmt(f),
+            Error::UnsupportedEventSink(sink) => {
+                format!("The event sink '{}' is not supported on this platform",
+                        sink).fmt(f)
+            }
         }
     }
 }
@@ -40,8 +48,10 @@ impl error::Error for Error {
         match self {
             Error::ConnectNatsServer => None,
             Error::BiomeHttpClient(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
             Error::Rants(ref e) => Some(e),
             Error::NativeTls(ref e) => Some(e),
+            Error::UnsupportedEventSink(_) => None,
         }
     }
 }
@@ -50,6 +60,10 @@ impl From<biome_http_client::Error> for Error {
     fn from(error: biome_http_client::Error) -> Self { Error::BiomeHttpClient(error) }
 }
 
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self { Error::Io(error) }
+}
+
 impl From<RantsError> for Error {
     fn from(error: RantsError) -> Self { Error::Rants(error) }
 }
@@ -1,5 +1,7 @@
-use crate::event::{Error,
-                   EventStreamConfig,
+use crate::event::{sink::{Event,
+                          EventSink},
+                   Error,
+                   NatsConfig,
                    Result};
 use futures::{channel::{mpsc as futures_mpsc,
                         mpsc::UnboundedSender},
@@ -22,6 +24,8 @@ impl NatsMessage {
         NatsMessage { subject, payload }
     }
 
+    pub fn subject(&self) -> &'static Subject { self.subject }
+
     pub fn payload(&self) -> &[u8] { self.payload.as_slice() }
 }
 
@@ -33,12 +37,11 @@ impl NatsMessage {
 pub struct NatsMessageStream(pub(super) UnboundedSender<NatsMessage>);
 
 impl NatsMessageStream {
-    pub async fn new(supervisor_id: &str, config: EventStreamConfig) -> Result<NatsMessageStream> {
-        let EventStreamConfig { url,
-                                token,
-                                connect_method,
-                                server_certificate,
-                                .. } = config;
+    pub async fn new(supervisor_id: &str, config: NatsConfig) -> Result<NatsMessageStream> {
+        let NatsConfig { url,
+                         token,
+                         connect_method,
+                         server_certificate, } = config;
 
         let mut client = Client::new(vec![url]);
 
@@ -78,7 +81,7 @@ impl NatsMessageStream {
         // Spawn a task to handle publishing received messages
         tokio::spawn(async move {
             while let Some(packet) = rx.next().await {
-                if let Err(e) = client.publish(packet.subject, packet.payload()).await {
+                if let Err(e) = client.publish(packet.subject(), packet.payload()).await {
                     // We do not retry any messages. If we are not connected when the message is
                     // processed or there is an error in publishing the message, the message will
                     // never be sent.
@@ -98,10 +101,16 @@ impl NatsMessageStream {
     }
 
     /// Queues a NATS message to be published
-    pub fn send(&self, event_packet: NatsMessage) {
+    pub fn queue(&self, event_packet: NatsMessage) {
         trace!("Queueing message: {:?}", event_packet);
         if let Err(e) = self.0.unbounded_send(event_packet) {
             error!("Failed to queue message, err: {}", e);
         }
     }
 }
+
+impl EventSink for NatsMessageStream {
+    fn send(&self, event: &Event) {
+        self.queue(NatsMessage::new(event.subject(), event.payload().to_vec()));
+    }
+}
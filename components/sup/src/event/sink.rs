@@ -0,0 +1,120 @@
+//! The interface every destination for events implements, along with
+//! the destinations other than NATS.
+//!
+//! Each sink hands events off to a task of its own, so that sending
+//! an event never blocks the part of the Supervisor it came from.
+//! Events are not persisted: a sink which cannot deliver an event
+//! logs the failure and drops it.
+
+mod json_lines;
+mod syslog;
+mod webhook;
+
+use super::{types::EventMessage,
+            Result};
+use biome_common::types::EventSinkAddress;
+use futures::{channel::mpsc::{self as futures_mpsc,
+                              UnboundedReceiver,
+                              UnboundedSender},
+              Future};
+use rants::Subject;
+
+/// An event on its way to each sink: encoded as protobuf for NATS,
+/// and as JSON for everything else.
+#[derive(Debug)]
+pub struct Event {
+    subject: &'static Subject,
+    payload: Vec<u8>,
+    json:    serde_json::Value,
+}
+
+impl Event {
+    pub fn new(subject: &'static Subject, message: &impl EventMessage) -> Self {
+        Event { subject,
+                payload: message.to_bytes(),
+                json: json!({
+                    "subject": subject.to_string(),
+                    "event": message.to_json(),
+                }) }
+    }
+
+    pub fn subject(&self) -> &'static Subject { self.subject }
+
+    pub fn payload(&self) -> &[u8] { self.payload.as_slice() }
+
+    /// The event as a JSON object, alongside its subject.
+    pub fn json(&self) -> &serde_json::Value { &self.json }
+}
+
+/// Somewhere events are sent.
+pub trait EventSink: Send + Sync {
+    /// Queues an event to be sent
+    fn send(&self, event: &Event);
+}
+
+/// Start sending events to `address`. The Supervisor is identified
+/// to syslog by `hostname`.
+pub async fn start(address: EventSinkAddress, hostname: &str) -> Result<Box<dyn EventSink>> {
+    let name = address.to_string();
+    let sink = match address {
+        EventSinkAddress::File(path) => json_lines::file(name, &path).await?,
+        EventSinkAddress::UnixSocket(path) => json_lines::unix_socket(name, path)?,
+        EventSinkAddress::Webhook(url) => webhook::start(name, &url)?,
+        EventSinkAddress::Syslog(address) => syslog::start(name, address, hostname).await?,
+    };
+    Ok(Box::new(sink))
+}
+
+/// A sink which hands the JSON form of each event to a task that
+/// sends it on.
+pub struct JsonSink {
+    name: String,
+    tx:   UnboundedSender<serde_json::Value>,
+}
+
+impl JsonSink {
+    fn spawn<F, T>(name: String, task: F) -> Self
+        where F: FnOnce(UnboundedReceiver<serde_json::Value>) -> T,
+              T: Future<Output = ()> + Send + 'static
+    {
+        let (tx, rx) = futures_mpsc::unbounded();
+        tokio::spawn(task(rx));
+        JsonSink { name, tx }
+    }
+}
+
+impl EventSink for JsonSink {
+    fn send(&self, event: &Event) {
+        trace!("Queueing event for {}: {:?}", self.name, event);
+        if let Err(e) = self.tx.unbounded_send(event.json().clone()) {
+            error!("Failed to queue event for {}, err: {}", self.name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{types::{EventMetadata,
+                               ServiceStartedEvent},
+                       SERVICE_STARTED_SUBJECT};
+
+    /// A service started event from the Supervisor `supervisor_id`.
+    pub fn event(supervisor_id: &str) -> Event {
+        let metadata = EventMetadata { supervisor_id: supervisor_id.to_string(),
+                                       ..EventMetadata::default() };
+        Event::new(&SERVICE_STARTED_SUBJECT,
+                   &ServiceStartedEvent { event_metadata:   Some(metadata),
+                                          service_metadata: None, })
+    }
+
+    #[test]
+    fn events_carry_their_subject_in_json() {
+        let event = event("sup");
+        assert_eq!(event.json()["subject"], "habitat.event.service_started");
+        assert_eq!(event.json()["event"]["event_metadata"]["supervisor_id"],
+                   "sup");
+        assert_eq!(event.json()["event"]["service_metadata"],
+                   serde_json::Value::Null);
+    }
+}
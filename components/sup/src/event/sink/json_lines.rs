@@ -0,0 +1,141 @@
+//! Newline-delimited JSON, appended to a file or written to a Unix
+//! domain socket.
+
+use super::JsonSink;
+#[cfg(not(unix))]
+use crate::event::Error;
+use crate::event::Result;
+use futures::stream::StreamExt;
+use std::{io,
+          path::{Path,
+                 PathBuf}};
+use tokio::{fs::OpenOptions,
+            io::{AsyncWrite,
+                 AsyncWriteExt}};
+
+/// Append events to the file at `path`, which is created if need be.
+pub async fn file(name: String, path: &Path) -> Result<JsonSink> {
+    let mut file = OpenOptions::new().create(true)
+                                     .append(true)
+                                     .open(path)
+                                     .await?;
+    Ok(JsonSink::spawn(name.clone(), move |mut rx| {
+        async move {
+            while let Some(event) = rx.next().await {
+                if let Err(e) = write_line(&mut file, &event).await {
+                    error!("Failed to write event to {}, err: {}", name, e);
+                }
+            }
+        }
+    }))
+}
+
+/// Write events to the Unix domain socket at `path`.
+///
+/// Whatever is listening on the socket may come and go, so it is
+/// connected to (again) when there is an event to send. Events sent
+/// while it cannot be connected to are dropped.
+#[cfg(unix)]
+pub fn unix_socket(name: String, path: PathBuf) -> Result<JsonSink> {
+    use tokio::net::UnixStream;
+
+    Ok(JsonSink::spawn(name.clone(), move |mut rx| {
+        async move {
+            let mut stream = None;
+            while let Some(event) = rx.next().await {
+                if stream.is_none() {
+                    match UnixStream::connect(&path).await {
+                        Ok(connected) => stream = Some(connected),
+                        Err(e) => {
+                            trace!("Failed to send event to {} because it could not be connected \
+                                    to, err: {}",
+                                   name,
+                                   e);
+                            continue;
+                        }
+                    }
+                }
+                if let Some(connected) = stream.as_mut() {
+                    if let Err(e) = write_line(connected, &event).await {
+                        error!("Failed to send event to {}, err: {}", name, e);
+                        stream = None;
+                    }
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+pub fn unix_socket(name: String, _path: PathBuf) -> Result<JsonSink> {
+    Err(Error::UnsupportedEventSink(name))
+}
+
+async fn write_line<W>(writer: &mut W, event: &serde_json::Value) -> io::Result<()>
+    where W: AsyncWrite + Unpin
+{
+    let mut line = event.to_string().into_bytes();
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::{tests::event,
+                        EventSink},
+                *};
+    use std::{fs,
+              time::Duration};
+    use tempfile::TempDir;
+    use tokio::{io::{AsyncBufReadExt,
+                     BufReader},
+                time};
+
+    #[tokio::test]
+    async fn events_are_appended_to_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.ndjson");
+        fs::write(&path, "{}\n").unwrap();
+
+        let sink = file(String::from("file"), &path).await.unwrap();
+        sink.send(&event("one"));
+        sink.send(&event("two"));
+
+        let mut lines = Vec::new();
+        for _ in 0..50 {
+            lines = fs::read_to_string(&path).unwrap()
+                                             .lines()
+                                             .filter_map(|line| serde_json::from_str(line).ok())
+                                             .collect::<Vec<serde_json::Value>>();
+            if lines.len() == 3 {
+                break;
+            }
+            time::delay_for(Duration::from_millis(20)).await;
+        }
+        assert_eq!(lines.len(), 3, "events are appended");
+        assert_eq!(lines[1]["event"]["event_metadata"]["supervisor_id"], "one");
+        assert_eq!(lines[2]["event"]["event_metadata"]["supervisor_id"], "two");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn events_are_written_to_unix_sockets() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.sock");
+        let mut listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let sink = unix_socket(String::from("unix"), path).unwrap();
+        sink.send(&event("one"));
+        sink.send(&event("two"));
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        for supervisor_id in &["one", "two"] {
+            let line = lines.next_line().await.unwrap().unwrap();
+            let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(event["event"]["event_metadata"]["supervisor_id"],
+                       *supervisor_id);
+        }
+    }
+}
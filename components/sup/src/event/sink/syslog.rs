@@ -0,0 +1,116 @@
+//! Events as RFC 5424 syslog messages, sent over UDP to a remote
+//! server or to the local syslog daemon's socket.
+//!
+//! Each message carries the JSON form of its event, and the last part
+//! of the event's subject (`service_started`, say) as its MSGID.
+
+use super::JsonSink;
+#[cfg(not(unix))]
+use crate::event::Error;
+use crate::{event::Result,
+            PRODUCT};
+use chrono::{SecondsFormat,
+             Utc};
+use futures::stream::StreamExt;
+use std::{net::SocketAddr,
+          process};
+use tokio::net::UdpSocket;
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
+
+/// The daemon facility, at informational severity.
+const PRIORITY: u8 = 3 * 8 + 6;
+#[cfg(unix)]
+const LOCAL_SOCKET: &str = "/dev/log";
+
+/// Send events to the syslog server at `address`, or to the local
+/// syslog daemon if there is none.
+pub async fn start(name: String, address: Option<SocketAddr>, hostname: &str) -> Result<JsonSink> {
+    let hostname = hostname.to_string();
+    match address {
+        Some(address) => {
+            let bind = if address.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let mut socket = UdpSocket::bind(bind).await?;
+            Ok(JsonSink::spawn(name.clone(), move |mut rx| {
+                async move {
+                    while let Some(event) = rx.next().await {
+                        let message = message(&hostname, &event);
+                        if let Err(e) = socket.send_to(message.as_bytes(), address).await {
+                            error!("Failed to send event to {}, err: {}", name, e);
+                        }
+                    }
+                }
+            }))
+        }
+        None => local(name, hostname),
+    }
+}
+
+#[cfg(unix)]
+fn local(name: String, hostname: String) -> Result<JsonSink> {
+    let mut socket = UnixDatagram::unbound()?;
+    Ok(JsonSink::spawn(name.clone(), move |mut rx| {
+        async move {
+            while let Some(event) = rx.next().await {
+                let message = message(&hostname, &event);
+                if let Err(e) = socket.send_to(message.as_bytes(), LOCAL_SOCKET).await {
+                    error!("Failed to send event to {}, err: {}", name, e);
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn local(name: String, _hostname: String) -> Result<JsonSink> {
+    Err(Error::UnsupportedEventSink(name))
+}
+
+/// An RFC 5424 message carrying `event`.
+fn message(hostname: &str, event: &serde_json::Value) -> String {
+    let msgid = event["subject"].as_str()
+                                .and_then(|subject| subject.rsplit('.').next())
+                                .unwrap_or("-");
+    format!("<{}>1 {} {} {} {} {} - {}",
+            PRIORITY,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            hostname,
+            PRODUCT,
+            process::id(),
+            msgid,
+            event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::{tests::event,
+                        EventSink},
+                *};
+
+    #[tokio::test]
+    async fn events_are_sent_to_syslog_servers() {
+        let mut server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+        let sink = start(address.to_string(), Some(address), "sup.example.com").await
+                                                                               .unwrap();
+        sink.send(&event("sup"));
+
+        let mut buffer = [0; 4096];
+        let (len, _) = server.recv_from(&mut buffer).await.unwrap();
+        let message = String::from_utf8_lossy(&buffer[..len]);
+        let fields = message.splitn(8, ' ').collect::<Vec<_>>();
+
+        assert_eq!(fields[0], "<30>1");
+        assert_eq!(fields[2], "sup.example.com");
+        assert_eq!(fields[3], "bio-sup");
+        assert_eq!(fields[4], process::id().to_string());
+        assert_eq!(fields[5], "service_started");
+        assert_eq!(fields[6], "-");
+        let json: serde_json::Value = serde_json::from_str(fields[7]).unwrap();
+        assert_eq!(json["event"]["event_metadata"]["supervisor_id"], "sup");
+    }
+}
@@ -0,0 +1,147 @@
+//! Batches of JSON events, POSTed to a URL.
+//!
+//! Events are gathered for up to `BATCH_WAIT`, or until there are
+//! `BATCH_SIZE` of them, and POSTed as a JSON array. A batch which
+//! cannot be delivered is retried with exponential backoff, and
+//! dropped after `MAX_ATTEMPTS` attempts.
+
+use super::JsonSink;
+use crate::{event::Result,
+            PRODUCT,
+            VERSION};
+use biome_http_client::ApiClient;
+use futures::stream::StreamExt;
+use std::time::Duration;
+use tokio::time::{self,
+                  Instant};
+
+const BATCH_SIZE: usize = 100;
+const BATCH_WAIT: Duration = Duration::from_secs(1);
+const MAX_ATTEMPTS: u32 = 5;
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// POST events to `url`.
+pub fn start(name: String, url: &str) -> Result<JsonSink> {
+    let client = ApiClient::new(url, PRODUCT, VERSION, None)?;
+    Ok(JsonSink::spawn(name.clone(), move |mut rx| {
+        async move {
+            while let Some(event) = rx.next().await {
+                let mut batch = vec![event];
+                let deadline = Instant::now() + BATCH_WAIT;
+                while batch.len() < BATCH_SIZE {
+                    match time::timeout_at(deadline, rx.next()).await {
+                        Ok(Some(event)) => batch.push(event),
+                        _ => break,
+                    }
+                }
+                post(&name, &client, &batch).await;
+            }
+        }
+    }))
+}
+
+async fn post(name: &str, client: &ApiClient, batch: &[serde_json::Value]) {
+    let body = serde_json::Value::from(batch).to_string();
+    let mut delay = FIRST_RETRY_DELAY;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = client.post("")
+                           .header("Content-Type", "application/json")
+                           .body(body.clone())
+                           .send()
+                           .await;
+        match result {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => {
+                warn!("Failed to send {} events to {} (attempt {} of {}), status: {}",
+                      batch.len(),
+                      name,
+                      attempt,
+                      MAX_ATTEMPTS,
+                      response.status())
+            }
+            Err(e) => {
+                warn!("Failed to send {} events to {} (attempt {} of {}), err: {}",
+                      batch.len(),
+                      name,
+                      attempt,
+                      MAX_ATTEMPTS,
+                      e)
+            }
+        }
+        if attempt < MAX_ATTEMPTS {
+            time::delay_for(delay).await;
+            delay *= 2;
+        }
+    }
+    error!("Dropping {} events which could not be sent to {}",
+           batch.len(),
+           name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::{tests::event,
+                        EventSink},
+                *};
+    use futures::channel::mpsc as futures_mpsc;
+    use hyper::{service::{make_service_fn,
+                          service_fn},
+                Body,
+                Request,
+                Response,
+                Server,
+                StatusCode};
+    use std::{convert::Infallible,
+              sync::{atomic::{AtomicUsize,
+                              Ordering},
+                     Arc}};
+
+    type Requests = futures_mpsc::UnboundedSender<(StatusCode, hyper::body::Bytes)>;
+
+    /// Record each request, failing the first so that it is retried.
+    async fn handle(request: Request<Body>,
+                    count: Arc<AtomicUsize>,
+                    tx: Requests)
+                    -> std::result::Result<Response<Body>, hyper::Error> {
+        let status = if count.fetch_add(1, Ordering::SeqCst) == 0 {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        };
+        let body = hyper::body::to_bytes(request.into_body()).await?;
+        tx.unbounded_send((status, body)).unwrap();
+        Ok(Response::builder().status(status)
+                              .body(Body::empty())
+                              .unwrap())
+    }
+
+    #[tokio::test]
+    async fn batches_of_events_are_retried_until_delivered() {
+        let (tx, mut rx) = futures_mpsc::unbounded();
+        let count = Arc::new(AtomicUsize::new(0));
+        let make_service = make_service_fn(move |_| {
+            let (count, tx) = (Arc::clone(&count), tx.clone());
+            let service =
+                service_fn(move |request| handle(request, Arc::clone(&count), tx.clone()));
+            async move { Ok::<_, Infallible>(service) }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let url = format!("http://{}/events", server.local_addr());
+        tokio::spawn(server);
+
+        let sink = start(url.clone(), &url).unwrap();
+        sink.send(&event("one"));
+        sink.send(&event("two"));
+
+        let (status, failed) = rx.next().await.unwrap();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, delivered) = rx.next().await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(failed, delivered, "the same batch is retried");
+
+        let batch: Vec<serde_json::Value> = serde_json::from_slice(&delivered).unwrap();
+        assert_eq!(batch.len(), 2, "events are batched");
+        assert_eq!(batch[0]["event"]["event_metadata"]["supervisor_id"], "one");
+        assert_eq!(batch[1]["event"]["event_metadata"]["supervisor_id"], "two");
+    }
+}
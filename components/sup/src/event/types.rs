@@ -2,10 +2,21 @@
 //! Supervisor.
 
 use super::EventCore;
-use crate::manager::service::{HealthCheckResult as DomainHealthCheckResult,
-                              Service,
-                              UpdateStrategy as DomainUpdateStrategy};
+use crate::manager::{service::{HealthCheckResult as DomainHealthCheckResult,
+                               Service,
+                               ServiceSpec,
+                               UpdateStrategy as DomainUpdateStrategy},
+                     ShutdownMode};
+use biome_butterfly::member::Health;
+use biome_core::{package::PackageIdent,
+                 service::ServiceGroup,
+                 ChannelIdent};
+use chrono::{SecondsFormat,
+             TimeZone,
+             Utc};
 use prost::Message;
+use serde::{Serialize,
+            Serializer};
 
 include!(concat!(env!("OUT_DIR"), "/chef.habitat.supervisor.event.rs"));
 
@@ -23,6 +34,27 @@ impl Into<HealthCheckResult> for DomainHealthCheckResult {
     }
 }
 
+impl From<Health> for MemberHealth {
+    fn from(health: Health) -> Self {
+        match health {
+            Health::Alive => MemberHealth::Alive,
+            Health::Suspect => MemberHealth::Suspect,
+            Health::Confirmed => MemberHealth::Confirmed,
+            Health::Departed => MemberHealth::Departed,
+        }
+    }
+}
+
+impl From<ShutdownMode> for ShutdownReason {
+    fn from(mode: ShutdownMode) -> Self {
+        match mode {
+            ShutdownMode::Normal => ShutdownReason::Normal,
+            ShutdownMode::Departed => ShutdownReason::Departed,
+            ShutdownMode::Restarting => ShutdownReason::Restarting,
+        }
+    }
+}
+
 impl Service {
     /// Create a protobuf metadata struct for Service-related event
     /// messages.
@@ -37,26 +69,48 @@ impl Service {
                           update_config: self.update_config(), }
     }
 
-    /// `UpdateConfig` is a (currently protobuf-only) type that
-    /// encapsulates a channel and update strategy. Importantly, the
-    /// existing `UpdateStrategy::None` variant is essentially
-    /// converted to `Option::None`, whereas the other variants are
-    /// coupled with the channel from which the Supervisor pulls
-    /// updates.
     fn update_config(&self) -> Option<UpdateConfig> {
-        let strategy = match self.update_strategy() {
-            DomainUpdateStrategy::None => {
-                return None;
-            }
-            DomainUpdateStrategy::AtOnce => UpdateStrategy::AtOnce,
-            DomainUpdateStrategy::Rolling => UpdateStrategy::Rolling,
-        };
+        update_config(self.update_strategy(), &self.channel())
+    }
+}
 
-        Some(UpdateConfig { strategy: strategy.into(),
-                            channel:  self.channel().to_string(), })
+impl ServiceSpec {
+    /// Create a protobuf metadata struct for events about a service
+    /// which may not be running, such as one being loaded or
+    /// unloaded.
+    pub fn to_service_metadata(&self,
+                               package_ident: &PackageIdent,
+                               organization: Option<&str>)
+                               -> ServiceMetadata {
+        let service_group = ServiceGroup::new(&self.ident.name, &self.group, organization)
+            .map(|service_group| service_group.to_string())
+            .unwrap_or_default();
+        ServiceMetadata { package_ident: package_ident.to_string(),
+                          spec_ident: self.ident.to_string(),
+                          service_group,
+                          update_config: update_config(self.update_strategy, &self.channel) }
     }
 }
 
+/// `UpdateConfig` is a (currently protobuf-only) type that
+/// encapsulates a channel and update strategy. Importantly, the
+/// existing `UpdateStrategy::None` variant is essentially
+/// converted to `Option::None`, whereas the other variants are
+/// coupled with the channel from which the Supervisor pulls
+/// updates.
+fn update_config(strategy: DomainUpdateStrategy, channel: &ChannelIdent) -> Option<UpdateConfig> {
+    let strategy = match strategy {
+        DomainUpdateStrategy::None => {
+            return None;
+        }
+        DomainUpdateStrategy::AtOnce => UpdateStrategy::AtOnce,
+        DomainUpdateStrategy::Rolling => UpdateStrategy::Rolling,
+    };
+
+    Some(UpdateConfig { strategy: strategy.into(),
+                        channel:  channel.to_string(), })
+}
+
 impl EventCore {
     /// Create a protobuf metadata struct for all event messages.
     pub(super) fn to_event_metadata(&self) -> EventMetadata {
@@ -72,12 +126,18 @@ impl EventCore {
     }
 }
 
-pub trait EventMessage: Message + Sized {
+pub trait EventMessage: Message + Serialize + Sized {
     /// All messages will have some top-level metadata about the
     /// Supervisor they come from. This function allows us to set it
     /// generically when we send the message out.
     fn event_metadata(&mut self, event_metadata: EventMetadata);
 
+    /// Convert a message to JSON for sending to other sinks.
+    fn to_json(&self) -> serde_json::Value {
+        // Every field serializes to JSON, so this cannot fail.
+        serde_json::to_value(self).expect("event serializes to JSON")
+    }
+
     /// Convert a message to bytes for sending to NATS.
     fn to_bytes(&self) -> Vec<u8> {
         let mut buf = bytes::BytesMut::with_capacity(self.encoded_len());
@@ -90,6 +150,60 @@ pub trait EventMessage: Message + Sized {
     }
 }
 
+// The `serialize_with` functions the generated types name for their
+// well-known types and enums; see `build.rs`.
+
+fn serialize_timestamp<S>(timestamp: &Option<prost_types::Timestamp>,
+                          serializer: S)
+                          -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    match timestamp {
+        Some(timestamp) => {
+            let timestamp = Utc.timestamp(timestamp.seconds, timestamp.nanos as u32);
+            serializer.serialize_str(&timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_duration<S>(duration: &Option<prost_types::Duration>,
+                         serializer: S)
+                         -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    match duration {
+        Some(duration) if duration.nanos == 0 => {
+            serializer.serialize_str(&format!("{}s", duration.seconds))
+        }
+        Some(duration) => {
+            serializer.serialize_str(&format!("{}.{:09}s", duration.seconds, duration.nanos))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Generates a `serialize_with` function which renders a field
+/// holding an enum by the name of its variant, or by its number if it
+/// is not one this version of the Supervisor knows.
+macro_rules! serialize_enum {
+    ($name:ident, $t:ty) => {
+        fn $name<S>(value: &i32, serializer: S) -> Result<S::Ok, S::Error>
+            where S: Serializer
+        {
+            match <$t>::from_i32(*value) {
+                Some(variant) => serializer.serialize_str(&format!("{:?}", variant)),
+                None => serializer.serialize_i32(*value),
+            }
+        }
+    };
+}
+
+serialize_enum!(serialize_health_check_result, HealthCheckResult);
+serialize_enum!(serialize_update_strategy, UpdateStrategy);
+serialize_enum!(serialize_member_health, MemberHealth);
+serialize_enum!(serialize_shutdown_reason, ShutdownReason);
+
 macro_rules! event_msg_impl {
     ($t:ty) => {
         impl EventMessage for $t {
@@ -104,3 +218,16 @@ event_msg_impl!(ServiceStartedEvent);
 event_msg_impl!(ServiceStoppedEvent);
 event_msg_impl!(ServiceUpdateStartedEvent);
 event_msg_impl!(HealthCheckEvent);
+event_msg_impl!(ServiceLoadedEvent);
+event_msg_impl!(ServiceUnloadedEvent);
+event_msg_impl!(ServiceConfigAppliedEvent);
+event_msg_impl!(ServiceFileUploadedEvent);
+event_msg_impl!(ElectionStartedEvent);
+event_msg_impl!(ElectionWonEvent);
+event_msg_impl!(ElectionLostEvent);
+event_msg_impl!(MemberHealthEvent);
+event_msg_impl!(HookFailedEvent);
+event_msg_impl!(PackageInstalledEvent);
+event_msg_impl!(PackageUninstalledEvent);
+event_msg_impl!(SupervisorStartedEvent);
+event_msg_impl!(SupervisorStoppedEvent);
@@ -10,7 +10,8 @@ use actix_web::{dev::{Body,
                        StatusCode},
                 web::{self,
                       Data,
-                      Path},
+                      Path,
+                      Query},
                 App,
                 Error,
                 HttpResponse,
@@ -26,7 +27,8 @@ use biome_common::{self,
 use biome_core::{crypto,
                    env as henv,
                    service::ServiceGroup};
-use manager::sync::GatewayState;
+use manager::sync::{GatewayState,
+                    ManagerServices};
 
 use prometheus::{self,
                  CounterVec,
@@ -85,6 +87,13 @@ struct HealthCheckBody {
     stderr: String,
 }
 
+#[derive(Deserialize)]
+struct ConfigQuery {
+    /// List each key of the configuration with the layer that supplied it
+    #[serde(default)]
+    explain: bool,
+}
+
 impl Into<StatusCode> for HealthCheckResult {
     fn into(self) -> StatusCode {
         match self {
@@ -97,6 +106,7 @@ impl Into<StatusCode> for HealthCheckResult {
 
 struct AppState {
     gateway_state:        Arc<GatewayState>,
+    services:             Arc<ManagerServices>,
     authentication_token: Option<String>,
     timer:                Cell<Option<HistogramTimer>>,
     feature_flags:        FeatureFlag,
@@ -104,10 +114,12 @@ struct AppState {
 
 impl AppState {
     fn new(gs: Arc<GatewayState>,
+           services: Arc<ManagerServices>,
            authentication_token: GatewayAuthenticationToken,
            feature_flags: FeatureFlag)
            -> Self {
         AppState { gateway_state: gs,
+                   services,
                    // We'll unwrap to the inner type, since the
                    // GatewayAuthenticationToken type has done its job by this point.
                    authentication_token: authentication_token.0,
@@ -222,6 +234,7 @@ impl Server {
     pub fn run(listen_addr: HttpListenAddr,
                tls_config: Option<ServerConfig>,
                gateway_state: Arc<GatewayState>,
+               services: Arc<ManagerServices>,
                authentication_token: GatewayAuthenticationToken,
                feature_flags: FeatureFlag,
                control: Arc<(Mutex<ServerStartup>, Condvar)>) {
@@ -241,6 +254,7 @@ impl Server {
             let mut server = HttpServer::new(move || {
                                  let app_state =
                                      Data::new(AppState::new(gateway_state.clone(),
+                                                             services.clone(),
                                                              authentication_token.clone(),
                                                              feature_flags));
                                  App::new().app_data(app_state)
@@ -283,12 +297,12 @@ fn services_routes() -> Scope {
     web::scope("/services").route("", web::get().to(services_gsr))
                            .route("/{svc}/{group}", web::get().to(service_without_org_gsr))
                            .route("/{svc}/{group}/config",
-                                  web::get().to(config_without_org_gsr))
+                                  web::get().to(config_without_org_gsr_msr))
                            .route("/{svc}/{group}/health",
                                   web::get().to(health_without_org_gsr))
                            .route("/{svc}/{group}/{org}", web::get().to(service_with_org_gsr))
                            .route("/{svc}/{group}/{org}/config",
-                                  web::get().to(config_with_org_gsr))
+                                  web::get().to(config_with_org_gsr_msr))
                            .route("/{svc}/{group}/{org}/health",
                                   web::get().to(health_with_org_gsr))
 }
@@ -336,32 +350,48 @@ fn services_gsr(state: Data<AppState>) -> HttpResponse {
 
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
+/// * `ManagerServices::inner` (read)
 // Honestly, this doesn't feel great, but it's the pattern builder-api uses, and at the
 // moment, I don't have a better way of doing it.
 #[allow(clippy::needless_pass_by_value)]
-fn config_with_org_gsr(path: Path<(String, String, String)>,
-                       state: Data<AppState>)
-                       -> HttpResponse {
+fn config_with_org_gsr_msr(path: Path<(String, String, String)>,
+                           query: Query<ConfigQuery>,
+                           state: Data<AppState>)
+                           -> HttpResponse {
     let (svc, group, org) = path.into_inner();
-    config_gsr(svc, group, Some(&org), &state)
+    config_gsr_msr(svc, group, Some(&org), query.explain, &state)
 }
 
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
+/// * `ManagerServices::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
-fn config_without_org_gsr(path: Path<(String, String)>, state: Data<AppState>) -> HttpResponse {
+fn config_without_org_gsr_msr(path: Path<(String, String)>,
+                              query: Query<ConfigQuery>,
+                              state: Data<AppState>)
+                              -> HttpResponse {
     let (svc, group) = path.into_inner();
-    config_gsr(svc, group, None, &state)
+    config_gsr_msr(svc, group, None, query.explain, &state)
 }
 
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
-fn config_gsr(svc: String, group: String, org: Option<&str>, state: &AppState) -> HttpResponse {
+/// * `ManagerServices::inner` (read)
+fn config_gsr_msr(svc: String,
+                  group: String,
+                  org: Option<&str>,
+                  explain: bool,
+                  state: &AppState)
+                  -> HttpResponse {
     let service_group = match ServiceGroup::new(svc, group, org) {
         Ok(sg) => sg,
         Err(_) => return HttpResponse::BadRequest().finish(),
     };
 
+    if explain {
+        return config_explain_msr(&service_group, state);
+    }
+
     match service_from_services(&service_group,
                                 state.gateway_state.lock_gsr().services_data())
     {
@@ -370,6 +400,27 @@ fn config_gsr(svc: String, group: String, org: Option<&str>, state: &AppState) -
     }
 }
 
+/// The provenance of every configuration key is only worked out when it is asked for, rather
+/// than being kept in the gateway state alongside the rest of the service.
+///
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+fn config_explain_msr(service_group: &ServiceGroup, state: &AppState) -> HttpResponse {
+    // Matches the `null` configuration of services rendered while the HTTP API is redacted
+    if state.feature_flags.contains(FeatureFlag::REDACT_HTTP) {
+        return HttpResponse::Ok().json(Json::Null);
+    }
+
+    match state.services
+               .lock_msr()
+               .services()
+               .find(|s| s.service_group == *service_group)
+    {
+        Some(service) => HttpResponse::Ok().json(service.explain_cfg()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
@@ -488,7 +539,19 @@ fn service_from_services(service_group: &ServiceGroup, services_json: &str) -> O
 
 #[cfg(test)]
 mod tests {
-    use crate::test_helpers::*;
+    use super::{routes,
+                AppState,
+                GatewayAuthenticationToken};
+    use crate::{manager::{service::{tests::initialize_test_service,
+                                    ConfigRendering,
+                                    ServiceProxy},
+                          sync::{GatewayState,
+                                 ManagerServices}},
+                test_helpers::*};
+    use actix_web::{test,
+                    web::Data,
+                    App};
+    use biome_common::FeatureFlag;
     use biome_butterfly::{member::Member,
                             server::{Server,
                                      ServerProxy,
@@ -499,7 +562,8 @@ mod tests {
                     Ipv4Addr,
                     SocketAddr},
               path::PathBuf,
-              sync::Mutex};
+              sync::{Arc,
+                     Mutex}};
 
     fn validate_sample_file_against_schema(name: &str, schema: &str) {
         let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
@@ -601,6 +665,66 @@ mod tests {
                                             "http_gateway_services_schema.json");
     }
 
+    /// Request the configuration of a loaded test service from the gateway's routes.
+    async fn get_config(query: &str, feature_flags: FeatureFlag) -> serde_json::Value {
+        let mut service = initialize_test_service().await;
+        let gossip = toml::from_str("port = 6379\npassword = \"hunter2\"").unwrap();
+        service.cfg.set_gossip(1, gossip);
+        let sg = service.service_group.clone();
+        let gateway_state = Arc::new(GatewayState::default());
+        let services_json =
+            serde_json::to_string(&[ServiceProxy::new(&service, ConfigRendering::Full)]).unwrap();
+        gateway_state.lock_gsw().set_services_data(services_json);
+        let services = Arc::new(ManagerServices::default());
+        services.lock_msw().insert(service.pkg.ident.clone(), service);
+
+        let app_state = Data::new(AppState::new(gateway_state,
+                                                services,
+                                                GatewayAuthenticationToken::default(),
+                                                feature_flags));
+        let mut app = test::init_service(App::new().app_data(app_state).service(routes())).await;
+        let path = match sg.org() {
+            Some(org) => format!("/services/{}/{}/{}/config", sg.service(), sg.group(), org),
+            None => format!("/services/{}/{}/config", sg.service(), sg.group()),
+        };
+        let req = test::TestRequest::get().uri(&format!("{}{}", path, query))
+                                          .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert!(resp.status().is_success());
+        serde_json::from_slice(&test::read_body(resp).await).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn config_is_not_explained_by_default() {
+        let cfg = get_config("", FeatureFlag::empty()).await;
+        assert_eq!(cfg["port"], 6379);
+
+        let cfg = get_config("?explain=false", FeatureFlag::empty()).await;
+        assert_eq!(cfg["port"], 6379);
+    }
+
+    #[actix_rt::test]
+    async fn config_is_explained_when_asked_for() {
+        let explained = get_config("?explain=true", FeatureFlag::empty()).await;
+        let keys = explained.as_array().expect("an array of keys");
+        let port = keys.iter()
+                       .find(|k| k["key"] == "port")
+                       .expect("the gossiped port");
+        assert_eq!(port["value"], 6379);
+        assert!(port["layer"].is_string());
+        assert!(port["origin"].is_string());
+        let password = keys.iter()
+                           .find(|k| k["key"] == "password")
+                           .expect("the gossiped password");
+        assert_eq!(password["value"], "<redacted>");
+    }
+
+    #[actix_rt::test]
+    async fn explained_config_is_redacted_with_the_http_api() {
+        let explained = get_config("?explain=true", FeatureFlag::REDACT_HTTP).await;
+        assert!(explained.is_null());
+    }
+
     #[test]
     fn trivial_services_failure() {
         let failure = validate_string(r#"[{"lulz": true}]"#, "http_gateway_services_schema.json");
@@ -16,7 +16,8 @@ use crate::sup::{cli::cli,
                  command,
                  error::{Error,
                          Result},
-                 event::EventStreamConfig,
+                 event::{EventStreamConfig,
+                         NatsConfig},
                  logger,
                  manager::{Manager,
                            ManagerConfig,
@@ -246,24 +247,28 @@ async fn split_apart_sup_run(sup_run: SupRun,
 
     let shared_load = sup_run.shared_load;
 
-    let event_stream_config = if sup_run.event_stream_url.is_some() {
+    let nats = if let Some(url) = sup_run.event_stream_url {
+        Some(NatsConfig { token:
+                              sup_run.event_stream_token
+                                     .expect("Required option for EventStream feature"),
+                          url:                url.into(),
+                          connect_method:     sup_run.event_stream_connect_timeout,
+                          server_certificate: sup_run.event_stream_server_certificate, })
+    } else {
+        None
+    };
+
+    let event_stream_config = if nats.is_some() || !sup_run.event_sink.is_empty() {
         Some(EventStreamConfig { environment:
                                      sup_run.event_stream_environment
                                             .expect("Required option for EventStream feature"),
                                  application:
                                      sup_run.event_stream_application
                                             .expect("Required option for EventStream feature"),
-                                 site:               sup_run.event_stream_site,
-                                 meta:               sup_run.event_meta.into(),
-                                 token:
-                                     sup_run.event_stream_token
-                                            .expect("Required option for EventStream feature"),
-                                 url:
-                                     sup_run.event_stream_url
-                                            .expect("Required option for EventStream feature")
-                                            .into(),
-                                 connect_method:     sup_run.event_stream_connect_timeout,
-                                 server_certificate: sup_run.event_stream_server_certificate, })
+                                 site: sup_run.event_stream_site,
+                                 meta: sup_run.event_meta.into(),
+                                 nats,
+                                 sinks: sup_run.event_sink })
     } else {
         None
     };
@@ -309,6 +314,7 @@ async fn split_apart_sup_run(sup_run: SupRun,
                               feature_flags,
                               event_stream_config,
                               keep_latest_packages: sup_run.keep_latest_packages,
+                              member_labels: sup_run.member_label,
                               sys_ip: sup_run.sys_ip_address
                                              .or_else(|| {
                                                  let result_ip = biome_core::util::sys::ip();
@@ -393,7 +399,8 @@ mod test {
     use bio::cli::bio::sup::Sup;
     use biome_common::types::{GossipListenAddr,
                                 HttpListenAddr,
-                                ListenCtlAddr};
+                                ListenCtlAddr,
+                                MemberLabel};
     use biome_core::{fs::CACHE_KEY_PATH,
                        locked_env_var};
     use biome_sup_protocol::{ctl::ServiceBindList,
@@ -597,6 +604,15 @@ mod test {
             assert_eq!(config.gossip_permanent, false);
         }
 
+        #[test]
+        fn member_labels_should_be_set() {
+            let config =
+                config_from_cmd_str("bio-sup run --member-label rack=r1 --member-label zone=a");
+            let expected: Vec<MemberLabel> =
+                vec!["rack=r1".parse().unwrap(), "zone=a".parse().unwrap()];
+            assert_eq!(config.member_labels, expected);
+        }
+
         #[test]
         fn peers_should_be_set() {
             let config = config_from_cmd_str("bio-sup run --peer 1.1.1.1:1 2.2.2.2:1 3.3.3.3:1");
@@ -734,8 +750,8 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
 
             let maybe_service_load = maybe_service_load_from_cmd_str("bio-sup run");
@@ -809,6 +825,7 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags: FeatureFlag::empty(),
                                        event_stream_config: None,
                                        keep_latest_packages: Some(5),
+                                       member_labels: vec![],
                                        sys_ip: "7.8.9.0".parse().unwrap() },
                        config);
         }
@@ -843,8 +860,8 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
         }
 
@@ -877,8 +894,8 @@ gpoVMSncu2jMIDZX63IkQII=
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
         }
 
@@ -947,16 +964,33 @@ gpoVMSncu2jMIDZX63IkQII=
                                         application: String::from("MY_APP"),
                                         site: Some(String::from("my_site")),
                                         meta: meta.into(),
-                                        token: "some_token".parse().unwrap(),
-                                        url: "127.0.0.1:3456".parse().unwrap(),
-                                        connect_method: EventStreamConnectMethod::Timeout {secs: 5},
-                                        server_certificate: Some(certificate_path_str.parse().unwrap()),
+                                        nats: Some(NatsConfig {
+                                            token: "some_token".parse().unwrap(),
+                                            url: "127.0.0.1:3456".parse().unwrap(),
+                                            connect_method: EventStreamConnectMethod::Timeout {secs: 5},
+                                            server_certificate: Some(certificate_path_str.parse().unwrap()),
+                                        }),
+                                        sinks: vec![],
                                        }),
                                        keep_latest_packages: None,
+                                       member_labels:        vec![],
                                        sys_ip:               biome_core::util::sys::ip().unwrap(), },
                        config,);
         }
 
+        #[test]
+        fn test_bio_sup_run_cli_event_sinks() {
+            let args = "bio-sup run --event-stream-application=MY_APP \
+                        --event-stream-environment=MY_ENV --event-sink file:/tmp/events.json \
+                        --event-sink syslog";
+            let config = config_from_cmd_str(args);
+            let event_stream_config = config.event_stream_config.unwrap();
+            assert_eq!(event_stream_config.nats, None);
+            assert_eq!(event_stream_config.sinks,
+                       vec!["file:/tmp/events.json".parse().unwrap(),
+                            "syslog".parse().unwrap()]);
+        }
+
         #[test]
         fn test_bio_sup_run_cli_svc() {
             let temp_dir = TempDir::new().expect("Could not create tempdir");
@@ -1002,7 +1036,8 @@ gpoVMSncu2jMIDZX63IkQII=
                                                      Some(health_check_interval),
                                                  shutdown_timeout:        Some(12),
                                                  update_condition:
-                                                     Some(UpdateCondition::TrackChannel.into()), },
+                                                     Some(UpdateCondition::TrackChannel.into()),
+                                                 hook_timeouts:           vec![], },
                        service_load);
         }
 
@@ -1127,6 +1162,7 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags: FeatureFlag::empty(),
                                        event_stream_config: None,
                                        keep_latest_packages: Some(5),
+                                       member_labels: vec![],
                                        sys_ip: "7.8.9.0".parse().unwrap() },
                        config);
         }
@@ -1170,8 +1206,8 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
         }
 
@@ -1213,8 +1249,8 @@ sys_ip_address = "7.8.9.0"
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
         }
 
@@ -1320,12 +1356,16 @@ event_stream_server_certificate = "{}"
                                         application: String::from("MY_APP"),
                                         site: Some(String::from("my_site")),
                                         meta: meta.into(),
-                                        token: "some_token".parse().unwrap(),
-                                        url: "127.0.0.1:3456".parse().unwrap(),
-                                        connect_method: EventStreamConnectMethod::Timeout {secs: 5},
-                                        server_certificate: Some(certificate_path_str.parse().unwrap()),
+                                        nats: Some(NatsConfig {
+                                            token: "some_token".parse().unwrap(),
+                                            url: "127.0.0.1:3456".parse().unwrap(),
+                                            connect_method: EventStreamConnectMethod::Timeout {secs: 5},
+                                            server_certificate: Some(certificate_path_str.parse().unwrap()),
+                                        }),
+                                        sinks: vec![],
                                        }),
                                        keep_latest_packages: None,
+                                       member_labels:        vec![],
                                        sys_ip:               biome_core::util::sys::ip().unwrap(), },
                        config,);
         }
@@ -1393,7 +1433,8 @@ pkg_ident_or_artifact = "core/redis"
                                                      Some(health_check_interval),
                                                  shutdown_timeout:        Some(12),
                                                  update_condition:
-                                                     Some(UpdateCondition::TrackChannel.into()), },
+                                                     Some(UpdateCondition::TrackChannel.into()),
+                                                 hook_timeouts:           vec![], },
                        service_load);
         }
 
@@ -1505,8 +1546,8 @@ organization = "MY_ORG_FROM_SECOND_CONFG"
                                        feature_flags:         FeatureFlag::empty(),
                                        event_stream_config:   None,
                                        keep_latest_packages:  None,
-                                       sys_ip:
-                                           biome_core::util::sys::ip().unwrap(), },
+                                       member_labels:         vec![],
+                                       sys_ip:                biome_core::util::sys::ip().unwrap(), },
                        config);
         }
 
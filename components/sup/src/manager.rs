@@ -57,7 +57,8 @@ use biome_common::{liveliness_checker,
                      outputln,
                      types::{GossipListenAddr,
                              HttpListenAddr,
-                             ListenCtlAddr},
+                             ListenCtlAddr,
+                             MemberLabel},
                      FeatureFlag};
 #[cfg(unix)]
 use biome_core::os::{process::{ShutdownSignal,
@@ -195,7 +196,7 @@ impl ServicePidSource {
 
 /// A Supervisor can stop in a handful of ways.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum ShutdownMode {
+pub enum ShutdownMode {
     /// When the Supervisor is shutting down for normal reasons and
     /// should take all services down with it (i.e., it's actually
     /// shutting down).
@@ -280,6 +281,8 @@ pub struct ManagerConfig {
     /// others during service start. If this field is `None`, automatic package cleanup is
     /// disabled.
     pub keep_latest_packages:  Option<usize>,
+    /// Labels describing this Supervisor, which targeted service configs can match.
+    pub member_labels:         Vec<MemberLabel>,
     pub sys_ip:                IpAddr,
 }
 
@@ -643,6 +646,7 @@ impl Manager {
                                cfg.ctl_listen,
                                cfg.http_listen,
                                cfg.sys_ip);
+        sys.labels = cfg.member_labels.clone();
         let member = Self::load_member(&mut sys, &fs_cfg)?;
         let services = Arc::default();
         let suitability_lookup = Arc::clone(&services) as Arc<dyn Suitability>;
@@ -979,6 +983,7 @@ impl Manager {
             http_gateway::Server::run(http_listen_addr,
                                       tls_server_config,
                                       self.state.gateway_state.clone(),
+                                      self.state.services.clone(),
                                       http_gateway::GatewayAuthenticationToken::configured_value(),
                                       self.feature_flags,
                                       pair.clone());
@@ -1028,6 +1033,8 @@ impl Manager {
             debug!("http-gateway started");
         }
 
+        event::supervisor_started();
+
         // Enter the main Supervisor loop. When we break out, it'll be
         // because we've been instructed to shutdown. The value we
         // break out with governs exactly how we shut down.
@@ -1106,6 +1113,7 @@ impl Manager {
                     UnloadService { service_spec,
                                     shutdown_input, } => {
                         self.remove_spec_file(&service_spec.ident).ok();
+                        self.service_unloaded_msr(&service_spec);
                         self.stop_service_gsw_msw(&service_spec.ident, &shutdown_input);
                     }
                     UpdateService { service_spec } => {
@@ -1207,6 +1215,8 @@ impl Manager {
         // user commands as we're trying to shut down.
         ctl_shutdown_tx.send(()).ok();
 
+        event::supervisor_stopped(shutdown_mode);
+
         match shutdown_mode {
             ShutdownMode::Restarting => {
                 outputln!("Preparing services for Supervisor restart");
@@ -1464,6 +1474,22 @@ impl Manager {
         }
     }
 
+    /// # Locking (see locking.md)
+    /// * `ManagerServices::inner` (read)
+    fn service_unloaded_msr(&self, spec: &ServiceSpec) {
+        // A service that is down has no package running; report the
+        // package it was loaded with instead.
+        let package_ident =
+            self.state
+                .services
+                .lock_msr()
+                .services()
+                .find(|service| service.spec_ident() == spec.ident)
+                .map_or_else(|| spec.ident.clone(), |service| service.pkg.ident.clone());
+        event::service_unloaded(spec.to_service_metadata(&package_ident,
+                                                         self.organization.as_deref()));
+    }
+
     /// Create a future for stopping a Service removing it from the manager. The Service is assumed
     /// to have been removed from the internal list of active services already (see, e.g.,
     /// restart_services_rsw_mlr_rhw_msw and remove_service_from_state).
@@ -2,6 +2,7 @@
 
 use crate::{ctl_gateway::CtlRequest,
             error::Error,
+            event,
             manager::{action::{ActionSender,
                                SupervisorAction},
                       service::{spec::ServiceSpec,
@@ -9,10 +10,12 @@ use crate::{ctl_gateway::CtlRequest,
                                 ProcessState},
                       ManagerState},
             util};
-use biome_butterfly as butterfly;
+use biome_butterfly::{self as butterfly,
+                        rumor::service_config::ConfigTarget};
 use biome_common::{command::package::install::InstallSource,
                      outputln,
                      templating::package::Pkg,
+                     types::MemberLabel,
                      ui::UIWriter};
 use biome_core::{package::{Identifiable,
                              PackageIdent,
@@ -55,6 +58,59 @@ pub fn service_cfg_msr(mgr: &ManagerState,
     Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
 }
 
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_member_cfg_msr(mgr: &ManagerState,
+                              req: &mut CtlRequest,
+                              opts: protocol::ctl::SvcGetMemberCfg)
+                              -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    for service in mgr.services.lock_msr().services() {
+        if service.pkg.ident.satisfies(&ident) {
+            let keys = service.explain_cfg()
+                              .into_iter()
+                              .map(|k| {
+                                  protocol::ctl::SvcCfgKey { key:    Some(k.key),
+                                                             value:  Some(k.value.to_string()),
+                                                             layer:  Some(k.layer),
+                                                             origin: Some(k.origin), }
+                              })
+                              .collect();
+            req.reply_complete(protocol::ctl::SvcMemberCfg { keys });
+            return Ok(());
+        }
+    }
+    Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
+}
+
+/// # Locking (see locking.md)
+/// * `ManagerServices::inner` (read)
+pub fn service_render_context_msr(mgr: &ManagerState,
+                                  req: &mut CtlRequest,
+                                  opts: protocol::ctl::SvcGetRenderContext)
+                                  -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    for service in mgr.services.lock_msr().services() {
+        if service.pkg.ident.satisfies(&ident) {
+            return match service.render_context_json() {
+                Some(context) => {
+                    let msg =
+                        protocol::ctl::SvcRenderContext { context: Some(context.to_string()), };
+                    req.reply_complete(msg);
+                    Ok(())
+                }
+                None => {
+                    Err(net::err(ErrCode::NotFound,
+                                 format!("Service {} has not rendered its \
+                                          templates yet",
+                                         ident)))
+                }
+            };
+        }
+    }
+    Err(net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))
+}
+
 pub fn service_cfg_validate(_mgr: &ManagerState,
                             req: &mut CtlRequest,
                             opts: protocol::ctl::SvcValidateCfg)
@@ -122,9 +178,16 @@ pub fn service_cfg_set(mgr: &ManagerState,
     if cfg.len() > protocol::butterfly::MAX_SVC_CFG_SIZE {
         return Err(net::err(ErrCode::EntityTooLarge, "Configuration too large."));
     }
-    outputln!("Setting new configuration version {} for {}",
+    let labels = opts.labels
+                     .iter()
+                     .map(|l| l.parse::<MemberLabel>())
+                     .collect::<result::Result<Vec<_>, _>>()
+                     .map_err(|e| net::err(ErrCode::InvalidPayload, e.to_string()))?;
+    let target = ConfigTarget::new(opts.member_ids, labels);
+    outputln!("Setting new configuration version {} for {} ({})",
               version,
-              service_group,);
+              service_group,
+              target);
     let mut client =
         match butterfly::client::Client::new(&mgr.cfg.gossip_listen.local_addr().to_string(),
                                              mgr.cfg.ring_key.clone())
@@ -135,7 +198,7 @@ pub fn service_cfg_set(mgr: &ManagerState,
                 return Err(net::err(ErrCode::Internal, err.to_string()));
             }
         };
-    client.send_service_config(service_group, version, &cfg, is_encrypted)
+    client.send_targeted_service_config(service_group, target, version, &cfg, is_encrypted)
           .map_err(|e| net::err(ErrCode::Internal, e.to_string()))
           .map(|_| {
               req.reply_complete(net::ok());
@@ -200,6 +263,8 @@ pub async fn service_load(mgr: &ManagerState,
     let package = util::pkg::satisfy_or_install(req, &source, &spec.bldr_url, &spec.channel).await?;
     spec.validate(&package)?;
     mgr.cfg.save_spec_for(&spec)?;
+    event::service_loaded(spec.to_service_metadata(package.ident(),
+                                                   mgr.cfg.organization.as_deref()));
 
     req.info(format!("The {} service was successfully loaded", spec.ident))?;
     req.reply_complete(net::ok());
@@ -393,6 +458,7 @@ impl From<ServiceStatus> for protocol::types::ServiceStatus {
         proto.process = Some(other.process.into());
         proto.service_group = other.service_group.into();
         proto.desired_state = Some(other.desired_state.into());
+        proto.deps = other.pkg.deps.into_iter().map(Into::into).collect();
         proto
     }
 }
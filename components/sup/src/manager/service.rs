@@ -21,6 +21,7 @@ mod terminator;
 use self::{context::RenderContext,
            hook_runner::HookRunner,
            hooks::{HookCompileTable,
+                   HookExitValue,
                    HookTable},
            supervisor::Supervisor};
 pub use self::{health::{HealthCheckBundle,
@@ -37,6 +38,7 @@ use crate::{census::{CensusGroup,
                      ServiceFile},
             error::{Error,
                     Result},
+            event::ServiceMetadata,
             manager::{event,
                       sync::GatewayState,
                       FsCfg,
@@ -49,6 +51,7 @@ use biome_butterfly::rumor::service::Service as ServiceRumor;
 #[cfg(windows)]
 use biome_common::templating::package::DEFAULT_USER;
 pub use biome_common::templating::{config::{Cfg,
+                                              MemberGossipLayer,
                                               UserConfigPath},
                                      package::{Env,
                                                Pkg,
@@ -68,7 +71,8 @@ use biome_core::{crypto::hash,
                    package::{metadata::Bind,
                              PackageIdent,
                              PackageInstall},
-                   service::{ServiceBind,
+                   service::{HookTimeout,
+                             ServiceBind,
                              ServiceGroup},
                    ChannelIdent};
 use biome_launcher_client::LauncherCli;
@@ -79,12 +83,14 @@ pub use biome_sup_protocol::types::{ProcessState,
                                       UpdateStrategy};
 use parking_lot::RwLock;
 use prometheus::{HistogramTimer,
-                 HistogramVec};
+                 HistogramVec,
+                 IntCounterVec};
 use serde::{ser::SerializeStruct,
             Serialize,
             Serializer};
 use std::{self,
-          collections::HashSet,
+          collections::{BTreeMap,
+                        HashSet},
           fmt,
           fs,
           ops::Deref,
@@ -93,7 +99,8 @@ use std::{self,
           result,
           sync::{Arc,
                  Mutex},
-          time::SystemTime};
+          time::{Duration,
+                 SystemTime}};
 
 static LOGKEY: &str = "SR";
 
@@ -105,6 +112,10 @@ lazy_static! {
         register_histogram_vec!("bio_sup_hook_duration_seconds",
                                 "The time it takes for a hook to run",
                                 &["hook"]).unwrap();
+    static ref HOOK_TIMEOUTS: IntCounterVec =
+        register_int_counter_vec!("bio_sup_hook_timeouts_total",
+                                  "The number of hooks killed for exceeding their timeout",
+                                  &["hook"]).unwrap();
 }
 
 /// When evaluating whether a particular service group can satisfy a
@@ -164,6 +175,20 @@ impl TemplateUpdate {
     }
 }
 
+/// A key of a service's merged configuration and where its value came
+/// from.
+#[derive(Debug, Serialize)]
+pub struct CfgKeyProvenance {
+    /// Dotted path of the key
+    pub key:    String,
+    pub value:  toml::Value,
+    /// Name of the configuration layer which supplied the value
+    pub layer:  String,
+    /// The file, environment variable or gossip incarnation the layer
+    /// was read from
+    pub origin: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum InitializationState {
     Uninitialized,
@@ -193,6 +218,9 @@ pub struct Service {
     initialization_state:    Arc<RwLock<InitializationState>>,
 
     config_renderer:      CfgRenderer,
+    /// The redacted JSON form of the context the service last rendered
+    /// its templates with, served to `bio config render`.
+    render_context_json:  Option<String>,
     // Note: This field is really only needed for serializing a
     // Service in the gateway (see ServiceProxy's Serialize
     // implementation). Ideally, we could get rid of this, since we're
@@ -211,6 +239,9 @@ pub struct Service {
     /// current state of the census; once you get into the actual
     /// running of the service, the distinction is immaterial.
     all_pkg_binds:        Vec<Bind>,
+    /// The hook timeouts declared by the current service package. Any
+    /// timeout given in the spec takes precedence over these.
+    pkg_hook_timeouts:    BTreeMap<String, HookTimeout>,
     /// Binds specified by the user that are currently mapped to
     /// service groups that do _not_ satisfy the bind's contract, as
     /// defined in the service's current package.
@@ -254,6 +285,17 @@ impl Service {
 
     pub(crate) fn spec(&self) -> ServiceSpec { self.spec.clone() }
 
+    /// The maximum amount of time hook `H` is allowed to run before it
+    /// is killed, if any. A timeout set on the service spec overrides
+    /// one declared by the package.
+    fn hook_timeout<H: Hook>(&self) -> Option<Duration> {
+        self.spec
+            .hook_timeouts
+            .get(H::FILE_NAME)
+            .or_else(|| self.pkg_hook_timeouts.get(H::FILE_NAME))
+            .map(|&t| t.into())
+    }
+
     pub(crate) fn set_spec(&mut self, spec: ServiceSpec) {
         trace!("Setting spec for {}: {:?}", self.spec.ident, spec);
         self.spec = spec
@@ -271,6 +313,7 @@ impl Service {
                           -> Result<Service> {
         spec.validate(&package)?;
         let all_pkg_binds = package.all_binds()?;
+        let pkg_hook_timeouts = package.hook_timeouts()?;
         let pkg = Self::resolve_pkg(&package, &spec).await?;
         let spec_file = manager_fs_cfg.specs_path.join(spec.file());
         let service_group = ServiceGroup::new(&pkg.name, &spec.group, organization)?;
@@ -281,6 +324,7 @@ impl Service {
                      sys,
                      cfg,
                      config_renderer: CfgRenderer::new(&config_root)?,
+                     render_context_json: None,
                      health_check_result: Arc::new(Mutex::new(HealthCheckResult::Unknown)),
                      hooks: HookTable::load(&pkg.name,
                                             &hooks_root,
@@ -297,6 +341,7 @@ impl Service {
                      pkg,
                      service_group,
                      all_pkg_binds,
+                     pkg_hook_timeouts,
                      unsatisfied_binds: HashSet::new(),
                      spec_file,
                      gateway_state,
@@ -412,7 +457,9 @@ impl Service {
                                               self.spec.health_check_interval,
                                               self.service_group.clone(),
                                               self.pkg.clone(),
-                                              self.spec.svc_encrypted_password.clone());
+                                              self.to_service_metadata(),
+                                              self.spec.svc_encrypted_password.clone(),
+                                              self.hook_timeout::<HealthCheckHook>());
 
         let service_group = self.service_group.clone();
         let service_event_metadata = self.to_service_metadata();
@@ -581,6 +628,9 @@ impl Service {
                         if self.last_election_status != census_group.election_status {
                             outputln!(preamble self.service_group,
                                       "Waiting to execute hooks; election in progress.");
+                            if self.last_election_status != ElectionStatus::ElectionNoQuorum {
+                                event::election_started(self);
+                            }
                             self.last_election_status = census_group.election_status;
                         }
                     }
@@ -589,6 +639,9 @@ impl Service {
                             outputln!(preamble self.service_group,
                                       "Waiting to execute hooks; election in progress, \
                                       and we have no quorum.");
+                            if self.last_election_status != ElectionStatus::ElectionInProgress {
+                                event::election_started(self);
+                            }
 
                             self.last_election_status = census_group.election_status;
                         }
@@ -601,6 +654,11 @@ impl Service {
                             outputln!(preamble self.service_group,
                                       "Executing hooks; {} is the leader",
                                       leader_id.to_string());
+                            if *leader_id == self.sys.member_id {
+                                event::election_won(self);
+                            } else {
+                                event::election_lost(self, leader_id);
+                            }
                             self.last_election_status = census_group.election_status;
                         }
                         self.execute_hooks(launcher, &template_update);
@@ -761,17 +819,41 @@ impl Service {
     ///
     /// Returns `true` if the configuration was updated.
     fn update_gossip(&mut self, census_group: &CensusGroup) -> bool {
-        match census_group.service_config {
-            Some(ref config) => {
-                if config.incarnation <= self.cfg.gossip_incarnation {
-                    return false;
-                }
+        let group_updated = match census_group.service_config {
+            Some(ref config) if config.incarnation > self.cfg.gossip_incarnation => {
                 self.cfg
                     .set_gossip(config.incarnation, config.value.clone());
+                event::service_config_applied(self, config.incarnation);
                 true
             }
-            None => false,
+            _ => false,
+        };
+        let member_updated = self.update_member_gossip(census_group);
+        group_updated || member_updated
+    }
+
+    /// Layer the service configs targeted at this member on top of the group-wide one.
+    fn update_member_gossip(&mut self, census_group: &CensusGroup) -> bool {
+        let targeted =
+            census_group.targeted_service_configs_for(&self.sys.member_id, &self.sys.labels);
+        let unchanged = targeted.len() == self.cfg.member_gossip.len()
+                        && targeted.iter()
+                                   .zip(self.cfg.member_gossip.iter())
+                                   .all(|(config, layer)| {
+                                       config.incarnation == layer.incarnation
+                                       && config.target.to_string() == layer.target
+                                   });
+        if unchanged {
+            return false;
         }
+        let layers = targeted.into_iter()
+                             .map(|config| {
+                                 MemberGossipLayer { target:      config.target.to_string(),
+                                                     incarnation: config.incarnation,
+                                                     cfg:         config.value.clone(), }
+                             })
+                             .collect();
+        self.cfg.set_member_gossip(layers)
     }
 
     /// Compares the current state of the service to the current state of the census ring and the
@@ -792,16 +874,53 @@ impl Service {
         }
 
         let template_update = if template_data_changed || census_ring.changed() {
-            let ctx = self.render_context(census_ring);
-            TemplateUpdate::new(self.compile_hooks(&ctx),
-                                self.compile_configuration(&ctx),
-                                self.hooks.reconfigure.is_some() || self.hooks.reload.is_some())
+            let (template_update, ctx_json) = {
+                let ctx = self.render_context(census_ring);
+                (TemplateUpdate::new(self.compile_hooks(&ctx),
+                                     self.compile_configuration(&ctx),
+                                     self.hooks.reconfigure.is_some()
+                                     || self.hooks.reload.is_some()),
+                 ctx.to_redacted_json())
+            };
+            match ctx_json {
+                Ok(json) => self.render_context_json = Some(json.to_string()),
+                Err(e) => {
+                    outputln!(preamble self.service_group,
+                              "Failed to serialize render context: {}", e)
+                }
+            }
+            template_update
         } else {
             TemplateUpdate::default()
         };
         (template_data_changed, template_update)
     }
 
+    /// The redacted JSON form of the context the service last rendered
+    /// its templates with, if it has rendered them yet.
+    pub fn render_context_json(&self) -> Option<&str> { self.render_context_json.as_deref() }
+
+    /// Every key of the merged configuration, along with the layer
+    /// which supplied it. Values of keys that look like secrets are
+    /// redacted.
+    pub fn explain_cfg(&self) -> Vec<CfgKeyProvenance> {
+        self.cfg
+            .explain()
+            .into_iter()
+            .map(|(key, (value, source))| {
+                let value = if context::is_secret_key(&key) {
+                    toml::Value::String(context::REDACTED.to_string())
+                } else {
+                    value
+                };
+                CfgKeyProvenance { key,
+                                   value,
+                                   layer: source.layer,
+                                   origin: source.origin }
+            })
+            .collect()
+    }
+
     pub fn to_rumor(&self, incarnation: u64) -> ServiceRumor {
         let exported = match self.cfg.to_exported(&self.pkg) {
             Ok(exported) => Some(exported),
@@ -829,7 +948,9 @@ impl Service {
             let hook_runner = HookRunner::new(Arc::clone(&hook),
                                               self.service_group.clone(),
                                               self.pkg.clone(),
-                                              self.spec.svc_encrypted_password.clone());
+                                              self.to_service_metadata(),
+                                              self.spec.svc_encrypted_password.clone(),
+                                              self.hook_timeout::<hooks::InitHook>());
             // These clones are unfortunate. async/await will make this much better.
             let service_group = self.service_group.clone();
             let initialization_state = Arc::clone(&self.initialization_state);
@@ -868,17 +989,21 @@ impl Service {
         let _timer = hook_timer("reconfigure");
 
         if let Some(ref hook) = self.hooks.reload {
-            hook.run(&self.service_group,
+            run_hook(hook.as_ref(),
+                     &self.service_group,
                      &self.pkg,
-                     self.spec.svc_encrypted_password.as_ref())
-                .ok();
+                     &self.to_service_metadata(),
+                     self.spec.svc_encrypted_password.as_ref(),
+                     self.hook_timeout::<hooks::ReloadHook>()).ok();
         }
 
         if let Some(ref hook) = self.hooks.reconfigure {
-            hook.run(&self.service_group,
+            run_hook(hook.as_ref(),
+                     &self.service_group,
                      &self.pkg,
-                     self.spec.svc_encrypted_password.as_ref())
-                .ok();
+                     &self.to_service_metadata(),
+                     self.spec.svc_encrypted_password.as_ref(),
+                     self.hook_timeout::<hooks::ReconfigureHook>()).ok();
             // The intention here is to do a health check soon after a service's configuration
             // changes, as a way to (among other things) detect potential impacts when bound
             // services change exported configuration.
@@ -891,7 +1016,9 @@ impl Service {
             let hook_runner = HookRunner::new(Arc::clone(&hook),
                                               self.service_group.clone(),
                                               self.pkg.clone(),
-                                              self.spec.svc_encrypted_password.clone());
+                                              self.to_service_metadata(),
+                                              self.spec.svc_encrypted_password.clone(),
+                                              self.hook_timeout::<hooks::PostRunHook>());
             let f = HookRunner::retryable_future(hook_runner);
             let (f, handle) = future::abortable(f);
             self.post_run_handle = Some(handle);
@@ -912,7 +1039,9 @@ impl Service {
                                          HookRunner::new(Arc::clone(&hook),
                                                          self.service_group.clone(),
                                                          self.pkg.clone(),
-                                                         self.spec.svc_encrypted_password.clone())
+                                                         self.to_service_metadata(),
+                                                         self.spec.svc_encrypted_password.clone(),
+                                                         self.hook_timeout::<hooks::PostStopHook>())
                                      })
     }
 
@@ -927,10 +1056,12 @@ impl Service {
             .suitability
             .as_ref()
             .and_then(|hook| {
-                hook.run(&self.service_group,
+                run_hook(hook.as_ref(),
+                         &self.service_group,
                          &self.pkg,
-                         self.spec.svc_encrypted_password.as_ref())
-                    .ok()
+                         &self.to_service_metadata(),
+                         self.spec.svc_encrypted_password.as_ref(),
+                         self.hook_timeout::<hooks::SuitabilityHook>()).ok()
             })
             .unwrap_or(None)
     }
@@ -1059,10 +1190,12 @@ impl Service {
 
         if self.initialized() {
             if let Some(ref hook) = self.hooks.file_updated {
-                return hook.run(&self.service_group,
+                return run_hook(hook.as_ref(),
+                                &self.service_group,
                                 &self.pkg,
-                                self.spec.svc_encrypted_password.as_ref())
-                           .unwrap_or(false);
+                                &self.to_service_metadata(),
+                                self.spec.svc_encrypted_password.as_ref(),
+                                self.hook_timeout::<hooks::FileUpdatedHook>()).unwrap_or(false);
             }
         }
 
@@ -1096,7 +1229,11 @@ impl Service {
             census_ring.census_group_for(&self.service_group)
                        .expect("Service update service files failed; unable to find own service \
                                 group");
-        self.write_service_files(census_group, CensusGroup::changed_service_files)
+        let written = self.write_service_files(census_group, CensusGroup::changed_service_files);
+        for service_file in &written {
+            event::service_file_uploaded(self, &service_file.filename, service_file.incarnation);
+        }
+        !written.is_empty()
     }
 
     /// Abstracts the logic for writing out service files for a
@@ -1108,20 +1245,23 @@ impl Service {
     /// `CensusGroup::changed_service_files`, to write out only the
     /// files that have had recent gossip activity.
     ///
-    /// Returns `true` if any service files were written to disk.
-    fn write_service_files<'a, F, I>(&mut self, census_group: &'a CensusGroup, file_fn: F) -> bool
+    /// Returns the service files that were written to disk.
+    fn write_service_files<'a, F, I>(&mut self,
+                                     census_group: &'a CensusGroup,
+                                     file_fn: F)
+                                     -> Vec<&'a ServiceFile>
         where F: Fn(&'a CensusGroup) -> I,
               I: IntoIterator<Item = &'a ServiceFile>
     {
-        let mut updated = false;
+        let mut written = Vec::new();
         for service_file in file_fn(census_group) {
             if self.cache_service_file(&service_file) {
                 outputln!(preamble self.service_group, "Service file updated, {}",
                           service_file.filename);
-                updated = true;
+                written.push(service_file);
             }
         }
-        updated
+        written
     }
 
     /// Helper for constructing a new render context for the service.
@@ -1228,6 +1368,35 @@ fn hook_timer(name: &str) -> HistogramTimer {
     HOOK_DURATION.with_label_values(&[name]).start_timer()
 }
 
+// Runs a hook with the given timeout, recording in Prometheus whenever the hook had to be killed
+// for exceeding it, and sending an event whenever it fails.
+fn run_hook<H, T>(hook: &H,
+                  service_group: &ServiceGroup,
+                  pkg: &Pkg,
+                  metadata: &ServiceMetadata,
+                  password: Option<T>,
+                  timeout: Option<Duration>)
+                  -> biome_common::error::Result<H::ExitValue>
+    where H: Hook,
+          H::ExitValue: HookExitValue,
+          T: ToString
+{
+    let result = hook.run(service_group, pkg, password, timeout);
+    match result {
+        Ok(ref exit_value) if exit_value.failed() => {
+            event::hook_failed(metadata, H::FILE_NAME, exit_value.exit_code(), None);
+        }
+        Ok(_) => {}
+        Err(ref e) => {
+            if let biome_common::Error::HookTimedOut(..) = e {
+                HOOK_TIMEOUTS.with_label_values(&[H::FILE_NAME]).inc();
+            }
+            event::hook_failed(metadata, H::FILE_NAME, None, Some(e.to_string()));
+        }
+    }
+    result
+}
+
 /// This enum represents whether or not we want to render config information when we serialize this
 /// service via the ServiceProxy struct below. Choosing ConfigRendering::Full will render the
 /// config, and choosing ConfigRendering::Redacted will not render it. This matches up to the
@@ -1314,7 +1483,7 @@ impl<'a> Serialize for ServiceProxy<'a> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::test_helpers::*;
     use biome_common::types::{GossipListenAddr,
@@ -1324,7 +1493,7 @@ mod tests {
                     Ipv4Addr},
               str::FromStr};
 
-    async fn initialize_test_service() -> Service {
+    pub(crate) async fn initialize_test_service() -> Service {
         let listen_ctl_addr =
             ListenCtlAddr::from_str("127.0.0.1:1234").expect("Can't parse IP into SocketAddr");
         let sys = Sys::new(false,
@@ -49,6 +49,7 @@ use biome_core::{package::{FullyQualifiedPackageIdent,
 use serde::{ser::SerializeMap,
             Serialize,
             Serializer};
+use serde_json::Value as Json;
 use std::{borrow::Cow,
           collections::BTreeMap,
           net::IpAddr,
@@ -108,12 +109,50 @@ impl<'a> RenderContext<'a> {
 
     // Exposed only for logging... can probably do this another way.
     pub fn service_group_name(&self) -> String { format!("{}", self.svc.service_group) }
+
+    /// Serialize the context to JSON, replacing the values of any keys
+    /// that look like they hold secrets. This is what gets handed out
+    /// to operators via `bio config render`, so it must never contain
+    /// credentials.
+    pub fn to_redacted_json(&self) -> serde_json::Result<Json> {
+        let mut json = serde_json::to_value(self)?;
+        redact_secrets(&mut json);
+        Ok(json)
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////
 // PRIVATE CODE BELOW
 ////////////////////////////////////////////////////////////////////////
 
+/// Substrings of a key name that mark its value as sensitive.
+const SECRET_KEY_MARKERS: &[&str] =
+    &["password", "passwd", "secret", "token", "private_key", "credential"];
+
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Whether the value of a configuration key should be kept from operators.
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|m| key.contains(m))
+}
+
+fn redact_secrets(json: &mut Json) {
+    match json {
+        Json::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) {
+                    *value = Json::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Json::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
 /// Templating proxy for a `manager::Sys` struct.
 ///
 /// Exposed to users under the `sys` key. This section represents Supervisor system information
@@ -725,4 +764,28 @@ two = 2
         let j = serde_json::to_string(&render_context).expect("can't serialize to JSON");
         assert_valid(&j, "render_context_schema.json");
     }
+
+    #[test]
+    fn redact_secrets_replaces_sensitive_values() {
+        let mut json = serde_json::json!({
+            "cfg": {
+                "port": 5432,
+                "admin_password": "hunter2",
+                "Api_Token": ["abc"],
+                "tls": { "private_key": "-----BEGIN", "cert": "public" }
+            },
+            "bind": [{ "db_secret": { "nested": true } }]
+        });
+        redact_secrets(&mut json);
+        assert_eq!(json,
+                   serde_json::json!({
+                       "cfg": {
+                           "port": 5432,
+                           "admin_password": "<redacted>",
+                           "Api_Token": "<redacted>",
+                           "tls": { "private_key": "<redacted>", "cert": "public" }
+                       },
+                       "bind": [{ "db_secret": "<redacted>" }]
+                   }));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::{error::Error,
+            event::ServiceMetadata,
             manager::service::{hook_runner,
                                hooks::HealthCheckHook,
                                supervisor::Supervisor,
@@ -100,14 +101,18 @@ async fn check(supervisor: Arc<Mutex<Supervisor>>,
                hook: Option<Arc<HealthCheckHook>>,
                service_group: ServiceGroup,
                package: Pkg,
-               password: Option<String>)
+               metadata: ServiceMetadata,
+               password: Option<String>,
+               timeout: Option<Duration>)
                -> (HealthCheckHookStatus, HealthCheckResult) {
     let status = if let Some(hook) = hook {
         let result = hook_runner::HookRunner::new(hook,
                                                   service_group.clone(),
                                                   package.clone(),
-                                                  password).into_future()
-                                                           .await;
+                                                  metadata,
+                                                  password,
+                                                  timeout).into_future()
+                                                          .await;
         match result {
             Ok((output, duration)) => HealthCheckHookStatus::Ran(output, duration),
             Err(Error::WithDuration(e, duration)) => {
@@ -171,7 +176,9 @@ pub fn check_repeatedly(supervisor: Arc<Mutex<Supervisor>>,
                         nominal_interval: HealthCheckInterval,
                         service_group: ServiceGroup,
                         package: Pkg,
-                        password: Option<String>)
+                        metadata: ServiceMetadata,
+                        password: Option<String>,
+                        timeout: Option<Duration>)
                         -> UnboundedReceiver<HealthCheckBundle> {
     // TODO (CM): If we wanted to keep track of how many times
     // a health check has failed in the past X executions, or
@@ -188,7 +195,9 @@ pub fn check_repeatedly(supervisor: Arc<Mutex<Supervisor>>,
                                          hook.as_ref().map(Arc::clone),
                                          service_group.clone(),
                                          package.clone(),
-                                         password.clone()).await;
+                                         metadata.clone(),
+                                         password.clone(),
+                                         timeout).await;
 
             let interval = if result == HealthCheckResult::Ok {
                 if !first_ok_health_check_recorded {
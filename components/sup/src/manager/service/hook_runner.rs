@@ -9,9 +9,12 @@
 //! this seems to do the trick.
 
 use super::{hook_timer,
+            hooks::HookExitValue,
+            run_hook,
             Pkg};
-use crate::error::{Error,
-                   Result};
+use crate::{error::{Error,
+                    Result},
+            event::ServiceMetadata};
 use biome_common::templating::hooks::Hook;
 use biome_core::service::ServiceGroup;
 use std::{clone::Clone,
@@ -24,7 +27,9 @@ pub struct HookRunner<H: Hook + Sync> {
     hook:          Arc<H>,
     service_group: ServiceGroup,
     pkg:           Pkg,
+    metadata:      ServiceMetadata,
     passwd:        Option<String>,
+    timeout:       Option<Duration>,
 }
 
 // We cannot use `#[derive(Clone)]` here because it unnecessarily requires `H` to be
@@ -34,21 +39,29 @@ impl<H: Hook + Sync> Clone for HookRunner<H> {
         Self { hook:          self.hook.clone(),
                service_group: self.service_group.clone(),
                pkg:           self.pkg.clone(),
-               passwd:        self.passwd.clone(), }
+               metadata:      self.metadata.clone(),
+               passwd:        self.passwd.clone(),
+               timeout:       self.timeout, }
     }
 }
 
-impl<H> HookRunner<H> where H: Hook + Sync + 'static
+impl<H> HookRunner<H>
+    where H: Hook + Sync + 'static,
+          H::ExitValue: HookExitValue
 {
     pub fn new(hook: Arc<H>,
                service_group: ServiceGroup,
                pkg: Pkg,
-               passwd: Option<String>)
+               metadata: ServiceMetadata,
+               passwd: Option<String>,
+               timeout: Option<Duration>)
                -> HookRunner<H> {
         HookRunner { hook,
                      service_group,
                      pkg,
-                     passwd }
+                     metadata,
+                     passwd,
+                     timeout }
     }
 
     pub async fn retryable_future(self) {
@@ -67,16 +80,18 @@ impl<H> HookRunner<H> where H: Hook + Sync + 'static
     }
 
     pub async fn into_future(self) -> Result<(H::ExitValue, Duration)> {
-        // TODO (CM): May want to consider adding a configurable
-        // timeout to how long this hook is allowed to run.
         task::spawn_blocking(move || {
             // _timer is for Prometheus metrics, but we also want
             // the runtime for other purposes. Unfortunately,
             // we're not able to use the same timer for both :(
             let _timer = hook_timer(H::FILE_NAME);
             let start = Instant::now();
-            let result = self.hook
-                             .run(&self.service_group, &self.pkg, self.passwd.as_ref());
+            let result = run_hook(self.hook.as_ref(),
+                                  &self.service_group,
+                                  &self.pkg,
+                                  &self.metadata,
+                                  self.passwd.as_ref(),
+                                  self.timeout);
             let run_time = start.elapsed();
             let exit_value = result.map_err(|e| Error::from(e).with_duration(run_time))?;
             Ok((exit_value, run_time))
@@ -19,7 +19,8 @@ use std::{self,
           io::BufRead,
           path::{Path,
                  PathBuf},
-          sync::Arc};
+          sync::Arc,
+          time::Duration};
 
 static LOGKEY: &str = "HK";
 
@@ -52,6 +53,36 @@ impl ProcessOutput {
     pub fn standard_streams(self) -> StandardStreams { self.standard_streams }
 }
 
+/// What a hook's exit value says about whether it failed, for the
+/// hook failed event.
+pub trait HookExitValue {
+    fn failed(&self) -> bool;
+
+    /// The hook's exit code, if its exit value records one.
+    fn exit_code(&self) -> Option<i32> { None }
+}
+
+impl HookExitValue for bool {
+    fn failed(&self) -> bool { !*self }
+}
+
+impl HookExitValue for ExitCode {
+    fn failed(&self) -> bool { self.0 != 0 }
+
+    fn exit_code(&self) -> Option<i32> { Some(self.0) }
+}
+
+/// A suitability hook which reports no suitability has failed.
+impl HookExitValue for Option<u64> {
+    fn failed(&self) -> bool { self.is_none() }
+}
+
+/// A health check hook's exit status is the service's health, which
+/// the health check event reports, so it is never a failure.
+impl HookExitValue for ProcessOutput {
+    fn failed(&self) -> bool { false }
+}
+
 #[derive(Debug, Serialize)]
 pub struct FileUpdatedHook {
     render_pair:     RenderPair,
@@ -134,10 +165,13 @@ impl Hook for HealthCheckHook {
     fn run<T>(&self,
               service_group: &str,
               pkg: &Pkg,
-              svc_encrypted_password: Option<T>)
+              svc_encrypted_password: Option<T>,
+              timeout: Option<Duration>)
               -> Result<Self::ExitValue>
         where T: ToString
     {
+        // The named pipe server runs the health check inside a long-lived powershell process
+        // that we cannot kill per invocation, so timeouts only apply to directly spawned hooks.
         if let Some(client) = &self.pipe_client {
             match client.exec_hook(service_group, pkg, svc_encrypted_password) {
                 Ok(exit) => {
@@ -152,7 +186,7 @@ impl Hook for HealthCheckHook {
                 }
             }
         } else {
-            self.run_impl(service_group, pkg, svc_encrypted_password)
+            self.run_impl(service_group, pkg, svc_encrypted_password, timeout)
         }
     }
 
@@ -238,7 +272,7 @@ impl Hook for RunHook {
                   stderr_log_path: hooks::stderr_log_path::<Self>(package_name), }
     }
 
-    fn run<T>(&self, _: &str, _: &Pkg, _: Option<T>) -> Result<Self::ExitValue>
+    fn run<T>(&self, _: &str, _: &Pkg, _: Option<T>, _: Option<Duration>) -> Result<Self::ExitValue>
         where T: ToString
     {
         panic!("The run hook is a an exception to the lifetime of a service. It should only be \
@@ -925,7 +959,7 @@ mod tests {
 
         hook.compile(&service_group, &ctx).unwrap();
 
-        let result = hook.run(&service_group, &pkg, None::<&str>).unwrap();
+        let result = hook.run(&service_group, &pkg, None::<&str>, None).unwrap();
 
         assert_eq!(Some(1), result.exit_status().code());
         assert!(result.standard_streams()
@@ -973,7 +1007,7 @@ mod tests {
 
         hook.compile(&service_group, &ctx).unwrap();
 
-        let result = hook.run(&service_group, &pkg, None::<&str>).unwrap();
+        let result = hook.run(&service_group, &pkg, None::<&str>, None).unwrap();
 
         assert_eq!(Some(1), result.exit_status().code());
         assert!(!result.standard_streams()
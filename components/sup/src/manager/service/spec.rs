@@ -9,7 +9,9 @@ use biome_core::{fs::atomic_write,
                    package::{PackageIdent,
                              PackageInstall},
                    service::{HealthCheckInterval,
-                             ServiceBind},
+                             HookTimeout,
+                             ServiceBind,
+                             TIMEOUT_HOOK_NAMES},
                    url::DEFAULT_BLDR_URL,
                    util,
                    ChannelIdent};
@@ -17,7 +19,8 @@ use biome_sup_protocol::{self,
                            net};
 use serde::{self,
             Deserialize};
-use std::{collections::HashSet,
+use std::{collections::{BTreeMap,
+                        HashSet},
           convert::TryFrom,
           fmt,
           fs::{self,
@@ -93,14 +96,18 @@ pub struct ServiceSpec {
     pub shutdown_timeout:       Option<ShutdownTimeout>,
     pub svc_encrypted_password: Option<String>,
     // it is important that the health check interval
-    // is the last field to be serialized because it
-    // is serialized as a table. Individual values
-    // serialized after the health check interval will
+    // and hook timeouts are the last fields to be
+    // serialized because they are serialized as tables.
+    // Individual values serialized after them will
     // break the parser.
     // Note that there is an issue to ultimately fix this:
     // https://github.com/habitat-sh/habitat/issues/6469
     // and eliminate the need to keep this field last.
     pub health_check_interval:  HealthCheckInterval,
+    /// Per-hook timeouts, keyed by hook name, overriding any declared
+    /// by the package.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hook_timeouts:          BTreeMap<String, HookTimeout>,
 }
 
 impl ServiceSpec {
@@ -117,6 +124,7 @@ impl ServiceSpec {
                config_from: None,
                desired_state: DesiredState::default(),
                health_check_interval: HealthCheckInterval::default(),
+               hook_timeouts: BTreeMap::new(),
                svc_encrypted_password: None,
                shutdown_timeout: None }
     }
@@ -276,6 +284,7 @@ impl ServiceSpec {
         if let Some(shutdown_timeout) = svc_load.shutdown_timeout {
             self.shutdown_timeout = Some(ShutdownTimeout::from(shutdown_timeout));
         }
+        self.merge_hook_timeouts(svc_load.hook_timeouts);
         Ok(self)
     }
 
@@ -337,6 +346,29 @@ impl ServiceSpec {
         if let Some(shutdown_timeout) = svc_update.shutdown_timeout {
             self.shutdown_timeout = Some(ShutdownTimeout::from(shutdown_timeout));
         }
+        self.merge_hook_timeouts(svc_update.hook_timeouts);
+    }
+
+    fn merge_hook_timeouts(&mut self,
+                           hook_timeouts: Vec<biome_sup_protocol::types::HookTimeout>) {
+        for hook_timeout in hook_timeouts {
+            if !TIMEOUT_HOOK_NAMES.contains(&hook_timeout.hook.as_str()) {
+                warn!("Unable to parse hook timeout for unknown hook '{}' from protocol message; \
+                       ignoring",
+                      hook_timeout.hook);
+                continue;
+            }
+            match HookTimeout::try_from(hook_timeout.seconds) {
+                Ok(timeout) => {
+                    self.hook_timeouts.insert(hook_timeout.hook, timeout);
+                }
+                Err(e) => {
+                    warn!("Unable to parse hook timeout for '{}' from protocol message; \
+                           ignoring: {}",
+                          hook_timeout.hook, e);
+                }
+            }
+        }
     }
 
     /// Given an `old` and a `new` spec, figure out what operations
@@ -429,6 +461,7 @@ impl ServiceSpec {
                         shutdown_timeout,
                         svc_encrypted_password,
                         health_check_interval,
+                        hook_timeouts,
                     } = &running_spec;
 
                     // Currently, if any of these bits of data are
@@ -458,6 +491,9 @@ impl ServiceSpec {
                         || svc_encrypted_password != &disk_spec.svc_encrypted_password
                         // TODO (CM): This probably doesn't need to be here, either
                         || health_check_interval != &disk_spec.health_check_interval
+                        // TODO: Hooks could pick up new timeouts
+                        // without a restart
+                        || hook_timeouts != &disk_spec.hook_timeouts
                     {
                         debug!("Reconciliation: '{}' queued for restart",
                                running_spec.ident);
@@ -598,6 +634,10 @@ mod test {
             [health_check_interval]
             secs = 5
             nanos = 0
+
+            [hook_timeouts]
+            init = 30
+            post-stop = 5
             "#;
         let spec = ServiceSpec::from_str(toml).unwrap();
 
@@ -615,6 +655,56 @@ mod test {
                    Some(PathBuf::from("/only/for/development")));
         assert_eq!(spec.health_check_interval,
                    HealthCheckInterval::from_str("5").unwrap());
+        assert_eq!(spec.hook_timeouts.get("init"), Some(&HookTimeout::try_from(30).unwrap()));
+        assert_eq!(spec.hook_timeouts.get("post-stop"),
+                   Some(&HookTimeout::try_from(5).unwrap()));
+    }
+
+    #[test]
+    fn service_spec_from_str_zero_hook_timeout() {
+        let toml = r#"
+            ident = "origin/name/1.2.3/20170223130020"
+
+            [hook_timeouts]
+            init = 0
+            "#;
+
+        assert!(ServiceSpec::from_str(toml).is_err());
+    }
+
+    #[test]
+    fn merge_svc_load_ignores_invalid_hook_timeouts() {
+        let hook_timeout = |hook: &str, seconds| {
+            biome_sup_protocol::types::HookTimeout { hook: hook.to_string(),
+                                                     seconds }
+        };
+        let ident = PackageIdent::from_str("origin/name").unwrap();
+        let svc_load = biome_sup_protocol::ctl::SvcLoad { ident: Some(ident.into()),
+                                                          hook_timeouts:
+                                                              vec![hook_timeout("init", 0),
+                                                                   hook_timeout("inti", 30),
+                                                                   hook_timeout("reload", 30),],
+                                                          ..Default::default() };
+        let spec = ServiceSpec::try_from(svc_load).unwrap();
+
+        assert_eq!(spec.hook_timeouts,
+                   vec![("reload".to_string(), HookTimeout::try_from(30).unwrap())].into_iter()
+                                                                                     .collect());
+    }
+
+    #[test]
+    fn service_spec_with_version_constraint() {
+        let toml = r#"
+            ident = "origin/name@>=1.2,<2"
+            "#;
+        let spec = ServiceSpec::from_str(toml).unwrap();
+
+        assert_eq!(spec.ident.version, None);
+        assert_eq!(spec.ident.constraint, Some(">=1.2,<2".parse().unwrap()));
+
+        let toml = spec.to_toml_string().unwrap();
+        assert!(toml.contains(r#"ident = "origin/name@>=1.2,<2""#));
+        assert_eq!(ServiceSpec::from_str(&toml).unwrap().ident, spec.ident);
     }
 
     #[test]
@@ -686,6 +776,10 @@ mod test {
                                                                               acmecorp").unwrap(),],
                           binding_mode:           BindingMode::Relaxed,
                           health_check_interval:  HealthCheckInterval::from_str("123").unwrap(),
+                          hook_timeouts:          vec![("init".to_string(),
+                                                        HookTimeout::try_from(30).unwrap())]
+                                                      .into_iter()
+                                                      .collect(),
                           config_from:            Some(PathBuf::from("/only/for/development")),
                           desired_state:          DesiredState::Down,
                           svc_encrypted_password: None,
@@ -707,6 +801,8 @@ mod test {
         assert!(toml.contains(r#"secs = 123"#));
         assert!(toml.contains(r#"nanos = 0"#));
         assert!(toml.contains(r#"shutdown_timeout = 10"#));
+        assert!(toml.contains(r#"[hook_timeouts]"#));
+        assert!(toml.contains(r#"init = 30"#));
     }
 
     #[test]
@@ -855,6 +951,7 @@ mod test {
                                                                               acmecorp").unwrap(),],
                           binding_mode:           BindingMode::Relaxed,
                           health_check_interval:  HealthCheckInterval::from_str("23").unwrap(),
+                          hook_timeouts:          BTreeMap::new(),
                           config_from:            Some(PathBuf::from("/only/for/development")),
                           desired_state:          DesiredState::Down,
                           svc_encrypted_password: None,
@@ -1157,6 +1254,11 @@ mod test {
                    restart,
                    health_check_interval,
                    10000.into());
+        reconcile!(hook_timeouts_causes_restart,
+                   restart,
+                   hook_timeouts,
+                   vec![("init".to_string(), HookTimeout::try_from(30).unwrap())].into_iter()
+                                                                                   .collect());
 
         reconcile!(bldr_url_causes_update,
                    update,
@@ -1,7 +1,9 @@
 use crate::{manager::service::Service,
             util};
 use biome_core::{self,
-                   package::{FullyQualifiedPackageIdent,
+                   package::{constraint::VersionConstraint,
+                             FullyQualifiedPackageIdent,
+                             Identifiable,
                              PackageIdent},
                    service::ServiceGroup,
                    ChannelIdent};
@@ -53,6 +55,8 @@ pub struct PackageUpdateWorker {
     service_group:    ServiceGroup,
     ident:            PackageIdent,
     full_ident:       FullyQualifiedPackageIdent,
+    /// The version constraint of the service spec's ident, which every update must stay within
+    constraint:       Option<VersionConstraint>,
     update_condition: UpdateCondition,
     channel:          ChannelIdent,
     builder_url:      String,
@@ -61,21 +65,36 @@ pub struct PackageUpdateWorker {
 
 impl PackageUpdateWorker {
     pub fn new(service: &Service, period: Duration) -> Self {
+        let ident = service.spec_ident();
         Self { service_group: service.service_group.clone(),
-               ident: service.spec_ident(),
+               constraint: ident.constraint.clone(),
+               ident,
                full_ident: service.pkg.ident.clone(),
                update_condition: service.update_condition(),
                channel: service.channel(),
                builder_url: service.bldr_url(),
                period }
     }
+
+    /// Whether `ident` is within the version constraint of the service spec's ident, if it has
+    /// one.
+    fn within_constraint(&self, ident: &PackageIdent) -> bool {
+        within_constraint(self.constraint.as_ref(), ident)
+    }
+}
+
+fn within_constraint(constraint: Option<&VersionConstraint>, ident: &PackageIdent) -> bool {
+    constraint.map_or(true, |constraint| {
+                  ident.version().map_or(false, |version| constraint.matches(version))
+              })
 }
 
 impl PackageUpdateWorker {
     /// Use the specified package ident to search for packages.
     ///
     /// If a fully qualified package ident is used, the future will only resolve when that exact
-    /// package is found.
+    /// package is found. A package outside the version constraint of the service spec's ident is
+    /// never resolved, even when it is the latest in the channel or the one a leader updated to.
     // TODO (DM): The returned package ident should use FullyQualifiedPackageIdent.
     pub async fn update_to(&self, ident: PackageIdent) -> PackageIdent {
         let period = PackageUpdateWorkerPeriod::get().unwrap_or(self.period);
@@ -95,6 +114,15 @@ impl PackageUpdateWorker {
                 }
             };
             match package_result {
+                Ok(package) if !self.within_constraint(&package.ident) => {
+                    debug!("'{}' package update worker ignoring '{}' for '{}' in channel '{}' as \
+                            it is outside of '{}'",
+                           self.service_group,
+                           package.ident,
+                           ident,
+                           self.channel,
+                           self.ident)
+                }
                 Ok(package) => {
                     if &package.ident != self.full_ident.as_ref() {
                         debug!("'{}' package update worker found change from '{}' to '{}' for \
@@ -131,3 +159,35 @@ impl PackageUpdateWorker {
     /// Use the service spec's package ident to search for packages.
     pub async fn update(&self) -> PackageIdent { self.update_to(self.ident.clone()).await }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_constraint_only_accepts_matching_versions() {
+        let spec_ident: PackageIdent = "core/redis@>=4,<6".parse().unwrap();
+        let constraint = spec_ident.constraint.clone();
+        assert!(constraint.is_some());
+
+        for ident in &["core/redis/4.0.14/20190319155852", "core/redis/5.0.7/20200305231346"] {
+            assert!(within_constraint(constraint.as_ref(), &ident.parse().unwrap()),
+                    "'{}' should be within '{}'",
+                    ident,
+                    spec_ident);
+        }
+        for ident in &["core/redis/3.2.4/20170514150022", "core/redis/6.0.5/20200813120000"] {
+            assert!(!within_constraint(constraint.as_ref(), &ident.parse().unwrap()),
+                    "'{}' should not be within '{}'",
+                    ident,
+                    spec_ident);
+        }
+    }
+
+    #[test]
+    fn within_constraint_accepts_everything_without_a_constraint() {
+        let spec_ident: PackageIdent = "core/redis/4.0.14".parse().unwrap();
+        assert!(spec_ident.constraint.is_none());
+        assert!(within_constraint(None, &"core/redis/6.0.5/20200813120000".parse().unwrap()));
+    }
+}
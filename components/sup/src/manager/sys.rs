@@ -3,7 +3,8 @@ use biome_butterfly::rumor::service::SysInfo;
 use biome_common::{outputln,
                      types::{GossipListenAddr,
                              HttpListenAddr,
-                             ListenCtlAddr}};
+                             ListenCtlAddr,
+                             MemberLabel}};
 use std::{net::{IpAddr,
                 SocketAddr},
           str};
@@ -23,6 +24,9 @@ pub struct Sys {
     pub http_gateway_ip:   IpAddr,
     pub http_gateway_port: u16,
     pub permanent:         bool,
+    /// Labels that targeted service configs can match this Supervisor by
+    #[serde(default)]
+    pub labels:            Vec<MemberLabel>,
 }
 
 impl Sys {
@@ -50,7 +54,8 @@ impl Sys {
                ctl_gateway_port: ctl.port(),
                http_gateway_ip: http.ip(),
                http_gateway_port: http.port(),
-               permanent }
+               permanent,
+               labels: Vec::new() }
     }
 
     pub fn as_sys_info(&self) -> SysInfo {
@@ -1,29 +1,19 @@
 use crate::{manager::ShutdownConfig,
             sys::ShutdownMethod};
 use biome_core::os::process::{handle_from_pid,
+                                kill_process_tree,
                                 windows_child::{ExitStatus,
                                                 Handle},
                                 Pid};
-use std::{collections::HashMap,
-          io,
-          mem,
+use std::{io,
           thread,
           time::{Duration,
                  Instant}};
-use winapi::{shared::minwindef::{DWORD,
-                                 LPDWORD,
-                                 MAX_PATH},
-             um::{handleapi::{self,
-                              INVALID_HANDLE_VALUE},
-                  processthreadsapi,
-                  tlhelp32::{self,
-                             LPPROCESSENTRY32W,
-                             PROCESSENTRY32W,
-                             TH32CS_SNAPPROCESS},
+use winapi::{shared::minwindef::LPDWORD,
+             um::{processthreadsapi,
                   wincon}};
 
 const PROCESS_ACTIVE: u32 = 259;
-type ProcessTable = HashMap<DWORD, Vec<DWORD>>;
 
 /// Kill a service process
 pub fn kill(pid: Pid, shutdown_config: &ShutdownConfig) -> ShutdownMethod {
@@ -77,8 +67,9 @@ impl Process {
         let start_time = Instant::now();
         loop {
             if ret == 0 || start_time.elapsed() > timeout {
-                let proc_table = build_proc_table();
-                terminate_process_descendants(&proc_table, self.id());
+                if let Err(e) = kill_process_tree(self.id()) {
+                    error!("Failed to terminate process tree of pid {}: {}", self.id(), e);
+                }
                 return ShutdownMethod::Killed;
             }
 
@@ -104,52 +95,6 @@ impl Process {
     }
 }
 
-fn build_proc_table() -> ProcessTable {
-    let processes_snap_handle =
-        unsafe { tlhelp32::CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
-
-    if processes_snap_handle == INVALID_HANDLE_VALUE {
-        error!("Failed to call CreateToolhelp32Snapshot: {}",
-               io::Error::last_os_error());
-        return ProcessTable::new();
-    }
-    let mut table = ProcessTable::new();
-    let mut process_entry = PROCESSENTRY32W { dwSize:              mem::size_of::<PROCESSENTRY32W>()
-                                                                   as u32,
-                                              cntUsage:            0,
-                                              th32ProcessID:       0,
-                                              th32DefaultHeapID:   0,
-                                              th32ModuleID:        0,
-                                              cntThreads:          0,
-                                              th32ParentProcessID: 0,
-                                              pcPriClassBase:      0,
-                                              dwFlags:             0,
-                                              szExeFile:           [0; MAX_PATH], };
-    // Get the first process from the snapshot.
-    match unsafe {
-              tlhelp32::Process32FirstW(processes_snap_handle,
-                                        &mut process_entry as LPPROCESSENTRY32W)
-          } {
-        1 => {
-            // First process worked, loop to find the process with the correct name.
-            let mut process_success: i32 = 1;
-            // Loop through all processes until we find one where `szExeFile` == `name`.
-            while process_success == 1 {
-                let children = table.entry(process_entry.th32ParentProcessID)
-                                    .or_insert_with(Vec::new);
-                (*children).push(process_entry.th32ProcessID);
-                process_success =
-                    unsafe { tlhelp32::Process32NextW(processes_snap_handle, &mut process_entry) };
-            }
-            unsafe { handleapi::CloseHandle(processes_snap_handle) };
-        }
-        _ => unsafe {
-            handleapi::CloseHandle(processes_snap_handle);
-        },
-    }
-    table
-}
-
 fn exit_code(handle: &Handle) -> Option<u32> {
     let mut exit_code: u32 = 0;
     unsafe {
@@ -162,20 +107,3 @@ fn exit_code(handle: &Handle) -> Option<u32> {
     }
     Some(exit_code)
 }
-
-fn terminate_process_descendants(table: &ProcessTable, pid: DWORD) {
-    if let Some(children) = table.get(&pid) {
-        for child in children {
-            terminate_process_descendants(table, *child);
-        }
-    }
-    unsafe {
-        if let Some(h) = handle_from_pid(pid) {
-            if processthreadsapi::TerminateProcess(h, 1) == 0 {
-                error!("Failed to call TerminateProcess on pid {}: {}",
-                       pid,
-                       io::Error::last_os_error());
-            }
-        }
-    }
-}
@@ -1,9 +1,11 @@
 use crate::{error::{Error,
                     Result},
+            event,
             PRODUCT,
             VERSION};
 use bio::{command::pkg::{self,
                          uninstall::{self,
+                                     DependenciesMode,
                                      UninstallHookMode,
                                      UninstallSafety}},
           config,
@@ -13,7 +15,8 @@ use biome_common::{command::package::install::{self as install_cmd,
                                                  InstallHookMode,
                                                  InstallMode,
                                                  InstallSource,
-                                                 LocalPackageUsage},
+                                                 LocalPackageUsage,
+                                                 SignaturePolicy},
                      outputln,
                      ui::{NullUi,
                           UIWriter}};
@@ -45,22 +48,26 @@ pub async fn install<T>(ui: &mut T,
 {
     let fs_root_path = Path::new(&*FS_ROOT_PATH);
     let auth_token = get_auth_token();
-    install_cmd::start(ui,
-                       url,
-                       channel,
-                       install_source,
-                       PRODUCT,
-                       VERSION,
-                       fs_root_path,
-                       &fs::cache_artifact_path(None::<String>),
-                       auth_token.as_deref(),
-                       &InstallMode::default(),
-                       &LocalPackageUsage::default(),
-                       // Install hooks are run when the supervisor
-                       // loads the package in add_service so it is
-                       // repetitive to run them here
-                       InstallHookMode::Ignore).await
-                                               .map_err(Error::from)
+    let (package, new_packages) =
+        install_cmd::start_with_new_packages(ui,
+                                             url,
+                                             channel,
+                                             install_source,
+                                             PRODUCT,
+                                             VERSION,
+                                             fs_root_path,
+                                             &fs::cache_artifact_path(None::<String>),
+                                             auth_token.as_deref(),
+                                             &InstallMode::default(),
+                                             &LocalPackageUsage::default(),
+                                             // Install hooks are run when the supervisor
+                                             // loads the package in add_service so it is
+                                             // repetitive to run them here
+                                             InstallHookMode::Ignore,
+                                             &SignaturePolicy::from_env()?).await?;
+    let new_packages: Vec<PackageIdent> = new_packages.into_iter().map(Into::into).collect();
+    send_package_events(&new_packages, &[]);
+    Ok(package)
 }
 
 // `install` but with no ui output and the benefit of thread safety
@@ -129,15 +136,17 @@ pub async fn install_channel_head(url: &str,
 pub async fn uninstall_all_but_latest(ident: impl AsRef<PackageIdent>,
                                       number_latest_to_keep: usize)
                                       -> BioResult<usize> {
-    uninstall::uninstall_all_but_latest(&mut NullUi::new(),
-                                        ident,
-                                        number_latest_to_keep,
-                                        &*FS_ROOT_PATH,
-                                        pkg::ExecutionStrategy::Run,
-                                        pkg::Scope::PackageAndDependencies,
-                                        &[],
-                                        UninstallHookMode::default(),
-                                        UninstallSafety::Safe).await
+    let uninstalled = uninstall::uninstall_all_but_latest(&mut NullUi::new(),
+                                                          ident,
+                                                          number_latest_to_keep,
+                                                          &*FS_ROOT_PATH,
+                                                          pkg::ExecutionStrategy::Run,
+                                                          pkg::Scope::PackageAndDependencies,
+                                                          &[],
+                                                          UninstallHookMode::default(),
+                                                          UninstallSafety::Safe).await?;
+    send_package_events(&[], &uninstalled);
+    Ok(uninstalled.len())
 }
 
 /// Uninstall a package given a package identifier.
@@ -146,12 +155,27 @@ pub async fn uninstall_all_but_latest(ident: impl AsRef<PackageIdent>,
 /// loaded by the Supervisor. This is needed for service rollback where the package we are
 /// uninstalling is the currently loaded package.
 pub async fn uninstall_even_if_loaded(ident: impl AsRef<PackageIdent>) -> BioResult<()> {
-    uninstall::uninstall(&mut NullUi::new(),
-                         &ident.as_ref(),
-                         &*FS_ROOT_PATH,
-                         pkg::ExecutionStrategy::Run,
-                         pkg::Scope::PackageAndDependencies,
-                         &[],
-                         UninstallHookMode::default(),
-                         UninstallSafety::Force).await
+    let uninstalled = uninstall::uninstall(&mut NullUi::new(),
+                                           &ident.as_ref(),
+                                           &*FS_ROOT_PATH,
+                                           pkg::ExecutionStrategy::Run,
+                                           pkg::Scope::PackageAndDependencies,
+                                           &[],
+                                           UninstallHookMode::default(),
+                                           UninstallSafety::Force,
+                                           DependenciesMode::default()).await?;
+    send_package_events(&[], &uninstalled);
+    Ok(())
+}
+
+/// Send an event for every package the Supervisor installed, which
+/// includes any dependencies installed along with the package asked
+/// for, and for every package it uninstalled.
+pub(crate) fn send_package_events(installed: &[PackageIdent], uninstalled: &[PackageIdent]) {
+    for ident in installed {
+        event::package_installed(ident);
+    }
+    for ident in uninstalled {
+        event::package_uninstalled(ident);
+    }
 }